@@ -41,6 +41,7 @@ CREATE TABLE IF NOT EXISTS cost_daily_stats (
     merchant_id      String,                    -- our merchant that owns the account
     txn_date         Date,                      -- the TRANSACTION (booking) day this bucket aggregates
     ingestion_id     String DEFAULT '',         -- the cost_ingestion row (UUIDv7) that last wrote this bucket (delete-by-ingestion)
+    revision         UInt32 DEFAULT 0,          -- that ingestion's reprocess count; older revisions are deleted once a reprocess lands
     card_network     LowCardinality(String),    -- 'visa', 'mc', …          ┐
     variant          String,                    -- 'visastandarddebit', …    │ cluster key
     funding          LowCardinality(String),    -- 'debit' | 'credit' | ''   │ (fit groups on this)
@@ -62,6 +63,14 @@ CREATE TABLE IF NOT EXISTS cost_daily_stats (
     suy              Float64,                    -- Σ total_fee/gross  │ and NON_LINEAR check are
     suuy             Float64,                    -- Σ total_fee/gross² │ built from these
     syyuu            Float64,                    -- Σ total_fee²/gross²┘
+    -- IC++ pass-through sums (interchange + scheme fee), over the txns whose report itemised them.
+    -- Read only where n_itemised = n; the shared interchange table and each connector's markup are
+    -- fitted from them (src/cost_ingestion/interchange.rs). Zero for a blended-pricing report.
+    n_itemised       UInt64 DEFAULT 0,           -- txns with itemised interchange/scheme fees
+    spt              Float64 DEFAULT 0,          -- Σ pass-through
+    sxpt             Float64 DEFAULT 0,          -- Σ gross·pass-through
+    sptpt            Float64 DEFAULT 0,          -- Σ pass-through²
+    sypt             Float64 DEFAULT 0,          -- Σ total_fee·pass-through
     ingested_at      DateTime DEFAULT now()
 )
 -- Identity is the (cluster, DAY, band, channel) bucket, NOT the transaction. A day re-delivered by
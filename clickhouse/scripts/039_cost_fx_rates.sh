@@ -0,0 +1,34 @@
+#!/bin/sh
+set -eu
+
+# FX rates for in-house cost estimation, with the date each rate takes effect.
+#
+# Settlement reports are fitted per settlement currency, but decisions are made in the order
+# currency: a EUR-settled cluster pricing a GBP payment must convert its fixed fee (and the payment
+# amount it tiers on) between the two. Serving reads this table, alongside the optional rates CSV
+# (`cost_ingestion.fx.rates_file`), and uses the latest rate effective on or before the conversion
+# date. A pair only needs loading in one direction — the inverse is derived — and a pair with
+# neither is triangulated through the configured pivot currency.
+#
+# ReplacingMergeTree on (base, quote, effective_from): re-loading a day's rate replaces it.
+
+CLICKHOUSE_DATABASE="${CLICKHOUSE_DATABASE:-default}"
+CLICKHOUSE_USER="${CLICKHOUSE_USER:-default}"
+CLICKHOUSE_PASSWORD="${CLICKHOUSE_PASSWORD:-}"
+
+auth_args="--database=${CLICKHOUSE_DATABASE} --user=${CLICKHOUSE_USER}"
+if [ -n "${CLICKHOUSE_PASSWORD}" ]; then
+  auth_args="${auth_args} --password=${CLICKHOUSE_PASSWORD}"
+fi
+
+clickhouse-client ${auth_args} --multiquery <<SQL
+CREATE TABLE IF NOT EXISTS cost_fx_rates (
+    base             LowCardinality(String),    -- ISO currency being priced, uppercase ('EUR')
+    quote            LowCardinality(String),    -- ISO currency it is priced in, uppercase ('GBP')
+    rate             Float64,                   -- units of quote per 1 unit of base
+    effective_from   Date,                      -- first day the rate applies
+    loaded_at        DateTime DEFAULT now()     -- version: the latest load of a day's rate wins
+)
+ENGINE = ReplacingMergeTree(loaded_at)
+ORDER BY (base, quote, effective_from);
+SQL
@@ -0,0 +1,48 @@
+#!/bin/sh
+set -eu
+
+# Fee price changes raised as alerts for in-house cost estimation.
+#
+# After every fit, the worker diffs each cluster's two latest cost_fee_model snapshots at the
+# merchant's alert thresholds. A change not seen before is recorded here with its severity, then
+# emailed and sent to the merchant's webhook. `id` is a hash of the cluster and the snapshot date
+# the new price appeared, so detecting the same step again never re-raises it.
+#
+# ReplacingMergeTree on (merchant_id, id): acknowledging an alert re-inserts it with
+# acknowledged_at set, and the latest updated_at wins.
+
+CLICKHOUSE_DATABASE="${CLICKHOUSE_DATABASE:-default}"
+CLICKHOUSE_USER="${CLICKHOUSE_USER:-default}"
+CLICKHOUSE_PASSWORD="${CLICKHOUSE_PASSWORD:-}"
+
+auth_args="--database=${CLICKHOUSE_DATABASE} --user=${CLICKHOUSE_USER}"
+if [ -n "${CLICKHOUSE_PASSWORD}" ]; then
+  auth_args="${auth_args} --password=${CLICKHOUSE_PASSWORD}"
+fi
+
+clickhouse-client ${auth_args} --multiquery <<SQL
+CREATE TABLE IF NOT EXISTS cost_price_change (
+    merchant_id      String,
+    id               String,                    -- hash of the cluster dims and changed_on
+    connector        LowCardinality(String),
+    account          String,
+    card_network     LowCardinality(String),
+    variant          LowCardinality(String),
+    funding          LowCardinality(String),
+    issuer_country   LowCardinality(String),
+    currency         LowCardinality(String),
+    ic_category      LowCardinality(String),
+    changed_on       Date,                      -- snapshot date the new price first appeared
+    old_pct_bps      Float64,
+    new_pct_bps      Float64,
+    old_fixed        Float64,                   -- settlement-currency units
+    new_fixed        Float64,
+    severity         LowCardinality(String),    -- 'minor' | 'major', at the thresholds in force
+    detected_at      DateTime,
+    acknowledged_at  Nullable(DateTime),
+    acknowledged_by  String DEFAULT '',
+    updated_at       DateTime64(3) DEFAULT now64(3)  -- version: an acknowledgement replaces the row
+)
+ENGINE = ReplacingMergeTree(updated_at)
+ORDER BY (merchant_id, id);
+SQL
@@ -0,0 +1,48 @@
+#!/bin/sh
+set -eu
+
+# Refund and dispute fees for in-house cost estimation.
+#
+# Settlement reports carry refund fees and chargeback/dispute fees alongside the capture fees that
+# feed the fit. They are not a function of the captured amount, so they never enter
+# cost_daily_stats; the rollup counts them here per (cluster, day, kind) with their summed fees.
+# Serving divides a cluster's fees by its capture count over the fit window to get the expected
+# post-capture cost of one payment (event rate × fee) and adds it to the connector's price.
+#
+# Same delete-by-ingestion and re-delivery semantics as cost_daily_stats: a day re-delivered by a
+# later report collapses onto the same key and the latest ingested_at wins.
+
+CLICKHOUSE_DATABASE="${CLICKHOUSE_DATABASE:-default}"
+CLICKHOUSE_USER="${CLICKHOUSE_USER:-default}"
+CLICKHOUSE_PASSWORD="${CLICKHOUSE_PASSWORD:-}"
+
+auth_args="--database=${CLICKHOUSE_DATABASE} --user=${CLICKHOUSE_USER}"
+if [ -n "${CLICKHOUSE_PASSWORD}" ]; then
+  auth_args="${auth_args} --password=${CLICKHOUSE_PASSWORD}"
+fi
+
+clickhouse-client ${auth_args} --multiquery <<SQL
+CREATE TABLE IF NOT EXISTS cost_post_capture_stats (
+    connector        LowCardinality(String),
+    account          String,
+    merchant_id      String,
+    txn_date         Date,                      -- the day the refund or dispute was booked
+    ingestion_id     String DEFAULT '',         -- the cost_ingestion row that last wrote this bucket
+    revision         UInt32 DEFAULT 0,          -- that ingestion's reprocess count; older revisions are deleted once a reprocess lands
+    kind             LowCardinality(String),    -- 'refund' | 'dispute'
+    card_network     LowCardinality(String),
+    variant          String,
+    funding          LowCardinality(String),
+    issuer_country   LowCardinality(String),
+    currency         LowCardinality(String),
+    ic_category      String,
+    n                UInt64,                    -- events
+    fees             Float64,                   -- Σ fee charged for the events (negative = reversed)
+    amount           Float64,                   -- Σ refunded / disputed amount
+    ingested_at      DateTime DEFAULT now()
+)
+ENGINE = ReplacingMergeTree(ingested_at)
+PARTITION BY toYYYYMM(txn_date)
+ORDER BY (connector, account, merchant_id, txn_date, kind, card_network, variant, funding,
+          issuer_country, currency, ic_category);
+SQL
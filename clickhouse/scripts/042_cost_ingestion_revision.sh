@@ -0,0 +1,23 @@
+#!/bin/sh
+set -eu
+
+# Migration: add the `revision` column to the ingestion-stamped cost tables of an existing database.
+#
+# Reprocessing an ingestion from its retained report writes the new buckets as that ingestion's next
+# revision, then deletes the older revision's leftovers. 035 and 041 create the column on a fresh
+# volume; this is the additive ALTER for tables created before it existed. `revision` is not in the
+# sorting key, so this is metadata only — existing rows read revision 0, their first ingest.
+
+CLICKHOUSE_DATABASE="${CLICKHOUSE_DATABASE:-default}"
+CLICKHOUSE_USER="${CLICKHOUSE_USER:-default}"
+CLICKHOUSE_PASSWORD="${CLICKHOUSE_PASSWORD:-}"
+
+auth_args="--database=${CLICKHOUSE_DATABASE} --user=${CLICKHOUSE_USER}"
+if [ -n "${CLICKHOUSE_PASSWORD}" ]; then
+  auth_args="${auth_args} --password=${CLICKHOUSE_PASSWORD}"
+fi
+
+clickhouse-client ${auth_args} --multiquery <<SQL
+ALTER TABLE cost_daily_stats ADD COLUMN IF NOT EXISTS revision UInt32 DEFAULT 0 AFTER ingestion_id;
+ALTER TABLE cost_post_capture_stats ADD COLUMN IF NOT EXISTS revision UInt32 DEFAULT 0 AFTER ingestion_id;
+SQL
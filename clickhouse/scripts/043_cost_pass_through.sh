@@ -0,0 +1,28 @@
+#!/bin/sh
+set -eu
+
+# Migration: add the IC++ pass-through sums to a `cost_daily_stats` created before they existed.
+#
+# Reports that itemise interchange and scheme fees now also stage Σ pass-through (and its cross
+# terms) per bucket, from which the shared interchange table and each connector's markup are fitted.
+# 035 creates the columns on a fresh volume; this is the additive ALTER for an existing table. None
+# of them is in the sorting key, so this is metadata only. Existing rows read n_itemised = 0 and
+# contribute nothing to the interchange fit until their ingestion is reprocessed.
+
+CLICKHOUSE_DATABASE="${CLICKHOUSE_DATABASE:-default}"
+CLICKHOUSE_USER="${CLICKHOUSE_USER:-default}"
+CLICKHOUSE_PASSWORD="${CLICKHOUSE_PASSWORD:-}"
+
+auth_args="--database=${CLICKHOUSE_DATABASE} --user=${CLICKHOUSE_USER}"
+if [ -n "${CLICKHOUSE_PASSWORD}" ]; then
+  auth_args="${auth_args} --password=${CLICKHOUSE_PASSWORD}"
+fi
+
+clickhouse-client ${auth_args} --multiquery <<SQL
+ALTER TABLE cost_daily_stats
+    ADD COLUMN IF NOT EXISTS n_itemised UInt64 DEFAULT 0 AFTER syyuu,
+    ADD COLUMN IF NOT EXISTS spt Float64 DEFAULT 0 AFTER n_itemised,
+    ADD COLUMN IF NOT EXISTS sxpt Float64 DEFAULT 0 AFTER spt,
+    ADD COLUMN IF NOT EXISTS sptpt Float64 DEFAULT 0 AFTER sxpt,
+    ADD COLUMN IF NOT EXISTS sypt Float64 DEFAULT 0 AFTER sptpt;
+SQL
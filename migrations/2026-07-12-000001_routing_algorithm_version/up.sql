@@ -0,0 +1,19 @@
+-- Immutable history of routing algorithms (MySQL parity of the Postgres migration).
+CREATE TABLE routing_algorithm_version (
+    id                   VARCHAR(64)  NOT NULL PRIMARY KEY,
+    routing_algorithm_id VARCHAR(255) NOT NULL,
+    version              INT          NOT NULL,
+    created_by           VARCHAR(255) NOT NULL,
+    name                 VARCHAR(255) NOT NULL,
+    description          TEXT         NOT NULL,
+    algorithm_data       TEXT         NOT NULL,
+    algorithm_for        VARCHAR(64)  NOT NULL,
+    metadata             TEXT,
+    author               VARCHAR(255),
+    change_kind          VARCHAR(16)  NOT NULL,
+    restored_from        INT,
+    diff                 TEXT,
+    created_at           TIMESTAMP    NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    UNIQUE KEY uq_routing_algorithm_version (routing_algorithm_id, version),
+    KEY idx_routing_algorithm_version_merchant (created_by, created_at)
+);
@@ -0,0 +1,3 @@
+-- Scheduled activation: while set, the routing scheduler flips `routing_algorithm_id` between the
+-- scheduled algorithm and its fallback as the window opens and closes. JSON-encoded.
+ALTER TABLE routing_algorithm_mapper ADD COLUMN schedule TEXT;
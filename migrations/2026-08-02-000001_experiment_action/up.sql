@@ -0,0 +1,14 @@
+-- Audit trail of automated A/B experiment actions (MySQL parity of the Postgres migration).
+CREATE TABLE experiment_action (
+    id                     VARCHAR(64)  NOT NULL PRIMARY KEY,
+    routing_algorithm_id   VARCHAR(255) NOT NULL,
+    created_by             VARCHAR(255) NOT NULL,
+    action                 VARCHAR(16)  NOT NULL,
+    arm                    VARCHAR(255) NOT NULL,
+    verdict                VARCHAR(32)  NOT NULL,
+    activated_algorithm_id VARCHAR(255),
+    results                TEXT         NOT NULL,
+    created_at             TIMESTAMP    NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    UNIQUE KEY uq_experiment_action (routing_algorithm_id, action, arm),
+    KEY idx_experiment_action_merchant (created_by, created_at)
+);
@@ -0,0 +1,2 @@
+-- Content address of the retained raw report (MySQL parity of the Postgres migration).
+ALTER TABLE cost_ingestion ADD COLUMN report_blob VARCHAR(80);
@@ -0,0 +1,4 @@
+ALTER TABLE merchant_api_keys DROP COLUMN last_used_at;
+ALTER TABLE merchant_api_keys DROP COLUMN rate_limit_per_minute;
+ALTER TABLE merchant_api_keys DROP COLUMN expires_at;
+ALTER TABLE merchant_api_keys DROP COLUMN permissions;
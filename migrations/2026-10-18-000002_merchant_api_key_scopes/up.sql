@@ -0,0 +1,5 @@
+-- Key scopes, expiry, quota and last use (MySQL parity of the Postgres migration).
+ALTER TABLE merchant_api_keys ADD COLUMN permissions TEXT;
+ALTER TABLE merchant_api_keys ADD COLUMN expires_at DATETIME(6);
+ALTER TABLE merchant_api_keys ADD COLUMN rate_limit_per_minute INT;
+ALTER TABLE merchant_api_keys ADD COLUMN last_used_at DATETIME(6);
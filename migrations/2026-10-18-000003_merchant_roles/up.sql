@@ -0,0 +1,11 @@
+-- Custom member roles (MySQL parity of the Postgres migration).
+CREATE TABLE merchant_roles (
+    id BIGINT AUTO_INCREMENT PRIMARY KEY,
+    merchant_id VARCHAR(255) NOT NULL,
+    role_name VARCHAR(50) NOT NULL,
+    permissions TEXT NOT NULL,
+    description VARCHAR(255) NULL,
+    created_at DATETIME(6) NOT NULL DEFAULT CURRENT_TIMESTAMP(6),
+    updated_at DATETIME(6) NOT NULL DEFAULT CURRENT_TIMESTAMP(6),
+    UNIQUE KEY uk_merchant_role (merchant_id, role_name)
+);
@@ -0,0 +1,3 @@
+DROP TRIGGER IF EXISTS config_audit_log_no_delete;
+DROP TRIGGER IF EXISTS config_audit_log_no_update;
+DROP TABLE config_audit_log;
@@ -0,0 +1,25 @@
+-- Configuration audit log (MySQL parity of the Postgres migration).
+CREATE TABLE config_audit_log (
+    id BIGINT AUTO_INCREMENT PRIMARY KEY,
+    merchant_id VARCHAR(255) NULL,
+    actor VARCHAR(255) NOT NULL,
+    actor_kind VARCHAR(32) NOT NULL,
+    user_id VARCHAR(64) NULL,
+    http_method VARCHAR(16) NOT NULL,
+    route VARCHAR(255) NOT NULL,
+    path VARCHAR(1024) NOT NULL,
+    request_id VARCHAR(128) NULL,
+    status_code INT NOT NULL,
+    request_body TEXT NULL,
+    before_state TEXT NULL,
+    after_state TEXT NULL,
+    created_at DATETIME(6) NOT NULL DEFAULT CURRENT_TIMESTAMP(6)
+);
+
+CREATE INDEX idx_config_audit_log_merchant ON config_audit_log (merchant_id, id);
+CREATE INDEX idx_config_audit_log_request_id ON config_audit_log (request_id);
+
+CREATE TRIGGER config_audit_log_no_update BEFORE UPDATE ON config_audit_log
+    FOR EACH ROW SIGNAL SQLSTATE '45000' SET MESSAGE_TEXT = 'config_audit_log is append-only';
+CREATE TRIGGER config_audit_log_no_delete BEFORE DELETE ON config_audit_log
+    FOR EACH ROW SIGNAL SQLSTATE '45000' SET MESSAGE_TEXT = 'config_audit_log is append-only';
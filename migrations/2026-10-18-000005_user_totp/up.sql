@@ -0,0 +1,5 @@
+-- TOTP two-factor authentication (MySQL parity of the Postgres migration).
+ALTER TABLE users ADD COLUMN totp_secret VARCHAR(64);
+ALTER TABLE users ADD COLUMN totp_enabled TINYINT(1) NOT NULL DEFAULT 0;
+ALTER TABLE users ADD COLUMN totp_recovery_codes TEXT;
+ALTER TABLE users ADD COLUMN totp_last_step BIGINT;
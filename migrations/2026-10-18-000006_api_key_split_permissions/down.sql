@@ -0,0 +1,11 @@
+UPDATE merchant_api_keys
+SET permissions = REPLACE(
+    REPLACE(
+        permissions,
+        '"routing:read","cost:read","credentials:read","analytics:read"',
+        '"routing:read"'
+    ),
+    '"routing:write","cost:write","credentials:write","api_keys:manage"',
+    '"routing:write"'
+)
+WHERE permissions IS NOT NULL;
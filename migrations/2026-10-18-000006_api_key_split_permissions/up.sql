@@ -0,0 +1,18 @@
+-- Widen keys scoped before the permission split (MySQL parity of the Postgres migration).
+UPDATE merchant_api_keys
+SET permissions = REPLACE(
+    REPLACE(
+        permissions,
+        '"routing:read"',
+        '"routing:read","cost:read","credentials:read","analytics:read"'
+    ),
+    '"routing:write"',
+    '"routing:write","cost:write","credentials:write","api_keys:manage"'
+)
+WHERE permissions IS NOT NULL
+    AND permissions NOT LIKE '%"cost:%'
+    AND permissions NOT LIKE '%"credentials:%'
+    AND permissions NOT LIKE '%"analytics:%'
+    AND permissions NOT LIKE '%"api_keys:%'
+    AND permissions NOT LIKE '%"members:%'
+    AND permissions NOT LIKE '%"audit:%';
@@ -0,0 +1,12 @@
+-- SSO identity links (MySQL parity of the Postgres migration).
+CREATE TABLE user_identities (
+    id BIGINT AUTO_INCREMENT PRIMARY KEY,
+    issuer VARCHAR(255) NOT NULL,
+    subject VARCHAR(255) NOT NULL,
+    user_id VARCHAR(64) NOT NULL,
+    created_at DATETIME(6) NOT NULL DEFAULT CURRENT_TIMESTAMP(6),
+    UNIQUE KEY uk_user_identity_subject (issuer, subject),
+    UNIQUE KEY uk_user_identity_user (issuer, user_id)
+);
+
+CREATE INDEX idx_user_identities_user_id ON user_identities (user_id);
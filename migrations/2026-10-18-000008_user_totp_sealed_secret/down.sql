@@ -0,0 +1,4 @@
+UPDATE users SET totp_secret = NULL, totp_enabled = 0, totp_recovery_codes = NULL,
+    totp_last_step = NULL
+WHERE totp_secret LIKE '%:%';
+ALTER TABLE users MODIFY COLUMN totp_secret VARCHAR(64);
@@ -0,0 +1,2 @@
+-- Sealed TOTP secrets (MySQL parity of the Postgres migration).
+ALTER TABLE users MODIFY COLUMN totp_secret TEXT;
@@ -0,0 +1,28 @@
+-- Immutable history of routing algorithms.
+--
+-- Every create, update and rollback of a `routing_algorithm` row appends a snapshot here; rows are
+-- never updated or deleted by the application. `version` counts up from 1 per algorithm, `author`
+-- is taken from the authenticated caller (NULL for admin-secret requests and for history recorded
+-- before this table existed), and `diff` holds the structural diff against the previous version as
+-- JSON. A rollback copies an older snapshot back into `routing_algorithm` and records itself as a
+-- `restored` version pointing at `restored_from`.
+CREATE TABLE routing_algorithm_version (
+    id                   VARCHAR(64)  PRIMARY KEY,
+    routing_algorithm_id VARCHAR(255) NOT NULL,
+    version              INTEGER      NOT NULL,
+    created_by           VARCHAR(255) NOT NULL,
+    name                 VARCHAR(255) NOT NULL,
+    description          TEXT         NOT NULL,
+    algorithm_data       TEXT         NOT NULL,
+    algorithm_for        VARCHAR(64)  NOT NULL,
+    metadata             JSONB,
+    author               VARCHAR(255),
+    change_kind          VARCHAR(16)  NOT NULL,              -- 'created', 'updated', 'restored'
+    restored_from        INTEGER,
+    diff                 TEXT,
+    created_at           TIMESTAMP    NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    UNIQUE (routing_algorithm_id, version)
+);
+
+CREATE INDEX idx_routing_algorithm_version_merchant
+    ON routing_algorithm_version (created_by, created_at);
@@ -0,0 +1 @@
+ALTER TABLE routing_algorithm_mapper DROP COLUMN schedule;
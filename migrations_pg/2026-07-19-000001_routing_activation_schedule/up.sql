@@ -0,0 +1,3 @@
+-- Scheduled activation (Postgres parity of the MySQL migration). JSON-encoded; see the MySQL
+-- migration for how the routing scheduler uses it.
+ALTER TABLE routing_algorithm_mapper ADD COLUMN schedule TEXT;
@@ -0,0 +1,21 @@
+-- Audit trail of automated A/B experiment actions.
+--
+-- The experiment evaluator appends a row whenever it pauses a variant that breached its guardrail
+-- or promotes a winning arm; rows are never updated or deleted by the application. `results` holds
+-- the experiment results the decision was made on, as JSON. The unique key allows each action once
+-- per arm, which also keeps replicas evaluating at the same time from acting twice.
+CREATE TABLE experiment_action (
+    id                     VARCHAR(64)  PRIMARY KEY,
+    routing_algorithm_id   VARCHAR(255) NOT NULL,
+    created_by             VARCHAR(255) NOT NULL,
+    action                 VARCHAR(16)  NOT NULL,              -- 'paused', 'promoted'
+    arm                    VARCHAR(255) NOT NULL,
+    verdict                VARCHAR(32)  NOT NULL,
+    activated_algorithm_id VARCHAR(255),
+    results                TEXT         NOT NULL,
+    created_at             TIMESTAMP    NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    UNIQUE (routing_algorithm_id, action, arm)
+);
+
+CREATE INDEX idx_experiment_action_merchant
+    ON experiment_action (created_by, created_at);
@@ -0,0 +1 @@
+ALTER TABLE cost_ingestion DROP COLUMN report_blob;
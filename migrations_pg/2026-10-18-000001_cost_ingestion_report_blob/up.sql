@@ -0,0 +1,4 @@
+-- Content address of the raw report this ingestion read (`sha256:<hex>`), retained in the report
+-- archive so the ingestion can be reprocessed with today's parser and column mapping. NULL when the
+-- report was not retained (no archive configured, or the ingestion predates it).
+ALTER TABLE cost_ingestion ADD COLUMN report_blob VARCHAR(80);
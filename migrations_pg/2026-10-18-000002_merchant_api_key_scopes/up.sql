@@ -0,0 +1,7 @@
+-- What a key may do (a JSON array of permission strings; NULL is unrestricted, which is every key
+-- created before scopes existed), when it stops working, how many requests a minute it may make,
+-- and when it was last seen.
+ALTER TABLE merchant_api_keys ADD COLUMN permissions TEXT;
+ALTER TABLE merchant_api_keys ADD COLUMN expires_at TIMESTAMP;
+ALTER TABLE merchant_api_keys ADD COLUMN rate_limit_per_minute INTEGER;
+ALTER TABLE merchant_api_keys ADD COLUMN last_used_at TIMESTAMP;
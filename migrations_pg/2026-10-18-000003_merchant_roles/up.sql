@@ -0,0 +1,13 @@
+-- Roles a merchant defines for its own members, alongside the built-in admin, member and viewer.
+-- `permissions` is a JSON array of permission strings; `role_name` is what `user_merchants.role`
+-- holds for a member given the role.
+CREATE TABLE merchant_roles (
+    id BIGSERIAL PRIMARY KEY,
+    merchant_id VARCHAR(255) NOT NULL,
+    role_name VARCHAR(50) NOT NULL,
+    permissions TEXT NOT NULL,
+    description VARCHAR(255),
+    created_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    UNIQUE (merchant_id, role_name)
+);
@@ -0,0 +1,3 @@
+DROP TRIGGER IF EXISTS config_audit_log_append_only ON config_audit_log;
+DROP FUNCTION IF EXISTS config_audit_log_append_only();
+DROP TABLE config_audit_log;
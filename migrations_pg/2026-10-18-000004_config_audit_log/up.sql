@@ -0,0 +1,32 @@
+-- One row per configuration change: who made it, through which route, under which request id, and
+-- the state before and after where the handler knows it. Rows are only ever inserted; the trigger
+-- below refuses updates and deletes, so not even a bug in this service can rewrite history.
+CREATE TABLE config_audit_log (
+    id BIGSERIAL PRIMARY KEY,
+    merchant_id VARCHAR(255),
+    actor VARCHAR(255) NOT NULL,
+    actor_kind VARCHAR(32) NOT NULL,
+    user_id VARCHAR(64),
+    http_method VARCHAR(16) NOT NULL,
+    route VARCHAR(255) NOT NULL,
+    path VARCHAR(1024) NOT NULL,
+    request_id VARCHAR(128),
+    status_code INTEGER NOT NULL,
+    request_body TEXT,
+    before_state TEXT,
+    after_state TEXT,
+    created_at TIMESTAMP NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_config_audit_log_merchant ON config_audit_log (merchant_id, id);
+CREATE INDEX idx_config_audit_log_request_id ON config_audit_log (request_id);
+
+CREATE FUNCTION config_audit_log_append_only() RETURNS trigger AS $$
+BEGIN
+    RAISE EXCEPTION 'config_audit_log is append-only';
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER config_audit_log_append_only
+    BEFORE UPDATE OR DELETE ON config_audit_log
+    FOR EACH ROW EXECUTE FUNCTION config_audit_log_append_only();
@@ -0,0 +1,7 @@
+-- TOTP two-factor authentication. The secret is set at enrollment and only counts once a code from
+-- it has been confirmed (`totp_enabled`); recovery codes are a JSON array of bcrypt hashes, each
+-- removed as it is used; the last accepted time step stops a code being replayed within its window.
+ALTER TABLE users ADD COLUMN totp_secret VARCHAR(64);
+ALTER TABLE users ADD COLUMN totp_enabled BOOLEAN NOT NULL DEFAULT FALSE;
+ALTER TABLE users ADD COLUMN totp_recovery_codes TEXT;
+ALTER TABLE users ADD COLUMN totp_last_step BIGINT;
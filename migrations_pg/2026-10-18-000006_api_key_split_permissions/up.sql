@@ -0,0 +1,21 @@
+-- Keys scoped before permissions were split by area name only the routing permissions, which then
+-- also covered cost, credentials, analytics and key management. Give them the split-out
+-- permissions they already had, as sessions minted before the split are read. A key naming any of
+-- the newer permissions was scoped knowing about them and is left as it is.
+UPDATE merchant_api_keys
+SET permissions = REPLACE(
+    REPLACE(
+        permissions,
+        '"routing:read"',
+        '"routing:read","cost:read","credentials:read","analytics:read"'
+    ),
+    '"routing:write"',
+    '"routing:write","cost:write","credentials:write","api_keys:manage"'
+)
+WHERE permissions IS NOT NULL
+    AND permissions NOT LIKE '%"cost:%'
+    AND permissions NOT LIKE '%"credentials:%'
+    AND permissions NOT LIKE '%"analytics:%'
+    AND permissions NOT LIKE '%"api_keys:%'
+    AND permissions NOT LIKE '%"members:%'
+    AND permissions NOT LIKE '%"audit:%';
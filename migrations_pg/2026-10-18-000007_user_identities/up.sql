@@ -0,0 +1,14 @@
+-- Which identity-provider account each SSO user signed in with: the issuer and its stable subject
+-- id. A user is found by email only on their first sign-in through an issuer; later sign-ins go by
+-- (issuer, subject), so an email that changes hands at the provider cannot reach this user.
+CREATE TABLE user_identities (
+    id BIGSERIAL PRIMARY KEY,
+    issuer VARCHAR(255) NOT NULL,
+    subject VARCHAR(255) NOT NULL,
+    user_id VARCHAR(64) NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    UNIQUE (issuer, subject),
+    UNIQUE (issuer, user_id)
+);
+
+CREATE INDEX idx_user_identities_user_id ON user_identities (user_id);
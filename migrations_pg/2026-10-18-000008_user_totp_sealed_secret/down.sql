@@ -0,0 +1,5 @@
+-- Sealed secrets do not fit the old column; two-factor has to be set up again.
+UPDATE users SET totp_secret = NULL, totp_enabled = FALSE, totp_recovery_codes = NULL,
+    totp_last_step = NULL
+WHERE totp_secret LIKE '%:%';
+ALTER TABLE users ALTER COLUMN totp_secret TYPE VARCHAR(64);
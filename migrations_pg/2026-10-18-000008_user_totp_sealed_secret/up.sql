@@ -0,0 +1,4 @@
+-- TOTP secrets are stored sealed with AES-256-GCM (`"{key_id}:{base64}"`), which outgrows the
+-- plain base32 column. Secrets stored before sealing stay readable and are sealed the next time
+-- one of their codes is accepted.
+ALTER TABLE users ALTER COLUMN totp_secret TYPE TEXT;
@@ -86,6 +86,9 @@ EXPECTED_CLICKHOUSE_TABLES=(
     cost_fee_model
     cost_fee_model_segment
     cost_bin_product
+    cost_fx_rates
+    cost_price_change
+    cost_post_capture_stats
 )
 
 check_and_kill_ports() {
@@ -624,13 +627,13 @@ if ! check_clickhouse_schema; then
     echo "ClickHouse schema is incomplete — attempting to (re)create cost-ingestion tables..."
     # The cost tables (cost_daily_stats / cost_fee_model from 035_cost_model.sh,
     # cost_bin_product from 036, the piecewise cost_fee_model_segment from 037,
-    # and the card_product ALTER migration in 038) are only auto-run by the container on a fresh
-    # clickhouse-data volume. Every one is idempotent and non-destructive — the CREATEs are
+    # the card_product ALTER migration in 038, cost_fx_rates from 039, cost_price_change from 040, cost_post_capture_stats from 041, the reprocess `revision` column in 042, and the IC++ pass-through sums in 043)
+    # are only auto-run by the container on a fresh clickhouse-data volume. Every one is idempotent and non-destructive — the CREATEs are
     # IF NOT EXISTS, and 038 is ADD COLUMN IF NOT EXISTS + a same-key MODIFY ORDER BY (a metadata-only
     # append) — so re-running against an existing DB heals it without wiping analytics data. 038 is
     # what upgrades a database that already ran 035/036 before card_product existed. Add new cost DDL
     # scripts here.
-    for cost_script in 035_cost_model.sh 036_cost_bin_product.sh 037_cost_fee_model_segment.sh 038_cost_card_product.sh; do
+    for cost_script in 035_cost_model.sh 036_cost_bin_product.sh 037_cost_fee_model_segment.sh 038_cost_card_product.sh 039_cost_fx_rates.sh 040_cost_price_change.sh 041_cost_post_capture_stats.sh 042_cost_ingestion_revision.sh 043_cost_pass_through.sh; do
         if docker compose exec -T clickhouse sh "/docker-entrypoint-initdb.d/${cost_script}" >/dev/null 2>&1; then
             echo "  Ran ${cost_script}."
         else
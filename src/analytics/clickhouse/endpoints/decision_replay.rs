@@ -0,0 +1,47 @@
+use clickhouse::Row;
+use serde::Deserialize;
+
+use crate::analytics::flow::FlowType;
+use crate::analytics::store::RecordedDecision;
+use crate::error::ApiError;
+
+use super::super::common::{fetch_all, DOMAIN_TABLE};
+use super::super::query::{BoundQueryBuilder, FilterClause, OrderClause};
+
+#[derive(Debug, Clone, Deserialize, Row)]
+struct DecisionRow {
+    created_at_ms: i64,
+    details: String,
+}
+
+/// A merchant's successful `/decide-gateway` decisions in `[start_ms, end_ms]`, oldest first, with
+/// the request and response each was recorded with. Read-only: feeds the routing replay.
+pub async fn load(
+    client: &clickhouse::Client,
+    merchant_id: &str,
+    start_ms: i64,
+    end_ms: i64,
+    limit: u64,
+) -> Result<Vec<RecordedDecision>, ApiError> {
+    let mut builder = BoundQueryBuilder::new(DOMAIN_TABLE);
+    builder.extend_selects(["created_at_ms", "assumeNotNull(details) AS details"]);
+    builder.add_filter(FilterClause::eq("merchant_id", merchant_id.to_string()));
+    builder.add_filter(FilterClause::raw(format!(
+        "flow_type = '{}'",
+        FlowType::DecideGatewayDecision.as_str()
+    )));
+    builder.add_filter(FilterClause::gte("created_at_ms", start_ms));
+    builder.add_filter(FilterClause::lte("created_at_ms", end_ms));
+    builder.add_filter(FilterClause::raw("details IS NOT NULL"));
+    builder.add_order_by(OrderClause::asc("created_at_ms"));
+    builder.set_limit(Some(limit));
+
+    let rows = fetch_all::<DecisionRow>(builder.build(client)).await?;
+    Ok(rows
+        .into_iter()
+        .map(|row| RecordedDecision {
+            created_at_ms: row.created_at_ms,
+            details: row.details,
+        })
+        .collect())
+}
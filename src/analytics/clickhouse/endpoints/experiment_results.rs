@@ -7,6 +7,7 @@ use crate::analytics::clickhouse::query::{BoundQueryBuilder, FilterClause, Order
 use crate::analytics::flow::FlowType;
 use crate::analytics::models::{
     ExperimentArmMetrics, ExperimentResultsQuery, ExperimentResultsResponse, ExperimentVerdict,
+    SignificanceMethod,
 };
 use crate::error::ApiError;
 
@@ -15,6 +16,9 @@ use crate::error::ApiError;
 /// pass the merchant's real margin.
 pub const DEFAULT_EVALUATION_MARGIN: f64 = 1.0;
 
+/// Significance level of both tests.
+const ALPHA: f64 = 0.05;
+
 #[derive(Debug, Clone, Deserialize, Row)]
 struct ArmRow {
     arm: String,
@@ -94,10 +98,11 @@ pub async fn load(
         "JSONExtractString(assumeNotNull(details), 'experiment_id') = '{}'",
         query.experiment_id.replace('\'', "\\'")
     )));
-    builder.add_filter(FilterClause::raw(
-        "JSONExtractString(assumeNotNull(details), 'variant_arm') IN ('control', 'variant')"
-            .to_string(),
-    ));
+    builder.add_filter(FilterClause::raw(format!(
+        "JSONExtractString(assumeNotNull(details), 'variant_arm') IN ('{}', '{}')",
+        query.control_arm.replace('\'', "\\'"),
+        query.variant_arm.replace('\'', "\\'")
+    )));
 
     if let Some(start) = query.start_ms {
         builder.add_filter(FilterClause::gte("created_at_ms", start));
@@ -111,11 +116,11 @@ pub async fn load(
 
     let rows = fetch_all::<ArmRow>(builder.build(client)).await?;
 
-    let control_row = rows.iter().find(|r| r.arm == "control");
-    let variant_row = rows.iter().find(|r| r.arm == "variant");
+    let control_row = rows.iter().find(|r| r.arm == query.control_arm);
+    let variant_row = rows.iter().find(|r| r.arm == query.variant_arm);
 
-    let control = arm_metrics("control", control_row, query.evaluation_margin);
-    let variant = arm_metrics("variant", variant_row, query.evaluation_margin);
+    let control = arm_metrics(&query.control_arm, control_row, query.evaluation_margin);
+    let variant = arm_metrics(&query.variant_arm, variant_row, query.evaluation_margin);
     let total = control.transaction_count + variant.transaction_count;
 
     let delta_pp = (variant.auth_rate - control.auth_rate) * 100.0;
@@ -142,6 +147,7 @@ pub async fn load(
         query.guardrail_threshold_pp,
         is_cost_experiment,
         query.evaluation_margin,
+        query.method,
     );
 
     Ok(ExperimentResultsResponse {
@@ -153,6 +159,7 @@ pub async fn load(
         p_value,
         confidence_interval,
         verdict,
+        method: query.method,
         min_sample_size: query.min_sample_size,
         net_delta_bps,
         evaluation_margin: query.evaluation_margin,
@@ -257,6 +264,7 @@ fn compute_significance(
     guardrail_threshold_pp: f64,
     is_cost_experiment: bool,
     evaluation_margin: f64,
+    method: SignificanceMethod,
 ) -> (Option<f64>, Option<(f64, f64)>, ExperimentVerdict) {
     let n_c = control.transaction_count as f64;
     let n_v = variant.transaction_count as f64;
@@ -287,9 +295,10 @@ fn compute_significance(
         return (None, None, ExperimentVerdict::NotSignificant);
     }
 
-    let z = delta / se;
-    let p_value = two_tailed_p_value(z);
-    let half = 1.96 * se;
+    let (p_value, half) = match method {
+        SignificanceMethod::FixedHorizon => (two_tailed_p_value(delta / se), 1.96 * se),
+        SignificanceMethod::Sequential => msprt(delta, se, evaluation_margin * 100.0),
+    };
     // CI units follow what the UI shows: bps of EV delta for cost experiments; auth percentage
     // points for auth-only experiments (exact conversion there — saved ≡ 0 means the EV delta
     // is margin·10⁴ × Δauth, so pp = bps / (margin·100)).
@@ -300,7 +309,7 @@ fn compute_significance(
         ((delta - half) / to_pp, (delta + half) / to_pp)
     };
 
-    let verdict = if p_value < 0.05 {
+    let verdict = if p_value < ALPHA {
         if delta > 0.0 {
             ExperimentVerdict::VariantWins
         } else {
@@ -313,6 +322,24 @@ fn compute_significance(
     (Some(p_value), Some(ci), verdict)
 }
 
+/// Always-valid p-value and confidence-sequence half-width for an observed `delta` with standard
+/// error `se`, under a normal mixture of effects with standard deviation `tau` (Johari et al.,
+/// "Always Valid Inference"). The p-value is the inverse of the mixture likelihood ratio against
+/// a zero effect, so rejecting whenever it falls below α keeps the false-positive rate at α no
+/// matter how often the results are read.
+///
+/// `tau` is the size of effect the test is tuned to detect fastest; the caller passes one
+/// percentage point of auth rate in EV bps (`margin·100`), which also suits cost experiments,
+/// where savings of tens of bps are typical.
+fn msprt(delta: f64, se: f64, tau: f64) -> (f64, f64) {
+    let v = se * se;
+    let t = tau * tau;
+    let log_ratio = 0.5 * (v / (v + t)).ln() + t * delta * delta / (2.0 * v * (v + t));
+    let p_value = (-log_ratio).exp().min(1.0);
+    let half = (v * (v + t) / t * (2.0 * (1.0 / ALPHA).ln() + ((v + t) / v).ln())).sqrt();
+    (p_value, half)
+}
+
 /// Two-tailed p-value from z-score.
 /// Uses the Abramowitz & Stegun erfc approximation (7.1.26), max error < 1.5e-7.
 fn two_tailed_p_value(z: f64) -> f64 {
@@ -326,3 +353,37 @@ fn erfc_approx(x: f64) -> f64 {
             + t * (-0.284496736 + t * (1.421413741 + t * (-1.453152027 + t * 1.061405429))));
     poly * (-x * x).exp()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sequential_interval_excludes_zero_exactly_when_significant() {
+        for (delta, se) in [
+            (30.0, 10.0),
+            (12.0, 10.0),
+            (-45.0, 12.0),
+            (5.0, 1.0),
+            (0.5, 1.0),
+        ] {
+            let (p_value, half) = msprt(delta, se, 20.0);
+            assert_eq!(p_value < ALPHA, delta.abs() > half, "delta={delta} se={se}");
+        }
+    }
+
+    #[test]
+    fn sequential_test_is_more_conservative_than_fixed_horizon() {
+        for (delta, se) in [(25.0, 10.0), (40.0, 10.0), (3.0, 1.0)] {
+            let (p_value, half) = msprt(delta, se, 20.0);
+            assert!(p_value >= two_tailed_p_value(delta / se));
+            assert!(half > 1.96 * se);
+        }
+    }
+
+    #[test]
+    fn sequential_p_value_is_capped_at_one() {
+        let (p_value, _) = msprt(0.0, 10.0, 20.0);
+        assert_eq!(p_value, 1.0);
+    }
+}
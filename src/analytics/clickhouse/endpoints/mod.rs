@@ -1,4 +1,5 @@
 pub mod cost_savings;
+pub mod decision_replay;
 pub mod decisions;
 pub mod experiment_results;
 pub mod experiment_transactions;
@@ -153,4 +153,14 @@ impl AnalyticsReadStore for ClickHouseAnalyticsStore {
     ) -> Result<Vec<crate::analytics::store::SegmentTraffic>, ApiError> {
         endpoints::segment_traffic::load(&self.client, merchant_id, since_ms, active_dims).await
     }
+
+    async fn recorded_decisions(
+        &self,
+        merchant_id: &str,
+        start_ms: i64,
+        end_ms: i64,
+        limit: u64,
+    ) -> Result<Vec<crate::analytics::store::RecordedDecision>, ApiError> {
+        endpoints::decision_replay::load(&self.client, merchant_id, start_ms, end_ms, limit).await
+    }
 }
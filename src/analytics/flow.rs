@@ -89,6 +89,9 @@ pub enum FlowType {
     RoutingCreateAbTest,
     RoutingEvaluateAbTest,
     AutopilotCalibration,
+    RoutingScheduleTransition,
+    ExperimentAutoAction,
+    ConfigAudit,
 }
 
 impl FlowType {
@@ -156,6 +159,9 @@ impl FlowType {
             Self::RoutingCreateAbTest => "routing_create_ab_test",
             Self::RoutingEvaluateAbTest => "routing_evaluate_ab_test",
             Self::AutopilotCalibration => "autopilot_calibration",
+            Self::RoutingScheduleTransition => "routing_schedule_transition",
+            Self::ExperimentAutoAction => "experiment_auto_action",
+            Self::ConfigAudit => "config_audit",
         }
     }
 }
@@ -183,6 +189,7 @@ pub enum AnalyticsRoute {
     UpdateScore,
     RoutingEvaluate,
     RoutingCreate,
+    RoutingActivate,
     RuleConfigCreate,
     RuleConfigGet,
     RuleConfigUpdate,
@@ -197,6 +204,7 @@ impl AnalyticsRoute {
             Self::UpdateScore => "update_score",
             Self::RoutingEvaluate => "routing_evaluate",
             Self::RoutingCreate => "routing_create",
+            Self::RoutingActivate => "routing_activate",
             Self::RuleConfigCreate => "rule_config_create",
             Self::RuleConfigGet => "rule_config_get",
             Self::RuleConfigUpdate => "rule_config_update",
@@ -211,6 +219,7 @@ impl AnalyticsRoute {
             "update_score" => Some(Self::UpdateScore),
             "routing_evaluate" => Some(Self::RoutingEvaluate),
             "routing_create" => Some(Self::RoutingCreate),
+            "routing_activate" => Some(Self::RoutingActivate),
             "rule_config_create" => Some(Self::RuleConfigCreate),
             "rule_config_get" => Some(Self::RuleConfigGet),
             "rule_config_update" => Some(Self::RuleConfigUpdate),
@@ -227,6 +236,7 @@ impl AnalyticsRoute {
             "Update Score" => Some(Self::UpdateScore),
             "Rule Evaluate" => Some(Self::RoutingEvaluate),
             "Routing Create" => Some(Self::RoutingCreate),
+            "Routing Activate" => Some(Self::RoutingActivate),
             "Rule Config Create" => Some(Self::RuleConfigCreate),
             "Rule Config Get" => Some(Self::RuleConfigGet),
             "Rule Config Update" => Some(Self::RuleConfigUpdate),
@@ -242,6 +252,7 @@ impl AnalyticsRoute {
             Self::UpdateScore => "Update Score",
             Self::RoutingEvaluate => "Rule Evaluate",
             Self::RoutingCreate => "Routing Create",
+            Self::RoutingActivate => "Routing Activate",
             Self::RuleConfigCreate => "Rule Config Create",
             Self::RuleConfigGet => "Rule Config Get",
             Self::RuleConfigUpdate => "Rule Config Update",
@@ -517,8 +517,9 @@ pub struct ExperimentArmMetrics {
     pub net_ev_bps: Option<f64>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, strum::Display)]
 #[serde(rename_all = "snake_case")]
+#[strum(serialize_all = "snake_case")]
 pub enum ExperimentVerdict {
     /// Not enough transactions yet to make a judgment.
     CollectingData,
@@ -532,6 +533,20 @@ pub enum ExperimentVerdict {
     GuardrailBreached,
 }
 
+/// How an experiment's significance is judged.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SignificanceMethod {
+    /// Fixed-horizon z-test. Valid when the results are read once, at the planned sample size;
+    /// checking it repeatedly and stopping at the first significant reading inflates false
+    /// positives.
+    #[default]
+    FixedHorizon,
+    /// Mixture sequential probability ratio test (mSPRT): an always-valid p-value and confidence
+    /// sequence, which stay valid however often the results are read.
+    Sequential,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ExperimentResultsResponse {
     pub experiment_id: String,
@@ -549,6 +564,8 @@ pub struct ExperimentResultsResponse {
     /// experiments, auth percentage points for auth-only experiments.
     pub confidence_interval: Option<(f64, f64)>,
     pub verdict: ExperimentVerdict,
+    /// Which test produced `p_value`, `confidence_interval` and `verdict`.
+    pub method: SignificanceMethod,
     /// Min sample size from experiment config; used to show progress.
     pub min_sample_size: u32,
     /// EV delta in bps of ticket (variant − control), valued at `evaluation_margin`. The verdict
@@ -565,11 +582,15 @@ pub struct ExperimentResultsQuery {
     pub merchant_id: String,
     pub start_ms: Option<i64>,
     pub end_ms: Option<i64>,
+    /// The arms compared. Multi-arm experiments are read one pair at a time.
+    pub control_arm: String,
+    pub variant_arm: String,
     pub min_sample_size: u32,
     pub guardrail_threshold_pp: f64,
     /// Common business margin (fraction of ticket) used to score net value for both arms.
     /// Defaults to `DEFAULT_EVALUATION_MARGIN` when the caller omits it.
     pub evaluation_margin: f64,
+    pub method: SignificanceMethod,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -34,6 +34,14 @@ pub struct SegmentTraffic {
     pub gateway_count: i64,
 }
 
+/// One recorded `/decide-gateway` decision: when it was made and its event `details`, the
+/// serialized request and response the routing replay re-runs.
+#[derive(Debug, Clone)]
+pub struct RecordedDecision {
+    pub created_at_ms: i64,
+    pub details: String,
+}
+
 #[async_trait]
 pub trait AnalyticsReadStore: Send + Sync {
     async fn overview(&self, query: &AnalyticsQuery)
@@ -100,6 +108,15 @@ pub trait AnalyticsReadStore: Send + Sync {
         &self,
         query: &RoutingEventsQuery,
     ) -> Result<RoutingEventsResponse, ApiError>;
+
+    /// A merchant's recorded decisions in `[start_ms, end_ms]`, oldest first, at most `limit`.
+    async fn recorded_decisions(
+        &self,
+        merchant_id: &str,
+        start_ms: i64,
+        end_ms: i64,
+        limit: u64,
+    ) -> Result<Vec<RecordedDecision>, ApiError>;
 }
 
 #[derive(Clone)]
@@ -204,4 +221,14 @@ impl AnalyticsReadStore for UnavailableAnalyticsReadStore {
     ) -> Result<RoutingEventsResponse, ApiError> {
         Err(ApiError::DatabaseError)
     }
+
+    async fn recorded_decisions(
+        &self,
+        _merchant_id: &str,
+        _start_ms: i64,
+        _end_ms: i64,
+        _limit: u64,
+    ) -> Result<Vec<RecordedDecision>, ApiError> {
+        Err(ApiError::DatabaseError)
+    }
 }
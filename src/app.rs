@@ -326,6 +326,17 @@ where
         global_app_state.global_config.sr_auto_calibration.clone(),
     );
 
+    // Background job: apply scheduled routing activations, flipping a merchant's active algorithm
+    // as its activation window opens and closes.
+    crate::routing_scheduler::spawn(global_app_state.global_config.routing_scheduler.clone());
+
+    // Background job: pause A/B variants that breach their guardrail and promote winners, for
+    // experiments whose policy allows it.
+    crate::experiment_evaluator::spawn(
+        global_app_state.analytics_runtime.clone(),
+        global_app_state.global_config.experiment_evaluator.clone(),
+    );
+
     // Background job: drain the settlement-report ingest queue (download → parse → stage).
     // No-op unless `cost_ingestion.worker_enabled` is set.
     crate::cost_ingestion::worker::spawn(
@@ -337,12 +348,33 @@ where
     // them. Connector-agnostic; no-op unless `report_poll_enabled`.
     crate::cost_ingestion::poller::spawn(global_app_state.global_config.cost_ingestion.clone());
 
+    // Background job: watch the configured drop folders (SFTP-delivered settlement files in a
+    // directory or bucket) and enqueue each new file. No-op unless `drop_folder.enabled`.
+    crate::cost_ingestion::drop_folder::spawn(
+        global_app_state
+            .global_config
+            .cost_ingestion
+            .drop_folder
+            .clone(),
+    );
+
+    // Background job: reload the FX rates that let a cost model fitted in one currency price a
+    // payment in another (rollup at ingest, serving at decide).
+    crate::cost_ingestion::fx::spawn(
+        global_app_state.global_config.cost_ingestion.fx.clone(),
+        global_app_state.global_config.analytics.clickhouse.clone(),
+    );
+
     // Background job: refresh the in-house cost serving view from the fitted models, so the
     // multi-objective router can price candidates from our own ingested data.
     crate::cost_ingestion::serving::spawn(
         global_app_state.global_config.analytics.clickhouse.clone(),
     );
 
+    // Background job: mirror each volume-tier contract's month-to-date volume from Redis, so the
+    // serving lookup can price the marginal tier without a network call.
+    crate::cost_ingestion::volume_tiers::spawn();
+
     // Create a signal stream for SIGTERM
     let mut sigterm = signal(SignalKind::terminate()).expect("Failed to create SIGTERM handler");
 
@@ -397,6 +429,38 @@ where
             "/routing/evaluate",
             axum::routing::post(crate::euclid::handlers::routing_rules::routing_evaluate),
         )
+        .route(
+            "/routing/format",
+            axum::routing::post(crate::euclid::handlers::routing_rules::format_routing_program),
+        )
+        .route(
+            "/routing/lint",
+            axum::routing::post(crate::euclid::handlers::routing_rules::lint_routing_algorithm),
+        )
+        .route(
+            "/routing/versions/list",
+            axum::routing::post(crate::euclid::handlers::routing_versions::list_routing_versions),
+        )
+        .route(
+            "/routing/versions/diff",
+            axum::routing::post(crate::euclid::handlers::routing_versions::diff_routing_versions),
+        )
+        .route(
+            "/routing/versions/activate",
+            axum::routing::post(
+                crate::euclid::handlers::routing_versions::activate_routing_version,
+            ),
+        )
+        .route(
+            "/routing/experiment/actions",
+            axum::routing::post(
+                crate::euclid::handlers::experiment_actions::list_experiment_actions,
+            ),
+        )
+        .route(
+            "/routing/replay",
+            axum::routing::post(crate::euclid::handlers::routing_replay::replay_routing),
+        )
         .route(
             "/decision_gateway",
             post(routes::decision_gateway::decision_gateway),
@@ -516,6 +580,27 @@ where
             put(routes::connector_fees::set_fee_override)
                 .delete(routes::connector_fees::delete_fee_override),
         )
+        .route(
+            "/merchant-account/:merchant-id/volume-tiers",
+            get(routes::connector_fees::list_volume_tiers),
+        )
+        .route(
+            "/merchant-account/:merchant-id/connectors/:connector/volume-tiers",
+            put(routes::connector_fees::set_volume_tiers)
+                .delete(routes::connector_fees::delete_volume_tiers),
+        )
+        .route(
+            "/merchant-account/:merchant-id/ic-markups",
+            get(routes::connector_fees::list_markups),
+        )
+        .route(
+            "/merchant-account/:merchant-id/connectors/:connector/ic-markup",
+            put(routes::connector_fees::set_markup).delete(routes::connector_fees::delete_markup),
+        )
+        .route(
+            "/merchant-account/:merchant-id/interchange-comparison",
+            get(routes::connector_fees::interchange_comparison),
+        )
         .route(
             "/merchant-account/:merchant-id/cost-clusters",
             get(routes::cost_clusters::list_cost_clusters),
@@ -551,10 +636,31 @@ where
             "/merchant-account/:merchant-id/cost-ingestions/:ingestion-id",
             delete(routes::report_upload::delete_ingestion),
         )
+        .route(
+            "/merchant-account/:merchant-id/cost-ingestions/reprocess",
+            post(routes::report_upload::reprocess_ingestions),
+        )
+        .route(
+            "/merchant-account/:merchant-id/cost-ingestions/:ingestion-id/reprocess",
+            post(routes::report_upload::reprocess_ingestion),
+        )
         .route(
             "/merchant-account/:merchant-id/cost-price-changes",
             get(routes::report_upload::list_price_changes),
         )
+        .route(
+            "/merchant-account/:merchant-id/cost-price-alerts",
+            get(routes::cost_price_alerts::list_price_alerts),
+        )
+        .route(
+            "/merchant-account/:merchant-id/cost-price-alerts/:alert-id/acknowledge",
+            post(routes::cost_price_alerts::acknowledge_price_alert),
+        )
+        .route(
+            "/merchant-account/:merchant-id/cost-price-alert-settings",
+            get(routes::cost_price_alerts::get_price_alert_settings)
+                .put(routes::cost_price_alerts::set_price_alert_settings),
+        )
         .route(
             "/merchant-account/:merchant-id/debit-routing",
             post(routes::merchant_account_config::update_debit_routing),
@@ -603,22 +709,31 @@ where
             get(routes::api_key::list_api_keys),
         )
         .route("/api-key/:key_id", delete(routes::api_key::revoke_api_key))
+        .route(
+            "/api-key/:key_id/rotate",
+            post(routes::api_key::rotate_api_key),
+        )
+        .route("/audit", get(routes::audit::list_audit_log))
         .nest("/analytics", routes::analytics::serve())
+        // Inside authentication, so the audit log can name whoever it let through.
+        .layer(middleware::from_fn(crate::audit::capture))
         .layer(middleware::from_fn(custom_middleware::authenticate));
 
-    // Routes that do not require authentication (public)
+    // Routes that do not require authentication (public). Those changing configuration check
+    // credentials in their handlers, and are audited one by one.
+    let audited = || middleware::from_fn(crate::audit::capture);
     let public_router = axum::Router::new()
         .route(
             "/merchant-account/create",
-            post(routes::merchant_account_config::create_merchant_config),
+            post(routes::merchant_account_config::create_merchant_config).layer(audited()),
         )
         .route(
             "/admin/hierarchy/reconcile",
-            post(routes::hierarchy::reconcile_hierarchy),
+            post(routes::hierarchy::reconcile_hierarchy).layer(audited()),
         )
         .route(
             "/admin/hierarchy/sync",
-            post(routes::hierarchy::sync_hierarchy),
+            post(routes::hierarchy::sync_hierarchy).layer(audited()),
         )
         .route(
             "/webhooks/settlement/:connector",
@@ -645,6 +760,10 @@ where
             "/auth/super-admin/lookup",
             post(routes::user_auth::lookup_merchants),
         )
+        .route(
+            "/auth/super-admin/reset-2fa",
+            post(routes::two_factor::reset).layer(audited()),
+        )
         .route(
             "/onboarding/merchant",
             post(routes::user_auth::create_merchant),
@@ -652,7 +771,18 @@ where
         .route("/merchant/members", get(routes::user_auth::list_members))
         .route(
             "/merchant/members/invite",
-            post(routes::user_auth::invite_member),
+            post(routes::user_auth::invite_member).layer(audited()),
+        )
+        .route(
+            "/merchant/members/:user_id/role",
+            put(routes::user_auth::update_member_role).layer(audited()),
+        )
+        .route("/merchant/roles", get(routes::user_auth::list_roles))
+        .route(
+            "/merchant/roles/:role_name",
+            put(routes::user_auth::upsert_role)
+                .delete(routes::user_auth::delete_role)
+                .layer(audited()),
         )
         .route("/auth/verify-email", get(routes::user_auth::verify_email))
         .route(
@@ -674,6 +804,28 @@ where
         .route(
             "/auth/admin/merchant-token/exchange",
             post(routes::user_auth::exchange_merchant_token),
+        )
+        .route("/auth/sso/providers", get(routes::sso::list_providers))
+        .route("/auth/sso/:provider/start", get(routes::sso::start))
+        .route("/auth/sso/:provider/callback", get(routes::sso::callback))
+        .route("/auth/sso/exchange", post(routes::sso::exchange))
+        .route(
+            "/merchant/sso",
+            get(routes::sso::get_sso_requirement)
+                .merge(put(routes::sso::set_sso_requirement).layer(audited())),
+        )
+        .route("/auth/2fa/verify", post(routes::two_factor::verify))
+        .route("/auth/2fa/enroll", post(routes::two_factor::enroll))
+        .route("/auth/2fa/confirm", post(routes::two_factor::confirm))
+        .route("/auth/2fa/disable", post(routes::two_factor::disable))
+        .route(
+            "/auth/2fa/recovery-codes",
+            post(routes::two_factor::regenerate_recovery_codes),
+        )
+        .route(
+            "/merchant/two-factor",
+            get(routes::two_factor::get_two_factor_requirement)
+                .merge(put(routes::two_factor::set_two_factor_requirement).layer(audited())),
         );
 
     let router = axum::Router::new()
@@ -0,0 +1,384 @@
+//! The configuration audit log: who changed what, through which route, and what it was before.
+//!
+//! [`capture`] wraps every route that changes configuration — as [`access::is_config_change`]
+//! classifies it — and, once the change has succeeded, appends a row to `config_audit_log` and
+//! exports the same row to the analytics pipeline. The row names the actor from the request's
+//! credentials and keeps the request body, with anything secret-looking redacted.
+//!
+//! The body says what was asked for, not what it replaced. A handler that knows the state it is
+//! replacing reports it with [`record_change`], and that pair is stored alongside.
+//!
+//! A background job that changes configuration on its own, with no request behind it, appends its
+//! row directly with [`record_job_change`], naming itself as the actor.
+//!
+//! The table is append-only: nothing here updates or deletes a row, and the migration installs
+//! triggers that refuse to.
+
+use std::sync::{Arc, Mutex};
+
+use axum::{
+    body::Body,
+    extract::{MatchedPath, Request},
+    http::{header, HeaderMap},
+    middleware::Next,
+    response::Response,
+};
+use masking::PeekInterface;
+use serde::Serialize;
+
+use crate::analytics::{
+    AnalyticsFlowContext, AnalyticsRoute, ApiFlow, DomainAnalyticsEvent, FlowType,
+};
+use crate::app::{get_tenant_app_state, APP_STATE};
+use crate::auth::{access, AuthContext, AuthKind};
+use crate::logger;
+use crate::storage::{consts, types::ConfigAuditEntryNew};
+
+/// Request bodies larger than this are recorded as their size alone.
+const MAX_BODY_BYTES: usize = 32 * 1024;
+
+/// Fields whose value is replaced before a body or state is stored, matched case-insensitively
+/// anywhere in the field name. A `_hint` field is the masked preview the API already shows, and is
+/// kept so a credential rotation can be told apart from a re-save.
+const SECRET_FIELDS: &[&str] = &[
+    "secret",
+    "password",
+    "token",
+    "api_key",
+    "apikey",
+    "private_key",
+    "download_auth",
+    "credential",
+    "authorization",
+];
+
+const REDACTED: &str = "[redacted]";
+
+#[derive(Debug, Default)]
+struct Change {
+    before: Option<serde_json::Value>,
+    after: Option<serde_json::Value>,
+}
+
+tokio::task_local! {
+    static CHANGE: Arc<Mutex<Option<Change>>>;
+}
+
+/// Record what the current request replaced and what it left in its place.
+///
+/// Either side serializing to `null` means there was nothing there: `&()` before a create, or
+/// after a delete. Secrets are redacted as they are in request bodies, but a handler should still
+/// pass the masked view of anything sensitive rather than rely on that. Outside a request that is
+/// being audited this does nothing.
+pub fn record_change(before: &impl Serialize, after: &impl Serialize) {
+    let change = Change {
+        before: to_state(before),
+        after: to_state(after),
+    };
+    let _ = CHANGE.try_with(|slot| {
+        if let Ok(mut slot) = slot.lock() {
+            *slot = Some(change);
+        }
+    });
+}
+
+/// Middleware recording each successful configuration change. Layered inside authentication, so
+/// the credentials it reads have already been checked.
+pub async fn capture(request: Request<Body>, next: Next) -> Response {
+    let route = request
+        .extensions()
+        .get::<MatchedPath>()
+        .map(|matched| matched.as_str().to_string());
+    if !access::is_config_change(request.method(), route.as_deref()) {
+        return next.run(request).await;
+    }
+
+    let actor = Actor::of(&request);
+    let route = route.unwrap_or_default();
+    let path = request.uri().path().to_string();
+    let method = request.method().to_string();
+    let request_id = request
+        .headers()
+        .get(consts::X_REQUEST_ID)
+        .and_then(|value| value.to_str().ok())
+        .map(str::to_string);
+    let path_merchant_id = path_param(&route, &path, &[":merchant-id", ":merchant_id"]);
+
+    // Only JSON is kept: uploads are reports and invoices, large and stored in full elsewhere.
+    let (request, body_handle) = if is_json(request.headers()) {
+        let (parts, body) = request.into_parts();
+        let (body, handle) = crate::analytics::CaptureBody::new(body);
+        (Request::from_parts(parts, Body::new(body)), Some(handle))
+    } else {
+        (request, None)
+    };
+
+    let slot = Arc::new(Mutex::new(None));
+    let response = CHANGE.scope(slot.clone(), next.run(request)).await;
+    let status_code = response.status().as_u16();
+    if status_code >= 400 {
+        return response;
+    }
+    let change = slot.lock().ok().and_then(|mut slot| slot.take());
+
+    tokio::spawn(async move {
+        let body = match body_handle {
+            Some(handle) => Some(handle.wait().await.bytes),
+            None => None,
+        };
+        let body = body.as_deref().and_then(parse_body);
+        let merchant_id = path_merchant_id
+            .or_else(|| body.as_ref().and_then(merchant_of_body))
+            .or(actor.merchant_id);
+        let change = change.unwrap_or_default();
+
+        let entry = ConfigAuditEntryNew {
+            merchant_id,
+            actor: actor.name,
+            actor_kind: actor.kind.to_string(),
+            user_id: actor.user_id,
+            http_method: method,
+            route,
+            path,
+            request_id,
+            status_code: i32::from(status_code),
+            request_body: body.map(|body| body.to_string()),
+            before_state: change.before.map(|state| state.to_string()),
+            after_state: change.after.map(|state| state.to_string()),
+            created_at: crate::utils::date_time::now(),
+        };
+        append(entry).await;
+    });
+
+    response
+}
+
+/// Append a change a background job made, such as the routing scheduler swapping a merchant's
+/// active algorithm. `job` is recorded as the actor and as the route; there is no HTTP method or
+/// status to record.
+pub async fn record_job_change(
+    job: &str,
+    merchant_id: &str,
+    before: &impl Serialize,
+    after: &impl Serialize,
+) {
+    append(ConfigAuditEntryNew {
+        merchant_id: Some(merchant_id.to_string()),
+        actor: job.to_string(),
+        actor_kind: "job".to_string(),
+        user_id: None,
+        http_method: String::new(),
+        route: job.to_string(),
+        path: job.to_string(),
+        request_id: None,
+        status_code: 0,
+        request_body: None,
+        before_state: to_state(before).map(|state| state.to_string()),
+        after_state: to_state(after).map(|state| state.to_string()),
+        created_at: crate::utils::date_time::now(),
+    })
+    .await;
+}
+
+/// Store one row, and export it to the analytics pipeline.
+async fn append(entry: ConfigAuditEntryNew) {
+    DomainAnalyticsEvent::record_operation(
+        AnalyticsFlowContext::new(ApiFlow::MerchantAccount, FlowType::ConfigAudit),
+        AnalyticsRoute::RuleConfigUpdate,
+        entry.merchant_id.clone(),
+        None,
+        entry.request_id.clone(),
+        None,
+        None,
+        Some("success".to_string()),
+        crate::analytics::serialize_details(&entry),
+        Some("config_audit".to_string()),
+    );
+
+    let state = get_tenant_app_state().await;
+    if let Err(error) = crate::generics::generic_insert(&state.db, entry).await {
+        logger::error!(?error, "failed to append to the configuration audit log");
+    }
+}
+
+/// Who made a request, as far as its credentials say.
+#[derive(Debug)]
+struct Actor {
+    name: String,
+    kind: &'static str,
+    user_id: Option<String>,
+    merchant_id: Option<String>,
+}
+
+impl Actor {
+    fn of(request: &Request<Body>) -> Self {
+        if let Some(context) = request.extensions().get::<AuthContext>() {
+            return Self::from_context(context);
+        }
+        // Routes outside the authenticated router check credentials in the handler; they are
+        // checked again here only to name the actor.
+        let Some(app_state) = APP_STATE.get() else {
+            return Self::unnamed("anonymous");
+        };
+        if let Some(claims) = bearer(request.headers()).and_then(|token| {
+            crate::auth::verify_jwt(token, app_state.global_config.user_auth.jwt_secret.peek()).ok()
+        }) {
+            return Self::from_context(&AuthContext::from_jwt(&claims, false));
+        }
+        let admin_secret = app_state.global_config.admin_secret.secret.peek();
+        let provided = request
+            .headers()
+            .get("x-admin-secret")
+            .and_then(|value| value.to_str().ok());
+        if !admin_secret.is_empty() && provided == Some(admin_secret.as_str()) {
+            return Self::unnamed("admin_secret");
+        }
+        Self::unnamed("anonymous")
+    }
+
+    fn from_context(context: &AuthContext) -> Self {
+        Self {
+            name: context.actor(),
+            kind: match context.auth_kind {
+                AuthKind::Jwt => "jwt",
+                AuthKind::ApiKey => "api_key",
+            },
+            user_id: context.user_id.clone(),
+            merchant_id: Some(context.merchant_id.clone()),
+        }
+    }
+
+    fn unnamed(kind: &'static str) -> Self {
+        Self {
+            name: kind.to_string(),
+            kind,
+            user_id: None,
+            merchant_id: None,
+        }
+    }
+}
+
+fn bearer(headers: &HeaderMap) -> Option<&str> {
+    headers
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+}
+
+fn is_json(headers: &HeaderMap) -> bool {
+    headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value.starts_with("application/json"))
+}
+
+/// The value of a path parameter, found by lining the routed pattern up against the request path.
+fn path_param(route: &str, path: &str, names: &[&str]) -> Option<String> {
+    route
+        .split('/')
+        .zip(path.split('/'))
+        .find(|(pattern, _)| names.contains(pattern))
+        .map(|(_, value)| value.to_string())
+}
+
+/// The body as it will be stored: redacted, or only its size when it is too large to keep.
+fn parse_body(bytes: &[u8]) -> Option<serde_json::Value> {
+    if bytes.len() > MAX_BODY_BYTES {
+        return Some(serde_json::json!({ "truncated": true, "bytes": bytes.len() }));
+    }
+    let mut body = serde_json::from_slice::<serde_json::Value>(bytes).ok()?;
+    redact(&mut body);
+    Some(body)
+}
+
+fn merchant_of_body(body: &serde_json::Value) -> Option<String> {
+    ["merchant_id", "merchantId", "created_by", "createdBy"]
+        .into_iter()
+        .find_map(|key| body.get(key).and_then(|value| value.as_str()))
+        .map(str::to_string)
+}
+
+fn to_state(value: &impl Serialize) -> Option<serde_json::Value> {
+    let mut state = serde_json::to_value(value).ok().filter(|v| !v.is_null())?;
+    redact(&mut state);
+    Some(state)
+}
+
+fn redact(value: &mut serde_json::Value) {
+    match value {
+        serde_json::Value::Object(fields) => {
+            for (name, field) in fields.iter_mut() {
+                let name = name.to_ascii_lowercase();
+                let secret = SECRET_FIELDS.iter().any(|secret| name.contains(secret))
+                    && !name.ends_with("_hint");
+                if secret && !field.is_null() {
+                    *field = serde_json::Value::String(REDACTED.to_string());
+                } else {
+                    redact(field);
+                }
+            }
+        }
+        serde_json::Value::Array(items) => items.iter_mut().for_each(redact),
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn secrets_are_redacted_at_any_depth() {
+        let body = parse_body(
+            br#"{"connector":"adyen","source":{"download_auth":{"api_key":"k"},"webhook_secret":"s"},
+                "accounts":[{"password":"p","name":"eu"}],"token_ttl":null,
+                "webhook_secret_hint":"****a3f9"}"#,
+        )
+        .expect("json");
+        assert_eq!(body["connector"], "adyen");
+        assert_eq!(body["source"]["download_auth"], REDACTED);
+        assert_eq!(body["source"]["webhook_secret"], REDACTED);
+        assert_eq!(body["accounts"][0]["password"], REDACTED);
+        assert_eq!(body["accounts"][0]["name"], "eu");
+        assert_eq!(body["webhook_secret_hint"], "****a3f9");
+        // Nothing to hide in an absent value, and saying it was absent is useful.
+        assert!(body["token_ttl"].is_null());
+    }
+
+    #[test]
+    fn an_oversized_body_is_recorded_by_size() {
+        let large = format!(r#"{{"rules":"{}"}}"#, "x".repeat(MAX_BODY_BYTES));
+        let body = parse_body(large.as_bytes()).expect("recorded");
+        assert_eq!(body["truncated"], true);
+        assert_eq!(body["bytes"], large.len());
+    }
+
+    #[test]
+    fn the_merchant_comes_from_the_routed_path() {
+        assert_eq!(
+            path_param(
+                "/merchant-account/:merchant-id/connectors/:connector/credentials",
+                "/merchant-account/m_1/connectors/adyen/credentials",
+                &[":merchant-id"],
+            ),
+            Some("m_1".to_string())
+        );
+        assert_eq!(
+            path_param(
+                "/gateway-score/reset",
+                "/gateway-score/reset",
+                &[":merchant-id"]
+            ),
+            None
+        );
+    }
+
+    #[test]
+    fn nothing_recorded_reads_as_absent() {
+        assert!(to_state(&()).is_none());
+        assert_eq!(
+            to_state(&serde_json::json!({ "enabled": true })),
+            Some(serde_json::json!({ "enabled": true }))
+        );
+    }
+}
@@ -6,6 +6,8 @@
 //!
 //! Adding a permission is a two-line change — a variant on [`Permission`], and the routes that
 //! need it named below. The token, the middleware, and the dashboard contract stay as they are.
+//! An area of its own is a prefix in [`AREA_PREFIXES`] and a pair of arms in
+//! [`required_permission`].
 
 use super::Permission;
 use axum::http::Method;
@@ -23,33 +25,156 @@ const READ_ONLY_POST_ROUTES: &[&str] = &[
     "/routing/list/:created_by",
     "/routing/list/active/:created_by",
     "/routing/evaluate",
+    "/routing/format",
+    "/routing/lint",
+    "/routing/versions/list",
+    "/routing/versions/diff",
+    "/routing/experiment/actions",
+    "/routing/replay",
     "/rule/get",
     "/merchant-account/:merchant-id/seed-costs/simulate",
 ];
 
+/// The endpoints a payment service calls on every transaction: asking for a decision, and reporting
+/// how it went. Claimed by `RoutingDecide`, so a key holding only that cannot touch the rules.
+/// Matched against the routed pattern, like the list above.
+const DECIDE_ROUTES: &[&str] = &[
+    "/decide-gateway",
+    "/decision_gateway",
+    "/routing/hybrid",
+    "/update-score",
+    "/update-gateway-score",
+];
+
+/// The areas with permissions of their own, by the routed pattern they start with. Anything not
+/// listed here is routing.
+///
+/// Prefixes rather than whole patterns, so a cost endpoint added later under one of these is
+/// covered by the cost permissions the day it is written.
+const AREA_PREFIXES: &[(&str, Area)] = &[
+    ("/analytics", Area::Analytics),
+    ("/api-key/", Area::ApiKeys),
+    ("/audit", Area::Audit),
+    (
+        "/merchant-account/:merchant-id/connectors/:connector/credentials",
+        Area::Credentials,
+    ),
+    ("/cost-ingestion/", Area::Cost),
+    ("/merchant-account/:merchant-id/cost-", Area::Cost),
+    ("/merchant-account/:merchant-id/seed-costs", Area::Cost),
+    ("/merchant-account/:merchant-id/connector-fees", Area::Cost),
+    ("/merchant-account/:merchant-id/volume-tiers", Area::Cost),
+    ("/merchant-account/:merchant-id/ic-markups", Area::Cost),
+    (
+        "/merchant-account/:merchant-id/interchange-comparison",
+        Area::Cost,
+    ),
+    ("/merchant-account/:merchant-id/invoice-", Area::Cost),
+    (
+        "/merchant-account/:merchant-id/connectors/:connector/report",
+        Area::Cost,
+    ),
+    (
+        "/merchant-account/:merchant-id/connectors/:connector/invoice",
+        Area::Cost,
+    ),
+    (
+        "/merchant-account/:merchant-id/connectors/:connector/fee-override",
+        Area::Cost,
+    ),
+    (
+        "/merchant-account/:merchant-id/connectors/:connector/volume-tiers",
+        Area::Cost,
+    ),
+    (
+        "/merchant-account/:merchant-id/connectors/:connector/ic-markup",
+        Area::Cost,
+    ),
+];
+
+/// Whole patterns that belong to an area but do not share a prefix with the rest of it.
+const AREA_ROUTES: &[(&str, Area)] = &[
+    // The connector list is the credentials list: which connectors, under which accounts.
+    (
+        "/merchant-account/:merchant-id/connectors",
+        Area::Credentials,
+    ),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Area {
+    Routing,
+    Cost,
+    Credentials,
+    Analytics,
+    ApiKeys,
+    Audit,
+}
+
+fn area_of(matched_path: Option<&str>) -> Area {
+    let Some(path) = matched_path else {
+        return Area::Routing;
+    };
+    AREA_ROUTES
+        .iter()
+        .find(|(route, _)| *route == path)
+        .or_else(|| {
+            AREA_PREFIXES
+                .iter()
+                .find(|(prefix, _)| path.starts_with(prefix))
+        })
+        .map_or(Area::Routing, |(_, area)| *area)
+}
+
 /// The permission a request needs.
 ///
-/// GET (with the other safe methods) is taken to be free of side effects — a rule HTTP already
-/// imposes, and one that holds across every route here. Everything else counts as a write unless
-/// it is named above, so a route added later requires a write until someone decides otherwise.
-/// That is the safe direction for the mistake to fall: a limited session meets a visible 403,
-/// rather than silently gaining a way to change something.
+/// The routed pattern picks the area — routing, cost, credentials, analytics, API keys or the audit
+/// log — and the method picks read or write within it. GET (with the other safe methods) is taken
+/// to be free of side effects — a rule HTTP already imposes, and one that holds across every route
+/// here.
+/// Everything else counts as a write unless it is named above, so a route added later requires a
+/// write until someone decides otherwise. That is the safe direction for the mistake to fall: a
+/// limited session meets a visible 403, rather than silently gaining a way to change something.
 ///
-/// When a later permission covers an area of its own, this is where it is claimed — a branch on
-/// the matched path returning, say, `AnalyticsRead` for `/analytics/...`.
+/// Member administration is not here: those routes authenticate in their handlers, which check
+/// `MembersManage` themselves.
 pub fn required_permission(method: &Method, matched_path: Option<&str>) -> Permission {
-    if matches!(*method, Method::GET | Method::HEAD | Method::OPTIONS) {
-        return Permission::RoutingRead;
+    match (area_of(matched_path), is_read(method, matched_path)) {
+        (Area::Analytics, true) => Permission::AnalyticsRead,
+        // Analytics has no writes; one appearing would be a mistake, so it needs the most.
+        (Area::Analytics, false) => Permission::RoutingWrite,
+        (Area::ApiKeys, _) => Permission::ApiKeysManage,
+        (Area::Audit, true) => Permission::AuditRead,
+        // The log is append-only; nothing may write to it over HTTP.
+        (Area::Audit, false) => Permission::RoutingWrite,
+        (Area::Credentials, true) => Permission::CredentialsRead,
+        (Area::Credentials, false) => Permission::CredentialsWrite,
+        (Area::Cost, true) => Permission::CostRead,
+        (Area::Cost, false) => Permission::CostWrite,
+        (Area::Routing, true) => Permission::RoutingRead,
+        (Area::Routing, false) if is_post_to(method, matched_path, DECIDE_ROUTES) => {
+            Permission::RoutingDecide
+        }
+        (Area::Routing, false) => Permission::RoutingWrite,
     }
+}
 
-    let is_read = *method == Method::POST
-        && matched_path.is_some_and(|path| READ_ONLY_POST_ROUTES.contains(&path));
+/// Whether a request changes configuration, and so belongs in the audit log: anything that is not
+/// a read, other than the decide routes, which carry payment traffic rather than change settings.
+///
+/// Classified exactly as [`required_permission`] classifies it, so what needs a write permission
+/// and what gets audited cannot drift apart.
+pub fn is_config_change(method: &Method, matched_path: Option<&str>) -> bool {
+    !is_read(method, matched_path) && !is_post_to(method, matched_path, DECIDE_ROUTES)
+}
 
-    if is_read {
-        Permission::RoutingRead
-    } else {
-        Permission::RoutingWrite
-    }
+fn is_read(method: &Method, matched_path: Option<&str>) -> bool {
+    matches!(*method, Method::GET | Method::HEAD | Method::OPTIONS)
+        || is_post_to(method, matched_path, READ_ONLY_POST_ROUTES)
+}
+
+fn is_post_to(method: &Method, matched_path: Option<&str>, routes: &[&str]) -> bool {
+    *method == Method::POST && matched_path.is_some_and(|p| routes.contains(&p))
 }
 
 #[cfg(test)]
@@ -60,9 +185,11 @@ mod tests {
     fn reads_served_over_post_stay_reachable() {
         // The failure this exists to prevent: a read-only user who cannot see the rules.
         for path in READ_ONLY_POST_ROUTES {
-            assert_eq!(
-                required_permission(&Method::POST, Some(path)),
-                Permission::RoutingRead,
+            assert!(
+                matches!(
+                    required_permission(&Method::POST, Some(path)),
+                    Permission::RoutingRead | Permission::CostRead
+                ),
                 "{path} must stay readable"
             );
         }
@@ -77,6 +204,7 @@ mod tests {
             "/rule/delete",
             "/routing/create",
             "/routing/activate",
+            "/routing/versions/activate",
         ] {
             assert_eq!(
                 required_permission(&Method::POST, Some(path)),
@@ -93,18 +221,124 @@ mod tests {
         let path = Some("/merchant-account/:merchant-id/seed-costs");
         assert_eq!(
             required_permission(&Method::GET, path),
-            Permission::RoutingRead
+            Permission::CostRead
         );
         assert_eq!(
             required_permission(&Method::PUT, path),
-            Permission::RoutingWrite
+            Permission::CostWrite
         );
         assert_eq!(
             required_permission(&Method::DELETE, path),
+            Permission::CostWrite
+        );
+    }
+
+    #[test]
+    fn the_decide_routes_need_only_decide() {
+        for path in DECIDE_ROUTES {
+            assert_eq!(
+                required_permission(&Method::POST, Some(path)),
+                Permission::RoutingDecide,
+                "{path} must be reachable with decide alone"
+            );
+        }
+        // Resetting scores changes routing for everyone, so it stays a write.
+        assert_eq!(
+            required_permission(&Method::POST, Some("/gateway-score/reset")),
             Permission::RoutingWrite
         );
     }
 
+    #[test]
+    fn reading_rules_no_longer_reads_everything() {
+        // What the split is for: each of these used to need only routing read or write.
+        for (method, path, needed) in [
+            (
+                Method::GET,
+                "/merchant-account/:merchant-id/connectors",
+                Permission::CredentialsRead,
+            ),
+            (
+                Method::POST,
+                "/merchant-account/:merchant-id/connectors/:connector/credentials",
+                Permission::CredentialsWrite,
+            ),
+            (
+                Method::GET,
+                "/merchant-account/:merchant-id/invoice-addons",
+                Permission::CostRead,
+            ),
+            (
+                Method::PUT,
+                "/merchant-account/:merchant-id/cost-clusters/:cluster-key/fee-override",
+                Permission::CostWrite,
+            ),
+            (
+                Method::POST,
+                "/merchant-account/:merchant-id/connectors/:connector/report",
+                Permission::CostWrite,
+            ),
+            (
+                Method::GET,
+                "/analytics/overview",
+                Permission::AnalyticsRead,
+            ),
+            (
+                Method::GET,
+                "/api-key/list/:merchant_id",
+                Permission::ApiKeysManage,
+            ),
+            (Method::POST, "/api-key/create", Permission::ApiKeysManage),
+        ] {
+            assert_eq!(
+                required_permission(&method, Some(path)),
+                needed,
+                "{method} {path}"
+            );
+        }
+    }
+
+    #[test]
+    fn only_changes_are_audited() {
+        assert!(is_config_change(
+            &Method::POST,
+            Some("/gateway-score/reset")
+        ));
+        assert!(is_config_change(
+            &Method::DELETE,
+            Some("/merchant-account/:merchant-id/cost-clusters/:cluster-key/fee-override")
+        ));
+        assert!(!is_config_change(
+            &Method::GET,
+            Some("/merchant-account/:merchant-id")
+        ));
+        assert!(!is_config_change(&Method::POST, Some("/routing/evaluate")));
+        // Payment traffic, not configuration; auditing it would bury every real change.
+        for path in DECIDE_ROUTES {
+            assert!(!is_config_change(&Method::POST, Some(path)), "{path}");
+        }
+    }
+
+    #[test]
+    fn routing_keeps_its_own_permissions() {
+        assert_eq!(
+            required_permission(&Method::GET, Some("/merchant-account/:merchant-id")),
+            Permission::RoutingRead
+        );
+        assert_eq!(
+            required_permission(&Method::POST, Some("/routing/create")),
+            Permission::RoutingWrite
+        );
+        // A connector's credentials are not its report, nor the other way round.
+        assert_eq!(
+            required_permission(
+                &Method::PUT,
+                Some("/merchant-account/:merchant-id/connectors/:connector/report/column-mapping")
+            ),
+            Permission::CostWrite
+        );
+    }
+
     #[test]
     fn an_unclassified_post_requires_a_write() {
         // A route added later needs a write until someone says otherwise.
@@ -34,22 +34,39 @@ impl AuthContext {
     }
 
     /// An API key is a service credential with no person behind it, so there is no dashboard role
-    /// to limit. Keys are scoped by merchant, and revoked rather than downgraded.
-    pub fn from_api_key(merchant_id: impl Into<String>) -> Self {
+    /// to limit. A key is scoped to one merchant, and to the permissions it was created with —
+    /// `None` for a key created unrestricted, which covers every key minted before scopes existed.
+    ///
+    /// Stored scopes are read as they stand: those written before permissions were split by area
+    /// were widened once, by the `api_key_split_permissions` migration, rather than on every read.
+    pub fn from_api_key(
+        merchant_id: impl Into<String>,
+        permissions: Option<Vec<super::Permission>>,
+    ) -> Self {
         Self {
             merchant_id: merchant_id.into(),
             auth_kind: AuthKind::ApiKey,
             user_id: None,
             email: None,
             role: None,
-            permissions: None,
+            permissions,
+        }
+    }
+
+    /// Who is acting, as recorded against the changes they make: the user's email for a dashboard
+    /// session, and the merchant's key for a service credential.
+    pub fn actor(&self) -> String {
+        match (&self.email, &self.user_id) {
+            (Some(email), _) => email.clone(),
+            (None, Some(user_id)) => user_id.clone(),
+            (None, None) => format!("api_key:{}", self.merchant_id),
         }
     }
 
     /// Whether this session holds `permission`. An unrestricted session holds everything.
     pub fn allows(&self, permission: &super::Permission) -> bool {
         match &self.permissions {
-            Some(held) => held.contains(permission),
+            Some(held) => held.iter().any(|p| p.satisfies(permission)),
             None => true,
         }
     }
@@ -1,5 +1,8 @@
 pub mod access;
 pub mod context;
+pub mod oidc;
+pub mod roles;
+pub mod totp;
 
 use error_stack::{Report, ResultExt};
 use josekit::jws::JwsHeader;
@@ -56,13 +59,33 @@ pub struct JwtClaims {
     /// that do not send them; [`JwtClaims::permissions`] decides what that means.
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub perms: Option<Vec<Permission>>,
+    /// `perms` was written by a build that gives every area its own permissions, so is taken as it
+    /// stands rather than read the way [`widen_pre_split`] reads an older list.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub perms_split: bool,
+    /// Signed in through the merchant's identity provider rather than with a password. A merchant
+    /// that requires SSO only admits sessions carrying this, and only such a session can turn the
+    /// requirement on.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub sso: bool,
+    /// Passed the second step of a two-factor sign-in. A merchant that requires two-factor only
+    /// admits password sessions carrying this; having an authenticator enrolled is not enough.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub mfa: bool,
 }
 
 impl JwtClaims {
+    /// How to mint a session that carries this one's sign-in over, as it moves to another
+    /// merchant: an SSO session stays one, and so does one that passed two-factor.
+    pub fn minter(&self) -> SessionMinter {
+        session_minter(self.sso, self.mfa)
+    }
+
     /// Whether this session holds `permission`.
     pub fn allows(&self, permission: &Permission, require_explicit_permissions: bool) -> bool {
         self.permissions(require_explicit_permissions)
-            .contains(permission)
+            .iter()
+            .any(|held| held.satisfies(permission))
     }
 
     /// The permissions this session effectively holds — what the middleware enforces, and what
@@ -80,17 +103,76 @@ impl JwtClaims {
     /// hold everything, so a Decision Engine deployed ahead of a Hyperswitch that does not send
     /// them keeps working, and turning it on inverts that once omitting them is a bug rather than
     /// an old build.
+    ///
+    /// A list written before cost, credentials, analytics, API keys and members had permissions of
+    /// their own is read as it was then — see [`widen_pre_split`].
     pub fn permissions(&self, require_explicit_permissions: bool) -> Vec<Permission> {
         match &self.perms {
-            Some(held) => held.clone(),
+            Some(held) if self.perms_split => held.clone(),
+            Some(held) => widen_pre_split(held.clone(), &self.role),
             None if require_explicit_permissions && self.token_type == TOKEN_TYPE_HS_REDIRECT => {
                 Vec::new()
             }
-            None => KNOWN_PERMISSIONS.to_vec(),
+            None => widen_pre_split(
+                vec![
+                    Permission::RoutingRead,
+                    Permission::RoutingWrite,
+                    Permission::RoutingDecide,
+                ],
+                &self.role,
+            ),
         }
     }
 }
 
+/// The permissions carved out of routing's, and those added since. Before them, reading routing
+/// rules let a session read everything, writing them let it change everything, and managing
+/// members came from being an admin.
+static SPLIT_FROM_ROUTING: &[Permission] = &[
+    Permission::CostRead,
+    Permission::CostWrite,
+    Permission::CredentialsRead,
+    Permission::CredentialsWrite,
+    Permission::AnalyticsRead,
+    Permission::ApiKeysManage,
+    Permission::MembersManage,
+    Permission::AuditRead,
+];
+
+/// Reads a list that names none of [`SPLIT_FROM_ROUTING`] as it was meant when it was written.
+///
+/// Such a list comes from a token minted before the split, or a Hyperswitch that only knows the
+/// routing permissions — never from a session Decision Engine issued for a role, which is marked
+/// with `perms_split` and read as it stands. Taking it at its word would take away cost, analytics
+/// and credentials from every such session on upgrade — access they had, and nobody decided to
+/// remove. A list naming any of the newer permissions was written knowing about them, and is taken
+/// as it stands.
+///
+/// An admin also reads the audit log, as the built-in `admin` role does.
+fn widen_pre_split(mut held: Vec<Permission>, role: &str) -> Vec<Permission> {
+    if held.iter().any(|p| SPLIT_FROM_ROUTING.contains(p)) {
+        return held;
+    }
+    if held.contains(&Permission::RoutingRead) {
+        held.extend([
+            Permission::CostRead,
+            Permission::CredentialsRead,
+            Permission::AnalyticsRead,
+        ]);
+    }
+    if held.contains(&Permission::RoutingWrite) {
+        held.extend([
+            Permission::CostWrite,
+            Permission::CredentialsWrite,
+            Permission::ApiKeysManage,
+        ]);
+    }
+    if role == roles::ADMIN {
+        held.extend([Permission::MembersManage, Permission::AuditRead]);
+    }
+    held
+}
+
 /// One thing a session is allowed to do.
 ///
 /// Orthogonal to `ScopeGrant`, which decides *which* scopes a session can reach: the grant comes
@@ -104,6 +186,24 @@ impl JwtClaims {
 pub enum Permission {
     RoutingRead,
     RoutingWrite,
+    /// Asking for a routing decision and reporting its outcome — what a payment service does on
+    /// every transaction, without being able to read or change the rules behind it.
+    RoutingDecide,
+    /// Reading the cost model: fitted fees, clusters, coverage, ingestions and uploaded invoices.
+    CostRead,
+    /// Changing it: uploading settlement reports and invoices, and setting overrides, contracts
+    /// and markups.
+    CostWrite,
+    /// Which connectors have credentials stored, and for which accounts. Never the secrets.
+    CredentialsRead,
+    CredentialsWrite,
+    AnalyticsRead,
+    /// Creating, listing, rotating and revoking the merchant's API keys.
+    ApiKeysManage,
+    /// Inviting members, changing their roles, and defining the merchant's custom roles.
+    MembersManage,
+    /// Reading the audit log of configuration changes.
+    AuditRead,
     /// A permission this build has never heard of, kept verbatim.
     ///
     /// A newer Hyperswitch may grant permissions added after this Decision Engine was built. Failing
@@ -115,7 +215,19 @@ pub enum Permission {
 }
 
 /// Every permission this build understands. A session naming none is described with these.
-pub static KNOWN_PERMISSIONS: &[Permission] = &[Permission::RoutingRead, Permission::RoutingWrite];
+pub static KNOWN_PERMISSIONS: &[Permission] = &[
+    Permission::RoutingRead,
+    Permission::RoutingWrite,
+    Permission::RoutingDecide,
+    Permission::CostRead,
+    Permission::CostWrite,
+    Permission::CredentialsRead,
+    Permission::CredentialsWrite,
+    Permission::AnalyticsRead,
+    Permission::ApiKeysManage,
+    Permission::MembersManage,
+    Permission::AuditRead,
+];
 
 impl Permission {
     /// The wire spelling, which is what Hyperswitch sends and what the token carries. Namespaced by
@@ -124,9 +236,26 @@ impl Permission {
         match self {
             Self::RoutingRead => "routing:read",
             Self::RoutingWrite => "routing:write",
+            Self::RoutingDecide => "routing:decide",
+            Self::CostRead => "cost:read",
+            Self::CostWrite => "cost:write",
+            Self::CredentialsRead => "credentials:read",
+            Self::CredentialsWrite => "credentials:write",
+            Self::AnalyticsRead => "analytics:read",
+            Self::ApiKeysManage => "api_keys:manage",
+            Self::MembersManage => "members:manage",
+            Self::AuditRead => "audit:read",
             Self::Unknown(raw) => raw,
         }
     }
+
+    /// Whether holding this is enough for a request that needs `needed`.
+    ///
+    /// Write covers decide: a session that can change where payments go can already ask where one
+    /// would go, and sessions minted before `RoutingDecide` existed name write but not decide.
+    pub fn satisfies(&self, needed: &Self) -> bool {
+        self == needed || (*self == Self::RoutingWrite && *needed == Self::RoutingDecide)
+    }
 }
 
 impl From<&str> for Permission {
@@ -134,6 +263,15 @@ impl From<&str> for Permission {
         match raw {
             "routing:read" => Self::RoutingRead,
             "routing:write" => Self::RoutingWrite,
+            "routing:decide" => Self::RoutingDecide,
+            "cost:read" => Self::CostRead,
+            "cost:write" => Self::CostWrite,
+            "credentials:read" => Self::CredentialsRead,
+            "credentials:write" => Self::CredentialsWrite,
+            "analytics:read" => Self::AnalyticsRead,
+            "api_keys:manage" => Self::ApiKeysManage,
+            "members:manage" => Self::MembersManage,
+            "audit:read" => Self::AuditRead,
             other => Self::Unknown(other.to_owned()),
         }
     }
@@ -160,16 +298,19 @@ pub const TOKEN_TYPE_HS_REDIRECT: &str = "hs_redirect";
 /// is a cross-merchant session, not a full account session — identity/account operations reject it.
 pub const TOKEN_TYPE_SUPER_ADMIN_VIEW: &str = "super_admin_view";
 
+/// A session for `role`, holding `perms` — the role's permissions, resolved by the caller.
+#[allow(clippy::too_many_arguments)]
 pub fn generate_jwt(
     user_id: &str,
     email: &str,
     merchant_id: &str,
     role: &str,
+    perms: &[Permission],
     token_type: &str,
     secret: &str,
     expiry_seconds: u64,
 ) -> Result<String, Report<AuthError>> {
-    generate_scoped_jwt(
+    mint_jwt(
         user_id,
         email,
         merchant_id,
@@ -178,7 +319,139 @@ pub fn generate_jwt(
         None,
         // Named rather than left absent, so enabling `require_explicit_permissions` — which is
         // about Hyperswitch callers — cannot strand a session Decision Engine issued itself.
+        Some(perms),
+        true,
+        false,
+        false,
+        secret,
+        expiry_seconds,
+    )
+}
+
+/// As [`generate_jwt`], for a user who signed in through an identity provider — see
+/// [`JwtClaims::sso`].
+#[allow(clippy::too_many_arguments)]
+pub fn generate_sso_jwt(
+    user_id: &str,
+    email: &str,
+    merchant_id: &str,
+    role: &str,
+    perms: &[Permission],
+    token_type: &str,
+    secret: &str,
+    expiry_seconds: u64,
+) -> Result<String, Report<AuthError>> {
+    mint_jwt(
+        user_id,
+        email,
+        merchant_id,
+        role,
+        token_type,
+        None,
+        Some(perms),
+        true,
+        true,
+        false,
+        secret,
+        expiry_seconds,
+    )
+}
+
+/// As [`generate_jwt`], for a user who passed the second step of a two-factor sign-in — see
+/// [`JwtClaims::mfa`].
+#[allow(clippy::too_many_arguments)]
+pub fn generate_mfa_jwt(
+    user_id: &str,
+    email: &str,
+    merchant_id: &str,
+    role: &str,
+    perms: &[Permission],
+    token_type: &str,
+    secret: &str,
+    expiry_seconds: u64,
+) -> Result<String, Report<AuthError>> {
+    mint_jwt(
+        user_id,
+        email,
+        merchant_id,
+        role,
+        token_type,
+        None,
+        Some(perms),
+        true,
+        false,
+        true,
+        secret,
+        expiry_seconds,
+    )
+}
+
+/// As [`generate_jwt`], for a user who signed in through an identity provider and then passed the
+/// second step for the authenticator they enrolled.
+#[allow(clippy::too_many_arguments)]
+pub fn generate_sso_mfa_jwt(
+    user_id: &str,
+    email: &str,
+    merchant_id: &str,
+    role: &str,
+    perms: &[Permission],
+    token_type: &str,
+    secret: &str,
+    expiry_seconds: u64,
+) -> Result<String, Report<AuthError>> {
+    mint_jwt(
+        user_id,
+        email,
+        merchant_id,
+        role,
+        token_type,
+        None,
+        Some(perms),
+        true,
+        true,
+        true,
+        secret,
+        expiry_seconds,
+    )
+}
+
+/// [`generate_jwt`], or one of its variants that mark how the user signed in.
+pub type SessionMinter =
+    fn(&str, &str, &str, &str, &[Permission], &str, &str, u64) -> Result<String, Report<AuthError>>;
+
+/// The minter for a session signed in through an identity provider (`sso`), past a second factor
+/// (`mfa`), both or neither.
+pub fn session_minter(sso: bool, mfa: bool) -> SessionMinter {
+    match (sso, mfa) {
+        (true, true) => generate_sso_mfa_jwt,
+        (true, false) => generate_sso_jwt,
+        (false, true) => generate_mfa_jwt,
+        (false, false) => generate_jwt,
+    }
+}
+
+/// A super-admin-view session onto `merchant_id`, entered from the admin's own session `from`. It
+/// keeps how `from` signed in, so exiting hands back a session that passed the same checks.
+pub fn generate_super_admin_view_jwt(
+    from: &JwtClaims,
+    merchant_id: &str,
+    grant: &crate::types::merchant::hierarchy::ScopeGrant,
+    secret: &str,
+    expiry_seconds: u64,
+) -> Result<String, Report<AuthError>> {
+    mint_jwt(
+        &from.user_id,
+        &from.email,
+        merchant_id,
+        roles::ADMIN,
+        TOKEN_TYPE_SUPER_ADMIN_VIEW,
+        Some(grant),
+        // Named rather than left absent, exactly as `generate_jwt` does, so the reading never
+        // depends on the explicit-permissions flag for a session Decision Engine issued.
         Some(KNOWN_PERMISSIONS),
+        false,
+        from.sso,
+        from.mfa,
         secret,
         expiry_seconds,
     )
@@ -197,6 +470,37 @@ pub fn generate_scoped_jwt(
     perms: Option<&[Permission]>,
     secret: &str,
     expiry_seconds: u64,
+) -> Result<String, Report<AuthError>> {
+    mint_jwt(
+        user_id,
+        email,
+        merchant_id,
+        role,
+        token_type,
+        grant,
+        perms,
+        false,
+        false,
+        false,
+        secret,
+        expiry_seconds,
+    )
+}
+
+#[allow(clippy::too_many_arguments)]
+fn mint_jwt(
+    user_id: &str,
+    email: &str,
+    merchant_id: &str,
+    role: &str,
+    token_type: &str,
+    grant: Option<&crate::types::merchant::hierarchy::ScopeGrant>,
+    perms: Option<&[Permission]>,
+    perms_split: bool,
+    sso: bool,
+    mfa: bool,
+    secret: &str,
+    expiry_seconds: u64,
 ) -> Result<String, Report<AuthError>> {
     let now = SystemTime::now()
         .duration_since(UNIX_EPOCH)
@@ -250,6 +554,21 @@ pub fn generate_scoped_jwt(
             )
             .change_context(AuthError::JwtClaimError)?;
     }
+    if perms_split {
+        payload
+            .set_claim("perms_split", Some(serde_json::Value::Bool(true)))
+            .change_context(AuthError::JwtClaimError)?;
+    }
+    if sso {
+        payload
+            .set_claim("sso", Some(serde_json::Value::Bool(true)))
+            .change_context(AuthError::JwtClaimError)?;
+    }
+    if mfa {
+        payload
+            .set_claim("mfa", Some(serde_json::Value::Bool(true)))
+            .change_context(AuthError::JwtClaimError)?;
+    }
     payload
         .set_claim("iat", Some(serde_json::Value::Number(now.into())))
         .change_context(AuthError::JwtClaimError)?;
@@ -334,6 +653,18 @@ pub fn verify_jwt(token: &str, secret: &str) -> Result<JwtClaims, Report<AuthErr
     let perms = payload
         .claim("perms")
         .and_then(|value| serde_json::from_value(value.clone()).ok());
+    let perms_split = payload
+        .claim("perms_split")
+        .and_then(|v| v.as_bool())
+        .unwrap_or(false);
+    let sso = payload
+        .claim("sso")
+        .and_then(|v| v.as_bool())
+        .unwrap_or(false);
+    let mfa = payload
+        .claim("mfa")
+        .and_then(|v| v.as_bool())
+        .unwrap_or(false);
 
     Ok(JwtClaims {
         sub: user_id.clone(),
@@ -347,6 +678,9 @@ pub fn verify_jwt(token: &str, secret: &str) -> Result<JwtClaims, Report<AuthErr
         iat,
         grant,
         perms,
+        perms_split,
+        sso,
+        mfa,
     })
 }
 
@@ -408,7 +742,7 @@ mod tests {
     fn a_permission_this_build_does_not_know_survives_the_token() {
         // The reason `Unknown` exists: a newer Hyperswitch grants something added after this build.
         // Failing the token would log the user out over a permission they may not even need.
-        let perms = vec![Permission::RoutingRead, Permission::from("analytics:read")];
+        let perms = vec![Permission::RoutingRead, Permission::from("reports:export")];
         let token = generate_scoped_jwt(
             "hs_pro_1",
             "",
@@ -472,7 +806,7 @@ mod tests {
 
         // Phase one: a Decision Engine ahead of Hyperswitch keeps working.
         assert!(claims.allows(&Permission::RoutingWrite, false));
-        assert_eq!(claims.permissions(false), KNOWN_PERMISSIONS.to_vec());
+        assert!(KNOWN_PERMISSIONS.iter().all(|p| claims.allows(p, false)));
 
         // Phase two: once every Hyperswitch sends them, silence grants nothing.
         assert!(!claims.allows(&Permission::RoutingWrite, true));
@@ -490,6 +824,7 @@ mod tests {
                 "a@b.com",
                 "merc_1",
                 "admin",
+                KNOWN_PERMISSIONS,
                 token_type,
                 TEST_SECRET,
                 60,
@@ -524,6 +859,9 @@ mod tests {
             iat: 0,
             grant: None,
             perms: None,
+            perms_split: false,
+            sso: false,
+            mfa: false,
         };
 
         for require_explicit in [false, true] {
@@ -531,6 +869,183 @@ mod tests {
         }
     }
 
+    #[test]
+    fn a_list_from_before_the_split_keeps_what_it_had() {
+        // A Hyperswitch that knows only the routing permissions, or a token minted before the
+        // others existed: read still reads costs and analytics, write still uploads reports.
+        let mut claims = claims_with(Some(vec![Permission::RoutingRead]), "member");
+        assert!(claims.allows(&Permission::CostRead, true));
+        assert!(claims.allows(&Permission::AnalyticsRead, true));
+        assert!(!claims.allows(&Permission::CostWrite, true));
+
+        claims.perms = Some(vec![Permission::RoutingRead, Permission::RoutingWrite]);
+        assert!(claims.allows(&Permission::CostWrite, true));
+        assert!(claims.allows(&Permission::ApiKeysManage, true));
+        // Managing members came from being an admin, not from any permission.
+        assert!(!claims.allows(&Permission::MembersManage, true));
+        claims.role = "admin".to_string();
+        assert!(claims.allows(&Permission::MembersManage, true));
+    }
+
+    #[test]
+    fn a_list_written_after_the_split_is_taken_as_it_stands() {
+        // Naming any of the newer permissions shows the writer knew about them, so what is left
+        // out was left out on purpose.
+        let claims = claims_with(
+            Some(vec![Permission::RoutingRead, Permission::AnalyticsRead]),
+            "admin",
+        );
+        assert!(claims.allows(&Permission::AnalyticsRead, true));
+        assert!(!claims.allows(&Permission::CostRead, true));
+        assert!(!claims.allows(&Permission::MembersManage, true));
+    }
+
+    #[test]
+    fn a_role_granting_only_routing_is_not_widened() {
+        // A custom role can name nothing but routing permissions; a session Decision Engine issued
+        // for it must not pick up cost or credentials on the way through the token.
+        let token = generate_jwt(
+            "user_1",
+            "a@b.com",
+            "merc_1",
+            "rules-only",
+            &[Permission::RoutingRead, Permission::RoutingWrite],
+            TOKEN_TYPE_STANDARD,
+            "secret",
+            60,
+        )
+        .expect("token");
+        let claims = verify_jwt(&token, "secret").expect("verifies");
+        assert!(claims.allows(&Permission::RoutingWrite, false));
+        assert!(!claims.allows(&Permission::CostRead, false));
+        assert!(!claims.allows(&Permission::CredentialsWrite, false));
+    }
+
+    #[test]
+    fn only_a_provider_sign_in_is_marked_sso() {
+        for sso in [false, true] {
+            let mint = if sso { generate_sso_jwt } else { generate_jwt };
+            let token = mint(
+                "user_1",
+                "a@b.com",
+                "merc_1",
+                "member",
+                KNOWN_PERMISSIONS,
+                TOKEN_TYPE_STANDARD,
+                TEST_SECRET,
+                60,
+            )
+            .expect("token");
+            let claims = verify_jwt(&token, TEST_SECRET).expect("verifies");
+            assert_eq!(claims.sso, sso);
+            assert!(!claims.mfa);
+            // Otherwise the same session: what it may do does not depend on how it signed in.
+            assert_eq!(claims.permissions(true), KNOWN_PERMISSIONS);
+        }
+    }
+
+    #[test]
+    fn a_session_that_passed_two_factor_stays_marked_as_it_moves() {
+        let token = generate_mfa_jwt(
+            "user_1",
+            "a@b.com",
+            "merc_1",
+            "member",
+            KNOWN_PERMISSIONS,
+            TOKEN_TYPE_STANDARD,
+            TEST_SECRET,
+            60,
+        )
+        .expect("token");
+        let claims = verify_jwt(&token, TEST_SECRET).expect("verifies");
+        assert!(claims.mfa && !claims.sso);
+
+        let moved = (claims.minter())(
+            "user_1",
+            "a@b.com",
+            "merc_2",
+            "member",
+            KNOWN_PERMISSIONS,
+            TOKEN_TYPE_STANDARD,
+            TEST_SECRET,
+            60,
+        )
+        .expect("token");
+        assert!(verify_jwt(&moved, TEST_SECRET).expect("verifies").mfa);
+    }
+
+    #[test]
+    fn a_super_admin_view_keeps_how_the_admin_signed_in() {
+        let token = session_minter(true, true)(
+            "user_1",
+            "a@b.com",
+            "merc_1",
+            "admin",
+            KNOWN_PERMISSIONS,
+            TOKEN_TYPE_STANDARD,
+            TEST_SECRET,
+            60,
+        )
+        .expect("token");
+        let claims = verify_jwt(&token, TEST_SECRET).expect("verifies");
+
+        let view = generate_super_admin_view_jwt(
+            &claims,
+            "merc_2",
+            &crate::types::merchant::hierarchy::ScopeGrant::profile("pro_2"),
+            TEST_SECRET,
+            60,
+        )
+        .expect("token");
+        let view = verify_jwt(&view, TEST_SECRET).expect("verifies");
+        assert_eq!(view.token_type, TOKEN_TYPE_SUPER_ADMIN_VIEW);
+        assert!(view.sso && view.mfa);
+
+        let home = (view.minter())(
+            "user_1",
+            "a@b.com",
+            "merc_1",
+            "admin",
+            KNOWN_PERMISSIONS,
+            TOKEN_TYPE_STANDARD,
+            TEST_SECRET,
+            60,
+        )
+        .expect("token");
+        let home = verify_jwt(&home, TEST_SECRET).expect("verifies");
+        assert!(home.sso && home.mfa);
+    }
+
+    fn claims_with(perms: Option<Vec<Permission>>, role: &str) -> JwtClaims {
+        JwtClaims {
+            sub: "hs_pro_1".to_string(),
+            user_id: "hs_pro_1".to_string(),
+            email: String::new(),
+            merchant_id: "pro_1".to_string(),
+            role: role.to_string(),
+            token_type: TOKEN_TYPE_HS_REDIRECT.to_string(),
+            jti: "jti".to_string(),
+            exp: 0,
+            iat: 0,
+            grant: None,
+            perms,
+            perms_split: false,
+            sso: false,
+            mfa: false,
+        }
+    }
+
+    #[test]
+    fn write_covers_decide_but_read_does_not() {
+        // A handed-over session naming only write predates decide, and kept calling the decide
+        // routes, which needed write until they were split out.
+        assert!(Permission::RoutingWrite.satisfies(&Permission::RoutingDecide));
+        assert!(!Permission::RoutingRead.satisfies(&Permission::RoutingDecide));
+        // And decide is not a back door to the rules.
+        assert!(!Permission::RoutingDecide.satisfies(&Permission::RoutingWrite));
+        assert!(!Permission::RoutingDecide.satisfies(&Permission::RoutingRead));
+    }
+
     #[test]
     fn permissions_travel_as_plain_strings() {
         // Hyperswitch writes this list, so the wire form is part of the contract.
@@ -572,6 +1087,7 @@ mod tests {
             "a@b.com",
             "merc_1",
             "admin",
+            KNOWN_PERMISSIONS,
             TOKEN_TYPE_STANDARD,
             TEST_SECRET,
             60,
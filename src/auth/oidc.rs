@@ -0,0 +1,647 @@
+//! Signing dashboard users in through an OpenID Connect provider.
+//!
+//! The authorization-code flow with PKCE. [`authorization_request`] sends the user to the provider
+//! with a fresh state, nonce and code verifier; the provider sends them back with a code, which
+//! [`exchange_code`] trades for an ID token; [`verify_id_token`] checks that token against the
+//! provider's published keys, and that it was issued by that provider, for this client, for this
+//! sign-in, and has not expired. What the claims then say about the user — who they are and which
+//! merchants they belong to — is [`subject_of`], [`email_of`] and [`memberships`]. `routes::sso`
+//! turns that into a session.
+
+use std::sync::OnceLock;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use base64::Engine;
+use error_stack::{Report, ResultExt};
+use josekit::jwk::JwkSet;
+use josekit::jws::JwsVerifier;
+use masking::PeekInterface;
+use serde::Deserialize;
+
+use crate::config::{ClaimMembership, OidcProviderConfig};
+
+const HTTP_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// How far the provider's clock may run ahead of ours before a token it just issued reads as
+/// expired.
+const CLOCK_SKEW_SECS: u64 = 60;
+
+#[derive(Debug, thiserror::Error)]
+pub enum OidcError {
+    #[error("Failed to read the provider's discovery document")]
+    Discovery,
+    #[error("The provider did not accept the authorization code")]
+    TokenExchange,
+    #[error("Failed to read the provider's signing keys")]
+    Keys,
+    #[error("ID token rejected: {0}")]
+    InvalidIdToken(&'static str),
+}
+
+/// An ID token's claims, once verified.
+pub type Claims = serde_json::Map<String, serde_json::Value>;
+
+/// The parts of a provider's discovery document the flow uses.
+#[derive(Debug, Clone, Deserialize)]
+pub struct ProviderMetadata {
+    pub issuer: String,
+    pub authorization_endpoint: String,
+    pub token_endpoint: String,
+    pub jwks_uri: String,
+}
+
+/// Where to send the user, and what to remember until they come back.
+#[derive(Debug, Clone)]
+pub struct AuthorizationRequest {
+    pub url: String,
+    pub state: String,
+    pub nonce: String,
+    pub code_verifier: String,
+}
+
+fn client() -> &'static reqwest::Client {
+    static CLIENT: OnceLock<reqwest::Client> = OnceLock::new();
+    CLIENT.get_or_init(|| {
+        reqwest::Client::builder()
+            .timeout(HTTP_TIMEOUT)
+            .build()
+            .expect("failed to build OIDC reqwest client")
+    })
+}
+
+pub async fn discover(
+    provider: &OidcProviderConfig,
+) -> Result<ProviderMetadata, Report<OidcError>> {
+    let url = format!(
+        "{}/.well-known/openid-configuration",
+        provider.issuer.trim_end_matches('/')
+    );
+    let metadata: ProviderMetadata = client()
+        .get(&url)
+        .send()
+        .await
+        .and_then(reqwest::Response::error_for_status)
+        .change_context(OidcError::Discovery)?
+        .json()
+        .await
+        .change_context(OidcError::Discovery)?;
+
+    // A document naming some other issuer would have us accept that issuer's tokens as this
+    // provider's.
+    if metadata.issuer.trim_end_matches('/') != provider.issuer.trim_end_matches('/') {
+        return Err(Report::new(OidcError::Discovery)
+            .attach_printable(format!("{url} names {} as its issuer", metadata.issuer)));
+    }
+    Ok(metadata)
+}
+
+pub fn authorization_request(
+    provider: &OidcProviderConfig,
+    metadata: &ProviderMetadata,
+) -> AuthorizationRequest {
+    let state = random_token();
+    let nonce = random_token();
+    let code_verifier = random_token();
+
+    let query = form_urlencoded::Serializer::new(String::new())
+        .append_pair("response_type", "code")
+        .append_pair("client_id", &provider.client_id)
+        .append_pair("redirect_uri", &provider.redirect_uri)
+        .append_pair("scope", &provider.scopes.join(" "))
+        .append_pair("state", &state)
+        .append_pair("nonce", &nonce)
+        .append_pair("code_challenge", &code_challenge(&code_verifier))
+        .append_pair("code_challenge_method", "S256")
+        .finish();
+    let separator = if metadata.authorization_endpoint.contains('?') {
+        '&'
+    } else {
+        '?'
+    };
+
+    AuthorizationRequest {
+        url: format!("{}{separator}{query}", metadata.authorization_endpoint),
+        state,
+        nonce,
+        code_verifier,
+    }
+}
+
+/// Trades the code the provider redirected back with for the user's ID token.
+pub async fn exchange_code(
+    provider: &OidcProviderConfig,
+    metadata: &ProviderMetadata,
+    code: &str,
+    code_verifier: &str,
+) -> Result<String, Report<OidcError>> {
+    #[derive(Deserialize)]
+    struct TokenResponse {
+        id_token: String,
+    }
+
+    let response = client()
+        .post(&metadata.token_endpoint)
+        .form(&[
+            ("grant_type", "authorization_code"),
+            ("code", code),
+            ("redirect_uri", provider.redirect_uri.as_str()),
+            ("client_id", provider.client_id.as_str()),
+            ("client_secret", provider.client_secret.peek().as_str()),
+            ("code_verifier", code_verifier),
+        ])
+        .send()
+        .await
+        .change_context(OidcError::TokenExchange)?;
+
+    let status = response.status();
+    if !status.is_success() {
+        let body = response.text().await.unwrap_or_default();
+        return Err(
+            Report::new(OidcError::TokenExchange).attach_printable(format!("{status}: {body}"))
+        );
+    }
+    let tokens: TokenResponse = response
+        .json()
+        .await
+        .change_context(OidcError::TokenExchange)?;
+    Ok(tokens.id_token)
+}
+
+/// The claims of `id_token`, once its signature checks out against the provider's keys and it was
+/// issued to this client for the sign-in that sent `nonce`.
+pub async fn verify_id_token(
+    provider: &OidcProviderConfig,
+    metadata: &ProviderMetadata,
+    id_token: &str,
+    nonce: &str,
+) -> Result<Claims, Report<OidcError>> {
+    let (alg, kid) = header_of(id_token)?;
+
+    let jwks = client()
+        .get(&metadata.jwks_uri)
+        .send()
+        .await
+        .and_then(reqwest::Response::error_for_status)
+        .change_context(OidcError::Keys)?
+        .bytes()
+        .await
+        .change_context(OidcError::Keys)?;
+    let keys = JwkSet::from_bytes(&jwks).change_context(OidcError::Keys)?;
+
+    // Without a key id the set has to be unambiguous.
+    let candidates = match kid.as_deref() {
+        Some(kid) => keys.get(kid),
+        None => keys.keys(),
+    };
+    let [jwk] = candidates.as_slice() else {
+        return Err(Report::new(OidcError::InvalidIdToken(
+            "no single signing key matches the token",
+        )));
+    };
+
+    // Asymmetric algorithms only: `none` and the HMAC family would let anyone holding the client
+    // secret — or nobody at all — mint a token.
+    let verifier: Box<dyn JwsVerifier> = match alg.as_str() {
+        "RS256" => Box::new(
+            josekit::jws::RS256
+                .verifier_from_jwk(jwk)
+                .change_context(OidcError::Keys)?,
+        ),
+        "RS384" => Box::new(
+            josekit::jws::RS384
+                .verifier_from_jwk(jwk)
+                .change_context(OidcError::Keys)?,
+        ),
+        "RS512" => Box::new(
+            josekit::jws::RS512
+                .verifier_from_jwk(jwk)
+                .change_context(OidcError::Keys)?,
+        ),
+        "PS256" => Box::new(
+            josekit::jws::PS256
+                .verifier_from_jwk(jwk)
+                .change_context(OidcError::Keys)?,
+        ),
+        "ES256" => Box::new(
+            josekit::jws::ES256
+                .verifier_from_jwk(jwk)
+                .change_context(OidcError::Keys)?,
+        ),
+        "ES384" => Box::new(
+            josekit::jws::ES384
+                .verifier_from_jwk(jwk)
+                .change_context(OidcError::Keys)?,
+        ),
+        _ => {
+            return Err(
+                Report::new(OidcError::InvalidIdToken("unsupported signing algorithm"))
+                    .attach_printable(alg),
+            )
+        }
+    };
+
+    let (payload, _) = josekit::jwt::decode_with_verifier(id_token, &*verifier)
+        .change_context(OidcError::InvalidIdToken("signature does not verify"))?;
+    let claims = payload.claims_set().clone();
+    check_claims(provider, metadata, &claims, nonce, unix_now())?;
+    Ok(claims)
+}
+
+/// Whether verified claims were meant for this sign-in, at `now` in Unix seconds.
+fn check_claims(
+    provider: &OidcProviderConfig,
+    metadata: &ProviderMetadata,
+    claims: &Claims,
+    nonce: &str,
+    now: u64,
+) -> Result<(), Report<OidcError>> {
+    let string = |name: &str| claims.get(name).and_then(|value| value.as_str());
+
+    if string("iss") != Some(metadata.issuer.as_str()) {
+        return Err(Report::new(OidcError::InvalidIdToken("wrong issuer")));
+    }
+    let for_us = match claims.get("aud") {
+        Some(serde_json::Value::String(aud)) => *aud == provider.client_id,
+        Some(serde_json::Value::Array(auds)) => auds
+            .iter()
+            .any(|aud| aud.as_str() == Some(provider.client_id.as_str())),
+        _ => false,
+    };
+    // A token shared among several audiences names the one it was issued to.
+    if !for_us || string("azp").is_some_and(|azp| azp != provider.client_id) {
+        return Err(Report::new(OidcError::InvalidIdToken(
+            "issued to another client",
+        )));
+    }
+    let exp = claims
+        .get("exp")
+        .and_then(|value| value.as_u64())
+        .ok_or_else(|| Report::new(OidcError::InvalidIdToken("no expiry")))?;
+    if exp + CLOCK_SKEW_SECS <= now {
+        return Err(Report::new(OidcError::InvalidIdToken("expired")));
+    }
+    // Ties the token to the browser that started this sign-in, so one captured from another
+    // cannot be replayed through the callback.
+    if string("nonce") != Some(nonce) {
+        return Err(Report::new(OidcError::InvalidIdToken(
+            "nonce does not match",
+        )));
+    }
+    Ok(())
+}
+
+/// The user's email, which is how a first sign-in finds its Decision Engine user. Trusted only
+/// when the provider says it verified it (`email_verified`), or the provider is configured as one
+/// that only releases verified emails.
+pub fn email_of(provider: &OidcProviderConfig, claims: &Claims) -> Option<String> {
+    let verified = match claims.get("email_verified") {
+        Some(serde_json::Value::Bool(verified)) => *verified,
+        // Some providers send it as a string.
+        Some(serde_json::Value::String(verified)) => verified == "true",
+        None => provider.emails_verified,
+        _ => false,
+    };
+    if !verified {
+        return None;
+    }
+    claims
+        .get(&provider.email_claim)
+        .and_then(|value| value.as_str())
+        .map(str::trim)
+        .filter(|email| !email.is_empty())
+        .map(str::to_string)
+}
+
+/// Who the provider says signed in: its stable identifier for the user, which unlike the email
+/// never passes to someone else. Links the provider's account to a Decision Engine user after the
+/// first sign-in.
+pub fn subject_of(claims: &Claims) -> Option<&str> {
+    claims
+        .get("sub")
+        .and_then(|value| value.as_str())
+        .filter(|sub| !sub.is_empty())
+}
+
+/// The memberships `claims` confer: a rule applies when its claim is its value, or is a list
+/// holding it. The first rule to apply to a merchant decides the role there, so list a merchant's
+/// rules most powerful first.
+pub fn memberships<'a>(rules: &'a [ClaimMembership], claims: &Claims) -> Vec<&'a ClaimMembership> {
+    let mut granted: Vec<&ClaimMembership> = Vec::new();
+    for rule in rules {
+        let applies = match claims.get(&rule.claim) {
+            Some(serde_json::Value::String(value)) => *value == rule.value,
+            Some(serde_json::Value::Array(values)) => values
+                .iter()
+                .any(|value| value.as_str() == Some(rule.value.as_str())),
+            _ => false,
+        };
+        if applies && !granted.iter().any(|g| g.merchant_id == rule.merchant_id) {
+            granted.push(rule);
+        }
+    }
+    granted
+}
+
+/// The merchants whose membership `provider` decides, whatever a particular user's claims say.
+pub fn managed_merchants(provider: &OidcProviderConfig) -> Vec<&str> {
+    let mut merchants: Vec<&str> = Vec::new();
+    for rule in &provider.memberships {
+        if !merchants.contains(&rule.merchant_id.as_str()) {
+            merchants.push(&rule.merchant_id);
+        }
+    }
+    merchants
+}
+
+/// The signing algorithm and key id a compact JWS names in its header.
+fn header_of(token: &str) -> Result<(String, Option<String>), Report<OidcError>> {
+    let malformed = || Report::new(OidcError::InvalidIdToken("malformed"));
+    let encoded = token.split('.').next().ok_or_else(malformed)?;
+    let decoded = base64::engine::general_purpose::URL_SAFE_NO_PAD
+        .decode(encoded)
+        .map_err(|_| malformed())?;
+    let header: serde_json::Value = serde_json::from_slice(&decoded).map_err(|_| malformed())?;
+    let alg = header
+        .get("alg")
+        .and_then(|value| value.as_str())
+        .ok_or_else(malformed)?
+        .to_string();
+    let kid = header
+        .get("kid")
+        .and_then(|value| value.as_str())
+        .map(str::to_string);
+    Ok((alg, kid))
+}
+
+/// 256 random bits, URL-safe: long enough for a PKCE verifier, and used for state and nonce too.
+fn random_token() -> String {
+    use rand::RngCore;
+    let mut bytes = [0u8; 32];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(bytes)
+}
+
+fn code_challenge(code_verifier: &str) -> String {
+    let digest = ring::digest::digest(&ring::digest::SHA256, code_verifier.as_bytes());
+    base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(digest.as_ref())
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs())
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::sync::{Arc, Mutex};
+
+    use axum::routing::{get, post};
+    use axum::{Form, Json, Router};
+    use josekit::jws::{JwsHeader, RS256};
+    use josekit::jwt::JwtPayload;
+    use serde_json::json;
+
+    use super::*;
+
+    const CODE: &str = "code-123";
+
+    fn provider(issuer: &str) -> OidcProviderConfig {
+        OidcProviderConfig {
+            id: "okta".to_string(),
+            display_name: None,
+            issuer: issuer.to_string(),
+            client_id: "decision-engine".to_string(),
+            client_secret: masking::Secret::new("s3cret".to_string()),
+            redirect_uri: "https://de.example.com/auth/sso/okta/callback".to_string(),
+            scopes: vec!["openid".to_string(), "email".to_string()],
+            email_claim: "email".to_string(),
+            emails_verified: false,
+            jit_provisioning: true,
+            memberships: vec![
+                rule("groups", "payments-admins", "m_1", "admin"),
+                rule("groups", "payments", "m_1", "viewer"),
+                rule("department", "finance", "m_2", "member"),
+            ],
+        }
+    }
+
+    fn rule(claim: &str, value: &str, merchant_id: &str, role: &str) -> ClaimMembership {
+        ClaimMembership {
+            claim: claim.to_string(),
+            value: value.to_string(),
+            merchant_id: merchant_id.to_string(),
+            role: role.to_string(),
+        }
+    }
+
+    fn metadata(issuer: &str) -> ProviderMetadata {
+        ProviderMetadata {
+            issuer: issuer.to_string(),
+            authorization_endpoint: format!("{issuer}/authorize"),
+            token_endpoint: format!("{issuer}/token"),
+            jwks_uri: format!("{issuer}/jwks"),
+        }
+    }
+
+    fn claims(value: serde_json::Value) -> Claims {
+        value.as_object().cloned().expect("object")
+    }
+
+    /// An identity provider on a local port: discovery, a token endpoint that hands out whatever
+    /// `id_token` holds for [`CODE`], and the public half of `jwks`.
+    async fn mock_idp(id_token: Arc<Mutex<String>>, jwks: serde_json::Value) -> String {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0")
+            .await
+            .expect("bind");
+        let issuer = format!("http://{}", listener.local_addr().expect("addr"));
+        let discovery = json!({
+            "issuer": issuer,
+            "authorization_endpoint": format!("{issuer}/authorize"),
+            "token_endpoint": format!("{issuer}/token"),
+            "jwks_uri": format!("{issuer}/jwks"),
+        });
+
+        let router = Router::new()
+            .route(
+                "/.well-known/openid-configuration",
+                get(move || async move { Json(discovery) }),
+            )
+            .route(
+                "/token",
+                post(
+                    move |Form(form): Form<HashMap<String, String>>| async move {
+                        let accepted = form.get("grant_type").map(String::as_str)
+                            == Some("authorization_code")
+                            && form.get("code").map(String::as_str) == Some(CODE)
+                            && form.get("client_secret").map(String::as_str) == Some("s3cret")
+                            && form.get("code_verifier").is_some_and(|v| !v.is_empty());
+                        if !accepted {
+                            return Err(axum::http::StatusCode::BAD_REQUEST);
+                        }
+                        let id_token = id_token.lock().expect("lock").clone();
+                        Ok(Json(
+                            json!({ "id_token": id_token, "token_type": "Bearer" }),
+                        ))
+                    },
+                ),
+            )
+            .route("/jwks", get(move || async move { Json(jwks) }));
+        tokio::spawn(async move { axum::serve(listener, router).await });
+        issuer
+    }
+
+    #[tokio::test]
+    async fn signs_in_through_a_provider() {
+        let key_pair = RS256.generate_key_pair(2048).expect("key pair");
+        let mut public = key_pair.to_jwk_public_key();
+        public.set_key_id("k1");
+        let signer = RS256
+            .signer_from_jwk(&key_pair.to_jwk_private_key())
+            .expect("signer");
+
+        let id_token = Arc::new(Mutex::new(String::new()));
+        let jwks = json!({ "keys": [serde_json::Value::Object(public.as_ref().clone())] });
+        let issuer = mock_idp(id_token.clone(), jwks).await;
+        let provider = provider(&issuer);
+
+        let metadata = discover(&provider).await.expect("discovery");
+        let request = authorization_request(&provider, &metadata);
+        assert!(request.url.starts_with(&format!("{issuer}/authorize?")));
+        assert!(request.url.contains(&format!(
+            "code_challenge={}",
+            code_challenge(&request.code_verifier)
+        )));
+        assert!(request.url.contains("code_challenge_method=S256"));
+
+        // What the provider would issue once the user signs in.
+        let mut payload = JwtPayload::new();
+        payload.set_issuer(&issuer);
+        payload.set_audience(vec!["decision-engine"]);
+        payload.set_subject("00u1");
+        payload.set_expires_at(&(SystemTime::now() + Duration::from_secs(300)));
+        for (name, value) in [
+            ("nonce", json!(request.nonce)),
+            ("email", json!("ana@example.com")),
+            ("email_verified", json!(true)),
+            ("groups", json!(["payments", "everyone"])),
+        ] {
+            payload.set_claim(name, Some(value)).expect("claim");
+        }
+        let mut header = JwsHeader::new();
+        header.set_key_id("k1");
+        *id_token.lock().expect("lock") =
+            josekit::jwt::encode_with_signer(&payload, &header, &signer).expect("sign");
+
+        let token = exchange_code(&provider, &metadata, CODE, &request.code_verifier)
+            .await
+            .expect("exchange");
+        assert!(
+            exchange_code(&provider, &metadata, "replayed", &request.code_verifier)
+                .await
+                .is_err()
+        );
+
+        let verified = verify_id_token(&provider, &metadata, &token, &request.nonce)
+            .await
+            .expect("verified");
+        assert_eq!(
+            email_of(&provider, &verified).as_deref(),
+            Some("ana@example.com")
+        );
+        assert_eq!(subject_of(&verified), Some("00u1"));
+        let granted = memberships(&provider.memberships, &verified);
+        assert_eq!(granted, vec![&rule("groups", "payments", "m_1", "viewer")]);
+
+        // Another sign-in's nonce, and a token altered after signing, are both refused.
+        assert!(verify_id_token(&provider, &metadata, &token, "other")
+            .await
+            .is_err());
+        let forged = base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(
+            serde_json::to_vec(&json!({
+                "iss": issuer, "aud": "decision-engine", "exp": unix_now() + 300,
+                "nonce": request.nonce, "email": "admin@example.com",
+            }))
+            .expect("json"),
+        );
+        let mut parts: Vec<&str> = token.split('.').collect();
+        parts[1] = &forged;
+        assert!(
+            verify_id_token(&provider, &metadata, &parts.join("."), &request.nonce)
+                .await
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn a_token_meant_for_another_sign_in_is_refused() {
+        let issuer = "https://idp.example.com";
+        let provider = provider(issuer);
+        let metadata = metadata(issuer);
+        let good = json!({
+            "iss": issuer, "aud": ["decision-engine", "other"], "azp": "decision-engine",
+            "exp": 1_000, "nonce": "n",
+        });
+        assert!(check_claims(&provider, &metadata, &claims(good.clone()), "n", 1_000).is_ok());
+
+        for (field, value) in [
+            ("iss", json!("https://evil.example.com")),
+            ("aud", json!("other")),
+            ("azp", json!("other")),
+            ("nonce", json!("m")),
+            ("exp", json!(900)),
+        ] {
+            let mut bad = good.clone();
+            bad[field] = value;
+            assert!(
+                check_claims(&provider, &metadata, &claims(bad), "n", 1_000).is_err(),
+                "{field}"
+            );
+        }
+    }
+
+    #[test]
+    fn memberships_follow_claims() {
+        let provider = provider("https://idp.example.com");
+        let granted = memberships(
+            &provider.memberships,
+            &claims(json!({ "groups": ["payments", "payments-admins"], "department": "finance" })),
+        );
+        // The first rule to match m_1 decides the role there.
+        assert_eq!(
+            granted,
+            vec![
+                &rule("groups", "payments-admins", "m_1", "admin"),
+                &rule("department", "finance", "m_2", "member"),
+            ]
+        );
+        assert!(
+            memberships(&provider.memberships, &claims(json!({ "groups": "sales" }))).is_empty()
+        );
+        assert_eq!(managed_merchants(&provider), vec!["m_1", "m_2"]);
+    }
+
+    #[test]
+    fn an_unverified_email_is_not_trusted() {
+        let mut provider = provider("https://idp.example.com");
+        let verified = claims(json!({ "email": "ana@example.com", "email_verified": true }));
+        let unverified = claims(json!({ "email": "ana@example.com", "email_verified": false }));
+        let unsaid = claims(json!({ "email": "ana@example.com" }));
+        assert_eq!(
+            email_of(&provider, &verified).as_deref(),
+            Some("ana@example.com")
+        );
+        assert!(email_of(&provider, &unverified).is_none());
+        assert!(email_of(&provider, &unsaid).is_none());
+
+        // A provider that only releases verified emails is taken at its word when it says nothing.
+        provider.emails_verified = true;
+        assert_eq!(
+            email_of(&provider, &unsaid).as_deref(),
+            Some("ana@example.com")
+        );
+        assert!(email_of(&provider, &unverified).is_none());
+    }
+}
@@ -0,0 +1,123 @@
+//! What a member's role lets them do.
+//!
+//! Three roles are built in and exist for every merchant. A merchant can define more of its own,
+//! each a named list of [`Permission`]s, stored per merchant and managed from `routes::user_auth`.
+//! A session carries its role's permissions as they were when it was issued, so a change to a role
+//! reaches its members at their next sign-in or merchant switch.
+
+use super::Permission;
+
+pub const ADMIN: &str = "admin";
+pub const MEMBER: &str = "member";
+pub const VIEWER: &str = "viewer";
+
+/// The longest a role name may be — the width of `user_merchants.role`.
+pub const MAX_ROLE_NAME_LEN: usize = 50;
+
+/// Everything a member does day to day, short of deciding who else is a member. What `member` meant
+/// before roles were split into permissions.
+static MEMBER_PERMISSIONS: &[Permission] = &[
+    Permission::RoutingRead,
+    Permission::RoutingWrite,
+    Permission::RoutingDecide,
+    Permission::CostRead,
+    Permission::CostWrite,
+    Permission::CredentialsRead,
+    Permission::CredentialsWrite,
+    Permission::AnalyticsRead,
+    Permission::ApiKeysManage,
+    Permission::AuditRead,
+];
+
+/// Looking without touching: rules, costs and analytics. Not credentials, which say which accounts
+/// the merchant holds with whom.
+static VIEWER_PERMISSIONS: &[Permission] = &[
+    Permission::RoutingRead,
+    Permission::CostRead,
+    Permission::AnalyticsRead,
+];
+
+/// The permissions of a built-in role, or `None` when `role` is not one.
+pub fn builtin(role: &str) -> Option<&'static [Permission]> {
+    match role {
+        ADMIN => Some(super::KNOWN_PERMISSIONS),
+        MEMBER => Some(MEMBER_PERMISSIONS),
+        VIEWER => Some(VIEWER_PERMISSIONS),
+        _ => None,
+    }
+}
+
+pub fn builtin_names() -> [&'static str; 3] {
+    [ADMIN, MEMBER, VIEWER]
+}
+
+/// Why a custom role cannot be saved as given, or `None` when it can.
+pub fn validate_custom(name: &str, permissions: &[Permission]) -> Option<String> {
+    if builtin(name).is_some() {
+        return Some(format!("{name} is a built-in role and cannot be redefined"));
+    }
+    if name.is_empty()
+        || name.len() > MAX_ROLE_NAME_LEN
+        || !name
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_' || c == '-')
+    {
+        return Some(format!(
+            "role names are 1 to {MAX_ROLE_NAME_LEN} characters of a-z, 0-9, '_' and '-'"
+        ));
+    }
+    if permissions.is_empty() {
+        return Some("a role must hold at least one permission".to_string());
+    }
+    if let Some(unknown) = permissions
+        .iter()
+        .find(|p| matches!(p, Permission::Unknown(_)))
+    {
+        return Some(format!("unknown permission {}", unknown.as_str()));
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn a_member_keeps_everything_but_member_administration() {
+        let member = builtin(MEMBER).expect("built in");
+        assert!(!member.contains(&Permission::MembersManage));
+        assert_eq!(member.len() + 1, super::super::KNOWN_PERMISSIONS.len());
+    }
+
+    #[test]
+    fn a_viewer_changes_nothing() {
+        let viewer = builtin(VIEWER).expect("built in");
+        for write in [
+            Permission::RoutingWrite,
+            Permission::RoutingDecide,
+            Permission::CostWrite,
+            Permission::CredentialsWrite,
+            Permission::ApiKeysManage,
+            Permission::MembersManage,
+        ] {
+            assert!(
+                !viewer.iter().any(|held| held.satisfies(&write)),
+                "viewer must not hold {}",
+                write.as_str()
+            );
+        }
+    }
+
+    #[test]
+    fn a_custom_role_cannot_shadow_a_built_in_one() {
+        assert!(validate_custom(ADMIN, &[Permission::RoutingRead]).is_some());
+        assert!(validate_custom("finance", &[Permission::CostRead]).is_none());
+    }
+
+    #[test]
+    fn a_custom_role_names_known_permissions() {
+        assert!(validate_custom("finance", &[]).is_some());
+        assert!(validate_custom("finance", &[Permission::from("cost:everything")]).is_some());
+        assert!(validate_custom("Finance Team", &[Permission::CostRead]).is_some());
+    }
+}
@@ -0,0 +1,310 @@
+//! Time-based one-time passwords (RFC 6238), as authenticator apps generate them: HMAC-SHA1 over
+//! 30-second steps, six digits. Plus the recovery codes that stand in for the app when it is lost,
+//! and the keyring secrets are sealed with at rest.
+
+use std::collections::HashMap;
+
+use base64::Engine;
+use masking::{PeekInterface, Secret};
+use ring::hmac;
+
+use crate::crypto::encryption_manager::{
+    encryption_interface::Encryption, managers::aes::GcmAes256,
+};
+
+const STEP_SECS: u64 = 30;
+const DIGITS: u32 = 6;
+
+/// Steps either side of now a code is still accepted for, so a phone whose clock has drifted by
+/// up to half a minute still works.
+const WINDOW: u64 = 1;
+
+/// 160 bits, the size RFC 4226 recommends for an HMAC-SHA1 key.
+const SECRET_BYTES: usize = 20;
+
+/// What authenticator apps list the account under.
+const ISSUER: &str = "Decision Engine";
+
+pub const RECOVERY_CODE_COUNT: usize = 10;
+
+const BASE32_ALPHABET: &[u8; 32] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ234567";
+
+/// A fresh secret, base32-encoded as authenticator apps take it.
+pub fn generate_secret() -> String {
+    use rand::RngCore;
+    let mut secret = [0u8; SECRET_BYTES];
+    rand::thread_rng().fill_bytes(&mut secret);
+    base32_encode(&secret)
+}
+
+/// The `otpauth://` URI an authenticator app reads from a QR code.
+pub fn provisioning_uri(secret: &str, account: &str) -> String {
+    // Spaces as `%20`: apps read the URI as a path and query, not as a submitted form.
+    let encode = |value: &str| {
+        form_urlencoded::byte_serialize(value.as_bytes())
+            .collect::<String>()
+            .replace('+', "%20")
+    };
+    format!(
+        "otpauth://totp/{}?secret={secret}&issuer={}&algorithm=SHA1&digits={DIGITS}&period={STEP_SECS}",
+        encode(&format!("{ISSUER}:{account}")),
+        encode(ISSUER),
+    )
+}
+
+/// The time step `code` is valid for at `now` (Unix seconds), if it is valid at all.
+///
+/// A step at or before `last_step` — the last one a code was accepted for — is refused, so a code
+/// seen over someone's shoulder cannot be used again while it is still showing.
+pub fn verify(secret: &str, code: &str, now: u64, last_step: Option<i64>) -> Option<i64> {
+    let key = base32_decode(secret)?;
+    let code = code.trim();
+    if code.len() != DIGITS as usize || !code.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let code: u32 = code.parse().ok()?;
+
+    let current = now / STEP_SECS;
+    (current.saturating_sub(WINDOW)..=current + WINDOW)
+        .filter_map(|step| i64::try_from(step).ok())
+        .filter(|step| !last_step.is_some_and(|last| *step <= last))
+        .find(|step| code_at(&key, *step as u64) == code)
+}
+
+fn code_at(key: &[u8], step: u64) -> u32 {
+    let key = hmac::Key::new(hmac::HMAC_SHA1_FOR_LEGACY_USE_ONLY, key);
+    let mac = hmac::sign(&key, &step.to_be_bytes());
+    let mac = mac.as_ref();
+    // Dynamic truncation, RFC 4226 §5.3.
+    let offset = usize::from(mac[mac.len() - 1] & 0x0f);
+    let binary = u32::from_be_bytes([
+        mac[offset],
+        mac[offset + 1],
+        mac[offset + 2],
+        mac[offset + 3],
+    ]) & 0x7fff_ffff;
+    binary % 10u32.pow(DIGITS)
+}
+
+/// Single-use codes to sign in with when the authenticator is lost, shown to the user once. Only
+/// their hashes are kept.
+pub fn generate_recovery_codes() -> Vec<String> {
+    use rand::Rng;
+    const ALPHABET: &[u8] = b"abcdefghjkmnpqrstuvwxyz23456789";
+    let mut rng = rand::thread_rng();
+    (0..RECOVERY_CODE_COUNT)
+        .map(|_| {
+            let mut code: String = (0..10)
+                .map(|_| ALPHABET[rng.gen_range(0..ALPHABET.len())] as char)
+                .collect();
+            code.insert(5, '-');
+            code
+        })
+        .collect()
+}
+
+/// A recovery code as typed, in the form it was hashed in: case and spacing do not matter.
+pub fn normalize_recovery_code(input: &str) -> String {
+    let compact: String = input
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric())
+        .map(|c| c.to_ascii_lowercase())
+        .collect();
+    match compact.len() {
+        10 => format!("{}-{}", &compact[..5], &compact[5..]),
+        _ => compact,
+    }
+}
+
+/// Seals secrets for storage with AES-256-GCM, the way connector credentials are sealed: the code
+/// has to be recomputed from the secret, so it is encrypted rather than hashed.
+///
+/// A sealed secret is `"{key_id}:{base64}"`. New secrets use `current_id`; opening uses whichever
+/// key the secret names, so rotating the current key leaves enrolled apps working as long as their
+/// key stays in the ring.
+pub struct SecretKeyring {
+    current_id: String,
+    ciphers: HashMap<String, GcmAes256>,
+}
+
+impl SecretKeyring {
+    /// `None` (two-factor unavailable) unless there is at least one key, every key is a valid
+    /// 32-byte hex string, and `current_id` names one of them.
+    pub fn from_keyring(current_id: &str, keys: &HashMap<String, Secret<String>>) -> Option<Self> {
+        if current_id.is_empty() || keys.is_empty() {
+            return None;
+        }
+        let mut ciphers = HashMap::with_capacity(keys.len());
+        for (id, key) in keys {
+            let bytes = hex::decode(key.peek())
+                .ok()
+                .filter(|bytes| bytes.len() == 32)?;
+            ciphers.insert(id.clone(), GcmAes256::new(bytes));
+        }
+        ciphers.contains_key(current_id).then(|| Self {
+            current_id: current_id.to_string(),
+            ciphers,
+        })
+    }
+
+    /// `secret` as it is stored.
+    pub fn seal(&self, secret: &str) -> Option<String> {
+        let cipher = self.ciphers.get(&self.current_id)?;
+        let ciphertext = cipher.encrypt(secret.as_bytes().to_vec()).ok()?;
+        let encoded = base64::engine::general_purpose::STANDARD.encode(ciphertext);
+        Some(format!("{}:{}", self.current_id, encoded))
+    }
+
+    /// The secret `stored` holds. One stored before secrets were sealed is plain base32, which
+    /// never contains `:`, and comes back as it is.
+    pub fn open(&self, stored: &str) -> Option<String> {
+        let Some((key_id, encoded)) = stored.split_once(':') else {
+            return Some(stored.to_string());
+        };
+        let cipher = self.ciphers.get(key_id)?;
+        let ciphertext = base64::engine::general_purpose::STANDARD
+            .decode(encoded)
+            .ok()?;
+        String::from_utf8(cipher.decrypt(ciphertext).ok()?).ok()
+    }
+
+    /// Whether `stored` is sealed, rather than a secret stored before secrets were.
+    pub fn is_sealed(stored: &str) -> bool {
+        stored.contains(':')
+    }
+}
+
+fn base32_encode(bytes: &[u8]) -> String {
+    let mut encoded = String::with_capacity(bytes.len().div_ceil(5) * 8);
+    let mut buffer: u32 = 0;
+    let mut bits = 0;
+    for &byte in bytes {
+        buffer = (buffer << 8) | u32::from(byte);
+        bits += 8;
+        while bits >= 5 {
+            bits -= 5;
+            encoded.push(BASE32_ALPHABET[((buffer >> bits) & 0x1f) as usize] as char);
+        }
+    }
+    if bits > 0 {
+        encoded.push(BASE32_ALPHABET[((buffer << (5 - bits)) & 0x1f) as usize] as char);
+    }
+    encoded
+}
+
+fn base32_decode(encoded: &str) -> Option<Vec<u8>> {
+    let mut decoded = Vec::with_capacity(encoded.len() * 5 / 8);
+    let mut buffer: u32 = 0;
+    let mut bits = 0;
+    for c in encoded.chars().filter(|c| !c.is_whitespace() && *c != '=') {
+        let value = BASE32_ALPHABET
+            .iter()
+            .position(|&a| a == c.to_ascii_uppercase() as u8)?;
+        buffer = (buffer << 5) | value as u32;
+        bits += 5;
+        if bits >= 8 {
+            bits -= 8;
+            decoded.push((buffer >> bits) as u8);
+        }
+    }
+    Some(decoded)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The SHA-1 key of RFC 6238's test vectors, `12345678901234567890`.
+    const RFC_SECRET: &str = "GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ";
+
+    #[test]
+    fn matches_the_rfc_test_vectors() {
+        assert_eq!(base32_encode(b"12345678901234567890"), RFC_SECRET);
+        let key = base32_decode(RFC_SECRET).expect("base32");
+        // The RFC's eight-digit values, cut to six.
+        for (time, code) in [
+            (59, 287_082),
+            (1_111_111_109, 81_804),
+            (1_234_567_890, 5_924),
+            (2_000_000_000, 279_037),
+        ] {
+            assert_eq!(code_at(&key, time / STEP_SECS), code, "at {time}");
+        }
+    }
+
+    #[test]
+    fn a_code_holds_for_its_window_and_only_once() {
+        let step = 1_234_567_890 / STEP_SECS;
+        assert_eq!(
+            verify(RFC_SECRET, "005924", 1_234_567_890, None),
+            Some(step as i64)
+        );
+        // A step late, as a slow phone would be; two steps late is too late.
+        assert!(verify(RFC_SECRET, "005924", 1_234_567_890 + STEP_SECS, None).is_some());
+        assert!(verify(RFC_SECRET, "005924", 1_234_567_890 + 2 * STEP_SECS, None).is_none());
+        // Already used.
+        assert!(verify(RFC_SECRET, "005924", 1_234_567_890, Some(step as i64)).is_none());
+        assert!(verify(RFC_SECRET, "5924", 1_234_567_890, None).is_none());
+        assert!(verify(RFC_SECRET, "00592a", 1_234_567_890, None).is_none());
+    }
+
+    #[test]
+    fn secrets_survive_the_round_trip_through_base32() {
+        let secret = generate_secret();
+        assert_eq!(secret.len(), 32);
+        assert_eq!(
+            base32_decode(&secret).map(|key| base32_encode(&key)),
+            Some(secret.clone())
+        );
+        let uri = provisioning_uri(&secret, "ana@example.com");
+        assert!(uri.starts_with("otpauth://totp/Decision%20Engine%3Aana%40example.com?secret="));
+        assert!(uri.contains("&issuer=Decision%20Engine&"));
+    }
+
+    fn keyring(current_id: &str, keys: &[(&str, String)]) -> Option<SecretKeyring> {
+        let keys = keys
+            .iter()
+            .map(|(id, key)| (id.to_string(), Secret::new(key.clone())))
+            .collect();
+        SecretKeyring::from_keyring(current_id, &keys)
+    }
+
+    #[test]
+    fn sealed_secrets_open_with_the_key_that_sealed_them() {
+        let v1 = keyring("v1", &[("v1", "01".repeat(32))]).expect("valid");
+        let sealed = v1.seal(RFC_SECRET).expect("sealed");
+        assert!(sealed.starts_with("v1:"));
+        assert!(!sealed.contains(RFC_SECRET));
+        assert!(SecretKeyring::is_sealed(&sealed));
+        assert_eq!(v1.open(&sealed).as_deref(), Some(RFC_SECRET));
+
+        // Rotated: new secrets use v2, and v1's still open while v1 stays in the ring.
+        let rotated =
+            keyring("v2", &[("v1", "01".repeat(32)), ("v2", "02".repeat(32))]).expect("valid");
+        assert!(rotated.seal(RFC_SECRET).expect("sealed").starts_with("v2:"));
+        assert_eq!(rotated.open(&sealed).as_deref(), Some(RFC_SECRET));
+        let retired = keyring("v2", &[("v2", "02".repeat(32))]).expect("valid");
+        assert!(retired.open(&sealed).is_none());
+
+        // Stored before sealing.
+        assert!(!SecretKeyring::is_sealed(RFC_SECRET));
+        assert_eq!(v1.open(RFC_SECRET).as_deref(), Some(RFC_SECRET));
+
+        assert!(keyring("v1", &[]).is_none());
+        assert!(keyring("v9", &[("v1", "01".repeat(32))]).is_none());
+        assert!(keyring("v1", &[("v1", "01".repeat(16))]).is_none());
+    }
+
+    #[test]
+    fn recovery_codes_are_read_however_they_are_typed() {
+        let codes = generate_recovery_codes();
+        assert_eq!(codes.len(), RECOVERY_CODE_COUNT);
+        for code in &codes {
+            assert_eq!(normalize_recovery_code(code), *code);
+            assert_eq!(
+                normalize_recovery_code(&format!(" {} ", code.to_uppercase().replace('-', " "))),
+                *code
+            );
+        }
+    }
+}
@@ -67,6 +67,10 @@ pub struct GlobalConfig {
     #[serde(default)]
     pub sr_auto_calibration: SrAutoCalibrationConfig,
     #[serde(default)]
+    pub routing_scheduler: RoutingSchedulerConfig,
+    #[serde(default)]
+    pub experiment_evaluator: ExperimentEvaluatorConfig,
+    #[serde(default)]
     pub card_info_service: CardInfoServiceConfig,
 }
 
@@ -123,6 +127,29 @@ pub struct SrAutoCalibrationConfig {
     pub min_volume: Option<i64>,
 }
 
+/// Applies scheduled routing activations (see `crate::routing_scheduler`).
+#[derive(Clone, serde::Deserialize, Debug, Default)]
+pub struct RoutingSchedulerConfig {
+    /// Run the scheduler on this deployment. Off by default so only the replica(s) meant to apply
+    /// schedules do; each transition is still applied once even if several run it.
+    #[serde(default)]
+    pub enabled: bool,
+    /// How often schedules are checked, in seconds, which bounds how late a window opens or
+    /// closes. When unset (or 0), the job uses a built-in default (30s).
+    #[serde(default)]
+    pub interval_secs: Option<u64>,
+}
+
+/// Acts on A/B experiment results under each experiment's policy (see
+/// `crate::experiment_evaluator`).
+#[derive(Clone, serde::Deserialize, Debug, Default)]
+pub struct ExperimentEvaluatorConfig {
+    /// How often active experiments are evaluated, in seconds. When unset (or 0), the job uses a
+    /// built-in default (300s).
+    #[serde(default)]
+    pub interval_secs: Option<u64>,
+}
+
 #[derive(Clone, serde::Deserialize, Debug)]
 pub struct UserAuthConfig {
     /// Secret used to sign JWTs — set a strong random value in production
@@ -162,6 +189,73 @@ pub struct UserAuthConfig {
     /// and a caller that omits them is a bug rather than an old build.
     #[serde(default)]
     pub require_explicit_permissions: bool,
+    /// Identity providers dashboard users may sign in with instead of a password.
+    #[serde(default)]
+    pub sso: SsoConfig,
+    /// Key id used to encrypt *new* TOTP secrets. Must name a key in `totp_encryption_keys`.
+    /// Rotate as with `cost_ingestion.creds_encryption_current`. Empty ⇒ two-factor unavailable.
+    #[serde(default)]
+    pub totp_encryption_current: String,
+    /// Keyring: key-id → hex-encoded 32-byte AES-256 key. Keep a retired key listed until no
+    /// secret is sealed with it. Generate keys with `openssl rand -hex 32`.
+    #[serde(default)]
+    pub totp_encryption_keys: std::collections::HashMap<String, masking::Secret<String>>,
+}
+
+#[derive(Clone, serde::Deserialize, Debug, Default)]
+pub struct SsoConfig {
+    /// OpenID Connect providers, each offered on the login page under its `id`.
+    #[serde(default)]
+    pub providers: Vec<OidcProviderConfig>,
+}
+
+impl SsoConfig {
+    pub fn provider(&self, id: &str) -> Option<&OidcProviderConfig> {
+        self.providers.iter().find(|provider| provider.id == id)
+    }
+}
+
+#[derive(Clone, serde::Deserialize, Debug)]
+pub struct OidcProviderConfig {
+    /// Names the provider in URLs (`/auth/sso/<id>/start`). Lowercase, stable once users sign in.
+    pub id: String,
+    /// What the login page calls it. Defaults to `id`.
+    #[serde(default)]
+    pub display_name: Option<String>,
+    /// The issuer URL; its `/.well-known/openid-configuration` names every other endpoint.
+    pub issuer: String,
+    pub client_id: String,
+    pub client_secret: masking::Secret<String>,
+    /// This deployment's `/auth/sso/<id>/callback`, exactly as registered with the provider.
+    pub redirect_uri: String,
+    /// Scopes requested. Add whatever the provider needs to release the claims `memberships`
+    /// reads, such as `groups`.
+    #[serde(default = "default_oidc_scopes")]
+    pub scopes: Vec<String>,
+    /// The claim holding the user's email, which is how a first sign-in finds its Decision Engine
+    /// user.
+    #[serde(default = "default_oidc_email_claim")]
+    pub email_claim: String,
+    /// The provider only releases emails it has verified, so a token that omits `email_verified`
+    /// is trusted. Otherwise the token must say the email is verified.
+    #[serde(default)]
+    pub emails_verified: bool,
+    /// Create a user on first sign-in rather than requiring an invite.
+    #[serde(default = "default_true")]
+    pub jit_provisioning: bool,
+    /// Which memberships a sign-in's claims confer. Every merchant named here is managed by the
+    /// provider: each sign-in adds, changes or removes the user's membership on it to match.
+    #[serde(default)]
+    pub memberships: Vec<ClaimMembership>,
+}
+
+/// Membership of `merchant_id` as `role`, for anyone whose `claim` is, or contains, `value`.
+#[derive(Clone, serde::Deserialize, Debug, PartialEq, Eq)]
+pub struct ClaimMembership {
+    pub claim: String,
+    pub value: String,
+    pub merchant_id: String,
+    pub role: String,
 }
 
 /// Deserialize a `Vec<String>` from either a sequence (TOML array) or a single comma-separated
@@ -212,6 +306,18 @@ fn default_true() -> bool {
     true
 }
 
+fn default_oidc_scopes() -> Vec<String> {
+    vec![
+        "openid".to_string(),
+        "email".to_string(),
+        "profile".to_string(),
+    ]
+}
+
+fn default_oidc_email_claim() -> String {
+    "email".to_string()
+}
+
 fn default_jwt_expiry() -> u64 {
     86400
 }
@@ -252,6 +358,9 @@ impl Default for UserAuthConfig {
             signup_requires_admin_secret: true,
             super_admin_emails: Vec::new(),
             require_explicit_permissions: false,
+            sso: SsoConfig::default(),
+            totp_encryption_current: String::new(),
+            totp_encryption_keys: std::collections::HashMap::new(),
         }
     }
 }
@@ -430,6 +539,115 @@ pub struct CostIngestionConfig {
     /// AWS region for S3 sample downloads. If omitted, the SDK uses the default region provider
     /// chain (env vars, profile, IMDS, etc.).
     pub aws_region: Option<String>,
+    /// Drop-folder poller: watches directories / S3-compatible prefixes that acquirers deliver
+    /// settlement files into and enqueues each new file (see `cost_ingestion::drop_folder`).
+    pub drop_folder: DropFolderConfig,
+    /// FX rates used to price a payment off a model fitted in another currency (see
+    /// `cost_ingestion::fx`).
+    pub fx: FxConfig,
+    /// Where raw reports are kept so past ingestions can be reprocessed (see
+    /// `cost_ingestion::archive`).
+    pub report_archive: ReportArchiveConfig,
+}
+
+/// Retention of raw settlement reports, content-addressed: a local directory (`path`) or an
+/// S3-compatible prefix (`bucket` + `prefix`, with `endpoint_url` for MinIO and friends). Neither
+/// set ⇒ reports are not kept, and an ingestion can't be reprocessed. Setting both is an error.
+#[derive(Clone, Debug, Default, serde::Deserialize)]
+#[serde(default)]
+pub struct ReportArchiveConfig {
+    pub path: Option<String>,
+    pub bucket: Option<String>,
+    pub prefix: String,
+    pub region: Option<String>,
+    pub endpoint_url: Option<String>,
+}
+
+/// Where FX rates come from and what converting costs. Rates are read from `rates_file` (CSV with
+/// a `base,quote,rate,effective_from` header) and from the `cost_fx_rates` ClickHouse table; a
+/// pair present in both takes the file's rate for the same effective date.
+#[derive(Clone, Debug, serde::Deserialize)]
+#[serde(default)]
+pub struct FxConfig {
+    /// Path of a rates CSV. Empty ⇒ the ClickHouse table only.
+    pub rates_file: String,
+    /// How often the rates are reloaded, in seconds.
+    pub refresh_interval_secs: u64,
+    /// Currency a pair with no direct (or inverse) rate is triangulated through.
+    pub pivot_currency: String,
+    /// A rate older than this many days on the conversion date is treated as missing; `0` accepts a
+    /// rate of any age.
+    pub max_rate_age_days: i64,
+    /// FX markup (bps of the converted amount) charged when a connector settles in a currency other
+    /// than the payment's. Applies to connectors without an entry in `connector_markup_bps`.
+    pub markup_bps: f64,
+    /// Per-connector FX markup (lowercase connector → bps), overriding `markup_bps`.
+    pub connector_markup_bps: std::collections::HashMap<String, f64>,
+}
+
+impl Default for FxConfig {
+    fn default() -> Self {
+        Self {
+            rates_file: String::new(),
+            refresh_interval_secs: 3600,
+            pivot_currency: "USD".to_string(),
+            max_rate_age_days: 7,
+            markup_bps: 0.0,
+            connector_markup_bps: std::collections::HashMap::new(),
+        }
+    }
+}
+
+/// Settlement files delivered into a folder rather than by webhook or reporting API — typically by
+/// SFTP into a directory or bucket.
+#[derive(Clone, Debug, serde::Deserialize)]
+#[serde(default)]
+pub struct DropFolderConfig {
+    /// Enable the drop-folder poller. Off by default; enable on the deployment that owns ingestion
+    /// (and, for a local directory, the host it is mounted on).
+    pub enabled: bool,
+    /// How often each location is listed, in seconds.
+    pub interval_secs: u64,
+    pub locations: Vec<DropFolderLocation>,
+}
+
+impl Default for DropFolderConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            interval_secs: 300,
+            locations: Vec::new(),
+        }
+    }
+}
+
+/// One watched folder: a local directory (`path`) or an S3-compatible prefix (`bucket` + `prefix`,
+/// with `endpoint_url` for MinIO and friends). Exactly one of `path` / `bucket` is set.
+#[derive(Clone, Debug, Default, serde::Deserialize)]
+#[serde(default)]
+pub struct DropFolderLocation {
+    /// Stable name for the location (no `/`); queued jobs refer to their file through it, so
+    /// renaming a location orphans its pending jobs.
+    pub name: String,
+    pub path: Option<String>,
+    pub bucket: Option<String>,
+    pub prefix: String,
+    pub region: Option<String>,
+    pub endpoint_url: Option<String>,
+    /// File → merchant/connector rules, first match wins. Files no rule matches are ignored.
+    pub rules: Vec<DropFolderRule>,
+}
+
+/// Files whose path (relative to the location, `/`-separated) matches `glob` are `connector`
+/// settlement reports for `merchant_id`'s `account`. `*` and `?` stay within one path segment; `**`
+/// spans segments.
+#[derive(Clone, Debug, Default, serde::Deserialize)]
+#[serde(default)]
+pub struct DropFolderRule {
+    pub glob: String,
+    pub connector: String,
+    pub merchant_id: String,
+    pub account: String,
 }
 
 impl Default for CostIngestionConfig {
@@ -444,6 +662,9 @@ impl Default for CostIngestionConfig {
             report_poll_interval_secs: 3600,
             aws_bucket: String::new(),
             aws_region: None,
+            drop_folder: DropFolderConfig::default(),
+            fx: FxConfig::default(),
+            report_archive: ReportArchiveConfig::default(),
         }
     }
 }
@@ -1065,6 +1286,15 @@ impl GlobalConfig {
                 "user_auth_jwt_secret",
             ))?;
 
+        for provider in &mut self.user_auth.sso.providers {
+            provider.client_secret = secret_management_client
+                .get_secret(provider.client_secret.clone())
+                .await
+                .change_context(error::ConfigurationError::KmsDecryptError(
+                    "user_auth_sso_client_secret",
+                ))?;
+        }
+
         self.admin_secret.secret = secret_management_client
             .get_secret(self.admin_secret.secret.clone())
             .await
@@ -1072,6 +1302,15 @@ impl GlobalConfig {
                 "admin_secret_secret",
             ))?;
 
+        for secret in self.user_auth.totp_encryption_keys.values_mut() {
+            *secret = secret_management_client
+                .get_secret(secret.clone())
+                .await
+                .change_context(error::ConfigurationError::KmsDecryptError(
+                    "user_auth_totp_encryption_keys",
+                ))?;
+        }
+
         for secret in self.cost_ingestion.creds_encryption_keys.values_mut() {
             *secret = secret_management_client
                 .get_secret(secret.clone())
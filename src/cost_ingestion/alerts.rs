@@ -0,0 +1,785 @@
+//! Price-change alerting: run [`detect`](super::detect) after every fit and tell the merchant.
+//!
+//! The dashboard's price-change timeline only answers when someone opens it, so a PSP raising its
+//! markup could go unnoticed for weeks. After each fit the worker (and the manual-upload path)
+//! calls [`check_after_fit`]: it diffs the merchant's two latest snapshots at the merchant's own
+//! thresholds, grades each move [`Severity::Minor`] or [`Severity::Major`], persists the ones not
+//! seen before to the `cost_price_change` ClickHouse table, and delivers them — by email to
+//! `notify_emails` and as a signed POST to `webhook_url`. Delivery is best-effort: a failure is
+//! logged and the alert stays listed, unacknowledged, on `/cost-price-alerts`.
+//!
+//! An alert's id is derived from the cluster and the date the new price appeared, so re-running
+//! detection on the same snapshots (the next ingest, a reprocess) never re-raises it. Acknowledging
+//! re-inserts the row with `acknowledged_at` set; the table is a `ReplacingMergeTree` on
+//! `updated_at`, so the newest version is the one read back.
+//!
+//! Settings live per merchant in the `service_configuration` key-value store, like the fee
+//! overrides ([`super::overrides`]). Without any, detection still runs at the dashboard's
+//! tolerances and alerts are recorded, but nothing is sent.
+//!
+//! ## Webhook signature
+//! The body is JSON (`{"event": "cost.price_change", "merchant_id", "alerts": [...]}`). It is sent
+//! with `X-Decision-Engine-Timestamp` (unix seconds) and `X-Decision-Engine-Signature:
+//! v1=<hex>`, where `<hex>` is HMAC-SHA256 under the merchant's webhook secret over
+//! `"{timestamp}.{body}"`. Receivers should recompute it and reject stale timestamps.
+//!
+//! The URL is the merchant's, so it is not trusted to point outside the deployment: it must be
+//! https, every address its host resolves to must be public, and delivery connects to the address
+//! that was checked and does not follow redirects.
+
+use std::collections::HashSet;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::OnceLock;
+use std::time::Duration;
+
+use masking::PeekInterface;
+use ring::{digest, hmac};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use crate::config::ClickHouseAnalyticsConfig;
+use crate::email::templates::PriceChangeAlertTemplate;
+use crate::logger;
+
+use super::detect::{self, PriceChange};
+use super::overrides::{read_json, write_json};
+use super::types::IngestError;
+
+const TIMEOUT: Duration = Duration::from_secs(30);
+/// Outbound webhook budget. The receiver is the merchant's endpoint, so it gets less patience
+/// than ClickHouse does.
+const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(10);
+/// The most alerts `/cost-price-alerts` returns.
+const LIST_LIMIT: usize = 500;
+
+pub const SIGNATURE_HEADER: &str = "X-Decision-Engine-Signature";
+pub const TIMESTAMP_HEADER: &str = "X-Decision-Engine-Timestamp";
+pub const WEBHOOK_EVENT: &str = "cost.price_change";
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Minor,
+    Major,
+}
+
+impl Severity {
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Minor => "minor",
+            Self::Major => "major",
+        }
+    }
+}
+
+/// A merchant's alert thresholds and destinations.
+///
+/// A move is reported when the percentage moved more than `min_pct_bps` or the flat fee more than
+/// `min_fixed`, and is major when it moved more than `major_pct_bps` or `major_fixed`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct AlertSettings {
+    pub min_pct_bps: f64,
+    /// Settlement-currency units.
+    pub min_fixed: f64,
+    pub major_pct_bps: f64,
+    pub major_fixed: f64,
+    pub notify_emails: Vec<String>,
+    pub webhook_url: Option<String>,
+    /// HMAC key for the webhook signature. Generated here, never chosen by the caller, and only
+    /// shown in full on the response that created it.
+    pub webhook_secret: Option<String>,
+    /// RFC3339 timestamp of the last edit; empty while the defaults apply.
+    pub updated_at: String,
+}
+
+impl Default for AlertSettings {
+    fn default() -> Self {
+        Self {
+            min_pct_bps: detect::TOL_BPS,
+            min_fixed: detect::TOL_FIXED,
+            major_pct_bps: 10.0,
+            major_fixed: 0.05,
+            notify_emails: Vec::new(),
+            webhook_url: None,
+            webhook_secret: None,
+            updated_at: String::new(),
+        }
+    }
+}
+
+impl AlertSettings {
+    /// Reject thresholds detection can't use (negative or non-finite), a major threshold below
+    /// the reporting one, or a webhook URL that isn't https. Where the URL's host resolves to is
+    /// checked separately, by [`resolve_webhook_target`].
+    pub fn validate(&self) -> Result<(), String> {
+        for (name, value) in [
+            ("min_pct_bps", self.min_pct_bps),
+            ("min_fixed", self.min_fixed),
+            ("major_pct_bps", self.major_pct_bps),
+            ("major_fixed", self.major_fixed),
+        ] {
+            if !value.is_finite() || value < 0.0 {
+                return Err(format!("{name} must be finite and non-negative"));
+            }
+        }
+        if self.major_pct_bps < self.min_pct_bps || self.major_fixed < self.min_fixed {
+            return Err("major thresholds must be at least the minimum thresholds".to_string());
+        }
+        if let Some(address) = self.notify_emails.iter().find(|a| !a.contains('@')) {
+            return Err(format!("'{address}' is not an email address"));
+        }
+        if let Some(url) = &self.webhook_url {
+            let parsed = reqwest::Url::parse(url).map_err(|e| format!("webhook_url: {e}"))?;
+            if parsed.scheme() != "https" {
+                return Err("webhook_url must be https".to_string());
+            }
+        }
+        Ok(())
+    }
+
+    pub fn severity(&self, change: &PriceChange) -> Severity {
+        let pct = (change.new_pct_bps - change.old_pct_bps).abs();
+        let fixed = (change.new_fixed - change.old_fixed).abs();
+        if pct > self.major_pct_bps || fixed > self.major_fixed {
+            Severity::Major
+        } else {
+            Severity::Minor
+        }
+    }
+}
+
+/// A detected price change, as persisted and listed.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct PriceChangeAlert {
+    pub id: String,
+    #[serde(flatten)]
+    pub change: PriceChange,
+    pub severity: Severity,
+    /// `YYYY-MM-DD hh:mm:ss` (UTC).
+    pub detected_at: String,
+    pub acknowledged_at: Option<String>,
+    pub acknowledged_by: Option<String>,
+}
+
+/// Stable id of a change: the cluster plus the snapshot date its new price appeared on.
+pub fn alert_id(change: &PriceChange) -> String {
+    let key = [
+        change.connector.as_str(),
+        &change.account,
+        &change.card_network,
+        &change.variant,
+        &change.funding,
+        &change.issuer_country,
+        &change.currency,
+        &change.ic_category,
+        &change.changed_on,
+    ]
+    .join("|");
+    let digest = digest::digest(&digest::SHA256, key.as_bytes());
+    hex::encode(&digest.as_ref()[..16])
+}
+
+/// `v1=<hex HMAC-SHA256(secret, "{timestamp}.{body}")>` — the webhook signature header value.
+pub fn sign(secret: &str, timestamp: i64, body: &str) -> String {
+    let key = hmac::Key::new(hmac::HMAC_SHA256, secret.as_bytes());
+    let tag = hmac::sign(&key, format!("{timestamp}.{body}").as_bytes());
+    format!("v1={}", hex::encode(tag.as_ref()))
+}
+
+/// A new random webhook secret.
+pub fn generate_webhook_secret() -> String {
+    let bytes: Vec<u8> = uuid::Uuid::new_v4()
+        .as_bytes()
+        .iter()
+        .chain(uuid::Uuid::new_v4().as_bytes())
+        .copied()
+        .collect();
+    format!("whsec_{}", hex::encode(bytes))
+}
+
+// ── settings ──────────────────────────────────────────────────────────────────────────────────
+
+fn settings_name(merchant_id: &str) -> String {
+    format!("cost_price_alerts::{merchant_id}")
+}
+
+/// The merchant's settings, or the defaults when none were saved.
+pub async fn get_settings(merchant_id: &str) -> Result<AlertSettings, IngestError> {
+    Ok(read_json(settings_name(merchant_id))
+        .await?
+        .unwrap_or_default())
+}
+
+pub async fn put_settings(merchant_id: &str, settings: &AlertSettings) -> Result<(), IngestError> {
+    write_json(settings_name(merchant_id), settings).await
+}
+
+// ── detection ─────────────────────────────────────────────────────────────────────────────────
+
+/// Detect, record, and deliver this merchant's new price changes. Called after every fit; never
+/// fails the ingest — errors are logged.
+pub async fn check_after_fit(cfg: &ClickHouseAnalyticsConfig, merchant_id: &str) {
+    match raise_new(cfg, merchant_id).await {
+        Ok((settings, raised)) if !raised.is_empty() => {
+            logger::info!(
+                tag = "cost_price_alerts",
+                "{} new price change(s) for merchant {}",
+                raised.len(),
+                merchant_id
+            );
+            deliver(&settings, merchant_id, &raised).await;
+        }
+        Ok(_) => {}
+        Err(e) => {
+            logger::warn!(
+                tag = "cost_price_alerts",
+                "price-change check for {} failed: {}",
+                merchant_id,
+                e
+            );
+        }
+    }
+}
+
+/// The alerts detection raised that weren't already recorded, after recording them.
+async fn raise_new(
+    cfg: &ClickHouseAnalyticsConfig,
+    merchant_id: &str,
+) -> Result<(AlertSettings, Vec<PriceChangeAlert>), IngestError> {
+    let settings = get_settings(merchant_id).await?;
+    let changes =
+        detect::price_changes_over(cfg, merchant_id, settings.min_pct_bps, settings.min_fixed)
+            .await?;
+    if changes.is_empty() {
+        return Ok((settings, Vec::new()));
+    }
+    let known: HashSet<String> = list(cfg, merchant_id, false)
+        .await?
+        .into_iter()
+        .map(|a| a.id)
+        .collect();
+    let detected_at = now();
+    let raised: Vec<PriceChangeAlert> = changes
+        .into_iter()
+        .filter_map(|change| {
+            let id = alert_id(&change);
+            (!known.contains(&id)).then(|| PriceChangeAlert {
+                id,
+                severity: settings.severity(&change),
+                change,
+                detected_at: detected_at.clone(),
+                acknowledged_at: None,
+                acknowledged_by: None,
+            })
+        })
+        .collect();
+    insert(cfg, merchant_id, &raised).await?;
+    Ok((settings, raised))
+}
+
+// ── storage ───────────────────────────────────────────────────────────────────────────────────
+
+const LIST_SQL: &str = r#"
+SELECT
+    id, connector, account, card_network, variant, funding, issuer_country, currency, ic_category,
+    toString(changed_on), old_pct_bps, new_pct_bps, old_fixed, new_fixed, severity,
+    toString(detected_at), ifNull(toString(acknowledged_at), ''), acknowledged_by
+FROM __DB__.cost_price_change FINAL
+WHERE merchant_id = {merchant_id:String}
+  AND ({alert_id:String} = '' OR id = {alert_id:String})
+  AND ({unacknowledged:UInt8} = 0 OR isNull(acknowledged_at))
+ORDER BY detected_at DESC, abs(new_pct_bps - old_pct_bps) DESC
+LIMIT __LIMIT__
+FORMAT TSV
+"#;
+
+/// The merchant's recorded alerts, newest first; only the open ones when `unacknowledged_only`.
+pub async fn list(
+    cfg: &ClickHouseAnalyticsConfig,
+    merchant_id: &str,
+    unacknowledged_only: bool,
+) -> Result<Vec<PriceChangeAlert>, IngestError> {
+    select(cfg, merchant_id, "", unacknowledged_only).await
+}
+
+/// Mark an alert acknowledged by `by`. `None` when the merchant has no alert with that id; an
+/// alert already acknowledged keeps its first acknowledgement.
+pub async fn acknowledge(
+    cfg: &ClickHouseAnalyticsConfig,
+    merchant_id: &str,
+    alert_id: &str,
+    by: &str,
+) -> Result<Option<PriceChangeAlert>, IngestError> {
+    let Some(mut alert) = select(cfg, merchant_id, alert_id, false)
+        .await?
+        .into_iter()
+        .next()
+    else {
+        return Ok(None);
+    };
+    if alert.acknowledged_at.is_none() {
+        alert.acknowledged_at = Some(now());
+        alert.acknowledged_by = Some(by.to_string());
+        insert(cfg, merchant_id, std::slice::from_ref(&alert)).await?;
+    }
+    Ok(Some(alert))
+}
+
+async fn select(
+    cfg: &ClickHouseAnalyticsConfig,
+    merchant_id: &str,
+    alert_id: &str,
+    unacknowledged_only: bool,
+) -> Result<Vec<PriceChangeAlert>, IngestError> {
+    let sql = LIST_SQL
+        .replace("__DB__", &cfg.database)
+        .replace("__LIMIT__", &LIST_LIMIT.to_string());
+    let unacknowledged = if unacknowledged_only { "1" } else { "0" };
+    let text = run(
+        cfg,
+        &[
+            ("param_merchant_id", merchant_id),
+            ("param_alert_id", alert_id),
+            ("param_unacknowledged", unacknowledged),
+        ],
+        sql,
+    )
+    .await?;
+
+    let mut out = Vec::new();
+    for line in text.lines() {
+        if line.is_empty() {
+            continue;
+        }
+        let f: Vec<&str> = line.split('\t').collect();
+        if f.len() < 18 {
+            continue;
+        }
+        let g = |i: usize| f[i].trim().parse::<f64>().unwrap_or(0.0);
+        let opt = |i: usize| Some(f[i].trim().to_string()).filter(|s| !s.is_empty());
+        out.push(PriceChangeAlert {
+            id: f[0].to_string(),
+            change: PriceChange {
+                connector: f[1].to_string(),
+                account: f[2].to_string(),
+                card_network: f[3].to_string(),
+                variant: f[4].to_string(),
+                funding: f[5].to_string(),
+                issuer_country: f[6].to_string(),
+                currency: f[7].to_string(),
+                ic_category: f[8].to_string(),
+                changed_on: f[9].trim().to_string(),
+                old_pct_bps: g(10),
+                new_pct_bps: g(11),
+                old_fixed: g(12),
+                new_fixed: g(13),
+            },
+            severity: if f[14].trim() == "major" {
+                Severity::Major
+            } else {
+                Severity::Minor
+            },
+            detected_at: f[15].trim().to_string(),
+            acknowledged_at: opt(16),
+            acknowledged_by: opt(17),
+        });
+    }
+    Ok(out)
+}
+
+async fn insert(
+    cfg: &ClickHouseAnalyticsConfig,
+    merchant_id: &str,
+    alerts: &[PriceChangeAlert],
+) -> Result<(), IngestError> {
+    if alerts.is_empty() {
+        return Ok(());
+    }
+    let mut body = String::new();
+    for a in alerts {
+        let c = &a.change;
+        let obj = json!({
+            "merchant_id": merchant_id,
+            "id": a.id,
+            "connector": c.connector,
+            "account": c.account,
+            "card_network": c.card_network,
+            "variant": c.variant,
+            "funding": c.funding,
+            "issuer_country": c.issuer_country,
+            "currency": c.currency,
+            "ic_category": c.ic_category,
+            "changed_on": c.changed_on,
+            "old_pct_bps": c.old_pct_bps,
+            "new_pct_bps": c.new_pct_bps,
+            "old_fixed": c.old_fixed,
+            "new_fixed": c.new_fixed,
+            "severity": a.severity.as_str(),
+            "detected_at": a.detected_at,
+            "acknowledged_at": a.acknowledged_at,
+            "acknowledged_by": a.acknowledged_by.clone().unwrap_or_default(),
+        });
+        body.push_str(
+            &serde_json::to_string(&obj).map_err(|e| IngestError::Storage(e.to_string()))?,
+        );
+        body.push('\n');
+    }
+    let query = format!(
+        "INSERT INTO {}.cost_price_change FORMAT JSONEachRow",
+        cfg.database
+    );
+    run(cfg, &[("query", query.as_str())], body).await?;
+    Ok(())
+}
+
+async fn run(
+    cfg: &ClickHouseAnalyticsConfig,
+    params: &[(&str, &str)],
+    body: String,
+) -> Result<String, IngestError> {
+    let mut req = client()
+        .post(cfg.url.trim_end_matches('/'))
+        .query(params)
+        .body(body);
+    if !cfg.user.is_empty() {
+        req = req.basic_auth(&cfg.user, cfg.password.as_ref().map(|p| p.peek().clone()));
+    }
+    let resp = req
+        .send()
+        .await
+        .map_err(|e| IngestError::Storage(e.to_string()))?;
+    if !resp.status().is_success() {
+        let status = resp.status();
+        let text = resp.text().await.unwrap_or_default();
+        return Err(IngestError::Storage(format!(
+            "clickhouse price-alert query failed ({status}): {text}"
+        )));
+    }
+    resp.text()
+        .await
+        .map_err(|e| IngestError::Storage(e.to_string()))
+}
+
+fn now() -> String {
+    chrono::Utc::now().format("%Y-%m-%d %H:%M:%S").to_string()
+}
+
+fn client() -> &'static reqwest::Client {
+    static CLIENT: OnceLock<reqwest::Client> = OnceLock::new();
+    CLIENT.get_or_init(|| super::ch_http::client(TIMEOUT))
+}
+
+// ── delivery ──────────────────────────────────────────────────────────────────────────────────
+
+async fn deliver(settings: &AlertSettings, merchant_id: &str, alerts: &[PriceChangeAlert]) {
+    if !settings.notify_emails.is_empty() {
+        email(settings, merchant_id, alerts).await;
+    }
+    if let (Some(url), Some(secret)) = (&settings.webhook_url, &settings.webhook_secret) {
+        if let Err(e) = post_webhook(url, secret, merchant_id, alerts).await {
+            logger::warn!(
+                tag = "cost_price_alerts",
+                "webhook for {} failed: {}",
+                merchant_id,
+                e
+            );
+        }
+    }
+}
+
+async fn email(settings: &AlertSettings, merchant_id: &str, alerts: &[PriceChangeAlert]) {
+    let Some(global_state) = crate::app::APP_STATE.get() else {
+        return;
+    };
+    let lines: Vec<String> = alerts.iter().map(summary_line).collect();
+    let major = alerts
+        .iter()
+        .filter(|a| a.severity == Severity::Major)
+        .count();
+    for address in &settings.notify_emails {
+        let message = PriceChangeAlertTemplate {
+            user_email: address.clone(),
+            merchant_id: merchant_id.to_string(),
+            changes: lines.clone(),
+            major,
+        }
+        .into_message();
+        if let Err(err) = global_state.email_client.send_email(message).await {
+            logger::warn!(
+                tag = "cost_price_alerts",
+                "could not notify {} about price changes for {}: {:?}",
+                address,
+                merchant_id,
+                err
+            );
+        }
+    }
+}
+
+/// `adyen/acct visa credit GB EUR: 240.0 → 250.0 bps, 0.2200 → 0.2500 (major)`.
+fn summary_line(alert: &PriceChangeAlert) -> String {
+    let c = &alert.change;
+    let cluster = [
+        c.card_network.as_str(),
+        &c.variant,
+        &c.funding,
+        &c.issuer_country,
+        &c.ic_category,
+    ]
+    .iter()
+    .filter(|d| !d.is_empty())
+    .copied()
+    .collect::<Vec<_>>()
+    .join(" ");
+    format!(
+        "{}/{} {} {}: {:.1} → {:.1} bps, {:.4} → {:.4} ({}, from {})",
+        c.connector,
+        c.account,
+        cluster,
+        c.currency,
+        c.old_pct_bps,
+        c.new_pct_bps,
+        c.old_fixed,
+        c.new_fixed,
+        alert.severity.as_str(),
+        c.changed_on
+    )
+}
+
+async fn post_webhook(
+    url: &str,
+    secret: &str,
+    merchant_id: &str,
+    alerts: &[PriceChangeAlert],
+) -> Result<(), IngestError> {
+    let body = serde_json::to_string(&json!({
+        "event": WEBHOOK_EVENT,
+        "merchant_id": merchant_id,
+        "alerts": alerts,
+    }))
+    .map_err(|e| IngestError::Storage(e.to_string()))?;
+    let (url, address) = resolve_webhook_target(url)
+        .await
+        .map_err(IngestError::Storage)?;
+    let timestamp = chrono::Utc::now().timestamp();
+    let resp = webhook_client(&url, address)?
+        .post(url)
+        .header(reqwest::header::CONTENT_TYPE, "application/json")
+        .header(TIMESTAMP_HEADER, timestamp.to_string())
+        .header(SIGNATURE_HEADER, sign(secret, timestamp, &body))
+        .body(body)
+        .send()
+        .await
+        .map_err(|e| IngestError::Storage(e.to_string()))?;
+    if !resp.status().is_success() {
+        return Err(IngestError::Storage(format!(
+            "webhook responded {}",
+            resp.status()
+        )));
+    }
+    Ok(())
+}
+
+/// Parse `url` and resolve its host, refusing it unless it is https and every address it resolves
+/// to is public. Returns the address to connect to, so delivery goes where was checked rather than
+/// wherever a second lookup points.
+pub async fn resolve_webhook_target(url: &str) -> Result<(reqwest::Url, SocketAddr), String> {
+    let parsed = reqwest::Url::parse(url).map_err(|e| format!("webhook_url: {e}"))?;
+    if parsed.scheme() != "https" {
+        return Err("webhook_url must be https".to_string());
+    }
+    let host = parsed
+        .host_str()
+        .ok_or_else(|| "webhook_url has no host".to_string())?
+        .trim_start_matches('[')
+        .trim_end_matches(']')
+        .to_string();
+    let port = parsed.port_or_known_default().unwrap_or(443);
+    let addresses: Vec<SocketAddr> = tokio::net::lookup_host((host.as_str(), port))
+        .await
+        .map_err(|e| format!("webhook_url: cannot resolve {host}: {e}"))?
+        .collect();
+    if addresses.is_empty() {
+        return Err(format!("webhook_url: {host} does not resolve"));
+    }
+    if addresses.iter().any(|address| !is_public(address.ip())) {
+        return Err(format!(
+            "webhook_url: {host} resolves to a private, loopback or link-local address"
+        ));
+    }
+    Ok((parsed, addresses[0]))
+}
+
+/// Whether a webhook may be sent to `ip`: not this host, the deployment's own networks, or a
+/// cloud metadata endpoint.
+fn is_public(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(v4) => {
+            let [a, b, ..] = v4.octets();
+            !(v4.is_private()
+                || v4.is_loopback()
+                || v4.is_link_local()
+                || v4.is_unspecified()
+                || v4.is_broadcast()
+                || v4.is_multicast()
+                || v4.is_documentation()
+                // Carrier-grade NAT, 100.64.0.0/10.
+                || (a == 100 && (64..128).contains(&b)))
+        }
+        IpAddr::V6(v6) => match v6.to_ipv4_mapped() {
+            Some(v4) => is_public(IpAddr::V4(v4)),
+            None => {
+                let first = v6.segments()[0];
+                !(v6.is_loopback()
+                    || v6.is_unspecified()
+                    || v6.is_multicast()
+                    // Unique local, fc00::/7, and link-local, fe80::/10.
+                    || (first & 0xfe00) == 0xfc00
+                    || (first & 0xffc0) == 0xfe80)
+            }
+        },
+    }
+}
+
+/// The webhook goes to the merchant's own endpoint, out through whatever egress proxy the
+/// deployment sets — so not [`super::ch_http::client`], which bypasses it. Built per delivery, to
+/// pin the host to the `address` that was checked; redirects are not followed, since one could
+/// lead anywhere.
+fn webhook_client(url: &reqwest::Url, address: SocketAddr) -> Result<reqwest::Client, IngestError> {
+    let mut builder = reqwest::Client::builder()
+        .timeout(WEBHOOK_TIMEOUT)
+        .redirect(reqwest::redirect::Policy::none());
+    if let Some(domain) = url.domain() {
+        builder = builder.resolve(domain, address);
+    }
+    builder
+        .build()
+        .map_err(|e| IngestError::Storage(e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn change(old_pct: f64, new_pct: f64, old_fixed: f64, new_fixed: f64) -> PriceChange {
+        PriceChange {
+            connector: "adyen".to_string(),
+            account: "acct".to_string(),
+            card_network: "visa".to_string(),
+            variant: "classic".to_string(),
+            funding: "credit".to_string(),
+            issuer_country: "GB".to_string(),
+            currency: "EUR".to_string(),
+            ic_category: String::new(),
+            old_pct_bps: old_pct,
+            new_pct_bps: new_pct,
+            old_fixed,
+            new_fixed,
+            changed_on: "2026-09-01".to_string(),
+        }
+    }
+
+    #[test]
+    fn either_part_crossing_the_major_threshold_makes_a_change_major() {
+        let settings = AlertSettings::default();
+        assert_eq!(
+            settings.severity(&change(240.0, 245.0, 0.22, 0.22)),
+            Severity::Minor
+        );
+        assert_eq!(
+            settings.severity(&change(240.0, 255.0, 0.22, 0.22)),
+            Severity::Major
+        );
+        // A fall is as reportable as a rise.
+        assert_eq!(
+            settings.severity(&change(240.0, 240.0, 0.30, 0.22)),
+            Severity::Major
+        );
+    }
+
+    #[test]
+    fn the_id_is_stable_per_cluster_and_snapshot() {
+        let a = change(240.0, 250.0, 0.22, 0.25);
+        // The prices don't enter the id: the same step seen again is the same alert.
+        assert_eq!(alert_id(&a), alert_id(&change(240.0, 251.0, 0.22, 0.25)));
+        let mut later = a.clone();
+        later.changed_on = "2026-10-01".to_string();
+        assert_ne!(alert_id(&a), alert_id(&later));
+        let mut other = a.clone();
+        other.funding = "debit".to_string();
+        assert_ne!(alert_id(&a), alert_id(&other));
+    }
+
+    #[test]
+    fn the_signature_covers_timestamp_and_body() {
+        const BODY: &str = r#"{"event":"cost.price_change"}"#;
+        let sig = sign("whsec_test", 1_760_000_000, BODY);
+        assert!(sig.starts_with("v1="));
+        assert_eq!(sig.len(), 3 + 64);
+        assert_eq!(sig, sign("whsec_test", 1_760_000_000, BODY));
+        assert_ne!(sig, sign("whsec_test", 1_760_000_001, BODY));
+        assert_ne!(sig, sign("whsec_other", 1_760_000_000, BODY));
+        assert_ne!(sig, sign("whsec_test", 1_760_000_000, "{}"));
+    }
+
+    #[test]
+    fn settings_reject_inverted_thresholds_and_non_http_webhooks() {
+        assert!(AlertSettings::default().validate().is_ok());
+        let inverted = AlertSettings {
+            major_pct_bps: 1.0,
+            ..AlertSettings::default()
+        };
+        assert!(inverted.validate().is_err());
+        let ftp = AlertSettings {
+            webhook_url: Some("ftp://example.com/hook".to_string()),
+            ..AlertSettings::default()
+        };
+        assert!(ftp.validate().is_err());
+        let plain = AlertSettings {
+            webhook_url: Some("http://example.com/hook".to_string()),
+            ..AlertSettings::default()
+        };
+        assert!(plain.validate().is_err());
+    }
+
+    #[test]
+    fn webhooks_only_reach_public_addresses() {
+        for internal in [
+            "127.0.0.1",
+            "10.0.0.5",
+            "172.16.3.4",
+            "192.168.1.1",
+            "169.254.169.254",
+            "100.64.0.1",
+            "0.0.0.0",
+            "::1",
+            "fe80::1",
+            "fd00::1",
+            "::ffff:10.0.0.5",
+        ] {
+            assert!(!is_public(internal.parse().unwrap()), "{internal}");
+        }
+        for public in ["93.184.216.34", "2606:2800:220:1::1"] {
+            assert!(is_public(public.parse().unwrap()), "{public}");
+        }
+    }
+
+    #[tokio::test]
+    async fn a_literal_internal_address_is_refused_before_delivery() {
+        assert!(
+            resolve_webhook_target("https://169.254.169.254/latest/meta-data")
+                .await
+                .is_err()
+        );
+        assert!(resolve_webhook_target("https://[::1]:8443/hook")
+            .await
+            .is_err());
+        assert!(resolve_webhook_target("http://93.184.216.34/hook")
+            .await
+            .is_err());
+        assert!(resolve_webhook_target("https://93.184.216.34/hook")
+            .await
+            .is_ok());
+    }
+}
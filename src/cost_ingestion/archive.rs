@@ -0,0 +1,354 @@
+//! Report archive — the raw settlement reports behind past ingestions, kept so they can be
+//! reprocessed.
+//!
+//! The pipeline stores only per-day sufficient statistics, never the report itself, so a fix to a
+//! connector parser or a merchant's column mapping used to leave every earlier ingestion wrong for
+//! good. Each entry point (manual upload, sample run, ingest worker) now retains the report here
+//! before parsing it, and records its address on the `cost_ingestion` row (`report_blob`);
+//! [`super::reprocess`] reads it back.
+//!
+//! Storage is **content-addressed**: a report is filed under the SHA-256 of its bytes, so the same
+//! file delivered twice (a webhook and a manual upload of one report) is kept once, and a stored
+//! report can't be changed underneath the ingestions that reference it. The archive is a local
+//! directory or an S3-compatible prefix (`cost_ingestion.report_archive`); with neither configured
+//! nothing is kept and those ingestions simply can't be reprocessed.
+//!
+//! Retention is best-effort: a report that fails to archive still ingests, with a warning.
+
+use std::io::{BufReader, Read};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use aws_sdk_s3::primitives::ByteStream;
+use bytes::Bytes;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+use crate::config::ReportArchiveConfig;
+use crate::logger;
+
+use super::connectors::sftp_drop::{self, DropLocation};
+use super::store;
+use super::types::IngestError;
+
+/// Prefix of a `report_blob` address: `sha256:<hex>`.
+const SCHEME: &str = "sha256:";
+
+/// Label for the shared S3 transport's error messages.
+const LABEL: &str = "report archive";
+
+/// Retain the report staged at `path` for ingestion `ingestion_id` and record its address on the
+/// ingestion row. Best-effort: failures are logged, never returned.
+pub async fn retain_file(config: &ReportArchiveConfig, ingestion_id: &str, path: &Path) {
+    let result = match Archive::new(config) {
+        Ok(Some(archive)) => archive.put_file(path).await.map(Some),
+        Ok(None) => Ok(None),
+        Err(e) => Err(e),
+    };
+    record(ingestion_id, result).await;
+}
+
+/// [`retain_file`] for a report already in memory (a connector download).
+pub async fn retain_bytes(config: &ReportArchiveConfig, ingestion_id: &str, bytes: &Bytes) {
+    let result = match Archive::new(config) {
+        Ok(Some(archive)) => archive.put_bytes(bytes).await.map(Some),
+        Ok(None) => Ok(None),
+        Err(e) => Err(e),
+    };
+    record(ingestion_id, result).await;
+}
+
+async fn record(ingestion_id: &str, result: Result<Option<String>, IngestError>) {
+    let outcome = match result {
+        Ok(Some(blob)) => store::set_report_blob(ingestion_id, &blob).await,
+        Ok(None) => Ok(()),
+        Err(e) => Err(e),
+    };
+    if let Err(e) = outcome {
+        logger::warn!(
+            tag = "report_archive",
+            "ingestion {} report not retained, so it can't be reprocessed: {:?}",
+            ingestion_id,
+            e
+        );
+    }
+}
+
+/// Open a retained report for reading. The pipeline parses off a blocking `Read`, so a bucket
+/// object is first streamed to a temporary file, which is removed when the reader is dropped.
+pub async fn open(
+    config: &ReportArchiveConfig,
+    blob: &str,
+) -> Result<Box<dyn Read + Send>, IngestError> {
+    let archive = Archive::new(config)?
+        .ok_or_else(|| IngestError::Storage("no report archive is configured".to_string()))?;
+    let hash = blob_hash(blob)?;
+    match &archive {
+        Archive::Dir(root) => {
+            let file = std::fs::File::open(dir_path(root, hash))
+                .map_err(|e| IngestError::Storage(format!("report archive read {blob}: {e}")))?;
+            Ok(Box::new(BufReader::new(file)))
+        }
+        Archive::Bucket(location) => {
+            let path = temp_path();
+            let file = TempFile(path.clone());
+            let mut body = sftp_drop::open(LABEL, location, &object_key(location, hash)).await?;
+            let mut out = tokio::fs::File::create(&path)
+                .await
+                .map_err(|e| IngestError::Storage(format!("report archive temp file: {e}")))?;
+            while let Some(chunk) = body
+                .try_next()
+                .await
+                .map_err(|e| IngestError::Download(format!("report archive body: {e}")))?
+            {
+                out.write_all(&chunk)
+                    .await
+                    .map_err(|e| IngestError::Storage(format!("report archive temp file: {e}")))?;
+            }
+            out.flush()
+                .await
+                .map_err(|e| IngestError::Storage(format!("report archive temp file: {e}")))?;
+            let reader = std::fs::File::open(&path)
+                .map_err(|e| IngestError::Storage(format!("report archive temp file: {e}")))?;
+            Ok(Box::new(TempReader {
+                reader: BufReader::new(reader),
+                _file: file,
+            }))
+        }
+    }
+}
+
+/// The hex digest of a `sha256:<hex>` address, refusing anything that isn't one — the digest
+/// becomes a path and an object key.
+fn blob_hash(blob: &str) -> Result<&str, IngestError> {
+    blob.strip_prefix(SCHEME)
+        .filter(|h| h.len() == 64 && h.bytes().all(|b| b.is_ascii_hexdigit()))
+        .ok_or_else(|| IngestError::Storage(format!("not a report archive address: {blob}")))
+}
+
+/// Where a digest lives: `sha256/<first two hex digits>/<hex>`, so no directory grows unbounded.
+fn relative_path(hash: &str) -> String {
+    format!("sha256/{}/{hash}", &hash[..2])
+}
+
+fn dir_path(root: &Path, hash: &str) -> PathBuf {
+    root.join(relative_path(hash))
+}
+
+fn object_key(location: &DropLocation, hash: &str) -> String {
+    format!("{}{}", location.prefix, relative_path(hash))
+}
+
+/// The configured archive.
+enum Archive {
+    Dir(PathBuf),
+    Bucket(DropLocation),
+}
+
+impl Archive {
+    /// `None` when no archive is configured.
+    fn new(config: &ReportArchiveConfig) -> Result<Option<Self>, IngestError> {
+        match (&config.path, &config.bucket) {
+            (None, None) => Ok(None),
+            (Some(path), None) => Ok(Some(Self::Dir(PathBuf::from(path)))),
+            (None, Some(bucket)) => Ok(Some(Self::Bucket(DropLocation {
+                bucket: bucket.clone(),
+                prefix: config.prefix.clone(),
+                region: config.region.clone(),
+                endpoint_url: config.endpoint_url.clone(),
+            }))),
+            (Some(_), Some(_)) => Err(IngestError::Storage(
+                "report archive: set at most one of path / bucket".to_string(),
+            )),
+        }
+    }
+
+    /// Store a report file, returning its address. A report already stored is not written again.
+    async fn put_file(&self, path: &Path) -> Result<String, IngestError> {
+        let hash = file_hash(path).await?;
+        match self {
+            Self::Dir(root) => {
+                let dest = dir_path(root, &hash);
+                if !tokio::fs::try_exists(&dest).await.unwrap_or(false) {
+                    let partial = partial_path(&dest).await?;
+                    tokio::fs::copy(path, &partial).await.map_err(write_error)?;
+                    publish(&partial, &dest).await?;
+                }
+            }
+            Self::Bucket(location) => {
+                let key = object_key(location, &hash);
+                if !sftp_drop::exists(LABEL, location, &key).await? {
+                    let body = ByteStream::from_path(path).await.map_err(write_error)?;
+                    sftp_drop::upload(LABEL, location, &key, body).await?;
+                }
+            }
+        }
+        Ok(format!("{SCHEME}{hash}"))
+    }
+
+    /// [`put_file`](Self::put_file) for a report in memory.
+    async fn put_bytes(&self, bytes: &Bytes) -> Result<String, IngestError> {
+        let hash = hex::encode(ring::digest::digest(&ring::digest::SHA256, bytes));
+        match self {
+            Self::Dir(root) => {
+                let dest = dir_path(root, &hash);
+                if !tokio::fs::try_exists(&dest).await.unwrap_or(false) {
+                    let partial = partial_path(&dest).await?;
+                    tokio::fs::write(&partial, bytes)
+                        .await
+                        .map_err(write_error)?;
+                    publish(&partial, &dest).await?;
+                }
+            }
+            Self::Bucket(location) => {
+                let key = object_key(location, &hash);
+                if !sftp_drop::exists(LABEL, location, &key).await? {
+                    sftp_drop::upload(LABEL, location, &key, ByteStream::from(bytes.clone()))
+                        .await?;
+                }
+            }
+        }
+        Ok(format!("{SCHEME}{hash}"))
+    }
+}
+
+/// A sibling of `dest` to write into before the rename that publishes it, creating the directory.
+/// Unique per write, so two processes archiving the same report never share one.
+async fn partial_path(dest: &Path) -> Result<PathBuf, IngestError> {
+    let dir = dest
+        .parent()
+        .ok_or_else(|| IngestError::Storage("report archive path has no parent".to_string()))?;
+    tokio::fs::create_dir_all(dir).await.map_err(write_error)?;
+    let name = dest
+        .file_name()
+        .and_then(|n| n.to_str())
+        .unwrap_or("report");
+    Ok(dir.join(format!("{name}.{}.partial", unique_suffix())))
+}
+
+/// Rename a fully written file into place, so a reader never sees a partial report at an address.
+async fn publish(partial: &Path, dest: &Path) -> Result<(), IngestError> {
+    if let Err(e) = tokio::fs::rename(partial, dest).await {
+        let _ = tokio::fs::remove_file(partial).await;
+        return Err(write_error(e));
+    }
+    Ok(())
+}
+
+fn write_error(e: impl std::fmt::Display) -> IngestError {
+    IngestError::Storage(format!("report archive write: {e}"))
+}
+
+/// Hex SHA-256 of a file's content, read in chunks — settlement files can run to gigabytes.
+async fn file_hash(path: &Path) -> Result<String, IngestError> {
+    let mut digest = ring::digest::Context::new(&ring::digest::SHA256);
+    let mut file = tokio::fs::File::open(path).await.map_err(write_error)?;
+    let mut buf = vec![0u8; 64 * 1024];
+    loop {
+        let n = file.read(&mut buf).await.map_err(write_error)?;
+        if n == 0 {
+            break;
+        }
+        digest.update(&buf[..n]);
+    }
+    Ok(hex::encode(digest.finish()))
+}
+
+fn unique_suffix() -> String {
+    static SEQ: AtomicU64 = AtomicU64::new(0);
+    format!(
+        "{}-{}",
+        std::process::id(),
+        SEQ.fetch_add(1, Ordering::Relaxed)
+    )
+}
+
+fn temp_path() -> PathBuf {
+    std::env::temp_dir().join(format!("de-archived-report-{}.csv", unique_suffix()))
+}
+
+/// Removes its file when dropped.
+struct TempFile(PathBuf);
+
+impl Drop for TempFile {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.0);
+    }
+}
+
+/// A reader over a temporary copy of a bucket object, which goes away with the reader.
+struct TempReader {
+    reader: BufReader<std::fs::File>,
+    _file: TempFile,
+}
+
+impl Read for TempReader {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        self.reader.read(buf)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn a_local_archive_keeps_one_copy_per_content() {
+        let root = std::env::temp_dir().join(format!("report_archive_test_{}", std::process::id()));
+        let config = ReportArchiveConfig {
+            path: Some(root.display().to_string()),
+            ..Default::default()
+        };
+        let archive = Archive::new(&config).unwrap().unwrap();
+
+        let report = root.join("upload.csv");
+        tokio::fs::create_dir_all(&root).await.unwrap();
+        tokio::fs::write(&report, b"a,b\n1,2\n").await.unwrap();
+
+        let from_file = archive.put_file(&report).await.unwrap();
+        let from_bytes = archive
+            .put_bytes(&Bytes::from_static(b"a,b\n1,2\n"))
+            .await
+            .unwrap();
+        assert_eq!(from_file, from_bytes);
+        assert_eq!(
+            from_file,
+            format!(
+                "sha256:{}",
+                hex::encode(ring::digest::digest(&ring::digest::SHA256, b"a,b\n1,2\n"))
+            )
+        );
+
+        let mut read_back = String::new();
+        open(&config, &from_file)
+            .await
+            .unwrap()
+            .read_to_string(&mut read_back)
+            .unwrap();
+        assert_eq!(read_back, "a,b\n1,2\n");
+
+        // No partial files are left behind next to the stored report.
+        let hash = blob_hash(&from_file).unwrap();
+        let mut entries = std::fs::read_dir(dir_path(&root, hash).parent().unwrap()).unwrap();
+        assert!(entries.next().is_some());
+        assert!(entries.next().is_none());
+
+        tokio::fs::remove_dir_all(&root).await.unwrap();
+    }
+
+    #[test]
+    fn only_sha256_addresses_resolve() {
+        let hash = "ab".repeat(32);
+        assert_eq!(blob_hash(&format!("sha256:{hash}")).unwrap(), hash);
+        assert!(blob_hash(&hash).is_err());
+        assert!(blob_hash("sha256:../../etc/passwd").is_err());
+        assert!(Archive::new(&ReportArchiveConfig::default())
+            .unwrap()
+            .is_none());
+        assert!(Archive::new(&ReportArchiveConfig {
+            path: Some("/srv/archive".to_string()),
+            bucket: Some("reports".to_string()),
+            ..Default::default()
+        })
+        .is_err());
+    }
+}
@@ -18,7 +18,7 @@ use serde_json::Value;
 use crate::cost_ingestion::connectors::csv_reader;
 use crate::cost_ingestion::source::SettlementReportSource;
 use crate::cost_ingestion::types::{
-    ConnectorCreds, IngestError, ReportNotification, SettledFeeRow,
+    ConnectorCreds, IngestError, ReportNotification, RowKind, SettledFeeRow,
 };
 
 /// Adyen record types that actually carry settlement fees; everything else (Authorised,
@@ -41,6 +41,14 @@ use crate::cost_ingestion::types::{
 /// the newer report supersedes that day cleanly.
 const FEE_RECORD_TYPES: [&str; 1] = ["Settled"];
 
+/// Record types booking a refund. Their fee columns carry Adyen's refund fee (and any interchange
+/// the scheme hands back, as a negative), so they become [`RowKind::Refund`] rows, not fit input.
+const REFUND_RECORD_TYPES: [&str; 1] = ["Refunded"];
+
+/// Record types booking a chargeback, first or second presentment. The fee columns carry the
+/// dispute fee; they become [`RowKind::Dispute`] rows. Reversals are skipped: the fee is not returned.
+const DISPUTE_RECORD_TYPES: [&str; 2] = ["Chargeback", "SecondChargeback"];
+
 const DOWNLOAD_TIMEOUT: Duration = Duration::from_secs(120);
 
 pub struct AdyenReportSource;
@@ -198,6 +206,7 @@ impl SettlementReportSource for AdyenReportSource {
             booking: Option<usize>,
             terminal: Option<usize>,
             card_number: Option<usize>,
+            payment_ccy: Option<usize>,
         }
 
         csv_reader::parse(
@@ -225,20 +234,28 @@ impl SettlementReportSource for AdyenReportSource {
                     // Optional: the (masked) PAN — its leading digits are the issuer BIN that seeds
                     // the global card-product map. Absent in trimmed/tokenized exports ⇒ no BIN.
                     card_number: h.index("Card Number"),
+                    // Optional: the shopper's currency. When it differs from the settlement
+                    // currency the rollup re-states the row in it (see `SettledFeeRow`).
+                    payment_ccy: h.index("Payment Currency"),
                 })
             },
             |c, row| {
                 // Skip non-fee rows before any field extraction — this is the ~90% majority.
-                if !FEE_RECORD_TYPES.contains(&row.get(c.record)) {
+                let Some(kind) = record_kind(row.get(c.record)) else {
                     return Ok(None);
-                }
+                };
 
                 let commission = to_float(row.get(c.commission));
                 let markup = to_float(row.get(c.markup));
                 let scheme_fee = to_float(row.get(c.scheme));
                 let interchange = to_float(row.get(c.interchange));
                 let total_fee = commission + markup + scheme_fee + interchange;
-                let gross = to_float(row.get(c.payable)) + total_fee;
+                // A refund or chargeback pays money back out, so its payable is negative; the amount
+                // it concerns is that outflow, fee excluded.
+                let gross = match kind {
+                    RowKind::Capture => to_float(row.get(c.payable)) + total_fee,
+                    RowKind::Refund | RowKind::Dispute => to_float(row.get(c.payable)).abs(),
+                };
 
                 let variant = row.get(c.variant).to_lowercase();
                 // Resolve funding from the variant, falling back to the interchange rate for
@@ -266,6 +283,7 @@ impl SettlementReportSource for AdyenReportSource {
                     funding,
                     issuer_country: row.get(c.issuer).to_string(),
                     currency: row.get(c.ccy).to_string(),
+                    presentment_currency: row.get_opt(c.payment_ccy).trim().to_uppercase(),
                     ic_category: ic_category(icsf),
                     ic_bps: SettledFeeRow::ic_bps_key(interchange_bps),
                     txn_date,
@@ -277,6 +295,7 @@ impl SettlementReportSource for AdyenReportSource {
                     markup,
                     commission,
                     bin,
+                    kind,
                 }))
             },
             on_row,
@@ -284,6 +303,19 @@ impl SettlementReportSource for AdyenReportSource {
     }
 }
 
+/// The kind of row a PAR record type becomes, or `None` for records that carry no fee.
+fn record_kind(record: &str) -> Option<RowKind> {
+    if FEE_RECORD_TYPES.contains(&record) {
+        Some(RowKind::Capture)
+    } else if REFUND_RECORD_TYPES.contains(&record) {
+        Some(RowKind::Refund)
+    } else if DISPUTE_RECORD_TYPES.contains(&record) {
+        Some(RowKind::Dispute)
+    } else {
+        None
+    }
+}
+
 /// Normalize an Adyen webhook body into a single `NotificationRequestItem`-shaped object,
 /// accepting both formats Adyen can post: the JSON envelope
 /// (`{"notificationItems":[{"NotificationRequestItem":{…}}]}`) and the legacy / "Test
@@ -504,6 +536,35 @@ ref2,Settled,visastandarddebit,visa,FR,EUR,100.00,0.05,0.00,0.02,0.20,\"[{\"\"t\
         assert!(r.txn_date.is_none(), "no Booking Date column -> None");
     }
 
+    #[test]
+    fn refund_and_chargeback_records_become_post_capture_rows() {
+        let csv = "\
+Psp Reference,Record Type,Payment Method Variant,Global Card Brand,Issuer Country,Settlement Currency,Payable (SC),Commission (SC),Markup (SC),Scheme Fees (SC),Interchange (SC),ICSF details\n\
+ref1,Settled,visastandarddebit,visa,FR,EUR,100.00,0.05,0.00,0.02,0.20,\n\
+ref1,Refunded,visastandarddebit,visa,FR,EUR,-40.00,0.10,0.00,0.00,-0.08,\n\
+ref2,Chargeback,visastandarddebit,visa,FR,EUR,-60.00,15.00,0.00,0.00,0.00,\n\
+ref2,ChargebackReversed,visastandarddebit,visa,FR,EUR,60.00,0.00,0.00,0.00,0.00,\n";
+        let rows = AdyenReportSource::new()
+            .parse_report(csv.as_bytes())
+            .unwrap();
+        let kinds: Vec<RowKind> = rows.iter().map(|r| r.kind).collect();
+        assert_eq!(
+            kinds,
+            [RowKind::Capture, RowKind::Refund, RowKind::Dispute],
+            "the reversal returns no fee and is skipped"
+        );
+        assert!(
+            (rows[1].gross - 40.0).abs() < 1e-9,
+            "refunded amount, positive"
+        );
+        assert!(
+            (rows[1].total_fee - 0.02).abs() < 1e-9,
+            "0.10 fee less 0.08 interchange back"
+        );
+        assert!((rows[2].gross - 60.0).abs() < 1e-9);
+        assert!((rows[2].total_fee - 15.0).abs() < 1e-9);
+    }
+
     #[test]
     fn booking_date_parses_date_part() {
         assert_eq!(
@@ -37,14 +37,21 @@ use masking::Secret;
 use crate::cost_ingestion::connectors::csv_reader;
 use crate::cost_ingestion::source::SettlementReportSource;
 use crate::cost_ingestion::types::{
-    ConnectorCreds, IngestError, ReportNotification, SettledFeeRow,
+    ConnectorCreds, IngestError, ReportNotification, RowKind, SettledFeeRow,
 };
 
 /// Braintree `Transaction Type` values that carry a settled processing fee. Sales are the fit's
-/// signal; `credit` / `dispute debit` / `dispute credit` rows are reversals whose signed amounts
-/// would distort the gross→fee regression, so they're skipped (mirrors Adyen's record-type filter).
+/// signal; refunds and disputes have signed amounts that would distort the gross→fee regression, so
+/// they become post-capture rows instead (mirrors Adyen's record-type split).
 const FEE_TRANSACTION_TYPES: [&str; 1] = ["sale"];
 
+/// `Transaction Type` of a refund.
+const REFUND_TRANSACTION_TYPE: &str = "credit";
+
+/// `Transaction Type` of a lost-funds dispute, which carries the dispute fee. `dispute credit` (funds
+/// returned on a won dispute) carries no fee and is skipped.
+const DISPUTE_TRANSACTION_TYPE: &str = "dispute debit";
+
 #[allow(dead_code)] // used once download_report is implemented
 const DOWNLOAD_TIMEOUT: Duration = Duration::from_secs(120);
 
@@ -157,13 +164,14 @@ impl SettlementReportSource for BraintreeReportSource {
                 })
             },
             |c, row| {
-                // Keep only settled sales; skip credits/disputes whose signed amounts would pollute
-                // the gross→fee regression — done before any field extraction.
-                if !FEE_TRANSACTION_TYPES
-                    .contains(&row.get(c.r#type).trim().to_lowercase().as_str())
-                {
-                    return Ok(None);
-                }
+                // Sales feed the fit; refunds and lost disputes are post-capture rows; anything else
+                // is skipped — decided before any field extraction.
+                let kind = match row.get(c.r#type).trim().to_lowercase().as_str() {
+                    t if FEE_TRANSACTION_TYPES.contains(&t) => RowKind::Capture,
+                    REFUND_TRANSACTION_TYPE => RowKind::Refund,
+                    DISPUTE_TRANSACTION_TYPE => RowKind::Dispute,
+                    _ => return Ok(None),
+                };
 
                 let interchange = to_float(row.get(c.interchange));
                 let scheme_fee = to_float(row.get_opt(c.scheme));
@@ -174,8 +182,8 @@ impl SettlementReportSource for BraintreeReportSource {
                 let total_fee = interchange + scheme_fee + markup + commission;
                 // `Settlement Amount` is already the gross transaction value (the fee's calculation
                 // base), so it maps to `gross` as-is — do NOT add `total_fee` (that's the Adyen path,
-                // where `Payable (SC)` is net-of-fees).
-                let gross = to_float(row.get(c.amount));
+                // where `Payable (SC)` is net-of-fees). Refunds and disputes report it negative.
+                let gross = to_float(row.get(c.amount)).abs();
 
                 // Prefer the card brand; fall back to the debit network (pinless), then the instrument.
                 let network = {
@@ -202,6 +210,7 @@ impl SettlementReportSource for BraintreeReportSource {
                     funding,
                     issuer_country: row.get_opt(c.issuer).trim().to_string(),
                     currency: row.get(c.ccy).trim().to_string(),
+                    presentment_currency: String::new(),
                     ic_category: row.get_opt(c.ic_desc).trim().to_string(),
                     // No published interchange-rate line in the Braintree report — one cluster.
                     ic_bps: String::new(),
@@ -218,6 +227,7 @@ impl SettlementReportSource for BraintreeReportSource {
                     // Braintree's PAR carries no PAN, so this connector contributes no BIN
                     // observation (bin stays empty).
                     bin: String::new(),
+                    kind,
                 }))
             },
             on_row,
@@ -301,7 +311,7 @@ mod tests {
     use super::*;
 
     #[test]
-    fn parses_sales_and_skips_reversals() {
+    fn parses_sales_and_splits_out_refunds() {
         // Header order intentionally not the code's order — indices resolve by label.
         let csv = "\
 Transaction ID,Transaction Type,Settlement Currency,Settlement Amount,Card Brand,Card Type,Payment Instrument,Interchange Description,Interchange Total Amount,Total Scheme Fees,Braintree Total Amount,Total Fee Amount,Card Issuing Country,Settlement Date,Payment Network\n\
@@ -310,8 +320,11 @@ r2,credit,USD,-10.00,Visa,credit,credit_card,MC-REGULATED,0.01,0.00,0.01,0.02,US
         let rows = BraintreeReportSource::new()
             .parse_report(csv.as_bytes())
             .unwrap();
-        assert_eq!(rows.len(), 1, "only the sale row is kept");
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[1].kind, RowKind::Refund, "the credit is not fit input");
+        assert!((rows[1].gross - 10.00).abs() < 1e-9);
         let r = &rows[0];
+        assert_eq!(r.kind, RowKind::Capture);
         assert_eq!(r.txn_ref, "9celsl42");
         assert_eq!(r.card_network, "mc", "MasterCard -> mc");
         assert_eq!(r.funding, "debit");
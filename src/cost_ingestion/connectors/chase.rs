@@ -23,7 +23,8 @@
 //! Fee sign. Chase reports settled fees as **negative** amounts (money deducted from the merchant);
 //! the OLS fit regresses a *positive* `total_fee` on a positive `gross`, so every fee column is
 //! negated here to a positive cost magnitude. `gross = Transaction Amount in Presentment Currency`
-//! is already positive for sales (refunds are filtered out before any fee extraction).
+//! is already positive for sales; refunds report it negative and become [`RowKind::Refund`] rows
+//! with the absolute amount. Chargebacks are not in Deposit Details, so Chase emits no dispute rows.
 //!
 //! Report envelope. A preset report is framed: a UTF-8 BOM, a `BEGIN…` line, an
 //! `EntityId=…,ReportTypeName=…` metadata line, then the CSV header + data rows, then an `END…`
@@ -53,14 +54,17 @@ use uuid::Uuid;
 use crate::cost_ingestion::connectors::csv_reader;
 use crate::cost_ingestion::source::SettlementReportSource;
 use crate::cost_ingestion::types::{
-    ConnectorCreds, IngestError, ReadyReport, ReportNotification, SettledFeeRow,
+    ConnectorCreds, IngestError, ReadyReport, ReportNotification, RowKind, SettledFeeRow,
 };
 
 /// `Action Type Code Text` value that carries a settled processing fee. Only sales feed the fit;
 /// `REFUND` rows are reversals whose signed amounts would distort the gross→fee regression, so they
-/// are skipped (mirrors Adyen's record-type and Braintree's transaction-type filter).
+/// become post-capture rows (mirrors Adyen's record-type and Braintree's transaction-type split).
 const SALE_ACTION: &str = "SALE";
 
+/// `Action Type Code Text` value of a refund.
+const REFUND_ACTION: &str = "REFUND";
+
 const DOWNLOAD_TIMEOUT: Duration = Duration::from_secs(120);
 
 /// The report *type* the J.P. Morgan **Deposit details** preset report is delivered under. Deposit
@@ -330,11 +334,16 @@ impl SettlementReportSource for ChaseReportSource {
                 })
             },
             |c, row| {
-                // Keep only settled sales; skip refunds (and any stray envelope remnant, whose
-                // action field is out of range ⇒ "" ⇒ not a sale) before any field extraction.
-                if !row.get(c.action).trim().eq_ignore_ascii_case(SALE_ACTION) {
+                // Keep settled sales and refunds; skip anything else (including any stray envelope
+                // remnant, whose action field is out of range ⇒ "") before any field extraction.
+                let action = row.get(c.action).trim();
+                let kind = if action.eq_ignore_ascii_case(SALE_ACTION) {
+                    RowKind::Capture
+                } else if action.eq_ignore_ascii_case(REFUND_ACTION) {
+                    RowKind::Refund
+                } else {
                     return Ok(None);
-                }
+                };
 
                 // Chase reports settled fees as negative deductions; negate to the positive cost
                 // magnitudes the OLS fit expects.
@@ -346,8 +355,8 @@ impl SettlementReportSource for ChaseReportSource {
                 let commission = 0.0;
                 let total_fee = interchange + scheme_fee + markup + commission;
                 // `Transaction Amount in Presentment Currency` is the sale value (the fee's
-                // calculation base) and is already positive for sales.
-                let gross = to_float(row.get(c.amount));
+                // calculation base) and is already positive for sales; negative for refunds.
+                let gross = to_float(row.get(c.amount)).abs();
 
                 let network = normalize_network(row.get(c.method));
                 // Reconcile funding: the interchange qualification code is authoritative about the
@@ -371,6 +380,7 @@ impl SettlementReportSource for ChaseReportSource {
                     funding,
                     issuer_country: row.get(c.issuer).trim().to_string(),
                     currency: row.get(c.currency).trim().to_string(),
+                    presentment_currency: String::new(),
                     ic_category: row.get(c.ic_code).trim().to_string(),
                     // Chase report carries no published interchange-rate bps — one cluster.
                     ic_bps: String::new(),
@@ -384,6 +394,7 @@ impl SettlementReportSource for ChaseReportSource {
                     commission,
                     // Chase's report carries no PAN here, so no BIN observation.
                     bin: String::new(),
+                    kind,
                 }))
             },
             on_row,
@@ -785,14 +796,16 @@ Submission Date,Presentment Currency Code,Settlement Currency Code,Transaction D
 END,EntityId=418553,Frequency=adhoc\n";
 
     #[test]
-    fn parses_sales_and_skips_refunds_and_envelope() {
+    fn parses_sales_and_refunds_and_skips_envelope() {
         let rows = ChaseReportSource::new().parse_report(REPORT).unwrap();
-        assert_eq!(
-            rows.len(),
-            1,
-            "only the sale row survives (refund + envelope skipped)"
+        assert_eq!(rows.len(), 2, "sale + refund survive (envelope skipped)");
+        assert_eq!(rows[1].kind, RowKind::Refund);
+        assert!(
+            (rows[1].gross - 20.0).abs() < 1e-9,
+            "refunded amount, positive"
         );
         let r = &rows[0];
+        assert_eq!(r.kind, RowKind::Capture);
         assert_eq!(r.txn_ref, "SYN0002500");
         assert_eq!(r.card_network, "visa", "VI -> visa");
         assert_eq!(r.funding, "credit", "Card Usage Type 3 -> credit");
@@ -44,9 +44,10 @@
 //! settlement-currency `(SC)` columns. Fee rows are negative in the report and are negated to a
 //! positive cost magnitude; capture rows are already positive.
 //!
-//! Refund / Partial Refund / Void actions are skipped whole (a "captures only" filter, matching the
-//! other connectors' sale-only filters). Auth-only / declined payments carry auth fees but no
-//! capture, so they never reach a `gross` and are dropped — they can't feed the `fee ~ gross` fit.
+//! Refund / Partial Refund and Chargeback actions are grouped apart from the payment's capture, into
+//! one [`RowKind::Refund`] / [`RowKind::Dispute`] row per payment carrying that event's fees, so their
+//! signed amounts never touch the capture's `fee ~ gross` fit. Voids are skipped. Auth-only /
+//! declined payments carry auth fees but no capture, so they never reach a `gross` and are dropped.
 //!
 //! Webhook ingestion. Checkout pushes a [`report_generated`] event (configured via a
 //! Dashboard/Workflow webhook) when a scheduled report is ready. `verify_and_parse_notification`
@@ -74,7 +75,7 @@ use serde_json::Value;
 use crate::cost_ingestion::connectors::csv_reader;
 use crate::cost_ingestion::source::SettlementReportSource;
 use crate::cost_ingestion::types::{
-    ConnectorCreds, IngestError, ReportNotification, SettledFeeRow,
+    ConnectorCreds, IngestError, ReportNotification, RowKind, SettledFeeRow,
 };
 
 /// The `type` of the report-ready webhook event this connector handles.
@@ -163,11 +164,18 @@ impl Default for CheckoutReportSource {
     }
 }
 
-/// Running aggregate for one `Payment ID` while scanning the report. Metadata is stamped from the
-/// payment's capture row (authoritative and always present when we emit); fee components accumulate
-/// across the payment's many rows.
+/// `Action Type`s booking a refund; their fee rows are the refund's cost.
+const REFUND_ACTIONS: [&str; 2] = ["refund", "partial refund"];
+
+/// `Action Type`s booking a chargeback; their fee rows are the dispute's cost.
+const DISPUTE_ACTIONS: [&str; 1] = ["chargeback"];
+
+/// Running aggregate for one `(Payment ID, kind)` while scanning the report. Metadata is stamped from
+/// the money-movement row (the capture, refund or chargeback line) when there is one, else from the
+/// first row seen; fee components accumulate across the payment's many rows.
 #[derive(Default)]
 struct PaymentAcc {
+    kind: RowKind,
     txn_ref: String,
     card_network: String,
     funding: String,
@@ -180,19 +188,25 @@ struct PaymentAcc {
     interchange: f64,
     scheme_fee: f64,
     commission: f64,
+    /// The money-movement line was seen: the capture, or the refund/chargeback amount.
     has_capture: bool,
 }
 
 impl PaymentAcc {
     /// Materialize the aggregate. Payments without a capture (auth-only/declined) have no gross and
-    /// are dropped — they can't anchor the `fee ~ gross` regression.
+    /// are dropped — they can't anchor the `fee ~ gross` regression. A refund or chargeback is kept
+    /// whenever it charged a fee, even if its money line fell in another report.
     fn into_row(self) -> Option<SettledFeeRow> {
-        if !self.has_capture {
-            return None;
-        }
         // `total_fee` is the report's own sum of this payment's fee rows. Interchange is itemized on
         // interchange++ pricing and simply 0 on blended, where it's already inside the bundled rows.
         let total_fee = self.interchange + self.scheme_fee + self.commission;
+        let keep = match self.kind {
+            RowKind::Capture => self.has_capture,
+            RowKind::Refund | RowKind::Dispute => self.has_capture || total_fee != 0.0,
+        };
+        if !keep {
+            return None;
+        }
         Some(SettledFeeRow {
             txn_ref: self.txn_ref,
             card_network: self.card_network,
@@ -200,6 +214,7 @@ impl PaymentAcc {
             funding: self.funding,
             issuer_country: self.issuer_country,
             currency: self.currency,
+            presentment_currency: String::new(),
             // `Card Category` — "Consumer" / "Commercial". Coarse next to Adyen's full label
             // ("Visa UAE Consumer Credit Platinum") but it is the single biggest interchange split
             // there is: commercial cards are exempt from the EU/UK consumer caps and price far
@@ -220,6 +235,7 @@ impl PaymentAcc {
             commission: self.commission,
             // Checkout's blended report carries no PAN, so no BIN observation.
             bin: String::new(),
+            kind: self.kind,
         })
     }
 }
@@ -431,8 +447,9 @@ impl SettlementReportSource for CheckoutReportSource {
         }
 
         // One payment fans across many rows, so we accumulate rather than emit per row. Bounded by
-        // the distinct-payment count (hundreds/thousands in a daily report), not the row count.
-        let mut acc: HashMap<String, PaymentAcc> = HashMap::new();
+        // the distinct-payment count (hundreds/thousands in a daily report), not the row count. Keyed
+        // by kind too, so a payment's refund or chargeback fees never fold into its capture.
+        let mut acc: HashMap<(String, RowKind), PaymentAcc> = HashMap::new();
 
         // `parse` emits per row; we never emit mid-scan (map_row always returns `Ok(None)`) and
         // instead fold into `acc`, so its `on_row` is a discard. The real `on_row` runs after.
@@ -455,12 +472,18 @@ impl SettlementReportSource for CheckoutReportSource {
                 })
             },
             |c, row| {
-                // Skip the refund/void side entirely — their signed amounts would distort the
-                // gross→fee regression (mirrors the other connectors' sale-only filter).
+                // Refunds and chargebacks are accumulated as their own kind so their signed amounts
+                // can't distort the capture's gross→fee regression; voids carry no fee.
                 let action = row.get(c.action_type).trim().to_lowercase();
-                if matches!(action.as_str(), "refund" | "partial refund" | "void") {
+                let kind = if REFUND_ACTIONS.contains(&action.as_str()) {
+                    RowKind::Refund
+                } else if DISPUTE_ACTIONS.contains(&action.as_str()) {
+                    RowKind::Dispute
+                } else if action == "void" {
                     return Ok(None);
-                }
+                } else {
+                    RowKind::Capture
+                };
                 let pid = row.get(c.payment_id).trim();
                 if pid.is_empty() {
                     return Ok(None);
@@ -468,13 +491,21 @@ impl SettlementReportSource for CheckoutReportSource {
 
                 let breakdown = row.get(c.breakdown_type).trim().to_lowercase();
                 let amount = to_float(row.get(c.holding_amount));
-                let entry = acc.entry(pid.to_string()).or_default();
-
-                if matches!(breakdown.as_str(), "capture" | "partial capture") {
-                    // The money-movement line: gross (positive). Sum handles partial captures.
-                    entry.gross += amount;
-                    entry.has_capture = true;
-                    // Stamp metadata from the capture row (authoritative, always present here).
+                let entry = acc.entry((pid.to_string(), kind)).or_default();
+                let money_line = match kind {
+                    RowKind::Capture => matches!(breakdown.as_str(), "capture" | "partial capture"),
+                    RowKind::Refund | RowKind::Dispute => {
+                        matches!(
+                            breakdown.as_str(),
+                            "refund" | "partial refund" | "chargeback"
+                        )
+                    }
+                };
+
+                if money_line || entry.txn_ref.is_empty() {
+                    // Stamp metadata from the money line (authoritative), or from the first row of
+                    // an event whose money line is elsewhere.
+                    entry.kind = kind;
                     entry.txn_ref = pid.to_string();
                     let network = normalize_network(row.get(c.payment_method));
                     let funding = funding_from_card_type(row.get(c.card_type));
@@ -485,6 +516,13 @@ impl SettlementReportSource for CheckoutReportSource {
                     entry.currency = row.get(c.holding_currency).trim().to_string();
                     entry.ic_category = normalize_card_category(row.get_opt(c.card_category));
                     entry.txn_date = parse_date(row.get(c.processed_on));
+                }
+
+                if money_line {
+                    // The money-movement line: gross for a capture (positive; the sum handles
+                    // partial captures), the refunded or disputed amount (negative) for an event.
+                    entry.gross += amount.abs();
+                    entry.has_capture = true;
                 } else if breakdown.starts_with("interchange") {
                     // `Interchange Fixed Fee` — the issuer pass-through, itemized on interchange++
                     // pricing. Kept separate from `commission` (Checkout's own take) so the
@@ -510,14 +548,20 @@ impl SettlementReportSource for CheckoutReportSource {
         // Owned, so the flush below can consume `acc`.
         let interchange_networks: HashSet<String> = acc
             .values()
-            .filter(|p| p.has_capture && p.interchange > 0.0 && !p.card_network.is_empty())
+            .filter(|p| {
+                p.kind == RowKind::Capture
+                    && p.has_capture
+                    && p.interchange > 0.0
+                    && !p.card_network.is_empty()
+            })
             .map(|p| p.card_network.clone())
             .collect();
 
         // Flush: one aggregated row per captured payment, minus the fee-incomplete ones.
         let mut incomplete = 0usize;
-        for (_pid, payment) in acc {
-            if payment.has_capture
+        for (_key, payment) in acc {
+            if payment.kind == RowKind::Capture
+                && payment.has_capture
                 && payment.interchange <= 0.0
                 && interchange_networks.contains(&payment.card_network)
             {
@@ -681,7 +725,7 @@ mod tests {
     use super::*;
 
     /// One report exercising: scheme+premium fees, an auth fee folded into a captured payment, a
-    /// blended payment whose partial refund is skipped, an auth-only payment (dropped), and a
+    /// blended payment whose partial refund is split out, an auth-only payment (dropped), and a
     /// multi-partial-capture payment. Header order intentionally differs from `Cols` — indices
     /// resolve by label. Amounts are all in the holding currency (GBP) for a clean assertion.
     const REPORT: &str = "\
@@ -702,7 +746,15 @@ pay_3,Authorization,Scheme Fixed Fee,VISA,Credit,GB,GBP,-0.003,2026-07-09T11:00:
 pay_4,Partial Capture,Partial Capture,AMEX,Credit,GB,GBP,30.00,2026-07-09T12:00:00.000\n\
 pay_4,Partial Capture,Partial Capture,AMEX,Credit,GB,GBP,20.00,2026-07-09T12:00:00.000\n";
 
+    /// The captured payments — the rows the fit sees.
     fn parse() -> Vec<SettledFeeRow> {
+        parse_all()
+            .into_iter()
+            .filter(|r| r.kind == RowKind::Capture)
+            .collect()
+    }
+
+    fn parse_all() -> Vec<SettledFeeRow> {
         CheckoutReportSource::new()
             .parse_report(REPORT.as_bytes())
             .unwrap()
@@ -744,7 +796,7 @@ pay_4,Partial Capture,Partial Capture,AMEX,Credit,GB,GBP,20.00,2026-07-09T12:00:
     }
 
     #[test]
-    fn refund_rows_are_skipped_not_netted() {
+    fn refund_rows_are_not_netted_into_the_capture() {
         let p2 = parse();
         let p2 = by_ref(&p2, "pay_2");
         assert_eq!(p2.card_network, "mc", "MASTERCARD -> mc");
@@ -757,11 +809,38 @@ pay_4,Partial Capture,Partial Capture,AMEX,Credit,GB,GBP,20.00,2026-07-09T12:00:
         assert_eq!(p2.scheme_fee, 0.0);
         assert!(
             (p2.commission - 0.03).abs() < 1e-9,
-            "blended fees only; refund fee skipped"
+            "blended fees only; refund fee kept apart"
         );
         assert!((p2.total_fee - 0.03).abs() < 1e-9);
     }
 
+    #[test]
+    fn refund_becomes_its_own_row() {
+        let rows = parse_all();
+        let refunds: Vec<_> = rows.iter().filter(|r| r.kind == RowKind::Refund).collect();
+        assert_eq!(refunds.len(), 1);
+        let r = refunds[0];
+        assert_eq!(r.txn_ref, "pay_2");
+        assert_eq!(r.card_network, "mc");
+        assert!((r.gross - 5.00).abs() < 1e-9, "refunded amount, positive");
+        assert!((r.total_fee - 0.01).abs() < 1e-9, "the refund fee");
+    }
+
+    #[test]
+    fn chargeback_fee_without_its_money_line_is_kept() {
+        let csv = "\
+Payment ID,Action Type,Breakdown Type,Payment Method,Card Type,Issuer Country,Holding Currency,Holding Currency Amount,Processed On\n\
+pay_9,Chargeback,Chargeback Fixed Fee,VISA,Credit,GB,GBP,-15.00,2026-08-01T09:00:00.000\n";
+        let rows = CheckoutReportSource::new()
+            .parse_report(csv.as_bytes())
+            .unwrap();
+        assert_eq!(rows.len(), 1);
+        assert_eq!(rows[0].kind, RowKind::Dispute);
+        assert_eq!(rows[0].gross, 0.0);
+        assert!((rows[0].total_fee - 15.00).abs() < 1e-9);
+        assert_eq!(rows[0].txn_date, NaiveDate::from_ymd_opt(2026, 8, 1));
+    }
+
     #[test]
     fn auth_only_payment_is_dropped() {
         assert!(
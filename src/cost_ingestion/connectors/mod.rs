@@ -9,4 +9,7 @@ pub mod braintree;
 pub mod chase;
 pub mod checkout;
 pub mod csv_reader;
+pub mod paypal;
+pub mod sftp_drop;
 pub mod stripe;
+pub mod worldpay;
@@ -0,0 +1,405 @@
+//! PayPal `SettlementReportSource`.
+//!
+//! Port of PayPal's daily **Settlement Report** (STL) onto the canonical [`SettledFeeRow`]. The STL
+//! file is the one PayPal report that lists every balance-affecting transaction with its gross and
+//! the fee PayPal took, so it is the one report we ingest for PayPal. Everything PayPal-specific —
+//! the row-type framing, the column labels, the minor-unit amounts, the event-code filter — is
+//! contained in this file.
+//!
+//! Fee model. PayPal charges a single blended fee per transaction and the report itemizes nothing
+//! beneath it, so:
+//!   * `commission`  ← `Fee Amount`
+//!   * `interchange` / `scheme_fee` / `markup` ← 0.0
+//!
+//! and `total_fee = commission`. `gross = Gross Transaction Amount`, which is the payment value
+//! before the fee. A PayPal payment carries no card, so every row is one `paypal` cluster: no
+//! network, funding, issuer, interchange category or BIN.
+//!
+//! Amounts. STL amounts are integers in the currency's minor unit (`1000` is `10.00 USD`) with the
+//! sign carried separately in the `… Debit or Credit` columns (`CR` / `DR`). [`minor_unit_divisor`]
+//! holds the currencies PayPal treats as zero-decimal.
+//!
+//! Report framing. Every STL line starts with a row-type field: `RH`/`FH`/`SH` headers, `CH` column
+//! headers (repeated once per section), `SB` section body rows, and `SF`/`SC`/`RF`/`RC`/`FF`
+//! footers and counts. [`EnvelopeReader`] keeps the first `CH` line and every `SB` line with the
+//! type field cut off, so [`csv_reader::parse`] sees a plain header + data stream.
+//!
+//! Delivery. PayPal drops STL files by SFTP; the poller lists them where the SFTP server lands them
+//! (see [`sftp_drop`]). There is no report-ready webhook.
+
+use std::io::{BufReader, Read};
+
+use async_trait::async_trait;
+use axum::http::HeaderMap;
+use bytes::Bytes;
+use masking::Secret;
+
+use crate::cost_ingestion::connectors::csv_reader;
+use crate::cost_ingestion::connectors::sftp_drop::{self, DropLocation, ReportFiles};
+use crate::cost_ingestion::source::SettlementReportSource;
+use crate::cost_ingestion::types::{
+    ConnectorCreds, IngestError, ReadyReport, ReportNotification, RowKind, SettledFeeRow,
+};
+
+/// STL files are named `STL-yyyymmdd.sequence.version.CSV`.
+const REPORT_FILES: ReportFiles = ReportFiles {
+    name_prefix: "STL-",
+    name_suffix: ".csv",
+};
+
+/// Event codes of payments received — the `T00xx` family. Refunds and reversals (`T11xx`),
+/// chargebacks (`T12xx`), transfers and holds would distort the gross→fee regression, so they're
+/// skipped (mirrors Braintree's transaction-type filter).
+const PAYMENT_EVENT_PREFIX: &str = "T00";
+
+pub struct PaypalReportSource;
+
+impl PaypalReportSource {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl Default for PaypalReportSource {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl SettlementReportSource for PaypalReportSource {
+    fn connector(&self) -> &'static str {
+        "paypal"
+    }
+
+    fn peek_account(&self, _raw_body: &[u8]) -> Result<String, IngestError> {
+        // PayPal is pull-based (SFTP drop, no report-ready webhook), so the webhook ingress never
+        // routes here — discovery happens in the report poller via `poll_ready_reports`.
+        Err(not_implemented("peek_account"))
+    }
+
+    fn verify_and_parse_notification(
+        &self,
+        _headers: &HeaderMap,
+        _raw_body: &[u8],
+        _secret: &Secret<String>,
+    ) -> Result<ReportNotification, IngestError> {
+        Err(not_implemented("verify_and_parse_notification"))
+    }
+
+    fn is_pull(&self) -> bool {
+        true
+    }
+
+    /// List the STL files in the account's SFTP drop.
+    async fn poll_ready_reports(
+        &self,
+        creds: &ConnectorCreds,
+    ) -> Result<Vec<ReadyReport>, IngestError> {
+        let location = DropLocation::parse(self.connector(), creds)?;
+        sftp_drop::list_reports(self.connector(), &location, &REPORT_FILES).await
+    }
+
+    /// Fetch one STL file; `note.report_ref` is its key in the drop.
+    async fn download_report(
+        &self,
+        creds: &ConnectorCreds,
+        note: &ReportNotification,
+    ) -> Result<Bytes, IngestError> {
+        let location = DropLocation::parse(self.connector(), creds)?;
+        sftp_drop::download(self.connector(), &location, &note.report_ref).await
+    }
+
+    fn unwrap_envelope(&self, reader: Box<dyn Read + Send>) -> Box<dyn Read + Send> {
+        Box::new(EnvelopeReader::new(reader))
+    }
+
+    fn parse_rows(
+        &self,
+        reader: Box<dyn Read + Send>,
+        mapping: &crate::cost_ingestion::mapping::ColumnMapping,
+        on_row: &mut dyn FnMut(SettledFeeRow) -> Result<(), IngestError>,
+    ) -> Result<(), IngestError> {
+        // Resolved column indices for one report. The STL column set grows between report versions
+        // (the version is in the file name), so never index positionally.
+        struct Cols {
+            txn: usize,
+            event: usize,
+            direction: usize,
+            gross: usize,
+            currency: usize,
+            fee_direction: usize,
+            fee: usize,
+            // Optional — present in every current version, but not needed to price a row.
+            completed: Option<usize>,
+        }
+
+        let reader = self.unwrap_envelope(reader);
+
+        csv_reader::parse(
+            reader,
+            mapping,
+            |h| {
+                Ok(Cols {
+                    txn: h.require("Transaction ID")?,
+                    event: h.require("Transaction Event Code")?,
+                    direction: h.require("Transaction Debit or Credit")?,
+                    gross: h.require("Gross Transaction Amount")?,
+                    currency: h.require("Gross Transaction Currency")?,
+                    fee_direction: h.require("Fee Debit or Credit")?,
+                    fee: h.require("Fee Amount")?,
+                    completed: h.index("Transaction Completion Date"),
+                })
+            },
+            |c, row| {
+                // Keep only payments received, before any field extraction.
+                let event = row.get(c.event).trim();
+                if !event.starts_with(PAYMENT_EVENT_PREFIX)
+                    || !row.get(c.direction).trim().eq_ignore_ascii_case("CR")
+                {
+                    return Ok(None);
+                }
+
+                let currency = row.get(c.currency).trim().to_uppercase();
+                let divisor = minor_unit_divisor(&currency);
+                let gross = to_float(row.get(c.gross)) / divisor;
+                // A fee is a `DR` against the merchant; a `CR` fee (a fee refund) never prices a
+                // payment, so it counts as no fee.
+                let commission = if row.get(c.fee_direction).trim().eq_ignore_ascii_case("DR") {
+                    to_float(row.get(c.fee)) / divisor
+                } else {
+                    0.0
+                };
+                let interchange = 0.0;
+                let scheme_fee = 0.0;
+                let markup = 0.0;
+                let total_fee = interchange + scheme_fee + markup + commission;
+                let txn_date = c.completed.and_then(|i| parse_date(row.get(i)));
+
+                Ok(Some(SettledFeeRow {
+                    txn_ref: row.get(c.txn).trim().to_string(),
+                    card_network: "paypal".to_string(),
+                    variant: "paypal".to_string(),
+                    funding: String::new(),
+                    issuer_country: String::new(),
+                    currency,
+                    presentment_currency: String::new(),
+                    // A flat-fee method: no interchange category or rate.
+                    ic_category: String::new(),
+                    ic_bps: String::new(),
+                    txn_date,
+                    channel: "ecom".to_string(),
+                    gross,
+                    total_fee,
+                    interchange,
+                    scheme_fee,
+                    markup,
+                    commission,
+                    bin: String::new(),
+                    kind: RowKind::Capture,
+                }))
+            },
+            on_row,
+        )
+    }
+}
+
+/// The webhook path is unused for PayPal (pull-based); these trait methods are never called.
+fn not_implemented(method: &str) -> IngestError {
+    IngestError::MalformedNotification(format!(
+        "paypal connector: {method} is unused — paypal is pull-based (see the report poller)"
+    ))
+}
+
+/// What an STL minor-unit amount is divided by: 1 for the currencies PayPal treats as
+/// zero-decimal, 100 for the rest.
+fn minor_unit_divisor(currency: &str) -> f64 {
+    match currency {
+        "HUF" | "JPY" | "TWD" => 1.0,
+        _ => 100.0,
+    }
+}
+
+/// Parse an integer minor-unit cell; blanks/garbage become `0.0` (mirrors Adyen's `to_float`).
+fn to_float(s: &str) -> f64 {
+    s.trim().parse::<f64>().unwrap_or(0.0)
+}
+
+/// Parse the date out of an STL timestamp (`2025/03/03 10:12:01 -0800`).
+fn parse_date(s: &str) -> Option<chrono::NaiveDate> {
+    let date = s.split_whitespace().next()?;
+    chrono::NaiveDate::parse_from_str(date, "%Y/%m/%d").ok()
+}
+
+/// Streams an STL file as plain CSV: the first `CH` line becomes the header and each `SB` line a
+/// data row, both without their row-type field. Every other line (report/file/section headers,
+/// footers, counts, repeated `CH` lines) is dropped. Line-at-a-time, so memory stays flat.
+struct EnvelopeReader<R> {
+    inner: BufReader<R>,
+    line: Vec<u8>, // current kept line (row-type field already cut), being handed out
+    pos: usize,    // bytes of `line` already emitted
+    first: bool,   // strip a UTF-8 BOM on the first physical line
+    header_seen: bool,
+    eof: bool,
+}
+
+impl<R: Read> EnvelopeReader<R> {
+    fn new(inner: R) -> Self {
+        Self {
+            inner: BufReader::new(inner),
+            line: Vec::new(),
+            pos: 0,
+            first: true,
+            header_seen: false,
+            eof: false,
+        }
+    }
+}
+
+/// Split an STL line into its row type (`"CH"`, `"SB"`, … with any quotes removed) and the rest
+/// of the line after the type field's comma.
+fn split_row_type(line: &[u8]) -> (&[u8], &[u8]) {
+    let comma = line.iter().position(|b| *b == b',').unwrap_or(line.len());
+    let kind = line[..comma].trim_ascii();
+    let kind = kind
+        .strip_prefix(b"\"")
+        .and_then(|k| k.strip_suffix(b"\""))
+        .unwrap_or(kind);
+    let rest = line.get(comma + 1..).unwrap_or(&[]);
+    (kind, rest)
+}
+
+impl<R: Read> Read for EnvelopeReader<R> {
+    fn read(&mut self, out: &mut [u8]) -> std::io::Result<usize> {
+        use std::io::BufRead;
+        loop {
+            // Drain the current kept line first.
+            if self.pos < self.line.len() {
+                let n = (self.line.len() - self.pos).min(out.len());
+                out[..n].copy_from_slice(&self.line[self.pos..self.pos + n]);
+                self.pos += n;
+                return Ok(n);
+            }
+            if self.eof {
+                return Ok(0);
+            }
+            // Pull the next physical line.
+            self.line.clear();
+            self.pos = 0;
+            if self.inner.read_until(b'\n', &mut self.line)? == 0 {
+                self.eof = true;
+                return Ok(0);
+            }
+            if self.first {
+                if self.line.starts_with(&[0xEF, 0xBB, 0xBF]) {
+                    self.line.drain(0..3);
+                }
+                self.first = false;
+            }
+            let keep = match split_row_type(&self.line).0 {
+                b"CH" if !self.header_seen => {
+                    self.header_seen = true;
+                    true
+                }
+                b"SB" => self.header_seen,
+                _ => false,
+            };
+            if keep {
+                let start = self.line.len() - split_row_type(&self.line).1.len();
+                self.line.drain(0..start);
+            } else {
+                // Discard the frame line so the drain check at the loop top doesn't emit it.
+                self.line.clear();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A minimal STL file: report/file/section headers, the column header, a payment, a refund, a
+    /// JPY payment, then a second section repeating the column header, and the footers. Column
+    /// order is the real report's order.
+    const REPORT: &[u8] = b"\xef\xbb\xbf\"RH\",2025/03/04 03:01:02 -0800,\"A\",\"ACMEPAYER\",009\n\
+\"FH\",01\n\
+\"SH\",2025/03/03 00:00:00 -0800,2025/03/03 23:59:59 -0800,\"ACMEPAYER\",\"\"\n\
+\"CH\",\"Transaction ID\",\"Invoice ID\",\"PayPal Reference ID\",\"PayPal Reference ID Type\",\"Transaction Event Code\",\"Transaction Initiation Date\",\"Transaction Completion Date\",\"Transaction Debit or Credit\",\"Gross Transaction Amount\",\"Gross Transaction Currency\",\"Fee Debit or Credit\",\"Fee Amount\",\"Fee Currency\",\"Custom Field\",\"Consumer ID\"\n\
+\"SB\",\"4HX12345AB678901C\",\"INV-1\",\"\",\"\",\"T0006\",\"2025/03/03 10:12:01 -0800\",\"2025/03/03 10:12:03 -0800\",\"CR\",\"12000\",\"USD\",\"DR\",\"378\",\"USD\",\"\",\"buyer@example.com\"\n\
+\"SB\",\"9JK12345AB678901D\",\"INV-0\",\"4HX00000AB678901C\",\"TXN\",\"T1107\",\"2025/03/03 11:00:00 -0800\",\"2025/03/03 11:00:00 -0800\",\"DR\",\"5000\",\"USD\",\"CR\",\"145\",\"USD\",\"\",\"\"\n\
+\"SB\",\"7LM12345AB678901E\",\"INV-2\",\"\",\"\",\"T0006\",\"2025/03/03 12:00:00 -0800\",\"2025/03/03 12:00:00 -0800\",\"CR\",\"5000\",\"JPY\",\"DR\",\"215\",\"JPY\",\"\",\"\"\n\
+\"SF\",\"USD\",\"CR\",12000,\"DR\",378\n\
+\"SC\",3\n\
+\"SH\",2025/03/03 00:00:00 -0800,2025/03/03 23:59:59 -0800,\"ACMEPAYER\",\"\"\n\
+\"CH\",\"Transaction ID\",\"Invoice ID\",\"PayPal Reference ID\",\"PayPal Reference ID Type\",\"Transaction Event Code\",\"Transaction Initiation Date\",\"Transaction Completion Date\",\"Transaction Debit or Credit\",\"Gross Transaction Amount\",\"Gross Transaction Currency\",\"Fee Debit or Credit\",\"Fee Amount\",\"Fee Currency\",\"Custom Field\",\"Consumer ID\"\n\
+\"SB\",\"2AB12345AB678901F\",\"INV-3\",\"\",\"\",\"T0007\",\"2025/03/03 13:00:00 -0800\",\"2025/03/03 13:00:00 -0800\",\"CR\",\"2500\",\"EUR\",\"DR\",\"110\",\"EUR\",\"\",\"\"\n\
+\"SF\",\"EUR\",\"CR\",2500,\"DR\",110\n\
+\"SC\",1\n\
+\"RF\",4\n\
+\"RC\",4\n\
+\"FF\",4\n";
+
+    #[test]
+    fn parses_payments_and_skips_reversals_and_framing() {
+        let rows = PaypalReportSource::new().parse_report(REPORT).unwrap();
+        assert_eq!(
+            rows.iter().map(|r| r.txn_ref.as_str()).collect::<Vec<_>>(),
+            vec![
+                "4HX12345AB678901C",
+                "7LM12345AB678901E",
+                "2AB12345AB678901F"
+            ],
+            "payments from both sections; the refund and every frame line skipped"
+        );
+        let r = &rows[0];
+        assert_eq!(r.card_network, "paypal");
+        assert_eq!(r.variant, "paypal");
+        assert_eq!(r.funding, "");
+        assert_eq!(r.currency, "USD");
+        assert_eq!(r.ic_category, "");
+        assert_eq!(r.channel, "ecom");
+        assert!((r.gross - 120.0).abs() < 1e-9, "minor units -> 120.00");
+        assert!((r.commission - 3.78).abs() < 1e-9);
+        assert_eq!(r.interchange, 0.0);
+        assert_eq!(r.scheme_fee, 0.0);
+        assert_eq!(r.markup, 0.0);
+        assert!(
+            (r.total_fee - 3.78).abs() < 1e-9,
+            "the blended fee is the whole fee"
+        );
+        assert_eq!(r.txn_date, chrono::NaiveDate::from_ymd_opt(2025, 3, 3));
+    }
+
+    #[test]
+    fn zero_decimal_currencies_are_not_scaled() {
+        let rows = PaypalReportSource::new().parse_report(REPORT).unwrap();
+        let jpy = rows.iter().find(|r| r.currency == "JPY").unwrap();
+        assert!((jpy.gross - 5000.0).abs() < 1e-9);
+        assert!((jpy.total_fee - 215.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn envelope_yields_a_plain_csv() {
+        let mut out = String::new();
+        EnvelopeReader::new(REPORT)
+            .read_to_string(&mut out)
+            .unwrap();
+        let lines: Vec<&str> = out.lines().collect();
+        assert_eq!(lines.len(), 5, "one header and four body rows: {lines:?}");
+        assert!(lines[0].starts_with("\"Transaction ID\","));
+        assert!(lines[1].starts_with("\"4HX12345AB678901C\","));
+    }
+
+    #[test]
+    fn missing_required_column_errors() {
+        let report =
+            b"\"CH\",\"Transaction ID\",\"Transaction Event Code\"\n\"SB\",\"x\",\"T0006\"\n";
+        let err = PaypalReportSource::new().parse_report(report).unwrap_err();
+        let IngestError::MissingColumns { missing, .. } = err else {
+            panic!("expected MissingColumns, got {err:?}");
+        };
+        assert!(missing.contains(&"Fee Amount".to_string()));
+        assert!(!missing.contains(&"Transaction ID".to_string()));
+    }
+}
@@ -0,0 +1,312 @@
+//! Shared pull transport for connectors that deliver settlement reports by **SFTP**.
+//!
+//! Worldpay and PayPal push their settlement files to an SFTP server rather than exposing them on a
+//! reporting API. We don't speak SFTP ourselves: the server we point them at lands each upload in a
+//! bucket (AWS Transfer Family, or an S3-compatible store such as MinIO behind any SFTP front), and
+//! the connector's `poll_ready_reports` lists that bucket. So a connector here only decides which
+//! files are its reports ([`ReportFiles`]); listing, period extraction and download live once below.
+//!
+//! The bucket location rides in the opaque [`ConnectorCreds::download_auth`] as a JSON blob (see
+//! [`DropLocation`]). Access uses the default AWS credential chain of the ingest deployment, like the
+//! sample-report fetch in `routes::report_upload`. The connector-agnostic drop-folder poller
+//! (`cost_ingestion::drop_folder`) reads its bucket locations through the same functions.
+
+use aws_config::BehaviorVersion;
+use aws_sdk_s3::primitives::ByteStream;
+use bytes::Bytes;
+use chrono::NaiveDate;
+use masking::PeekInterface;
+use serde::Deserialize;
+
+use crate::cost_ingestion::types::{ConnectorCreds, IngestError, ReadyReport};
+
+/// Cap on listing pages followed per poll — a backstop against a runaway continuation token.
+const MAX_LIST_PAGES: usize = 100;
+
+/// Where one account's SFTP uploads land.
+#[derive(Debug, Clone, Deserialize)]
+pub struct DropLocation {
+    pub bucket: String,
+    /// Key prefix the account's uploads land under (the SFTP user's home directory), e.g.
+    /// `"worldpay/acme/"`. Empty lists the whole bucket.
+    #[serde(default)]
+    pub prefix: String,
+    #[serde(default)]
+    pub region: Option<String>,
+    /// S3-compatible endpoint (MinIO, a local stub). Unset uses AWS S3.
+    #[serde(default)]
+    pub endpoint_url: Option<String>,
+}
+
+impl DropLocation {
+    /// Read the JSON location out of `download_auth`.
+    pub fn parse(connector: &str, creds: &ConnectorCreds) -> Result<Self, IngestError> {
+        serde_json::from_str(creds.download_auth.peek()).map_err(|e| {
+            IngestError::MalformedNotification(format!(
+                "{connector} download_auth must be a JSON drop location \
+                 ({{\"bucket\":…,\"prefix\":…}}): {e}"
+            ))
+        })
+    }
+
+    async fn client(&self) -> aws_sdk_s3::Client {
+        let mut loader = aws_config::defaults(BehaviorVersion::latest());
+        if let Some(region) = &self.region {
+            loader = loader.region(aws_config::Region::new(region.clone()));
+        }
+        let sdk_config = loader.load().await;
+        let mut config = aws_sdk_s3::config::Builder::from(&sdk_config);
+        if let Some(endpoint) = &self.endpoint_url {
+            // S3-compatible stores are addressed by path, not by bucket subdomain.
+            config = config.endpoint_url(endpoint).force_path_style(true);
+        }
+        aws_sdk_s3::Client::from_conf(config.build())
+    }
+}
+
+/// Which files under a drop location are a connector's settlement reports.
+pub struct ReportFiles {
+    /// Case-insensitive file-name prefix, e.g. PayPal's `"STL-"`. Empty accepts any name.
+    pub name_prefix: &'static str,
+    /// Case-insensitive file-name suffix, e.g. `".csv"`.
+    pub name_suffix: &'static str,
+}
+
+impl ReportFiles {
+    /// The [`ReadyReport`] for an object key, or `None` when it isn't one of this connector's
+    /// reports. The key is the report id — SFTP deliveries are named uniquely per file, so
+    /// re-listing a file already enqueued is a no-op — and the report's date is read from the
+    /// first `YYYYMMDD` run in its name.
+    pub fn ready_report(&self, key: &str) -> Option<ReadyReport> {
+        let name = key.rsplit('/').next().unwrap_or(key).to_lowercase();
+        if !name.starts_with(&self.name_prefix.to_lowercase())
+            || !name.ends_with(&self.name_suffix.to_lowercase())
+        {
+            return None;
+        }
+        let date = date_in_name(&name);
+        Some(ReadyReport {
+            report_id: key.to_string(),
+            report_ref: key.to_string(),
+            period_start: date,
+            period_end: date,
+        })
+    }
+}
+
+/// The first eight-digit run in a file name that is a valid `YYYYMMDD` date.
+fn date_in_name(name: &str) -> Option<NaiveDate> {
+    let bytes = name.as_bytes();
+    (0..bytes.len().saturating_sub(7))
+        .filter(|&i| bytes[i..i + 8].iter().all(u8::is_ascii_digit))
+        .filter(|&i| i == 0 || !bytes[i - 1].is_ascii_digit())
+        .find_map(|i| NaiveDate::parse_from_str(&name[i..i + 8], "%Y%m%d").ok())
+}
+
+/// One object under a drop location.
+#[derive(Debug, Clone)]
+pub struct DropObject {
+    pub key: String,
+    pub size: i64,
+    /// The store's content tag; changes whenever the object is rewritten.
+    pub e_tag: String,
+}
+
+/// Every object under `location`.
+pub async fn list_objects(
+    connector: &str,
+    location: &DropLocation,
+) -> Result<Vec<DropObject>, IngestError> {
+    let client = location.client().await;
+    let mut out = Vec::new();
+    let mut token: Option<String> = None;
+    for _ in 0..MAX_LIST_PAGES {
+        let page = client
+            .list_objects_v2()
+            .bucket(&location.bucket)
+            .prefix(&location.prefix)
+            .set_continuation_token(token.take())
+            .send()
+            .await
+            .map_err(|e| {
+                IngestError::Download(format!(
+                    "{connector} drop listing s3://{}/{}: {e}",
+                    location.bucket, location.prefix
+                ))
+            })?;
+        out.extend(page.contents().iter().filter_map(|object| {
+            Some(DropObject {
+                key: object.key()?.to_string(),
+                size: object.size().unwrap_or(0),
+                e_tag: object.e_tag().unwrap_or_default().to_string(),
+            })
+        }));
+        match page.next_continuation_token() {
+            Some(next) if page.is_truncated().unwrap_or(false) => token = Some(next.to_string()),
+            _ => break,
+        }
+    }
+    Ok(out)
+}
+
+/// Every report under `location` that `files` accepts.
+pub async fn list_reports(
+    connector: &str,
+    location: &DropLocation,
+    files: &ReportFiles,
+) -> Result<Vec<ReadyReport>, IngestError> {
+    Ok(list_objects(connector, location)
+        .await?
+        .iter()
+        .filter_map(|object| files.ready_report(&object.key))
+        .collect())
+}
+
+/// Open one object as a stream, for callers that read it in chunks rather than buffer it.
+pub async fn open(
+    connector: &str,
+    location: &DropLocation,
+    key: &str,
+) -> Result<ByteStream, IngestError> {
+    let output = location
+        .client()
+        .await
+        .get_object()
+        .bucket(&location.bucket)
+        .key(key)
+        .send()
+        .await
+        .map_err(|e| {
+            IngestError::Download(format!(
+                "{connector} drop download s3://{}/{key}: {e}",
+                location.bucket
+            ))
+        })?;
+    Ok(output.body)
+}
+
+/// Fetch one report file by the key [`list_reports`] returned as its `report_ref`.
+pub async fn download(
+    connector: &str,
+    location: &DropLocation,
+    key: &str,
+) -> Result<Bytes, IngestError> {
+    let body = open(connector, location, key)
+        .await?
+        .collect()
+        .await
+        .map_err(|e| IngestError::Download(format!("{connector} drop body: {e}")))?;
+    Ok(body.into_bytes())
+}
+
+/// Write one object under `location`, replacing any object already at `key`. The report archive
+/// (`cost_ingestion::archive`) keeps its S3-compatible copies through this.
+pub async fn upload(
+    label: &str,
+    location: &DropLocation,
+    key: &str,
+    body: ByteStream,
+) -> Result<(), IngestError> {
+    location
+        .client()
+        .await
+        .put_object()
+        .bucket(&location.bucket)
+        .key(key)
+        .body(body)
+        .send()
+        .await
+        .map_err(|e| {
+            IngestError::Storage(format!(
+                "{label} upload s3://{}/{key}: {e}",
+                location.bucket
+            ))
+        })?;
+    Ok(())
+}
+
+/// Whether an object exists at `key`.
+pub async fn exists(label: &str, location: &DropLocation, key: &str) -> Result<bool, IngestError> {
+    match location
+        .client()
+        .await
+        .head_object()
+        .bucket(&location.bucket)
+        .key(key)
+        .send()
+        .await
+    {
+        Ok(_) => Ok(true),
+        Err(e) if e.as_service_error().is_some_and(|e| e.is_not_found()) => Ok(false),
+        Err(e) => Err(IngestError::Storage(format!(
+            "{label} head s3://{}/{key}: {e}",
+            location.bucket
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const STL: ReportFiles = ReportFiles {
+        name_prefix: "STL-",
+        name_suffix: ".csv",
+    };
+
+    #[test]
+    fn matches_report_files_and_reads_their_date() {
+        let r = STL
+            .ready_report("paypal/acme/STL-20250303.01.009.CSV")
+            .expect("a settlement report");
+        assert_eq!(r.report_id, "paypal/acme/STL-20250303.01.009.CSV");
+        assert_eq!(r.report_ref, r.report_id);
+        assert_eq!(r.period_start, NaiveDate::from_ymd_opt(2025, 3, 3));
+        assert_eq!(r.period_end, r.period_start);
+
+        assert!(STL
+            .ready_report("paypal/acme/TRR-20250303.01.009.CSV")
+            .is_none());
+        assert!(STL
+            .ready_report("paypal/acme/STL-20250303.01.009.CSV.pgp")
+            .is_none());
+        assert!(
+            STL.ready_report("STL-20250303/readme.csv").is_none(),
+            "only the file name is matched"
+        );
+    }
+
+    #[test]
+    fn date_is_the_first_valid_eight_digit_run() {
+        assert_eq!(
+            date_in_name("settlement_123456789_20250131.csv"),
+            NaiveDate::from_ymd_opt(2025, 1, 31)
+        );
+        assert_eq!(date_in_name("settlement_20251399.csv"), None);
+        assert_eq!(date_in_name("settlement.csv"), None);
+    }
+
+    #[test]
+    fn location_parses_from_download_auth() {
+        let creds = ConnectorCreds {
+            webhook_secret: masking::Secret::new(String::new()),
+            download_auth: masking::Secret::new(
+                r#"{"bucket":"sftp-drop","prefix":"paypal/acme/","endpoint_url":"http://localhost:9000"}"#
+                    .to_string(),
+            ),
+        };
+        let location = DropLocation::parse("paypal", &creds).unwrap();
+        assert_eq!(location.bucket, "sftp-drop");
+        assert_eq!(location.prefix, "paypal/acme/");
+        assert_eq!(location.region, None);
+
+        let bad = ConnectorCreds {
+            webhook_secret: masking::Secret::new(String::new()),
+            download_auth: masking::Secret::new("user:password".to_string()),
+        };
+        assert!(matches!(
+            DropLocation::parse("paypal", &bad),
+            Err(IngestError::MalformedNotification(_))
+        ));
+    }
+}
@@ -49,14 +49,20 @@ use masking::Secret;
 use crate::cost_ingestion::connectors::csv_reader;
 use crate::cost_ingestion::source::SettlementReportSource;
 use crate::cost_ingestion::types::{
-    ConnectorCreds, IngestError, ReportNotification, SettledFeeRow,
+    ConnectorCreds, IngestError, ReportNotification, RowKind, SettledFeeRow,
 };
 
-/// `Refund Flag` value for forward (settled) fees. `Refund` rows are fee reversals whose amounts
-/// would pollute the gross→fee relationship, so they're skipped (mirrors Adyen's record-type
-/// filter and Braintree's sale-only filter).
+/// `Refund Flag` value for forward (settled) fees. `Refund` lines are fees charged on refunded
+/// turnover, whose amounts would pollute the gross→fee relationship, so they become
+/// [`RowKind::Refund`] rows rather than fit input (mirrors Adyen's record-type split).
 const SETTLE_FLAG: &str = "settle";
 
+/// `Refund Flag` value for fee lines booked against refunds.
+const REFUND_FLAG: &str = "refund";
+
+/// `Fee Name` fragments marking a settled line as a dispute fee rather than a processing fee.
+const DISPUTE_FEE_NAMES: [&str; 2] = ["dispute", "chargeback"];
+
 #[allow(dead_code)] // used once download_report is implemented
 const DOWNLOAD_TIMEOUT: Duration = Duration::from_secs(120);
 
@@ -162,10 +168,20 @@ impl SettlementReportSource for StripeReportSource {
             let get = |i: usize| record.get(i).unwrap_or("");
             let get_opt = |i: Option<usize>| i.map(get).unwrap_or("");
 
-            // Keep only forward (settled) fees; skip refund/reversal lines.
-            if get(c_refund_flag).trim().to_lowercase() != SETTLE_FLAG {
-                continue;
-            }
+            // Forward (settled) fees are captures unless the fee line is a dispute fee; refund lines
+            // are refunds; anything else (reversal adjustments) is skipped.
+            let kind = match get(c_refund_flag).trim().to_lowercase().as_str() {
+                SETTLE_FLAG => {
+                    let fee_name = get_opt(c_fee_name).to_lowercase();
+                    if DISPUTE_FEE_NAMES.iter().any(|n| fee_name.contains(n)) {
+                        RowKind::Dispute
+                    } else {
+                        RowKind::Capture
+                    }
+                }
+                REFUND_FLAG => RowKind::Refund,
+                _ => continue,
+            };
 
             let gross = to_float(get(c_gross));
             let total_fee = to_float(get(c_cost));
@@ -204,6 +220,7 @@ impl SettlementReportSource for StripeReportSource {
                 funding,
                 issuer_country: String::new(),
                 currency,
+                presentment_currency: String::new(),
                 ic_category: String::new(),
                 // Stripe's blended report has no interchange-rate line — one cluster.
                 ic_bps: String::new(),
@@ -220,6 +237,7 @@ impl SettlementReportSource for StripeReportSource {
                 commission: 0.0,
                 // Stripe's aggregated report carries no PAN, so no BIN observation.
                 bin: String::new(),
+                kind,
             })?;
         }
         Ok(())
@@ -294,7 +312,7 @@ mod tests {
     use super::*;
 
     #[test]
-    fn parses_settled_lines_and_skips_refunds() {
+    fn parses_settled_lines_and_splits_out_refunds() {
         // Header order intentionally not the code's order — indices resolve by label.
         let csv = "\
 Company,Merchant,Card Brand,Shopper Interaction,Regionality,Funding Source,Payment Method Variant,Fee Name,Fee,Count,Gross Ccy,Gross Qty,Cost Ccy,Cost Qty,Month,Lookup String,Refund Flag,Fixed Fee Ccy,Fixed Fee,Fixed Fee in USD,Variable Fee,USD Amount,USD Fee,Fixed Amount,Variable Amount\n\
@@ -303,8 +321,14 @@ Merchant-A,Merchant-A-Stripe-US,mc,Ecommerce,DOMESTIC,CREDIT,mccommercialcredit,
         let rows = StripeReportSource::new()
             .parse_report(csv.as_bytes())
             .unwrap();
-        assert_eq!(rows.len(), 1, "only the Settle row is kept");
+        assert_eq!(rows.len(), 2);
+        assert_eq!(
+            rows[1].kind,
+            RowKind::Refund,
+            "the Refund line is not fit input"
+        );
         let r = &rows[0];
+        assert_eq!(r.kind, RowKind::Capture);
         assert_eq!(r.card_network, "mc");
         assert_eq!(r.variant, "maestro");
         assert_eq!(r.funding, "debit");
@@ -339,6 +363,20 @@ mc,Ecommerce,DOMESTIC,CREDIT,mccommercialcredit,Scheme Fee,100000,150,USD,USD,20
         );
     }
 
+    #[test]
+    fn dispute_fee_lines_are_dispute_rows() {
+        let csv = "\
+Card Brand,Shopper Interaction,Regionality,Funding Source,Payment Method Variant,Fee Name,Gross Qty,Cost Qty,Gross Ccy,Cost Ccy,Month,Refund Flag,Fixed Fee,Variable Fee\n\
+visa,Ecommerce,DOMESTIC,CREDIT,visacredit,Interchange,100000,1500,EUR,EUR,2025/01,Settle,0.00,0.015\n\
+visa,Ecommerce,DOMESTIC,CREDIT,visacredit,Dispute Fee,0,30,EUR,EUR,2025/01,Settle,15.00,0\n";
+        let rows = StripeReportSource::new()
+            .parse_report(csv.as_bytes())
+            .unwrap();
+        assert_eq!(rows[0].kind, RowKind::Capture);
+        assert_eq!(rows[1].kind, RowKind::Dispute);
+        assert!((rows[1].total_fee - 30.0).abs() < 1e-9);
+    }
+
     #[test]
     fn missing_required_column_errors() {
         let csv = "Card Brand,Shopper Interaction\nmc,Ecommerce\n";
@@ -0,0 +1,406 @@
+//! Worldpay `SettlementReportSource`.
+//!
+//! Port of Worldpay's transaction-level **Settlement Detail** report onto the canonical
+//! [`SettledFeeRow`]. It is the Worldpay report that prices every settled transaction on an
+//! interchange++ basis — interchange, scheme fees and Worldpay's own charges on separate columns —
+//! alongside the card dimensions the fit keys on, so it is the one report we ingest for Worldpay.
+//! Everything Worldpay-specific — the column labels, payment-method codes, the fee decomposition —
+//! is contained in this file.
+//!
+//! Fee model. The report splits the pass-through from Worldpay's take, so the decomposition
+//! mirrors Adyen's:
+//!   * `interchange` ← `Interchange Fee`
+//!   * `scheme_fee`  ← `Scheme Fee`
+//!   * `markup`      ← `Acquirer Markup` (Worldpay's percentage margin over interchange++)
+//!   * `commission`  ← `Processing Fee` (Worldpay's per-transaction fee; 0 when the column is absent)
+//!
+//! and `total_fee = interchange + scheme_fee + markup + commission`, which the report's
+//! `Total Fees` column equals. `gross = Gross Amount`, the settled transaction value the fees are
+//! charged on (the report's `Net Amount` is that less the fees). Fees are stated as positive charges;
+//! exports that sign them as deductions are read by magnitude.
+//!
+//! The report also carries the published interchange rate (`Interchange Rate`, a percentage), the
+//! card BIN and the acceptance channel, so Worldpay rows seed the per-BIN card-product map and the
+//! POS/online predictor like Adyen's do.
+//!
+//! Delivery. Worldpay drops settlement files by SFTP; the poller lists them where the SFTP server
+//! lands them (see [`sftp_drop`]). There is no report-ready webhook.
+
+use std::io::Read;
+
+use async_trait::async_trait;
+use axum::http::HeaderMap;
+use bytes::Bytes;
+use masking::Secret;
+
+use crate::cost_ingestion::connectors::csv_reader;
+use crate::cost_ingestion::connectors::sftp_drop::{self, DropLocation, ReportFiles};
+use crate::cost_ingestion::source::SettlementReportSource;
+use crate::cost_ingestion::types::{
+    ConnectorCreds, IngestError, ReadyReport, ReportNotification, RowKind, SettledFeeRow,
+};
+
+/// Worldpay names delivered files per merchant configuration, so any CSV in the account's drop is
+/// taken as a settlement report; a file that isn't one fails its parse on the missing columns.
+const REPORT_FILES: ReportFiles = ReportFiles {
+    name_prefix: "",
+    name_suffix: ".csv",
+};
+
+/// `Transaction Type` values that carry a settled processing fee. `REFUNDED` / `CHARGED_BACK` /
+/// adjustment rows are reversals whose signed amounts would distort the gross→fee regression, so
+/// they're skipped (mirrors Adyen's record-type filter).
+const FEE_TRANSACTION_TYPES: [&str; 2] = ["settled", "sale"];
+
+pub struct WorldpayReportSource;
+
+impl WorldpayReportSource {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl Default for WorldpayReportSource {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl SettlementReportSource for WorldpayReportSource {
+    fn connector(&self) -> &'static str {
+        "worldpay"
+    }
+
+    fn peek_account(&self, _raw_body: &[u8]) -> Result<String, IngestError> {
+        // Worldpay is pull-based (SFTP drop, no report-ready webhook), so the webhook ingress never
+        // routes here — discovery happens in the report poller via `poll_ready_reports`.
+        Err(not_implemented("peek_account"))
+    }
+
+    fn verify_and_parse_notification(
+        &self,
+        _headers: &HeaderMap,
+        _raw_body: &[u8],
+        _secret: &Secret<String>,
+    ) -> Result<ReportNotification, IngestError> {
+        Err(not_implemented("verify_and_parse_notification"))
+    }
+
+    fn is_pull(&self) -> bool {
+        true
+    }
+
+    /// List the settlement files in the account's SFTP drop.
+    async fn poll_ready_reports(
+        &self,
+        creds: &ConnectorCreds,
+    ) -> Result<Vec<ReadyReport>, IngestError> {
+        let location = DropLocation::parse(self.connector(), creds)?;
+        sftp_drop::list_reports(self.connector(), &location, &REPORT_FILES).await
+    }
+
+    /// Fetch one settlement file; `note.report_ref` is its key in the drop.
+    async fn download_report(
+        &self,
+        creds: &ConnectorCreds,
+        note: &ReportNotification,
+    ) -> Result<Bytes, IngestError> {
+        let location = DropLocation::parse(self.connector(), creds)?;
+        sftp_drop::download(self.connector(), &location, &note.report_ref).await
+    }
+
+    fn parse_rows(
+        &self,
+        reader: Box<dyn Read + Send>,
+        mapping: &crate::cost_ingestion::mapping::ColumnMapping,
+        on_row: &mut dyn FnMut(SettledFeeRow) -> Result<(), IngestError>,
+    ) -> Result<(), IngestError> {
+        // Resolved column indices for one report. Column order follows each merchant's report
+        // template, so never index positionally.
+        struct Cols {
+            order: usize,
+            r#type: usize,
+            method: usize,
+            currency: usize,
+            gross: usize,
+            interchange: usize,
+            scheme: usize,
+            markup: usize,
+            // Optional columns — not every report template carries them. Missing ⇒ blank/0.
+            processing: Option<usize>,
+            card_type: Option<usize>,
+            product: Option<usize>,
+            issuer: Option<usize>,
+            ic_desc: Option<usize>,
+            ic_rate: Option<usize>,
+            bin: Option<usize>,
+            channel: Option<usize>,
+            settle_date: Option<usize>,
+        }
+
+        csv_reader::parse(
+            reader,
+            mapping,
+            |h| {
+                Ok(Cols {
+                    order: h.require("Order Code")?,
+                    r#type: h.require("Transaction Type")?,
+                    method: h.require("Payment Method")?,
+                    currency: h.require("Settlement Currency")?,
+                    gross: h.require("Gross Amount")?,
+                    interchange: h.require("Interchange Fee")?,
+                    scheme: h.require("Scheme Fee")?,
+                    markup: h.require("Acquirer Markup")?,
+                    processing: h.index("Processing Fee"),
+                    card_type: h.index("Card Type"),
+                    product: h.index("Card Product"),
+                    issuer: h.index("Issuer Country"),
+                    ic_desc: h.index("Interchange Category"),
+                    ic_rate: h.index("Interchange Rate"),
+                    bin: h.index("Card BIN"),
+                    channel: h.index("Channel"),
+                    settle_date: h.index("Settlement Date"),
+                })
+            },
+            |c, row| {
+                // Keep only settled sales; skip refunds/chargebacks/adjustments before any field
+                // extraction.
+                if !FEE_TRANSACTION_TYPES
+                    .contains(&row.get(c.r#type).trim().to_lowercase().as_str())
+                {
+                    return Ok(None);
+                }
+
+                let interchange = to_float(row.get(c.interchange)).abs();
+                let scheme_fee = to_float(row.get(c.scheme)).abs();
+                let markup = to_float(row.get(c.markup)).abs();
+                let commission = to_float(row.get_opt(c.processing)).abs();
+                let total_fee = interchange + scheme_fee + markup + commission;
+                let gross = to_float(row.get(c.gross));
+
+                let network = normalize_network(row.get(c.method));
+                let ic_bps = parse_rate_bps(row.get_opt(c.ic_rate));
+                // Worldpay states funding when the template carries `Card Type`; otherwise infer it
+                // from the published interchange rate like the co-badged Adyen path.
+                let funding = match funding_from_card_type(row.get_opt(c.card_type)) {
+                    f if !f.is_empty() => f,
+                    _ => SettledFeeRow::resolve_funding("", ic_bps),
+                };
+                let variant = build_variant(&network, row.get_opt(c.product), &funding);
+                let channel = if is_pos(row.get_opt(c.channel)) {
+                    "pos"
+                } else {
+                    "ecom"
+                }
+                .to_string();
+                let txn_date = c.settle_date.and_then(|i| parse_date(row.get(i)));
+
+                Ok(Some(SettledFeeRow {
+                    txn_ref: row.get(c.order).trim().to_string(),
+                    card_network: network,
+                    variant,
+                    funding,
+                    issuer_country: row.get_opt(c.issuer).trim().to_uppercase(),
+                    currency: row.get(c.currency).trim().to_uppercase(),
+                    presentment_currency: String::new(),
+                    ic_category: row.get_opt(c.ic_desc).trim().to_string(),
+                    ic_bps: SettledFeeRow::ic_bps_key(ic_bps),
+                    txn_date,
+                    channel,
+                    gross,
+                    total_fee,
+                    interchange,
+                    scheme_fee,
+                    markup,
+                    commission,
+                    bin: SettledFeeRow::bin_from_pan(row.get_opt(c.bin)),
+                    kind: RowKind::Capture,
+                }))
+            },
+            on_row,
+        )
+    }
+}
+
+/// The webhook path is unused for Worldpay (pull-based); these trait methods are never called.
+fn not_implemented(method: &str) -> IngestError {
+    IngestError::MalformedNotification(format!(
+        "worldpay connector: {method} is unused — worldpay is pull-based (see the report poller)"
+    ))
+}
+
+/// Canonicalize a Worldpay payment-method code (`VISA-SSL`, `ECMC-SSL`, `AMEX-SSL`, …) to the
+/// lowercased network ids the rest of the pipeline uses (matching Adyen: `mc`, `visa`, `amex`, …).
+/// The `-SSL` channel suffix and a debit marker in the code (`VISA_DEBIT-SSL`) don't name a
+/// network, so they're dropped.
+fn normalize_network(code: &str) -> String {
+    let code = code.trim().to_uppercase();
+    let code = code.split('-').next().unwrap_or_default();
+    let code = code.strip_suffix("_DEBIT").unwrap_or(code);
+    match code {
+        "" => String::new(),
+        "ECMC" | "MASTERCARD" => "mc".to_string(),
+        "VISA" | "VISA_ELECTRON" => "visa".to_string(),
+        "MAESTRO" => "maestro".to_string(),
+        "AMEX" => "amex".to_string(),
+        "DINERS" => "diners".to_string(),
+        "DISCOVER" => "discover".to_string(),
+        "JCB" => "jcb".to_string(),
+        "CB" | "CARTEBLEUE" => "cartebancaire".to_string(),
+        other => other.to_lowercase().replace('_', ""),
+    }
+}
+
+/// Map Worldpay's `Card Type` (`Credit` / `Debit` / `Prepaid` / blank) onto the canonical funding
+/// bucket. Prepaid cards are priced on the debit schedule, so they share its bucket.
+fn funding_from_card_type(card_type: &str) -> String {
+    match card_type.trim().to_lowercase().as_str() {
+        "debit" | "prepaid" => "debit".to_string(),
+        "credit" => "credit".to_string(),
+        _ => String::new(),
+    }
+}
+
+/// Synthesize a `variant` cluster key, `{network}{product}{funding}` (e.g. `visacommercialcredit`)
+/// — the closest Worldpay gets to Adyen's tiered `visastandarddebit`. `Card Product` is only kept
+/// when it names a priced tier (consumer cards are the default and add nothing).
+fn build_variant(network: &str, product: &str, funding: &str) -> String {
+    let product = match product.trim().to_lowercase().as_str() {
+        "commercial" | "corporate" | "business" | "purchasing" => "commercial",
+        "premium" | "super premium" => "premium",
+        _ => "",
+    };
+    format!("{network}{product}{funding}")
+}
+
+/// Whether a `Channel` cell marks in-person acceptance. Absent/blank ⇒ online (ecom).
+fn is_pos(channel: &str) -> bool {
+    matches!(
+        channel.trim().to_lowercase().as_str(),
+        "pos" | "cardholder present" | "cp" | "terminal"
+    )
+}
+
+/// A percentage cell (`1.50%` or `1.5`) as bps, or `None` when blank/garbage.
+fn parse_rate_bps(s: &str) -> Option<f64> {
+    let s = s.trim().trim_end_matches('%').trim();
+    s.parse::<f64>().ok().map(|pct| pct * 100.0)
+}
+
+/// Parse a money cell; blanks/garbage become `0.0` (mirrors Adyen's `to_float`).
+fn to_float(s: &str) -> f64 {
+    s.trim().parse::<f64>().unwrap_or(0.0)
+}
+
+/// Parse a Worldpay date cell — ISO `YYYY-MM-DD` or the UK `DD/MM/YYYY` — ignoring any time part.
+fn parse_date(s: &str) -> Option<chrono::NaiveDate> {
+    let date = s.split_whitespace().next()?;
+    chrono::NaiveDate::parse_from_str(date, "%Y-%m-%d")
+        .or_else(|_| chrono::NaiveDate::parse_from_str(date, "%d/%m/%Y"))
+        .ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A settled Visa commercial card sale at POS, a refund, and a Mastercard debit e-commerce sale.
+    const REPORT: &str = "\
+Merchant Code,Order Code,Transaction Type,Settlement Date,Payment Method,Card BIN,Card Type,Card Product,Issuer Country,Channel,Settlement Currency,Gross Amount,Interchange Category,Interchange Rate,Interchange Fee,Scheme Fee,Acquirer Markup,Processing Fee,Total Fees,Net Amount\n\
+ACMEGB,ORD-1001,SETTLED,2025-03-03,VISA-SSL,41111111,Credit,Commercial,gb,POS,GBP,200.00,Visa Commercial Credit Business,1.50%,3.00,0.24,0.40,0.05,3.69,196.31\n\
+ACMEGB,ORD-1002,REFUNDED,2025-03-03,VISA-SSL,41111111,Credit,Consumer,GB,ECOM,GBP,-50.00,,,0.00,0.02,0.00,0.00,0.02,-50.02\n\
+ACMEGB,ORD-1003,SETTLED,03/03/2025,ECMC-SSL,52000000,Debit,Consumer,FR,ECOM,EUR,80.00,MC Consumer Debit EEA,0.20%,-0.16,-0.08,-0.12,0.00,0.36,79.64\n";
+
+    #[test]
+    fn parses_sales_with_full_fee_split() {
+        let rows = WorldpayReportSource::new()
+            .parse_report(REPORT.as_bytes())
+            .unwrap();
+        assert_eq!(rows.len(), 2, "the refund is skipped");
+
+        let r = &rows[0];
+        assert_eq!(r.txn_ref, "ORD-1001");
+        assert_eq!(r.card_network, "visa", "VISA-SSL -> visa");
+        assert_eq!(r.funding, "credit");
+        assert_eq!(r.variant, "visacommercialcredit");
+        assert_eq!(r.issuer_country, "GB");
+        assert_eq!(r.currency, "GBP");
+        assert_eq!(r.ic_category, "Visa Commercial Credit Business");
+        assert_eq!(r.ic_bps, "150", "1.50% -> 150 bps");
+        assert_eq!(r.channel, "pos");
+        assert_eq!(r.bin, "41111111");
+        assert!((r.interchange - 3.00).abs() < 1e-9);
+        assert!((r.scheme_fee - 0.24).abs() < 1e-9);
+        assert!((r.markup - 0.40).abs() < 1e-9);
+        assert!((r.commission - 0.05).abs() < 1e-9);
+        assert!(
+            (r.total_fee - 3.69).abs() < 1e-9,
+            "components sum to Total Fees"
+        );
+        assert!(
+            (r.gross - 200.0).abs() < 1e-9,
+            "Gross Amount is gross as-is"
+        );
+        assert_eq!(r.txn_date, chrono::NaiveDate::from_ymd_opt(2025, 3, 3));
+    }
+
+    #[test]
+    fn reads_signed_fees_by_magnitude() {
+        let rows = WorldpayReportSource::new()
+            .parse_report(REPORT.as_bytes())
+            .unwrap();
+        let r = &rows[1];
+        assert_eq!(r.card_network, "mc", "ECMC-SSL -> mc");
+        assert_eq!(r.variant, "mcdebit", "consumer adds no tier");
+        assert_eq!(r.channel, "ecom");
+        assert!((r.interchange - 0.16).abs() < 1e-9);
+        assert!((r.total_fee - 0.36).abs() < 1e-9);
+        assert_eq!(
+            r.txn_date,
+            chrono::NaiveDate::from_ymd_opt(2025, 3, 3),
+            "UK date format"
+        );
+    }
+
+    #[test]
+    fn funding_falls_back_to_the_interchange_rate() {
+        let csv = "\
+Order Code,Transaction Type,Payment Method,Settlement Currency,Gross Amount,Interchange Fee,Scheme Fee,Acquirer Markup,Interchange Rate\n\
+o1,SETTLED,CB-SSL,EUR,100.00,0.20,0.05,0.10,0.2\n";
+        let rows = WorldpayReportSource::new()
+            .parse_report(csv.as_bytes())
+            .unwrap();
+        assert_eq!(rows.len(), 1);
+        assert_eq!(rows[0].card_network, "cartebancaire");
+        assert_eq!(rows[0].funding, "debit", "20 bps -> regulated debit");
+        assert_eq!(rows[0].commission, 0.0, "absent Processing Fee -> 0");
+        assert_eq!(rows[0].bin, "");
+    }
+
+    #[test]
+    fn missing_required_column_errors() {
+        let csv = "Order Code,Transaction Type\nx,SETTLED\n";
+        let err = WorldpayReportSource::new()
+            .parse_report(csv.as_bytes())
+            .unwrap_err();
+        let IngestError::MissingColumns {
+            missing, required, ..
+        } = err
+        else {
+            panic!("expected MissingColumns, got {err:?}");
+        };
+        assert_eq!(missing.len(), required.len() - 2, "all but the two present");
+        assert!(missing.contains(&"Acquirer Markup".to_string()));
+    }
+
+    #[test]
+    fn network_and_rate_helpers() {
+        assert_eq!(normalize_network("VISA_DEBIT-SSL"), "visa");
+        assert_eq!(normalize_network("AMEX-SSL"), "amex");
+        assert_eq!(normalize_network("MAESTRO-SSL"), "maestro");
+        assert_eq!(normalize_network(""), "");
+        assert_eq!(parse_rate_bps("1.25%"), Some(125.0));
+        assert_eq!(parse_rate_bps(""), None);
+    }
+}
@@ -12,8 +12,10 @@ use masking::PeekInterface;
 use serde::Serialize;
 
 use crate::config::ClickHouseAnalyticsConfig;
+use crate::logger;
 
 use super::types::IngestError;
+use super::volume_tiers::{self, TierPosition};
 
 const TIMEOUT: Duration = Duration::from_secs(30);
 
@@ -54,6 +56,9 @@ pub struct CoverageSummary {
     pub bps_rmse_p90: f64,
     /// The snapshot these numbers are from (`YYYY-MM-DD`), for a freshness indicator.
     pub report_date: String,
+    /// Where each connector priced by a volume-tier contract stands this month — its current tier
+    /// and how much more volume reaches the next one. Empty when no contract is configured.
+    pub volume_tiers: Vec<TierPosition>,
 }
 
 // `rd` (the latest snapshot date) is bound once via WITH and reused both as a constant column and
@@ -122,7 +127,8 @@ fn client() -> &'static reqwest::Client {
     CLIENT.get_or_init(|| super::ch_http::client(TIMEOUT))
 }
 
-/// Coverage of a merchant's latest fitted snapshot across all its connectors/accounts.
+/// Coverage of a merchant's latest fitted snapshot across all its connectors/accounts, plus its
+/// standing on any volume-tier contracts.
 pub async fn for_merchant(
     cfg: &ClickHouseAnalyticsConfig,
     merchant_id: &str,
@@ -188,6 +194,19 @@ pub async fn for_merchant(
     let priced_txn_pct = pct((good_txns + tiered_txns) as f64, total_txns as f64);
     let good_gross_pct = pct(good_gross, total_gross);
     let priced_gross_pct = pct(good_gross + tiered_gross, total_gross);
+    // The tier standing is a side panel on the card: a failed read leaves it empty rather than
+    // failing the coverage numbers.
+    let volume_tiers = volume_tiers::positions(merchant_id)
+        .await
+        .unwrap_or_else(|e| {
+            logger::warn!(
+                tag = "cost_coverage",
+                "volume-tier positions failed for {}: {:?}",
+                merchant_id,
+                e
+            );
+            Vec::new()
+        });
     Ok(CoverageSummary {
         total_clusters,
         good_clusters,
@@ -219,5 +238,6 @@ pub async fn for_merchant(
             bps_rmse_p90
         },
         report_date,
+        volume_tiers,
     })
 }
@@ -433,6 +433,8 @@ mod tests {
     #[test]
     fn only_pull_connectors_are_polled() {
         assert!(is_pull_connector("chase"));
+        assert!(is_pull_connector("paypal"));
+        assert!(is_pull_connector("worldpay"));
         assert!(!is_pull_connector("adyen"));
         assert!(!is_pull_connector("braintree"));
         assert_eq!(poll_index_name("chase"), "cost_ingest_poll::chase");
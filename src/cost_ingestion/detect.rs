@@ -5,12 +5,15 @@
 //! snapshot to the next. This diffs each cluster's two most recent fits and reports the material
 //! moves — a price-change timeline for the dashboard. The two-part model means we can say *which*
 //! part moved: the percentage (`pct_bps`) or the flat fee (`fixed`). See §10.
+//!
+//! The same diff runs after every fit, at the merchant's own thresholds, to raise alerts
+//! ([`super::alerts`]).
 
 use std::sync::OnceLock;
 use std::time::Duration;
 
 use masking::PeekInterface;
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 
 use crate::config::ClickHouseAnalyticsConfig;
 
@@ -18,11 +21,11 @@ use super::types::IngestError;
 
 const TIMEOUT: Duration = Duration::from_secs(30);
 /// Report a change only if the percentage moved more than this (basis points).
-const TOL_BPS: f64 = 3.0;
+pub const TOL_BPS: f64 = 3.0;
 /// …or the flat per-transaction fee moved more than this (settlement-currency units).
-const TOL_FIXED: f64 = 0.01;
+pub const TOL_FIXED: f64 = 0.01;
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct PriceChange {
     pub connector: String,
     pub account: String,
@@ -73,14 +76,25 @@ FORMAT TSV
 pub async fn price_changes(
     cfg: &ClickHouseAnalyticsConfig,
     merchant_id: &str,
+) -> Result<Vec<PriceChange>, IngestError> {
+    price_changes_over(cfg, merchant_id, TOL_BPS, TOL_FIXED).await
+}
+
+/// [`price_changes`] at caller-chosen tolerances: a change is reported when the percentage moved
+/// more than `tol_bps` or the flat fee more than `tol_fixed`.
+pub async fn price_changes_over(
+    cfg: &ClickHouseAnalyticsConfig,
+    merchant_id: &str,
+    tol_bps: f64,
+    tol_fixed: f64,
 ) -> Result<Vec<PriceChange>, IngestError> {
     let sql = CHANGES_SQL.replace("__DB__", &cfg.database);
     let mut req = client()
         .post(cfg.url.trim_end_matches('/'))
         .query(&[
             ("param_merchant_id", merchant_id),
-            ("param_tol_bps", &TOL_BPS.to_string()),
-            ("param_tol_fixed", &TOL_FIXED.to_string()),
+            ("param_tol_bps", &tol_bps.to_string()),
+            ("param_tol_fixed", &tol_fixed.to_string()),
         ])
         .body(sql);
     if !cfg.user.is_empty() {
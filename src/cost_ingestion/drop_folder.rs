@@ -0,0 +1,493 @@
+//! Drop-folder poller — settlement files that arrive in a folder rather than by webhook or reporting
+//! API. Some acquirers only deliver by SFTP; the SFTP server lands each file in a local directory or
+//! an S3-compatible bucket (MinIO works locally), and this poller watches those locations.
+//!
+//! Each cycle it lists every configured location (`cost_ingestion.drop_folder.locations`), matches
+//! each file's path against the location's glob rules to find the merchant / connector / account it
+//! belongs to, and enqueues a `pending` job per new file (`source = "drop"`). From there it is the
+//! same path as a webhook delivery: `worker` claims the job and parses and fits the file, reading it
+//! back through [`read_report`] instead of a connector download — drop-folder files need no
+//! connector credentials.
+//!
+//! Dedupe is by **content**: the job's `notification_id` is the file's SHA-256, so the existing
+//! `(connector, notification_id)` idempotency makes a re-delivered or renamed copy of a file a
+//! no-op. Hashing reads the file, so the poller remembers each file's listing fingerprint (size +
+//! mtime, or the object's ETag) and only re-reads a file whose fingerprint changed.
+//!
+//! Modeled on `poller::spawn` — a panic-isolated interval loop that runs only where
+//! `cost_ingestion.drop_folder.enabled` is set.
+
+use std::collections::HashMap;
+use std::path::{Component, Path, PathBuf};
+use std::time::{Duration, UNIX_EPOCH};
+
+use bytes::Bytes;
+use futures::FutureExt;
+use tokio::io::AsyncReadExt;
+
+use crate::config::{DropFolderConfig, DropFolderLocation, DropFolderRule};
+use crate::logger;
+
+use super::connectors::sftp_drop::{self, DropLocation};
+use super::store;
+use super::types::IngestError;
+
+/// `source` of the jobs this poller enqueues. The worker reads their file with [`read_report`].
+pub const DROP_SOURCE: &str = "drop";
+
+/// Scheme of a drop job's `report_ref`: `drop://<location name>/<file>`.
+const REF_SCHEME: &str = "drop://";
+
+/// Label for the shared S3 transport's error messages.
+const LABEL: &str = "drop folder";
+
+/// Spawn the recurring drop-folder loop. Call once at startup after `APP_STATE` is set. A no-op
+/// unless `drop_folder.enabled` is true.
+pub fn spawn(config: DropFolderConfig) {
+    if !config.enabled {
+        logger::info!(tag = "drop_folder", "drop-folder poller disabled");
+        return;
+    }
+    let interval = Duration::from_secs(config.interval_secs.max(1));
+
+    tokio::spawn(async move {
+        logger::info!(
+            tag = "drop_folder",
+            "drop-folder poller started; {} location(s), interval {:?}",
+            config.locations.len(),
+            interval
+        );
+        let mut seen = Seen::default();
+        let mut ticker = tokio::time::interval(interval);
+        loop {
+            ticker.tick().await;
+            // Isolate each cycle so a panic doesn't kill the loop.
+            if std::panic::AssertUnwindSafe(run_once(&config, &mut seen))
+                .catch_unwind()
+                .await
+                .is_err()
+            {
+                logger::error!(
+                    tag = "drop_folder",
+                    "drop-folder cycle panicked; continuing next cycle"
+                );
+            }
+        }
+    });
+}
+
+/// What the poller remembers between cycles, keyed by `(location, file)`: the listing fingerprint
+/// of every file already enqueued (or found to be a duplicate), and of every local file waiting to
+/// settle. Lost on restart, which only costs one re-hash of each file — the enqueue stays a no-op.
+#[derive(Default)]
+struct Seen {
+    handled: HashMap<(String, String), String>,
+    settling: HashMap<(String, String), String>,
+}
+
+async fn run_once(config: &DropFolderConfig, seen: &mut Seen) {
+    for location in &config.locations {
+        if let Err(e) = poll_location(location, seen).await {
+            // One bad location (unmounted directory, bucket permissions) must not stop the others.
+            logger::warn!(
+                tag = "drop_folder",
+                "poll of drop folder {} failed: {:?}",
+                location.name,
+                e
+            );
+        }
+    }
+}
+
+/// List one location and enqueue every new file a rule claims.
+async fn poll_location(location: &DropFolderLocation, seen: &mut Seen) -> Result<(), IngestError> {
+    let root = Root::new(location)?;
+    let rules = location
+        .rules
+        .iter()
+        .map(|rule| Ok((glob_regex(&rule.glob)?, rule)))
+        .collect::<Result<Vec<_>, regex::Error>>()
+        .map_err(|e| {
+            IngestError::Download(format!("drop folder {}: bad glob: {e}", location.name))
+        })?;
+
+    let mut enqueued = 0usize;
+    for file in root.list().await? {
+        let Some(rule) = rules
+            .iter()
+            .find(|(glob, _)| glob.is_match(&file.path))
+            .map(|(_, rule)| *rule)
+        else {
+            continue;
+        };
+        let id = (location.name.clone(), file.locator.clone());
+        if seen.handled.get(&id) == Some(&file.fingerprint) {
+            continue;
+        }
+        // A local file may still be mid-upload: take it only once two consecutive listings agree on
+        // its size and mtime. An object store publishes an object whole, so S3 files go on first
+        // sight.
+        if root.is_dir()
+            && seen.settling.insert(id.clone(), file.fingerprint.clone())
+                != Some(file.fingerprint.clone())
+        {
+            continue;
+        }
+        seen.settling.remove(&id);
+
+        match enqueue(&root, location, rule, &file).await {
+            Ok(created) => {
+                enqueued += usize::from(created);
+                seen.handled.insert(id, file.fingerprint);
+            }
+            // Left unhandled, so the next cycle retries it.
+            Err(e) => logger::warn!(
+                tag = "drop_folder",
+                "{}/{}: enqueue failed: {:?}",
+                location.name,
+                file.path,
+                e
+            ),
+        }
+    }
+    if enqueued > 0 {
+        logger::info!(
+            tag = "drop_folder",
+            "{}: enqueued {} new file(s)",
+            location.name,
+            enqueued
+        );
+    }
+    Ok(())
+}
+
+/// Hash one file and enqueue it under its rule. `false` when a file with the same content was
+/// already enqueued for that connector.
+async fn enqueue(
+    root: &Root,
+    location: &DropFolderLocation,
+    rule: &DropFolderRule,
+    file: &DropFile,
+) -> Result<bool, IngestError> {
+    let hash = root.content_hash(&file.locator).await?;
+    store::enqueue_pending(
+        &rule.connector.to_lowercase(),
+        &rule.account,
+        &rule.merchant_id,
+        &format!("sha256:{hash}"),
+        &format!("{REF_SCHEME}{}/{}", location.name, file.locator),
+        DROP_SOURCE,
+    )
+    .await
+}
+
+/// Read back the file a drop job's `report_ref` names. Called by the worker in place of a
+/// connector download.
+pub async fn read_report(
+    config: &DropFolderConfig,
+    report_ref: &str,
+) -> Result<Bytes, IngestError> {
+    let (name, locator) = report_ref
+        .strip_prefix(REF_SCHEME)
+        .and_then(|rest| rest.split_once('/'))
+        .ok_or_else(|| {
+            IngestError::MalformedNotification(format!("not a drop-folder ref: {report_ref}"))
+        })?;
+    let location = config
+        .locations
+        .iter()
+        .find(|l| l.name == name)
+        .ok_or_else(|| {
+            IngestError::Download(format!("drop folder {name} is no longer configured"))
+        })?;
+    Root::new(location)?.read(locator).await
+}
+
+/// One file found in a location.
+struct DropFile {
+    /// Path relative to the location, `/`-separated — what the rules match.
+    path: String,
+    /// What the location reads the file back by: the relative path in a directory, the full key in
+    /// a bucket.
+    locator: String,
+    /// Changes whenever the file is rewritten.
+    fingerprint: String,
+}
+
+/// A location's storage.
+enum Root {
+    Dir(PathBuf),
+    Bucket(DropLocation),
+}
+
+impl Root {
+    fn new(location: &DropFolderLocation) -> Result<Self, IngestError> {
+        match (&location.path, &location.bucket) {
+            (Some(path), None) => Ok(Self::Dir(PathBuf::from(path))),
+            (None, Some(bucket)) => Ok(Self::Bucket(DropLocation {
+                bucket: bucket.clone(),
+                prefix: location.prefix.clone(),
+                region: location.region.clone(),
+                endpoint_url: location.endpoint_url.clone(),
+            })),
+            _ => Err(IngestError::Download(format!(
+                "drop folder {}: set exactly one of path / bucket",
+                location.name
+            ))),
+        }
+    }
+
+    fn is_dir(&self) -> bool {
+        matches!(self, Self::Dir(_))
+    }
+
+    async fn list(&self) -> Result<Vec<DropFile>, IngestError> {
+        match self {
+            Self::Dir(root) => list_dir(root).await,
+            Self::Bucket(location) => Ok(sftp_drop::list_objects(LABEL, location)
+                .await?
+                .into_iter()
+                .filter(|object| !object.key.ends_with('/'))
+                .map(|object| DropFile {
+                    path: object
+                        .key
+                        .strip_prefix(location.prefix.as_str())
+                        .unwrap_or(&object.key)
+                        .trim_start_matches('/')
+                        .to_string(),
+                    fingerprint: format!("{}:{}", object.size, object.e_tag),
+                    locator: object.key,
+                })
+                .collect()),
+        }
+    }
+
+    /// Hex SHA-256 of a file's content, read in chunks — settlement files can run to gigabytes.
+    async fn content_hash(&self, locator: &str) -> Result<String, IngestError> {
+        let mut digest = ring::digest::Context::new(&ring::digest::SHA256);
+        match self {
+            Self::Dir(root) => {
+                let path = dir_file(root, locator)?;
+                let mut file = tokio::fs::File::open(&path)
+                    .await
+                    .map_err(|e| read_error(locator, e))?;
+                let mut buf = vec![0u8; 64 * 1024];
+                loop {
+                    let n = file
+                        .read(&mut buf)
+                        .await
+                        .map_err(|e| read_error(locator, e))?;
+                    if n == 0 {
+                        break;
+                    }
+                    digest.update(&buf[..n]);
+                }
+            }
+            Self::Bucket(location) => {
+                let mut body = sftp_drop::open(LABEL, location, locator).await?;
+                while let Some(chunk) = body.try_next().await.map_err(|e| read_error(locator, e))? {
+                    digest.update(&chunk);
+                }
+            }
+        }
+        Ok(hex::encode(digest.finish()))
+    }
+
+    async fn read(&self, locator: &str) -> Result<Bytes, IngestError> {
+        match self {
+            Self::Dir(root) => tokio::fs::read(dir_file(root, locator)?)
+                .await
+                .map(Bytes::from)
+                .map_err(|e| read_error(locator, e)),
+            Self::Bucket(location) => {
+                // A ref only ever names a key under the location's own prefix.
+                if !locator.starts_with(location.prefix.as_str()) {
+                    return Err(IngestError::Download(format!(
+                        "{locator} is outside the drop folder"
+                    )));
+                }
+                sftp_drop::download(LABEL, location, locator).await
+            }
+        }
+    }
+}
+
+fn read_error(locator: &str, e: impl std::fmt::Display) -> IngestError {
+    IngestError::Download(format!("drop folder read {locator}: {e}"))
+}
+
+/// The file a relative path names under `root`. A ref only ever names a file under the root, so
+/// anything but plain path segments is refused.
+fn dir_file(root: &Path, relative: &str) -> Result<PathBuf, IngestError> {
+    let relative = Path::new(relative);
+    if !relative
+        .components()
+        .all(|c| matches!(c, Component::Normal(_)))
+    {
+        return Err(IngestError::Download(format!(
+            "{} is outside the drop folder",
+            relative.display()
+        )));
+    }
+    Ok(root.join(relative))
+}
+
+/// Every regular file under `root`, recursively. Symlinks are not followed.
+async fn list_dir(root: &Path) -> Result<Vec<DropFile>, IngestError> {
+    let list_error = |e: std::io::Error| {
+        IngestError::Download(format!("drop folder listing {}: {e}", root.display()))
+    };
+    let mut out = Vec::new();
+    let mut dirs = vec![root.to_path_buf()];
+    while let Some(dir) = dirs.pop() {
+        let mut entries = tokio::fs::read_dir(&dir).await.map_err(list_error)?;
+        while let Some(entry) = entries.next_entry().await.map_err(list_error)? {
+            let file_type = entry.file_type().await.map_err(list_error)?;
+            if file_type.is_dir() {
+                dirs.push(entry.path());
+                continue;
+            }
+            if !file_type.is_file() {
+                continue;
+            }
+            let path = entry.path();
+            let Some(relative) = path.strip_prefix(root).ok().and_then(relative_path) else {
+                continue;
+            };
+            let meta = entry.metadata().await.map_err(list_error)?;
+            let modified = meta
+                .modified()
+                .ok()
+                .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+                .map(|d| d.as_nanos())
+                .unwrap_or(0);
+            out.push(DropFile {
+                locator: relative.clone(),
+                path: relative,
+                fingerprint: format!("{}:{modified}", meta.len()),
+            });
+        }
+    }
+    Ok(out)
+}
+
+/// A relative path as `/`-separated UTF-8, whatever the platform separator. `None` for a non-UTF-8
+/// name, which no rule could name either.
+fn relative_path(path: &Path) -> Option<String> {
+    let segments = path
+        .components()
+        .map(|c| c.as_os_str().to_str())
+        .collect::<Option<Vec<_>>>()?;
+    Some(segments.join("/"))
+}
+
+/// Compile a rule's glob to an anchored, case-insensitive regex. `*` and `?` match within one path
+/// segment; `**` matches across segments, and `**/` also matches no directory at all.
+fn glob_regex(glob: &str) -> Result<regex::Regex, regex::Error> {
+    let mut re = String::from("(?i)^");
+    let mut chars = glob.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '*' if chars.peek() == Some(&'*') => {
+                chars.next();
+                if chars.peek() == Some(&'/') {
+                    chars.next();
+                    re.push_str("(?:.*/)?");
+                } else {
+                    re.push_str(".*");
+                }
+            }
+            '*' => re.push_str("[^/]*"),
+            '?' => re.push_str("[^/]"),
+            c => re.push_str(&regex::escape(c.encode_utf8(&mut [0u8; 4]))),
+        }
+    }
+    re.push('$');
+    regex::Regex::new(&re)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn matches(glob: &str, path: &str) -> bool {
+        glob_regex(glob).unwrap().is_match(path)
+    }
+
+    #[test]
+    fn globs_match_within_and_across_segments() {
+        assert!(matches(
+            "worldpay/*.csv",
+            "worldpay/settlement_20250301.CSV"
+        ));
+        assert!(!matches("worldpay/*.csv", "worldpay/uk/settlement.csv"));
+        assert!(matches(
+            "worldpay/**/*.csv",
+            "worldpay/uk/2025/settlement.csv"
+        ));
+        assert!(matches("worldpay/**/*.csv", "worldpay/settlement.csv"));
+        assert!(matches(
+            "paypal/STL-????????.*.csv",
+            "paypal/STL-20250303.01.009.CSV"
+        ));
+        assert!(!matches(
+            "paypal/STL-*.csv",
+            "paypal/STL-20250303.csv.filepart"
+        ));
+        // Regex metacharacters in the glob are literal.
+        assert!(matches("acme+co/(eu)/*.csv", "acme+co/(eu)/r.csv"));
+        assert!(!matches("a.csv", "abcsv"));
+    }
+
+    #[test]
+    fn refuses_paths_outside_the_root() {
+        let root = Path::new("/srv/drop");
+        assert_eq!(
+            dir_file(root, "worldpay/r.csv").unwrap(),
+            PathBuf::from("/srv/drop/worldpay/r.csv")
+        );
+        assert!(dir_file(root, "../etc/passwd").is_err());
+        assert!(dir_file(root, "/etc/passwd").is_err());
+    }
+
+    #[tokio::test]
+    async fn lists_hashes_and_reads_back_a_local_folder() {
+        let root = std::env::temp_dir().join(format!("drop_folder_test_{}", std::process::id()));
+        tokio::fs::create_dir_all(root.join("worldpay"))
+            .await
+            .unwrap();
+        tokio::fs::write(root.join("worldpay/settlement.csv"), b"a,b\n1,2\n")
+            .await
+            .unwrap();
+
+        let location = DropFolderLocation {
+            name: "local".to_string(),
+            path: Some(root.display().to_string()),
+            ..Default::default()
+        };
+        let dir = Root::new(&location).unwrap();
+        let files = dir.list().await.unwrap();
+        assert_eq!(files.len(), 1);
+        assert_eq!(files[0].path, "worldpay/settlement.csv");
+
+        let hash = dir.content_hash(&files[0].locator).await.unwrap();
+        assert_eq!(
+            hash,
+            hex::encode(ring::digest::digest(&ring::digest::SHA256, b"a,b\n1,2\n"))
+        );
+
+        let config = DropFolderConfig {
+            locations: vec![location],
+            ..Default::default()
+        };
+        let bytes = read_report(&config, "drop://local/worldpay/settlement.csv")
+            .await
+            .unwrap();
+        assert_eq!(&bytes[..], b"a,b\n1,2\n");
+        assert!(read_report(&config, "drop://gone/worldpay/settlement.csv")
+            .await
+            .is_err());
+
+        tokio::fs::remove_dir_all(&root).await.unwrap();
+    }
+}
@@ -577,42 +577,71 @@ pub async fn fit_snapshot(
     // ingest that has already written its snapshot. With no ingestion id to attribute rows to
     // (nothing staged them under one), fall back to the snapshot-wide figures rather than report a
     // misleading zero.
-    let (ingested, ingested_good) = if ingestion_id.is_empty() {
-        (total, good)
-    } else {
-        let mut p = params.to_vec();
-        p.push(("ingestion_id", ingestion_id.to_string()));
-        match exec(
-            cfg,
-            &INGESTED_SUMMARY_SQL.replace("__DB__", &cfg.database),
-            &p,
-        )
-        .await
-        {
-            Ok(text) => {
-                let mut f = text.trim().split('\t');
-                let t = f.next().and_then(|s| s.parse().ok()).unwrap_or(0);
-                let g = f.next().and_then(|s| s.parse().ok()).unwrap_or(0);
-                (t, g)
-            }
-            Err(e) => {
-                crate::logger::warn!(
-                    tag = "cost_ingestion",
-                    "per-ingestion cluster summary failed for {}: {:?}",
-                    ingestion_id,
-                    e
-                );
-                (total, good)
-            }
-        }
-    };
-
-    Ok(FitSummary {
+    let snapshot = FitSummary {
         total_clusters: total,
         good_clusters: good,
-        ingested_clusters: ingested,
-        ingested_good_clusters: ingested_good,
-    })
+        ingested_clusters: total,
+        ingested_good_clusters: good,
+    };
+    Ok(narrow_to_ingestion(
+        cfg,
+        connector,
+        account,
+        merchant_id,
+        report_date,
+        ingestion_id,
+        snapshot,
+    )
+    .await)
+}
+
+/// `snapshot` (a fit's snapshot-wide summary) with its per-ingestion counts narrowed to the clusters
+/// `ingestion_id` contributed to. A reprocessing fits once for several ingestions and narrows the
+/// one summary for each of them.
+pub async fn narrow_to_ingestion(
+    cfg: &ClickHouseAnalyticsConfig,
+    connector: &str,
+    account: &str,
+    merchant_id: &str,
+    report_date: &str,
+    ingestion_id: &str,
+    snapshot: FitSummary,
+) -> FitSummary {
+    if ingestion_id.is_empty() {
+        return snapshot;
+    }
+    let params = [
+        ("connector", connector.to_string()),
+        ("account", account.to_string()),
+        ("merchant_id", merchant_id.to_string()),
+        ("report_date", report_date.to_string()),
+        ("ingestion_id", ingestion_id.to_string()),
+    ];
+    match exec(
+        cfg,
+        &INGESTED_SUMMARY_SQL.replace("__DB__", &cfg.database),
+        &params,
+    )
+    .await
+    {
+        Ok(text) => {
+            let mut f = text.trim().split('\t');
+            FitSummary {
+                ingested_clusters: f.next().and_then(|s| s.parse().ok()).unwrap_or(0),
+                ingested_good_clusters: f.next().and_then(|s| s.parse().ok()).unwrap_or(0),
+                ..snapshot
+            }
+        }
+        Err(e) => {
+            crate::logger::warn!(
+                tag = "cost_ingestion",
+                "per-ingestion cluster summary failed for {}: {:?}",
+                ingestion_id,
+                e
+            );
+            snapshot
+        }
+    }
 }
 
 /// POST a query to ClickHouse with `{name:Type}` parameters bound as `param_<name>`.
@@ -0,0 +1,353 @@
+//! Foreign-exchange rates for the cost models.
+//!
+//! Settlement reports are fitted per **settlement** currency ([`SettledFeeRow::currency`]), but a
+//! payment is decided in its **order** currency. Without conversion a EUR-settled cluster can't
+//! price a GBP payment at all, and a fitted fixed fee stated in EUR would be read as GBP. This
+//! module holds the rates that bridge the two:
+//!
+//! * the rollup ([`super::rollup`]) re-states a row the report carries in both currencies into the
+//!   order currency, so its cluster is fitted where it will be decided;
+//! * serving ([`super::serving::lookup`]) prices a payment off a cluster (or a volume-tier contract)
+//!   in another currency when none exists in the order currency, converting the fixed fee and the
+//!   tiering amount and charging the connector's FX markup as its own cost component.
+//!
+//! Rates come from a CSV file and the `cost_fx_rates` ClickHouse table, each with the date it takes
+//! effect; a conversion uses the latest rate effective on or before its date. A pair needs loading
+//! in one direction only (the inverse is derived), and a pair with neither is triangulated through
+//! the pivot currency. The table is reloaded by [`spawn`]; readers take a [`snapshot`].
+//!
+//! [`SettledFeeRow::currency`]: super::types::SettledFeeRow::currency
+
+use std::collections::HashMap;
+use std::sync::{Arc, OnceLock, RwLock};
+use std::time::Duration;
+
+use chrono::NaiveDate;
+use masking::PeekInterface;
+
+use crate::config::{ClickHouseAnalyticsConfig, FxConfig};
+use crate::logger;
+
+const QUERY_TIMEOUT: Duration = Duration::from_secs(30);
+
+const LOAD_RATES_SQL: &str =
+    "SELECT base, quote, rate, effective_from FROM {db}.cost_fx_rates FINAL FORMAT TSV";
+
+/// One quoted rate: `rate` units of `quote` buy one unit of `base`, from `effective_from` on.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FxRate {
+    pub base: String,
+    pub quote: String,
+    pub rate: f64,
+    pub effective_from: NaiveDate,
+}
+
+/// Every loaded rate, plus what converting costs.
+#[derive(Debug, Default)]
+pub struct FxTable {
+    /// `BASE|QUOTE` → `(effective_from, rate)`, ascending by date.
+    rates: HashMap<String, Vec<(NaiveDate, f64)>>,
+    pivot: String,
+    max_age_days: i64,
+    markup_bps: f64,
+    connector_markup_bps: HashMap<String, f64>,
+}
+
+fn pair_key(base: &str, quote: &str) -> String {
+    format!("{base}|{quote}")
+}
+
+impl FxTable {
+    /// Build a table from `rates`; later entries for the same pair and date replace earlier ones.
+    /// Non-positive rates and same-currency pairs are dropped.
+    pub fn new(rates: impl IntoIterator<Item = FxRate>, cfg: &FxConfig) -> Self {
+        let mut by_pair: HashMap<String, Vec<(NaiveDate, f64)>> = HashMap::new();
+        for r in rates {
+            let (base, quote) = (r.base.trim().to_uppercase(), r.quote.trim().to_uppercase());
+            if !r.rate.is_finite() || r.rate <= 0.0 || base.is_empty() || quote.is_empty() {
+                continue;
+            }
+            if base == quote {
+                continue;
+            }
+            let quotes = by_pair.entry(pair_key(&base, &quote)).or_default();
+            match quotes.iter_mut().find(|(d, _)| *d == r.effective_from) {
+                Some(q) => q.1 = r.rate,
+                None => quotes.push((r.effective_from, r.rate)),
+            }
+        }
+        for quotes in by_pair.values_mut() {
+            quotes.sort_by_key(|(d, _)| *d);
+        }
+        Self {
+            rates: by_pair,
+            pivot: cfg.pivot_currency.trim().to_uppercase(),
+            max_age_days: cfg.max_rate_age_days,
+            markup_bps: cfg.markup_bps,
+            connector_markup_bps: cfg
+                .connector_markup_bps
+                .iter()
+                .map(|(c, bps)| (c.to_lowercase(), *bps))
+                .collect(),
+        }
+    }
+
+    /// Number of currency pairs with at least one rate.
+    pub fn pair_count(&self) -> usize {
+        self.rates.len()
+    }
+
+    /// The latest `base → quote` rate effective on `on`, if it isn't stale.
+    fn quoted(&self, base: &str, quote: &str, on: NaiveDate) -> Option<f64> {
+        let quotes = self.rates.get(&pair_key(base, quote))?;
+        let idx = quotes.partition_point(|(d, _)| *d <= on).checked_sub(1)?;
+        let (from, rate) = quotes[idx];
+        if self.max_age_days > 0 && (on - from).num_days() > self.max_age_days {
+            return None;
+        }
+        Some(rate)
+    }
+
+    /// The direct quote, or the inverse of the opposite quote.
+    fn direct(&self, from: &str, to: &str, on: NaiveDate) -> Option<f64> {
+        self.quoted(from, to, on)
+            .or_else(|| self.quoted(to, from, on).map(|r| 1.0 / r))
+    }
+
+    /// Units of `to` one unit of `from` buys on `on`: `1.0` for the same currency, else the pair's
+    /// own rate (either direction), else triangulated through the pivot. `None` when no rate covers
+    /// the date.
+    pub fn rate(&self, from: &str, to: &str, on: NaiveDate) -> Option<f64> {
+        let (from, to) = (from.trim().to_uppercase(), to.trim().to_uppercase());
+        if from == to {
+            return Some(1.0);
+        }
+        if from.is_empty() || to.is_empty() {
+            return None;
+        }
+        self.direct(&from, &to, on).or_else(|| {
+            if self.pivot.is_empty() || self.pivot == from || self.pivot == to {
+                return None;
+            }
+            Some(self.direct(&from, &self.pivot, on)? * self.direct(&self.pivot, &to, on)?)
+        })
+    }
+
+    /// `amount` of `from` expressed in `to` on `on`.
+    pub fn convert(&self, amount: f64, from: &str, to: &str, on: NaiveDate) -> Option<f64> {
+        self.rate(from, to, on).map(|r| amount * r)
+    }
+
+    /// The FX markup `connector` charges to convert, in bps of the converted amount.
+    pub fn markup_bps(&self, connector: &str) -> f64 {
+        self.connector_markup_bps
+            .get(&connector.to_lowercase())
+            .copied()
+            .unwrap_or(self.markup_bps)
+    }
+}
+
+/// Parse a rates CSV with a `base,quote,rate,effective_from` header (any column order, extra
+/// columns ignored). Dates are `YYYY-MM-DD`. Fails on the first malformed line, naming it.
+pub fn parse_csv(text: &str) -> Result<Vec<FxRate>, String> {
+    let mut lines = text
+        .lines()
+        .enumerate()
+        .filter(|(_, l)| !l.trim().is_empty());
+    let Some((_, header)) = lines.next() else {
+        return Ok(Vec::new());
+    };
+    let cols: Vec<String> = header.split(',').map(|c| c.trim().to_lowercase()).collect();
+    let col = |name: &str| {
+        cols.iter()
+            .position(|c| c == name)
+            .ok_or_else(|| format!("rates file has no '{name}' column"))
+    };
+    let (base, quote, rate, from) = (
+        col("base")?,
+        col("quote")?,
+        col("rate")?,
+        col("effective_from")?,
+    );
+    lines
+        .map(|(i, line)| {
+            let f: Vec<&str> = line.split(',').map(str::trim).collect();
+            let field = |idx: usize| f.get(idx).copied().unwrap_or("");
+            let bad = |what: &str| format!("line {}: invalid {what}", i + 1);
+            Ok(FxRate {
+                base: field(base).to_uppercase(),
+                quote: field(quote).to_uppercase(),
+                rate: field(rate).parse().map_err(|_| bad("rate"))?,
+                effective_from: NaiveDate::parse_from_str(field(from), "%Y-%m-%d")
+                    .map_err(|_| bad("effective_from"))?,
+            })
+        })
+        .collect()
+}
+
+fn cache() -> &'static RwLock<Arc<FxTable>> {
+    static CACHE: OnceLock<RwLock<Arc<FxTable>>> = OnceLock::new();
+    CACHE.get_or_init(|| RwLock::new(Arc::new(FxTable::default())))
+}
+
+/// The current rates. Empty (every cross-currency conversion misses) until the first load.
+pub fn snapshot() -> Arc<FxTable> {
+    cache()
+        .read()
+        .map(|t| t.clone())
+        .unwrap_or_else(|_| Arc::new(FxTable::default()))
+}
+
+/// Reload the rates from the ClickHouse table and the rates file, file last so it wins a day both
+/// quote. A missing table only loses its rates; an unreadable or malformed file fails the reload
+/// and keeps the previous table.
+pub async fn refresh(
+    cfg: &FxConfig,
+    clickhouse: &ClickHouseAnalyticsConfig,
+) -> Result<usize, String> {
+    let mut rates = match load_table(clickhouse).await {
+        Ok(r) => r,
+        Err(e) => {
+            logger::warn!(tag = "cost_fx", "fx table load skipped: {}", e);
+            Vec::new()
+        }
+    };
+    if !cfg.rates_file.is_empty() {
+        let text = std::fs::read_to_string(&cfg.rates_file)
+            .map_err(|e| format!("reading {}: {e}", cfg.rates_file))?;
+        rates.extend(parse_csv(&text).map_err(|e| format!("{}: {e}", cfg.rates_file))?);
+    }
+    let table = FxTable::new(rates, cfg);
+    let n = table.pair_count();
+    store(table);
+    Ok(n)
+}
+
+/// Swap in a freshly built table.
+pub(super) fn store(table: FxTable) {
+    if let Ok(mut guard) = cache().write() {
+        *guard = Arc::new(table);
+    }
+}
+
+async fn load_table(cfg: &ClickHouseAnalyticsConfig) -> Result<Vec<FxRate>, String> {
+    let sql = LOAD_RATES_SQL.replace("{db}", &cfg.database);
+    let mut req = client().post(cfg.url.trim_end_matches('/')).body(sql);
+    if !cfg.user.is_empty() {
+        req = req.basic_auth(&cfg.user, cfg.password.as_ref().map(|p| p.peek().clone()));
+    }
+    let resp = req.send().await.map_err(|e| e.to_string())?;
+    if !resp.status().is_success() {
+        let status = resp.status();
+        let text = resp.text().await.unwrap_or_default();
+        return Err(format!("clickhouse fx query failed ({status}): {text}"));
+    }
+    let text = resp.text().await.map_err(|e| e.to_string())?;
+    Ok(text
+        .lines()
+        .filter_map(|line| {
+            let f: Vec<&str> = line.split('\t').collect();
+            if f.len() < 4 {
+                return None;
+            }
+            Some(FxRate {
+                base: f[0].trim().to_string(),
+                quote: f[1].trim().to_string(),
+                rate: f[2].trim().parse().ok()?,
+                effective_from: NaiveDate::parse_from_str(f[3].trim(), "%Y-%m-%d").ok()?,
+            })
+        })
+        .collect())
+}
+
+fn client() -> &'static reqwest::Client {
+    static CLIENT: OnceLock<reqwest::Client> = OnceLock::new();
+    CLIENT.get_or_init(|| super::ch_http::client(QUERY_TIMEOUT))
+}
+
+/// Keep the rates fresh. Each reload is panic-isolated so one bad cycle can't stop the loop.
+pub fn spawn(cfg: FxConfig, clickhouse: ClickHouseAnalyticsConfig) {
+    tokio::spawn(async move {
+        let interval = Duration::from_secs(cfg.refresh_interval_secs.max(60));
+        logger::info!(
+            tag = "cost_fx",
+            "fx rate refresh started; interval {:?}",
+            interval
+        );
+        let mut ticker = tokio::time::interval(interval);
+        loop {
+            ticker.tick().await;
+            let (cfg, clickhouse) = (cfg.clone(), clickhouse.clone());
+            match tokio::spawn(async move { refresh(&cfg, &clickhouse).await }).await {
+                Ok(Ok(n)) => logger::info!(tag = "cost_fx", "loaded fx rates for {} pair(s)", n),
+                Ok(Err(e)) => logger::warn!(tag = "cost_fx", "fx refresh failed: {}", e),
+                Err(e) => logger::error!(tag = "cost_fx", "fx refresh panicked: {}", e),
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn d(s: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap()
+    }
+
+    fn table(csv: &str) -> FxTable {
+        FxTable::new(parse_csv(csv).unwrap(), &FxConfig::default())
+    }
+
+    #[test]
+    fn uses_the_latest_rate_effective_on_the_date() {
+        let t = table(
+            "base,quote,rate,effective_from\n\
+             EUR,GBP,0.85,2026-01-01\n\
+             EUR,GBP,0.86,2026-01-05\n",
+        );
+        assert_eq!(t.rate("EUR", "GBP", d("2026-01-04")), Some(0.85));
+        assert_eq!(t.rate("eur", "gbp", d("2026-01-05")), Some(0.86));
+        // Before the first rate there is nothing to use.
+        assert_eq!(t.rate("EUR", "GBP", d("2025-12-31")), None);
+        // Past the staleness window (7 days by default) the rate is no longer trusted.
+        assert_eq!(t.rate("EUR", "GBP", d("2026-01-13")), None);
+    }
+
+    #[test]
+    fn derives_inverse_and_pivot_rates() {
+        let t = table(
+            "effective_from,base,quote,rate\n\
+             2026-01-01,EUR,USD,1.10\n\
+             2026-01-01,GBP,USD,1.25\n",
+        );
+        let on = d("2026-01-02");
+        assert_eq!(t.rate("GBP", "GBP", on), Some(1.0));
+        let usd_eur = t.rate("USD", "EUR", on).unwrap();
+        assert!((usd_eur - 1.0 / 1.10).abs() < 1e-12);
+        // EUR → GBP has no quote either way: EUR → USD → GBP.
+        let eur_gbp = t.convert(100.0, "EUR", "GBP", on).unwrap();
+        assert!((eur_gbp - 100.0 * 1.10 / 1.25).abs() < 1e-9);
+        assert_eq!(t.rate("EUR", "JPY", on), None);
+    }
+
+    #[test]
+    fn connector_markup_overrides_the_default() {
+        let cfg = FxConfig {
+            markup_bps: 30.0,
+            connector_markup_bps: [("Adyen".to_string(), 60.0)].into_iter().collect(),
+            ..FxConfig::default()
+        };
+        let t = FxTable::new(Vec::new(), &cfg);
+        assert_eq!(t.markup_bps("adyen"), 60.0);
+        assert_eq!(t.markup_bps("stripe"), 30.0);
+    }
+
+    #[test]
+    fn rejects_a_malformed_rates_file() {
+        assert!(parse_csv("base,quote,rate\nEUR,GBP,0.85\n").is_err());
+        let err =
+            parse_csv("base,quote,rate,effective_from\nEUR,GBP,abc,2026-01-01\n").unwrap_err();
+        assert!(err.contains("line 2"), "{err}");
+    }
+}
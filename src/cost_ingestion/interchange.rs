@@ -0,0 +1,763 @@
+//! Interchange++ decomposition: one interchange table shared by every connector, and a markup per
+//! connector (architecture doc §3.3).
+//!
+//! An IC++ connector (Adyen, Worldpay, Checkout, …) itemises each settled transaction's interchange
+//! and scheme fee — the *pass-through*, set by the card scheme and the issuer and the same whoever
+//! acquires the card — apart from its own markup. The rollup stages pass-through sums beside the
+//! total-fee sums (`cost_daily_stats.n_itemised`, `spt`, …), and this module fits them two ways:
+//!
+//! - **Shared interchange table** ([`InterchangeTable`]): the pass-through line per card
+//!   `(network, funding, issuer country, currency, card product)`, pooled across every merchant and
+//!   every connector that itemises it. Merchant-independent, like the BIN → card-product map, and
+//!   backed off to the card-product blend and then the issuer region when the exact card is unseen.
+//! - **Markup per connector**: `total fee − pass-through`, one line per
+//!   `(merchant, connector, currency)` — what the connector charges on top of interchange. A
+//!   merchant can also declare one ([`put_markup`]) for a connector with no ingested reports yet.
+//!
+//! Together they price a card on a connector that has no fitted model for it — interchange from the
+//! shared table plus that connector's markup, the last step of
+//! [`serving::lookup`](super::serving::lookup) — and put a blended-pricing connector (Stripe) next
+//! to IC++ ones on the same cluster: its fitted all-in rate minus the shared interchange is the
+//! markup it implicitly charges ([`compare`]).
+//!
+//! Only buckets whose every transaction was itemised are read (`n_itemised = n`), so a bucket's gross
+//! sums describe exactly the transactions behind its pass-through sums.
+
+use std::collections::HashMap;
+use std::sync::{Arc, OnceLock, RwLock};
+
+use serde::{Deserialize, Serialize};
+
+use crate::config::ClickHouseAnalyticsConfig;
+use crate::decider::gatewaydecider::multi_objective::cluster_key::issuer_region;
+use crate::logger;
+use crate::types::service_configuration;
+
+use super::fit::BASE_WINDOW_DAYS;
+use super::overrides::{index_add, index_remove, read_json, read_list, write_json};
+use super::serving::{self, normalize_network};
+use super::types::IngestError;
+
+/// Transactions a line needs to be served on the strong path (the fit's `GOOD` sample gate).
+const MIN_SAMPLES: f64 = 200.0;
+/// The fit's L2 promotion: fewer transactions still serve when the rate's 95% CI is this tight.
+const L2_MIN_SAMPLES: f64 = 30.0;
+const L2_MAX_CI_BPS: f64 = 15.0;
+/// Clusters [`compare`] returns, by settled volume.
+const MAX_COMPARED_CLUSTERS: usize = 500;
+
+/// A fitted `{pct_bps, fixed}` fee line and the evidence behind it.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct FeeLine {
+    pub pct_bps: f64,
+    /// Per-transaction fee, in the line's currency.
+    pub fixed: f64,
+    /// Standard error of `pct_bps`.
+    pub stderr_bps: f64,
+    pub n: u64,
+    /// Settled volume the line was fitted on.
+    pub gross: f64,
+}
+
+impl FeeLine {
+    pub fn effective_bps(&self, amount: f64) -> f64 {
+        if amount > 0.0 {
+            self.pct_bps + self.fixed / amount * 10_000.0
+        } else {
+            self.pct_bps
+        }
+    }
+}
+
+/// Additive OLS sums of one fee component `y` over gross `x`.
+#[derive(Debug, Clone, Copy, Default)]
+struct Sums {
+    n: f64,
+    sx: f64,
+    sxx: f64,
+    sy: f64,
+    sxy: f64,
+    syy: f64,
+}
+
+impl Sums {
+    /// Parse six consecutive TSV fields `n, Σx, Σx², Σy, Σxy, Σy²`.
+    fn from_fields(f: &[&str]) -> Option<Self> {
+        let mut v = [0.0; 6];
+        for (slot, s) in v.iter_mut().zip(f) {
+            *slot = s.trim().parse().ok()?;
+        }
+        Some(Self {
+            n: v[0],
+            sx: v[1],
+            sxx: v[2],
+            sy: v[3],
+            sxy: v[4],
+            syy: v[5],
+        })
+    }
+
+    fn add(&mut self, o: &Self) {
+        self.n += o.n;
+        self.sx += o.sx;
+        self.sxx += o.sxx;
+        self.sy += o.sy;
+        self.sxy += o.sxy;
+        self.syy += o.syy;
+    }
+
+    /// The OLS line, when there is enough data to trust it — the same gate the cost fit grades
+    /// `GOOD` by (without its proportional-error check: the pass-through carries no reciprocal sums).
+    fn line(&self) -> Option<FeeLine> {
+        let n = self.n;
+        let denom = n * self.sxx - self.sx * self.sx;
+        if n <= 2.0 || denom <= 0.0 {
+            return None;
+        }
+        let slope = (n * self.sxy - self.sx * self.sy) / denom;
+        let intercept = (self.sy - slope * self.sx) / n;
+        let sse = self.syy - intercept * self.sy - slope * self.sxy;
+        let stderr_bps = (sse.max(0.0) / (n - 2.0) * n / denom).sqrt() * 10_000.0;
+        let trusted =
+            n >= MIN_SAMPLES || (n >= L2_MIN_SAMPLES && 1.96 * stderr_bps <= L2_MAX_CI_BPS);
+        (trusted && slope.is_finite() && intercept.is_finite()).then_some(FeeLine {
+            pct_bps: slope * 10_000.0,
+            fixed: intercept,
+            stderr_bps,
+            n: n as u64,
+            gross: self.sx,
+        })
+    }
+}
+
+// ── shared interchange table ────────────────────────────────────────────────────────────────────
+
+/// Pass-through sums per card over every itemising connector's trailing window (relative to each
+/// `(merchant, connector)`'s latest transaction, like the fit's), pooled across merchants.
+const INTERCHANGE_SQL: &str = r#"
+WITH latest AS (
+    SELECT merchant_id, connector, max(txn_date) AS last_day
+    FROM __DB__.cost_daily_stats
+    WHERE n_itemised > 0
+    GROUP BY merchant_id, connector
+)
+SELECT card_network, funding, issuer_country, currency, card_product,
+       sum(n), sum(sx), sum(sxx), sum(spt), sum(sxpt), sum(sptpt)
+FROM (
+    SELECT merchant_id, connector, txn_date, card_network, funding, issuer_country, currency,
+           card_product, n, sx, sxx, spt, sxpt, sptpt
+    FROM __DB__.cost_daily_stats FINAL
+    WHERE n > 0 AND n_itemised = n
+) AS s
+INNER JOIN latest USING (merchant_id, connector)
+WHERE s.txn_date > latest.last_day - toIntervalDay(__WINDOW__)
+GROUP BY card_network, funding, issuer_country, currency, card_product
+FORMAT TSV
+"#;
+
+/// Interchange + scheme fee per card, learned from every connector that itemises them.
+///
+/// Three levels, most specific first: the exact card product, the blend of every product under the
+/// issuer country (a request without a resolvable BIN), and the issuer's region (an issuer country
+/// no itemising report has covered yet).
+#[derive(Debug, Default)]
+pub struct InterchangeTable {
+    levels: [HashMap<String, FeeLine>; 3],
+}
+
+fn level_keys(
+    network: &str,
+    funding: &str,
+    issuer: &str,
+    region: &str,
+    currency: &str,
+    card_product: &str,
+) -> [Option<String>; 3] {
+    let net_l = network.to_lowercase();
+    let net = normalize_network(&net_l);
+    let fun = funding.to_lowercase();
+    let iss = issuer.to_lowercase();
+    let ccy = currency.to_lowercase();
+    [
+        (!iss.is_empty() && !card_product.is_empty())
+            .then(|| format!("{net}|{fun}|{iss}|{ccy}|{}", card_product.to_lowercase())),
+        (!iss.is_empty()).then(|| format!("{net}|{fun}|{iss}|{ccy}")),
+        (!region.is_empty()).then(|| format!("{net}|{fun}|{}|{ccy}", region.to_lowercase())),
+    ]
+}
+
+impl InterchangeTable {
+    /// Build from [`INTERCHANGE_SQL`]'s TSV. Sums are additive, so each coarser level is fitted from
+    /// the pooled sums of its members rather than averaged from their lines.
+    fn from_tsv(rows: &str) -> Self {
+        let mut acc: [HashMap<String, Sums>; 3] = Default::default();
+        for line in rows.lines() {
+            let f: Vec<&str> = line.split('\t').collect();
+            if f.len() < 11 {
+                continue;
+            }
+            let (network, funding, issuer, currency, card_product) = (f[0], f[1], f[2], f[3], f[4]);
+            let Some(sums) = Sums::from_fields(&f[5..11]) else {
+                continue;
+            };
+            let region = issuer_region(issuer);
+            let keys = level_keys(network, funding, issuer, &region, currency, card_product);
+            for (level, key) in acc.iter_mut().zip(keys) {
+                if let Some(key) = key {
+                    level.entry(key).or_default().add(&sums);
+                }
+            }
+        }
+        Self {
+            levels: acc.map(|level| {
+                level
+                    .into_iter()
+                    .filter_map(|(k, s)| s.line().map(|l| (k, l)))
+                    .collect()
+            }),
+        }
+    }
+
+    /// The card's interchange + scheme fee line, backing off from the exact card product.
+    pub fn get(
+        &self,
+        network: &str,
+        funding: &str,
+        issuer: &str,
+        region: &str,
+        currency: &str,
+        card_product: &str,
+    ) -> Option<FeeLine> {
+        level_keys(network, funding, issuer, region, currency, card_product)
+            .into_iter()
+            .zip(&self.levels)
+            .find_map(|(key, level)| level.get(key.as_ref()?).copied())
+    }
+}
+
+fn table_cache() -> &'static RwLock<Arc<InterchangeTable>> {
+    static TABLE: OnceLock<RwLock<Arc<InterchangeTable>>> = OnceLock::new();
+    TABLE.get_or_init(|| RwLock::new(Arc::new(InterchangeTable::default())))
+}
+
+/// The shared interchange table as of the last refresh.
+pub fn table() -> Arc<InterchangeTable> {
+    table_cache().read().map(|t| t.clone()).unwrap_or_default()
+}
+
+/// Re-fit the shared table. Run with every serving refresh; on failure the previous table stays.
+pub async fn refresh_table(cfg: &ClickHouseAnalyticsConfig) {
+    let sql = INTERCHANGE_SQL.replace("__WINDOW__", &BASE_WINDOW_DAYS.to_string());
+    match serving::query(cfg, &sql, None).await {
+        Ok(rows) => {
+            if let Ok(mut guard) = table_cache().write() {
+                *guard = Arc::new(InterchangeTable::from_tsv(&rows));
+            }
+        }
+        Err(e) => logger::warn!(
+            tag = "cost_interchange",
+            "interchange refresh failed: {}",
+            e
+        ),
+    }
+}
+
+// ── learned markups ─────────────────────────────────────────────────────────────────────────────
+
+/// Markup sums (`total fee − pass-through`) per `(merchant, connector, currency)`, over the same
+/// trailing window as [`INTERCHANGE_SQL`]. `{merchant_filter}` is `""` for every merchant or
+/// `" AND merchant_id = {merchant:String}"` for one.
+const MARKUP_SQL: &str = r#"
+WITH latest AS (
+    SELECT merchant_id, connector, max(txn_date) AS last_day
+    FROM __DB__.cost_daily_stats
+    WHERE n_itemised > 0{merchant_filter}
+    GROUP BY merchant_id, connector
+)
+SELECT merchant_id, connector, currency,
+       sum(n), sum(sx), sum(sxx), sum(sy - spt), sum(sxy - sxpt), sum(syy - 2 * sypt + sptpt)
+FROM (
+    SELECT merchant_id, connector, txn_date, currency, n, sx, sxx, sy, sxy, syy, spt, sxpt, sptpt,
+           sypt
+    FROM __DB__.cost_daily_stats FINAL
+    WHERE n > 0 AND n_itemised = n{merchant_filter}
+) AS s
+INNER JOIN latest USING (merchant_id, connector)
+WHERE s.txn_date > latest.last_day - toIntervalDay(__WINDOW__)
+GROUP BY merchant_id, connector, currency
+FORMAT TSV
+"#;
+
+/// The key a learned markup is stored under: lowercase `connector|currency`.
+pub fn markup_key(connector: &str, currency: &str) -> String {
+    format!("{}|{}", connector.to_lowercase(), currency.to_lowercase())
+}
+
+/// Every itemising connector's markup line: `merchant → markup_key → line`. `merchant` restricts
+/// the read to one merchant.
+pub async fn learned_markups(
+    cfg: &ClickHouseAnalyticsConfig,
+    merchant: Option<&str>,
+) -> Result<HashMap<String, HashMap<String, FeeLine>>, String> {
+    let sql = MARKUP_SQL
+        .replace("__WINDOW__", &BASE_WINDOW_DAYS.to_string())
+        .replace(
+            "{merchant_filter}",
+            if merchant.is_some() {
+                " AND merchant_id = {merchant:String}"
+            } else {
+                ""
+            },
+        );
+    let rows = serving::query(cfg, &sql, merchant).await?;
+    Ok(markups_from_tsv(&rows))
+}
+
+fn markups_from_tsv(rows: &str) -> HashMap<String, HashMap<String, FeeLine>> {
+    let mut out: HashMap<String, HashMap<String, FeeLine>> = HashMap::new();
+    for line in rows.lines() {
+        let f: Vec<&str> = line.split('\t').collect();
+        if f.len() < 9 {
+            continue;
+        }
+        let Some(markup) = Sums::from_fields(&f[3..9]).and_then(|s| s.line()) else {
+            continue;
+        };
+        out.entry(f[0].to_string())
+            .or_default()
+            .insert(markup_key(f[1], f[2]), markup);
+    }
+    out
+}
+
+// ── declared markups ────────────────────────────────────────────────────────────────────────────
+
+/// A markup the merchant states for a connector — its IC++ contract's `{pct_bps, fixed}` on top of
+/// interchange. Prices the connector before it has any itemised reports, and wins over the learned
+/// markup once it has.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct DeclaredMarkup {
+    pub pct_bps: f64,
+    /// Per-transaction fee, in the payment's currency.
+    pub fixed: f64,
+    /// RFC3339 timestamp of the last edit.
+    pub updated_at: String,
+}
+
+fn markup_name(merchant_id: &str, connector: &str) -> String {
+    format!("cost_markup::{merchant_id}::{connector}")
+}
+
+fn merchant_index_name(merchant_id: &str) -> String {
+    format!("cost_markup_index::{merchant_id}")
+}
+
+/// Merchants with at least one declared markup, so the periodic serving refresh can find them.
+const GLOBAL_INDEX_NAME: &str = "cost_markup_merchants";
+
+/// All `(connector, markup)` a merchant has declared.
+pub async fn list_markups(merchant_id: &str) -> Result<Vec<(String, DeclaredMarkup)>, IngestError> {
+    let connectors = read_list(merchant_index_name(merchant_id)).await?;
+    let mut out = Vec::with_capacity(connectors.len());
+    for connector in connectors {
+        if let Some(m) = read_json::<DeclaredMarkup>(markup_name(merchant_id, &connector)).await? {
+            out.push((connector, m));
+        }
+    }
+    Ok(out)
+}
+
+/// The declared markup for one `(merchant, connector)`, if set.
+pub async fn get_markup(
+    merchant_id: &str,
+    connector: &str,
+) -> Result<Option<DeclaredMarkup>, IngestError> {
+    read_json::<DeclaredMarkup>(markup_name(merchant_id, connector)).await
+}
+
+/// Merchants that currently have at least one declared markup (global index).
+pub async fn list_markup_merchants() -> Result<Vec<String>, IngestError> {
+    read_list(GLOBAL_INDEX_NAME.to_string()).await
+}
+
+/// Upsert a merchant's declared markup for a connector, recording it in both indices.
+pub async fn put_markup(
+    merchant_id: &str,
+    connector: &str,
+    markup: &DeclaredMarkup,
+) -> Result<(), IngestError> {
+    write_json(markup_name(merchant_id, connector), markup).await?;
+    index_add(merchant_index_name(merchant_id), connector).await?;
+    index_add(GLOBAL_INDEX_NAME.to_string(), merchant_id).await?;
+    Ok(())
+}
+
+/// Remove a merchant's declared markup for a connector; drops it from the global index once none
+/// remain.
+pub async fn delete_markup(merchant_id: &str, connector: &str) -> Result<(), IngestError> {
+    service_configuration::delete_config(markup_name(merchant_id, connector))
+        .await
+        .map_err(|e| IngestError::Storage(e.to_string()))?;
+    let remaining = index_remove(merchant_index_name(merchant_id), connector).await?;
+    if remaining == 0 {
+        index_remove(GLOBAL_INDEX_NAME.to_string(), merchant_id).await?;
+    }
+    Ok(())
+}
+
+// ── comparison ──────────────────────────────────────────────────────────────────────────────────
+
+/// The merchant's latest GOOD fitted clusters per connector, rolled up past `variant`, `ic_category`
+/// and `card_product` — the vocabularies connectors don't share — to the card dimensions every
+/// connector's report states.
+const COMPARE_SQL: &str = r#"
+SELECT connector, card_network, funding, issuer_country, currency,
+    sum(pct_bps * gross_sum) / sum(gross_sum) AS pct_bps,
+    sum(fixed * gross_sum)   / sum(gross_sum) AS fixed,
+    sum(gross_sum)           AS gross,
+    sum(n)                   AS n,
+    sqrt(sum(bps_rmse * bps_rmse * n) / sum(n)) AS rmse
+FROM __DB__.cost_fee_model FINAL
+WHERE verdict = 'GOOD' AND gross_sum > 0 AND merchant_id = {merchant:String}
+  AND (merchant_id, connector, account, report_date) IN (
+      SELECT merchant_id, connector, account, max(report_date)
+      FROM __DB__.cost_fee_model
+      WHERE merchant_id = {merchant:String}
+      GROUP BY merchant_id, connector, account)
+GROUP BY connector, card_network, funding, issuer_country, currency
+FORMAT TSV
+"#;
+
+/// How a connector's cost on a compared cluster was arrived at.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CostBasis {
+    /// Fitted from the connector's own settlement reports for this cluster.
+    Fitted,
+    /// Not processed on this cluster: the shared interchange plus the connector's markup.
+    InterchangePlusMarkup,
+}
+
+/// One connector's cost on a compared cluster.
+#[derive(Debug, Clone, Serialize)]
+pub struct ConnectorCost {
+    pub connector: String,
+    /// Whether the connector prices IC++ — its reports itemise interchange, or the merchant declared
+    /// a markup for it. `false` for a blended-pricing connector.
+    pub ic_plus_plus: bool,
+    pub basis: CostBasis,
+    /// The all-in line.
+    pub pct_bps: f64,
+    pub fixed: f64,
+    pub stderr_bps: f64,
+    /// All-in cost at the cluster's reference amount.
+    pub effective_bps: f64,
+    /// What the connector charges over the shared interchange: its (learned or declared) markup for
+    /// an IC++ connector, and all-in minus interchange — the implied markup — for a blended one.
+    /// `None` when the cluster has no shared interchange.
+    pub markup_pct_bps: Option<f64>,
+    pub markup_fixed: Option<f64>,
+}
+
+/// Every connector the merchant can price on one card cluster, side by side.
+#[derive(Debug, Clone, Serialize)]
+pub struct ClusterComparison {
+    pub card_network: String,
+    pub funding: String,
+    pub issuer_country: String,
+    pub currency: String,
+    /// The ticket `effective_bps` is evaluated at: the cluster's mean settled amount.
+    pub reference_amount: f64,
+    /// Settled volume across the merchant's connectors on this cluster.
+    pub gross: f64,
+    /// The shared interchange + scheme fee, blended over card products.
+    pub interchange: Option<FeeLine>,
+    /// Cheapest first at `reference_amount`.
+    pub connectors: Vec<ConnectorCost>,
+}
+
+/// Compare the merchant's connectors cluster by cluster: fitted all-in costs where a connector has
+/// processed the card, interchange plus markup where it hasn't, and the markup each one charges
+/// over the shared interchange. Largest clusters first.
+pub async fn compare(
+    cfg: &ClickHouseAnalyticsConfig,
+    merchant_id: &str,
+) -> Result<Vec<ClusterComparison>, IngestError> {
+    let rows = serving::query(cfg, COMPARE_SQL, Some(merchant_id))
+        .await
+        .map_err(IngestError::Storage)?;
+    let learned = learned_markups(cfg, Some(merchant_id))
+        .await
+        .map_err(IngestError::Storage)?
+        .remove(merchant_id)
+        .unwrap_or_default();
+    let declared: HashMap<String, FeeLine> = list_markups(merchant_id)
+        .await?
+        .into_iter()
+        .map(|(connector, m)| (connector.to_lowercase(), declared_line(&m)))
+        .collect();
+    Ok(comparisons(&rows, &table(), &learned, &declared))
+}
+
+/// A declared markup as a line: known exactly, so no standard error.
+pub fn declared_line(m: &DeclaredMarkup) -> FeeLine {
+    FeeLine {
+        pct_bps: m.pct_bps,
+        fixed: m.fixed,
+        stderr_bps: 0.0,
+        n: 0,
+        gross: 0.0,
+    }
+}
+
+/// Assemble [`compare`]'s result from [`COMPARE_SQL`]'s TSV. Pure, for tests.
+fn comparisons(
+    rows: &str,
+    interchange: &InterchangeTable,
+    learned: &HashMap<String, FeeLine>,
+    declared: &HashMap<String, FeeLine>,
+) -> Vec<ClusterComparison> {
+    // (network, funding, issuer, currency) → fitted (connector, line).
+    let mut clusters: HashMap<(String, String, String, String), Vec<(String, FeeLine)>> =
+        HashMap::new();
+    for line in rows.lines() {
+        let f: Vec<&str> = line.split('\t').collect();
+        if f.len() < 10 {
+            continue;
+        }
+        let num = |s: &str| s.trim().parse::<f64>().ok().filter(|v| v.is_finite());
+        let (Some(pct_bps), Some(fixed), Some(gross), Some(n)) =
+            (num(f[5]), num(f[6]), num(f[7]), num(f[8]))
+        else {
+            continue;
+        };
+        let rmse = num(f[9]).unwrap_or(0.0);
+        let fitted = FeeLine {
+            pct_bps,
+            fixed,
+            stderr_bps: if n > 0.0 { rmse / n.sqrt() } else { 0.0 },
+            n: n as u64,
+            gross,
+        };
+        clusters
+            .entry((
+                normalize_network(&f[1].to_lowercase()).to_string(),
+                f[2].to_lowercase(),
+                f[3].to_uppercase(),
+                f[4].to_uppercase(),
+            ))
+            .or_default()
+            .push((f[0].to_lowercase(), fitted));
+    }
+
+    // Every connector with a markup, for pricing the clusters it hasn't processed.
+    let mut markup_connectors: Vec<&str> = declared.keys().map(String::as_str).collect();
+    for key in learned.keys() {
+        if let Some((connector, _)) = key.split_once('|') {
+            if !markup_connectors.contains(&connector) {
+                markup_connectors.push(connector);
+            }
+        }
+    }
+    markup_connectors.sort_unstable();
+
+    let mut out: Vec<ClusterComparison> = clusters
+        .into_iter()
+        .map(|((network, funding, issuer, currency), fitted)| {
+            let gross: f64 = fitted.iter().map(|(_, l)| l.gross).sum();
+            let n: u64 = fitted.iter().map(|(_, l)| l.n).sum();
+            let reference_amount = if n > 0 { gross / n as f64 } else { 0.0 };
+            let region = issuer_region(&issuer);
+            let ic = interchange.get(&network, &funding, &issuer, &region, &currency, "");
+            let markup_of = |connector: &str| {
+                declared
+                    .get(connector)
+                    .or_else(|| learned.get(&markup_key(connector, &currency)))
+                    .copied()
+            };
+
+            let mut connectors: Vec<ConnectorCost> = fitted
+                .iter()
+                .map(|(connector, all_in)| {
+                    let markup = markup_of(connector);
+                    let (markup_pct_bps, markup_fixed) = match (markup, ic) {
+                        (Some(m), _) => (Some(m.pct_bps), Some(m.fixed)),
+                        (None, Some(ic)) => (
+                            Some(all_in.pct_bps - ic.pct_bps),
+                            Some(all_in.fixed - ic.fixed),
+                        ),
+                        (None, None) => (None, None),
+                    };
+                    ConnectorCost {
+                        connector: connector.clone(),
+                        ic_plus_plus: markup.is_some(),
+                        basis: CostBasis::Fitted,
+                        pct_bps: all_in.pct_bps,
+                        fixed: all_in.fixed,
+                        stderr_bps: all_in.stderr_bps,
+                        effective_bps: all_in.effective_bps(reference_amount),
+                        markup_pct_bps,
+                        markup_fixed,
+                    }
+                })
+                .collect();
+            if let Some(ic) = ic {
+                for connector in &markup_connectors {
+                    if fitted.iter().any(|(c, _)| c == connector) {
+                        continue;
+                    }
+                    let Some(m) = markup_of(connector) else {
+                        continue;
+                    };
+                    let all_in = FeeLine {
+                        pct_bps: ic.pct_bps + m.pct_bps,
+                        fixed: ic.fixed + m.fixed,
+                        stderr_bps: ic.stderr_bps.hypot(m.stderr_bps),
+                        n: 0,
+                        gross: 0.0,
+                    };
+                    connectors.push(ConnectorCost {
+                        connector: connector.to_string(),
+                        ic_plus_plus: true,
+                        basis: CostBasis::InterchangePlusMarkup,
+                        pct_bps: all_in.pct_bps,
+                        fixed: all_in.fixed,
+                        stderr_bps: all_in.stderr_bps,
+                        effective_bps: all_in.effective_bps(reference_amount),
+                        markup_pct_bps: Some(m.pct_bps),
+                        markup_fixed: Some(m.fixed),
+                    });
+                }
+            }
+            connectors.sort_by(|a, b| a.effective_bps.total_cmp(&b.effective_bps));
+
+            ClusterComparison {
+                card_network: network,
+                funding,
+                issuer_country: issuer,
+                currency,
+                reference_amount,
+                gross,
+                interchange: ic,
+                connectors,
+            }
+        })
+        .collect();
+    out.sort_by(|a, b| b.gross.total_cmp(&a.gross));
+    out.truncate(MAX_COMPARED_CLUSTERS);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// One TSV row of pass-through sums for `n` transactions of `amounts`, each charged
+    /// `pct_bps`/10⁴·x + `fixed`.
+    fn sums_row(key: &str, amounts: &[f64], n: usize, pct_bps: f64, fixed: f64) -> String {
+        let mut s = Sums::default();
+        for x in amounts.iter().cycle().take(n) {
+            let y = pct_bps / 10_000.0 * x + fixed;
+            s.add(&Sums {
+                n: 1.0,
+                sx: *x,
+                sxx: x * x,
+                sy: y,
+                sxy: x * y,
+                syy: y * y,
+            });
+        }
+        format!(
+            "{key}\t{}\t{}\t{}\t{}\t{}\t{}\n",
+            s.n, s.sx, s.sxx, s.sy, s.sxy, s.syy
+        )
+    }
+
+    #[test]
+    fn interchange_backs_off_from_card_product_to_region() {
+        let amounts = [20.0, 55.0, 140.0, 400.0];
+        let tsv = sums_row("visa\tcredit\tFR\tEUR\t30", &amounts, 300, 30.0, 0.0)
+            + &sums_row("visa\tcredit\tFR\tEUR\t150", &amounts, 100, 150.0, 0.0);
+        let t = InterchangeTable::from_tsv(&tsv);
+
+        let exact = t.get("visa", "credit", "FR", "eu", "EUR", "30").unwrap();
+        assert!((exact.pct_bps - 30.0).abs() < 1e-6);
+        assert!(exact.fixed.abs() < 1e-9);
+        assert_eq!(exact.n, 300);
+
+        // No BIN: the card products pooled by volume — 300 at 30 bps and 100 at 150.
+        let blend = t.get("visa", "credit", "FR", "eu", "EUR", "").unwrap();
+        assert!((blend.pct_bps - 60.0).abs() < 1e-6);
+        // An unseen EU issuer falls to the region.
+        let region = t.get("Visa", "credit", "DE", "eu", "EUR", "").unwrap();
+        assert!((region.pct_bps - 60.0).abs() < 1e-6);
+        // Nothing for another currency or network.
+        assert!(t.get("visa", "credit", "FR", "eu", "GBP", "30").is_none());
+        assert!(t.get("mc", "credit", "FR", "eu", "EUR", "30").is_none());
+    }
+
+    #[test]
+    fn a_thin_line_is_not_served() {
+        // 10 transactions with scatter: under both sample gates.
+        let tsv = "visa\tdebit\tUS\tUSD\t\t10\t1000\t150000\t5\t600\t3\n";
+        let t = InterchangeTable::from_tsv(tsv);
+        assert!(t.get("visa", "debit", "US", "us", "USD", "").is_none());
+    }
+
+    #[test]
+    fn blended_connectors_show_their_implied_markup() {
+        let amounts = [20.0, 80.0, 200.0];
+        let ic = InterchangeTable::from_tsv(&sums_row(
+            "visa\tcredit\tFR\tEUR\t30",
+            &amounts,
+            400,
+            30.0,
+            0.05,
+        ));
+        let learned = HashMap::from([(
+            markup_key("adyen", "EUR"),
+            FeeLine {
+                pct_bps: 60.0,
+                fixed: 0.11,
+                stderr_bps: 1.0,
+                n: 400,
+                gross: 40_000.0,
+            },
+        )]);
+        let declared = HashMap::from([(
+            "newpsp".to_string(),
+            declared_line(&DeclaredMarkup {
+                pct_bps: 40.0,
+                fixed: 0.10,
+                updated_at: String::new(),
+            }),
+        )]);
+        // Adyen fitted all-in; Stripe blended at 140 bps + €0.25.
+        let rows = "adyen\tvisa\tcredit\tfr\tEUR\t90\t0.16\t60000\t600\t4\n\
+                    stripe\tvisa\tcredit\tfr\tEUR\t140\t0.25\t40000\t400\t9\n";
+        let out = comparisons(rows, &ic, &learned, &declared);
+        assert_eq!(out.len(), 1);
+        let c = &out[0];
+        assert_eq!(c.issuer_country, "FR");
+        assert!((c.reference_amount - 100.0).abs() < 1e-9);
+        assert!((c.interchange.unwrap().pct_bps - 30.0).abs() < 1e-6);
+
+        let by = |name: &str| c.connectors.iter().find(|x| x.connector == name).unwrap();
+        let adyen = by("adyen");
+        assert!(adyen.ic_plus_plus);
+        assert_eq!(adyen.basis, CostBasis::Fitted);
+        assert_eq!(adyen.markup_pct_bps, Some(60.0));
+
+        let stripe = by("stripe");
+        assert!(!stripe.ic_plus_plus);
+        assert!((stripe.markup_pct_bps.unwrap() - 110.0).abs() < 1e-6);
+        assert!((stripe.markup_fixed.unwrap() - 0.20).abs() < 1e-9);
+
+        // Never processed this card: priced from the shared interchange plus its declared markup.
+        let new = by("newpsp");
+        assert_eq!(new.basis, CostBasis::InterchangePlusMarkup);
+        assert!((new.pct_bps - 70.0).abs() < 1e-6);
+        assert!((new.fixed - 0.15).abs() < 1e-9);
+        assert_eq!(c.connectors[0].connector, "newpsp", "cheapest first");
+    }
+}
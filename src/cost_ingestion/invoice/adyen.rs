@@ -135,6 +135,7 @@ fn parse_csv(bytes: &[u8]) -> Result<ParsedInvoice, IngestError> {
             card_volume: (card_volume > 0.0).then_some(card_volume),
             txn_count: (txn_count > 0).then_some(txn_count),
             subtotal_ex_tax: Some(subtotal_ex_tax),
+            includes_settled_fees: lines.iter().any(|l| l.kind == LineKind::AlreadyModeled),
             currency,
             period_start: None,
             period_end: None,
@@ -260,6 +261,7 @@ fn parse_summary_text(text: &str) -> Result<ParsedInvoice, IngestError> {
             card_volume: (card_volume > 0.0).then_some(card_volume),
             txn_count: (txn_count > 0).then_some(txn_count),
             subtotal_ex_tax: Some(subtotal_ex_tax),
+            includes_settled_fees: lines.iter().any(|l| l.kind == LineKind::AlreadyModeled),
             currency,
             period_start: None,
             period_end: None,
@@ -0,0 +1,214 @@
+//! Braintree `InvoiceSource`.
+//!
+//! Braintree bills through the monthly **merchant statement**. Its fee summary repeats the
+//! per-transaction fees the settlement (PAR) report already itemizes — the discount and
+//! per-transaction fee (`Braintree Total Amount`), interchange, and card-brand assessments — next to
+//! the account fees the PAR can't carry: dispute / chargeback fees, Advanced Fraud Protection
+//! screenings, gateway fees, account updater, and the monthly and minimum fees. We read the
+//! statement's CSV export (Control Panel → Statements → Download), one row per fee summary line:
+//!
+//! `Statement ID, Statement Period Start, Statement Period End, Fee Category, Description, Count,
+//! Amount, Currency`
+//!
+//! The statement's `Sales` summary row states settled sales volume and count, so the periodic fees
+//! amortize over Braintree's own figures. Amounts are positive for fees; refunds of fees (a won
+//! dispute's fee) are negative.
+
+use super::source::InvoiceSource;
+use super::table::{self, Table};
+use super::types::{InvoiceLine, LineKind, ParsedInvoice};
+use crate::cost_ingestion::types::IngestError;
+
+pub struct BraintreeInvoiceSource;
+
+impl BraintreeInvoiceSource {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl Default for BraintreeInvoiceSource {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl InvoiceSource for BraintreeInvoiceSource {
+    fn connector(&self) -> &'static str {
+        "braintree"
+    }
+
+    fn parse_invoice(&self, bytes: &[u8]) -> Result<ParsedInvoice, IngestError> {
+        let table = Table::read(bytes)?;
+        let description = table.require(&["Description", "Fee Description"])?;
+        let amount_col = table.require(&["Amount", "Total"])?;
+        let category = table.column(&["Fee Category", "Category"]);
+        let count = table.column(&["Count", "Quantity"]);
+        let currency = table.column(&["Currency", "Currency ISO Code"]);
+        let statement_id = table.column(&["Statement ID", "Statement Number"]);
+        let period_start = table.column(&["Statement Period Start", "Period Start"]);
+        let period_end = table.column(&["Statement Period End", "Period End"]);
+
+        let mut lines = Vec::new();
+        let mut invoice_ccy = String::new();
+        let mut invoice_ref = String::new();
+        let mut start = None;
+        let mut end = None;
+        for row in table.rows() {
+            let desc = row.get(Some(description)).to_lowercase();
+            let cat = row.get(category).to_lowercase();
+            if desc.is_empty() || desc.starts_with("total") || cat == "total" {
+                continue;
+            }
+            let line_ccy = row.get(currency).to_uppercase();
+            if invoice_ccy.is_empty() {
+                invoice_ccy = line_ccy.clone();
+            }
+            if invoice_ref.is_empty() {
+                invoice_ref = row.get(statement_id).to_string();
+            }
+            start = start.or_else(|| table::parse_date(row.get(period_start)));
+            end = end.or_else(|| table::parse_date(row.get(period_end)));
+
+            let amount = table::to_float(row.get(Some(amount_col)));
+            lines.push(InvoiceLine {
+                kind: classify(&cat, &desc, amount),
+                description: desc,
+                amount,
+                quantity: table::to_count(row.get(count)),
+                currency: if line_ccy.is_empty() {
+                    invoice_ccy.clone()
+                } else {
+                    line_ccy
+                },
+            });
+        }
+
+        if lines.is_empty() {
+            return Err(IngestError::Parse(
+                "no fee lines found in the Braintree statement".to_string(),
+            ));
+        }
+
+        let mut summary = table::summarize(&lines, invoice_ccy);
+        summary.invoice_ref = invoice_ref;
+        summary.period_start = start;
+        summary.period_end = end;
+        Ok(ParsedInvoice { summary, lines })
+    }
+}
+
+/// Map a Braintree statement line to its [`LineKind`], from its lowercased fee category and
+/// description. Most-specific first.
+///
+/// Unknown positive lines default to [`LineKind::Periodic`], as on the Adyen invoice; a
+/// misclassification surfaces as reconciliation residual.
+fn classify(category: &str, description: &str, amount: f64) -> LineKind {
+    let has = |needle: &str| category.contains(needle) || description.contains(needle);
+
+    // 1. Credits: returned dispute fees, fee refunds and adjustments in the merchant's favour.
+    if has("refund") || has("reversal") || has("credit") || has("rebate") || amount < 0.0 {
+        return LineKind::Credit;
+    }
+
+    // 2. The statement's sales summary: settled volume and count, not a fee.
+    if category == "sales" || has("sales volume") || has("settled sales") {
+        return LineKind::Volume;
+    }
+
+    // 3. What the PAR report already prices per transaction (see `connectors/braintree.rs`):
+    //    Braintree's discount and per-transaction fee, interchange, and the card-brand assessments
+    //    that make up `Total Scheme Fees`.
+    if has("discount")
+        || has("per transaction fee")
+        || has("per-transaction fee")
+        || has("interchange")
+        || has("assessment")
+        || has("card brand")
+        || has("scheme")
+    {
+        return LineKind::AlreadyModeled;
+    }
+
+    // 4. Charged once per screened / gateway-routed transaction.
+    if has("fraud protection") || has("kount") || has("gateway") || has("3d secure") {
+        return LineKind::FlatPerTxn;
+    }
+
+    // 5. Everything else is periodic: dispute fees, account updater, the monthly and minimum fees.
+    LineKind::Periodic
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::reduce::{reduce_to_addon, VolumeFallback};
+    use super::*;
+
+    const STATEMENT: &str = "\
+Statement ID,Statement Period Start,Statement Period End,Fee Category,Description,Count,Amount,Currency\n\
+ST-2025-03-acme,03/01/2025,03/31/2025,Sales,Settled sales,20000,800000.00,USD\n\
+ST-2025-03-acme,03/01/2025,03/31/2025,Processing,Discount fees,20000,17600.00,USD\n\
+ST-2025-03-acme,03/01/2025,03/31/2025,Processing,Per transaction fees,20000,6000.00,USD\n\
+ST-2025-03-acme,03/01/2025,03/31/2025,Processing,Card brand assessments,20000,1040.00,USD\n\
+ST-2025-03-acme,03/01/2025,03/31/2025,Risk,Advanced Fraud Protection screenings,20000,400.00,USD\n\
+ST-2025-03-acme,03/01/2025,03/31/2025,Disputes,Dispute fees,20,300.00,USD\n\
+ST-2025-03-acme,03/01/2025,03/31/2025,Disputes,Dispute fee refunds,4,-60.00,USD\n\
+ST-2025-03-acme,03/01/2025,03/31/2025,Account,Account updater,150,37.50,USD\n\
+ST-2025-03-acme,03/01/2025,03/31/2025,Account,Monthly fee,1,49.00,USD\n\
+ST-2025-03-acme,03/01/2025,03/31/2025,Total,Total fees,,25366.50,USD\n";
+
+    #[test]
+    fn classifies_braintree_statement_lines() {
+        assert_eq!(
+            classify("sales", "settled sales", 800_000.0),
+            LineKind::Volume
+        );
+        assert_eq!(
+            classify("processing", "discount fees", 17_600.0),
+            LineKind::AlreadyModeled
+        );
+        assert_eq!(
+            classify("processing", "per transaction fees", 6_000.0),
+            LineKind::AlreadyModeled
+        );
+        assert_eq!(
+            classify("risk", "advanced fraud protection screenings", 400.0),
+            LineKind::FlatPerTxn
+        );
+        assert_eq!(
+            classify("gateway", "gateway fees", 120.0),
+            LineKind::FlatPerTxn
+        );
+        assert_eq!(
+            classify("disputes", "dispute fees", 300.0),
+            LineKind::Periodic
+        );
+        assert_eq!(
+            classify("disputes", "dispute fee refunds", -60.0),
+            LineKind::Credit
+        );
+        assert_eq!(classify("account", "monthly fee", 49.0), LineKind::Periodic);
+    }
+
+    #[test]
+    fn reads_the_statement_export() {
+        let parsed = BraintreeInvoiceSource::new()
+            .parse_invoice(STATEMENT.as_bytes())
+            .unwrap();
+        let s = &parsed.summary;
+        assert_eq!(s.invoice_ref, "ST-2025-03-acme");
+        assert_eq!(s.currency, "USD");
+        assert_eq!(s.txn_count, Some(20_000));
+        assert_eq!(s.card_volume, Some(800_000.0));
+        assert!(s.includes_settled_fees);
+        assert_eq!(s.period_start, chrono::NaiveDate::from_ymd_opt(2025, 3, 1));
+        assert_eq!(s.period_end, chrono::NaiveDate::from_ymd_opt(2025, 3, 31));
+        assert!((s.subtotal_ex_tax.unwrap() - 25_366.50).abs() < 1e-9);
+
+        let addon = reduce_to_addon(&parsed, VolumeFallback::default());
+        assert!((addon.fixed_addon - 400.0 / 20_000.0).abs() < 1e-9);
+        // pct: (300 + 37.50 + 49 − 60) / 800k · 1e4.
+        let expected = 326.5 / 800_000.0 * 10_000.0;
+        assert!((addon.pct_addon_bps - expected).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,221 @@
+//! Checkout.com `InvoiceSource`.
+//!
+//! Checkout.com nets its per-payment fees (interchange, scheme, and its own `Premium*` / `Blended*`
+//! and fixed authorization fees) out of each settlement — they are the Financial Actions report's
+//! fee rows, which `connectors/checkout.rs` already prices. The monthly **invoice** bills what the
+//! settlement can't: chargeback fees, fraud-detection and network-token fees, the account updater,
+//! and the minimum-monthly top-up. We read the invoice's CSV export (Dashboard → Billing → Invoices),
+//! one row per charge:
+//!
+//! `Invoice Number, Period Start, Period End, Fee Type, Description, Quantity, Amount, Currency`
+//!
+//! `Fee Type` is Checkout's charge category and `Description` the product line; both feed
+//! [`classify`]. An invoice that also itemizes the netted settlement fees (some accounts get the
+//! full statement) has those rows classified [`LineKind::AlreadyModeled`], and its subtotal then
+//! covers the settled fees too — reconciliation reads that off the summary.
+
+use super::source::InvoiceSource;
+use super::table::{self, Table};
+use super::types::{InvoiceLine, LineKind, ParsedInvoice};
+use crate::cost_ingestion::types::IngestError;
+
+pub struct CheckoutInvoiceSource;
+
+impl CheckoutInvoiceSource {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl Default for CheckoutInvoiceSource {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl InvoiceSource for CheckoutInvoiceSource {
+    fn connector(&self) -> &'static str {
+        "checkout"
+    }
+
+    fn parse_invoice(&self, bytes: &[u8]) -> Result<ParsedInvoice, IngestError> {
+        let table = Table::read(bytes)?;
+        let fee_type = table.column(&["Fee Type", "Charge Type"]);
+        let description = table.column(&["Description", "Product"]);
+        if fee_type.is_none() && description.is_none() {
+            return Err(IngestError::Parse(
+                "invoice missing Fee Type/Description column".to_string(),
+            ));
+        }
+        let amount_col = table.require(&["Amount", "Net Amount"])?;
+        let quantity = table.column(&["Quantity", "Count"]);
+        let currency = table.column(&["Currency"]);
+        let invoice_number = table.column(&["Invoice Number", "Invoice ID"]);
+        let period_start = table.column(&["Period Start", "Billing Period Start"]);
+        let period_end = table.column(&["Period End", "Billing Period End"]);
+
+        let mut lines = Vec::new();
+        let mut invoice_ccy = String::new();
+        let mut invoice_ref = String::new();
+        let mut start = None;
+        let mut end = None;
+        for row in table.rows() {
+            // The line label is the fee type and the product together — `"Chargeback fee"` alone
+            // or `"Fraud Detection: Fraud Detection Pro"`.
+            let desc = match (row.get(fee_type), row.get(description)) {
+                (t, d) if t.is_empty() => d.to_lowercase(),
+                (t, d) if d.is_empty() || d.eq_ignore_ascii_case(t) => t.to_lowercase(),
+                (t, d) => format!("{t}: {d}").to_lowercase(),
+            };
+            if desc.is_empty() || desc.starts_with("total") || desc.starts_with("vat") {
+                continue;
+            }
+            let line_ccy = row.get(currency).to_uppercase();
+            if invoice_ccy.is_empty() {
+                invoice_ccy = line_ccy.clone();
+            }
+            if invoice_ref.is_empty() {
+                invoice_ref = row.get(invoice_number).to_string();
+            }
+            start = start.or_else(|| table::parse_date(row.get(period_start)));
+            end = end.or_else(|| table::parse_date(row.get(period_end)));
+
+            let amount = table::to_float(row.get(Some(amount_col)));
+            lines.push(InvoiceLine {
+                kind: classify(&desc, amount),
+                description: desc,
+                amount,
+                quantity: table::to_count(row.get(quantity)),
+                currency: if line_ccy.is_empty() {
+                    invoice_ccy.clone()
+                } else {
+                    line_ccy
+                },
+            });
+        }
+
+        if lines.is_empty() {
+            return Err(IngestError::Parse(
+                "no charge lines found in the Checkout.com invoice".to_string(),
+            ));
+        }
+
+        let mut summary = table::summarize(&lines, invoice_ccy);
+        summary.invoice_ref = invoice_ref;
+        summary.period_start = start;
+        summary.period_end = end;
+        Ok(ParsedInvoice { summary, lines })
+    }
+}
+
+/// Map a Checkout.com invoice line to its [`LineKind`]. Substring match on the lowercased
+/// `"<fee type>: <description>"`, most-specific first.
+///
+/// Unknown positive lines default to [`LineKind::Periodic`], as on the Adyen invoice: what the
+/// invoice bills beyond the netted settlement fees is overwhelmingly account-level charges.
+fn classify(description: &str, amount: f64) -> LineKind {
+    let has = |needle: &str| description.contains(needle);
+
+    // 1. Credits: rebates, refunded fees and adjustments in the merchant's favour.
+    if has("credit") || has("rebate") || has("refund") || has("reversal") || amount < 0.0 {
+        return LineKind::Credit;
+    }
+
+    // 2. Processed volume is the amortization denominator, not a fee.
+    if has("processed volume") || has("processing volume") || has("turnover") {
+        return LineKind::Volume;
+    }
+
+    // 3. The fees netted at settlement, which the Financial Actions report already itemizes per
+    //    payment (see `connectors/checkout.rs`): interchange, scheme fees, and Checkout's own
+    //    `Premium*` / `Blended*` rates and fixed authorization / authentication / card
+    //    verification fees.
+    if has("interchange")
+        || has("scheme")
+        || has("premium")
+        || has("blended")
+        || has("authorization fixed fee")
+        || has("authentication fixed fee")
+        || has("card verification")
+    {
+        return LineKind::AlreadyModeled;
+    }
+
+    // 4. Charged once per screened / tokenised payment.
+    if has("fraud detection") || has("network token") || has("per transaction") {
+        return LineKind::FlatPerTxn;
+    }
+
+    // 5. Everything else is periodic: chargeback fees, account updater, payouts, platform and
+    //    minimum-monthly fees.
+    LineKind::Periodic
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::reduce::{reduce_to_addon, VolumeFallback};
+    use super::*;
+
+    const INVOICE: &str = "\
+Invoice Number,Period Start,Period End,Fee Type,Description,Quantity,Amount,Currency\n\
+INV-CKO-2025-03,2025-03-01,2025-03-31,Processed Volume,Card payments,12000,1500000.00,GBP\n\
+INV-CKO-2025-03,2025-03-01,2025-03-31,Fraud Detection,Fraud Detection Pro,12000,360.00,GBP\n\
+INV-CKO-2025-03,2025-03-01,2025-03-31,Network Token,Network token provisioning,8000,80.00,GBP\n\
+INV-CKO-2025-03,2025-03-01,2025-03-31,Chargeback fee,,14,210.00,GBP\n\
+INV-CKO-2025-03,2025-03-01,2025-03-31,Account Updater,Real-time account updater,300,45.00,GBP\n\
+INV-CKO-2025-03,2025-03-01,2025-03-31,Minimum Fee,Minimum monthly fee top-up,1,500.00,GBP\n\
+INV-CKO-2025-03,2025-03-01,2025-03-31,Credit,Service credit,1,-100.00,GBP\n\
+INV-CKO-2025-03,2025-03-01,2025-03-31,Total,,,1095.00,GBP\n";
+
+    #[test]
+    fn classifies_checkout_invoice_lines() {
+        assert_eq!(
+            classify("fraud detection: fraud detection pro", 360.0),
+            LineKind::FlatPerTxn
+        );
+        assert_eq!(classify("chargeback fee", 210.0), LineKind::Periodic);
+        assert_eq!(
+            classify("minimum fee: minimum monthly fee top-up", 500.0),
+            LineKind::Periodic
+        );
+        assert_eq!(
+            classify("processed volume: card payments", 1_500_000.0),
+            LineKind::Volume
+        );
+        assert_eq!(
+            classify("interchange: interchange fees", 9000.0),
+            LineKind::AlreadyModeled
+        );
+        assert_eq!(
+            classify("premium: premium variable fee", 2400.0),
+            LineKind::AlreadyModeled
+        );
+        assert_eq!(classify("credit: service credit", -100.0), LineKind::Credit);
+    }
+
+    #[test]
+    fn reads_the_invoice_export() {
+        let parsed = CheckoutInvoiceSource::new()
+            .parse_invoice(INVOICE.as_bytes())
+            .unwrap();
+        let s = &parsed.summary;
+        assert_eq!(s.invoice_ref, "INV-CKO-2025-03");
+        assert_eq!(s.currency, "GBP");
+        assert_eq!(s.txn_count, Some(12_000));
+        assert_eq!(s.card_volume, Some(1_500_000.0));
+        assert_eq!(s.period_start, chrono::NaiveDate::from_ymd_opt(2025, 3, 1));
+        assert_eq!(s.period_end, chrono::NaiveDate::from_ymd_opt(2025, 3, 31));
+        // The invoice only bills what settlement didn't net, so its subtotal excludes those fees.
+        assert!(!s.includes_settled_fees);
+        // The "Total" row is not a line.
+        assert_eq!(parsed.lines.len(), 7);
+        assert!((s.subtotal_ex_tax.unwrap() - 1095.0).abs() < 1e-9);
+
+        let addon = reduce_to_addon(&parsed, VolumeFallback::default());
+        // Flat: (360 + 80) / 12_000.
+        assert!((addon.fixed_addon - 440.0 / 12_000.0).abs() < 1e-9);
+        // pct: (210 + 45 + 500 − 100) / 1.5M · 1e4.
+        let expected = 655.0 / 1_500_000.0 * 10_000.0;
+        assert!((addon.pct_addon_bps - expected).abs() < 1e-9);
+    }
+}
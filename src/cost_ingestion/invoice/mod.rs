@@ -23,11 +23,15 @@
 //! serving overlay, and reconciliation are written once for all connectors.
 
 pub mod adyen;
+pub mod braintree;
+pub mod checkout;
 pub mod pipeline;
 pub mod reconcile;
 pub mod reduce;
 pub mod source;
 pub mod store;
+pub mod stripe;
+mod table;
 pub mod types;
 
 pub use pipeline::{ingest_invoice_bytes, InvoiceOutcome};
@@ -79,7 +79,11 @@ pub async fn ingest_invoice_bytes(
     // Only reach for ClickHouse when the invoice itself does not state both denominators.
     let need_fallback = parsed.summary.card_volume.is_none() || parsed.summary.txn_count.is_none();
     let fallback = if need_fallback {
-        settled_volume(clickhouse, &connector, account, merchant_id)
+        let period = (
+            parsed.summary.period_start.map(|d| d.to_string()),
+            parsed.summary.period_end.map(|d| d.to_string()),
+        );
+        settled_volume(clickhouse, &connector, account, merchant_id, period)
             .await
             .unwrap_or_default()
     } else {
@@ -186,13 +190,15 @@ fn build_breakdown(parsed: &ParsedInvoice, txn_count: u64) -> (Vec<InvoiceLineGr
 }
 
 /// Settled volume + transaction count for `(merchant, connector, account)` from the fit window of
-/// `cost_daily_stats` — the amortization denominators when the invoice is silent. Best-effort: any
-/// error yields the zero fallback (which zeroes only the affected add-on term).
+/// `cost_daily_stats`, narrowed to the invoice's billing period when it states one — the
+/// amortization denominators when the invoice is silent. Best-effort: any error yields the zero
+/// fallback (which zeroes only the affected add-on term).
 async fn settled_volume(
     cfg: &ClickHouseAnalyticsConfig,
     connector: &str,
     account: &str,
     merchant_id: &str,
+    (period_start, period_end): (Option<String>, Option<String>),
 ) -> Result<VolumeFallback, IngestError> {
     // `sx` = Σ gross (turnover), `n` = transaction count. Account filter is optional: a blank
     // `account` sums across every account under the merchant/connector.
@@ -201,12 +207,6 @@ async fn settled_volume(
     } else {
         " AND account = {account:String}".to_string()
     };
-    let sql = format!(
-        "SELECT sum(sx), sum(n) FROM {db}.cost_daily_stats FINAL \
-         WHERE connector = {{connector:String}} AND merchant_id = {{merchant_id:String}}{account_pred} \
-         FORMAT TSV",
-        db = cfg.database,
-    );
     let mut params = vec![
         ("connector", connector.to_string()),
         ("merchant_id", merchant_id.to_string()),
@@ -214,6 +214,13 @@ async fn settled_volume(
     if !account.is_empty() {
         params.push(("account", account.to_string()));
     }
+    let period_pred = period_predicate(period_start, period_end, &mut params);
+    let sql = format!(
+        "SELECT sum(sx), sum(n) FROM {db}.cost_daily_stats FINAL \
+         WHERE connector = {{connector:String}} AND merchant_id = {{merchant_id:String}}{account_pred}{period_pred} \
+         FORMAT TSV",
+        db = cfg.database,
+    );
     let out = exec(cfg, &sql, &params).await?;
     let mut cols = out.trim().split('\t');
     let card_volume = cols
@@ -230,6 +237,25 @@ async fn settled_volume(
     })
 }
 
+/// The `txn_date` window for an invoice's billing period, pushing its bounds onto `params`. Either
+/// bound may be absent (Adyen invoices state none), which leaves that side of the window open.
+pub(super) fn period_predicate(
+    period_start: Option<String>,
+    period_end: Option<String>,
+    params: &mut Vec<(&str, String)>,
+) -> String {
+    let mut pred = String::new();
+    if let Some(start) = period_start {
+        pred.push_str(" AND txn_date >= {period_start:Date}");
+        params.push(("period_start", start));
+    }
+    if let Some(end) = period_end {
+        pred.push_str(" AND txn_date <= {period_end:Date}");
+        params.push(("period_end", end));
+    }
+    pred
+}
+
 /// POST a `{name:Type}`-parameterized query to ClickHouse (same transport as `fit`/`serving`).
 pub(super) async fn exec(
     cfg: &ClickHouseAnalyticsConfig,
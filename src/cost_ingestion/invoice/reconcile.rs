@@ -10,32 +10,49 @@
 use crate::config::ClickHouseAnalyticsConfig;
 use crate::cost_ingestion::types::IngestError;
 
-use super::pipeline::exec;
-use super::store;
+use super::pipeline::{exec, period_predicate};
+use super::store::{self, StoredAddon};
 
 /// The reconciliation of one merchant's stored add-on against its invoice.
 #[derive(Debug, Clone, serde::Serialize)]
 pub struct Reconciliation {
     pub connector: String,
-    /// Invoice subtotal excluding taxes — the true all-in cost.
+    /// Invoice subtotal excluding taxes, as the invoice states it.
     pub invoice_subtotal: f64,
+    /// The true all-in cost: the subtotal, plus the settled book's fees when the invoice doesn't
+    /// bill them (the connector netted them at settlement — Checkout.com, for one).
+    pub all_in_cost: f64,
     /// What the OLS fit alone captured (Σ of the four fee columns over the settled book).
     pub model_captured: f64,
     /// The add-on's contribution: `pct_addon_bps/1e4·volume + fixed_addon·count`.
     pub addon_contribution: f64,
     /// `model_captured + addon_contribution` — the model's all-in prediction.
     pub model_all_in: f64,
-    /// `invoice_subtotal - model_all_in` — the remaining unexplained gap (positive ⇒ still under).
+    /// `all_in_cost - model_all_in` — the remaining unexplained gap (positive ⇒ still under).
     pub residual: f64,
-    /// Share of the true cost captured before the add-on (`model_captured / invoice_subtotal`).
+    /// Share of the true cost captured before the add-on (`model_captured / all_in_cost`).
     pub coverage_before: f64,
-    /// Share of the true cost captured with the add-on (`model_all_in / invoice_subtotal`).
+    /// Share of the true cost captured with the add-on (`model_all_in / all_in_cost`).
     pub coverage_after: f64,
 }
 
+/// The settled book the model priced, from `cost_daily_stats`.
+#[derive(Debug, Clone, Copy, Default)]
+struct SettledBook {
+    /// Σ sy — the fee cost the fit captured.
+    fees: f64,
+    /// Σ sx — turnover.
+    volume: f64,
+    /// Σ n — transaction count.
+    count: f64,
+}
+
 /// Reconcile every stored add-on for `merchant_id` against its invoice. Returns one row per
 /// connector that has both an add-on and a stated subtotal. A connector whose add-on lacks a
 /// subtotal (the invoice didn't state one) is skipped — there is nothing to tie back to.
+///
+/// The settled book is windowed to the invoice's billing period when the invoice states one, so a
+/// monthly statement is compared with that month's settlements rather than the whole history.
 pub async fn reconcile_merchant(
     cfg: &ClickHouseAnalyticsConfig,
     merchant_id: &str,
@@ -43,49 +60,115 @@ pub async fn reconcile_merchant(
     let addons = store::list(merchant_id).await?;
     let mut out = Vec::new();
     for (connector, a) in addons {
-        let Some(subtotal) = a.subtotal_ex_tax.filter(|s| *s > 0.0) else {
+        if a.subtotal_ex_tax.filter(|s| *s > 0.0).is_none() {
             continue;
-        };
+        }
 
         // The settled book the model priced: Σ sy = captured fee cost, Σ sx = turnover, Σ n = count.
+        let mut params = vec![
+            ("connector", connector.clone()),
+            ("merchant_id", merchant_id.to_string()),
+        ];
+        let period_pred =
+            period_predicate(a.period_start.clone(), a.period_end.clone(), &mut params);
         let sql = format!(
             "SELECT sum(sy), sum(sx), sum(n) FROM {db}.cost_daily_stats FINAL \
-             WHERE connector = {{connector:String}} AND merchant_id = {{merchant_id:String}} \
+             WHERE connector = {{connector:String}} AND merchant_id = {{merchant_id:String}}{period_pred} \
              FORMAT TSV",
             db = cfg.database,
         );
-        let params = [
-            ("connector", connector.clone()),
-            ("merchant_id", merchant_id.to_string()),
-        ];
         let row = exec(cfg, &sql, &params).await?;
-        let mut cols = row.trim().split('\t');
-        let model_captured = cols
-            .next()
-            .and_then(|s| s.trim().parse::<f64>().ok())
-            .unwrap_or(0.0);
-        let volume = cols
-            .next()
-            .and_then(|s| s.trim().parse::<f64>().ok())
-            .unwrap_or(0.0);
-        let count = cols
-            .next()
-            .and_then(|s| s.trim().parse::<f64>().ok())
-            .unwrap_or(0.0);
-
-        let addon_contribution = a.pct_addon_bps / 10_000.0 * volume + a.fixed_addon * count;
-        let model_all_in = model_captured + addon_contribution;
+        let mut cols = row
+            .trim()
+            .split('\t')
+            .map(|s| s.trim().parse::<f64>().unwrap_or(0.0));
+        let settled = SettledBook {
+            fees: cols.next().unwrap_or(0.0),
+            volume: cols.next().unwrap_or(0.0),
+            count: cols.next().unwrap_or(0.0),
+        };
 
-        out.push(Reconciliation {
-            connector,
-            invoice_subtotal: subtotal,
-            model_captured,
-            addon_contribution,
-            model_all_in,
-            residual: subtotal - model_all_in,
-            coverage_before: model_captured / subtotal,
-            coverage_after: model_all_in / subtotal,
-        });
+        out.extend(reconcile(connector, &a, settled));
     }
     Ok(out)
 }
+
+/// Tie one add-on back to its invoice over the settled book it covers.
+fn reconcile(connector: String, a: &StoredAddon, settled: SettledBook) -> Option<Reconciliation> {
+    let subtotal = a.subtotal_ex_tax.filter(|s| *s > 0.0)?;
+    let all_in_cost = if a.includes_settled_fees {
+        subtotal
+    } else {
+        subtotal + settled.fees
+    };
+    let model_captured = settled.fees;
+    let addon_contribution =
+        a.pct_addon_bps / 10_000.0 * settled.volume + a.fixed_addon * settled.count;
+    let model_all_in = model_captured + addon_contribution;
+
+    Some(Reconciliation {
+        connector,
+        invoice_subtotal: subtotal,
+        all_in_cost,
+        model_captured,
+        addon_contribution,
+        model_all_in,
+        residual: all_in_cost - model_all_in,
+        coverage_before: model_captured / all_in_cost,
+        coverage_after: model_all_in / all_in_cost,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn addon(includes_settled_fees: bool) -> StoredAddon {
+        StoredAddon {
+            pct_addon_bps: 4.0,
+            fixed_addon: 0.02,
+            invoice_ref: "INV-1".to_string(),
+            subtotal_ex_tax: Some(1_000.0),
+            includes_settled_fees,
+            card_volume: Some(1_000_000.0),
+            txn_count: Some(10_000),
+            currency: "EUR".to_string(),
+            period_start: Some("2025-03-01".to_string()),
+            period_end: Some("2025-03-31".to_string()),
+            updated_at: String::new(),
+        }
+    }
+
+    const BOOK: SettledBook = SettledBook {
+        fees: 500.0,
+        volume: 1_000_000.0,
+        count: 10_000.0,
+    };
+
+    #[test]
+    fn invoice_that_bills_settled_fees_is_the_all_in_cost() {
+        let r = reconcile("adyen".to_string(), &addon(true), BOOK).unwrap();
+        assert_eq!(r.all_in_cost, 1_000.0);
+        // 4 bps of 1M + 0.02 · 10k = 600.
+        assert!((r.addon_contribution - 600.0).abs() < 1e-9);
+        assert!((r.residual - (1_000.0 - 1_100.0)).abs() < 1e-9);
+        assert!((r.coverage_before - 0.5).abs() < 1e-9);
+    }
+
+    #[test]
+    fn settled_fees_netted_at_settlement_are_added_back() {
+        let r = reconcile("checkout".to_string(), &addon(false), BOOK).unwrap();
+        assert_eq!(r.invoice_subtotal, 1_000.0);
+        assert_eq!(r.all_in_cost, 1_500.0);
+        // Only the add-on's own accuracy is left in the residual: 1000 billed vs 600 added.
+        assert!((r.residual - 400.0).abs() < 1e-9);
+        assert!((r.coverage_before - 500.0 / 1_500.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn skips_an_invoice_without_a_subtotal() {
+        let mut a = addon(true);
+        a.subtotal_ex_tax = None;
+        assert!(reconcile("stripe".to_string(), &a, BOOK).is_none());
+    }
+}
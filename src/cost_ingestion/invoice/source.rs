@@ -9,6 +9,9 @@ use std::collections::HashMap;
 use std::sync::Arc;
 
 use super::adyen::AdyenInvoiceSource;
+use super::braintree::BraintreeInvoiceSource;
+use super::checkout::CheckoutInvoiceSource;
+use super::stripe::StripeInvoiceSource;
 use super::types::ParsedInvoice;
 use crate::cost_ingestion::types::IngestError;
 
@@ -34,11 +37,20 @@ impl InvoiceRegistry {
     /// Registry seeded with every built-in invoice connector. Adding a connector = one line here.
     pub fn with_builtins() -> Self {
         let mut sources: HashMap<&'static str, Arc<dyn InvoiceSource>> = HashMap::new();
-        let adyen: Arc<dyn InvoiceSource> = Arc::new(AdyenInvoiceSource::new());
-        sources.insert(adyen.connector(), adyen);
+        Self::register(&mut sources, Arc::new(AdyenInvoiceSource::new()));
+        Self::register(&mut sources, Arc::new(BraintreeInvoiceSource::new()));
+        Self::register(&mut sources, Arc::new(CheckoutInvoiceSource::new()));
+        Self::register(&mut sources, Arc::new(StripeInvoiceSource::new()));
         Self { sources }
     }
 
+    fn register(
+        sources: &mut HashMap<&'static str, Arc<dyn InvoiceSource>>,
+        source: Arc<dyn InvoiceSource>,
+    ) {
+        sources.insert(source.connector(), source);
+    }
+
     /// Resolve a source by connector id, or `UnknownConnector` if none is registered.
     pub fn get(&self, connector: &str) -> Result<Arc<dyn InvoiceSource>, IngestError> {
         self.sources
@@ -28,6 +28,10 @@ pub struct StoredAddon {
     pub invoice_ref: String,
     /// Invoice subtotal excluding taxes — the "true all-in cost" reconciliation ties back to.
     pub subtotal_ex_tax: Option<f64>,
+    /// Whether the subtotal includes the fees the settlement report already carries. Add-ons
+    /// stored before this was recorded all came from Adyen invoices, which do.
+    #[serde(default = "default_true")]
+    pub includes_settled_fees: bool,
     /// Card turnover the periodic fees were amortized over.
     pub card_volume: Option<f64>,
     /// Settled transaction count the flat fees were blended over.
@@ -56,6 +60,7 @@ impl StoredAddon {
             fixed_addon: addon.fixed_addon,
             invoice_ref: summary.invoice_ref.clone(),
             subtotal_ex_tax: summary.subtotal_ex_tax,
+            includes_settled_fees: summary.includes_settled_fees,
             card_volume: summary.card_volume,
             txn_count: summary.txn_count,
             currency: summary.currency.clone(),
@@ -66,6 +71,10 @@ impl StoredAddon {
     }
 }
 
+fn default_true() -> bool {
+    true
+}
+
 fn addon_name(merchant_id: &str, connector: &str) -> String {
     format!("cost_invoice_addon::{merchant_id}::{connector}")
 }
@@ -0,0 +1,317 @@
+//! Stripe `InvoiceSource`.
+//!
+//! Stripe doesn't send a line-item invoice for what it takes: every fee is debited from the balance,
+//! so the monthly bill *is* the balance. We read the **Itemized balance change from activity** export
+//! (Dashboard → Reports → Balance, or the `balance_change_from_activity.itemized.*` report type), one
+//! row per balance transaction, keyed by its `reporting_category`:
+//!
+//!   * `charge` — a payment. Its `gross` is turnover ([`LineKind::Volume`], counted), its `fee` the
+//!     per-payment fee the Payments Fee Report already prices ([`LineKind::AlreadyModeled`]).
+//!   * `fee` — a fee Stripe charges on its own balance transaction: Radar screening, 3DS, card
+//!     account updater, Billing, Connect, the minimum-monthly top-up and, on IC+ pricing, the
+//!     network costs. The cost is `-net`, classified by its description ([`classify`]).
+//!   * `dispute` / `dispute_reversal` — the dispute fee and its return.
+//!   * `refund` — a returned payment fee, when the account's pricing returns one.
+//!
+//! Everything else (payouts, transfers, top-ups, adjustments, the `tax` on Stripe's fees) moves
+//! money but isn't a processing cost, so it is skipped. Payment, dispute and refund rows are summed
+//! into one line per category; `fee` rows stay one line each so the dashboard shows which product
+//! billed what.
+//!
+//! The billing period is the span of the rows' `created_utc` dates.
+
+use super::source::InvoiceSource;
+use super::table::{self, Table};
+use super::types::{InvoiceLine, LineKind, ParsedInvoice};
+use crate::cost_ingestion::types::IngestError;
+
+pub struct StripeInvoiceSource;
+
+impl StripeInvoiceSource {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl Default for StripeInvoiceSource {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl InvoiceSource for StripeInvoiceSource {
+    fn connector(&self) -> &'static str {
+        "stripe"
+    }
+
+    fn parse_invoice(&self, bytes: &[u8]) -> Result<ParsedInvoice, IngestError> {
+        let table = Table::read(bytes)?;
+        let category = table.require(&["reporting_category"])?;
+        let gross = table.require(&["gross"])?;
+        let fee = table.require(&["fee"])?;
+        let currency = table.require(&["currency"])?;
+        let net = table.column(&["net"]);
+        let description = table.column(&["description"]);
+        let created = table.column(&["created_utc", "created"]);
+
+        let mut payments = Sum::default();
+        let mut payment_fees = 0.0;
+        let mut dispute_fees = Sum::default();
+        let mut dispute_returns = 0.0;
+        let mut refund_returns = 0.0;
+        let mut fee_lines = Vec::new();
+        let mut invoice_ccy = String::new();
+        let mut period: Option<(chrono::NaiveDate, chrono::NaiveDate)> = None;
+
+        for row in table.rows() {
+            let gross_amt = table::to_float(row.get(Some(gross)));
+            let fee_amt = table::to_float(row.get(Some(fee)));
+            match row.get(Some(category)).to_lowercase().as_str() {
+                "charge" | "payment" => {
+                    payments.add(gross_amt);
+                    payment_fees += fee_amt;
+                }
+                "fee" => {
+                    // A fee row debits the balance: `net` is negative, and it is the whole cost
+                    // (`gross - fee`, whichever of the two the report carried it in).
+                    let cost = -net
+                        .map(|i| table::to_float(row.get(Some(i))))
+                        .unwrap_or(gross_amt - fee_amt);
+                    let desc = row.get(description).to_lowercase();
+                    let desc = if desc.is_empty() {
+                        "stripe fee".to_string()
+                    } else {
+                        desc
+                    };
+                    fee_lines.push(InvoiceLine {
+                        kind: classify(&desc, cost),
+                        description: desc,
+                        amount: cost,
+                        quantity: 0,
+                        currency: row.get(Some(currency)).to_uppercase(),
+                    });
+                }
+                "dispute" => dispute_fees.add(fee_amt),
+                "dispute_reversal" => dispute_returns -= fee_amt.abs(),
+                "refund" => refund_returns -= fee_amt.abs(),
+                _ => continue,
+            }
+            if invoice_ccy.is_empty() {
+                invoice_ccy = row.get(Some(currency)).to_uppercase();
+            }
+            if let Some(d) = table::parse_date(row.get(created)) {
+                period = Some(period.map_or((d, d), |(s, e)| (s.min(d), e.max(d))));
+            }
+        }
+
+        let line = |description: &str, kind, amount, quantity| InvoiceLine {
+            description: description.to_string(),
+            kind,
+            amount,
+            quantity,
+            currency: invoice_ccy.clone(),
+        };
+        let mut lines = Vec::new();
+        if payments.count > 0 {
+            lines.push(line(
+                "payments",
+                LineKind::Volume,
+                payments.amount,
+                payments.count,
+            ));
+            lines.push(line(
+                "payment fees",
+                LineKind::AlreadyModeled,
+                payment_fees,
+                payments.count,
+            ));
+        }
+        if dispute_fees.count > 0 {
+            lines.push(line(
+                "dispute fees",
+                LineKind::Periodic,
+                dispute_fees.amount,
+                dispute_fees.count,
+            ));
+        }
+        if dispute_returns != 0.0 {
+            lines.push(line(
+                "dispute fee returns",
+                LineKind::Credit,
+                dispute_returns,
+                0,
+            ));
+        }
+        if refund_returns != 0.0 {
+            lines.push(line(
+                "refunded payment fees",
+                LineKind::Credit,
+                refund_returns,
+                0,
+            ));
+        }
+        lines.extend(fee_lines);
+
+        if lines.is_empty() {
+            return Err(IngestError::Parse(
+                "no payment or fee rows found in the Stripe balance export".to_string(),
+            ));
+        }
+
+        let mut summary = table::summarize(&lines, invoice_ccy);
+        summary.period_start = period.map(|(s, _)| s);
+        summary.period_end = period.map(|(_, e)| e);
+        Ok(ParsedInvoice { summary, lines })
+    }
+}
+
+/// A running total and the rows that went into it.
+#[derive(Default)]
+struct Sum {
+    amount: f64,
+    count: u64,
+}
+
+impl Sum {
+    fn add(&mut self, amount: f64) {
+        self.amount += amount;
+        self.count += 1;
+    }
+}
+
+/// Map a Stripe `fee` row's description to its [`LineKind`]. Stripe names these rows
+/// `"<product> (<period>): <detail>"`, e.g. `"Radar (2025-03-01 - 2025-03-31): Radar for Fraud
+/// Teams"`; substring match on the lowercased description, most-specific first.
+///
+/// Unknown positive fees default to [`LineKind::Periodic`], as on the Adyen invoice: a Stripe
+/// product billed on its own balance transaction is a product subscription far more often than a
+/// per-payment charge, and a misclassification shows up as reconciliation residual.
+fn classify(description: &str, amount: f64) -> LineKind {
+    let has = |needle: &str| description.contains(needle);
+
+    // 1. Credits: a fee refund or rebate, or any negative cost.
+    if has("refund") || has("rebate") || has("credit") || has("reversal") || amount < 0.0 {
+        return LineKind::Credit;
+    }
+
+    // 2. IC+ network costs are billed as `fee` rows, but the Payments Fee Report carries them per
+    //    card bucket already — never add them twice.
+    if has("interchange")
+        || has("network cost")
+        || has("network fee")
+        || has("scheme fee")
+        || has("card payments")
+    {
+        return LineKind::AlreadyModeled;
+    }
+
+    // 3. Charged once per screened / authenticated / optimised payment.
+    if has("radar")
+        || has("3d secure")
+        || has("3ds")
+        || has("adaptive acceptance")
+        || has("authorization boost")
+        || has("network token")
+    {
+        return LineKind::FlatPerTxn;
+    }
+
+    // 4. Everything else is periodic: Billing, Connect, Tax, Identity, card account updater, the
+    //    minimum-monthly top-up.
+    LineKind::Periodic
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::reduce::{reduce_to_addon, VolumeFallback};
+    use super::*;
+
+    const EXPORT: &str = "\
+balance_transaction_id,created_utc,currency,gross,fee,net,reporting_category,description\n\
+txn_1,2025-03-01 09:12:44,usd,100.00,3.20,96.80,charge,Order 1001\n\
+txn_2,2025-03-02 10:00:01,usd,50.00,1.75,48.25,charge,Order 1002\n\
+txn_3,2025-03-03 11:20:00,usd,250.00,7.55,242.45,charge,Order 1003\n\
+txn_4,2025-03-04 08:00:00,usd,-100.00,0.00,-100.00,refund,Order 1001\n\
+txn_5,2025-03-10 12:00:00,usd,-50.00,15.00,-65.00,dispute,Order 1002\n\
+txn_6,2025-03-20 12:00:00,usd,50.00,-15.00,65.00,dispute_reversal,Order 1002\n\
+txn_7,2025-03-31 23:59:59,usd,-0.21,0.00,-0.21,fee,Radar (2025-03-01 - 2025-03-31): Radar for Fraud Teams\n\
+txn_8,2025-03-31 23:59:59,usd,-25.00,0.00,-25.00,fee,Billing (2025-03-01 - 2025-03-31): Billing usage\n\
+txn_9,2025-03-31 23:59:59,usd,-1.10,0.00,-1.10,fee,Card network costs (2025-03-01 - 2025-03-31)\n\
+po_1,2025-03-15 00:00:00,usd,-300.00,0.00,-300.00,payout,STRIPE PAYOUT\n\
+tx_10,2025-03-31 23:59:59,usd,-1.00,0.00,-1.00,tax,Tax on Stripe fees\n";
+
+    #[test]
+    fn classifies_stripe_fee_products() {
+        assert_eq!(
+            classify(
+                "radar (2025-03-01 - 2025-03-31): radar for fraud teams",
+                0.21
+            ),
+            LineKind::FlatPerTxn
+        );
+        assert_eq!(
+            classify("billing (2025-03-01 - 2025-03-31): billing usage", 25.0),
+            LineKind::Periodic
+        );
+        assert_eq!(
+            classify("connect (2025-03-01 - 2025-03-31): active accounts", 2.0),
+            LineKind::Periodic
+        );
+        assert_eq!(
+            classify("card network costs (2025-03-01 - 2025-03-31)", 1.1),
+            LineKind::AlreadyModeled
+        );
+        assert_eq!(classify("radar fee refund", -0.05), LineKind::Credit);
+    }
+
+    #[test]
+    fn reads_the_itemized_balance_export() {
+        let parsed = StripeInvoiceSource::new()
+            .parse_invoice(EXPORT.as_bytes())
+            .unwrap();
+        let s = &parsed.summary;
+        assert_eq!(s.currency, "USD");
+        assert_eq!(s.txn_count, Some(3));
+        assert_eq!(s.card_volume, Some(400.0));
+        assert!(s.includes_settled_fees);
+        assert_eq!(s.period_start, chrono::NaiveDate::from_ymd_opt(2025, 3, 1));
+        assert_eq!(s.period_end, chrono::NaiveDate::from_ymd_opt(2025, 3, 31));
+
+        let amount = |kind: LineKind| -> f64 {
+            parsed
+                .lines
+                .iter()
+                .filter(|l| l.kind == kind)
+                .map(|l| l.amount)
+                .sum()
+        };
+        // Payment fees 12.50 + the IC+ network cost 1.10 are already priced by the fee report.
+        assert!((amount(LineKind::AlreadyModeled) - 13.60).abs() < 1e-9);
+        assert!((amount(LineKind::FlatPerTxn) - 0.21).abs() < 1e-9);
+        // The dispute fee and Billing; the returned dispute fee nets it out.
+        assert!((amount(LineKind::Periodic) - 40.0).abs() < 1e-9);
+        assert!((amount(LineKind::Credit) + 15.0).abs() < 1e-9);
+        // Payouts and the tax on fees are not costs.
+        assert!(parsed
+            .lines
+            .iter()
+            .all(|l| !l.description.contains("payout") && !l.description.contains("tax")));
+        // Subtotal ex-tax = modeled + added fees.
+        assert!((s.subtotal_ex_tax.unwrap() - 38.81).abs() < 1e-9);
+
+        let addon = reduce_to_addon(&parsed, VolumeFallback::default());
+        assert!((addon.fixed_addon - 0.07).abs() < 1e-9);
+        assert!((addon.pct_addon_bps - 25.0 / 400.0 * 10_000.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn rejects_an_export_without_costs() {
+        let only_payouts = "reporting_category,gross,fee,currency\npayout,-10.00,0.00,usd\n";
+        assert!(StripeInvoiceSource::new()
+            .parse_invoice(only_payouts.as_bytes())
+            .is_err());
+        assert!(StripeInvoiceSource::new()
+            .parse_invoice(b"description,amount\nfee,1.00\n")
+            .is_err());
+    }
+}
@@ -0,0 +1,160 @@
+//! Shared reading for the CSV invoice / statement exports (Stripe, Checkout.com, Braintree).
+//!
+//! Those three connectors export their bill as a flat table, so the mechanics — header lookup by
+//! label, cell access, the summary totals derived from the classified lines — live here once. What
+//! stays in each connector's file is the part that differs: which columns it has, which rows are
+//! fees at all, and how a fee's description maps onto a [`LineKind`].
+
+use chrono::NaiveDate;
+
+use super::types::{InvoiceLine, InvoiceSummary, LineKind};
+use crate::cost_ingestion::types::IngestError;
+
+/// A buffered CSV export. Invoices are a few hundred lines, so — like the Adyen CSV path — reading
+/// the whole table up front is fine.
+pub(super) struct Table {
+    header: Vec<String>,
+    rows: Vec<Vec<String>>,
+}
+
+impl Table {
+    pub(super) fn read(bytes: &[u8]) -> Result<Self, IngestError> {
+        let bytes = bytes.strip_prefix(b"\xEF\xBB\xBF").unwrap_or(bytes);
+        let mut rdr = csv::ReaderBuilder::new()
+            .flexible(true)
+            .trim(csv::Trim::All)
+            .from_reader(bytes);
+        let header = rdr
+            .headers()
+            .map_err(|e| IngestError::Parse(e.to_string()))?
+            .iter()
+            .map(str::to_string)
+            .collect();
+        let mut rows = Vec::new();
+        for record in rdr.records() {
+            let record = record.map_err(|e| IngestError::Parse(e.to_string()))?;
+            rows.push(record.iter().map(str::to_string).collect());
+        }
+        Ok(Self { header, rows })
+    }
+
+    /// Index of the first of `labels` present in the header (case-insensitive). Exports rename
+    /// columns between versions, so a column is looked up by every label it has gone by.
+    pub(super) fn column(&self, labels: &[&str]) -> Option<usize> {
+        labels
+            .iter()
+            .find_map(|l| self.header.iter().position(|h| h.eq_ignore_ascii_case(l)))
+    }
+
+    /// Like [`Self::column`], but the invoice can't be read without it.
+    pub(super) fn require(&self, labels: &[&str]) -> Result<usize, IngestError> {
+        self.column(labels).ok_or_else(|| {
+            IngestError::Parse(format!("invoice missing {} column", labels.join("/")))
+        })
+    }
+
+    pub(super) fn rows(&self) -> impl Iterator<Item = Row<'_>> {
+        self.rows.iter().map(|cells| Row(cells))
+    }
+}
+
+/// One data row of a [`Table`].
+pub(super) struct Row<'a>(&'a [String]);
+
+impl Row<'_> {
+    /// The trimmed cell at `i`; `""` when the row is short or the column is absent.
+    pub(super) fn get(&self, i: Option<usize>) -> &str {
+        i.and_then(|i| self.0.get(i)).map_or("", String::as_str)
+    }
+}
+
+/// The summary totals an export doesn't state, derived from its classified lines — the same
+/// derivation as the Adyen CSV path:
+///  - `subtotal_ex_tax` = every fee-and-credit line (everything except turnover),
+///  - `card_volume` = the `Volume` lines,
+///  - `txn_count` = the count billed on the `Volume` lines, else the largest `FlatPerTxn` quantity.
+pub(super) fn summarize(lines: &[InvoiceLine], currency: String) -> InvoiceSummary {
+    let volume = || lines.iter().filter(|l| l.kind == LineKind::Volume);
+    let subtotal_ex_tax: f64 = lines
+        .iter()
+        .filter(|l| l.kind != LineKind::Volume)
+        .map(|l| l.amount)
+        .sum();
+    let card_volume: f64 = volume().map(|l| l.amount).sum();
+    let volume_count: u64 = volume().map(|l| l.quantity).sum();
+    let txn_count = if volume_count > 0 {
+        volume_count
+    } else {
+        lines
+            .iter()
+            .filter(|l| l.kind == LineKind::FlatPerTxn)
+            .map(|l| l.quantity)
+            .max()
+            .unwrap_or(0)
+    };
+
+    InvoiceSummary {
+        invoice_ref: String::new(),
+        account: String::new(),
+        card_volume: (card_volume > 0.0).then_some(card_volume),
+        txn_count: (txn_count > 0).then_some(txn_count),
+        subtotal_ex_tax: Some(subtotal_ex_tax),
+        includes_settled_fees: lines.iter().any(|l| l.kind == LineKind::AlreadyModeled),
+        currency,
+        period_start: None,
+        period_end: None,
+    }
+}
+
+/// Parse a money cell; strips thousands separators and a leading currency symbol, blanks/garbage →
+/// `0.0`.
+pub(super) fn to_float(s: &str) -> f64 {
+    s.trim()
+        .trim_start_matches(['$', '€', '£'])
+        .replace(',', "")
+        .parse::<f64>()
+        .unwrap_or(0.0)
+}
+
+/// Parse a count cell; blanks/garbage → `0`.
+pub(super) fn to_count(s: &str) -> u64 {
+    s.trim().replace(',', "").parse::<u64>().unwrap_or(0)
+}
+
+/// The date at the start of a date or timestamp cell: ISO (`2025-03-31`, `2025-03-31 12:00:00`,
+/// `2025-03-31T12:00:00Z`) or US `03/31/2025`.
+pub(super) fn parse_date(s: &str) -> Option<NaiveDate> {
+    let s = s.trim();
+    s.get(..10)
+        .and_then(|d| NaiveDate::parse_from_str(d, "%Y-%m-%d").ok())
+        .or_else(|| NaiveDate::parse_from_str(s, "%m/%d/%Y").ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn looks_up_columns_by_any_label() {
+        let table = Table::read("\u{feff}Description, AMOUNT ,Currency\nFee,1.00,USD\n".as_bytes())
+            .unwrap();
+        assert_eq!(table.column(&["Amount"]), Some(1));
+        assert_eq!(table.column(&["Fee Type", "Description"]), Some(0));
+        assert!(table.require(&["Quantity"]).is_err());
+        let row = table.rows().next().unwrap();
+        assert_eq!(row.get(Some(2)), "USD");
+        assert_eq!(row.get(None), "");
+    }
+
+    #[test]
+    fn parses_dates_and_money() {
+        let d = NaiveDate::from_ymd_opt(2025, 3, 31);
+        assert_eq!(parse_date("2025-03-31"), d);
+        assert_eq!(parse_date("2025-03-31T23:59:59Z"), d);
+        assert_eq!(parse_date("03/31/2025"), d);
+        assert_eq!(parse_date("March"), None);
+        assert_eq!(to_float("$1,234.50"), 1234.5);
+        assert_eq!(to_float("-15.00"), -15.0);
+        assert_eq!(to_count("1,204"), 1204);
+    }
+}
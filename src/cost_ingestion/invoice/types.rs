@@ -97,6 +97,11 @@ pub struct InvoiceSummary {
     pub txn_count: Option<u64>,
     /// Invoice subtotal excluding taxes — the "true all-in cost" reconciliation ties back to.
     pub subtotal_ex_tax: Option<f64>,
+    /// Whether the subtotal includes the fees the settlement report already carries (the invoice
+    /// has an `AlreadyModeled` line). Adyen bills them on the invoice; a connector that nets them at
+    /// settlement instead leaves them off, and reconciliation adds the settled fees back to reach
+    /// the all-in cost.
+    pub includes_settled_fees: bool,
     /// Primary invoice currency, uppercased.
     pub currency: String,
     /// Billing period start/end, when stated (used to window the reconciliation query).
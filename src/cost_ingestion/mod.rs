@@ -9,19 +9,25 @@
 //! lives behind that trait, so adding a connector is a new impl plus its credentials — the queue,
 //! staging, fit, and serving never change.
 
+pub mod alerts;
+pub mod archive;
 pub mod blended;
 pub mod ch_http;
 pub mod connectors;
 pub mod coverage;
 pub mod creds;
 pub mod detect;
+pub mod drop_folder;
 pub mod fit;
+pub mod fx;
+pub mod interchange;
 pub mod invoice;
 pub mod mapping;
 pub mod overrides;
 pub mod pipeline;
 pub mod poller;
 pub mod preflight;
+pub mod reprocess;
 pub mod rollup;
 pub mod segment;
 pub mod serving;
@@ -29,6 +35,7 @@ pub mod sink;
 pub mod source;
 pub mod store;
 pub mod types;
+pub mod volume_tiers;
 pub mod worker;
 
 pub use creds::{ConnectorCredsStore, ResolvedCreds};
@@ -94,7 +94,9 @@ const GLOBAL_INDEX_NAME: &str = "cost_fee_override_merchants";
 
 // ── generic JSON get/set over service_configuration ───────────────────────────────────────────
 
-async fn read_json<T: for<'de> Deserialize<'de>>(name: String) -> Result<Option<T>, IngestError> {
+pub(super) async fn read_json<T: for<'de> Deserialize<'de>>(
+    name: String,
+) -> Result<Option<T>, IngestError> {
     let stored = service_configuration::find_config_by_name(name)
         .await
         .map_err(|e| IngestError::Storage(e.to_string()))?;
@@ -106,7 +108,7 @@ async fn read_json<T: for<'de> Deserialize<'de>>(name: String) -> Result<Option<
     }
 }
 
-async fn write_json<T: Serialize>(name: String, value: &T) -> Result<(), IngestError> {
+pub(super) async fn write_json<T: Serialize>(name: String, value: &T) -> Result<(), IngestError> {
     let serialized =
         serde_json::to_string(value).map_err(|e| IngestError::Storage(e.to_string()))?;
     let exists = service_configuration::find_config_by_name(name.clone())
@@ -123,12 +125,12 @@ async fn write_json<T: Serialize>(name: String, value: &T) -> Result<(), IngestE
 
 // ── index maintenance ─────────────────────────────────────────────────────────────────────────
 
-async fn read_list(name: String) -> Result<Vec<String>, IngestError> {
+pub(super) async fn read_list(name: String) -> Result<Vec<String>, IngestError> {
     Ok(read_json::<Vec<String>>(name).await?.unwrap_or_default())
 }
 
 /// Add `item` to the JSON string-list at `name` (idempotent).
-async fn index_add(name: String, item: &str) -> Result<(), IngestError> {
+pub(super) async fn index_add(name: String, item: &str) -> Result<(), IngestError> {
     let mut list = read_list(name.clone()).await?;
     if list.iter().any(|c| c == item) {
         return Ok(());
@@ -138,7 +140,7 @@ async fn index_add(name: String, item: &str) -> Result<(), IngestError> {
 }
 
 /// Remove `item` from the JSON string-list at `name`; returns the remaining length.
-async fn index_remove(name: String, item: &str) -> Result<usize, IngestError> {
+pub(super) async fn index_remove(name: String, item: &str) -> Result<usize, IngestError> {
     let mut list = read_list(name.clone()).await?;
     list.retain(|c| c != item);
     let remaining = list.len();
@@ -2,7 +2,9 @@
 //!
 //! Both entry points converge here: the ingest worker (after downloading a report via a webhook)
 //! and manual dashboard upload (a report file). Keeping it in one place means an uploaded report
-//! is processed *identically* to a webhook-delivered one.
+//! is processed *identically* to a webhook-delivered one. Reprocessing a retained report
+//! ([`super::reprocess`]) stages through the same [`stage_report`], fitting once at the end instead
+//! of per report.
 //!
 //! Ingestion streams: the connector parses records off a `Read` in a blocking task and hands
 //! fixed-size batches across a bounded channel to the aggregator here. Each batch is folded into
@@ -21,10 +23,10 @@ use crate::config::ClickHouseAnalyticsConfig;
 
 use super::fit::{self, FitSummary};
 use super::rollup::RollupAccumulator;
-use super::sink;
+use super::sink::{self, IngestionStamp};
 use super::source::{parse_in_batches, ConnectorRegistry};
 use super::store;
-use super::types::{IngestError, SettledFeeRow};
+use super::types::{IngestError, RowKind, SettledFeeRow};
 
 /// Rows per staging INSERT. Bounds both the parsed-row buffer and the ClickHouse request body.
 /// ~50k JSONEachRow rows is a few MB — well within a single insert, far below a memory concern.
@@ -49,6 +51,35 @@ pub struct IngestOutcome {
     pub summary: FitSummary,
 }
 
+/// One report folded and written to staging, before any fit: its shape, for history.
+#[derive(Debug, Clone)]
+pub struct StagedReport {
+    pub staged: usize,
+    pub period_start: Option<NaiveDate>,
+    pub period_end: Option<NaiveDate>,
+    /// Distinct settlement currencies, sorted.
+    pub currencies: Vec<String>,
+    /// Distinct issuer countries, sorted.
+    pub countries: Vec<String>,
+    pub total_gross: f64,
+}
+
+impl StagedReport {
+    /// The outcome once the staged report has been fitted into the `report_date` snapshot.
+    pub fn into_outcome(self, report_date: String, summary: FitSummary) -> IngestOutcome {
+        IngestOutcome {
+            staged: self.staged,
+            report_date,
+            period_start: self.period_start,
+            period_end: self.period_end,
+            currencies: self.currencies,
+            countries: self.countries,
+            total_gross: self.total_gross,
+            summary,
+        }
+    }
+}
+
 impl IngestOutcome {
     /// Map to the history/completion record persisted by both the worker and the manual task.
     pub fn to_completion(&self) -> store::Completion {
@@ -104,6 +135,52 @@ pub async fn ingest_report_reader(
     reader: Box<dyn Read + Send>,
     progress_job: Option<&str>,
 ) -> Result<IngestOutcome, IngestError> {
+    // The fit runs today (the snapshot's `report_date` = fit-run date); it windows on transaction
+    // date internally, so a monthly file, daily files, or a mix all fold into the same model.
+    let report_date = crate::utils::date_time::now().date().to_string();
+    // Rows whose report carries no transaction date are dated to the ingest day for the rollup.
+    let fallback_date = NaiveDate::parse_from_str(&report_date, "%Y-%m-%d")
+        .map_err(|e| IngestError::Storage(format!("bad report date: {e}")))?;
+
+    let staged = stage_report(
+        clickhouse,
+        connector,
+        account,
+        merchant_id,
+        reader,
+        progress_job,
+        &IngestionStamp::new(progress_job.unwrap_or_default()),
+        fallback_date,
+    )
+    .await?;
+
+    let summary = fit::fit_snapshot(
+        clickhouse,
+        connector,
+        account,
+        merchant_id,
+        &report_date,
+        progress_job.unwrap_or_default(),
+    )
+    .await?;
+
+    Ok(staged.into_outcome(report_date, summary))
+}
+
+/// Normalize and stage a report read from `reader` under `stamp`, without fitting — the caller fits
+/// once it has staged everything it means to. Rows with no transaction date are dated
+/// `fallback_date`. `progress_job`, when set, is the `cost_ingestion` row id to tick progress.
+#[allow(clippy::too_many_arguments)]
+pub async fn stage_report(
+    clickhouse: &ClickHouseAnalyticsConfig,
+    connector: &str,
+    account: &str,
+    merchant_id: &str,
+    reader: Box<dyn Read + Send>,
+    progress_job: Option<&str>,
+    stamp: &IngestionStamp,
+    fallback_date: NaiveDate,
+) -> Result<StagedReport, IngestError> {
     let registry = ConnectorRegistry::with_builtins();
     let source = registry.get(connector)?;
 
@@ -113,13 +190,6 @@ pub async fn ingest_report_reader(
     // worker, poller — applies it without having to remember to.
     let mapping = super::mapping::load(merchant_id, connector, account).await?;
 
-    // The fit runs today (the snapshot's `report_date` = fit-run date); it windows on transaction
-    // date internally, so a monthly file, daily files, or a mix all fold into the same model.
-    let report_date = crate::utils::date_time::now().date().to_string();
-    // Rows whose report carries no transaction date are dated to the ingest day for the rollup.
-    let fallback_date = NaiveDate::parse_from_str(&report_date, "%Y-%m-%d")
-        .map_err(|e| IngestError::Storage(format!("bad report date: {e}")))?;
-
     // Blocking CSV parse → bounded channel → async aggregation. `blocking_send` inside the parser
     // applies backpressure, so parsing never runs more than CHANNEL_DEPTH batches ahead.
     let (tx, mut rx) = tokio::sync::mpsc::channel::<Vec<SettledFeeRow>>(CHANNEL_DEPTH);
@@ -148,7 +218,11 @@ pub async fn ingest_report_reader(
 
     // The report is aggregated into per-day sufficient statistics as it streams (never stored
     // row-by-row); we insert the buckets once, after the whole report is folded.
-    let mut acc = RollupAccumulator::new().with_bin_product(bin_product);
+    // FX rates re-state rows paid in one currency but settled in another into the currency they were
+    // paid in, so the fit lands where the payment will be decided.
+    let mut acc = RollupAccumulator::new()
+        .with_bin_product(bin_product)
+        .with_fx(super::fx::snapshot(), connector);
     let mut processed = 0usize;
     while let Some(batch) = rx.recv().await {
         for row in &batch {
@@ -162,7 +236,10 @@ pub async fn ingest_report_reader(
                 period_start = Some(period_start.map_or(d, |p| p.min(d)));
                 period_end = Some(period_end.map_or(d, |p| p.max(d)));
             }
-            total_gross += row.gross;
+            // Refunded and disputed amounts are not volume.
+            if row.kind == RowKind::Capture {
+                total_gross += row.gross;
+            }
             acc.add_row(row, fallback_date);
         }
         processed += batch.len();
@@ -179,18 +256,22 @@ pub async fn ingest_report_reader(
         .map_err(|e| IngestError::Storage(format!("parse task panicked: {e}")))?;
     parse_result?;
 
-    // One insert of the fully-aggregated buckets, then fit from what the rollup now holds. The
-    // history record reports transactions *processed* (`staged`), not bucket count.
+    // One insert of the fully-aggregated buckets; the caller fits from what the rollup now holds.
+    // The history record reports transactions *processed* (`staged`), not bucket count.
     // Grab the per-BIN observations first (borrows) before `into_rows` consumes the accumulator.
     let bin_rows = acc.bin_rows();
+    let post_capture_rows = acc.post_capture_rows();
     let rows = acc.into_rows();
-    sink::insert_daily_stats(
+    sink::insert_daily_stats(clickhouse, connector, account, merchant_id, stamp, &rows).await?;
+    // Refund and dispute fees, which serving spreads over the cluster's captures. Written before the
+    // fit like the daily stats, and removed with them when the ingestion is deleted.
+    sink::insert_post_capture_stats(
         clickhouse,
         connector,
         account,
         merchant_id,
-        progress_job.unwrap_or_default(),
-        &rows,
+        stamp,
+        &post_capture_rows,
     )
     .await?;
 
@@ -207,24 +288,12 @@ pub async fn ingest_report_reader(
         );
     }
 
-    let summary = fit::fit_snapshot(
-        clickhouse,
-        connector,
-        account,
-        merchant_id,
-        &report_date,
-        progress_job.unwrap_or_default(),
-    )
-    .await?;
-
-    Ok(IngestOutcome {
+    Ok(StagedReport {
         staged: processed,
-        report_date,
         period_start,
         period_end,
         currencies: currencies.into_iter().collect(),
         countries: countries.into_iter().collect(),
         total_gross,
-        summary,
     })
 }
@@ -30,7 +30,7 @@ use serde::Serialize;
 
 use super::mapping::ColumnMapping;
 use super::source::ConnectorRegistry;
-use super::types::{IngestError, SettledFeeRow};
+use super::types::{IngestError, RowKind, SettledFeeRow};
 
 /// How much of the uploaded file the caller need send. The header row is the first line; a few KB
 /// covers it comfortably (Chase also spends a handful of lines on its `BEGIN`/`EntityId=` envelope,
@@ -261,7 +261,12 @@ pub fn preview(
     let reader = Box::new(Cursor::new(sample.to_vec()));
     // Stop once we have enough: a sample is capped at a few KB, but there is no reason to parse
     // past the rows we will show. The sentinel error is swallowed below.
+    // Only captures are shown: a refund or dispute fee is not a rate on its amount, and a handful of
+    // them would swing the median the guardrail below is judged on.
     let outcome = source.parse_rows(reader, mapping, &mut |row| {
+        if row.kind != RowKind::Capture {
+            return Ok(());
+        }
         rows.push(row);
         if rows.len() >= PREVIEW_ROWS {
             return Err(IngestError::Parse(STOP.to_string()));
@@ -911,12 +916,25 @@ Gross Qty,Cost Qty,Refund Flag,Gross Ccy,Cost Ccy,Month,Fee Name,Variable Fee,Fi
 mc,Ecommerce,DEBIT,maestro,10000.00,250.00,Settle,USD,USD,2025/01,Scheme Fee,0.0165,0.15\n"
             .to_string();
 
+        let paypal = "\"CH\",\"Transaction ID\",\"Transaction Event Code\",\
+\"Transaction Debit or Credit\",\"Gross Transaction Amount\",\"Gross Transaction Currency\",\
+\"Fee Debit or Credit\",\"Fee Amount\"\n\
+\"SB\",\"4HX1\",\"T0006\",\"CR\",\"10000\",\"USD\",\"DR\",\"320\"\n"
+            .to_string();
+
+        let worldpay = "Order Code,Transaction Type,Payment Method,Settlement Currency,\
+Gross Amount,Interchange Fee,Scheme Fee,Acquirer Markup\n\
+o1,SETTLED,VISA-SSL,GBP,100.00,1.50,0.20,0.30\n"
+            .to_string();
+
         vec![
             ("adyen", adyen, "Payable (SC)", "Net Settlement Amount"),
             ("braintree", braintree, "Settlement Amount", "Settled Amt"),
             ("chase", chase, "Total Interchange Amount", "IC Amount"),
             ("checkout", checkout, "Holding Currency Amount", "Amount"),
+            ("paypal", paypal, "Fee Amount", "PayPal Fee"),
             ("stripe", stripe, "Gross Qty", "Turnover"),
+            ("worldpay", worldpay, "Acquirer Markup", "Worldpay Margin"),
         ]
     }
 
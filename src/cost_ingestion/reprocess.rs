@@ -0,0 +1,173 @@
+//! Reprocessing — re-run past ingestions from their retained reports with the current connector
+//! parsers and column mappings.
+//!
+//! A parser fix or a corrected [`ColumnMapping`](super::mapping::ColumnMapping) only changes
+//! reports ingested after it. This re-reads each ingestion's report from the [`super::archive`] and
+//! stages it again through [`pipeline::stage_report`], so the result is what a fresh upload of the
+//! same file would produce today.
+//!
+//! **Replacement.** The new buckets are written as the ingestion's next *revision*
+//! ([`sink::next_revision`]) and pinned to the ingestion's original `ingested_at`. Every bucket the
+//! new revision rewrites therefore supersedes the old one in place (same replacing key, same
+//! version, inserted later), while a day a *later* report has since re-delivered stays that
+//! report's. Only once the whole report is staged are the old revision's remaining buckets — those
+//! the new parse no longer produces — deleted. Staging never leaves the ingestion missing from
+//! `cost_daily_stats`: a run that fails partway leaves each of its buckets on one revision or the
+//! other, and can simply be run again.
+//!
+//! **One fit.** Fitting is per `(connector, account)` over the whole window, so nothing is refitted
+//! until every requested ingestion is restaged; then each affected source is refitted once, and the
+//! merchant's served models are refreshed once.
+
+use std::collections::BTreeSet;
+
+use chrono::NaiveDate;
+
+use crate::config::{ClickHouseAnalyticsConfig, ReportArchiveConfig};
+use crate::logger;
+use crate::storage::types::CostIngestion;
+
+use super::pipeline::{self, StagedReport};
+use super::types::IngestError;
+use super::{archive, fit, sink, store};
+
+/// Reprocess `jobs` — a merchant's ingestions, already claimed with
+/// [`store::claim_for_reprocess`] — in order, then refit and refresh once. Each job's row is marked
+/// completed or failed as it would be after a first ingest. Meant to run as a background task.
+pub async fn run(
+    clickhouse: ClickHouseAnalyticsConfig,
+    archive: ReportArchiveConfig,
+    merchant_id: String,
+    jobs: Vec<CostIngestion>,
+) {
+    let mut staged: Vec<(CostIngestion, StagedReport)> = Vec::new();
+    // Refit every source a job touched, including one whose restage failed partway: its first
+    // chunks may already be staged.
+    let mut sources: BTreeSet<(String, String)> = BTreeSet::new();
+    for job in jobs {
+        sources.insert((job.connector.clone(), job.account.clone()));
+        match restage(&clickhouse, &archive, &job).await {
+            Ok(report) => staged.push((job, report)),
+            Err(e) => {
+                let msg = format!("{e:?}");
+                logger::warn!(tag = "reprocess", "reprocess {} failed: {}", job.id, msg);
+                if let Err(e2) = store::mark_failed(&job.id, &msg).await {
+                    logger::warn!(tag = "reprocess", "mark_failed {} failed: {:?}", job.id, e2);
+                }
+            }
+        }
+    }
+
+    let report_date = crate::utils::date_time::now().date().to_string();
+    for (connector, account) in &sources {
+        let snapshot = match fit::fit_snapshot(
+            &clickhouse,
+            connector,
+            account,
+            &merchant_id,
+            &report_date,
+            "",
+        )
+        .await
+        {
+            Ok(summary) => summary,
+            Err(e) => {
+                let msg = format!("refit after reprocess failed: {e:?}");
+                logger::warn!(tag = "reprocess", "{}/{}: {}", connector, account, msg);
+                // The restaged rows are in place but the snapshot doesn't reflect them yet; say so on
+                // each row rather than report a completion whose fit never ran.
+                for (job, _) in staged
+                    .iter()
+                    .filter(|(job, _)| (&job.connector, &job.account) == (connector, account))
+                {
+                    if let Err(e2) = store::mark_failed(&job.id, &msg).await {
+                        logger::warn!(tag = "reprocess", "mark_failed {} failed: {:?}", job.id, e2);
+                    }
+                }
+                continue;
+            }
+        };
+        for (job, report) in staged
+            .iter()
+            .filter(|(job, _)| (&job.connector, &job.account) == (connector, account))
+        {
+            let summary = fit::narrow_to_ingestion(
+                &clickhouse,
+                connector,
+                account,
+                &merchant_id,
+                &report_date,
+                &job.id,
+                snapshot,
+            )
+            .await;
+            let outcome = report.clone().into_outcome(report_date.clone(), summary);
+            if let Err(e) = store::mark_completed(&job.id, &outcome.to_completion()).await {
+                logger::warn!(
+                    tag = "reprocess",
+                    "mark_completed {} failed: {:?}",
+                    job.id,
+                    e
+                );
+            }
+        }
+    }
+
+    if let Err(e) = super::serving::refresh_merchant(&clickhouse, &merchant_id).await {
+        logger::warn!(
+            tag = "reprocess",
+            "serving refresh after reprocess failed: {}",
+            e
+        );
+    }
+    super::alerts::check_after_fit(&clickhouse, &merchant_id).await;
+}
+
+/// Stage one ingestion's retained report as its next revision, then drop the revision it replaces.
+async fn restage(
+    clickhouse: &ClickHouseAnalyticsConfig,
+    archive: &ReportArchiveConfig,
+    job: &CostIngestion,
+) -> Result<StagedReport, IngestError> {
+    let blob = job
+        .report_blob
+        .as_deref()
+        .ok_or_else(|| IngestError::Storage("the report was not retained".to_string()))?;
+    let reader = archive::open(archive, blob).await?;
+    let stamp = sink::next_revision(
+        clickhouse,
+        &job.connector,
+        &job.account,
+        &job.merchant_id,
+        &job.id,
+    )
+    .await?;
+    let staged = pipeline::stage_report(
+        clickhouse,
+        &job.connector,
+        &job.account,
+        &job.merchant_id,
+        reader,
+        Some(job.id.as_str()),
+        &stamp,
+        ingest_day(job)?,
+    )
+    .await?;
+    sink::delete_superseded_revisions(
+        clickhouse,
+        &job.connector,
+        &job.account,
+        &job.merchant_id,
+        &stamp,
+    )
+    .await?;
+    Ok(staged)
+}
+
+/// The day the ingestion first ran. Rows whose report carries no transaction date were dated to it,
+/// and a reprocessing must date them the same way.
+fn ingest_day(job: &CostIngestion) -> Result<NaiveDate, IngestError> {
+    let d = job.created_at.date();
+    NaiveDate::from_yo_opt(d.year(), u32::from(d.ordinal()))
+        .ok_or_else(|| IngestError::Storage(format!("bad ingestion date: {d}")))
+}
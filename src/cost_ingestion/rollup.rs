@@ -7,6 +7,14 @@
 //! NON_LINEAR check — so summing buckets over any window reconstructs the exact same line the raw
 //! rows would give (see `scratch/settlement-table-removal-worked-example.md`).
 //!
+//! A row whose report itemises interchange and scheme fees (IC++ connectors) also folds its
+//! *pass-through* — `interchange + scheme_fee` — into a handful of extra sums, from which
+//! [`super::interchange`] fits the shared interchange table and each connector's markup.
+//!
+//! Refund and dispute rows never reach the fit: they are counted per `(cluster × day × kind)` with
+//! their summed fees, which serving divides by the cluster's capture count for the expected
+//! post-capture cost of a payment.
+//!
 //! Peak memory is O(distinct buckets) for one `(connector, account, merchant)` report — clusters ×
 //! days × bands × channels, a few MB even for a multi-GB monthly file — not O(transactions).
 
@@ -15,7 +23,8 @@ use std::sync::Arc;
 
 use chrono::NaiveDate;
 
-use super::types::{amount_band, SettledFeeRow};
+use super::fx::FxTable;
+use super::types::{amount_band, RowKind, SettledFeeRow};
 
 /// Global BIN → dominant `card_product` map, canonical 6-digit BIN → interchange-rate tier. Loaded
 /// from `cost_bin_product` before a report streams and consulted per row to stamp the cluster's
@@ -65,6 +74,28 @@ struct BinKey {
     card_product: String,
 }
 
+/// Identity of one post-capture bucket: the fit's cluster key (less the card product, which a
+/// refund or dispute row cannot be resolved to reliably) plus the day and the event kind.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct PostCaptureKey {
+    txn_date: NaiveDate,
+    card_network: String,
+    variant: String,
+    funding: String,
+    issuer_country: String,
+    currency: String,
+    ic_category: String,
+    kind: RowKind,
+}
+
+/// Running totals for the refund or dispute events in one post-capture bucket.
+#[derive(Debug, Clone, Copy, Default)]
+struct PostCaptureStats {
+    n: u64,
+    fees: f64,
+    amount: f64,
+}
+
 /// Additive sufficient statistics for the transactions in one bucket. Every field is a plain sum,
 /// so merging two buckets (or summing across days at fit time) is field-wise addition.
 #[derive(Debug, Clone, Copy, Default)]
@@ -80,6 +111,14 @@ struct Stats {
     suy: f64,
     suuy: f64,
     syyuu: f64,
+    /// Pass-through sums over the transactions whose report itemised interchange/scheme fees. Only
+    /// read where `n_itemised == n`, so the bucket's `sx`/`sxx`/`sy`/`sxy`/`syy` describe the same
+    /// transactions.
+    n_itemised: u64,
+    spt: f64,
+    sxpt: f64,
+    sptpt: f64,
+    sypt: f64,
 }
 
 impl Stats {
@@ -99,6 +138,17 @@ impl Stats {
         self.suuy += y * inv2;
         self.syyuu += y * y * inv2;
     }
+
+    /// Fold the pass-through `pt` (interchange + scheme fee) of a transaction already [`add`]ed.
+    ///
+    /// [`add`]: Self::add
+    fn add_pass_through(&mut self, x: f64, y: f64, pt: f64) {
+        self.n_itemised += 1;
+        self.spt += pt;
+        self.sxpt += x * pt;
+        self.sptpt += pt * pt;
+        self.sypt += y * pt;
+    }
 }
 
 /// One fully-aggregated bucket, ready to insert into `cost_daily_stats`.
@@ -124,6 +174,11 @@ pub struct DailyStatRow {
     pub suy: f64,
     pub suuy: f64,
     pub syyuu: f64,
+    pub n_itemised: u64,
+    pub spt: f64,
+    pub sxpt: f64,
+    pub sptpt: f64,
+    pub sypt: f64,
 }
 
 /// One aggregated global BIN → card-product observation, ready to insert into `cost_bin_product`.
@@ -136,15 +191,34 @@ pub struct BinProductRow {
     pub support_n: u64,
 }
 
+/// One aggregated refund or dispute bucket, ready to insert into `cost_post_capture_stats`.
+pub struct PostCaptureRow {
+    pub txn_date: NaiveDate,
+    pub card_network: String,
+    pub variant: String,
+    pub funding: String,
+    pub issuer_country: String,
+    pub currency: String,
+    pub ic_category: String,
+    pub kind: RowKind,
+    pub n: u64,
+    pub fees: f64,
+    pub amount: f64,
+}
+
 /// Accumulates a report's transactions into per-day sufficient statistics, and — in the same pass —
 /// per-BIN card-product observations for the global `cost_bin_product` map.
 #[derive(Default)]
 pub struct RollupAccumulator {
     buckets: HashMap<BucketKey, Stats>,
     bins: HashMap<BinKey, u64>,
+    post_capture: HashMap<PostCaptureKey, PostCaptureStats>,
     /// Global BIN → dominant `card_product`, loaded from `cost_bin_product` before the report
     /// streams. Empty on a cold start (first ever ingest), where each row falls back to its own rate.
     bin_product: BinProductMap,
+    /// FX rates for re-stating a row in its presentment currency, and the reporting connector's FX
+    /// markup (bps) charged on the conversion. `None` leaves every row in its settlement currency.
+    fx: Option<(Arc<FxTable>, f64)>,
 }
 
 impl RollupAccumulator {
@@ -160,6 +234,39 @@ impl RollupAccumulator {
         self
     }
 
+    /// Attach the FX rates and the reporting connector, so a row settled in one currency but paid in
+    /// another is bucketed in the currency it was paid in (see [`Self::restate`]).
+    pub fn with_fx(mut self, fx: Arc<FxTable>, connector: &str) -> Self {
+        let markup_bps = fx.markup_bps(connector);
+        self.fx = Some((fx, markup_bps));
+        self
+    }
+
+    /// The `(currency, gross, fee)` a row is bucketed under. A row whose presentment currency differs
+    /// from its settlement currency is converted at its date's rate, and the connector's FX markup on
+    /// the converted gross is added to the fee — the report folds that spread into the settled
+    /// amounts, so it is otherwise invisible. Without a rate for the date the row stays in its
+    /// settlement currency.
+    fn restate(&self, row: &SettledFeeRow, date: NaiveDate) -> (String, f64, f64) {
+        let settled = (row.currency.clone(), row.gross, row.total_fee);
+        let Some((fx, markup_bps)) = &self.fx else {
+            return settled;
+        };
+        if row.presentment_currency.is_empty()
+            || row.presentment_currency.eq_ignore_ascii_case(&row.currency)
+        {
+            return settled;
+        }
+        match fx.rate(&row.currency, &row.presentment_currency, date) {
+            Some(rate) => {
+                let gross = row.gross * rate;
+                let fee = row.total_fee * rate + gross * markup_bps / 10_000.0;
+                (row.presentment_currency.to_uppercase(), gross, fee)
+            }
+            None => settled,
+        }
+    }
+
     /// Resolve a row's cluster `card_product`: the BIN's DOMINANT rate from the global map when
     /// known (stable across reports, consolidates a straddling BIN onto one tier), else the row's own
     /// published rate (so a cold BIN still splits the fan on the first ingest), else `""` (blended /
@@ -181,6 +288,10 @@ impl RollupAccumulator {
     /// would make the reciprocal terms explode) are skipped — the same rows the fit/predictor
     /// filtered out at read time. `fallback_date` dates rows whose report carried no txn date.
     pub fn add_row(&mut self, row: &SettledFeeRow, fallback_date: NaiveDate) {
+        if row.kind != RowKind::Capture {
+            self.add_post_capture(row, fallback_date);
+            return;
+        }
         // Fold the BIN observation first — a card's product doesn't depend on the amount, so we
         // capture it even for sub-floor rows (more BIN coverage). Recorded for every row with a PAN
         // (not only rate-bearing ones): the `funding` signal is valid even when the row carries no
@@ -198,22 +309,52 @@ impl RollupAccumulator {
         if row.gross.is_nan() || row.gross <= MIN_GROSS {
             return;
         }
+        let txn_date = row.txn_date.unwrap_or(fallback_date);
+        let (currency, gross, fee) = self.restate(row, txn_date);
         let key = BucketKey {
-            txn_date: row.txn_date.unwrap_or(fallback_date),
+            txn_date,
             card_network: row.card_network.clone(),
             variant: row.variant.clone(),
             funding: row.funding.clone(),
             issuer_country: row.issuer_country.clone(),
-            currency: row.currency.clone(),
+            currency,
             ic_category: row.ic_category.clone(),
             card_product: self.resolve_card_product(row),
             channel: row.channel.clone(),
-            band: amount_band(row.gross),
+            band: amount_band(gross),
         };
-        self.buckets
-            .entry(key)
-            .or_default()
-            .add(row.gross, row.total_fee);
+        let stats = self.buckets.entry(key).or_default();
+        stats.add(gross, fee);
+        if row.interchange != 0.0 || row.scheme_fee != 0.0 {
+            // Restated like the gross: the FX markup `restate` adds to the fee is the connector's,
+            // so it lands in the markup, never in the pass-through.
+            let pt = (row.interchange + row.scheme_fee) * gross / row.gross;
+            stats.add_pass_through(gross, fee, pt);
+        }
+    }
+
+    /// Fold one refund or dispute row. No gross guard: the fee is what matters, and a dispute fee
+    /// line often carries no amount at all.
+    fn add_post_capture(&mut self, row: &SettledFeeRow, fallback_date: NaiveDate) {
+        if row.total_fee.is_nan() {
+            return;
+        }
+        let txn_date = row.txn_date.unwrap_or(fallback_date);
+        let (currency, amount, fees) = self.restate(row, txn_date);
+        let key = PostCaptureKey {
+            txn_date,
+            card_network: row.card_network.clone(),
+            variant: row.variant.clone(),
+            funding: row.funding.clone(),
+            issuer_country: row.issuer_country.clone(),
+            currency,
+            ic_category: row.ic_category.clone(),
+            kind: row.kind,
+        };
+        let stats = self.post_capture.entry(key).or_default();
+        stats.n += 1;
+        stats.fees += fees;
+        stats.amount += amount;
     }
 
     /// Number of distinct buckets accumulated (for capacity hints / diagnostics).
@@ -241,6 +382,27 @@ impl RollupAccumulator {
             .collect()
     }
 
+    /// The refund and dispute buckets gathered this report, for `cost_post_capture_stats`. Borrows
+    /// (call before [`into_rows`] consumes the accumulator).
+    pub fn post_capture_rows(&self) -> Vec<PostCaptureRow> {
+        self.post_capture
+            .iter()
+            .map(|(k, s)| PostCaptureRow {
+                txn_date: k.txn_date,
+                card_network: k.card_network.clone(),
+                variant: k.variant.clone(),
+                funding: k.funding.clone(),
+                issuer_country: k.issuer_country.clone(),
+                currency: k.currency.clone(),
+                ic_category: k.ic_category.clone(),
+                kind: k.kind,
+                n: s.n,
+                fees: s.fees,
+                amount: s.amount,
+            })
+            .collect()
+    }
+
     /// Drain into insertable rows. Consumes the accumulator.
     pub fn into_rows(self) -> Vec<DailyStatRow> {
         self.buckets
@@ -267,6 +429,11 @@ impl RollupAccumulator {
                 suy: s.suy,
                 suuy: s.suuy,
                 syyuu: s.syyuu,
+                n_itemised: s.n_itemised,
+                spt: s.spt,
+                sxpt: s.sxpt,
+                sptpt: s.sptpt,
+                sypt: s.sypt,
             })
             .collect()
     }
@@ -284,6 +451,7 @@ mod tests {
             funding: "credit".into(),
             issuer_country: "GB".into(),
             currency: "GBP".into(),
+            presentment_currency: String::new(),
             ic_category: "".into(),
             ic_bps: "".into(),
             txn_date: Some(NaiveDate::parse_from_str(date, "%Y-%m-%d").unwrap()),
@@ -295,6 +463,7 @@ mod tests {
             markup: 0.0,
             commission: 0.0,
             bin: String::new(),
+            kind: RowKind::Capture,
         }
     }
 
@@ -344,6 +513,7 @@ mod tests {
             funding: "commercial".into(),
             issuer_country: "IT".into(),
             currency: "EUR".into(),
+            presentment_currency: String::new(),
             ic_category: "Intra EEA Enhanced Electronic".into(),
             ic_bps: ic_bps.into(),
             txn_date: None,
@@ -355,6 +525,7 @@ mod tests {
             markup: 0.0,
             commission: 0.0,
             bin: bin.into(),
+            kind: RowKind::Capture,
         }
     }
 
@@ -463,4 +634,91 @@ mod tests {
             "map's dominant rate wins over the row's own"
         );
     }
+
+    #[test]
+    fn a_row_paid_in_another_currency_is_restated_in_it() {
+        // Paid in EUR, settled in GBP: the bucket is the EUR cluster the payment is decided in, with
+        // the connector's 50 bps FX markup on the converted gross added to the fee.
+        let d = NaiveDate::parse_from_str("2026-06-28", "%Y-%m-%d").unwrap();
+        let cfg = crate::config::FxConfig {
+            connector_markup_bps: HashMap::from([("adyen".to_string(), 50.0)]),
+            ..Default::default()
+        };
+        let rates = super::super::fx::parse_csv(
+            "base,quote,rate,effective_from\nGBP,EUR,1.20,2026-06-01\n",
+        )
+        .unwrap();
+        let fx = Arc::new(FxTable::new(rates, &cfg));
+        let mut acc = RollupAccumulator::new().with_fx(fx, "adyen");
+        let mut paid_in_eur = row(100.0, 2.0, "2026-06-28");
+        paid_in_eur.presentment_currency = "EUR".into();
+        acc.add_row(&paid_in_eur, d);
+        // No presentment currency: stays in GBP, unconverted.
+        acc.add_row(&row(100.0, 2.0, "2026-06-28"), d);
+        let mut rows = acc.into_rows();
+        rows.sort_by(|a, b| a.currency.cmp(&b.currency));
+        assert_eq!(rows[0].currency, "EUR");
+        assert!((rows[0].sx - 120.0).abs() < 1e-9);
+        assert!((rows[0].sy - (2.4 + 0.6)).abs() < 1e-9);
+        assert_eq!(rows[1].currency, "GBP");
+        assert!((rows[1].sx - 100.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn refunds_and_disputes_bypass_the_fit() {
+        let d = NaiveDate::parse_from_str("2026-06-28", "%Y-%m-%d").unwrap();
+        let mut acc = RollupAccumulator::new();
+        acc.add_row(&row(100.0, 2.70, "2026-06-28"), d);
+        for (kind, amount, fee) in [
+            (RowKind::Dispute, 100.0, 15.0),
+            (RowKind::Dispute, 0.0, 15.0),
+            (RowKind::Refund, 40.0, 0.10),
+        ] {
+            acc.add_row(
+                &SettledFeeRow {
+                    kind,
+                    ..row(amount, fee, "2026-06-28")
+                },
+                d,
+            );
+        }
+        let mut post = acc.post_capture_rows();
+        post.sort_by_key(|r| r.kind.as_str());
+        assert_eq!(post.len(), 2);
+        assert_eq!((post[0].kind, post[0].n), (RowKind::Dispute, 2));
+        assert!((post[0].fees - 30.0).abs() < 1e-9);
+        assert!((post[0].amount - 100.0).abs() < 1e-9);
+        assert_eq!((post[1].kind, post[1].n), (RowKind::Refund, 1));
+        let rows = acc.into_rows();
+        assert_eq!(
+            rows.iter().map(|r| r.n).sum::<u64>(),
+            1,
+            "only the capture is fitted"
+        );
+    }
+
+    #[test]
+    fn itemised_rows_carry_their_pass_through() {
+        let d = NaiveDate::parse_from_str("2026-06-28", "%Y-%m-%d").unwrap();
+        let mut acc = RollupAccumulator::new();
+        acc.add_row(
+            &SettledFeeRow {
+                interchange: 0.30,
+                scheme_fee: 0.05,
+                markup: 0.25,
+                ..row(100.0, 0.60, "2026-06-28")
+            },
+            d,
+        );
+        // A blended connector's row on the same bucket: counted, but contributes no pass-through.
+        acc.add_row(&row(100.0, 0.90, "2026-06-28"), d);
+        let rows = acc.into_rows();
+        assert_eq!(rows.len(), 1);
+        let r = &rows[0];
+        assert_eq!((r.n, r.n_itemised), (2, 1));
+        assert!((r.spt - 0.35).abs() < 1e-9);
+        assert!((r.sxpt - 35.0).abs() < 1e-9);
+        assert!((r.sptpt - 0.1225).abs() < 1e-9);
+        assert!((r.sypt - 0.21).abs() < 1e-9);
+    }
 }
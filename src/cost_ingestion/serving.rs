@@ -22,17 +22,29 @@ use crate::logger;
 // Shared with the rollup aggregator so decide-time bucketing and the stored `band` column (which is
 // stamped by the same thresholds at ingestion) can never diverge.
 use super::types::amount_band;
+use super::volume_tiers::{self, VolumeTierSchedule};
+use super::{fx, interchange};
 
 const REFRESH_INTERVAL: Duration = Duration::from_secs(300);
 const QUERY_TIMEOUT: Duration = Duration::from_secs(60);
 /// Minimum observations before a predictor level is trusted (mirrors the prototype's MIN_SUPPORT).
 const MIN_SUPPORT: u64 = 20;
+/// Trailing window (days before a connector's latest transaction) over which refund and dispute fees
+/// are spread over captures. Longer than the fit's: a chargeback lands weeks after its capture, and
+/// disputes are rare enough that a shorter window leaves most networks with none.
+const POST_CAPTURE_WINDOW_DAYS: i64 = 180;
+/// Captures a network's post-capture cost needs before it is served in place of the connector-wide
+/// one — a rate of a few disputes per thousand is noise on fewer.
+const POST_CAPTURE_MIN_CAPTURES: u64 = 1_000;
 
 /// Amount-independent `{pct_bps, fixed}` cost for one cluster.
 #[derive(Debug, Clone, Copy)]
 struct ServingCost {
     pct_bps: f64,
     fixed: f64,
+    /// Standard error of the fitted effective rate, in bps: the cluster's per-transaction
+    /// `bps_rmse` over `√n`. `0` for a contract rate the merchant stated, which is known exactly.
+    stderr_bps: f64,
 }
 
 impl ServingCost {
@@ -110,9 +122,10 @@ impl FineBlend {
             return ServingCost {
                 pct_bps: 0.0,
                 fixed: 0.0,
+                stderr_bps: 0.0,
             };
         }
-        let (mut pct, mut fixed) = (0.0, 0.0);
+        let (mut pct, mut fixed, mut var) = (0.0, 0.0, 0.0);
         for (w, m) in &self.members {
             let c = match m {
                 MemberCost::Flat(c) => *c,
@@ -120,10 +133,12 @@ impl FineBlend {
             };
             pct += c.pct_bps * w;
             fixed += c.fixed * w;
+            var += (c.stderr_bps * w).powi(2);
         }
         ServingCost {
             pct_bps: pct / self.total_weight,
             fixed: fixed / self.total_weight,
+            stderr_bps: var.sqrt() / self.total_weight,
         }
     }
 }
@@ -187,6 +202,26 @@ struct MerchantModels {
     /// fees). Deliberately *not* applied to the manual overrides above — those are all-in contract
     /// rates a merchant stated, already inclusive of everything.
     addons: HashMap<String, ServingCost>,
+    /// Contract volume-tier schedules (lowercase connector → schedule). Priced at the marginal tier
+    /// for the connector's month-to-date volume, after a cluster override and before a connector
+    /// override: both the schedule and the override are contract rates, and the schedule is the
+    /// more precise statement of the same contract.
+    volume_tiers: HashMap<String, VolumeTierSchedule>,
+    /// Currencies each connector has learned models in (lowercase connector → uppercase ISO codes,
+    /// most settled volume first). A payment in a currency the connector has no model in is priced
+    /// off these, converted at [`lookup`].
+    settlement_currencies: HashMap<String, Vec<String>>,
+    /// Expected refund and dispute fees per capture (event rate × fee), in the key's currency:
+    /// `connector|network|funding|currency`, backed off to `connector|currency`. Added to every price
+    /// [`lookup`] returns — no capture rate, contracted or learned, includes them.
+    post_capture: HashMap<String, f64>,
+    /// IC++ markups learned from the connector's itemised reports: `interchange::markup_key` →
+    /// markup over the shared interchange. With the shared table they price a card the connector has
+    /// no model for (see [`super::interchange`]).
+    markups: HashMap<String, ServingCost>,
+    /// Markups the merchant declared (lowercase connector → markup). Win over learned ones, and price
+    /// a connector with no ingested reports at all.
+    declared_markups: HashMap<String, ServingCost>,
 }
 
 impl MerchantModels {
@@ -202,6 +237,9 @@ impl MerchantModels {
             && self.overrides.is_empty()
             && self.cluster_overrides.is_empty()
             && self.addons.is_empty()
+            && self.volume_tiers.is_empty()
+            && self.markups.is_empty()
+            && self.declared_markups.is_empty()
     }
 }
 
@@ -213,6 +251,7 @@ impl ServingCost {
             Some(a) => Self {
                 pct_bps: self.pct_bps + a.pct_bps,
                 fixed: self.fixed + a.fixed,
+                stderr_bps: self.stderr_bps.hypot(a.stderr_bps),
             },
             None => self,
         }
@@ -252,7 +291,7 @@ fn resolve_bin_product(bin: &str) -> String {
 // ── keys ────────────────────────────────────────────────────────────────────────────────────
 
 /// Canonicalize network aliases (report `mc`/`amex` vs router `mastercard`/`american express`).
-fn normalize_network(network: &str) -> &str {
+pub(super) fn normalize_network(network: &str) -> &str {
     match network {
         "mastercard" | "master" => "mc",
         "american express" | "americanexpress" => "amex",
@@ -261,6 +300,20 @@ fn normalize_network(network: &str) -> &str {
     }
 }
 
+fn post_capture_key(connector: &str, network: &str, funding: &str, currency: &str) -> String {
+    format!(
+        "{}|{}|{}|{}",
+        connector.to_lowercase(),
+        normalize_network(&network.to_lowercase()),
+        funding.to_lowercase(),
+        currency.to_lowercase(),
+    )
+}
+
+fn post_capture_connector_key(connector: &str, currency: &str) -> String {
+    format!("{}|{}", connector.to_lowercase(), currency.to_lowercase())
+}
+
 fn coarse_key(
     connector: &str,
     network: &str,
@@ -381,21 +434,107 @@ const PREDICTOR_LEVELS: usize = 9;
 /// (category-predicted) path; the coarse blend leaves them `None`.
 #[derive(Debug, Clone)]
 pub struct InhouseMatch {
-    /// Amount-adjusted cost for this transaction (what EV ranks on).
+    /// Amount-adjusted cost for this transaction (what EV ranks on), including any FX markup.
     pub effective_bps: f64,
+    /// Standard error of `effective_bps`, in bps — how far a thin cluster's fit can be off. `0` for
+    /// a contract rate.
+    pub stderr_bps: f64,
     pub pct_bps: f64,
+    /// Flat per-transaction fee, in `currency`.
     pub fixed: f64,
     pub brand: String,
+    /// The payment's currency — the one `fixed` is stated in.
     pub currency: String,
     pub variant: Option<String>,
     pub issuer: Option<String>,
     pub ic_category: Option<String>,
+    /// The contract volume tier the payment was priced in, when a schedule priced it.
+    pub volume_tier: Option<usize>,
+    /// Set when the cost was fitted (or contracted) in another currency and converted.
+    pub fx: Option<FxApplied>,
+    /// Expected refund and dispute fees per payment, in `currency` — included in `effective_bps`, not
+    /// in `fixed`. `None` when the connector's reports carried no refunds or disputes.
+    pub post_capture_fee: Option<f64>,
+    /// Set when the connector had no model for the card and it was priced as the shared interchange
+    /// plus the connector's markup ([`super::interchange`]): the interchange part of `pct_bps` and
+    /// `fixed`.
+    pub interchange_pct_bps: Option<f64>,
+    pub interchange_fixed: Option<f64>,
+}
+
+/// How a cost stated in the connector's settlement currency was brought into the payment's.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FxApplied {
+    /// The currency the model or contract that priced the payment is stated in.
+    pub settlement_currency: String,
+    /// Units of the payment's currency per unit of the settlement currency.
+    pub rate: f64,
+    /// The connector's FX markup, in bps — included in `effective_bps`, not in `pct_bps`.
+    pub markup_bps: f64,
+}
+
+impl InhouseMatch {
+    /// Re-state a match priced in its settlement currency into the payment's `currency`: the fixed
+    /// fee is converted at `rate`, and converting the payment costs the connector's `markup_bps` on
+    /// top of the fitted rate.
+    fn converted(mut self, currency: &str, rate: f64, markup_bps: f64, amount: f64) -> Self {
+        let cost = ServingCost {
+            pct_bps: self.pct_bps,
+            fixed: self.fixed * rate,
+            stderr_bps: self.stderr_bps,
+        };
+        self.fx = Some(FxApplied {
+            settlement_currency: std::mem::replace(&mut self.currency, currency.to_uppercase()),
+            rate,
+            markup_bps,
+        });
+        self.fixed = cost.fixed;
+        self.effective_bps = cost.effective_cost_bps(amount) + markup_bps;
+        self
+    }
+
+    /// Add the expected refund and dispute fees a payment on this network goes on to cost. Looked up
+    /// in the payment's currency, then in the settlement currency a converted match was priced in.
+    fn with_post_capture(mut self, m: &MerchantModels, card: &CardKey, amount: f64) -> Self {
+        let fee = m.post_capture_fee(card, &self.currency).or_else(|| {
+            let fx = self.fx.as_ref()?;
+            m.post_capture_fee(card, &fx.settlement_currency)
+                .map(|fee| fee * fx.rate)
+        });
+        if let Some(fee) = fee {
+            if amount > 0.0 {
+                self.effective_bps += fee / amount * 10_000.0;
+            }
+            self.post_capture_fee = Some(fee);
+        }
+        self
+    }
+}
+
+impl MerchantModels {
+    /// The expected post-capture fee for `card` in `currency`: its network's, else its connector's.
+    fn post_capture_fee(&self, card: &CardKey, currency: &str) -> Option<f64> {
+        self.post_capture
+            .get(&post_capture_key(
+                card.connector,
+                card.network,
+                card.funding,
+                currency,
+            ))
+            .or_else(|| {
+                self.post_capture
+                    .get(&post_capture_connector_key(card.connector, currency))
+            })
+            .copied()
+    }
 }
 
 /// Look up an in-house cost at decide time. Tries the fine, category-predicted cluster first, then
-/// the coarse region blend; returns `None` when neither covers the key (caller falls back to
-/// seed/hypersense). `issuer` is the raw ISO country when known (for the fine path); `region` is the
-/// bucketed pricing region (for the coarse fallback).
+/// the coarse region blend, then the shared interchange plus the connector's markup; returns `None`
+/// when none covers the key (caller falls back to seed/hypersense). `issuer` is the raw ISO country when known (for the fine path); `region` is the
+/// bucketed pricing region (for the coarse fallback). `currency` is the payment's: when the
+/// connector's models (or contract) are in another currency only, they are converted into it.
+/// Whatever priced the capture, the network's expected refund and dispute fees are added on top.
 #[allow(clippy::too_many_arguments)]
 pub fn lookup(
     merchant_id: &str,
@@ -419,28 +558,49 @@ pub fn lookup(
     // to blended clusters and otherwise degrades to the coarse blend.
     let card_product = resolve_bin_product(bin);
 
+    let card = CardKey {
+        connector,
+        network,
+        funding,
+        program,
+        issuer,
+        region,
+        channel,
+        wallet,
+        card_product: &card_product,
+    };
     // Resolve the fine cluster key once (needs a raw issuer + a predicted interchange category).
     // Reused for both the highest-precedence cluster-override check (display key) and the learned
     // fine-model lookup (display key + card_product), so the two can never disagree on the cluster.
-    let fine = if issuer.is_empty() {
-        None
-    } else {
-        let variant = reconstruct_variant(network, program, funding, wallet);
-        let band = amount_band(amount);
-        predict_category(m, network, &variant, funding, issuer, &band, channel).map(|cat| {
-            let key = fine_key(
-                connector, network, &variant, funding, issuer, currency, &cat,
-            );
-            (key, variant, cat)
-        })
-    };
+    let fine = card.fine(m, currency, amount);
+
+    let hit = capture_cost(m, &card, fine.as_ref(), merchant_id, currency, amount)?;
+    Some(hit.with_post_capture(m, &card, amount))
+}
+
+/// What capturing the payment costs — steps 1–9 of [`lookup`], before refunds and disputes.
+fn capture_cost(
+    m: &MerchantModels,
+    card: &CardKey,
+    fine: Option<&(String, String, String)>,
+    merchant_id: &str,
+    currency: &str,
+    amount: f64,
+) -> Option<InhouseMatch> {
+    let CardKey {
+        connector,
+        network,
+        issuer,
+        ..
+    } = *card;
 
     // 1. Cluster override — the merchant set a fee for this exact segment (including its card
-    //    program). Most specific, wins over everything (connector override + learned model).
-    if let Some((key, variant, cat)) = &fine {
+    //    program). Most specific, wins over everything (contracts, overrides, learned model).
+    if let Some((key, variant, cat)) = fine {
         if let Some(cost) = m.cluster_overrides.get(key) {
             return Some(InhouseMatch {
                 effective_bps: cost.effective_cost_bps(amount),
+                stderr_bps: cost.stderr_bps,
                 pct_bps: cost.pct_bps,
                 fixed: cost.fixed,
                 brand: normalize_network(&network.to_lowercase()).to_string(),
@@ -448,16 +608,75 @@ pub fn lookup(
                 variant: Some(variant.clone()),
                 issuer: Some(issuer.to_uppercase()),
                 ic_category: Some(cat.clone()),
+                volume_tier: None,
+                fx: None,
+                post_capture_fee: None,
+                interchange_pct_bps: None,
+                interchange_fixed: None,
             });
         }
     }
 
-    // 2. Connector override: the merchant gave us this connector's blanket contract rate, so use it
-    //    flat for every transaction not covered by a cluster override above. `connector` is already
-    //    lowercased by the caller; lowercase again defensively so the key always matches.
+    // 2. Volume-tier contract: the connector is priced by cumulative monthly volume, so this payment
+    //    costs the marginal tier rate on top of what was already routed to it this month. A payment
+    //    in another currency is tiered on its amount in the schedule's currency, and its fixed fee
+    //    converted back — with no rate for the day it falls through.
+    if let Some(schedule) = m.volume_tiers.get(&connector.to_lowercase()) {
+        let same_currency = schedule.currency.eq_ignore_ascii_case(currency);
+        let fx_table = (!same_currency).then(fx::snapshot);
+        let fx_rate = match &fx_table {
+            None => Some(1.0),
+            Some(t) => t.rate(
+                &schedule.currency,
+                currency,
+                chrono::Utc::now().date_naive(),
+            ),
+        };
+        if let Some(fx_rate) = fx_rate {
+            let scheduled_amount = amount / fx_rate;
+            let mtd = volume_tiers::mtd_volume(
+                merchant_id,
+                &connector.to_lowercase(),
+                &schedule.currency,
+            );
+            if let Some(rate) = schedule.marginal(mtd, scheduled_amount) {
+                let cost = ServingCost {
+                    pct_bps: rate.pct_bps,
+                    fixed: rate.fixed,
+                    stderr_bps: 0.0,
+                };
+                let hit = InhouseMatch {
+                    effective_bps: cost.effective_cost_bps(scheduled_amount),
+                    stderr_bps: cost.stderr_bps,
+                    pct_bps: cost.pct_bps,
+                    fixed: cost.fixed,
+                    brand: normalize_network(&network.to_lowercase()).to_string(),
+                    currency: schedule.currency.to_uppercase(),
+                    variant: None,
+                    issuer: None,
+                    ic_category: None,
+                    volume_tier: Some(rate.tier),
+                    fx: None,
+                    post_capture_fee: None,
+                    interchange_pct_bps: None,
+                    interchange_fixed: None,
+                };
+                return Some(match fx_table {
+                    None => hit,
+                    Some(t) => hit.converted(currency, fx_rate, t.markup_bps(connector), amount),
+                });
+            }
+        }
+    }
+
+    // 3. Connector override: the merchant gave us this connector's blanket contract rate, so use it
+    //    flat for every transaction not covered by a cluster override or a volume-tier contract
+    //    above. `connector` is already lowercased by the caller; lowercase again defensively so the
+    //    key always matches.
     if let Some(cost) = m.overrides.get(&connector.to_lowercase()) {
         return Some(InhouseMatch {
             effective_bps: cost.effective_cost_bps(amount),
+            stderr_bps: cost.stderr_bps,
             pct_bps: cost.pct_bps,
             fixed: cost.fixed,
             brand: normalize_network(&network.to_lowercase()).to_string(),
@@ -465,93 +684,240 @@ pub fn lookup(
             variant: None,
             issuer: None,
             ic_category: None,
+            volume_tier: None,
+            fx: None,
+            post_capture_fee: None,
+            interchange_pct_bps: None,
+            interchange_fixed: None,
         });
     }
 
-    // The invoice-derived add-on for this connector (if any), layered onto the *learned* models
-    // below — never onto the overrides above, which are already all-in contract rates.
-    let addon = m.addons.get(&connector.to_lowercase());
-
-    // Both learned tables key on display-key + card_product. Build that key ONCE: `lookup` runs per
-    // candidate PSP on the decide path, so formatting it per table would allocate a String per PSP
-    // per decision for nothing.
-    if let Some((key, variant, cat)) = &fine {
-        let model_key = fine_key_model(key, &card_product);
+    // 4–7. The learned models, in the payment's currency.
+    if let Some(hit) = card.learned(m, fine, currency, amount) {
+        return Some(hit);
+    }
 
-        // 3. Amount-aware tiers: a capped/tiered cluster is priced by the piece this AMOUNT falls
-        //    in, not by one line across the whole range. Checked before the flat fine model because
-        //    it is the more specific answer for the same key (in practice the two are disjoint — the
-        //    segmenter only runs where the whole-cluster fit was not GOOD, and `fine` is GOOD-only).
-        if let Some(seg) = m.segmented.get(&model_key) {
-            let cost = seg.for_amount(amount).with_addon(addon);
-            return Some(InhouseMatch {
-                effective_bps: cost.effective_cost_bps(amount),
-                pct_bps: cost.pct_bps,
-                fixed: cost.fixed,
-                brand: normalize_network(&network.to_lowercase()).to_string(),
-                currency: currency.to_uppercase(),
-                variant: Some(variant.clone()),
-                issuer: Some(issuer.to_uppercase()),
-                ic_category: Some(cat.clone()),
+    // 8. Cross-currency: the connector only has models in the currencies it settles this merchant
+    //    in. Price the payment off each of those (most volume first) at its amount in that currency,
+    //    then convert the fixed fee back and charge the connector's FX markup.
+    if let Some(settlement) = m.settlement_currencies.get(&connector.to_lowercase()) {
+        let fx_table = fx::snapshot();
+        let today = chrono::Utc::now().date_naive();
+        let hit = settlement
+            .iter()
+            .filter(|ccy| !ccy.eq_ignore_ascii_case(currency))
+            .find_map(|ccy| {
+                let rate = fx_table.rate(ccy, currency, today)?;
+                let settled_amount = amount / rate;
+                let fine = card.fine(m, ccy, settled_amount);
+                let hit = card.learned(m, fine.as_ref(), ccy, settled_amount)?;
+                Some(hit.converted(currency, rate, fx_table.markup_bps(connector), amount))
             });
+        if hit.is_some() {
+            return hit;
         }
+    }
 
-        // 4. Learned fine model: the specific fitted cluster (display key + card_product, so the
-        //    fan's 135/180 tiers resolve to their own rate), plus the invoice add-on.
-        if let Some(cost) = m.fine.get(&model_key) {
-            let cost = cost.with_addon(addon);
-            return Some(InhouseMatch {
+    // 9. Interchange++: the connector has no model for this card (or none at all), but its markup
+    //    is known — learned from its itemised reports or declared by the merchant — so price the
+    //    card's shared interchange plus that markup.
+    card.interchange_plus(m, currency, amount)
+}
+
+/// The decide-time card attributes [`lookup`] keys the learned models on — everything but the
+/// currency and amount, which change when a payment is priced off another currency's models.
+struct CardKey<'a> {
+    connector: &'a str,
+    network: &'a str,
+    funding: &'a str,
+    program: &'a str,
+    issuer: &'a str,
+    region: &'a str,
+    channel: &'a str,
+    wallet: &'a str,
+    card_product: &'a str,
+}
+
+impl CardKey<'_> {
+    /// The fine cluster's display key, reconstructed variant and predicted category, when the issuer
+    /// is known and a category can be predicted.
+    fn fine(
+        &self,
+        m: &MerchantModels,
+        currency: &str,
+        amount: f64,
+    ) -> Option<(String, String, String)> {
+        if self.issuer.is_empty() {
+            return None;
+        }
+        let variant = reconstruct_variant(self.network, self.program, self.funding, self.wallet);
+        let band = amount_band(amount);
+        predict_category(
+            m,
+            self.network,
+            &variant,
+            self.funding,
+            self.issuer,
+            &band,
+            self.channel,
+        )
+        .map(|cat| {
+            let key = fine_key(
+                self.connector,
+                self.network,
+                &variant,
+                self.funding,
+                self.issuer,
+                currency,
+                &cat,
+            );
+            (key, variant, cat)
+        })
+    }
+
+    /// Price off the learned models in `currency` (steps 4–7 of [`lookup`]), with the connector's
+    /// invoice add-on.
+    fn learned(
+        &self,
+        m: &MerchantModels,
+        fine: Option<&(String, String, String)>,
+        currency: &str,
+        amount: f64,
+    ) -> Option<InhouseMatch> {
+        // The invoice-derived add-on for this connector (if any), layered onto the *learned* models
+        // below — never onto the overrides, which are already all-in contract rates.
+        let addon = m.addons.get(&self.connector.to_lowercase());
+        let brand = normalize_network(&self.network.to_lowercase()).to_string();
+
+        // Both learned tables key on display-key + card_product. Build that key ONCE: `lookup` runs
+        // per candidate PSP on the decide path, so formatting it per table would allocate a String
+        // per PSP per decision for nothing.
+        if let Some((key, variant, cat)) = fine {
+            let model_key = fine_key_model(key, self.card_product);
+            let fine_match = |cost: ServingCost| InhouseMatch {
                 effective_bps: cost.effective_cost_bps(amount),
+                stderr_bps: cost.stderr_bps,
                 pct_bps: cost.pct_bps,
                 fixed: cost.fixed,
-                brand: normalize_network(&network.to_lowercase()).to_string(),
+                brand: brand.clone(),
                 currency: currency.to_uppercase(),
                 variant: Some(variant.clone()),
-                issuer: Some(issuer.to_uppercase()),
+                issuer: Some(self.issuer.to_uppercase()),
                 ic_category: Some(cat.clone()),
+                volume_tier: None,
+                fx: None,
+                post_capture_fee: None,
+                interchange_pct_bps: None,
+                interchange_fixed: None,
+            };
+
+            // 4. Amount-aware tiers: a capped/tiered cluster is priced by the piece this AMOUNT
+            //    falls in, not by one line across the whole range. Checked before the flat fine
+            //    model because it is the more specific answer for the same key (in practice the two
+            //    are disjoint — the segmenter only runs where the whole-cluster fit was not GOOD,
+            //    and `fine` is GOOD-only).
+            if let Some(seg) = m.segmented.get(&model_key) {
+                return Some(fine_match(seg.for_amount(amount).with_addon(addon)));
+            }
+
+            // 5. Learned fine model: the specific fitted cluster (display key + card_product, so the
+            //    fan's 135/180 tiers resolve to their own rate), plus the invoice add-on.
+            if let Some(cost) = m.fine.get(&model_key) {
+                return Some(fine_match(cost.with_addon(addon)));
+            }
+
+            // 6. Card product unresolved: price the display key by blending every product under
+            //    it, each evaluated at THIS amount. Reached when the request carries no BIN, or a
+            //    BIN we haven't mapped yet — previously that abstained and fell through to the
+            //    regional blend (or, with nothing there, to the seed table) despite an exact
+            //    display-key match existing. The variant-blanked key is the last rung: it also
+            //    covers a report whose variant vocabulary we can't reconstruct (see
+            //    `without_variant`), blending that cluster's card programs by volume rather than
+            //    abstaining to a regional average or the seed table.
+            let blend = m.fine_blend.get(key).or_else(|| {
+                without_variant(key).and_then(|k| m.fine_blend.get(&k).map(|b| b as &FineBlend))
             });
+            if let Some(blend) = blend {
+                return Some(fine_match(blend.for_amount(amount).with_addon(addon)));
+            }
         }
 
-        // 5. Card product unresolved: price the display key by blending every product under it,
-        //    each evaluated at THIS amount. Reached when the request carries no BIN, or a BIN we
-        //    haven't mapped yet — previously that abstained and fell through to the regional blend
-        //    (or, with nothing there, to the seed table) despite an exact display-key match existing.
-        //    The variant-blanked key is the last rung: it also covers a report whose variant
-        //    vocabulary we can't reconstruct (see `without_variant`), blending that cluster's card
-        //    programs by volume rather than abstaining to a regional average or the seed table.
-        let blend = m.fine_blend.get(key).or_else(|| {
-            without_variant(key).and_then(|k| m.fine_blend.get(&k).map(|b| b as &FineBlend))
-        });
-        if let Some(blend) = blend {
-            let cost = blend.for_amount(amount).with_addon(addon);
-            return Some(InhouseMatch {
+        // 7. Fallback: the coarse region blend (previous behavior) — no single
+        //    variant/issuer/category.
+        let key = coarse_key(
+            self.connector,
+            self.network,
+            self.funding,
+            currency,
+            self.region,
+        );
+        m.coarse.get(&key).map(|cost| {
+            let cost = cost.with_addon(addon);
+            InhouseMatch {
                 effective_bps: cost.effective_cost_bps(amount),
+                stderr_bps: cost.stderr_bps,
                 pct_bps: cost.pct_bps,
                 fixed: cost.fixed,
-                brand: normalize_network(&network.to_lowercase()).to_string(),
+                brand,
                 currency: currency.to_uppercase(),
-                variant: Some(variant.clone()),
-                issuer: Some(issuer.to_uppercase()),
-                ic_category: Some(cat.clone()),
-            });
-        }
+                variant: None,
+                issuer: None,
+                ic_category: None,
+                volume_tier: None,
+                fx: None,
+                post_capture_fee: None,
+                interchange_pct_bps: None,
+                interchange_fixed: None,
+            }
+        })
     }
+}
 
-    // 6. Fallback: the coarse region blend (previous behavior) — no single variant/issuer/category.
-    let key = coarse_key(connector, network, funding, currency, region);
-    m.coarse.get(&key).map(|cost| {
-        let cost = cost.with_addon(addon);
-        InhouseMatch {
+impl CardKey<'_> {
+    /// Price off the shared interchange table plus the connector's markup (step 9 of [`lookup`]),
+    /// with the connector's invoice add-on. A declared markup wins over the learned one.
+    fn interchange_plus(
+        &self,
+        m: &MerchantModels,
+        currency: &str,
+        amount: f64,
+    ) -> Option<InhouseMatch> {
+        let connector = self.connector.to_lowercase();
+        let markup = m.declared_markups.get(&connector).or_else(|| {
+            m.markups
+                .get(&interchange::markup_key(&connector, currency))
+        })?;
+        let ic = interchange::table().get(
+            self.network,
+            self.funding,
+            self.issuer,
+            self.region,
+            currency,
+            self.card_product,
+        )?;
+        let cost = ServingCost {
+            pct_bps: ic.pct_bps + markup.pct_bps,
+            fixed: ic.fixed + markup.fixed,
+            stderr_bps: ic.stderr_bps.hypot(markup.stderr_bps),
+        }
+        .with_addon(m.addons.get(&connector));
+        Some(InhouseMatch {
             effective_bps: cost.effective_cost_bps(amount),
+            stderr_bps: cost.stderr_bps,
             pct_bps: cost.pct_bps,
             fixed: cost.fixed,
-            brand: normalize_network(&network.to_lowercase()).to_string(),
+            brand: normalize_network(&self.network.to_lowercase()).to_string(),
             currency: currency.to_uppercase(),
             variant: None,
-            issuer: None,
+            issuer: (!self.issuer.is_empty()).then(|| self.issuer.to_uppercase()),
             ic_category: None,
-        }
-    })
+            volume_tier: None,
+            fx: None,
+            post_capture_fee: None,
+            interchange_pct_bps: Some(ic.pct_bps),
+            interchange_fixed: Some(ic.fixed),
+        })
+    }
 }
 
 /// Predict the interchange category by trying each back-off level, most specific first.
@@ -601,6 +967,7 @@ pub fn spawn(clickhouse: ClickHouseAnalyticsConfig) {
 
 /// Latest GOOD clusters (per merchant/connector/account snapshot), for the coarse blend and the
 /// fine per-category table. Per-country weighted numerators so we finish region bucketing here.
+/// `n_obs` / `rmse` pool the accounts' residuals, for the fit's standard error.
 /// `{merchant_filter}` / `{merchant_filter_sub}` are replaced with a `merchant_id = {merchant:String}`
 /// predicate for a single-merchant refresh (cheap, scans only that merchant — including the
 /// `max(report_date)` subquery), or with `""` for the periodic global rebuild.
@@ -610,7 +977,9 @@ SELECT
     card_product,
     sum(pct_bps * gross_sum) AS pct_num,
     sum(fixed * gross_sum)   AS fixed_num,
-    sum(gross_sum)           AS w
+    sum(gross_sum)           AS w,
+    sum(n)                   AS n_obs,
+    sqrt(sum(bps_rmse * bps_rmse * n) / sum(n)) AS rmse
 FROM __DB__.cost_fee_model FINAL
 WHERE verdict = 'GOOD' AND gross_sum > 0{merchant_filter}
   AND (merchant_id, connector, account, report_date) IN (
@@ -633,7 +1002,8 @@ FORMAT TSV
 const SEGMENT_SQL: &str = r#"
 WITH latest AS (
     SELECT merchant_id, connector, account, card_network, variant, funding, issuer_country, currency,
-           ic_category, card_product, seg_idx, lo, hi, pct_bps, fixed, gross_sum, verdict
+           ic_category, card_product, seg_idx, lo, hi, pct_bps, fixed, gross_sum, verdict,
+           bps_rmse, n
     FROM __DB__.cost_fee_model_segment FINAL
     WHERE gross_sum > 0{merchant_filter}
       AND (merchant_id, connector, account, report_date) IN (
@@ -654,7 +1024,7 @@ topped AS (
     FROM ranked
 )
 SELECT merchant_id, connector, card_network, variant, funding, issuer_country, currency,
-       ic_category, card_product, seg_idx, lo, hi, pct_bps, fixed, gross_sum, verdict
+       ic_category, card_product, seg_idx, lo, hi, pct_bps, fixed, gross_sum, verdict, bps_rmse, n
 FROM topped
 WHERE ladder_vol = top_vol
 ORDER BY merchant_id, connector, card_network, variant, funding, issuer_country, currency,
@@ -678,6 +1048,31 @@ GROUP BY merchant_id, card_network, variant, funding, issuer_country, band, chan
 FORMAT TSV
 "#;
 
+/// Captures and refund/dispute fees per (merchant, connector, network, funding, currency, kind) over
+/// the last `__WINDOW__` days before each connector's latest transaction — relative to the data, not
+/// the wall clock, like the fit's window. Captures come from `cost_daily_stats` (kind `capture`, no
+/// fees), the events from `cost_post_capture_stats`. `{merchant_filter_sub}` mirrors `COST_SQL`.
+const POST_CAPTURE_SQL: &str = r#"
+WITH latest AS (
+    SELECT merchant_id, connector, max(txn_date) AS last_day
+    FROM __DB__.cost_daily_stats{merchant_filter_sub}
+    GROUP BY merchant_id, connector
+)
+SELECT merchant_id, connector, card_network, funding, currency, kind, sum(n) AS n, sum(fees) AS fees
+FROM (
+    SELECT merchant_id, connector, txn_date, card_network, funding, currency,
+           'capture' AS kind, n, toFloat64(0) AS fees
+    FROM __DB__.cost_daily_stats FINAL{merchant_filter_sub}
+    UNION ALL
+    SELECT merchant_id, connector, txn_date, card_network, funding, currency, kind, n, fees
+    FROM __DB__.cost_post_capture_stats FINAL{merchant_filter_sub}
+) AS s
+INNER JOIN latest USING (merchant_id, connector)
+WHERE s.txn_date > latest.last_day - toIntervalDay(__WINDOW__)
+GROUP BY merchant_id, connector, card_network, funding, currency, kind
+FORMAT TSV
+"#;
+
 /// Rebuild the **entire** served-model cache from ClickHouse. Used by the periodic background ticker
 /// (off the request path). `O(all merchants)` — for the inline post-ingest/-delete refresh prefer
 /// [`refresh_merchant`], which touches only the affected merchant.
@@ -708,8 +1103,20 @@ async fn refresh_inner(
     if let Ok(mut guard) = bin_cache().write() {
         *guard = bin_map;
     }
+    // The shared interchange table is global too, and refit as often.
+    interchange::refresh_table(cfg).await;
 
     // Splice the merchant predicate into the queries (or clear the placeholders for a global rebuild).
+    let post_capture_sql = POST_CAPTURE_SQL
+        .replace("__WINDOW__", &POST_CAPTURE_WINDOW_DAYS.to_string())
+        .replace(
+            "{merchant_filter_sub}",
+            if merchant.is_some() {
+                " WHERE merchant_id = {merchant:String}"
+            } else {
+                ""
+            },
+        );
     let (cost_sql, pred_sql, seg_sql) = match merchant {
         Some(_) => (
             COST_SQL
@@ -748,15 +1155,33 @@ async fn refresh_inner(
             String::new()
         }
     };
+    // Refund and dispute fees are an enrichment too: without them captures are still priced.
+    let post_capture_rows = match query(cfg, &post_capture_sql, merchant).await {
+        Ok(r) => r,
+        Err(e) => {
+            logger::warn!(tag = "cost_serving", "post-capture load skipped: {}", e);
+            String::new()
+        }
+    };
+    // So are the IC++ markups, which only price cards the connector has no model for.
+    let markups = match interchange::learned_markups(cfg, merchant).await {
+        Ok(m) => m,
+        Err(e) => {
+            logger::warn!(tag = "cost_serving", "markup load skipped: {}", e);
+            HashMap::new()
+        }
+    };
 
     let mut snap: Snapshot = HashMap::new();
 
     // 1. Cost tables (coarse blend + fine per-category), volume-weighted.
-    let mut coarse_acc: HashMap<String, HashMap<String, (f64, f64, f64)>> = HashMap::new(); // merchant -> key -> (pct_num, fix_num, w)
-    let mut fine_acc: HashMap<String, HashMap<String, (f64, f64, f64)>> = HashMap::new();
+    let mut coarse_acc: HashMap<String, HashMap<String, (f64, f64, f64, f64)>> = HashMap::new(); // merchant -> key -> (pct_num, fix_num, w, var_num)
+    let mut fine_acc: HashMap<String, HashMap<String, (f64, f64, f64, f64)>> = HashMap::new();
+    // merchant -> (connector, CURRENCY) -> settled volume behind its models.
+    let mut currency_volume: HashMap<String, HashMap<(String, String), f64>> = HashMap::new();
     for line in cost_rows.lines() {
         let f: Vec<&str> = line.split('\t').collect();
-        if f.len() < 12 {
+        if f.len() < 14 {
             continue;
         }
         let (merchant, connector, network, variant, funding, issuer, currency, ic, card_product) =
@@ -767,6 +1192,12 @@ async fn refresh_inner(
         if w <= 0.0 {
             continue;
         }
+        let var_num = (w * stderr_bps(f[13], f[12])).powi(2);
+        *currency_volume
+            .entry(merchant.to_string())
+            .or_default()
+            .entry((connector.to_lowercase(), currency.to_uppercase()))
+            .or_default() += w;
         // Coarse blend deliberately drops card_product (and variant/issuer): it is the fallback that
         // averages a connector's whole region, so the fan's tiers merge back into one number here.
         let region = issuer_region(issuer);
@@ -777,6 +1208,7 @@ async fn refresh_inner(
             pct_num,
             fix_num,
             w,
+            var_num,
         );
         // Fine table keeps the tiers distinct — display key + card_product.
         let fk = fine_key_model(
@@ -789,6 +1221,7 @@ async fn refresh_inner(
             pct_num,
             fix_num,
             w,
+            var_num,
         );
     }
     for (merchant, keys) in coarse_acc {
@@ -802,7 +1235,9 @@ async fn refresh_inner(
         .map(|(m, keys)| {
             (
                 m.clone(),
-                keys.iter().map(|(k, (_, _, w))| (k.clone(), *w)).collect(),
+                keys.iter()
+                    .map(|(k, (_, _, w, _))| (k.clone(), *w))
+                    .collect(),
             )
         })
         .collect();
@@ -813,10 +1248,39 @@ async fn refresh_inner(
 
     // 1b. Amount tiers for capped/tiered clusters.
     for (merchant, ladders) in segments_from_tsv(&seg_rows) {
+        let volumes = currency_volume.entry(merchant.clone()).or_default();
+        for (model_key, ladder) in &ladders {
+            // connector | network | variant | funding | issuer | currency | ic_category | product
+            let p: Vec<&str> = model_key.split('|').collect();
+            if p.len() > 5 {
+                *volumes
+                    .entry((p[0].to_string(), p[5].to_uppercase()))
+                    .or_default() += ladder.weight;
+            }
+        }
         snap.entry(merchant).or_default().segmented = ladders;
     }
 
-    // 1c. Per-display-key blend across card products, for requests whose product can't be resolved
+    // 1c. Which currencies each connector is modelled in, by settled volume, for pricing a payment
+    //     in a currency it has no model in.
+    for (merchant, volumes) in currency_volume {
+        let mut by_connector: HashMap<String, Vec<(String, f64)>> = HashMap::new();
+        for ((connector, currency), w) in volumes {
+            by_connector
+                .entry(connector)
+                .or_default()
+                .push((currency, w));
+        }
+        snap.entry(merchant).or_default().settlement_currencies = by_connector
+            .into_iter()
+            .map(|(connector, mut ccys)| {
+                ccys.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
+                (connector, ccys.into_iter().map(|(c, _)| c).collect())
+            })
+            .collect();
+    }
+
+    // 1d. Per-display-key blend across card products, for requests whose product can't be resolved
     //     (no BIN, or an unmapped one). Members come from BOTH tables and they are disjoint by
     //     construction: `fine` holds only GOOD clusters, and the segmenter only runs on non-GOOD
     //     ones, so no cluster is counted twice.
@@ -919,6 +1383,28 @@ async fn refresh_inner(
         snap.entry(merchant).or_default().predictor = tables;
     }
 
+    // 2b. Expected refund and dispute fees per capture.
+    for (merchant, table) in post_capture_from_tsv(&post_capture_rows) {
+        snap.entry(merchant).or_default().post_capture = table;
+    }
+
+    // 2c. Learned IC++ markups.
+    for (merchant, lines) in markups {
+        snap.entry(merchant).or_default().markups = lines
+            .into_iter()
+            .map(|(key, l)| {
+                (
+                    key,
+                    ServingCost {
+                        pct_bps: l.pct_bps,
+                        fixed: l.fixed,
+                        stderr_bps: l.stderr_bps,
+                    },
+                )
+            })
+            .collect();
+    }
+
     // 3. Manual blended-fee overrides (Postgres, not ClickHouse). Attach them to the snapshot so
     //    `lookup` can prefer them. A single-merchant refresh loads just that merchant; the global
     //    rebuild walks the override-merchant index so override-only connectors (no ClickHouse data)
@@ -926,8 +1412,8 @@ async fn refresh_inner(
     match merchant {
         Some(mid) => load_overlays_into(&mut snap, mid).await,
         None => {
-            // Union the override-merchant and invoice-add-on-merchant indices, so an overlay-only
-            // merchant (manual override *or* invoice add-on, no ClickHouse data) is still hydrated.
+            // Union the override, invoice-add-on, volume-tier and declared-markup merchant indices,
+            // so an overlay-only merchant (no ClickHouse data) is still hydrated.
             let mut merchants = super::overrides::list_merchants()
                 .await
                 .unwrap_or_else(|e| {
@@ -944,6 +1430,30 @@ async fn refresh_inner(
                 }
                 Err(e) => logger::warn!(tag = "cost_serving", "add-on index load failed: {:?}", e),
             }
+            match volume_tiers::list_merchants().await {
+                Ok(tier_merchants) => {
+                    for mid in tier_merchants {
+                        if !merchants.contains(&mid) {
+                            merchants.push(mid);
+                        }
+                    }
+                }
+                Err(e) => logger::warn!(
+                    tag = "cost_serving",
+                    "volume-tier index load failed: {:?}",
+                    e
+                ),
+            }
+            match interchange::list_markup_merchants().await {
+                Ok(markup_merchants) => {
+                    for mid in markup_merchants {
+                        if !merchants.contains(&mid) {
+                            merchants.push(mid);
+                        }
+                    }
+                }
+                Err(e) => logger::warn!(tag = "cost_serving", "markup index load failed: {:?}", e),
+            }
             for mid in merchants {
                 load_overlays_into(&mut snap, &mid).await;
             }
@@ -982,9 +1492,9 @@ async fn refresh_inner(
     }
 }
 
-/// Load a merchant's serving-time overlays — manual overrides *and* the invoice-derived cost add-on
-/// — and set them on its snapshot entry (creating the entry when the merchant has overlays but no
-/// ClickHouse-derived models). Each overlay is non-fatal on error.
+/// Load a merchant's serving-time overlays — manual overrides, the invoice-derived cost add-on,
+/// volume-tier contracts and declared IC++ markups — and set them on its snapshot entry (creating the entry when the merchant
+/// has overlays but no ClickHouse-derived models). Each overlay is non-fatal on error.
 async fn load_overlays_into(snap: &mut Snapshot, merchant_id: &str) {
     // Connector-level overrides (lowercase connector → flat cost).
     match super::overrides::list(merchant_id).await {
@@ -997,6 +1507,7 @@ async fn load_overlays_into(snap: &mut Snapshot, merchant_id: &str) {
                         ServingCost {
                             pct_bps: ov.pct_bps,
                             fixed: ov.fixed,
+                            stderr_bps: 0.0,
                         },
                     )
                 })
@@ -1032,6 +1543,7 @@ async fn load_overlays_into(snap: &mut Snapshot, merchant_id: &str) {
                         ServingCost {
                             pct_bps: c.pct_bps,
                             fixed: c.fixed,
+                            stderr_bps: 0.0,
                         },
                     )
                 })
@@ -1061,6 +1573,7 @@ async fn load_overlays_into(snap: &mut Snapshot, merchant_id: &str) {
                         ServingCost {
                             pct_bps: a.pct_addon_bps,
                             fixed: a.fixed_addon,
+                            stderr_bps: 0.0,
                         },
                     )
                 })
@@ -1075,6 +1588,56 @@ async fn load_overlays_into(snap: &mut Snapshot, merchant_id: &str) {
             e
         ),
     }
+
+    // Volume-tier contracts (lowercase connector → schedule), priced against month-to-date volume
+    // at lookup.
+    match volume_tiers::list(merchant_id).await {
+        Ok(list) if !list.is_empty() => {
+            let schedules = list
+                .into_iter()
+                .map(|(connector, s)| (connector.to_lowercase(), s))
+                .collect();
+            snap.entry(merchant_id.to_string())
+                .or_default()
+                .volume_tiers = schedules;
+        }
+        Ok(_) => {}
+        Err(e) => logger::warn!(
+            tag = "cost_serving",
+            "volume-tier schedule load failed for {}: {:?}",
+            merchant_id,
+            e
+        ),
+    }
+
+    // Declared IC++ markups (lowercase connector → markup over the shared interchange).
+    match interchange::list_markups(merchant_id).await {
+        Ok(list) if !list.is_empty() => {
+            let markups = list
+                .into_iter()
+                .map(|(connector, mk)| {
+                    (
+                        connector.to_lowercase(),
+                        ServingCost {
+                            pct_bps: mk.pct_bps,
+                            fixed: mk.fixed,
+                            stderr_bps: 0.0,
+                        },
+                    )
+                })
+                .collect();
+            snap.entry(merchant_id.to_string())
+                .or_default()
+                .declared_markups = markups;
+        }
+        Ok(_) => {}
+        Err(e) => logger::warn!(
+            tag = "cost_serving",
+            "declared markup load failed for {}: {:?}",
+            merchant_id,
+            e
+        ),
+    }
 }
 
 /// Build each merchant's amount-tier ladders from [`SEGMENT_SQL`]'s TSV.
@@ -1086,7 +1649,7 @@ async fn load_overlays_into(snap: &mut Snapshot, merchant_id: &str) {
 /// Returns `merchant → fine_key_model → ladder`.
 fn segments_from_tsv(rows: &str) -> HashMap<String, HashMap<String, SegmentedCost>> {
     let mut acc: HashMap<String, HashMap<String, Vec<ServingSegment>>> = HashMap::new();
-    let mut blend_acc: HashMap<String, HashMap<String, (f64, f64, f64)>> = HashMap::new();
+    let mut blend_acc: HashMap<String, HashMap<String, (f64, f64, f64, f64)>> = HashMap::new();
 
     for line in rows.lines() {
         let f: Vec<&str> = line.split('\t').collect();
@@ -1107,6 +1670,11 @@ fn segments_from_tsv(rows: &str) -> HashMap<String, HashMap<String, SegmentedCos
         };
         let gross: f64 = f[14].trim().parse().unwrap_or(0.0);
         let good = f[15].trim().eq_ignore_ascii_case("GOOD");
+        // `bps_rmse`/`n` trail the row; a piece without them has no known error.
+        let stderr_bps = match (f.get(16), f.get(17)) {
+            (Some(rmse), Some(n)) => stderr_bps(rmse, n),
+            _ => 0.0,
+        };
         let key = fine_key_model(
             &fine_key(connector, network, variant, funding, issuer, currency, ic),
             card_product,
@@ -1118,7 +1686,11 @@ fn segments_from_tsv(rows: &str) -> HashMap<String, HashMap<String, SegmentedCos
             .push(ServingSegment {
                 lo,
                 hi,
-                cost: ServingCost { pct_bps, fixed },
+                cost: ServingCost {
+                    pct_bps,
+                    fixed,
+                    stderr_bps,
+                },
                 good,
             });
         // The ladder's fallback blend is weighted over its GOOD pieces only — averaging in a piece we
@@ -1130,6 +1702,7 @@ fn segments_from_tsv(rows: &str) -> HashMap<String, HashMap<String, SegmentedCos
                 pct_bps * gross,
                 fixed * gross,
                 gross,
+                (stderr_bps * gross).powi(2),
             );
         }
     }
@@ -1139,8 +1712,10 @@ fn segments_from_tsv(rows: &str) -> HashMap<String, HashMap<String, SegmentedCos
         let raw = blend_acc.remove(&merchant).unwrap_or_default();
         // Keep the raw weights before `finalize` divides them away — they weight this ladder against
         // sibling card products when the product can't be resolved at decide time.
-        let blends_w: HashMap<String, f64> =
-            raw.iter().map(|(k, (_, _, w))| (k.clone(), *w)).collect();
+        let blends_w: HashMap<String, f64> = raw
+            .iter()
+            .map(|(k, (_, _, w, _))| (k.clone(), *w))
+            .collect();
         let blends = finalize(raw);
         let entry = out.entry(merchant).or_default();
         for (key, mut segments) in ladders {
@@ -1166,35 +1741,104 @@ fn segments_from_tsv(rows: &str) -> HashMap<String, HashMap<String, SegmentedCos
     out
 }
 
+/// Expected refund and dispute fees per capture from [`POST_CAPTURE_SQL`] rows, per merchant, at the
+/// network (`connector|network|funding|currency`) and connector (`connector|currency`) levels. A
+/// network level is kept on [`POST_CAPTURE_MIN_CAPTURES`] captures, the connector level on
+/// [`MIN_SUPPORT`]. A connector whose reports carried no refund or dispute in the window gets no
+/// entry rather than a measured zero — most likely its reports just don't itemize them.
+fn post_capture_from_tsv(rows: &str) -> HashMap<String, HashMap<String, f64>> {
+    // merchant -> level key -> (captures, events, fees)
+    let mut acc: HashMap<String, HashMap<String, (u64, u64, f64)>> = HashMap::new();
+    for line in rows.lines() {
+        let f: Vec<&str> = line.split('\t').collect();
+        if f.len() < 8 {
+            continue;
+        }
+        let (merchant, connector, network, funding, currency, kind) =
+            (f[0], f[1], f[2], f[3], f[4], f[5]);
+        let n: u64 = f[6].trim().parse().unwrap_or(0);
+        let fees: f64 = f[7].trim().parse().unwrap_or(0.0);
+        let levels = acc.entry(merchant.to_string()).or_default();
+        for key in [
+            post_capture_key(connector, network, funding, currency),
+            post_capture_connector_key(connector, currency),
+        ] {
+            let e = levels.entry(key).or_default();
+            if kind == "capture" {
+                e.0 += n;
+            } else {
+                e.1 += n;
+                e.2 += fees;
+            }
+        }
+    }
+    acc.into_iter()
+        .map(|(merchant, levels)| {
+            let table = levels
+                .iter()
+                .filter_map(|(key, &(captures, _, fees))| {
+                    let parts: Vec<&str> = key.split('|').collect();
+                    let (connector, currency, min_captures) = match parts[..] {
+                        [connector, _, _, currency] => {
+                            (connector, currency, POST_CAPTURE_MIN_CAPTURES)
+                        }
+                        [connector, currency] => (connector, currency, MIN_SUPPORT),
+                        _ => return None,
+                    };
+                    let (_, connector_events, _) =
+                        levels.get(&post_capture_connector_key(connector, currency))?;
+                    (captures >= min_captures && *connector_events > 0)
+                        .then(|| (key.clone(), fees / captures as f64))
+                })
+                .collect();
+            (merchant, table)
+        })
+        .collect()
+}
+
+/// A fit's standard error in bps from its TSV `bps_rmse` and `n`: the per-transaction residual over
+/// `√n`. `0` when either is missing (`\N`) or degenerate.
+fn stderr_bps(rmse: &str, n: &str) -> f64 {
+    match (rmse.trim().parse::<f64>(), n.trim().parse::<f64>()) {
+        (Ok(rmse), Ok(n)) if rmse.is_finite() && n > 0.0 => rmse / n.sqrt(),
+        _ => 0.0,
+    }
+}
+
+/// Add one weighted cluster to a blend. `var_num` is `(w · stderr_bps)²`: the blend is a weighted
+/// mean of independent fits, so its variance is `Σ(w·se)² / (Σw)²`.
 fn accumulate(
-    map: &mut HashMap<String, (f64, f64, f64)>,
+    map: &mut HashMap<String, (f64, f64, f64, f64)>,
     key: String,
     pct_num: f64,
     fix_num: f64,
     w: f64,
+    var_num: f64,
 ) {
-    let e = map.entry(key).or_insert((0.0, 0.0, 0.0));
+    let e = map.entry(key).or_insert((0.0, 0.0, 0.0, 0.0));
     e.0 += pct_num;
     e.1 += fix_num;
     e.2 += w;
+    e.3 += var_num;
 }
 
-fn finalize(keys: HashMap<String, (f64, f64, f64)>) -> HashMap<String, ServingCost> {
+fn finalize(keys: HashMap<String, (f64, f64, f64, f64)>) -> HashMap<String, ServingCost> {
     keys.into_iter()
-        .filter(|(_, (_, _, w))| *w > 0.0)
-        .map(|(k, (pn, fn_, w))| {
+        .filter(|(_, (_, _, w, _))| *w > 0.0)
+        .map(|(k, (pn, fn_, w, vn))| {
             (
                 k,
                 ServingCost {
                     pct_bps: pn / w,
                     fixed: fn_ / w,
+                    stderr_bps: vn.sqrt() / w,
                 },
             )
         })
         .collect()
 }
 
-async fn query(
+pub(super) async fn query(
     cfg: &ClickHouseAnalyticsConfig,
     sql: &str,
     merchant: Option<&str>,
@@ -1240,6 +1884,7 @@ mod tests {
                     cost: ServingCost {
                         pct_bps: 170.1,
                         fixed: 0.42,
+                        stderr_bps: 0.0,
                     },
                     good: true,
                 },
@@ -1249,6 +1894,7 @@ mod tests {
                     cost: ServingCost {
                         pct_bps: 69.7,
                         fixed: 50.72,
+                        stderr_bps: 0.0,
                     },
                     good: true,
                 },
@@ -1256,6 +1902,7 @@ mod tests {
             blend: ServingCost {
                 pct_bps: 94.6,
                 fixed: 38.23,
+                stderr_bps: 0.0,
             },
             // Real settled volume behind this ladder, so it weights correctly against siblings.
             weight: 1_803_717.0,
@@ -1297,6 +1944,7 @@ mod tests {
         let one_line = ServingCost {
             pct_bps: 106.4,
             fixed: 7.06,
+            stderr_bps: 0.0,
         }
         .effective_cost_bps(50.0);
         assert!(
@@ -1406,6 +2054,7 @@ m1\tadyen\tvisa\tvisadebit\tdebit\tAE\tAED\tVisa UAE Consumer Debit\t100\t0\t20\
                     MemberCost::Flat(ServingCost {
                         pct_bps: 200.0,
                         fixed: 0.0,
+                        stderr_bps: 0.0,
                     }),
                 ),
                 // 1M of the capped UAE debit ladder: 170.1 bps under the knot, 69.7 above.
@@ -1564,10 +2213,12 @@ m1\tadyen\tvisa\tvisadebit\tdebit\tAE\tAED\tVisa UAE Consumer Debit\t100\t0\t20\
         let learned = ServingCost {
             pct_bps: 40.0,
             fixed: 0.10,
+            stderr_bps: 0.0,
         };
         let addon = ServingCost {
             pct_bps: 0.06,
             fixed: 0.04,
+            stderr_bps: 0.0,
         }; // ~invoice add-on
         let combined = learned.with_addon(Some(&addon));
         assert!((combined.pct_bps - 40.06).abs() < 1e-9);
@@ -1581,6 +2232,198 @@ m1\tadyen\tvisa\tvisadebit\tdebit\tAE\tAED\tVisa UAE Consumer Debit\t100\t0\t20\
         assert_eq!(learned.with_addon(None).fixed, learned.fixed);
     }
 
+    #[test]
+    fn volume_tier_contract_prices_before_the_connector_override() {
+        use super::super::volume_tiers::VolumeTier;
+
+        let merchant = "volume_tier_lookup_test";
+        let mut models = MerchantModels::default();
+        models.overrides.insert(
+            "adyen".to_string(),
+            ServingCost {
+                pct_bps: 250.0,
+                fixed: 0.0,
+                stderr_bps: 0.0,
+            },
+        );
+        models.volume_tiers.insert(
+            "adyen".to_string(),
+            VolumeTierSchedule {
+                currency: "EUR".to_string(),
+                tiers: vec![
+                    VolumeTier {
+                        up_to: Some(1_000_000.0),
+                        pct_bps: 190.0,
+                        fixed: 0.0,
+                    },
+                    VolumeTier {
+                        up_to: None,
+                        pct_bps: 160.0,
+                        fixed: 0.0,
+                    },
+                ],
+                updated_at: String::new(),
+            },
+        );
+        {
+            let mut guard = cache().write().unwrap();
+            let mut snap: Snapshot = (**guard).clone();
+            snap.insert(merchant.to_string(), models);
+            *guard = Arc::new(snap);
+        }
+        let price = |currency: &str| {
+            lookup(
+                merchant, "adyen", "visa", "credit", "", currency, "", "", "", "", "", 100.0,
+            )
+            .unwrap()
+        };
+
+        // No month-to-date volume synced yet: the first tier prices the payment.
+        let eur = price("EUR");
+        assert_eq!(eur.volume_tier, Some(0));
+        assert!((eur.pct_bps - 190.0).abs() < 1e-9);
+        // The schedule is stated in EUR and no EUR → USD rate is loaded, so a USD payment falls
+        // through to the connector override.
+        let usd = price("USD");
+        assert_eq!(usd.volume_tier, None);
+        assert!((usd.pct_bps - 250.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn a_payment_in_another_currency_is_priced_off_the_settlement_currency_model() {
+        let merchant = "fx_lookup_test";
+        let mut models = MerchantModels::default();
+        models.coarse.insert(
+            coarse_key("adyen", "visa", "credit", "CHF", "eea"),
+            ServingCost {
+                pct_bps: 150.0,
+                fixed: 0.20,
+                stderr_bps: 0.0,
+            },
+        );
+        models
+            .settlement_currencies
+            .insert("adyen".to_string(), vec!["CHF".to_string()]);
+        {
+            let mut guard = cache().write().unwrap();
+            let mut snap: Snapshot = (**guard).clone();
+            snap.insert(merchant.to_string(), models);
+            *guard = Arc::new(snap);
+        }
+        // Only CHF ↔ SEK is quoted (and no pivot), so no other test's currencies gain a rate.
+        let today = chrono::Utc::now().date_naive();
+        let cfg = crate::config::FxConfig {
+            pivot_currency: String::new(),
+            connector_markup_bps: HashMap::from([("adyen".to_string(), 40.0)]),
+            ..Default::default()
+        };
+        fx::store(fx::FxTable::new(
+            vec![fx::FxRate {
+                base: "CHF".to_string(),
+                quote: "SEK".to_string(),
+                rate: 11.0,
+                effective_from: today,
+            }],
+            &cfg,
+        ));
+
+        let m = lookup(
+            merchant, "adyen", "visa", "credit", "", "SEK", "", "eea", "", "", "", 1100.0,
+        )
+        .unwrap();
+        // SEK 1,100 is CHF 100 on the CHF model; the CHF 0.20 fixed fee is SEK 2.20, i.e. 20 bps,
+        // and converting costs Adyen's 40 bps markup on top of the 150 bps rate.
+        assert_eq!(m.currency, "SEK");
+        assert!((m.fixed - 2.2).abs() < 1e-9);
+        assert!((m.effective_bps - (150.0 + 20.0 + 40.0)).abs() < 1e-9);
+        let fx = m.fx.unwrap();
+        assert_eq!(fx.settlement_currency, "CHF");
+        assert_eq!((fx.rate, fx.markup_bps), (11.0, 40.0));
+        // A currency with no rate to CHF has nothing to price it.
+        assert!(lookup(
+            merchant, "adyen", "visa", "credit", "", "JPY", "", "eea", "", "", "", 1100.0,
+        )
+        .is_none());
+    }
+
+    #[test]
+    fn post_capture_fees_back_off_from_network_to_connector() {
+        // 2,000 visa credit captures with 4 disputes at EUR 15 and 20 refunds at EUR 0.10: EUR 0.031
+        // per capture. 50 mc captures with one dispute: too thin for its own rate, so mc payments
+        // take the connector-wide (60 + 2) / 2,050. Stripe carried no events and gets no entry.
+        let rows = "\
+m1\tadyen\tvisa\tcredit\tEUR\tcapture\t2000\t0
+m1\tadyen\tvisa\tcredit\tEUR\tdispute\t4\t60
+m1\tadyen\tvisa\tcredit\tEUR\trefund\t20\t2
+m1\tadyen\tmc\tdebit\tEUR\tcapture\t50\t0
+m1\tadyen\tmc\tdebit\tEUR\tdispute\t1\t15
+m1\tstripe\tvisa\tcredit\tEUR\tcapture\t5000\t0
+";
+        let table = post_capture_from_tsv(rows).remove("m1").unwrap();
+        assert!((table["adyen|visa|credit|eur"] - 0.031).abs() < 1e-9);
+        assert!(!table.contains_key("adyen|mc|debit|eur"));
+        assert!((table["adyen|eur"] - 77.0 / 2050.0).abs() < 1e-9);
+        assert!(!table.contains_key("stripe|eur"));
+
+        let merchant = "post_capture_lookup_test";
+        let mut models = MerchantModels::default();
+        models.overrides.insert(
+            "adyen".to_string(),
+            ServingCost {
+                pct_bps: 100.0,
+                fixed: 0.0,
+                stderr_bps: 0.0,
+            },
+        );
+        models.post_capture = table;
+        {
+            let mut guard = cache().write().unwrap();
+            let mut snap: Snapshot = (**guard).clone();
+            snap.insert(merchant.to_string(), models);
+            *guard = Arc::new(snap);
+        }
+        // Even a contract rate pays the disputes: EUR 0.031 on EUR 62 is 5 bps.
+        let m = lookup(
+            merchant, "adyen", "visa", "credit", "", "EUR", "", "eea", "", "", "", 62.0,
+        )
+        .unwrap();
+        assert_eq!(m.pct_bps, 100.0, "the capture rate is unchanged");
+        assert!((m.post_capture_fee.unwrap() - 0.031).abs() < 1e-9);
+        assert!((m.effective_bps - 105.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn a_thin_fit_carries_a_wider_standard_error_and_blends_pool_it() {
+        // bps_rmse 15 over n = 225 is ±1 bps; the same spread over 22,500 is ±0.1.
+        assert!((stderr_bps("15", "225") - 1.0).abs() < 1e-9);
+        assert!((stderr_bps("15", "22500") - 0.1).abs() < 1e-9);
+        assert_eq!(stderr_bps("\\N", "225"), 0.0);
+        assert_eq!(stderr_bps("15", "0"), 0.0);
+
+        // Two equal-volume clusters at ±2 bps each blend to ±2/√2: independent errors partly cancel.
+        let mut acc = HashMap::new();
+        for pct in [100.0, 200.0] {
+            accumulate(
+                &mut acc,
+                "k".to_string(),
+                pct * 50.0,
+                0.0,
+                50.0,
+                (50.0f64 * 2.0).powi(2),
+            );
+        }
+        let blend = finalize(acc)["k"];
+        assert!((blend.pct_bps - 150.0).abs() < 1e-9);
+        assert!((blend.stderr_bps - 2.0 / 2.0f64.sqrt()).abs() < 1e-9);
+        // A contract add-on is exact, so it doesn't widen the error.
+        let addon = ServingCost {
+            pct_bps: 5.0,
+            fixed: 0.0,
+            stderr_bps: 0.0,
+        };
+        assert_eq!(blend.with_addon(Some(&addon)).stderr_bps, blend.stderr_bps);
+    }
+
     #[test]
     fn amount_bands() {
         // Log buckets (10/decade): bucket k = floor(log10(amount)*10). Currency-native resolution.
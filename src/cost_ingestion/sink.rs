@@ -18,7 +18,7 @@ use crate::config::ClickHouseAnalyticsConfig;
 
 use std::collections::HashMap;
 
-use super::rollup::{BinProductMap, BinProductRow, DailyStatRow};
+use super::rollup::{BinProductMap, BinProductRow, DailyStatRow, PostCaptureRow};
 use super::types::IngestError;
 
 const INSERT_TIMEOUT: Duration = Duration::from_secs(60);
@@ -33,10 +33,54 @@ const INSERT_TIMEOUT: Duration = Duration::from_secs(60);
 /// safe: each bucket is still written exactly once.
 const INSERT_CHUNK_ROWS: usize = 25_000;
 
-/// Columns we provide; `ingested_at` is intentionally omitted so ClickHouse applies its DEFAULT.
+/// Columns we provide; `ingested_at` is omitted so ClickHouse applies its DEFAULT, unless the
+/// [`IngestionStamp`] pins it (see [`IngestionStamp::columns`]).
 const COLUMNS: &str =
-    "connector,account,merchant_id,txn_date,ingestion_id,card_network,variant,funding,\
-issuer_country,currency,ic_category,card_product,channel,band,n,sx,sy,sxx,sxy,syy,su,suu,suy,suuy,syyuu";
+    "connector,account,merchant_id,txn_date,ingestion_id,revision,card_network,variant,funding,\
+issuer_country,currency,ic_category,card_product,channel,band,n,sx,sy,sxx,sxy,syy,su,suu,suy,suuy,syyuu,\
+n_itemised,spt,sxpt,sptpt,sypt";
+
+/// Which ingestion wrote a set of buckets, and as which revision of it. Stamped on every row of
+/// both `cost_daily_stats` and `cost_post_capture_stats`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct IngestionStamp {
+    /// The `cost_ingestion` row id; empty when nothing tracks the run.
+    pub ingestion_id: String,
+    /// 0 for the first ingest; each reprocessing writes the next one (see [`next_revision`]).
+    pub revision: u32,
+    /// The `ingested_at` to write, as ClickHouse's `YYYY-MM-DD hh:mm:ss`. `None` lets the column's
+    /// `DEFAULT now()` apply, which is right for a fresh report. A reprocessing pins the original
+    /// ingest's time instead: `ingested_at` is the replacing-merge version, and a re-run stamped now
+    /// would win every day a *later* report has since re-delivered.
+    pub ingested_at: Option<String>,
+}
+
+impl IngestionStamp {
+    /// The stamp of a first ingest under `ingestion_id`.
+    pub fn new(ingestion_id: &str) -> Self {
+        Self {
+            ingestion_id: ingestion_id.to_string(),
+            ..Self::default()
+        }
+    }
+
+    /// `columns` plus `ingested_at` when this stamp pins it.
+    fn columns(&self, columns: &str) -> String {
+        match self.ingested_at {
+            Some(_) => format!("{columns},ingested_at"),
+            None => columns.to_string(),
+        }
+    }
+
+    /// Write the stamp's fields onto one JSONEachRow object.
+    fn apply(&self, obj: &mut serde_json::Value) {
+        obj["ingestion_id"] = json!(self.ingestion_id);
+        obj["revision"] = json!(self.revision);
+        if let Some(at) = &self.ingested_at {
+            obj["ingested_at"] = json!(at);
+        }
+    }
+}
 
 fn client() -> &'static reqwest::Client {
     static CLIENT: OnceLock<reqwest::Client> = OnceLock::new();
@@ -44,8 +88,9 @@ fn client() -> &'static reqwest::Client {
 }
 
 /// Bulk-insert one report's aggregated per-day buckets into `cost_daily_stats`, stamped with the
-/// ingestion context. Each bucket's `txn_date` is a transaction (booking) day. `ingestion_id` ties
-/// every bucket to its `cost_ingestion` job so an ingestion can later be deleted. A day re-delivered
+/// ingestion context. Each bucket's `txn_date` is a transaction (booking) day. The stamp's
+/// `ingestion_id` ties every bucket to its `cost_ingestion` job so an ingestion can later be deleted
+/// or reprocessed. A day re-delivered
 /// by a later report collapses onto the same key — the latest `ingested_at` wins (see the table's
 /// `ReplacingMergeTree` in `035_cost_model.sh`). The buckets are sent in bounded chunks (see
 /// [`INSERT_CHUNK_ROWS`]) so a large report stays within the request budget. Returns the number of
@@ -55,7 +100,7 @@ pub async fn insert_daily_stats(
     connector: &str,
     account: &str,
     merchant_id: &str,
-    ingestion_id: &str,
+    stamp: &IngestionStamp,
     rows: &[DailyStatRow],
 ) -> Result<usize, IngestError> {
     if rows.is_empty() {
@@ -66,7 +111,7 @@ pub async fn insert_daily_stats(
     // (see [`INSERT_CHUNK_ROWS`]). Any chunk failing aborts the whole insert; the buckets already
     // written are harmless — a re-run overwrites them via the `ReplacingMergeTree` key.
     for chunk in rows.chunks(INSERT_CHUNK_ROWS) {
-        insert_chunk(cfg, connector, account, merchant_id, ingestion_id, chunk).await?;
+        insert_chunk(cfg, connector, account, merchant_id, stamp, chunk).await?;
     }
     Ok(rows.len())
 }
@@ -77,17 +122,16 @@ async fn insert_chunk(
     connector: &str,
     account: &str,
     merchant_id: &str,
-    ingestion_id: &str,
+    stamp: &IngestionStamp,
     rows: &[DailyStatRow],
 ) -> Result<(), IngestError> {
     let mut body = String::with_capacity(rows.len() * 256);
     for r in rows {
-        let obj = json!({
+        let mut obj = json!({
             "connector": connector,
             "account": account,
             "merchant_id": merchant_id,
             "txn_date": r.txn_date.to_string(),
-            "ingestion_id": ingestion_id,
             "card_network": r.card_network,
             "variant": r.variant,
             "funding": r.funding,
@@ -108,7 +152,13 @@ async fn insert_chunk(
             "suy": r.suy,
             "suuy": r.suuy,
             "syyuu": r.syyuu,
+            "n_itemised": r.n_itemised,
+            "spt": r.spt,
+            "sxpt": r.sxpt,
+            "sptpt": r.sptpt,
+            "sypt": r.sypt,
         });
+        stamp.apply(&mut obj);
         body.push_str(
             &serde_json::to_string(&obj).map_err(|e| IngestError::Storage(e.to_string()))?,
         );
@@ -116,8 +166,9 @@ async fn insert_chunk(
     }
 
     let query = format!(
-        "INSERT INTO {}.cost_daily_stats ({COLUMNS}) FORMAT JSONEachRow",
-        cfg.database
+        "INSERT INTO {}.cost_daily_stats ({}) FORMAT JSONEachRow",
+        cfg.database,
+        stamp.columns(COLUMNS)
     );
     let mut req = client()
         .post(cfg.url.trim_end_matches('/'))
@@ -141,6 +192,76 @@ async fn insert_chunk(
     Ok(())
 }
 
+/// Columns for `cost_post_capture_stats`; `ingested_at` again left to the stamp.
+const POST_CAPTURE_COLUMNS: &str =
+    "connector,account,merchant_id,txn_date,ingestion_id,revision,kind,card_network,variant,funding,\
+issuer_country,currency,ic_category,n,fees,amount";
+
+/// Bulk-insert one report's refund and dispute buckets into `cost_post_capture_stats`, stamped with
+/// the same ingestion context as its daily stats so [`delete_ingestion_rows`] removes both. Chunked
+/// like [`insert_daily_stats`]. Returns the number of buckets written.
+pub async fn insert_post_capture_stats(
+    cfg: &ClickHouseAnalyticsConfig,
+    connector: &str,
+    account: &str,
+    merchant_id: &str,
+    stamp: &IngestionStamp,
+    rows: &[PostCaptureRow],
+) -> Result<usize, IngestError> {
+    for chunk in rows.chunks(INSERT_CHUNK_ROWS) {
+        let mut body = String::with_capacity(chunk.len() * 192);
+        for r in chunk {
+            let mut obj = json!({
+                "connector": connector,
+                "account": account,
+                "merchant_id": merchant_id,
+                "txn_date": r.txn_date.to_string(),
+                "kind": r.kind.as_str(),
+                "card_network": r.card_network,
+                "variant": r.variant,
+                "funding": r.funding,
+                "issuer_country": r.issuer_country,
+                "currency": r.currency,
+                "ic_category": r.ic_category,
+                "n": r.n,
+                "fees": r.fees,
+                "amount": r.amount,
+            });
+            stamp.apply(&mut obj);
+            body.push_str(
+                &serde_json::to_string(&obj).map_err(|e| IngestError::Storage(e.to_string()))?,
+            );
+            body.push('\n');
+        }
+
+        let query = format!(
+            "INSERT INTO {}.cost_post_capture_stats ({}) FORMAT JSONEachRow",
+            cfg.database,
+            stamp.columns(POST_CAPTURE_COLUMNS)
+        );
+        let mut req = client()
+            .post(cfg.url.trim_end_matches('/'))
+            .query(&[("query", query.as_str())])
+            .body(body);
+        if !cfg.user.is_empty() {
+            req = req.basic_auth(&cfg.user, cfg.password.as_ref().map(|p| p.peek().clone()));
+        }
+
+        let resp = req
+            .send()
+            .await
+            .map_err(|e| IngestError::Storage(e.to_string()))?;
+        if !resp.status().is_success() {
+            let status = resp.status();
+            let text = resp.text().await.unwrap_or_default();
+            return Err(IngestError::Storage(format!(
+                "clickhouse post-capture insert failed ({status}): {text}"
+            )));
+        }
+    }
+    Ok(rows.len())
+}
+
 /// Column list for the global BIN → card-product table.
 const BIN_COLUMNS: &str = "bin,card_network,issuer_country,funding,card_product,support_n";
 
@@ -257,12 +378,15 @@ pub async fn load_bin_product(cfg: &ClickHouseAnalyticsConfig) -> BinProductMap
     std::sync::Arc::new(map)
 }
 
-/// Delete the daily buckets an ingestion last wrote, identified by its `ingestion_id`, then the
-/// caller refits so the served model reflects what remains. Because a day re-delivered by a later
-/// report is owned by *that* job (it overwrote this one's bucket), deleting job X removes only the
-/// days still attributable to X. Caveat of the per-day model: undoing a report that *superseded* an
-/// earlier report's day drops that day until it is re-ingested — the raw-row era could resurrect it,
-/// the object-storage replay layer (deferred) will restore that.
+/// The tables a report's buckets land in, all stamped per [`IngestionStamp`].
+const STAMPED_TABLES: [&str; 2] = ["cost_daily_stats", "cost_post_capture_stats"];
+
+/// Delete the daily buckets (and refund/dispute buckets) an ingestion last wrote, identified by its
+/// `ingestion_id`, then the caller refits so the served model reflects what remains. Because a day
+/// re-delivered by a later report is owned by *that* job (it overwrote this one's bucket), deleting
+/// job X removes only the days still attributable to X. Caveat of the per-day model: undoing a report
+/// that *superseded* an earlier report's day drops that day until it is re-ingested — reprocessing
+/// the earlier ingestion from its retained report (see [`super::reprocess`]) restores it.
 pub async fn delete_ingestion_rows(
     cfg: &ClickHouseAnalyticsConfig,
     connector: &str,
@@ -270,12 +394,116 @@ pub async fn delete_ingestion_rows(
     merchant_id: &str,
     ingestion_id: &str,
 ) -> Result<(), IngestError> {
+    delete_stamped(cfg, connector, account, merchant_id, ingestion_id, None).await
+}
+
+/// The stamp a reprocessing of `ingestion_id` writes under: the revision after the highest one
+/// already staged, pinned to the latest `ingested_at` the ingestion wrote. An ingestion with nothing
+/// staged (it failed, or its rows were deleted) gets revision 1 and a fresh `ingested_at`.
+pub async fn next_revision(
+    cfg: &ClickHouseAnalyticsConfig,
+    connector: &str,
+    account: &str,
+    merchant_id: &str,
+    ingestion_id: &str,
+) -> Result<IngestionStamp, IngestError> {
+    let scope = "WHERE connector = {connector:String} AND account = {account:String} \
+                 AND merchant_id = {merchant_id:String} AND ingestion_id = {ingestion_id:String}";
     let sql = format!(
-        "DELETE FROM {}.cost_daily_stats WHERE connector = {{connector:String}} \
-         AND account = {{account:String}} AND merchant_id = {{merchant_id:String}} \
-         AND ingestion_id = {{ingestion_id:String}}",
-        cfg.database
+        "SELECT count(), max(revision), toString(max(ingested_at)) FROM (\
+           SELECT revision, ingested_at FROM {db}.cost_daily_stats {scope} \
+           UNION ALL \
+           SELECT revision, ingested_at FROM {db}.cost_post_capture_stats {scope}\
+         ) FORMAT TSV",
+        db = cfg.database
     );
+    let text = exec_scoped(
+        cfg,
+        "revision lookup",
+        sql,
+        connector,
+        account,
+        merchant_id,
+        ingestion_id,
+    )
+    .await?;
+    let mut fields = text.trim().split('\t');
+    let count: u64 = fields.next().and_then(|s| s.parse().ok()).unwrap_or(0);
+    let revision: u32 = fields.next().and_then(|s| s.parse().ok()).unwrap_or(0);
+    let ingested_at = fields.next().map(str::to_string);
+    Ok(IngestionStamp {
+        ingestion_id: ingestion_id.to_string(),
+        revision: revision.saturating_add(1),
+        ingested_at: ingested_at.filter(|_| count > 0),
+    })
+}
+
+/// Delete every bucket an ingestion wrote under a revision older than `stamp`'s, once the new
+/// revision is staged. Buckets the new revision rewrote are already superseded by it (same key, same
+/// `ingested_at`, inserted later); this removes the ones it no longer produces.
+pub async fn delete_superseded_revisions(
+    cfg: &ClickHouseAnalyticsConfig,
+    connector: &str,
+    account: &str,
+    merchant_id: &str,
+    stamp: &IngestionStamp,
+) -> Result<(), IngestError> {
+    delete_stamped(
+        cfg,
+        connector,
+        account,
+        merchant_id,
+        &stamp.ingestion_id,
+        Some(stamp.revision),
+    )
+    .await
+}
+
+/// Delete an ingestion's buckets from every stamped table, only those below `below_revision` when
+/// set.
+async fn delete_stamped(
+    cfg: &ClickHouseAnalyticsConfig,
+    connector: &str,
+    account: &str,
+    merchant_id: &str,
+    ingestion_id: &str,
+    below_revision: Option<u32>,
+) -> Result<(), IngestError> {
+    let revision_filter = below_revision
+        .map(|r| format!(" AND revision < {r}"))
+        .unwrap_or_default();
+    for table in STAMPED_TABLES {
+        let sql = format!(
+            "DELETE FROM {}.{table} WHERE connector = {{connector:String}} \
+             AND account = {{account:String}} AND merchant_id = {{merchant_id:String}} \
+             AND ingestion_id = {{ingestion_id:String}}{revision_filter}",
+            cfg.database
+        );
+        exec_scoped(
+            cfg,
+            &format!("delete {table}"),
+            sql,
+            connector,
+            account,
+            merchant_id,
+            ingestion_id,
+        )
+        .await?;
+    }
+    Ok(())
+}
+
+/// POST `sql` with one ingestion's `(connector, account, merchant_id, ingestion_id)` bound as query
+/// parameters, returning the response body. `what` names the statement in the error.
+async fn exec_scoped(
+    cfg: &ClickHouseAnalyticsConfig,
+    what: &str,
+    sql: String,
+    connector: &str,
+    account: &str,
+    merchant_id: &str,
+    ingestion_id: &str,
+) -> Result<String, IngestError> {
     let mut req = client()
         .post(cfg.url.trim_end_matches('/'))
         .query(&[
@@ -296,8 +524,10 @@ pub async fn delete_ingestion_rows(
         let status = resp.status();
         let text = resp.text().await.unwrap_or_default();
         return Err(IngestError::Storage(format!(
-            "clickhouse delete staging failed ({status}): {text}"
+            "clickhouse {what} failed ({status}): {text}"
         )));
     }
-    Ok(())
+    resp.text()
+        .await
+        .map_err(|e| IngestError::Storage(e.to_string()))
 }
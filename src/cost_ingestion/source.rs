@@ -17,7 +17,9 @@ use super::connectors::adyen::AdyenReportSource;
 use super::connectors::braintree::BraintreeReportSource;
 use super::connectors::chase::ChaseReportSource;
 use super::connectors::checkout::CheckoutReportSource;
+use super::connectors::paypal::PaypalReportSource;
 use super::connectors::stripe::StripeReportSource;
+use super::connectors::worldpay::WorldpayReportSource;
 use super::mapping::ColumnMapping;
 use super::types::{ConnectorCreds, IngestError, ReadyReport, ReportNotification, SettledFeeRow};
 
@@ -178,7 +180,9 @@ impl ConnectorRegistry {
         Self::register(&mut sources, Arc::new(BraintreeReportSource::new()));
         Self::register(&mut sources, Arc::new(ChaseReportSource::new()));
         Self::register(&mut sources, Arc::new(CheckoutReportSource::new()));
+        Self::register(&mut sources, Arc::new(PaypalReportSource::new()));
         Self::register(&mut sources, Arc::new(StripeReportSource::new()));
+        Self::register(&mut sources, Arc::new(WorldpayReportSource::new()));
         Self { sources }
     }
 
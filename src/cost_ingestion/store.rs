@@ -19,8 +19,8 @@ use crate::storage::schema_pg::cost_ingestion::dsl;
 use crate::app::get_tenant_app_state;
 use crate::generics;
 use crate::storage::types::{
-    CostIngestion, CostIngestionNew, CostIngestionOutcomeUpdate, CostIngestionProgressUpdate,
-    CostIngestionStatusUpdate,
+    CostIngestion, CostIngestionBlobUpdate, CostIngestionNew, CostIngestionOutcomeUpdate,
+    CostIngestionProgressUpdate, CostIngestionStatusUpdate,
 };
 use crate::storage::utils::generate_uuid;
 
@@ -269,6 +269,92 @@ pub async fn mark_failed(id: &str, error: &str) -> Result<(), IngestError> {
     Ok(())
 }
 
+/// Record the content address of an ingestion's retained raw report.
+pub async fn set_report_blob(id: &str, blob: &str) -> Result<(), IngestError> {
+    let app_state = get_tenant_app_state().await;
+    let conn = app_state
+        .db
+        .get_conn()
+        .await
+        .map_err(|_| IngestError::Storage("db connection".to_string()))?;
+    generics::generic_update_if_present::<<CostIngestion as HasTable>::Table, _, _>(
+        &conn,
+        dsl::id.eq(id.to_string()),
+        CostIngestionBlobUpdate {
+            report_blob: Some(blob.to_string()),
+            updated_at: crate::utils::date_time::now(),
+        },
+    )
+    .await
+    .map_err(|e| IngestError::Storage(format!("{e:?}")))?;
+    Ok(())
+}
+
+/// Statuses an ingestion can be reprocessed from: it has run, successfully or not.
+const REPROCESSABLE: [&str; 2] = ["completed", "failed"];
+
+/// Claim a finished ingestion for reprocessing by compare-and-swapping it to `processing`, the same
+/// CAS [`claim_pending`] uses. `false` when it is already running (or was never run), so two
+/// reprocess requests can't stage the same report at once, and a delete can't race one.
+pub async fn claim_for_reprocess(merchant_id: &str, id: &str) -> Result<bool, IngestError> {
+    let app_state = get_tenant_app_state().await;
+    let conn = app_state
+        .db
+        .get_conn()
+        .await
+        .map_err(|_| IngestError::Storage("db connection".to_string()))?;
+    let won = generics::generic_update_if_present::<<CostIngestion as HasTable>::Table, _, _>(
+        &conn,
+        dsl::id
+            .eq(id.to_string())
+            .and(dsl::merchant_id.eq(merchant_id.to_string()))
+            .and(dsl::status.eq_any(REPROCESSABLE)),
+        CostIngestionStatusUpdate {
+            status: "processing".to_string(),
+            last_error: None,
+            updated_at: crate::utils::date_time::now(),
+        },
+    )
+    .await
+    .map_err(|e| IngestError::Storage(format!("{e:?}")))?;
+    Ok(won == 1)
+}
+
+/// A merchant's completed ingestions whose reports cover any day in `[from, to]`, oldest first —
+/// the order they were originally ingested in. Optionally narrowed to one connector and account.
+pub async fn list_in_period(
+    merchant_id: &str,
+    from: chrono::NaiveDate,
+    to: chrono::NaiveDate,
+    connector: Option<&str>,
+    account: Option<&str>,
+) -> Result<Vec<CostIngestion>, IngestError> {
+    let app_state = get_tenant_app_state().await;
+    let conn = app_state
+        .db
+        .get_conn()
+        .await
+        .map_err(|_| IngestError::Storage("db connection".to_string()))?;
+    let (Some(from), Some(to)) = (to_time_date(from), to_time_date(to)) else {
+        return Ok(Vec::new());
+    };
+    let rows: Vec<CostIngestion> = dsl::cost_ingestion
+        .filter(dsl::merchant_id.eq(merchant_id.to_string()))
+        .filter(dsl::status.eq("completed".to_string()))
+        .filter(dsl::period_start.le(to))
+        .filter(dsl::period_end.ge(from))
+        .order(dsl::created_at.asc())
+        .get_results_async(&*conn)
+        .await
+        .map_err(|e| IngestError::Storage(format!("{e:?}")))?;
+    // A merchant has a handful of sources; narrowing here keeps the query one shape.
+    Ok(rows
+        .into_iter()
+        .filter(|r| connector.is_none_or(|c| r.connector == c))
+        .filter(|r| account.is_none_or(|a| r.account == a))
+        .collect())
+}
+
 /// A merchant's ingestion history (and any in-flight jobs), newest first.
 pub async fn list_for_merchant(
     merchant_id: &str,
@@ -26,8 +26,12 @@ pub struct SettledFeeRow {
     pub funding: String,
     /// Issuer country (as the report states it): `FR`, `IT`, …
     pub issuer_country: String,
-    /// Settlement currency: `EUR`, `AUD`, …
+    /// Settlement currency: `EUR`, `AUD`, … — the currency of every amount on this row.
     pub currency: String,
+    /// Currency the shopper paid in, when the report states it; `""` otherwise. When it differs
+    /// from `currency` the rollup converts the row into it at the transaction date's FX rate, so
+    /// the cluster is fitted in the currency payments are decided in.
+    pub presentment_currency: String,
     /// Interchange category from the report; `""` for flat-fee methods (iDEAL/Klarna/CB).
     pub ic_category: String,
     /// The published interchange **rate** for this transaction, as an integer-bps string (e.g.
@@ -61,6 +65,33 @@ pub struct SettledFeeRow {
     /// is the lookup key that resolves this row's `card_product` (the BIN's dominant interchange
     /// rate). Never itself a fit dimension — it is high-cardinality and would shatter clusters.
     pub bin: String,
+    /// What the row is charging for. Only [`RowKind::Capture`] rows feed the gross→fee fit; refund
+    /// and dispute rows are folded into per-cluster post-capture stats instead, with `gross` the
+    /// refunded/disputed amount and `total_fee` the fee charged for the event (both positive).
+    pub kind: RowKind,
+}
+
+/// The settlement event a [`SettledFeeRow`] belongs to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum RowKind {
+    /// A settled sale — the only kind the OLS fit regresses on.
+    #[default]
+    Capture,
+    /// A full or partial refund, carrying the PSP's refund fee (or fee reversal when negative).
+    Refund,
+    /// A chargeback or dispute, carrying the dispute fee.
+    Dispute,
+}
+
+impl RowKind {
+    /// The value stored in `cost_post_capture_stats.kind`.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Capture => "capture",
+            Self::Refund => "refund",
+            Self::Dispute => "dispute",
+        }
+    }
 }
 
 /// Base-10 **log** amount bucket ([`BUCKETS_PER_DECADE`] per decade), as a string. This replaces the
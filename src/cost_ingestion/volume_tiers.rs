@@ -0,0 +1,573 @@
+//! Contract volume tiers: pricing by cumulative month-to-date volume.
+//!
+//! Several PSP contracts price by how much a merchant has sent them this calendar month — e.g.
+//! 1.9% up to €1M, 1.6% above. Neither the fitted snapshots (`fit`) nor the seed tiers
+//! (`SeedFeeModel`) can express that: the rate of the *next* payment depends on the volume already
+//! routed, not on the payment itself. A [`VolumeTierSchedule`] states the contract per
+//! `(merchant, connector)`, and [`serving::lookup`](super::serving::lookup) prices each candidate
+//! at its **marginal** tier rate given the connector's month-to-date volume.
+//!
+//! Schedules live in the `service_configuration` key-value store with the same two indices as the
+//! manual overrides ([`super::overrides`]). Month-to-date volume is counted in Redis, one
+//! `INCRBYFLOAT` per successful payment ([`record_routed_volume`]), and mirrored into memory for
+//! the decide path: [`spawn`] re-reads every scheduled connector's counter each
+//! [`MTD_SYNC_INTERVAL`], and this instance's own payments are added in between. The counter key
+//! carries the month, so volume resets at the UTC month boundary by construction.
+//!
+//! Volume counts in the schedule's currency: a payment in another currency is converted at the
+//! day's FX rate ([`super::fx`]) before it is counted, and is priced off the schedule the same way.
+//! Without a rate it is recorded under its own currency's counter and doesn't advance the tier.
+
+use std::collections::HashMap;
+use std::sync::{OnceLock, RwLock};
+use std::time::Duration;
+
+use fred::prelude::KeysInterface;
+use serde::{Deserialize, Serialize};
+
+use crate::app::get_tenant_app_state;
+use crate::logger;
+use crate::types::service_configuration;
+
+use super::fx;
+use super::overrides::{index_add, index_remove, read_json, read_list, write_json};
+use super::types::IngestError;
+
+/// How often the in-memory month-to-date mirror is re-read from Redis.
+pub const MTD_SYNC_INTERVAL: Duration = Duration::from_secs(60);
+/// Counters outlive their month by a little, so a late read of last month still answers.
+const MTD_TTL_SECS: i64 = 40 * 24 * 60 * 60;
+
+/// One band of a volume-tier contract: `{pct_bps, fixed}` applies to the month's cumulative volume
+/// below `up_to` (and at or above the previous tier's `up_to`). The last tier has no `up_to`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct VolumeTier {
+    /// Upper bound of the band in schedule currency; `None` on the open-ended top tier.
+    #[serde(default)]
+    pub up_to: Option<f64>,
+    pub pct_bps: f64,
+    pub fixed: f64,
+}
+
+/// A merchant's volume-tier contract with one connector. The tier rates are all-in contract rates,
+/// like a connector fee override: the tier that prices a payment replaces the learned model for it.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct VolumeTierSchedule {
+    /// ISO currency the thresholds (and the fixed fees) are stated in, uppercase.
+    pub currency: String,
+    /// Ascending by `up_to`; only the last tier is open-ended.
+    pub tiers: Vec<VolumeTier>,
+    /// RFC3339 timestamp of the last edit.
+    pub updated_at: String,
+}
+
+/// The rate a payment of a given size is priced at, against a given month-to-date volume.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MarginalRate {
+    /// Volume-weighted over the tiers the payment spans.
+    pub pct_bps: f64,
+    /// The fixed fee of the tier the payment starts in.
+    pub fixed: f64,
+    /// Index of that tier.
+    pub tier: usize,
+}
+
+/// Where a connector stands against its schedule this month — the "push volume to reach the next
+/// tier" view on `/cost-coverage`.
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct TierPosition {
+    pub connector: String,
+    pub currency: String,
+    /// `YYYY-MM` (UTC) the volume is counted for.
+    pub month: String,
+    pub mtd_volume: f64,
+    /// Index of the tier the next payment starts in, and how many tiers the schedule has.
+    pub tier: usize,
+    pub tier_count: usize,
+    pub pct_bps: f64,
+    pub fixed: f64,
+    /// The volume at which the next tier starts; `None` on the top tier.
+    pub next_tier_at: Option<f64>,
+    /// Volume still to route this month to reach it.
+    pub volume_to_next_tier: Option<f64>,
+    pub next_pct_bps: Option<f64>,
+    pub next_fixed: Option<f64>,
+}
+
+impl VolumeTierSchedule {
+    /// Reject a schedule the tier walk can't price: no tiers, negative or non-finite rates,
+    /// thresholds out of order, or an open-ended tier anywhere but last.
+    pub fn validate(&self) -> Result<(), String> {
+        if self.tiers.is_empty() {
+            return Err("a schedule needs at least one tier".to_string());
+        }
+        if self.currency.trim().is_empty() {
+            return Err("currency is required".to_string());
+        }
+        let mut prev = 0.0;
+        for (i, t) in self.tiers.iter().enumerate() {
+            if !t.pct_bps.is_finite() || !t.fixed.is_finite() || t.pct_bps < 0.0 || t.fixed < 0.0 {
+                return Err(format!(
+                    "tier {i}: pct_bps and fixed must be finite and non-negative"
+                ));
+            }
+            let last = i + 1 == self.tiers.len();
+            match t.up_to {
+                Some(up_to) if !up_to.is_finite() || up_to <= prev => {
+                    return Err(format!(
+                        "tier {i}: up_to must be greater than the previous tier's"
+                    ));
+                }
+                Some(up_to) => prev = up_to,
+                None if !last => {
+                    return Err(format!("tier {i}: only the last tier can be open-ended"));
+                }
+                None => {}
+            }
+        }
+        Ok(())
+    }
+
+    /// `[lo, hi)` volume bounds of each tier.
+    fn bands(&self) -> impl Iterator<Item = (usize, f64, f64, &VolumeTier)> {
+        let mut lo = 0.0;
+        self.tiers.iter().enumerate().map(move |(i, t)| {
+            let hi = t.up_to.unwrap_or(f64::INFINITY);
+            let band = (i, lo, hi, t);
+            lo = hi;
+            band
+        })
+    }
+
+    /// Index of the tier the month's next unit of volume falls in. Past the last threshold of a
+    /// schedule without an open-ended tier, the last tier keeps pricing.
+    pub fn tier_at(&self, mtd_volume: f64) -> usize {
+        self.bands()
+            .find(|(_, _, hi, _)| mtd_volume < *hi)
+            .map_or(self.tiers.len().saturating_sub(1), |(i, ..)| i)
+    }
+
+    /// The marginal rate of a payment of `amount` on top of `mtd_volume`. A payment that straddles
+    /// a threshold pays each tier's rate on its share of the amount, as the contract bills it.
+    pub fn marginal(&self, mtd_volume: f64, amount: f64) -> Option<MarginalRate> {
+        let tier = self.tier_at(mtd_volume);
+        let start = self.tiers.get(tier)?;
+        if amount <= 0.0 {
+            return Some(MarginalRate {
+                pct_bps: start.pct_bps,
+                fixed: start.fixed,
+                tier,
+            });
+        }
+        let (from, to) = (mtd_volume.max(0.0), mtd_volume.max(0.0) + amount);
+        let last = self.tiers.len() - 1;
+        let weighted: f64 = self
+            .bands()
+            .map(|(i, lo, hi, t)| {
+                // The last tier absorbs everything above the schedule's top threshold.
+                let hi = if i == last { f64::INFINITY } else { hi };
+                (to.min(hi) - from.max(lo)).max(0.0) * t.pct_bps
+            })
+            .sum();
+        Some(MarginalRate {
+            pct_bps: weighted / amount,
+            fixed: start.fixed,
+            tier,
+        })
+    }
+
+    /// This schedule's standing at `mtd_volume`, for the coverage view.
+    pub fn position(&self, connector: &str, month: &str, mtd_volume: f64) -> TierPosition {
+        let tier = self.tier_at(mtd_volume);
+        let current = &self.tiers[tier];
+        let next = self.tiers.get(tier + 1);
+        let next_tier_at = next.and(current.up_to);
+        TierPosition {
+            connector: connector.to_string(),
+            currency: self.currency.clone(),
+            month: month.to_string(),
+            mtd_volume,
+            tier,
+            tier_count: self.tiers.len(),
+            pct_bps: current.pct_bps,
+            fixed: current.fixed,
+            next_tier_at,
+            volume_to_next_tier: next_tier_at.map(|at| (at - mtd_volume).max(0.0)),
+            next_pct_bps: next.map(|t| t.pct_bps),
+            next_fixed: next.map(|t| t.fixed),
+        }
+    }
+}
+
+// ── schedule storage ────────────────────────────────────────────────────────────────────────────
+
+fn schedule_name(merchant_id: &str, connector: &str) -> String {
+    format!("cost_volume_tiers::{merchant_id}::{connector}")
+}
+
+fn merchant_index_name(merchant_id: &str) -> String {
+    format!("cost_volume_tiers_index::{merchant_id}")
+}
+
+/// Merchants with at least one schedule, so the periodic serving refresh and the month-to-date
+/// sync can find them all.
+const GLOBAL_INDEX_NAME: &str = "cost_volume_tier_merchants";
+
+/// The connectors a merchant has a volume-tier schedule for.
+pub async fn list_connectors(merchant_id: &str) -> Result<Vec<String>, IngestError> {
+    read_list(merchant_index_name(merchant_id)).await
+}
+
+/// All `(connector, schedule)` a merchant has.
+pub async fn list(merchant_id: &str) -> Result<Vec<(String, VolumeTierSchedule)>, IngestError> {
+    let connectors = list_connectors(merchant_id).await?;
+    let mut out = Vec::with_capacity(connectors.len());
+    for connector in connectors {
+        if let Some(s) = get(merchant_id, &connector).await? {
+            out.push((connector, s));
+        }
+    }
+    Ok(out)
+}
+
+/// Merchants that currently have at least one schedule (global index).
+pub async fn list_merchants() -> Result<Vec<String>, IngestError> {
+    read_list(GLOBAL_INDEX_NAME.to_string()).await
+}
+
+/// The schedule for one `(merchant, connector)`, if set.
+pub async fn get(
+    merchant_id: &str,
+    connector: &str,
+) -> Result<Option<VolumeTierSchedule>, IngestError> {
+    read_json::<VolumeTierSchedule>(schedule_name(merchant_id, connector)).await
+}
+
+/// Upsert a merchant's schedule for a connector, recording it in both indices.
+pub async fn put(
+    merchant_id: &str,
+    connector: &str,
+    schedule: &VolumeTierSchedule,
+) -> Result<(), IngestError> {
+    write_json(schedule_name(merchant_id, connector), schedule).await?;
+    index_add(merchant_index_name(merchant_id), connector).await?;
+    index_add(GLOBAL_INDEX_NAME.to_string(), merchant_id).await?;
+    Ok(())
+}
+
+/// Remove a merchant's schedule for a connector; drops it from the global index once none remain.
+pub async fn delete(merchant_id: &str, connector: &str) -> Result<(), IngestError> {
+    service_configuration::delete_config(schedule_name(merchant_id, connector))
+        .await
+        .map_err(|e| IngestError::Storage(e.to_string()))?;
+    let remaining = index_remove(merchant_index_name(merchant_id), connector).await?;
+    if remaining == 0 {
+        index_remove(GLOBAL_INDEX_NAME.to_string(), merchant_id).await?;
+    }
+    Ok(())
+}
+
+// ── month-to-date volume ────────────────────────────────────────────────────────────────────────
+
+/// The current month, `YYYY-MM` in UTC.
+pub fn current_month() -> String {
+    chrono::Utc::now().format("%Y-%m").to_string()
+}
+
+fn mtd_key(merchant_id: &str, connector: &str, currency: &str, month: &str) -> String {
+    format!("COST_MTD_VOLUME_{merchant_id}_{connector}_{currency}_{month}")
+}
+
+/// Marks a transaction as counted, for as long as a month's counter is kept, so feedback retried
+/// days later is still recognised.
+fn counted_key(merchant_id: &str, txn_uuid: &str) -> String {
+    format!("COST_MTD_VOLUME_COUNTED_{merchant_id}_{txn_uuid}")
+}
+
+fn mirror_key(merchant_id: &str, connector: &str, currency: &str) -> String {
+    format!("{merchant_id}|{connector}|{currency}")
+}
+
+/// Month-to-date volume of every scheduled `(merchant, connector, currency)`, for one month.
+#[derive(Default)]
+struct MtdMirror {
+    month: String,
+    volumes: HashMap<String, f64>,
+}
+
+fn mirror() -> &'static RwLock<MtdMirror> {
+    static MIRROR: OnceLock<RwLock<MtdMirror>> = OnceLock::new();
+    MIRROR.get_or_init(|| RwLock::new(MtdMirror::default()))
+}
+
+/// Volume routed to `connector` this month in `currency`, as of the last sync plus this instance's
+/// own payments since. `0.0` when nothing is known — a new month, or a schedule added since the
+/// last sync.
+pub fn mtd_volume(merchant_id: &str, connector: &str, currency: &str) -> f64 {
+    let Ok(m) = mirror().read() else { return 0.0 };
+    if m.month != current_month() {
+        return 0.0;
+    }
+    m.volumes
+        .get(&mirror_key(
+            merchant_id,
+            connector,
+            &currency.to_uppercase(),
+        ))
+        .copied()
+        .unwrap_or(0.0)
+}
+
+/// Count one successful payment toward its connector's month-to-date volume, once per `txn_uuid`:
+/// feedback for a transaction that has already been counted, such as a retried call, is ignored.
+/// Best effort: a failed write is logged and the payment is simply not counted.
+pub async fn record_routed_volume(
+    merchant_id: &str,
+    txn_uuid: &str,
+    connector: &str,
+    currency: &str,
+    amount: f64,
+) {
+    if !amount.is_finite() || amount <= 0.0 {
+        return;
+    }
+    let connector = connector.to_lowercase();
+    let (currency, amount) = counted_in(merchant_id, &connector, currency, amount);
+    let month = current_month();
+    let key = mtd_key(merchant_id, &connector, &currency, &month);
+    let app_state = get_tenant_app_state().await;
+    let counted = counted_key(merchant_id, txn_uuid);
+    match app_state
+        .redis_conn
+        .set_key_if_not_exists(&counted, "1", MTD_TTL_SECS)
+        .await
+    {
+        Ok(true) => {}
+        Ok(false) => {
+            logger::debug!(
+                tag = "cost_volume_tiers",
+                "routed volume for {} / {} already counted",
+                merchant_id,
+                txn_uuid
+            );
+            return;
+        }
+        Err(e) => {
+            logger::warn!(
+                tag = "cost_volume_tiers",
+                "failed to mark routed volume for {} / {} as counted: {:?}",
+                merchant_id,
+                txn_uuid,
+                e
+            );
+            return;
+        }
+    }
+    let r: Result<Vec<String>, error_stack::Report<redis_interface::errors::RedisError>> =
+        app_state
+            .redis_conn
+            .multi(false, |transaction| {
+                Box::pin(async move {
+                    transaction
+                        .incr_by_float::<(), _>(&fred::types::RedisKey::from(key.clone()), amount)
+                        .await?;
+                    transaction.expire::<(), _>(&key, MTD_TTL_SECS).await?;
+                    Ok(())
+                })
+            })
+            .await;
+    if let Err(e) = r {
+        logger::warn!(
+            tag = "cost_volume_tiers",
+            "failed to record routed volume for {} / {}: {:?}",
+            merchant_id,
+            connector,
+            e
+        );
+        // Nothing was counted, so a retry may count it.
+        let _ = app_state.redis_conn.delete_key(&counted).await;
+        return;
+    }
+    // Only scheduled connectors are mirrored; the rest are counted in Redis and picked up by the
+    // next sync if a schedule appears.
+    if let Ok(mut m) = mirror().write() {
+        if m.month == month {
+            if let Some(v) = m
+                .volumes
+                .get_mut(&mirror_key(merchant_id, &connector, &currency))
+            {
+                *v += amount;
+            }
+        }
+    }
+}
+
+/// The currency and amount a payment counts toward: the connector's schedule currency when the
+/// payment is in another one and today's rate converts it, else the payment's own.
+fn counted_in(merchant_id: &str, connector: &str, currency: &str, amount: f64) -> (String, f64) {
+    let currency = currency.to_uppercase();
+    let scheduled = mirror().read().ok().and_then(|m| {
+        let prefix = mirror_key(merchant_id, connector, "");
+        m.volumes
+            .keys()
+            .find_map(|k| k.strip_prefix(prefix.as_str()).map(str::to_string))
+    });
+    match scheduled {
+        Some(target) if target != currency => {
+            let today = chrono::Utc::now().date_naive();
+            match fx::snapshot().convert(amount, &currency, &target, today) {
+                Some(converted) => (target, converted),
+                None => (currency, amount),
+            }
+        }
+        _ => (currency, amount),
+    }
+}
+
+/// Re-read the month-to-date counter of every scheduled connector into the mirror.
+pub async fn sync_mtd() -> Result<usize, IngestError> {
+    let month = current_month();
+    let app_state = get_tenant_app_state().await;
+    let mut volumes = HashMap::new();
+    for merchant_id in list_merchants().await? {
+        for (connector, schedule) in list(&merchant_id).await? {
+            let currency = schedule.currency.to_uppercase();
+            let volume = app_state
+                .redis_conn
+                .get_key_string(&mtd_key(&merchant_id, &connector, &currency, &month))
+                .await
+                .ok()
+                .and_then(|v| v.parse::<f64>().ok())
+                .unwrap_or(0.0);
+            volumes.insert(mirror_key(&merchant_id, &connector, &currency), volume);
+        }
+    }
+    let n = volumes.len();
+    if let Ok(mut m) = mirror().write() {
+        *m = MtdMirror { month, volumes };
+    }
+    Ok(n)
+}
+
+/// Every scheduled connector's standing this month, from the synced mirror.
+pub async fn positions(merchant_id: &str) -> Result<Vec<TierPosition>, IngestError> {
+    let month = current_month();
+    Ok(list(merchant_id)
+        .await?
+        .into_iter()
+        .map(|(connector, schedule)| {
+            let mtd = mtd_volume(merchant_id, &connector, &schedule.currency);
+            schedule.position(&connector, &month, mtd)
+        })
+        .collect())
+}
+
+/// Keep the month-to-date mirror fresh. Each sync is panic-isolated so one bad cycle can't stop
+/// the loop.
+pub fn spawn() {
+    tokio::spawn(async move {
+        logger::info!(
+            tag = "cost_volume_tiers",
+            "month-to-date volume sync started; interval {:?}",
+            MTD_SYNC_INTERVAL
+        );
+        let mut ticker = tokio::time::interval(MTD_SYNC_INTERVAL);
+        loop {
+            ticker.tick().await;
+            match tokio::spawn(sync_mtd()).await {
+                Ok(Ok(n)) => logger::debug!(
+                    tag = "cost_volume_tiers",
+                    "synced month-to-date volume for {} scheduled connector(s)",
+                    n
+                ),
+                Ok(Err(e)) => {
+                    logger::warn!(tag = "cost_volume_tiers", "sync failed: {:?}", e)
+                }
+                Err(e) => logger::error!(tag = "cost_volume_tiers", "sync panicked: {}", e),
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 1.9% up to €1M, 1.6% to €5M, 1.3% above.
+    fn schedule() -> VolumeTierSchedule {
+        VolumeTierSchedule {
+            currency: "EUR".to_string(),
+            tiers: vec![
+                VolumeTier {
+                    up_to: Some(1_000_000.0),
+                    pct_bps: 190.0,
+                    fixed: 0.10,
+                },
+                VolumeTier {
+                    up_to: Some(5_000_000.0),
+                    pct_bps: 160.0,
+                    fixed: 0.08,
+                },
+                VolumeTier {
+                    up_to: None,
+                    pct_bps: 130.0,
+                    fixed: 0.05,
+                },
+            ],
+            updated_at: String::new(),
+        }
+    }
+
+    #[test]
+    fn prices_at_the_tier_the_month_has_reached() {
+        let s = schedule();
+        assert_eq!(s.tier_at(0.0), 0);
+        assert_eq!(s.tier_at(999_999.0), 0);
+        assert_eq!(s.tier_at(1_000_000.0), 1);
+        assert_eq!(s.tier_at(7_500_000.0), 2);
+        let r = s.marginal(2_000_000.0, 100.0).unwrap();
+        assert_eq!(r.tier, 1);
+        assert!((r.pct_bps - 160.0).abs() < 1e-9);
+        assert!((r.fixed - 0.08).abs() < 1e-9);
+    }
+
+    #[test]
+    fn a_payment_across_a_threshold_blends_both_tiers() {
+        // €400 below the €1M threshold, €600 above it.
+        let r = schedule().marginal(999_600.0, 1_000.0).unwrap();
+        assert_eq!(r.tier, 0);
+        assert!((r.pct_bps - (0.4 * 190.0 + 0.6 * 160.0)).abs() < 1e-9);
+        assert!((r.fixed - 0.10).abs() < 1e-9);
+    }
+
+    #[test]
+    fn position_shows_the_volume_left_to_the_next_tier() {
+        let p = schedule().position("adyen", "2026-10", 820_000.0);
+        assert_eq!(p.tier, 0);
+        assert_eq!(p.tier_count, 3);
+        assert_eq!(p.next_tier_at, Some(1_000_000.0));
+        assert_eq!(p.volume_to_next_tier, Some(180_000.0));
+        assert_eq!(p.next_pct_bps, Some(160.0));
+
+        let top = schedule().position("adyen", "2026-10", 6_000_000.0);
+        assert_eq!(top.tier, 2);
+        assert_eq!(top.next_tier_at, None);
+        assert_eq!(top.volume_to_next_tier, None);
+    }
+
+    #[test]
+    fn rejects_schedules_the_walk_cannot_price() {
+        assert!(schedule().validate().is_ok());
+        let mut s = schedule();
+        s.tiers.swap(0, 1);
+        assert!(s.validate().is_err());
+        let mut s = schedule();
+        s.tiers[0].up_to = None;
+        assert!(s.validate().is_err());
+        let mut s = schedule();
+        s.tiers[1].pct_bps = -1.0;
+        assert!(s.validate().is_err());
+        s.tiers.clear();
+        assert!(s.validate().is_err());
+    }
+}
@@ -1,5 +1,5 @@
-//! Background ingest worker: drains pending `cost_ingestion` jobs (webhook-delivered reports),
-//! downloading, staging, and fitting each.
+//! Background ingest worker: drains pending `cost_ingestion` jobs (webhook-delivered, polled, and
+//! drop-folder reports), downloading, staging, and fitting each.
 //!
 //! Modeled on `sr_auto_calibration::spawn` — a panic-isolated interval loop. Everything heavy
 //! (download, parse, ClickHouse insert, fit) happens here, off the webhook's request path. Runs
@@ -18,7 +18,7 @@ use crate::storage::types::CostIngestion;
 
 use super::pipeline::IngestOutcome;
 use super::types::{IngestError, ReportNotification};
-use super::{pipeline, store, ConnectorCredsStore, ConnectorRegistry};
+use super::{archive, drop_folder, pipeline, store, ConnectorCredsStore, ConnectorRegistry};
 
 /// Spawn the recurring ingest loop. Call once at startup after `APP_STATE` is set. A no-op unless
 /// `worker_enabled` is true. The ClickHouse config is passed in because it lives on the global
@@ -88,6 +88,8 @@ async fn run_once(batch: usize, clickhouse: &ClickHouseAnalyticsConfig) {
                         e
                     );
                 }
+                // Diff the new snapshot against the last one and alert on what moved.
+                super::alerts::check_after_fit(clickhouse, &merchant_id).await;
             }
             Err(e) => {
                 let msg = format!("{e:?}");
@@ -111,32 +113,40 @@ async fn process(
     let registry = ConnectorRegistry::with_builtins();
     let source = registry.get(&job.connector)?;
 
-    // Credentials for this (connector, account).
-    let store_ = ConnectorCredsStore::from_keyring(
-        &cfg.creds_encryption_current,
-        &cfg.creds_encryption_keys,
-    )
-    .ok_or_else(|| {
-        IngestError::Storage("credential encryption keyring not configured".to_string())
-    })?;
-    let resolved = store_
-        .get(&job.connector, &job.account)
-        .await?
+    let bytes = if job.source == drop_folder::DROP_SOURCE {
+        // A drop-folder file needs no connector credentials: read it back from where it landed.
+        drop_folder::read_report(&cfg.drop_folder, &job.report_ref).await?
+    } else {
+        // Credentials for this (connector, account).
+        let store_ = ConnectorCredsStore::from_keyring(
+            &cfg.creds_encryption_current,
+            &cfg.creds_encryption_keys,
+        )
         .ok_or_else(|| {
-            IngestError::Storage(format!(
-                "no credentials for {}/{}",
-                job.connector, job.account
-            ))
+            IngestError::Storage("credential encryption keyring not configured".to_string())
         })?;
+        let resolved = store_
+            .get(&job.connector, &job.account)
+            .await?
+            .ok_or_else(|| {
+                IngestError::Storage(format!(
+                    "no credentials for {}/{}",
+                    job.connector, job.account
+                ))
+            })?;
 
-    // Download the report (buffered) via the connector, then normalize it.
-    let note = ReportNotification {
-        notification_id: job.notification_id.clone().unwrap_or_default(),
-        report_ref: job.report_ref.clone(),
-        report_date: None,
-        account: job.account.clone(),
+        // Download the report (buffered) via the connector, then normalize it.
+        let note = ReportNotification {
+            notification_id: job.notification_id.clone().unwrap_or_default(),
+            report_ref: job.report_ref.clone(),
+            report_date: None,
+            account: job.account.clone(),
+        };
+        source.download_report(&resolved.creds, &note).await?
     };
-    let bytes = source.download_report(&resolved.creds, &note).await?;
+
+    // Keep the report before parsing it, so a job a parser bug fails can be reprocessed once fixed.
+    archive::retain_bytes(&cfg.report_archive, &job.id, &bytes).await;
 
     // Same parse → stage → fit path a manual upload uses; tick progress against this job row.
     pipeline::ingest_report_bytes(
@@ -1,13 +1,222 @@
-/// Deterministic arm assignment using djb2 hash of payment_id.
-/// Returns "variant" if hash % 100 < variant_split_pct, else "control".
-/// Same payment_id always returns the same arm — retries land on the same gateway.
-pub fn assign_arm(payment_id: &str, variant_split_pct: u8) -> &'static str {
-    let hash = payment_id.bytes().fold(5381u64, |acc, b| {
-        acc.wrapping_mul(33).wrapping_add(b as u64)
-    });
-    if (hash % 100) < variant_split_pct as u64 {
-        "variant"
+use crate::euclid::types::{ABTestData, ExperimentArm, HashUnit};
+
+/// The value an experiment hashes for a payment. A payment missing the experiment's unit (a guest
+/// checkout has no customer id) is split by its payment id instead.
+pub fn hash_unit_value<'a>(
+    unit: HashUnit,
+    payment_id: &'a str,
+    customer_id: Option<&'a str>,
+    card_fingerprint: Option<&'a str>,
+) -> &'a str {
+    let value = match unit {
+        HashUnit::PaymentId => None,
+        HashUnit::CustomerId => customer_id,
+        HashUnit::CardFingerprint => card_fingerprint,
+    };
+    value
+        .filter(|value| !value.is_empty())
+        .unwrap_or(payment_id)
+}
+
+/// Deterministic arm assignment for `unit`, the payment's value of the experiment's hash unit.
+/// The same unit always gets the same arm, so retries land on the same gateway.
+///
+/// Salted experiments hash `"{salt}:{unit}"` with SHA-256, which keeps concurrent experiments
+/// independent: a unit's arm in one says nothing about its arm in another. Experiments created
+/// before salting keep the original unsalted djb2 split, so their assignments don't move.
+/// Paused arms keep their slots, so pausing one never moves units between the other arms; a
+/// unit whose slot lands on a paused arm goes to the first arm still running instead. Returns
+/// `None` when no arm carries any weight or every arm is paused.
+pub fn assign_arm(data: &ABTestData, unit: &str) -> Option<ExperimentArm> {
+    let arms = data.arms();
+    let total: u64 = arms.iter().map(|arm| u64::from(arm.weight)).sum();
+    if total == 0 {
+        return None;
+    }
+    let fallback = arms.iter().find(|arm| !arm.paused).cloned();
+    let arm = match &data.salt {
+        Some(salt) => pick(arms, salted_hash(salt, unit) % total),
+        // The original split gave the variant the lowest `variant_split_pct` of 100 slots;
+        // walking the arms from the back reproduces it.
+        None => pick(arms.into_iter().rev(), djb2(unit) % total),
+    }?;
+    if arm.paused {
+        fallback
     } else {
-        "control"
+        Some(arm)
+    }
+}
+
+fn pick(arms: impl IntoIterator<Item = ExperimentArm>, slot: u64) -> Option<ExperimentArm> {
+    let mut cumulative = 0u64;
+    arms.into_iter().find(|arm| {
+        cumulative += u64::from(arm.weight);
+        slot < cumulative
+    })
+}
+
+fn salted_hash(salt: &str, unit: &str) -> u64 {
+    let digest = ring::digest::digest(&ring::digest::SHA256, format!("{salt}:{unit}").as_bytes());
+    let mut prefix = [0u8; 8];
+    prefix.copy_from_slice(&digest.as_ref()[..8]);
+    u64::from_be_bytes(prefix)
+}
+
+/// The djb2 string hash, as used by the original unsalted split and by volume-split arms.
+pub fn djb2(value: &str) -> u64 {
+    value.bytes().fold(5381u64, |acc, b| {
+        acc.wrapping_mul(33).wrapping_add(u64::from(b))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn experiment(arms: &[(&str, u32)], salt: Option<&str>) -> ABTestData {
+        ABTestData {
+            control_algorithm_id: String::new(),
+            variant_algorithm_id: String::new(),
+            variant_split_pct: 0,
+            arms: arms
+                .iter()
+                .map(|(name, weight)| ExperimentArm {
+                    name: name.to_string(),
+                    algorithm_id: "sr_routing".to_string(),
+                    weight: *weight,
+                    sr_config: None,
+                    paused: false,
+                })
+                .collect(),
+            hash_unit: HashUnit::PaymentId,
+            salt: salt.map(str::to_string),
+            min_sample_size: 100,
+            guardrail_threshold_pp: 3.0,
+            variant_sr_config: None,
+            control_sr_config: None,
+            policy: None,
+        }
+    }
+
+    fn arm_name(data: &ABTestData, unit: &str) -> String {
+        assign_arm(data, unit).expect("an arm").name
+    }
+
+    #[test]
+    fn unsalted_shorthand_keeps_the_original_split() {
+        let mut data = experiment(&[], None);
+        data.control_algorithm_id = "sr_routing".to_string();
+        data.variant_algorithm_id = "routing_rules".to_string();
+        data.variant_split_pct = 20;
+
+        for i in 0..500 {
+            let payment_id = format!("pay_{i}");
+            let expected = if djb2(&payment_id) % 100 < 20 {
+                "variant"
+            } else {
+                "control"
+            };
+            assert_eq!(arm_name(&data, &payment_id), expected);
+        }
+    }
+
+    #[test]
+    fn salted_split_follows_weights() {
+        let data = experiment(&[("control", 50), ("a", 30), ("b", 20)], Some("exp_1"));
+
+        let mut counts = std::collections::HashMap::new();
+        for i in 0..10_000 {
+            *counts
+                .entry(arm_name(&data, &format!("cus_{i}")))
+                .or_insert(0u32) += 1;
+        }
+
+        for (arm, share) in [("control", 5_000), ("a", 3_000), ("b", 2_000)] {
+            let count = counts[arm];
+            assert!(count.abs_diff(share) < 300, "{arm}: {count}");
+        }
+    }
+
+    #[test]
+    fn salts_split_independently() {
+        let first = experiment(&[("control", 50), ("variant", 50)], Some("exp_1"));
+        let second = experiment(&[("control", 50), ("variant", 50)], Some("exp_2"));
+
+        let same = (0..10_000)
+            .filter(|i| {
+                let unit = format!("cus_{i}");
+                arm_name(&first, &unit) == arm_name(&second, &unit)
+            })
+            .count();
+
+        // Independent 50/50 splits agree about half the time.
+        assert!(same.abs_diff(5_000) < 300, "{same}");
+    }
+
+    #[test]
+    fn missing_unit_falls_back_to_payment_id() {
+        assert_eq!(
+            hash_unit_value(HashUnit::CustomerId, "pay_1", Some("cus_1"), None),
+            "cus_1"
+        );
+        assert_eq!(
+            hash_unit_value(HashUnit::CustomerId, "pay_1", Some(""), None),
+            "pay_1"
+        );
+        assert_eq!(
+            hash_unit_value(HashUnit::CardFingerprint, "pay_1", Some("cus_1"), None),
+            "pay_1"
+        );
+    }
+
+    #[test]
+    fn paused_arm_gets_no_traffic() {
+        let mut shorthand = experiment(&[], None);
+        shorthand.control_algorithm_id = "sr_routing".to_string();
+        shorthand.variant_algorithm_id = "sr_routing".to_string();
+        shorthand.variant_split_pct = 20;
+        shorthand.pause_arm("variant");
+
+        let mut weighted = experiment(&[("control", 50), ("a", 30), ("b", 20)], Some("exp_1"));
+        weighted.pause_arm("a");
+
+        for i in 0..1_000 {
+            let unit = format!("pay_{i}");
+            assert_eq!(arm_name(&shorthand, &unit), "control");
+            assert_ne!(arm_name(&weighted, &unit), "a");
+        }
+    }
+
+    #[test]
+    fn a_paused_arm_leaves_other_arms_assignments_alone() {
+        for salt in [Some("exp_1"), None] {
+            let mut data = experiment(&[("control", 50), ("a", 30), ("b", 20)], salt);
+            let before: Vec<String> = (0..1_000)
+                .map(|i| arm_name(&data, &format!("pay_{i}")))
+                .collect();
+            data.pause_arm("a");
+
+            for (i, arm) in before.iter().enumerate() {
+                let after = arm_name(&data, &format!("pay_{i}"));
+                let expected = if arm == "a" { "control" } else { arm.as_str() };
+                assert_eq!(after, expected, "pay_{i}");
+            }
+        }
+    }
+
+    #[test]
+    fn promoted_arm_gets_all_traffic() {
+        let mut data = experiment(&[("control", 50), ("a", 30), ("b", 20)], Some("exp_1"));
+        data.concentrate_on("b");
+
+        for i in 0..1_000 {
+            assert_eq!(arm_name(&data, &format!("pay_{i}")), "b");
+        }
+    }
+
+    #[test]
+    fn weightless_experiment_has_no_arm() {
+        let data = experiment(&[("control", 0), ("variant", 0)], Some("exp_1"));
+        assert!(assign_arm(&data, "pay_1").is_none());
     }
 }
@@ -27,6 +27,13 @@ pub struct StaticArmResult {
 /// the rule. Dimensions not populated here make any rule that references them fall through to the
 /// program's default_selection.
 fn build_card_context(dreq: &DomainDeciderRequestForApiCallV2) -> Context {
+    Context::new(card_parameters(dreq))
+}
+
+/// The card dimensions of [`build_card_context`], for callers that add dimensions of their own.
+pub(crate) fn card_parameters(
+    dreq: &DomainDeciderRequestForApiCallV2,
+) -> HashMap<String, Option<ValueType>> {
     let mut params: HashMap<String, Option<ValueType>> = HashMap::new();
 
     params.insert(
@@ -51,7 +58,7 @@ fn build_card_context(dreq: &DomainDeciderRequestForApiCallV2) -> Context {
         params.insert("card".to_string(), Some(ValueType::EnumVariant(ct)));
     }
 
-    Context::new(params)
+    params
 }
 
 pub async fn evaluate_static_arm(
@@ -78,6 +85,17 @@ pub async fn evaluate_static_arm(
         })
         .ok()?;
 
+    evaluate_static_algorithm(&parsed, algorithm_id, payment_id, &build_card_context(dreq))
+}
+
+/// Evaluates an already-loaded algorithm for one payment. Pure: reads and writes nothing, so the
+/// routing replay runs candidates through it too. `ctx` only matters to an Advanced algorithm.
+pub(crate) fn evaluate_static_algorithm(
+    parsed: &StaticRoutingAlgorithm,
+    algorithm_id: &str,
+    payment_id: &str,
+    ctx: &Context,
+) -> Option<StaticArmResult> {
     match parsed {
         StaticRoutingAlgorithm::Single(conn) => Some(StaticArmResult {
             decided_gateway: conn.gateway_name.clone(),
@@ -98,17 +116,15 @@ pub async fn evaluate_static_arm(
             })
         }
         StaticRoutingAlgorithm::VolumeSplit(splits) => {
-            // Deterministic split via djb2 hash of payment_id (same as arm assignment)
-            let hash = payment_id.bytes().fold(5381u64, |acc, b| {
-                acc.wrapping_mul(33).wrapping_add(b as u64)
-            });
+            // Deterministic split via djb2 hash of payment_id
+            let hash = super::common::djb2(payment_id);
             let total_weight: u64 = splits.iter().map(|s| s.split as u64).sum();
             if total_weight == 0 {
                 return None;
             }
             let slot = hash % total_weight;
             let mut cumulative: u64 = 0;
-            for split in &splits {
+            for split in splits {
                 cumulative += split.split as u64;
                 if slot < cumulative {
                     return Some(StaticArmResult {
@@ -126,8 +142,7 @@ pub async fn evaluate_static_arm(
         // fallback. We only fall back to SR (None) if the program can't be evaluated or yields no
         // gateway.
         StaticRoutingAlgorithm::Advanced(program) => {
-            let ctx = build_card_context(dreq);
-            let result = InterpreterBackend::eval_program(&program, &ctx)
+            let result = InterpreterBackend::eval_program(program, ctx)
                 .inspect_err(|e| {
                     logger::warn!(
                         "ab_test evaluator: Advanced arm '{}' interpreter error: {:?} — falling back to SR routing",
@@ -5,6 +5,7 @@ use crate::analytics::{serialize_details, AnalyticsFlowContext, ApiFlow, FlowTyp
 use crate::decider::gatewaydecider::types::{
     DecidedGateway, DomainDeciderRequestForApiCallV2, GatewayDeciderApproach, ResetApproach,
 };
+use crate::euclid::types::ExperimentArm;
 use crate::logger;
 
 pub enum AbTestIntercept {
@@ -14,8 +15,8 @@ pub enum AbTestIntercept {
     SrArm {
         experiment_id: String,
         variant_arm: String,
-        /// SR hyperparameter overrides to apply during routing. Only set for the variant arm
-        /// in SR Config Tuning experiments; None for control arm and standard A/B tests.
+        /// SR hyperparameter overrides to apply during routing: the assigned arm's `sr_config`,
+        /// None when the arm routes with the live SR config.
         sr_config_override: Option<crate::euclid::types::SrConfigOverride>,
     },
     /// This payment is assigned to a static algorithm arm — return this result directly.
@@ -73,31 +74,41 @@ pub async fn intercept(dreq: &DomainDeciderRequestForApiCallV2) -> AbTestInterce
     };
 
     let payment_id = dreq.payment_id();
-    let arm = super::common::assign_arm(payment_id, data.variant_split_pct);
-    let arm_algorithm_id = if arm == "variant" {
-        &data.variant_algorithm_id
-    } else {
-        &data.control_algorithm_id
+    let unit = super::common::hash_unit_value(
+        data.hash_unit,
+        payment_id,
+        dreq.customer_id(),
+        dreq.card_fingerprint(),
+    );
+    let Some(ExperimentArm {
+        name: arm,
+        algorithm_id: arm_algorithm_id,
+        sr_config,
+        ..
+    }) = super::common::assign_arm(&data, unit)
+    else {
+        logger::warn!(
+            "ab_test intercept: experiment {} has no weighted arm, routing normally",
+            experiment_id
+        );
+        return AbTestIntercept::Disabled;
     };
+    let arm = arm.as_str();
+    let arm_algorithm_id = arm_algorithm_id.as_str();
 
     logger::debug!(
-        "ab_test intercept: payment_id={} merchant={} experiment={} arm={}",
+        "ab_test intercept: payment_id={} merchant={} experiment={} arm={} unit={}",
         payment_id,
         dreq.merchant_id,
         experiment_id,
-        arm
+        arm,
+        data.hash_unit
     );
 
     // SR arm: gateway unknown until the decider runs — emit routing event without gateway.
     if arm_algorithm_id == "sr_routing" {
-        // Per-arm routing overrides: the variant carries variant_sr_config, the control carries
-        // control_sr_config. Either may be None (→ live SR config), which preserves the original
-        // "control always uses live config" behavior for standard A/B tests.
-        let sr_config_override = if arm == "variant" {
-            data.variant_sr_config.clone()
-        } else {
-            data.control_sr_config.clone()
-        };
+        // Per-arm routing overrides. None (→ live SR config) preserves the original "control
+        // always uses live config" behavior for standard A/B tests.
         emit_routing_event(
             payment_id,
             &dreq.merchant_id,
@@ -118,7 +129,7 @@ pub async fn intercept(dreq: &DomainDeciderRequestForApiCallV2) -> AbTestInterce
         return AbTestIntercept::SrArm {
             experiment_id,
             variant_arm: arm.to_string(),
-            sr_config_override,
+            sr_config_override: sr_config,
         };
     }
 
@@ -162,6 +173,7 @@ pub async fn intercept(dreq: &DomainDeciderRequestForApiCallV2) -> AbTestInterce
                     is_rust_based_decider: true,
                     latency: None,
                     multi_objective_info: None,
+                    scoring_strategy: None,
                 }),
                 experiment_id,
                 variant_arm: arm.to_string(),
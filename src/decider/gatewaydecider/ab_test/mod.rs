@@ -5,6 +5,6 @@ pub mod interceptor;
 pub mod outcome;
 pub mod preview;
 
-pub use common::assign_arm;
+pub use common::{assign_arm, hash_unit_value};
 pub use interceptor::{intercept, AbTestIntercept};
 pub use outcome::{emit_if_in_flight, is_static_arm_inflight, record_cost_outcome, store_inflight};
@@ -348,6 +348,49 @@ pub async fn load_margin(merchant_id: &str) -> f64 {
         .unwrap_or(multi_objective::DEFAULT_MARGIN)
 }
 
+/// The merchant's latency objective (`latencyPenaltyBpsPerSecond` / `latencySlaMs` from
+/// `SR_V3_INPUT_CONFIG_<merchant_id>`). Non-positive values are ignored; unset leaves the
+/// multi-objective post step ranking on auth and cost alone.
+pub async fn load_latency_objective(
+    merchant_id: &str,
+) -> multi_objective::latency::LatencyObjective {
+    let read = || async {
+        let key = format!("SR_V3_INPUT_CONFIG_{}", merchant_id);
+        let row = service_configuration::find_config_by_name(key)
+            .await
+            .ok()??;
+        let value = row.value?;
+        serde_json::from_str::<SuccessRateData>(&value).ok()
+    };
+    let cfg = read().await;
+    multi_objective::latency::LatencyObjective {
+        penalty_bps_per_second: cfg
+            .as_ref()
+            .and_then(|cfg| cfg.latency_penalty_bps_per_second)
+            .filter(|p| *p > 0.0),
+        sla_ms: cfg
+            .as_ref()
+            .and_then(|cfg| cfg.latency_sla_ms)
+            .filter(|sla| *sla > 0.0),
+    }
+}
+
+/// The merchant's cost risk aversion (`costRiskAversion` from `SR_V3_INPUT_CONFIG_<merchant_id>`):
+/// standard errors of a PSP's fitted cost added to it in the multi-objective EV. Non-positive
+/// values are ignored; unset ranks on the point estimate.
+pub async fn load_cost_risk_aversion(merchant_id: &str) -> Option<f64> {
+    let read = || async {
+        let key = format!("SR_V3_INPUT_CONFIG_{}", merchant_id);
+        let row = service_configuration::find_config_by_name(key)
+            .await
+            .ok()??;
+        let value = row.value?;
+        let cfg: SuccessRateData = serde_json::from_str(&value).ok()?;
+        cfg.cost_risk_aversion
+    };
+    read().await.filter(|k| *k > 0.0)
+}
+
 /// The merchant's configured default SRV3 bucket size (`defaultBucketSize` from
 /// `SR_V3_INPUT_CONFIG_<merchant_id>`). Shared with the routing-events analytics so the
 /// historically-detected auth band uses the same `B` the live decider does when sizing
@@ -457,6 +500,7 @@ pub async fn run_decider_flow(
                     is_rust_based_decider: true,
                     latency: Some(cpu_time),
                     multi_objective_info: None,
+                    scoring_strategy: None,
                 })
             } else {
                 decider_flow
@@ -700,6 +744,8 @@ pub async fn run_decider_flow(
                                 &deciderParams.dpTxnDetail,
                                 &deciderParams.dpTxnCardInfo,
                                 margin,
+                                load_latency_objective(&merchant_id_text).await,
+                                load_cost_risk_aversion(&merchant_id_text).await,
                             )
                             .await;
                         // Only a cost-driven promotion relabels the approach as multi-objective.
@@ -864,6 +910,7 @@ pub async fn run_decider_flow(
                                     .writer
                                     .multi_objective_info
                                     .clone(),
+                                scoring_strategy: decider_flow.writer.scoring_strategy.clone(),
                             })
                         }
                         None => Err((
@@ -8,6 +8,8 @@
 //!   - <https://juspay.io/blog/juspay-orchestrator-and-merchant-controlled-routing-engine>
 //!   - <https://arxiv.org/abs/2510.16735>
 
+pub mod strategy;
+
 // use eulerhs::prelude::*;
 // use optics::core::{review, Field1};
 use crate::app::get_tenant_app_state;
@@ -337,14 +339,23 @@ pub async fn scoring_flow(
                     false
                 };
 
-                let sr_scores = get_cached_scores_based_on_srv3(
-                    decider_flow,
-                    merchant_sr_v3_input_config,
-                    default_sr_v3_input_config,
-                    pm_str,
-                    gateway_scoring_data.clone(),
-                )
-                .await;
+                let scoring_strategy = strategy::resolve(
+                    merchant_sr_v3_input_config.as_ref(),
+                    default_sr_v3_input_config.as_ref(),
+                );
+                decider_flow.writer.scoring_strategy = Some(scoring_strategy.name().to_string());
+
+                let sr_scores = scoring_strategy
+                    .score(
+                        decider_flow,
+                        strategy::ScoringInput {
+                            merchant_config: merchant_sr_v3_input_config,
+                            default_config: default_sr_v3_input_config,
+                            payment_method: pm_str,
+                            gateway_scoring_data: gateway_scoring_data.clone(),
+                        },
+                    )
+                    .await;
 
                 let initial_sr_gw_scores = if should_explore {
                     create_score_map(functional_gateways.clone())
@@ -372,14 +383,7 @@ pub async fn scoring_flow(
                         txn_detail.txnId.clone()
                     );
 
-                    if should_explore {
-                        set_decider_approach(decider_flow, GatewayDeciderApproach::SrV3Hedging);
-                    } else {
-                        set_decider_approach(
-                            decider_flow,
-                            GatewayDeciderApproach::SrSelectionV3Routing,
-                        );
-                    }
+                    set_decider_approach(decider_flow, scoring_strategy.approach(should_explore));
 
                     let is_route_random_traffic_enabled = is_feature_enabled(
                         C::ROUTE_RANDOM_TRAFFIC_SR_V3_ENABLED_MERCHANT.get_key(),
@@ -392,9 +396,9 @@ pub async fn scoring_flow(
                         if is_route_random_traffic_enabled && !is_explore_and_exploit_enabled {
                             route_random_traffic(
                                 decider_flow,
+                                scoring_strategy,
                                 initial_sr_gw_scores.clone(),
                                 hedging_percent,
-                                true,
                                 "SR_BASED_V3_ROUTING".to_string(),
                             )
                         } else {
@@ -2925,8 +2929,14 @@ pub async fn update_gateway_score_based_on_success_rate(
                         )
                     };
 
-                let gateway_decider_approach = Utils::modify_gateway_decider_approach(
-                    gateway_decider_approach.clone(),
+                let scoring_strategy = decider_flow
+                    .writer
+                    .scoring_strategy
+                    .as_deref()
+                    .and_then(strategy::find);
+                let gateway_decider_approach = strategy::downtime_approach(
+                    scoring_strategy,
+                    gateway_decider_approach,
                     downtime,
                 );
 
@@ -3359,9 +3369,9 @@ pub async fn reset_gateway_score(
 
 pub fn route_random_traffic(
     decider_flow: &mut DeciderFlow<'_>,
+    scoring_strategy: &dyn strategy::ScoringStrategy,
     gws: GatewayScoreMap,
     hedging_percent: f64,
-    _is_sr_v3_metric_enabled: bool,
     tag: String,
 ) -> GatewayScoreMap {
     let num = generate_random_number(
@@ -3394,7 +3404,7 @@ pub fn route_random_traffic(
                 .collect::<Vec<_>>()
         );
 
-        set_decider_approach(decider_flow, GatewayDeciderApproach::SrV3Hedging);
+        set_decider_approach(decider_flow, scoring_strategy.approach(true));
 
         remaining_gateways
             .into_iter()
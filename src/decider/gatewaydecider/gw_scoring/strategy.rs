@@ -0,0 +1,214 @@
+//! The pluggable part of SR scoring: how a gateway's recent outcomes become the score it is
+//! ranked on.
+//!
+//! [`super::scoring_flow`] owns everything around the strategy — the single-gateway and priority
+//! fallbacks, hedging, outages and elimination — and asks the merchant's strategy only for the
+//! scores. A new bandit implements [`ScoringStrategy`] in its own module, adds the approaches it
+//! reports (hedged and downtime variants included) to [`GatewayDeciderApproach`], and is listed
+//! in [`STRATEGIES`]; merchants opt in with `scoringStrategy` in their `SR_V3_INPUT_CONFIG`.
+
+use crate::decider::gatewaydecider::types::{
+    DeciderFlow, DownTime, GatewayDeciderApproach, GatewayScoreMap, GatewayScoringData,
+    SrV3InputConfig,
+};
+
+/// What a strategy scores a payment with, besides the flow itself.
+pub struct ScoringInput {
+    /// The merchant's SR config, and the default every merchant falls back to.
+    pub merchant_config: Option<SrV3InputConfig>,
+    pub default_config: Option<SrV3InputConfig>,
+    /// The payment method the scoring keys are built from.
+    pub payment_method: String,
+    pub gateway_scoring_data: GatewayScoringData,
+}
+
+#[async_trait::async_trait]
+pub trait ScoringStrategy: Send + Sync {
+    /// The name merchants select the strategy by, recorded with every decision it scores.
+    fn name(&self) -> &'static str;
+
+    /// The approach reported for a decision this strategy scored; `explored` when the flow
+    /// hedged and scored every gateway alike instead.
+    fn approach(&self, explored: bool) -> GatewayDeciderApproach;
+
+    /// The approach reported once SR elimination found `down_time` among the gateways this
+    /// strategy scored; `NoDowntime` reports [`Self::approach`] unchanged.
+    fn downtime_approach(&self, explored: bool, down_time: DownTime) -> GatewayDeciderApproach;
+
+    /// Scores the flow's functional gateways. A gateway left out of the map can't be picked on
+    /// SR; an empty map sends the payment to priority logic.
+    async fn score(
+        &self,
+        decider_flow: &mut DeciderFlow<'_>,
+        input: ScoringInput,
+    ) -> GatewayScoreMap;
+}
+
+/// SR v3: the success rate over each gateway's last `bucketSize` outcomes, with the optional
+/// reset, extra score and binomial/beta sampling the merchant's feature flags turn on.
+pub struct SrV3;
+
+#[async_trait::async_trait]
+impl ScoringStrategy for SrV3 {
+    fn name(&self) -> &'static str {
+        "sr_v3"
+    }
+
+    fn approach(&self, explored: bool) -> GatewayDeciderApproach {
+        if explored {
+            GatewayDeciderApproach::SrV3Hedging
+        } else {
+            GatewayDeciderApproach::SrSelectionV3Routing
+        }
+    }
+
+    fn downtime_approach(&self, explored: bool, down_time: DownTime) -> GatewayDeciderApproach {
+        match (explored, down_time) {
+            (false, DownTime::AllDowntime) => GatewayDeciderApproach::SrV3AllDowntimeRouting,
+            (false, DownTime::GlobalDowntime) => GatewayDeciderApproach::SrV3GlobalDowntimeRouting,
+            (false, DownTime::Downtime) => GatewayDeciderApproach::SrV3DowntimeRouting,
+            (true, DownTime::AllDowntime) => GatewayDeciderApproach::SrV3AllDowntimeHedging,
+            (true, DownTime::GlobalDowntime) => GatewayDeciderApproach::SrV3GlobalDowntimeHedging,
+            (true, DownTime::Downtime) => GatewayDeciderApproach::SrV3DowntimeHedging,
+            (explored, DownTime::NoDowntime) => self.approach(explored),
+        }
+    }
+
+    async fn score(
+        &self,
+        decider_flow: &mut DeciderFlow<'_>,
+        input: ScoringInput,
+    ) -> GatewayScoreMap {
+        super::get_cached_scores_based_on_srv3(
+            decider_flow,
+            input.merchant_config,
+            input.default_config,
+            input.payment_method,
+            input.gateway_scoring_data,
+        )
+        .await
+    }
+}
+
+/// Every strategy a merchant can select. The first is the default.
+pub static STRATEGIES: &[&dyn ScoringStrategy] = &[&SrV3];
+
+/// The strategy named in the merchant's config, else in the default config, else SR v3. Names
+/// are checked by [`validate_name`] when a config is written, so an unknown one here predates
+/// that check and is scored with the default.
+pub fn resolve(
+    merchant_config: Option<&SrV3InputConfig>,
+    default_config: Option<&SrV3InputConfig>,
+) -> &'static dyn ScoringStrategy {
+    merchant_config
+        .and_then(|config| config.scoringStrategy.as_deref())
+        .or_else(|| default_config.and_then(|config| config.scoringStrategy.as_deref()))
+        .and_then(find)
+        .unwrap_or(STRATEGIES[0])
+}
+
+/// The strategy called `name`, if there is one.
+pub fn find(name: &str) -> Option<&'static dyn ScoringStrategy> {
+    STRATEGIES
+        .iter()
+        .copied()
+        .find(|strategy| strategy.name() == name)
+}
+
+/// Rejects a `scoringStrategy` no strategy is registered under, naming the ones that are.
+pub fn validate_name(name: &str) -> Result<(), String> {
+    match find(name) {
+        Some(_) => Ok(()),
+        None => Err(format!(
+            "Unknown scoringStrategy {:?}; expected one of: {}",
+            name,
+            STRATEGIES
+                .iter()
+                .map(|strategy| strategy.name())
+                .collect::<Vec<_>>()
+                .join(", ")
+        )),
+    }
+}
+
+/// The approach a decision reports once SR elimination has weighed downtime. An approach one of
+/// the strategies reported is mapped by that strategy; anything else was priority logic.
+pub fn downtime_approach(
+    strategy: Option<&dyn ScoringStrategy>,
+    approach: GatewayDeciderApproach,
+    down_time: DownTime,
+) -> GatewayDeciderApproach {
+    if let Some(strategy) = strategy {
+        for explored in [false, true] {
+            if approach == strategy.approach(explored) {
+                return strategy.downtime_approach(explored, down_time);
+            }
+        }
+    }
+    match down_time {
+        DownTime::AllDowntime => GatewayDeciderApproach::PlAllDowntimeRouting,
+        DownTime::GlobalDowntime => GatewayDeciderApproach::PlGlobalDowntimeRouting,
+        DownTime::Downtime => GatewayDeciderApproach::PlDowntimeRouting,
+        DownTime::NoDowntime => GatewayDeciderApproach::PriorityLogic,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(strategy: Option<&str>) -> SrV3InputConfig {
+        serde_json::from_value(serde_json::json!({ "scoringStrategy": strategy })).expect("config")
+    }
+
+    #[test]
+    fn resolves_the_named_strategy() {
+        let named = config(Some("sr_v3"));
+        let unset = config(None);
+        assert_eq!(resolve(Some(&unset), Some(&named)).name(), "sr_v3");
+        assert_eq!(resolve(Some(&unset), None).name(), "sr_v3");
+        assert_eq!(resolve(None, None).name(), "sr_v3");
+    }
+
+    #[test]
+    fn unknown_strategy_is_rejected_on_write_and_scored_with_the_default() {
+        let merchant = config(Some("exp3"));
+        assert!(validate_name("sr_v3").is_ok());
+        let err = validate_name("exp3").expect_err("unknown strategy");
+        assert!(err.contains("sr_v3"), "{err}");
+        assert_eq!(resolve(Some(&merchant), None).name(), STRATEGIES[0].name());
+    }
+
+    #[test]
+    fn downtime_is_reported_by_the_strategy_that_scored() {
+        assert_eq!(
+            downtime_approach(Some(&SrV3), SrV3.approach(true), DownTime::Downtime),
+            GatewayDeciderApproach::SrV3DowntimeHedging
+        );
+        assert_eq!(
+            downtime_approach(Some(&SrV3), SrV3.approach(false), DownTime::NoDowntime),
+            GatewayDeciderApproach::SrSelectionV3Routing
+        );
+        assert_eq!(
+            downtime_approach(
+                Some(&SrV3),
+                GatewayDeciderApproach::PriorityLogic,
+                DownTime::AllDowntime
+            ),
+            GatewayDeciderApproach::PlAllDowntimeRouting
+        );
+        assert_eq!(
+            downtime_approach(None, SrV3.approach(false), DownTime::GlobalDowntime),
+            GatewayDeciderApproach::PlGlobalDowntimeRouting
+        );
+    }
+
+    #[test]
+    fn sr_v3_reports_the_v3_approaches() {
+        assert_eq!(
+            SrV3.approach(false),
+            GatewayDeciderApproach::SrSelectionV3Routing
+        );
+        assert_eq!(SrV3.approach(true), GatewayDeciderApproach::SrV3Hedging);
+    }
+}
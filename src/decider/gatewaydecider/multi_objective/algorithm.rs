@@ -3,6 +3,7 @@ use std::collections::HashMap;
 use super::cluster_key::derive_cluster_key;
 use super::hypersense_client;
 use super::hypersense_client::PspCost;
+use super::latency::{self, LatencyInputs, LatencyObjective};
 use super::{MultiObjectiveInfo, MultiObjectiveOutcome, PspSummary, RankedPsp};
 use crate::types::card::txn_card_info::TxnCardInfo;
 use crate::types::txn_details::types::TxnDetail;
@@ -24,33 +25,51 @@ pub async fn try_apply_multi_objective_post_step(
     txn_detail: &TxnDetail,
     txn_card_info: &TxnCardInfo,
     margin: f64,
+    latency_objective: LatencyObjective,
+    cost_risk_aversion: Option<f64>,
 ) -> ReorderOutcome {
     if score_map.len() < 2 {
         return auth_won(
             margin,
+            &latency_objective,
+            cost_risk_aversion,
             "Only one PSP available; nothing to reorder.".to_string(),
         );
     }
     let cluster_key = derive_cluster_key(txn_detail, txn_card_info);
     let psps: Vec<String> = score_map.keys().cloned().collect();
     let costs = hypersense_client::lookup_costs(merchant_id, &cluster_key, &psps).await;
-    reorder_for_cost(score_map, margin, &costs)
+    let latency = latency::load_latency_inputs(merchant_id, latency_objective, &psps).await;
+    let mut outcome = reorder_for_cost(score_map, margin, &costs, &latency, cost_risk_aversion);
+    // The in-house costs were normalized into the payment's currency at lookup, so every fixed fee
+    // behind the ranking is in it.
+    outcome.info.currency = cluster_key.transaction_currency;
+    outcome
 }
 
 /// Pure expected-value pick: rank every PSP that has cost data by
-/// `EV = auth·(margin − cost/10_000)` and promote the highest. There is **no explicit
-/// auth band** and no admission gate — a PSP wins purely on expected value.
+/// `EV = auth·(margin − (cost + risk premium + latency penalty)/10_000)` and promote the highest.
+/// There is **no explicit auth band** and no admission gate — a PSP wins purely on expected value,
+/// except that a PSP over the merchant's latency SLA is never promoted, and an SR head over it gives
+/// way to the best PSP within it.
 ///
+/// The risk premium is `cost_risk_aversion` standard errors of the PSP's cost estimate, so with
+/// aversion set a thinly-fitted cost is ranked nearer its upper bound than a well-pinned one.
 pub fn reorder_for_cost(
     score_map: &HashMap<String, f64>,
     margin: f64,
     costs: &HashMap<String, PspCost>,
+    latency: &LatencyInputs,
+    cost_risk_aversion: Option<f64>,
 ) -> ReorderOutcome {
+    let objective = &latency.objective;
     let best_auth = match current_head(score_map).map(|(_, a)| a) {
         Some(a) if a.is_finite() => a,
         _ => {
             return auth_won(
                 margin,
+                objective,
+                cost_risk_aversion,
                 "SR head has no finite score; cannot evaluate cost.".to_string(),
             );
         }
@@ -62,6 +81,13 @@ pub fn reorder_for_cost(
             _ => f64::INFINITY,
         }
     };
+    // What the EV charges a PSP: its cost, plus the merchant's price on the uncertainty in it and on
+    // its latency.
+    let charged_bps = |gw: &str| -> f64 {
+        cost_bps(gw)
+            + risk_premium_bps(costs, gw, cost_risk_aversion).unwrap_or(0.0)
+            + latency.penalty_bps(gw).unwrap_or(0.0)
+    };
 
     // Resolve the head PSP (lowest name on ties) and its cost.
     let head_psp = score_map
@@ -75,6 +101,8 @@ pub fn reorder_for_cost(
     if !head_cost.is_finite() {
         return auth_won(
             margin,
+            objective,
+            cost_risk_aversion,
             "No cost data for the SR head; cannot rank it on expected value.".to_string(),
         );
     }
@@ -82,8 +110,13 @@ pub fn reorder_for_cost(
     // Expected profit per unit ticket (ticket cancels across PSPs): auth·(margin − cost).
     let ev = |score: f64, c_bps: f64| -> f64 { score * (margin - c_bps / 10_000.0) };
 
-    // Start from the head and move to any PSP with a strictly better EV (no churn on ties).
-    let head_ev = ev(best_auth, head_cost);
+    // Start from the head and move to any PSP with a strictly better EV (no churn on ties). A head
+    // over the latency SLA gives way to the first PSP within it, whatever its EV.
+    let head_ev = ev(
+        best_auth,
+        charged_bps(head_psp.as_deref().unwrap_or_default()),
+    );
+    let head_over_sla = head_psp.as_deref().is_some_and(|gw| latency.over_sla(gw));
     // EVs of every PSP we can rank (i.e. that has cost data), so we can report the
     // top-two EV gap — the margin of victory of the winning pick.
     let mut ranked_evs: Vec<f64> = vec![head_ev];
@@ -91,6 +124,7 @@ pub fn reorder_for_cost(
     let mut chosen_score = best_auth;
     let mut chosen_cost = head_cost;
     let mut chosen_ev = head_ev;
+    let mut chosen_over_sla = head_over_sla;
     let mut ranked_count = 1usize; // head is always ranked
 
     for (gw, &score) in score_map.iter() {
@@ -103,10 +137,14 @@ pub fn reorder_for_cost(
             continue;
         }
         ranked_count += 1;
-        let cand_ev = ev(score, c);
+        let cand_ev = ev(score, charged_bps(gw));
         ranked_evs.push(cand_ev);
-        // Promote on a strict expected-value gain (no churn on ties).
-        if cand_ev > chosen_ev + f64::EPSILON {
+        // Promote on a strict expected-value gain (no churn on ties), never past the SLA.
+        if latency.over_sla(gw) {
+            continue;
+        }
+        if chosen_over_sla || cand_ev > chosen_ev + f64::EPSILON {
+            chosen_over_sla = false;
             chosen_ev = cand_ev;
             chosen_psp = Some(gw.clone());
             chosen_score = score;
@@ -123,6 +161,8 @@ pub fn reorder_for_cost(
         &ev,
         &cost_bps,
         costs,
+        latency,
+        cost_risk_aversion,
         head_psp.as_deref(),
         chosen_psp.as_deref(),
     );
@@ -133,7 +173,8 @@ pub fn reorder_for_cost(
     // block reports the cheaper head — decided_gateway and multi_objective_info then disagree.
     if chosen_psp == head_psp {
         let head_name = head_psp.clone().unwrap_or_default();
-        let fallbacks = build_ev_ordered_fallbacks(score_map, &head_name, &ev, &cost_bps);
+        let fallbacks =
+            build_ev_ordered_fallbacks(score_map, &head_name, &ev, &charged_bps, latency);
         return ReorderOutcome {
             head_moved: false,
             info: MultiObjectiveInfo {
@@ -147,6 +188,10 @@ pub fn reorder_for_cost(
                 margin,
                 ev_gap_top2,
                 ranked,
+                latency_penalty_bps_per_second: objective.penalty_bps_per_second,
+                latency_sla_ms: objective.sla_ms,
+                cost_risk_aversion,
+                currency: None,
             },
             cost_decision: Some(CostDecision {
                 chosen: head_name,
@@ -157,15 +202,24 @@ pub fn reorder_for_cost(
 
     let chosen_name = chosen_psp.clone().unwrap_or_default();
     let cost_saved_bps = head_cost - chosen_cost;
-    let reason = format!(
-        "Promoted '{}' over '{}' on expected value — saves {:.2} bps for {:.2}pp auth.",
-        chosen_name,
-        head_psp.clone().unwrap_or_default(),
-        cost_saved_bps,
-        (best_auth - chosen_score) * 100.0,
-    );
+    let reason = if head_over_sla {
+        format!(
+            "Promoted '{}' over '{}' — the SR head's p95 latency is over the {:.0}ms SLA.",
+            chosen_name,
+            head_psp.clone().unwrap_or_default(),
+            objective.sla_ms.unwrap_or_default(),
+        )
+    } else {
+        format!(
+            "Promoted '{}' over '{}' on expected value — saves {:.2} bps for {:.2}pp auth.",
+            chosen_name,
+            head_psp.clone().unwrap_or_default(),
+            cost_saved_bps,
+            (best_auth - chosen_score) * 100.0,
+        )
+    };
 
-    let fallbacks = build_ev_ordered_fallbacks(score_map, &chosen_name, &ev, &cost_bps);
+    let fallbacks = build_ev_ordered_fallbacks(score_map, &chosen_name, &ev, &charged_bps, latency);
 
     ReorderOutcome {
         head_moved: true,
@@ -177,6 +231,10 @@ pub fn reorder_for_cost(
             margin,
             ev_gap_top2,
             ranked,
+            latency_penalty_bps_per_second: objective.penalty_bps_per_second,
+            latency_sla_ms: objective.sla_ms,
+            cost_risk_aversion,
+            currency: None,
         },
         cost_decision: Some(CostDecision {
             chosen: chosen_name,
@@ -186,12 +244,14 @@ pub fn reorder_for_cost(
 }
 
 /// Fallbacks after the chosen PSP: PSPs with cost data ordered by descending expected value
-/// (best alternative first), then any PSPs without cost data ordered by descending auth.
+/// (best alternative first, those over the latency SLA after those within it), then any PSPs
+/// without cost data ordered by descending auth.
 fn build_ev_ordered_fallbacks(
     score_map: &HashMap<String, f64>,
     chosen: &str,
     ev: &dyn Fn(f64, f64) -> f64,
     cost_bps: &dyn Fn(&str) -> f64,
+    latency: &LatencyInputs,
 ) -> Vec<String> {
     let mut with_cost: Vec<(String, f64)> = score_map
         .iter()
@@ -199,7 +259,12 @@ fn build_ev_ordered_fallbacks(
         .filter(|(gw, _)| cost_bps(gw).is_finite())
         .map(|(gw, score)| (gw.clone(), ev(*score, cost_bps(gw))))
         .collect();
-    with_cost.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
+    with_cost.sort_by(|a, b| {
+        latency
+            .over_sla(&a.0)
+            .cmp(&latency.over_sla(&b.0))
+            .then_with(|| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal))
+    });
 
     let mut without_cost: Vec<(String, f64)> = score_map
         .iter()
@@ -224,7 +289,12 @@ fn current_head(score_map: &HashMap<String, f64>) -> Option<(String, f64)> {
         })
 }
 
-fn auth_won(margin: f64, reason: String) -> ReorderOutcome {
+fn auth_won(
+    margin: f64,
+    latency: &LatencyObjective,
+    cost_risk_aversion: Option<f64>,
+    reason: String,
+) -> ReorderOutcome {
     ReorderOutcome {
         head_moved: false,
         info: MultiObjectiveInfo {
@@ -239,18 +309,25 @@ fn auth_won(margin: f64, reason: String) -> ReorderOutcome {
             margin,
             ev_gap_top2: None,
             ranked: Vec::new(),
+            latency_penalty_bps_per_second: latency.penalty_bps_per_second,
+            latency_sla_ms: latency.sla_ms,
+            cost_risk_aversion,
+            currency: None,
         },
         cost_decision: None,
     }
 }
 
 /// Every candidate with cost data, as EV-ranked summaries ordered best-EV first, each flagged as
-/// the SR/auth head and/or the chosen PSP.
+/// the SR/auth head and/or the chosen PSP, with the risk premium and latency it was charged for.
+#[allow(clippy::too_many_arguments)]
 fn build_ranked(
     score_map: &HashMap<String, f64>,
     ev: &dyn Fn(f64, f64) -> f64,
     cost_bps: &dyn Fn(&str) -> f64,
     costs: &HashMap<String, PspCost>,
+    latency: &LatencyInputs,
+    cost_risk_aversion: Option<f64>,
     head_psp: Option<&str>,
     chosen_psp: Option<&str>,
 ) -> Vec<RankedPsp> {
@@ -259,9 +336,19 @@ fn build_ranked(
         .filter(|(gw, _)| cost_bps(gw).is_finite())
         .map(|(gw, &score)| {
             let c = cost_bps(gw);
+            let risk_adjusted_cost_bps =
+                risk_premium_bps(costs, gw, cost_risk_aversion).map(|p| c + p);
+            let latency_penalty_bps = latency.penalty_bps(gw);
             RankedPsp {
-                summary: make_summary(gw.clone(), score, Some(c), costs),
-                ev: ev(score, c),
+                summary: make_summary(gw.clone(), score, Some(c), costs, latency),
+                ev: ev(
+                    score,
+                    risk_adjusted_cost_bps.unwrap_or(c) + latency_penalty_bps.unwrap_or(0.0),
+                ),
+                cost_stderr_bps: costs.get(gw).and_then(|c| c.stderr_bps),
+                risk_adjusted_cost_bps,
+                latency_penalty_bps,
+                over_latency_sla: latency.over_sla(gw),
                 is_sr_head: head_psp == Some(gw.as_str()),
                 is_chosen: chosen_psp == Some(gw.as_str()),
             }
@@ -277,21 +364,37 @@ fn build_ranked(
     ranked
 }
 
+/// What the merchant's cost risk aversion adds to `gateway`'s cost, in bps: that many standard errors
+/// of its estimate. `None` when no aversion is set or the source gives no standard error.
+fn risk_premium_bps(
+    costs: &HashMap<String, PspCost>,
+    gateway: &str,
+    cost_risk_aversion: Option<f64>,
+) -> Option<f64> {
+    let aversion = cost_risk_aversion?;
+    let stderr_bps = costs.get(gateway)?.stderr_bps?;
+    Some(aversion.max(0.0) * stderr_bps.max(0.0))
+}
+
 /// Build a `PspSummary`, pulling the cost source and fitted breakdown (pct/fixed/ic_category) from
-/// the PSP's cost entry so callers can see *which* model priced it.
+/// the PSP's cost entry so callers can see *which* model priced it, and its observed latency.
 fn make_summary(
     psp: String,
     auth_rate: f64,
     cost_bps: Option<f64>,
     costs: &HashMap<String, PspCost>,
+    latency: &LatencyInputs,
 ) -> PspSummary {
     let c = costs.get(&psp);
+    let observed = latency.observed(&psp);
     PspSummary {
-        psp,
         auth_rate,
         cost_bps,
         cost_source: c.map(|c| c.source),
         cost_model: c.and_then(|c| c.cost_model.clone()),
+        latency_p50_ms: observed.map(|l| l.p50_ms),
+        latency_p95_ms: observed.map(|l| l.p95_ms),
+        psp,
     }
 }
 
@@ -307,6 +410,7 @@ fn top2_gap(mut evs: Vec<f64>) -> Option<f64> {
 
 #[cfg(test)]
 mod tests {
+    use super::super::latency::GatewayLatency;
     use super::super::CostSource;
     use super::*;
 
@@ -322,6 +426,7 @@ mod tests {
                     PspCost {
                         available: true,
                         effective_cost_bps: *c,
+                        stderr_bps: None,
                         source: CostSource::InHouse,
                         cost_model: None,
                     },
@@ -329,6 +434,30 @@ mod tests {
             })
             .collect()
     }
+    fn latency(
+        penalty_bps_per_second: Option<f64>,
+        sla_ms: Option<f64>,
+        p95s: &[(&str, f64)],
+    ) -> LatencyInputs {
+        LatencyInputs {
+            objective: LatencyObjective {
+                penalty_bps_per_second,
+                sla_ms,
+            },
+            observed: p95s
+                .iter()
+                .map(|(g, p95)| {
+                    (
+                        g.to_string(),
+                        GatewayLatency {
+                            p50_ms: p95 / 2.0,
+                            p95_ms: *p95,
+                        },
+                    )
+                })
+                .collect(),
+        }
+    }
 
     // §5.5 walkthrough: B=200, margin 20%. Gate drops D (6pp worse); EV picks B over the
     // cheaper C (B's extra auth beats C's lower cost).
@@ -338,7 +467,7 @@ mod tests {
     fn ev_picks_highest_ev_over_head_and_cheaper_psps() {
         let s = scores(&[("A", 0.910), ("B", 0.902), ("C", 0.885), ("D", 0.850)]);
         let c = costs(&[("A", 200.0), ("B", 150.0), ("C", 120.0), ("D", 110.0)]);
-        let out = reorder_for_cost(&s, 0.20, &c);
+        let out = reorder_for_cost(&s, 0.20, &c, &LatencyInputs::default(), None);
         assert_eq!(out.info.outcome, MultiObjectiveOutcome::CostWon);
         // No gate: every PSP with cost data is ranked, including D.
         assert_eq!(out.info.qualified_count, 4, "all four PSPs ranked on EV");
@@ -353,7 +482,7 @@ mod tests {
     fn pure_ev_promotes_far_worse_auth_when_ev_wins() {
         let s = scores(&[("A", 0.92), ("B", 0.87)]);
         let c = costs(&[("A", 180.0), ("B", 50.0)]);
-        let out = reorder_for_cost(&s, 0.20, &c);
+        let out = reorder_for_cost(&s, 0.20, &c, &LatencyInputs::default(), None);
         assert_eq!(
             out.info.outcome,
             MultiObjectiveOutcome::CostWon,
@@ -367,7 +496,7 @@ mod tests {
     fn head_wins_when_it_is_highest_ev() {
         let s = scores(&[("A", 0.91), ("B", 0.905)]);
         let c = costs(&[("A", 100.0), ("B", 130.0)]); // B pricier -> EV lower
-        let out = reorder_for_cost(&s, 0.20, &c);
+        let out = reorder_for_cost(&s, 0.20, &c, &LatencyInputs::default(), None);
         assert_eq!(out.info.outcome, MultiObjectiveOutcome::AuthWon);
     }
 
@@ -378,7 +507,7 @@ mod tests {
         // "dlocal" and "adyen" tie on auth; adyen is cheaper -> EV-best. Head = min name = adyen.
         let s = scores(&[("dlocal", 0.995), ("adyen", 0.995)]);
         let c = costs(&[("dlocal", 420.0), ("adyen", 194.0)]);
-        let out = reorder_for_cost(&s, 1.0, &c);
+        let out = reorder_for_cost(&s, 1.0, &c, &LatencyInputs::default(), None);
         assert_eq!(out.info.outcome, MultiObjectiveOutcome::AuthWon);
         let decision = out
             .cost_decision
@@ -397,7 +526,7 @@ mod tests {
         // dlocal wins on auth+EV; adyen loses despite being cheaper. Its cost must still appear.
         let s = scores(&[("dlocal", 0.99), ("adyen", 0.97)]);
         let c = costs(&[("dlocal", 420.0), ("adyen", 234.0)]);
-        let out = reorder_for_cost(&s, 1.0, &c);
+        let out = reorder_for_cost(&s, 1.0, &c, &LatencyInputs::default(), None);
         assert_eq!(out.info.outcome, MultiObjectiveOutcome::AuthWon);
         assert_eq!(out.info.ranked.len(), 2, "both candidates ranked");
         assert_eq!(out.info.ranked[0].summary.psp, "dlocal", "best EV first");
@@ -417,7 +546,7 @@ mod tests {
         // All four ranked; EVs B .16687 > C .16638 > A .1638 > D .16065. Top-two gap = B − C.
         let s = scores(&[("A", 0.910), ("B", 0.902), ("C", 0.885), ("D", 0.850)]);
         let c = costs(&[("A", 200.0), ("B", 150.0), ("C", 120.0), ("D", 110.0)]);
-        let out = reorder_for_cost(&s, 0.20, &c);
+        let out = reorder_for_cost(&s, 0.20, &c, &LatencyInputs::default(), None);
         assert_eq!(out.info.outcome, MultiObjectiveOutcome::CostWon);
         let gap = out.info.ev_gap_top2.expect("PSPs ranked on EV");
         assert!(
@@ -428,7 +557,7 @@ mod tests {
         // AuthWon still reports the head's EV lead over the runner-up (A − B).
         let s2 = scores(&[("A", 0.91), ("B", 0.905)]);
         let c2 = costs(&[("A", 100.0), ("B", 130.0)]);
-        let out2 = reorder_for_cost(&s2, 0.20, &c2);
+        let out2 = reorder_for_cost(&s2, 0.20, &c2, &LatencyInputs::default(), None);
         assert_eq!(out2.info.outcome, MultiObjectiveOutcome::AuthWon);
         let gap2 = out2.info.ev_gap_top2.expect("A, B ranked on EV");
         assert!(
@@ -439,10 +568,107 @@ mod tests {
         // Only one PSP has cost data → nothing to rank a second place against → None.
         let s3 = scores(&[("A", 0.90)]);
         let c3 = costs(&[("A", 100.0)]);
-        let out3 = reorder_for_cost(&s3, 0.20, &c3);
+        let out3 = reorder_for_cost(&s3, 0.20, &c3, &LatencyInputs::default(), None);
         assert_eq!(
             out3.info.ev_gap_top2, None,
             "single eligible PSP has no top-two gap"
         );
     }
+
+    // B wins on cost alone (see pure_ev_promotes_far_worse_auth_when_ev_wins), but at 100 bps per
+    // second its 2s p95 costs it 200 bps against A's 40 — and A keeps the head.
+    #[test]
+    fn latency_penalty_is_charged_in_the_ev() {
+        let s = scores(&[("A", 0.92), ("B", 0.87)]);
+        let c = costs(&[("A", 180.0), ("B", 50.0)]);
+        let l = latency(Some(100.0), None, &[("A", 400.0), ("B", 2_000.0)]);
+        let out = reorder_for_cost(&s, 0.20, &c, &l, None);
+        assert_eq!(out.info.outcome, MultiObjectiveOutcome::AuthWon);
+        assert_eq!(out.cost_decision.expect("cost decision").chosen, "A");
+        assert_eq!(out.info.latency_penalty_bps_per_second, Some(100.0));
+        let b = &out.info.ranked[1];
+        assert_eq!(b.summary.psp, "B");
+        assert_eq!(
+            b.summary.cost_bps,
+            Some(50.0),
+            "cost is reported unpenalized"
+        );
+        assert_eq!(b.summary.latency_p95_ms, Some(2_000.0));
+        assert_eq!(b.latency_penalty_bps, Some(200.0));
+        assert!((b.ev - 0.87 * (0.20 - 0.025)).abs() < 1e-9);
+    }
+
+    // B has the higher EV but is over the SLA, so it is ranked, flagged, and not promoted.
+    #[test]
+    fn psp_over_the_sla_is_not_promoted() {
+        let s = scores(&[("A", 0.92), ("B", 0.87)]);
+        let c = costs(&[("A", 180.0), ("B", 50.0)]);
+        let l = latency(None, Some(1_000.0), &[("A", 400.0), ("B", 2_000.0)]);
+        let out = reorder_for_cost(&s, 0.20, &c, &l, None);
+        assert_eq!(out.info.outcome, MultiObjectiveOutcome::AuthWon);
+        assert_eq!(out.cost_decision.expect("cost decision").chosen, "A");
+        assert_eq!(out.info.latency_sla_ms, Some(1_000.0));
+        let b = out
+            .info
+            .ranked
+            .iter()
+            .find(|r| r.summary.psp == "B")
+            .expect("B ranked");
+        assert!(b.over_latency_sla && !b.is_chosen);
+    }
+
+    // The head is over the SLA: the best PSP within it is chosen even on a lower EV, and the
+    // over-SLA PSPs fall to the back of the fallbacks.
+    #[test]
+    fn head_over_the_sla_gives_way() {
+        let s = scores(&[("A", 0.95), ("B", 0.90), ("C", 0.80)]);
+        let c = costs(&[("A", 100.0), ("B", 150.0), ("C", 150.0)]);
+        let l = latency(None, Some(1_000.0), &[("A", 3_000.0), ("B", 600.0)]);
+        let out = reorder_for_cost(&s, 0.20, &c, &l, None);
+        assert_eq!(out.info.outcome, MultiObjectiveOutcome::CostWon);
+        assert!(out.info.reason.contains("SLA"));
+        let decision = out.cost_decision.expect("cost decision");
+        assert_eq!(decision.chosen, "B");
+        assert_eq!(decision.fallbacks, vec!["C".to_string(), "A".to_string()]);
+    }
+
+    // B's 140 bps is cheaper than A's 150 but fitted on a thin cluster (±10 bps against A's ±0.5).
+    // Risk-neutral, B wins; at 1.645 standard errors (a one-sided 95% bound) it is charged 156.45
+    // against A's 150.82, and A keeps the head.
+    #[test]
+    fn risk_aversion_ranks_a_thin_fit_on_its_upper_bound() {
+        let s = scores(&[("A", 0.90), ("B", 0.90)]);
+        let mut c = costs(&[("A", 150.0), ("B", 140.0)]);
+        for (gw, se) in [("A", 0.5), ("B", 10.0)] {
+            if let Some(cost) = c.get_mut(gw) {
+                cost.stderr_bps = Some(se);
+            }
+        }
+
+        let neutral = reorder_for_cost(&s, 0.20, &c, &LatencyInputs::default(), None);
+        assert_eq!(neutral.cost_decision.expect("cost decision").chosen, "B");
+        assert!(neutral
+            .info
+            .ranked
+            .iter()
+            .all(|r| r.risk_adjusted_cost_bps.is_none()));
+
+        let averse = reorder_for_cost(&s, 0.20, &c, &LatencyInputs::default(), Some(1.645));
+        assert_eq!(averse.cost_decision.expect("cost decision").chosen, "A");
+        assert_eq!(averse.info.cost_risk_aversion, Some(1.645));
+        let b = averse
+            .info
+            .ranked
+            .iter()
+            .find(|r| r.summary.psp == "B")
+            .expect("B ranked");
+        assert_eq!(
+            b.summary.cost_bps,
+            Some(140.0),
+            "cost is reported unadjusted"
+        );
+        assert_eq!(b.cost_stderr_bps, Some(10.0));
+        assert!((b.risk_adjusted_cost_bps.expect("adjusted") - 156.45).abs() < 1e-9);
+        assert!((b.ev - 0.90 * (0.20 - 0.015645)).abs() < 1e-9);
+    }
 }
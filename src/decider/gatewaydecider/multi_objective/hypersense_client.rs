@@ -23,6 +23,9 @@ const FEE_TIMEOUT_MS: u64 = 2_000;
 pub struct PspCost {
     pub available: bool,
     pub effective_cost_bps: f64,
+    /// Standard error of `effective_cost_bps`, in bps, when the source knows how well its estimate
+    /// is pinned down (the in-house fits). `None` is not the same as exact: it is unknown.
+    pub stderr_bps: Option<f64>,
     /// Which source produced this cost (for observability on the response).
     pub source: CostSource,
     /// The fitted model behind `effective_cost_bps`, when the source exposes it.
@@ -305,6 +308,7 @@ impl CostCache {
                             PspCost {
                                 available: m.available,
                                 effective_cost_bps: m.effective_cost_bps(amount),
+                                stderr_bps: None,
                                 source: CostSource::Hypersense,
                                 cost_model: Some(CostModel {
                                     pct_bps: Some(m.pct_bps),
@@ -314,6 +318,13 @@ impl CostCache {
                                     issuer: None,
                                     ccy: None,
                                     ic_category: None,
+                                    volume_tier: None,
+                                    settlement_ccy: None,
+                                    fx_rate: None,
+                                    fx_markup_bps: None,
+                                    post_capture_fee: None,
+                                    interchange_pct_bps: None,
+                                    interchange_fixed_fee: None,
                                 }),
                             },
                         )
@@ -565,6 +576,7 @@ fn inhouse_costs(
                 PspCost {
                     available: true,
                     effective_cost_bps: m.effective_bps,
+                    stderr_bps: Some(m.stderr_bps),
                     source: CostSource::InHouse,
                     cost_model: Some(CostModel {
                         brand: Some(m.brand),
@@ -574,6 +586,13 @@ fn inhouse_costs(
                         ic_category: m.ic_category,
                         pct_bps: Some(m.pct_bps),
                         fixed_fee: Some(m.fixed),
+                        volume_tier: m.volume_tier,
+                        settlement_ccy: m.fx.as_ref().map(|f| f.settlement_currency.clone()),
+                        fx_rate: m.fx.as_ref().map(|f| f.rate),
+                        fx_markup_bps: m.fx.map(|f| f.markup_bps),
+                        post_capture_fee: m.post_capture_fee,
+                        interchange_pct_bps: m.interchange_pct_bps,
+                        interchange_fixed_fee: m.interchange_fixed,
                     }),
                 },
             );
@@ -707,6 +726,7 @@ async fn fallback_lookup_costs(
                 PspCost {
                     available: row.available,
                     effective_cost_bps: row.effective_cost_bps.unwrap_or(0.0),
+                    stderr_bps: None,
                     source: CostSource::Hypersense,
                     cost_model: None,
                 },
@@ -0,0 +1,224 @@
+//! Gateway latency as the third multi-objective objective, next to auth rate and cost.
+//!
+//! Every score update that reports a `gatewayLatency` pushes it onto a per-merchant, per-gateway
+//! window in Redis ([`record_latency`]); the post step reads the windows back as p50/p95
+//! ([`load_latency_inputs`]). The merchant prices latency in their `SR_V3_INPUT_CONFIG` — as a
+//! penalty in bps per second of p95 that is added to the PSP's cost in the EV, as an SLA ceiling
+//! on p95 above which a PSP can't be chosen over one within it, or both. With neither set the
+//! post step ranks on auth and cost alone, exactly as before.
+
+use std::collections::HashMap;
+
+use fred::prelude::{KeysInterface, ListInterface};
+
+use crate::app::get_tenant_app_state;
+use crate::logger;
+
+/// Samples kept per merchant and gateway: the newest win, like the SR outcome queues.
+pub const LATENCY_WINDOW_SIZE: i64 = 200;
+/// Fewer samples than this and the window doesn't speak for the gateway yet.
+pub const MIN_LATENCY_SAMPLES: usize = 20;
+const LATENCY_WINDOW_TTL_SECS: i64 = 7 * 24 * 60 * 60;
+
+/// How the merchant trades latency against EV.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct LatencyObjective {
+    /// Bps of ticket a PSP is charged in the EV per second of p95 latency.
+    pub penalty_bps_per_second: Option<f64>,
+    /// Highest p95, in ms, a PSP may have to be chosen over one within it.
+    pub sla_ms: Option<f64>,
+}
+
+impl LatencyObjective {
+    pub fn is_set(&self) -> bool {
+        self.penalty_bps_per_second.is_some() || self.sla_ms.is_some()
+    }
+}
+
+/// A gateway's observed latency over its window, in ms.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GatewayLatency {
+    pub p50_ms: f64,
+    pub p95_ms: f64,
+}
+
+/// The objective and the latency it is applied to. The default has neither, and leaves the post
+/// step ranking on auth and cost alone.
+#[derive(Debug, Clone, Default)]
+pub struct LatencyInputs {
+    pub objective: LatencyObjective,
+    pub observed: HashMap<String, GatewayLatency>,
+}
+
+impl LatencyInputs {
+    pub fn observed(&self, gateway: &str) -> Option<GatewayLatency> {
+        self.observed.get(gateway).copied()
+    }
+
+    /// The EV charge for `gateway`'s latency, in bps. A gateway without enough samples is not
+    /// charged: it can't be judged slow on data it doesn't have.
+    pub fn penalty_bps(&self, gateway: &str) -> Option<f64> {
+        let per_second = self.objective.penalty_bps_per_second?;
+        let observed = self.observed(gateway)?;
+        Some(per_second.max(0.0) * observed.p95_ms / 1_000.0)
+    }
+
+    /// Whether `gateway`'s p95 is over the SLA. Unmeasured gateways are within it.
+    pub fn over_sla(&self, gateway: &str) -> bool {
+        match (self.objective.sla_ms, self.observed(gateway)) {
+            (Some(sla_ms), Some(observed)) => observed.p95_ms > sla_ms,
+            _ => false,
+        }
+    }
+}
+
+fn window_key(merchant_id: &str, gateway: &str) -> String {
+    format!("GW_LATENCY_WINDOW_{}_{}", merchant_id, gateway)
+}
+
+/// Pushes one latency sample onto the gateway's window, dropping the oldest past
+/// [`LATENCY_WINDOW_SIZE`]. Best effort: a failed write is logged and routing carries on.
+pub async fn record_latency(merchant_id: &str, gateway: &str, latency_ms: f64) {
+    if !latency_ms.is_finite() || latency_ms < 0.0 {
+        return;
+    }
+    let app_state = get_tenant_app_state().await;
+    let key = window_key(merchant_id, gateway);
+    let value = latency_ms.to_string();
+    let r: Result<Vec<String>, error_stack::Report<redis_interface::errors::RedisError>> =
+        app_state
+            .redis_conn
+            .multi(false, |transaction| {
+                Box::pin(async move {
+                    transaction
+                        .lpush::<(), _, _>(&fred::types::RedisKey::from(key.clone()), vec![&value])
+                        .await?;
+                    transaction
+                        .ltrim::<(), _>(
+                            &fred::types::RedisKey::from(key.clone()),
+                            0,
+                            LATENCY_WINDOW_SIZE - 1,
+                        )
+                        .await?;
+                    transaction
+                        .expire::<(), _>(&key, LATENCY_WINDOW_TTL_SECS)
+                        .await?;
+                    Ok(())
+                })
+            })
+            .await;
+    if let Err(e) = r {
+        logger::warn!(
+            tag = "gatewayLatency",
+            action = "record_latency",
+            "failed to record latency for {} / {}: {:?}",
+            merchant_id,
+            gateway,
+            e
+        );
+    }
+}
+
+/// The merchant's latency objective with the p50/p95 of every gateway in `gateways` that has
+/// enough samples. Windows are only read when the merchant has set an objective.
+pub async fn load_latency_inputs(
+    merchant_id: &str,
+    objective: LatencyObjective,
+    gateways: &[String],
+) -> LatencyInputs {
+    let mut observed = HashMap::new();
+    if objective.is_set() {
+        let app_state = get_tenant_app_state().await;
+        for gateway in gateways {
+            let samples: Vec<f64> = app_state
+                .redis_conn
+                .get_list_range(
+                    &window_key(merchant_id, gateway),
+                    0,
+                    LATENCY_WINDOW_SIZE - 1,
+                )
+                .await
+                .unwrap_or_default()
+                .iter()
+                .filter_map(|sample| sample.parse().ok())
+                .collect();
+            if let Some(latency) = summarize(samples) {
+                observed.insert(gateway.clone(), latency);
+            }
+        }
+    }
+    LatencyInputs {
+        objective,
+        observed,
+    }
+}
+
+/// p50 and p95 of a window, or `None` below [`MIN_LATENCY_SAMPLES`].
+pub fn summarize(mut samples: Vec<f64>) -> Option<GatewayLatency> {
+    samples.retain(|sample| sample.is_finite());
+    if samples.len() < MIN_LATENCY_SAMPLES {
+        return None;
+    }
+    samples.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+    Some(GatewayLatency {
+        p50_ms: percentile(&samples, 0.50),
+        p95_ms: percentile(&samples, 0.95),
+    })
+}
+
+/// Nearest-rank percentile of sorted, non-empty samples.
+fn percentile(sorted: &[f64], p: f64) -> f64 {
+    let rank = (p * sorted.len() as f64).ceil() as usize;
+    sorted[rank.clamp(1, sorted.len()) - 1]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn inputs(objective: LatencyObjective, p95_ms: f64) -> LatencyInputs {
+        LatencyInputs {
+            objective,
+            observed: HashMap::from([(
+                "A".to_string(),
+                GatewayLatency {
+                    p50_ms: p95_ms / 2.0,
+                    p95_ms,
+                },
+            )]),
+        }
+    }
+
+    #[test]
+    fn summarizes_nearest_rank_percentiles() {
+        let samples: Vec<f64> = (1..=100).map(f64::from).collect();
+        let latency = summarize(samples).expect("enough samples");
+        assert_eq!(latency.p50_ms, 50.0);
+        assert_eq!(latency.p95_ms, 95.0);
+        assert_eq!(summarize(vec![100.0; MIN_LATENCY_SAMPLES - 1]), None);
+    }
+
+    #[test]
+    fn penalty_scales_with_p95_and_skips_unmeasured_gateways() {
+        let objective = LatencyObjective {
+            penalty_bps_per_second: Some(10.0),
+            sla_ms: None,
+        };
+        let latency = inputs(objective, 1_500.0);
+        assert_eq!(latency.penalty_bps("A"), Some(15.0));
+        assert_eq!(latency.penalty_bps("B"), None);
+        assert!(!latency.over_sla("A"));
+    }
+
+    #[test]
+    fn sla_applies_to_measured_p95_only() {
+        let objective = LatencyObjective {
+            penalty_bps_per_second: None,
+            sla_ms: Some(1_000.0),
+        };
+        let latency = inputs(objective, 1_500.0);
+        assert!(latency.over_sla("A"));
+        assert!(!latency.over_sla("B"));
+        assert_eq!(latency.penalty_bps("A"), None);
+    }
+}
@@ -1,6 +1,7 @@
 pub mod algorithm;
 pub mod cluster_key;
 pub mod hypersense_client;
+pub mod latency;
 pub mod seed_costs;
 pub mod seed_store;
 
@@ -46,6 +47,22 @@ pub struct MultiObjectiveInfo {
     /// the SR head had no finite score or no cost data.
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub ranked: Vec<RankedPsp>,
+    /// The merchant's latency penalty, in bps of ticket per second of p95, when one is set. Each
+    /// ranked PSP's EV was charged `latencyPenaltyBps` for its latency on top of its cost.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub latency_penalty_bps_per_second: Option<f64>,
+    /// The merchant's p95 latency ceiling in ms, when one is set. A PSP over it (`overLatencySla`)
+    /// was not chosen over one within it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub latency_sla_ms: Option<f64>,
+    /// The merchant's cost risk aversion, in standard errors, when one is set. Each ranked PSP's EV
+    /// was charged `riskAdjustedCostBps` in place of its point cost.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cost_risk_aversion: Option<f64>,
+    /// The payment's currency. Every ranked cost is in bps of the ticket in it: a fixed fee from a
+    /// model fitted in another currency was converted first (see `CostModel.settlementCcy`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub currency: Option<String>,
 }
 
 /// One EV-ranked candidate: a `PspSummary`, the expected value it was ranked by, and flags marking
@@ -55,8 +72,24 @@ pub struct MultiObjectiveInfo {
 pub struct RankedPsp {
     #[serde(flatten)]
     pub summary: PspSummary,
-    /// Expected value used for ranking: `auth·(margin − cost_bps/10_000)`.
+    /// Expected value used for ranking:
+    /// `auth·(margin − (riskAdjustedCostBps + latencyPenaltyBps)/10_000)`, with `costBps` standing
+    /// in for the risk-adjusted cost when none was charged.
     pub ev: f64,
+    /// Standard error of `costBps`, when its source fits one: thin clusters have wide ones.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cost_stderr_bps: Option<f64>,
+    /// `costBps` plus the merchant's risk aversion times `costStderrBps` — the upper-bound cost the
+    /// EV charged, when the merchant prices cost uncertainty.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub risk_adjusted_cost_bps: Option<f64>,
+    /// What the PSP's p95 latency added to its cost in the EV, when the merchant prices latency
+    /// and the PSP has enough latency samples.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub latency_penalty_bps: Option<f64>,
+    /// The PSP's p95 latency is over the merchant's SLA, so it could only be kept as the SR head.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub over_latency_sla: bool,
     /// The PSP pure success-rate routing would have picked (highest auth, deterministic tie-break).
     pub is_sr_head: bool,
     /// The PSP actually chosen — equals `decided_gateway`. Same row as `isSrHead` on `AUTH_WON`.
@@ -109,6 +142,29 @@ pub struct CostModel {
     pub pct_bps: Option<f64>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub fixed_fee: Option<f64>,
+    /// The contract volume tier (0-based) the payment was priced in, when the connector is priced
+    /// by month-to-date volume.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub volume_tier: Option<usize>,
+    /// The currency the model (or contract) is stated in, when it differs from the payment's `ccy`.
+    /// `fixedFee` was then converted into `ccy` at `fxRate`, and converting the payment costs
+    /// `fxMarkupBps` on top of `pctBps`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub settlement_ccy: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fx_rate: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fx_markup_bps: Option<f64>,
+    /// Expected refund and dispute fees per payment, in `ccy` — the connector's observed event rate
+    /// times its fees. Included in the effective cost, on top of `pctBps` and `fixedFee`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub post_capture_fee: Option<f64>,
+    /// Set when the connector had no fitted model for the card and it was priced as the shared
+    /// interchange plus the connector's IC++ markup: the interchange part of `pctBps` and `fixedFee`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub interchange_pct_bps: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub interchange_fixed_fee: Option<f64>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
@@ -123,4 +179,10 @@ pub struct PspSummary {
     /// The fitted model behind `cost_bps` (which cluster priced it), when the source exposes it.
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub cost_model: Option<CostModel>,
+    /// The PSP's observed p50/p95 gateway latency in ms, when the merchant has a latency objective
+    /// and the PSP has enough samples.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub latency_p50_ms: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub latency_p95_ms: Option<f64>,
 }
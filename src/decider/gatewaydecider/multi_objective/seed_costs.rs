@@ -193,6 +193,7 @@ pub fn lookup_seed_costs(
                 PspCost {
                     available: true,
                     effective_cost_bps: effective_cost_bps(pct_bps, fee.fixed, amount),
+                    stderr_bps: None,
                     source: super::CostSource::Seed,
                     cost_model: Some(super::CostModel {
                         brand: cluster.card_network.clone(),
@@ -202,6 +203,13 @@ pub fn lookup_seed_costs(
                         variant: None,
                         issuer: None,
                         ic_category: None,
+                        volume_tier: None,
+                        settlement_ccy: None,
+                        fx_rate: None,
+                        fx_markup_bps: None,
+                        post_capture_fee: None,
+                        interchange_pct_bps: None,
+                        interchange_fixed_fee: None,
                     }),
                 },
             ))
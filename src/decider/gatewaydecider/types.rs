@@ -477,6 +477,8 @@ pub struct DeciderState {
     /// overrides the merchant's elimination rule. Absent for control arm and non-tuning experiments.
     pub ab_test_sr_override: Option<crate::euclid::types::SrConfigOverride>,
     pub multi_objective_info: Option<super::multi_objective::MultiObjectiveInfo>,
+    /// The scoring strategy that ranked the gateways, when the flow scored on SR.
+    pub scoring_strategy: Option<String>,
 }
 
 pub fn initial_decider_state(date_created: String) -> DeciderState {
@@ -522,6 +524,7 @@ pub fn initial_decider_state(date_created: String) -> DeciderState {
         sr_v3_hedging_percent: None,
         gateway_reference_id: None,
         multi_objective_info: None,
+        scoring_strategy: None,
         gateway_scoring_data: GatewayScoringData {
             merchantId: String::new(),
             paymentMethodType: String::new(),
@@ -1033,6 +1036,10 @@ pub struct PaymentInfo {
     /// (the strongest signal between online and in-person interchange categories). Optional.
     #[serde(default, rename = "channel")]
     channel: Option<String>,
+    /// Stable fingerprint of the card, such as a vault or PSP card hash — never the PAN. Optional;
+    /// lets an A/B experiment keep a card on the same arm across orders.
+    #[serde(default)]
+    card_fingerprint: Option<String>,
 }
 
 // write a function to transfer DomainDeciderRequestForApiCallV2 to DomainDeciderRequest
@@ -1046,6 +1053,17 @@ impl DomainDeciderRequestForApiCallV2 {
         &self.payment_info.payment_method_type
     }
 
+    pub fn customer_id(&self) -> Option<&str> {
+        self.payment_info
+            .customer_id
+            .as_ref()
+            .map(|customer_id| customer_id.0.as_str())
+    }
+
+    pub fn card_fingerprint(&self) -> Option<&str> {
+        self.payment_info.card_fingerprint.as_deref()
+    }
+
     pub fn payment_method(&self) -> &str {
         &self.payment_info.payment_method
     }
@@ -1270,6 +1288,9 @@ pub struct SrV3InputConfig {
     pub defaultUpperResetFactor: Option<f64>,
     pub defaultGatewayExtraScore: Option<Vec<GatewayWiseExtraScore>>,
     pub subLevelInputConfig: Option<Vec<SrV3SubLevelInputConfig>>,
+    /// The [`super::gw_scoring::strategy`] the merchant's gateways are scored with.
+    #[serde(default)]
+    pub scoringStrategy: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -1378,6 +1399,10 @@ pub struct DecidedGateway {
     pub is_rust_based_decider: bool,
     pub latency: Option<u64>,
     pub multi_objective_info: Option<super::multi_objective::MultiObjectiveInfo>,
+    /// The [`super::gw_scoring::strategy`] that scored the gateways; absent when the decision
+    /// wasn't made on SR.
+    #[serde(default)]
+    pub scoring_strategy: Option<String>,
 }
 
 #[derive(Debug, Serialize, Clone, Deserialize)]
@@ -1295,54 +1295,6 @@ pub fn get_gateway_decider_approach(
     }
 }
 
-pub fn modify_gateway_decider_approach(
-    gw_decider_approach: types::GatewayDeciderApproach,
-    down_time: types::DownTime,
-) -> types::GatewayDeciderApproach {
-    match gw_decider_approach {
-        types::GatewayDeciderApproach::SrSelectionV3Routing => match down_time {
-            types::DownTime::AllDowntime => types::GatewayDeciderApproach::SrV3AllDowntimeRouting,
-            types::DownTime::GlobalDowntime => {
-                types::GatewayDeciderApproach::SrV3GlobalDowntimeRouting
-            }
-            types::DownTime::Downtime => types::GatewayDeciderApproach::SrV3DowntimeRouting,
-            types::DownTime::NoDowntime => types::GatewayDeciderApproach::SrSelectionV3Routing,
-        },
-        types::GatewayDeciderApproach::SrV3Hedging => match down_time {
-            types::DownTime::AllDowntime => types::GatewayDeciderApproach::SrV3AllDowntimeHedging,
-            types::DownTime::GlobalDowntime => {
-                types::GatewayDeciderApproach::SrV3GlobalDowntimeHedging
-            }
-            types::DownTime::Downtime => types::GatewayDeciderApproach::SrV3DowntimeHedging,
-            types::DownTime::NoDowntime => types::GatewayDeciderApproach::SrV3Hedging,
-        },
-        types::GatewayDeciderApproach::SrSelectionV2Routing => match down_time {
-            types::DownTime::AllDowntime => types::GatewayDeciderApproach::SrV2AllDowntimeRouting,
-            types::DownTime::GlobalDowntime => {
-                types::GatewayDeciderApproach::SrV2GlobalDowntimeRouting
-            }
-            types::DownTime::Downtime => types::GatewayDeciderApproach::SrV2DowntimeRouting,
-            types::DownTime::NoDowntime => types::GatewayDeciderApproach::SrSelectionV2Routing,
-        },
-        types::GatewayDeciderApproach::SrV2Hedging => match down_time {
-            types::DownTime::AllDowntime => types::GatewayDeciderApproach::SrV2AllDowntimeHedging,
-            types::DownTime::GlobalDowntime => {
-                types::GatewayDeciderApproach::SrV2GlobalDowntimeHedging
-            }
-            types::DownTime::Downtime => types::GatewayDeciderApproach::SrV2DowntimeHedging,
-            types::DownTime::NoDowntime => types::GatewayDeciderApproach::SrV2Hedging,
-        },
-        _ => match down_time {
-            types::DownTime::AllDowntime => types::GatewayDeciderApproach::PlAllDowntimeRouting,
-            types::DownTime::GlobalDowntime => {
-                types::GatewayDeciderApproach::PlGlobalDowntimeRouting
-            }
-            types::DownTime::Downtime => types::GatewayDeciderApproach::PlDowntimeRouting,
-            types::DownTime::NoDowntime => types::GatewayDeciderApproach::PriorityLogic,
-        },
-    }
-}
-
 pub fn get_juspay_bank_code_from_internal_metadata(txn_detail: &ETTD::TxnDetail) -> Option<String> {
     txn_detail.internalMetadata.as_ref().and_then(|metadata| {
         from_str::<Value>(metadata.peek()).ok().and_then(|json| {
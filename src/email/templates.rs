@@ -258,3 +258,80 @@ impl InviteUserTemplate {
         }
     }
 }
+
+pub struct ExperimentActionTemplate {
+    pub user_email: String,
+    pub experiment_name: String,
+    pub merchant_id: String,
+    /// What was done, e.g. "paused arm variant".
+    pub action: String,
+    /// Why, in one sentence.
+    pub reason: String,
+}
+
+impl ExperimentActionTemplate {
+    pub fn into_message(self) -> EmailMessage {
+        let experiment = escape_html(&self.experiment_name);
+        let content = format!(
+            r#"              <h1 style="margin:0 0 14px;font-size:20px;font-weight:600;color:#111827;line-height:1.3;">Experiment {experiment}: {action}</h1>
+              <p style="margin:0 0 28px;font-size:15px;line-height:1.6;color:#374151;">
+                {reason}
+              </p>
+              <p style="margin:28px 0 0;font-size:13px;line-height:1.6;color:#6b7280;">
+                This change was made automatically under the experiment's policy for merchant {merchant}. It is recorded in the experiment's action history.
+              </p>"#,
+            experiment = experiment,
+            action = escape_html(&self.action),
+            reason = escape_html(&self.reason),
+            merchant = escape_html(&self.merchant_id),
+        );
+
+        EmailMessage {
+            to: self.user_email,
+            subject: format!("Experiment {}: {}", self.experiment_name, self.action),
+            html_body: render_layout(
+                &format!("{} — {}", self.experiment_name, self.action),
+                &content,
+            ),
+        }
+    }
+}
+
+pub struct PriceChangeAlertTemplate {
+    pub user_email: String,
+    pub merchant_id: String,
+    /// One line per detected change, e.g. "adyen visa/credit/GB: 240.0 → 250.0 bps, 0.22 → 0.25".
+    pub changes: Vec<String>,
+    /// How many of `changes` crossed the merchant's major threshold.
+    pub major: usize,
+}
+
+impl PriceChangeAlertTemplate {
+    pub fn into_message(self) -> EmailMessage {
+        let count = self.changes.len();
+        let noun = if count == 1 { "change" } else { "changes" };
+        let items = self
+            .changes
+            .iter()
+            .map(|line| format!(r#"<li style="margin:0 0 6px;">{}</li>"#, escape_html(line)))
+            .collect::<String>();
+        let content = format!(
+            r#"              <h1 style="margin:0 0 14px;font-size:20px;font-weight:600;color:#111827;line-height:1.3;">{count} fee price {noun} detected</h1>
+              <p style="margin:0 0 16px;font-size:15px;line-height:1.6;color:#374151;">
+                The latest settlement reports for merchant {merchant} were priced differently from the previous fit ({major} major).
+              </p>
+              <ul style="margin:0 0 28px;padding-left:20px;font-size:14px;line-height:1.6;color:#374151;">{items}</ul>
+              <p style="margin:28px 0 0;font-size:13px;line-height:1.6;color:#6b7280;">
+                Acknowledge these on the cost price alerts page once they have been reviewed. Thresholds can be changed in the alert settings.
+              </p>"#,
+            merchant = escape_html(&self.merchant_id),
+            major = self.major,
+        );
+
+        EmailMessage {
+            to: self.user_email,
+            subject: format!("{count} fee price {noun} detected for {}", self.merchant_id),
+            html_body: render_layout(&format!("{count} fee price {noun} detected"), &content),
+        }
+    }
+}
@@ -106,6 +106,8 @@ pub enum RuleConfigurationError {
     DeserializationError,
     #[error("Debit routing not enabled for merchant")]
     DebitRoutingNotEnabled,
+    #[error("{0}")]
+    UnknownScoringStrategy(String),
 }
 
 impl axum::response::IntoResponse for RuleConfigurationError {
@@ -174,6 +176,15 @@ impl axum::response::IntoResponse for RuleConfigurationError {
                 )),
             )
                 .into_response(),
+            Self::UnknownScoringStrategy(message) => (
+                hyper::StatusCode::BAD_REQUEST,
+                axum::Json(crate::error::ApiErrorResponse::new(
+                    crate::error::error_codes::TE_04,
+                    message,
+                    None,
+                )),
+            )
+                .into_response(),
         }
     }
 }
@@ -278,11 +289,33 @@ pub enum ApiKeyError {
     MerchantNotFound,
     #[error("Storage error")]
     StorageError,
+    #[error("{0}")]
+    InvalidRequest(String),
+    #[error("A key cannot be granted permissions its creator does not hold")]
+    Forbidden,
 }
 
 impl axum::response::IntoResponse for ApiKeyError {
     fn into_response(self) -> axum::response::Response {
         match self {
+            Self::InvalidRequest(_) => (
+                hyper::StatusCode::BAD_REQUEST,
+                axum::Json(crate::error::ApiErrorResponse::new(
+                    crate::error::error_codes::TE_04,
+                    self.to_string(),
+                    None,
+                )),
+            )
+                .into_response(),
+            Self::Forbidden => (
+                hyper::StatusCode::FORBIDDEN,
+                axum::Json(crate::error::ApiErrorResponse::new(
+                    crate::error::error_codes::TE_04,
+                    self.to_string(),
+                    None,
+                )),
+            )
+                .into_response(),
             Self::NotFound => (
                 hyper::StatusCode::NOT_FOUND,
                 axum::Json(crate::error::ApiErrorResponse::new(
@@ -352,6 +385,34 @@ pub enum UserAuthError {
     UnsupportedOperation,
     #[error("Invalid or expired password reset token")]
     InvalidResetToken,
+    #[error("{0}")]
+    InvalidRole(String),
+    #[error("Role not found")]
+    RoleNotFound,
+    #[error("Role is still held by members of this merchant")]
+    RoleInUse,
+    #[error("Member not found")]
+    MemberNotFound,
+    #[error("This merchant requires signing in through single sign-on")]
+    SsoRequired,
+    #[error("Identity provider not found")]
+    SsoProviderNotFound,
+    #[error("Single sign-on failed: {0}")]
+    SsoFailed(String),
+    #[error("No account exists for this user; ask an admin for an invite")]
+    SsoNotProvisioned,
+    #[error("This merchant requires two-factor authentication; set up an authenticator app first")]
+    TwoFactorRequired,
+    #[error("Invalid two-factor code")]
+    InvalidTwoFactorCode,
+    #[error("Too many invalid two-factor codes; try again later")]
+    TooManyTwoFactorAttempts,
+    #[error("Two-factor authentication is already enabled")]
+    TwoFactorAlreadyEnabled,
+    #[error("Two-factor authentication is not enabled")]
+    TwoFactorNotEnabled,
+    #[error("Two-factor authentication is not configured on this deployment")]
+    TwoFactorUnavailable,
 }
 
 impl axum::response::IntoResponse for UserAuthError {
@@ -377,6 +438,26 @@ impl axum::response::IntoResponse for UserAuthError {
             Self::InvalidVerificationToken => (hyper::StatusCode::BAD_REQUEST, self.to_string()),
             Self::UnsupportedOperation => (hyper::StatusCode::FORBIDDEN, self.to_string()),
             Self::InvalidResetToken => (hyper::StatusCode::BAD_REQUEST, self.to_string()),
+            Self::InvalidRole(_) => (hyper::StatusCode::BAD_REQUEST, self.to_string()),
+            Self::RoleNotFound | Self::MemberNotFound => {
+                (hyper::StatusCode::NOT_FOUND, self.to_string())
+            }
+            Self::RoleInUse => (hyper::StatusCode::CONFLICT, self.to_string()),
+            Self::SsoRequired | Self::SsoNotProvisioned => {
+                (hyper::StatusCode::FORBIDDEN, self.to_string())
+            }
+            Self::SsoProviderNotFound => (hyper::StatusCode::NOT_FOUND, self.to_string()),
+            Self::SsoFailed(_) => (hyper::StatusCode::UNAUTHORIZED, self.to_string()),
+            Self::TwoFactorRequired => (hyper::StatusCode::FORBIDDEN, self.to_string()),
+            Self::InvalidTwoFactorCode => (hyper::StatusCode::UNAUTHORIZED, self.to_string()),
+            Self::TooManyTwoFactorAttempts => {
+                (hyper::StatusCode::TOO_MANY_REQUESTS, self.to_string())
+            }
+            Self::TwoFactorAlreadyEnabled => (hyper::StatusCode::CONFLICT, self.to_string()),
+            Self::TwoFactorNotEnabled => (hyper::StatusCode::BAD_REQUEST, self.to_string()),
+            Self::TwoFactorUnavailable => {
+                (hyper::StatusCode::SERVICE_UNAVAILABLE, self.to_string())
+            }
         };
         (
             status,
@@ -1,9 +1,13 @@
+pub mod analyzer;
 pub mod ast;
 pub mod cgraph;
+pub mod diff;
+pub mod dsl;
 pub mod errors;
 pub mod handlers;
 pub mod interpreter;
 pub mod pm_filter_graph;
+pub mod schedule;
 pub mod types;
 pub mod utils;
 
@@ -0,0 +1,999 @@
+//! Static analysis of routing programs.
+//!
+//! The interpreter takes the first rule whose statements match, so a rule set can be accepted,
+//! stored and activated while parts of it can never take effect: a rule shadowed by an earlier,
+//! broader one, a statement whose comparisons cannot all hold, or an output naming a connector
+//! the merchant has no account for. None of these are errors — the program still evaluates — so
+//! they are reported as warnings alongside the create, update and lint responses.
+//!
+//! Each rule is flattened into its paths: one conjunction of comparisons per way through its
+//! statements, their `any` groups and their nested blocks. A path is summarised per key, as an
+//! interval with exclusions for numbers and an allowed/excluded set for enum variants and strings.
+//! What cannot be summarised — metadata, `not` groups, operators the interpreter rejects — is
+//! kept aside and only ever matched structurally, so every finding is definite: an unsummarised
+//! comparison can hide a conflict, but never invent one.
+
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+
+use super::{
+    ast::{
+        Comparison, ComparisonType, Condition, ConnectorInfo, Globals, IfCondition, IfStatement,
+        Output, Program, Rule, ValueType,
+    },
+    types::StaticRoutingAlgorithm,
+};
+
+/// Past this many paths a rule is only checked for contradictions; comparing it pairwise against
+/// every other rule would cost more than a request should.
+const MAX_PATHS_PER_RULE: usize = 256;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WarningKind {
+    /// Every way the rule can match is already matched by an earlier rule.
+    UnreachableRule,
+    /// A statement whose comparisons can never all hold.
+    ContradictoryCondition,
+    /// Two rules with different outputs that both match some traffic; the earlier one wins it.
+    OverlappingRules,
+    /// An output names a connector the merchant has no enabled gateway account for.
+    UnknownConnector,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct AnalysisWarning {
+    pub kind: WarningKind,
+    /// The rule the warning is about. Absent for the default selection.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rule: Option<String>,
+    /// The earlier rule that shadows or overlaps `rule`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub related_rule: Option<String>,
+    pub message: String,
+}
+
+/// Analyses a routing algorithm of any kind. Only an advanced program has rules to reason about;
+/// the other kinds are checked for their connectors alone.
+///
+/// `configured_gateways` holds the merchant's gateway names, upper-cased as the gateway accounts
+/// store them. `None` skips the connector check.
+pub fn analyze_algorithm(
+    algorithm: &StaticRoutingAlgorithm,
+    configured_gateways: Option<&HashSet<String>>,
+) -> Vec<AnalysisWarning> {
+    match algorithm {
+        StaticRoutingAlgorithm::Advanced(program) => analyze_program(program, configured_gateways),
+        StaticRoutingAlgorithm::Single(connector) => {
+            unknown_connectors(None, [connector.as_ref()], configured_gateways)
+        }
+        StaticRoutingAlgorithm::Priority(connectors) => {
+            unknown_connectors(None, connectors, configured_gateways)
+        }
+        StaticRoutingAlgorithm::VolumeSplit(splits) => unknown_connectors(
+            None,
+            splits.iter().map(|split| &split.output),
+            configured_gateways,
+        ),
+        // An experiment only points at other algorithms, which were analysed when they were
+        // created.
+        StaticRoutingAlgorithm::AbTest(_) => Vec::new(),
+    }
+}
+
+pub fn analyze_program(
+    program: &Program,
+    configured_gateways: Option<&HashSet<String>>,
+) -> Vec<AnalysisWarning> {
+    let mut warnings = Vec::new();
+
+    let analysed: Vec<AnalysedRule<'_>> = program
+        .rules
+        .iter()
+        .map(|rule| AnalysedRule::new(rule, &program.globals))
+        .collect();
+
+    for rule in &analysed {
+        warnings.extend(rule.contradictions());
+    }
+
+    let mut unreachable = vec![false; analysed.len()];
+    for (index, later) in analysed.iter().enumerate() {
+        let Some(later_paths) = later.comparable_paths() else {
+            continue;
+        };
+
+        if later_paths.is_empty() {
+            // Nothing to shadow: the rule matches no traffic of its own accord, and the
+            // contradictions (if any) have been reported already.
+            if later.rule.statements.is_empty() {
+                unreachable[index] = true;
+                warnings.push(AnalysisWarning {
+                    kind: WarningKind::UnreachableRule,
+                    rule: Some(later.rule.name.clone()),
+                    related_rule: None,
+                    message: format!(
+                        "rule `{}` has no statements, so it never matches",
+                        later.rule.name
+                    ),
+                });
+            }
+            continue;
+        }
+
+        if let Some(shadowing) = shadowing_rule(&analysed[..index], &later_paths) {
+            unreachable[index] = true;
+            warnings.push(AnalysisWarning {
+                kind: WarningKind::UnreachableRule,
+                rule: Some(later.rule.name.clone()),
+                related_rule: Some(shadowing.to_string()),
+                message: format!(
+                    "rule `{}` can never fire: everything it matches is matched first by `{shadowing}`",
+                    later.rule.name
+                ),
+            });
+        }
+    }
+
+    for (index, later) in analysed.iter().enumerate() {
+        if unreachable[index] {
+            continue;
+        }
+        let Some(later_paths) = later.comparable_paths() else {
+            continue;
+        };
+        for earlier in &analysed[..index] {
+            if earlier.rule.output == later.rule.output {
+                continue;
+            }
+            let Some(earlier_paths) = earlier.comparable_paths() else {
+                continue;
+            };
+            let overlaps = later_paths.iter().any(|path| {
+                earlier_paths.iter().any(|other| {
+                    let both = path.terms.joined(&other.terms);
+                    Conjunction::of(&both, later.globals).is_satisfiable()
+                })
+            });
+            if overlaps {
+                warnings.push(AnalysisWarning {
+                    kind: WarningKind::OverlappingRules,
+                    rule: Some(later.rule.name.clone()),
+                    related_rule: Some(earlier.rule.name.clone()),
+                    message: format!(
+                        "rules `{}` and `{}` have different outputs but both match some traffic; `{}` is evaluated first and takes it",
+                        earlier.rule.name, later.rule.name, earlier.rule.name
+                    ),
+                });
+            }
+        }
+    }
+
+    for rule in &program.rules {
+        warnings.extend(unknown_connectors(
+            Some(&rule.name),
+            output_connectors(&rule.output),
+            configured_gateways,
+        ));
+    }
+    warnings.extend(unknown_connectors(
+        None,
+        output_connectors(&program.default_selection),
+        configured_gateways,
+    ));
+
+    warnings
+}
+
+/// The earlier rule that shadows every path of a later one, if together the earlier rules do.
+/// Where several share the work, the one covering the most paths is named, the earliest on a tie.
+fn shadowing_rule<'r>(earlier: &[AnalysedRule<'r>], later_paths: &[&Path<'_>]) -> Option<&'r str> {
+    let mut covered_by: Vec<Option<&'r str>> = vec![None; later_paths.len()];
+    for rule in earlier {
+        let Some(paths) = rule.comparable_paths() else {
+            continue;
+        };
+        for (slot, later_path) in covered_by.iter_mut().zip(later_paths) {
+            if slot.is_none()
+                && paths
+                    .iter()
+                    .any(|path| later_path.summary.implies(&path.summary))
+            {
+                *slot = Some(rule.rule.name.as_str());
+            }
+        }
+    }
+
+    let covering = covered_by.into_iter().collect::<Option<Vec<_>>>()?;
+    let mut best: Option<(&'r str, usize)> = None;
+    for rule in earlier {
+        let name = rule.rule.name.as_str();
+        let count = covering
+            .iter()
+            .filter(|covering| **covering == name)
+            .count();
+        if count > best.map_or(0, |(_, best)| best) {
+            best = Some((name, count));
+        }
+    }
+    best.map(|(name, _)| name)
+}
+
+fn output_connectors(output: &Output) -> Vec<&ConnectorInfo> {
+    match output {
+        Output::Single(connector) => vec![connector],
+        Output::Priority(connectors) => connectors.iter().collect(),
+        Output::VolumeSplit(splits) => splits.iter().map(|split| &split.output).collect(),
+        Output::VolumeSplitPriority(splits) => splits
+            .iter()
+            .flat_map(|split| split.output.iter())
+            .collect(),
+    }
+}
+
+fn unknown_connectors<'a>(
+    rule: Option<&str>,
+    connectors: impl IntoIterator<Item = &'a ConnectorInfo>,
+    configured_gateways: Option<&HashSet<String>>,
+) -> Vec<AnalysisWarning> {
+    let Some(configured) = configured_gateways else {
+        return Vec::new();
+    };
+
+    let mut reported = BTreeSet::new();
+    let mut warnings = Vec::new();
+    for connector in connectors {
+        let gateway = connector.gateway_name.to_uppercase();
+        if configured.contains(&gateway) || !reported.insert(gateway) {
+            continue;
+        }
+        let location = match rule {
+            Some(name) => format!("rule `{name}`"),
+            None => "the default selection".to_string(),
+        };
+        warnings.push(AnalysisWarning {
+            kind: WarningKind::UnknownConnector,
+            rule: rule.map(str::to_string),
+            related_rule: None,
+            message: format!(
+                "{location} routes to `{}`, which has no enabled gateway account for this merchant",
+                connector.gateway_name
+            ),
+        });
+    }
+    warnings
+}
+
+struct AnalysedRule<'a> {
+    rule: &'a Rule,
+    globals: &'a Globals,
+    /// `None` when the rule has more paths than are worth comparing.
+    paths: Option<Vec<Path<'a>>>,
+    /// Statements that end in an empty nested block, which the interpreter never matches.
+    empty_blocks: usize,
+}
+
+struct Path<'a> {
+    terms: Terms<'a>,
+    summary: Conjunction<'a>,
+}
+
+/// A conjunction with its groups resolved: `any` groups are split into separate paths, leaving
+/// plain comparisons and the `not` groups, which are only ever matched structurally.
+#[derive(Debug, Clone, Default)]
+struct Terms<'a> {
+    comparisons: Vec<&'a Comparison>,
+    negations: Vec<&'a IfCondition>,
+}
+
+impl Terms<'_> {
+    fn joined(&self, other: &Self) -> Self {
+        Self {
+            comparisons: [&self.comparisons[..], &other.comparisons[..]].concat(),
+            negations: [&self.negations[..], &other.negations[..]].concat(),
+        }
+    }
+}
+
+impl<'a> AnalysedRule<'a> {
+    fn new(rule: &'a Rule, globals: &'a Globals) -> Self {
+        let mut raw = Vec::new();
+        let mut empty_blocks = 0;
+        let complete = expand(
+            &rule.statements,
+            &Terms::default(),
+            &mut raw,
+            &mut empty_blocks,
+        );
+        let paths = complete.then(|| {
+            raw.into_iter()
+                .map(|terms| Path {
+                    summary: Conjunction::of(&terms, globals),
+                    terms,
+                })
+                .collect()
+        });
+        Self {
+            rule,
+            globals,
+            paths,
+            empty_blocks,
+        }
+    }
+
+    fn contradictions(&self) -> Vec<AnalysisWarning> {
+        let name = &self.rule.name;
+        let mut warnings = Vec::new();
+        if self.empty_blocks > 0 {
+            warnings.push(AnalysisWarning {
+                kind: WarningKind::ContradictoryCondition,
+                rule: Some(name.clone()),
+                related_rule: None,
+                message: format!(
+                    "rule `{name}` has {} statement(s) ending in an empty nested block, which never match",
+                    self.empty_blocks
+                ),
+            });
+        }
+        for path in self.paths.iter().flatten() {
+            if let Some(reason) = path.summary.contradiction() {
+                warnings.push(AnalysisWarning {
+                    kind: WarningKind::ContradictoryCondition,
+                    rule: Some(name.clone()),
+                    related_rule: None,
+                    message: format!("a statement in rule `{name}` can never match: {reason}"),
+                });
+            }
+        }
+        warnings
+    }
+
+    /// The paths that can match something, or `None` if the rule was too large to expand.
+    fn comparable_paths(&self) -> Option<Vec<&Path<'a>>> {
+        self.paths.as_ref().map(|paths| {
+            paths
+                .iter()
+                .filter(|path| path.summary.is_satisfiable())
+                .collect()
+        })
+    }
+}
+
+/// Collects one conjunction per way through `statements`. Returns `false` once the rule has
+/// more paths than [`MAX_PATHS_PER_RULE`].
+fn expand<'a>(
+    statements: &'a [IfStatement],
+    prefix: &Terms<'a>,
+    paths: &mut Vec<Terms<'a>>,
+    empty_blocks: &mut usize,
+) -> bool {
+    for statement in statements {
+        if statement.nested.as_ref().is_some_and(Vec::is_empty) {
+            *empty_blocks += 1;
+            continue;
+        }
+        let Some(alternatives) = alternatives(&statement.condition) else {
+            return false;
+        };
+        for alternative in alternatives {
+            let terms = prefix.joined(&alternative);
+            let complete = match &statement.nested {
+                Some(nested) => expand(nested, &terms, paths, empty_blocks),
+                None => {
+                    paths.push(terms);
+                    paths.len() <= MAX_PATHS_PER_RULE
+                }
+            };
+            if !complete {
+                return false;
+            }
+        }
+    }
+    true
+}
+
+/// The ways `condition` can hold, with every `any` group multiplied out. `None` past
+/// [`MAX_PATHS_PER_RULE`].
+fn alternatives(condition: &IfCondition) -> Option<Vec<Terms<'_>>> {
+    let mut result = vec![Terms::default()];
+    for member in condition {
+        match member {
+            Condition::Comparison(comparison) => {
+                for terms in &mut result {
+                    terms.comparisons.push(comparison);
+                }
+            }
+            Condition::Not { not } => {
+                for terms in &mut result {
+                    terms.negations.push(not);
+                }
+            }
+            Condition::Any { any } => {
+                let mut options = Vec::new();
+                for alternative in any {
+                    options.extend(alternatives(alternative)?);
+                }
+                if result.len().saturating_mul(options.len()) > MAX_PATHS_PER_RULE {
+                    return None;
+                }
+                result = result
+                    .iter()
+                    .flat_map(|terms| options.iter().map(|option| terms.joined(option)))
+                    .collect();
+            }
+        }
+    }
+    Some(result)
+}
+
+/// What a conjunction of comparisons allows, key by key.
+#[derive(Debug, Default)]
+struct Conjunction<'a> {
+    domains: BTreeMap<&'a str, Domain>,
+    /// Comparisons with no summary, only ever matched structurally.
+    opaque: Vec<&'a Comparison>,
+    /// `not` groups, likewise. Taken to exclude nothing when deciding whether the conjunction
+    /// can hold.
+    negations: Vec<&'a IfCondition>,
+    /// A global a comparison refers to that the program does not define. The interpreter treats
+    /// membership in it as false.
+    undefined_global: Option<&'a str>,
+}
+
+#[derive(Debug, Clone)]
+enum Domain {
+    Number(NumberDomain),
+    Enum(SetDomain),
+    Str(SetDomain),
+}
+
+#[derive(Debug, Clone)]
+struct NumberDomain {
+    min: u64,
+    max: u64,
+    only: Option<BTreeSet<u64>>,
+    except: BTreeSet<u64>,
+}
+
+#[derive(Debug, Clone, Default)]
+struct SetDomain {
+    only: Option<BTreeSet<String>>,
+    except: BTreeSet<String>,
+}
+
+/// One comparison, reduced to the constraint it puts on its key.
+enum Constraint {
+    Number(NumberDomain),
+    Enum(SetDomain),
+    Str(SetDomain),
+}
+
+impl<'a> Conjunction<'a> {
+    fn of(terms: &Terms<'a>, globals: &'a Globals) -> Self {
+        let mut conjunction = Self {
+            negations: terms.negations.clone(),
+            ..Self::default()
+        };
+        for &comparison in &terms.comparisons {
+            if let ValueType::GlobalRef(name) = &comparison.value {
+                if comparison.comparison == ComparisonType::Equal && !globals.contains_key(name) {
+                    conjunction.undefined_global.get_or_insert(name.as_str());
+                    continue;
+                }
+            }
+
+            let Some(constraint) = constraint(comparison, globals) else {
+                conjunction.opaque.push(comparison);
+                continue;
+            };
+            let key = comparison.lhs.as_str();
+            let merged = match (conjunction.domains.remove(key), constraint) {
+                (None, Constraint::Number(new)) => Some(Domain::Number(new)),
+                (None, Constraint::Enum(new)) => Some(Domain::Enum(new)),
+                (None, Constraint::Str(new)) => Some(Domain::Str(new)),
+                (Some(Domain::Number(old)), Constraint::Number(new)) => {
+                    Some(Domain::Number(old.intersect(new)))
+                }
+                (Some(Domain::Enum(old)), Constraint::Enum(new)) => {
+                    Some(Domain::Enum(old.intersect(new)))
+                }
+                (Some(Domain::Str(old)), Constraint::Str(new)) => {
+                    Some(Domain::Str(old.intersect(new)))
+                }
+                // One of the two comparisons fails on whatever value the key has; which one
+                // depends on the request, so neither is summarised.
+                (Some(old), _) => {
+                    conjunction.opaque.push(comparison);
+                    Some(old)
+                }
+            };
+            if let Some(domain) = merged {
+                conjunction.domains.insert(key, domain);
+            }
+        }
+        conjunction
+    }
+
+    fn is_satisfiable(&self) -> bool {
+        self.contradiction().is_none()
+    }
+
+    /// Why the conjunction can never hold, if it cannot.
+    fn contradiction(&self) -> Option<String> {
+        if let Some(name) = self.undefined_global {
+            return Some(format!("it refers to `@{name}`, which is not defined"));
+        }
+        self.domains.iter().find_map(|(key, domain)| {
+            domain
+                .is_empty()
+                .then(|| format!("no value of `{key}` satisfies all of its comparisons"))
+        })
+    }
+
+    /// Whether every request this conjunction matches is also matched by `other`.
+    fn implies(&self, other: &Self) -> bool {
+        other.undefined_global.is_none()
+            && other
+                .opaque
+                .iter()
+                .all(|theirs| self.opaque.iter().any(|ours| same_comparison(ours, theirs)))
+            && other.negations.iter().all(|theirs| {
+                self.negations
+                    .iter()
+                    .any(|ours| same_condition(ours, theirs))
+            })
+            && other.domains.iter().all(|(key, theirs)| {
+                self.domains
+                    .get(key)
+                    .is_some_and(|ours| ours.is_subset_of(theirs))
+            })
+    }
+}
+
+fn same_comparison(left: &Comparison, right: &Comparison) -> bool {
+    left.lhs == right.lhs && left.comparison == right.comparison && left.value == right.value
+}
+
+fn same_condition(left: &IfCondition, right: &IfCondition) -> bool {
+    left.len() == right.len()
+        && left.iter().zip(right).all(|pair| match pair {
+            (Condition::Comparison(left), Condition::Comparison(right)) => {
+                same_comparison(left, right)
+            }
+            (Condition::Any { any: left }, Condition::Any { any: right }) => {
+                left.len() == right.len()
+                    && left.iter().zip(right).all(|(l, r)| same_condition(l, r))
+            }
+            (Condition::Not { not: left }, Condition::Not { not: right }) => {
+                same_condition(left, right)
+            }
+            _ => false,
+        })
+}
+
+/// The constraint a comparison puts on its key, for the value/operator pairs the interpreter
+/// accepts. `None` for the rest.
+fn constraint(comparison: &Comparison, globals: &Globals) -> Option<Constraint> {
+    use ComparisonType::{Equal, NotEqual};
+
+    match (&comparison.comparison, &comparison.value) {
+        (op, ValueType::Number(number)) => {
+            Some(Constraint::Number(NumberDomain::any().compare(op, *number)))
+        }
+        (Equal, ValueType::NumberArray(numbers)) => Some(Constraint::Number(NumberDomain {
+            only: Some(numbers.iter().copied().collect()),
+            ..NumberDomain::any()
+        })),
+        (NotEqual, ValueType::NumberArray(numbers)) => Some(Constraint::Number(NumberDomain {
+            except: numbers.iter().copied().collect(),
+            ..NumberDomain::any()
+        })),
+        (Equal, ValueType::NumberComparisonArray(comparisons)) => Some(Constraint::Number(
+            comparisons
+                .iter()
+                .fold(NumberDomain::any(), |domain, comparison| {
+                    domain.compare(&comparison.comparison_type, comparison.number)
+                }),
+        )),
+        (Equal, ValueType::EnumVariant(variant)) => {
+            Some(Constraint::Enum(SetDomain::only([variant.clone()])))
+        }
+        (NotEqual, ValueType::EnumVariant(variant)) => {
+            Some(Constraint::Enum(SetDomain::except([variant.clone()])))
+        }
+        (Equal, ValueType::EnumVariantArray(variants)) => {
+            Some(Constraint::Enum(SetDomain::only(variants.iter().cloned())))
+        }
+        (NotEqual, ValueType::EnumVariantArray(variants)) => Some(Constraint::Enum(
+            SetDomain::except(variants.iter().cloned()),
+        )),
+        (Equal, ValueType::StrValue(text)) => {
+            Some(Constraint::Str(SetDomain::only([text.clone()])))
+        }
+        (NotEqual, ValueType::StrValue(text)) => {
+            Some(Constraint::Str(SetDomain::except([text.clone()])))
+        }
+        (Equal, ValueType::GlobalRef(name)) => global_constraint(globals.get(name)?),
+        _ => None,
+    }
+}
+
+/// Membership in a global, when its values are all of one kind.
+fn global_constraint(values: &HashSet<ValueType>) -> Option<Constraint> {
+    let numbers: Option<BTreeSet<u64>> = values
+        .iter()
+        .map(|value| match value {
+            ValueType::Number(number) => Some(*number),
+            _ => None,
+        })
+        .collect();
+    if let Some(numbers) = numbers {
+        return Some(Constraint::Number(NumberDomain {
+            only: Some(numbers),
+            ..NumberDomain::any()
+        }));
+    }
+
+    let variants: Option<Vec<String>> = values
+        .iter()
+        .map(|value| match value {
+            ValueType::EnumVariant(variant) => Some(variant.clone()),
+            _ => None,
+        })
+        .collect();
+    if let Some(variants) = variants {
+        return Some(Constraint::Enum(SetDomain::only(variants)));
+    }
+
+    let strings: Option<Vec<String>> = values
+        .iter()
+        .map(|value| match value {
+            ValueType::StrValue(text) => Some(text.clone()),
+            _ => None,
+        })
+        .collect();
+    strings.map(|strings| Constraint::Str(SetDomain::only(strings)))
+}
+
+impl Domain {
+    fn is_empty(&self) -> bool {
+        match self {
+            Self::Number(domain) => domain.is_empty(),
+            Self::Enum(domain) | Self::Str(domain) => domain.is_empty(),
+        }
+    }
+
+    fn is_subset_of(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Self::Number(ours), Self::Number(theirs)) => ours.is_subset_of(theirs),
+            (Self::Enum(ours), Self::Enum(theirs)) | (Self::Str(ours), Self::Str(theirs)) => {
+                ours.is_subset_of(theirs)
+            }
+            _ => false,
+        }
+    }
+}
+
+impl NumberDomain {
+    fn any() -> Self {
+        Self {
+            min: 0,
+            max: u64::MAX,
+            only: None,
+            except: BTreeSet::new(),
+        }
+    }
+
+    /// No number at all: `< 0`, or `> u64::MAX`.
+    fn nothing() -> Self {
+        Self {
+            min: 1,
+            max: 0,
+            ..Self::any()
+        }
+    }
+
+    fn compare(mut self, op: &ComparisonType, number: u64) -> Self {
+        match op {
+            ComparisonType::Equal => {
+                return self.intersect(Self {
+                    only: Some(BTreeSet::from([number])),
+                    ..Self::any()
+                })
+            }
+            ComparisonType::NotEqual => {
+                self.except.insert(number);
+            }
+            ComparisonType::LessThan => match number.checked_sub(1) {
+                Some(max) => self.max = self.max.min(max),
+                None => return Self::nothing(),
+            },
+            ComparisonType::LessThanEqual => self.max = self.max.min(number),
+            ComparisonType::GreaterThan => match number.checked_add(1) {
+                Some(min) => self.min = self.min.max(min),
+                None => return Self::nothing(),
+            },
+            ComparisonType::GreaterThanEqual => self.min = self.min.max(number),
+        }
+        self
+    }
+
+    fn intersect(self, other: Self) -> Self {
+        let only = match (self.only, other.only) {
+            (Some(ours), Some(theirs)) => Some(ours.intersection(&theirs).copied().collect()),
+            (ours, theirs) => ours.or(theirs),
+        };
+        Self {
+            min: self.min.max(other.min),
+            max: self.max.min(other.max),
+            only,
+            except: self.except.union(&other.except).copied().collect(),
+        }
+    }
+
+    fn contains(&self, number: u64) -> bool {
+        (self.min..=self.max).contains(&number)
+            && !self.except.contains(&number)
+            && self.only.as_ref().is_none_or(|only| only.contains(&number))
+    }
+
+    fn is_empty(&self) -> bool {
+        if let Some(only) = &self.only {
+            return !only.iter().any(|number| self.contains(*number));
+        }
+        if self.min > self.max {
+            return true;
+        }
+        let width = u128::from(self.max - self.min) + 1;
+        let excluded = self.except.range(self.min..=self.max).count();
+        u128::try_from(excluded).is_ok_and(|excluded| excluded >= width)
+    }
+
+    fn is_subset_of(&self, other: &Self) -> bool {
+        if let Some(only) = &self.only {
+            return only
+                .iter()
+                .filter(|number| self.contains(**number))
+                .all(|number| other.contains(*number));
+        }
+        // An unbounded set of ours cannot fit in a finite set of theirs, short of enumerating
+        // narrow ranges — which no rule written by hand needs.
+        other.only.is_none()
+            && self.min >= other.min
+            && self.max <= other.max
+            && other
+                .except
+                .range(self.min..=self.max)
+                .all(|number| self.except.contains(number))
+    }
+}
+
+impl SetDomain {
+    fn only(values: impl IntoIterator<Item = String>) -> Self {
+        Self {
+            only: Some(values.into_iter().collect()),
+            except: BTreeSet::new(),
+        }
+    }
+
+    fn except(values: impl IntoIterator<Item = String>) -> Self {
+        Self {
+            only: None,
+            except: values.into_iter().collect(),
+        }
+    }
+
+    fn intersect(self, other: Self) -> Self {
+        let only = match (self.only, other.only) {
+            (Some(ours), Some(theirs)) => Some(ours.intersection(&theirs).cloned().collect()),
+            (ours, theirs) => ours.or(theirs),
+        };
+        Self {
+            only,
+            except: self.except.union(&other.except).cloned().collect(),
+        }
+    }
+
+    fn contains(&self, value: &str) -> bool {
+        !self.except.contains(value) && self.only.as_ref().is_none_or(|only| only.contains(value))
+    }
+
+    fn is_empty(&self) -> bool {
+        self.only
+            .as_ref()
+            .is_some_and(|only| only.iter().all(|value| self.except.contains(value)))
+    }
+
+    fn is_subset_of(&self, other: &Self) -> bool {
+        match &self.only {
+            Some(only) => only
+                .iter()
+                .filter(|value| !self.except.contains(*value))
+                .all(|value| other.contains(value)),
+            None => other.only.is_none() && other.except.is_subset(&self.except),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::euclid::dsl::parse_program;
+
+    fn kinds(warnings: &[AnalysisWarning]) -> Vec<(WarningKind, Option<&str>)> {
+        warnings
+            .iter()
+            .map(|warning| (warning.kind, warning.rule.as_deref()))
+            .collect()
+    }
+
+    fn analyze(text: &str) -> Vec<AnalysisWarning> {
+        let program = parse_program(text).expect("test program parses");
+        analyze_program(&program, None)
+    }
+
+    #[test]
+    fn contradictory_comparisons_are_reported() {
+        let warnings = analyze(
+            "big_and_small: stripe { amount > 1000 & amount < 500 }\n\
+             not_card: adyen { payment_method = card & payment_method != card }\n\
+             fine: adyen { amount = (> 10, < 20) }\n\
+             default: checkout",
+        );
+
+        assert_eq!(
+            kinds(&warnings),
+            vec![
+                (WarningKind::ContradictoryCondition, Some("big_and_small")),
+                (WarningKind::ContradictoryCondition, Some("not_card")),
+            ]
+        );
+        assert!(warnings[0].message.contains("`amount`"));
+    }
+
+    #[test]
+    fn a_rule_covered_by_earlier_rules_is_unreachable() {
+        let warnings = analyze(
+            "cards: stripe { payment_method = (card, wallet) }\n\
+             large: adyen { amount > 100 }\n\
+             large_cards: checkout { payment_method = card & amount > 500 }\n\
+             debit_cards: checkout { payment_method = card & card_type = debit }\n\
+             default: checkout",
+        );
+
+        let unreachable: Vec<_> = warnings
+            .iter()
+            .filter(|warning| warning.kind == WarningKind::UnreachableRule)
+            .collect();
+        assert_eq!(unreachable.len(), 2);
+        assert_eq!(unreachable[0].rule.as_deref(), Some("large_cards"));
+        assert_eq!(unreachable[0].related_rule.as_deref(), Some("cards"));
+        assert_eq!(unreachable[1].rule.as_deref(), Some("debit_cards"));
+    }
+
+    #[test]
+    fn a_rule_is_unreachable_only_when_every_statement_is_covered() {
+        let warnings = analyze(
+            "usd: stripe { currency = USD }\n\
+             eur: adyen { currency = EUR }\n\
+             both: checkout { currency = USD\n currency = EUR }\n\
+             usd_or_gbp: checkout { currency = USD\n currency = GBP }\n\
+             default: checkout",
+        );
+
+        let unreachable: Vec<_> = warnings
+            .iter()
+            .filter(|warning| warning.kind == WarningKind::UnreachableRule)
+            .filter_map(|warning| warning.rule.as_deref())
+            .collect();
+        assert_eq!(unreachable, vec!["both"]);
+    }
+
+    #[test]
+    fn unsummarised_comparisons_never_produce_findings() {
+        // Metadata comparisons are not summarised: only the identical comparison shadows one, and
+        // two different ones are never taken to conflict.
+        let warnings = analyze(
+            "tiered: stripe { udf1 = metadata(\"tier\", \"gold\") }\n\
+             tiered_again: stripe { udf1 = metadata(\"tier\", \"gold\") & amount > 5 }\n\
+             other_tier: stripe { udf1 = metadata(\"tier\", \"silver\") }\n\
+             default: checkout",
+        );
+
+        assert_eq!(
+            kinds(&warnings),
+            vec![(WarningKind::UnreachableRule, Some("tiered_again"))]
+        );
+    }
+
+    #[test]
+    fn groups_are_split_into_paths_and_negations_matched_as_written() {
+        let warnings = analyze(
+            "either: stripe { (currency = USD | currency = EUR & amount > 10) }\n\
+             usd: adyen { currency = USD & amount > 5 }\n\
+             eur: adyen { currency = EUR }\n\
+             not_gold: checkout { !(card_type = gold) }\n\
+             not_gold_again: checkout { !(card_type = gold) & amount > 1 }\n\
+             not_silver: checkout { !(card_type = silver) & amount > 1 }\n\
+             default: checkout",
+        );
+
+        let unreachable: Vec<_> = warnings
+            .iter()
+            .filter(|warning| warning.kind == WarningKind::UnreachableRule)
+            .filter_map(|warning| warning.rule.as_deref())
+            .collect();
+        // `eur` still takes euro payments of 10 or less.
+        assert_eq!(unreachable, vec!["usd", "not_gold_again"]);
+    }
+
+    #[test]
+    fn overlapping_rules_with_different_outputs_are_reported() {
+        let warnings = analyze(
+            "cards: stripe { payment_method = card }\n\
+             usd: adyen { currency = USD }\n\
+             eur_wallets: checkout { currency = EUR & payment_method = wallet }\n\
+             usd_too: adyen { amount > 10 & currency = USD & payment_method = upi }\n\
+             default: checkout",
+        );
+
+        let overlaps: Vec<_> = warnings
+            .iter()
+            .filter(|warning| warning.kind == WarningKind::OverlappingRules)
+            .map(|warning| {
+                (
+                    warning.related_rule.as_deref().unwrap_or_default(),
+                    warning.rule.as_deref().unwrap_or_default(),
+                )
+            })
+            .collect();
+        // `eur_wallets` shares no traffic with either earlier rule, and `usd_too` is shadowed by
+        // `usd` outright, which is reported as unreachable instead.
+        assert_eq!(overlaps, vec![("cards", "usd")]);
+    }
+
+    #[test]
+    fn globals_and_empty_blocks_are_understood() {
+        let warnings = analyze(
+            "global eu = (EUR, GBP)\n\
+             eu: stripe { currency = @eu }\n\
+             gbp: adyen { currency = GBP }\n\
+             missing: adyen { currency = @nowhere }\n\
+             hollow: adyen { amount > 5 {} }\n\
+             default: checkout",
+        );
+
+        assert_eq!(
+            kinds(&warnings),
+            vec![
+                (WarningKind::ContradictoryCondition, Some("missing")),
+                (WarningKind::ContradictoryCondition, Some("hollow")),
+                (WarningKind::UnreachableRule, Some("gbp")),
+            ]
+        );
+    }
+
+    #[test]
+    fn connectors_without_a_gateway_account_are_reported() {
+        let program = parse_program(
+            "cards: [stripe, adyen] { payment_method = card }\n\
+             default: [[stripe, paypal]: 50, [checkout]: 50]",
+        )
+        .expect("test program parses");
+        let configured: HashSet<String> = ["STRIPE", "CHECKOUT"]
+            .into_iter()
+            .map(str::to_string)
+            .collect();
+
+        let warnings = analyze_program(&program, Some(&configured));
+        assert_eq!(
+            kinds(&warnings),
+            vec![
+                (WarningKind::UnknownConnector, Some("cards")),
+                (WarningKind::UnknownConnector, None),
+            ]
+        );
+        assert!(warnings[1].message.contains("`paypal`"));
+
+        assert!(analyze_program(&program, None).is_empty());
+    }
+}
@@ -95,13 +95,69 @@ pub struct Comparison {
     pub metadata: Metadata,
 }
 
+/// One member of an [`IfCondition`]: a single comparison, or a group of conditions.
+///
+/// Untagged, so a condition stored before groups existed — a plain list of comparisons — reads
+/// exactly as it always has. It is read by the key it carries rather than by trying each variant
+/// in turn, so an error names the field that is wrong instead of matching no variant.
+#[derive(Clone, Debug, Serialize)]
+#[serde(untagged)]
+pub enum Condition {
+    Comparison(Comparison),
+    /// Holds when any one of the alternatives holds. Each alternative is a conjunction, like the
+    /// condition of a statement.
+    /// eg: (payment.method.network = visa | billing.country = US & amount > 100)
+    Any {
+        any: Vec<IfCondition>,
+    },
+    /// Holds when the grouped conditions do not all hold. A comparison on a key missing from the
+    /// request does not hold, so its negation does.
+    /// eg: !(payment.method.cardtype = credit)
+    Not {
+        not: IfCondition,
+    },
+}
+
+impl<'de> Deserialize<'de> for Condition {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        use serde::de::Error;
+
+        /// A group's members, with any error prefixed by the key that holds them.
+        fn group<T: serde::de::DeserializeOwned, E: Error>(
+            key: &str,
+            value: serde_json::Value,
+        ) -> Result<T, E> {
+            serde_json::from_value(value).map_err(|error| E::custom(format!("{key}: {error}")))
+        }
+
+        let mut fields = serde_json::Map::deserialize(deserializer)?;
+        match (fields.remove("any"), fields.remove("not")) {
+            (Some(_), Some(_)) => Err(D::Error::custom(
+                "a condition groups with either `any` or `not`, not both",
+            )),
+            (Some(any), None) => Ok(Self::Any {
+                any: group("any", any)?,
+            }),
+            (None, Some(not)) => Ok(Self::Not {
+                not: group("not", not)?,
+            }),
+            (None, None) => serde_json::from_value(serde_json::Value::Object(fields))
+                .map(Self::Comparison)
+                .map_err(D::Error::custom),
+        }
+    }
+}
+
 /// Represents all the conditions of an IF statement
 /// eg:
 ///
 /// ```text
 /// payment.method = card & payment.method.cardtype = debit & payment.method.network = diners
 /// ```
-pub type IfCondition = Vec<Comparison>;
+pub type IfCondition = Vec<Condition>;
 
 /// Represents an IF statement with conditions and optional nested IF statements
 ///
@@ -146,7 +202,7 @@ pub struct Rule {
     pub statements: Vec<IfStatement>,
 }
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
 pub enum RoutingType {
     Priority,
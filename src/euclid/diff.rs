@@ -0,0 +1,516 @@
+//! Structural diff between two versions of a routing algorithm.
+//!
+//! Versions are compared as trees, not as the stored JSON, whose key order says nothing about
+//! routing. Rules are matched by name — the nth rule of a name in one version against the nth of
+//! that name in the other — so a rule that was edited, moved, or both is reported against itself
+//! instead of as a removal and an unrelated addition. A rule only counts as moved when it changed
+//! place relative to the rules kept around it: inserting a rule at the top moves nothing.
+//!
+//! Rules, outputs and values are shown in their textual form (see [`super::dsl`]). Comparison and
+//! program metadata are frontend bookkeeping with no bearing on routing, and are not compared.
+
+use std::collections::{BTreeSet, HashMap, HashSet};
+
+use super::{
+    ast::{Globals, Output, Program, Rule},
+    dsl::printer,
+    types::StaticRoutingAlgorithm,
+};
+
+/// One side of a diff: the parts of a stored version that describe what it routes.
+#[derive(Debug, Clone, Copy)]
+pub struct AlgorithmSnapshot<'a> {
+    pub name: &'a str,
+    pub description: &'a str,
+    pub algorithm: &'a StaticRoutingAlgorithm,
+}
+
+/// A single difference between two versions. Rule positions are indexes into the rule list, in
+/// evaluation order, counting from 0.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(tag = "change", rename_all = "snake_case")]
+pub enum AlgorithmChange {
+    Name {
+        before: String,
+        after: String,
+    },
+    Description {
+        before: String,
+        after: String,
+    },
+    /// The algorithm changed kind, or is one with no finer comparison (an A/B test). Both
+    /// definitions are given whole.
+    Replaced {
+        before: serde_json::Value,
+        after: serde_json::Value,
+    },
+    /// The connector selection of a single, priority or volume split algorithm.
+    Output {
+        before: String,
+        after: String,
+    },
+    GlobalAdded {
+        global: String,
+        values: Vec<String>,
+    },
+    GlobalRemoved {
+        global: String,
+        values: Vec<String>,
+    },
+    GlobalChanged {
+        global: String,
+        added: Vec<String>,
+        removed: Vec<String>,
+    },
+    DefaultSelection {
+        before: String,
+        after: String,
+    },
+    RuleAdded {
+        rule: String,
+        position: usize,
+        text: String,
+    },
+    RuleRemoved {
+        rule: String,
+        position: usize,
+        text: String,
+    },
+    RuleModified {
+        rule: String,
+        parts: Vec<RulePart>,
+        before: String,
+        after: String,
+    },
+    RuleMoved {
+        rule: String,
+        from: usize,
+        to: usize,
+    },
+}
+
+/// The part of a rule a [`AlgorithmChange::RuleModified`] touched.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RulePart {
+    RoutingType,
+    Output,
+    Statements,
+}
+
+/// Lists what changed from `before` to `after`: the name and description first, then the
+/// algorithm itself. Empty when the two route identically and read the same.
+pub fn diff_algorithms(
+    before: &AlgorithmSnapshot<'_>,
+    after: &AlgorithmSnapshot<'_>,
+) -> Vec<AlgorithmChange> {
+    let mut changes = Vec::new();
+
+    if before.name != after.name {
+        changes.push(AlgorithmChange::Name {
+            before: before.name.to_string(),
+            after: after.name.to_string(),
+        });
+    }
+    if before.description != after.description {
+        changes.push(AlgorithmChange::Description {
+            before: before.description.to_string(),
+            after: after.description.to_string(),
+        });
+    }
+
+    match (before.algorithm, after.algorithm) {
+        (StaticRoutingAlgorithm::Advanced(before), StaticRoutingAlgorithm::Advanced(after)) => {
+            diff_programs(before, after, &mut changes)
+        }
+        (before, after) => match (selection(before), selection(after)) {
+            (Some(before), Some(after)) => {
+                if before != after {
+                    changes.push(AlgorithmChange::Output {
+                        before: printer::output(&before),
+                        after: printer::output(&after),
+                    });
+                }
+            }
+            _ => {
+                let before = serde_json::to_value(before).unwrap_or_default();
+                let after = serde_json::to_value(after).unwrap_or_default();
+                if before != after {
+                    changes.push(AlgorithmChange::Replaced { before, after });
+                }
+            }
+        },
+    }
+
+    changes
+}
+
+/// The connector selection of an algorithm that is nothing more than one.
+fn selection(algorithm: &StaticRoutingAlgorithm) -> Option<Output> {
+    match algorithm {
+        StaticRoutingAlgorithm::Single(connector) => Some(Output::Single((**connector).clone())),
+        StaticRoutingAlgorithm::Priority(connectors) => Some(Output::Priority(connectors.clone())),
+        StaticRoutingAlgorithm::VolumeSplit(splits) => Some(Output::VolumeSplit(splits.clone())),
+        StaticRoutingAlgorithm::Advanced(_) | StaticRoutingAlgorithm::AbTest(_) => None,
+    }
+}
+
+fn diff_programs(before: &Program, after: &Program, changes: &mut Vec<AlgorithmChange>) {
+    diff_globals(&before.globals, &after.globals, changes);
+
+    if before.default_selection != after.default_selection {
+        changes.push(AlgorithmChange::DefaultSelection {
+            before: printer::output(&before.default_selection),
+            after: printer::output(&after.default_selection),
+        });
+    }
+
+    diff_rules(&before.rules, &after.rules, changes);
+}
+
+fn diff_globals(before: &Globals, after: &Globals, changes: &mut Vec<AlgorithmChange>) {
+    let names: BTreeSet<&String> = before.keys().chain(after.keys()).collect();
+    for name in names {
+        let values = |globals: &Globals| {
+            globals.get(name).map(|values| {
+                values
+                    .iter()
+                    .map(printer::value)
+                    .collect::<BTreeSet<String>>()
+            })
+        };
+
+        match (values(before), values(after)) {
+            (Some(before), Some(after)) => {
+                if before != after {
+                    changes.push(AlgorithmChange::GlobalChanged {
+                        global: name.clone(),
+                        added: after.difference(&before).cloned().collect(),
+                        removed: before.difference(&after).cloned().collect(),
+                    });
+                }
+            }
+            (None, Some(after)) => changes.push(AlgorithmChange::GlobalAdded {
+                global: name.clone(),
+                values: after.into_iter().collect(),
+            }),
+            (Some(before), None) => changes.push(AlgorithmChange::GlobalRemoved {
+                global: name.clone(),
+                values: before.into_iter().collect(),
+            }),
+            (None, None) => {}
+        }
+    }
+}
+
+/// A rule's identity across versions: its name, and which occurrence of that name it is.
+type RuleKey<'a> = (&'a str, usize);
+
+fn rule_keys(rules: &[Rule]) -> Vec<RuleKey<'_>> {
+    let mut seen: HashMap<&str, usize> = HashMap::new();
+    rules
+        .iter()
+        .map(|rule| {
+            let occurrence = seen.entry(rule.name.as_str()).or_default();
+            let key = (rule.name.as_str(), *occurrence);
+            *occurrence += 1;
+            key
+        })
+        .collect()
+}
+
+fn diff_rules(before: &[Rule], after: &[Rule], changes: &mut Vec<AlgorithmChange>) {
+    let before_keys = rule_keys(before);
+    let after_keys = rule_keys(after);
+    let before_positions: HashMap<RuleKey<'_>, usize> = before_keys
+        .iter()
+        .enumerate()
+        .map(|(position, key)| (*key, position))
+        .collect();
+    let after_positions: HashSet<RuleKey<'_>> = after_keys.iter().copied().collect();
+    let in_place = longest_common_subsequence(&before_keys, &after_keys);
+
+    for (position, (rule, key)) in before.iter().zip(&before_keys).enumerate() {
+        if !after_positions.contains(key) {
+            changes.push(AlgorithmChange::RuleRemoved {
+                rule: rule.name.clone(),
+                position,
+                text: printer::print_rule(rule),
+            });
+        }
+    }
+
+    for (position, (rule, key)) in after.iter().zip(&after_keys).enumerate() {
+        let Some(&from) = before_positions.get(key) else {
+            changes.push(AlgorithmChange::RuleAdded {
+                rule: rule.name.clone(),
+                position,
+                text: printer::print_rule(rule),
+            });
+            continue;
+        };
+
+        if !in_place.contains(key) {
+            changes.push(AlgorithmChange::RuleMoved {
+                rule: rule.name.clone(),
+                from,
+                to: position,
+            });
+        }
+
+        if let Some(previous) = before.get(from) {
+            let parts = changed_parts(previous, rule);
+            if !parts.is_empty() {
+                changes.push(AlgorithmChange::RuleModified {
+                    rule: rule.name.clone(),
+                    parts,
+                    before: printer::print_rule(previous),
+                    after: printer::print_rule(rule),
+                });
+            }
+        }
+    }
+}
+
+fn changed_parts(before: &Rule, after: &Rule) -> Vec<RulePart> {
+    let mut parts = Vec::new();
+    if before.routing_type != after.routing_type {
+        parts.push(RulePart::RoutingType);
+    }
+    if before.output != after.output {
+        parts.push(RulePart::Output);
+    }
+    if printer::block(&before.statements) != printer::block(&after.statements) {
+        parts.push(RulePart::Statements);
+    }
+    parts
+}
+
+/// The rules that kept their order relative to each other. Everything else that both versions
+/// share was moved.
+fn longest_common_subsequence<'a>(
+    before: &[RuleKey<'a>],
+    after: &[RuleKey<'a>],
+) -> HashSet<RuleKey<'a>> {
+    // lengths[i][j]: the longest common subsequence of before[i..] and after[j..].
+    let mut lengths = vec![vec![0usize; after.len() + 1]; before.len() + 1];
+    for i in (0..before.len()).rev() {
+        for j in (0..after.len()).rev() {
+            lengths[i][j] = if before[i] == after[j] {
+                lengths[i + 1][j + 1] + 1
+            } else {
+                lengths[i + 1][j].max(lengths[i][j + 1])
+            };
+        }
+    }
+
+    let mut common = HashSet::new();
+    let (mut i, mut j) = (0, 0);
+    while i < before.len() && j < after.len() {
+        if before[i] == after[j] {
+            common.insert(before[i]);
+            i += 1;
+            j += 1;
+        } else if lengths[i + 1][j] >= lengths[i][j + 1] {
+            i += 1;
+        } else {
+            j += 1;
+        }
+    }
+    common
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::euclid::{ast::ConnectorInfo, dsl::parse_program};
+
+    fn advanced(text: &str) -> StaticRoutingAlgorithm {
+        StaticRoutingAlgorithm::Advanced(parse_program(text).expect("test program parses"))
+    }
+
+    fn diff(
+        before: &StaticRoutingAlgorithm,
+        after: &StaticRoutingAlgorithm,
+    ) -> Vec<AlgorithmChange> {
+        let snapshot = |algorithm| AlgorithmSnapshot {
+            name: "rules",
+            description: "",
+            algorithm,
+        };
+        diff_algorithms(&snapshot(before), &snapshot(after))
+    }
+
+    fn connector(name: &str) -> ConnectorInfo {
+        ConnectorInfo {
+            gateway_name: name.to_string(),
+            gateway_id: None,
+        }
+    }
+
+    #[test]
+    fn identical_programs_have_no_changes() {
+        let text = "global bins = (\"411111\", \"422222\")\n\
+                    cards: [stripe, adyen] { payment_method = card & card_bin = @bins }\n\
+                    default: checkout";
+
+        assert_eq!(diff(&advanced(text), &advanced(text)), vec![]);
+    }
+
+    #[test]
+    fn edited_rules_report_the_parts_that_changed() {
+        let changes = diff(
+            &advanced(
+                "global bins = (\"411111\", \"422222\")\n\
+                 cards: [stripe, adyen] { payment_method = card }\n\
+                 large: stripe { amount > 1000 }\n\
+                 default: checkout",
+            ),
+            &advanced(
+                "global bins = (\"411111\", \"433333\")\n\
+                 cards: [adyen, stripe] { payment_method = card }\n\
+                 large: stripe { amount > 5000 }\n\
+                 default: adyen",
+            ),
+        );
+
+        assert_eq!(
+            changes,
+            vec![
+                AlgorithmChange::GlobalChanged {
+                    global: "bins".to_string(),
+                    added: vec!["\"433333\"".to_string()],
+                    removed: vec!["\"422222\"".to_string()],
+                },
+                AlgorithmChange::DefaultSelection {
+                    before: "checkout".to_string(),
+                    after: "adyen".to_string(),
+                },
+                AlgorithmChange::RuleModified {
+                    rule: "cards".to_string(),
+                    parts: vec![RulePart::Output],
+                    before: "cards: [stripe, adyen] {\n    payment_method = card\n}".to_string(),
+                    after: "cards: [adyen, stripe] {\n    payment_method = card\n}".to_string(),
+                },
+                AlgorithmChange::RuleModified {
+                    rule: "large".to_string(),
+                    parts: vec![RulePart::Statements],
+                    before: "large: stripe {\n    amount > 1000\n}".to_string(),
+                    after: "large: stripe {\n    amount > 5000\n}".to_string(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn only_rules_that_left_their_neighbours_are_moved() {
+        let changes = diff(
+            &advanced(
+                "a: stripe { amount > 1 }\n\
+                 b: stripe { amount > 2 }\n\
+                 c: stripe { amount > 3 }\n\
+                 default: checkout",
+            ),
+            &advanced(
+                "new: adyen { amount > 0 }\n\
+                 b: stripe { amount > 2 }\n\
+                 c: stripe { amount > 3 }\n\
+                 a: stripe { amount > 1 }\n\
+                 default: checkout",
+            ),
+        );
+
+        assert_eq!(
+            changes,
+            vec![
+                AlgorithmChange::RuleAdded {
+                    rule: "new".to_string(),
+                    position: 0,
+                    text: "new: adyen {\n    amount > 0\n}".to_string(),
+                },
+                AlgorithmChange::RuleMoved {
+                    rule: "a".to_string(),
+                    from: 0,
+                    to: 3,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn rules_sharing_a_name_are_matched_in_order() {
+        let changes = diff(
+            &advanced(
+                "cards: stripe { payment_method = card }\n\
+                 cards: adyen { payment_method = wallet }\n\
+                 default: checkout",
+            ),
+            &advanced(
+                "cards: stripe { payment_method = card }\n\
+                 default: checkout",
+            ),
+        );
+
+        assert_eq!(
+            changes,
+            vec![AlgorithmChange::RuleRemoved {
+                rule: "cards".to_string(),
+                position: 1,
+                text: "cards: adyen {\n    payment_method = wallet\n}".to_string(),
+            }]
+        );
+    }
+
+    #[test]
+    fn simple_algorithms_compare_their_selection() {
+        let priority =
+            StaticRoutingAlgorithm::Priority(vec![connector("stripe"), connector("adyen")]);
+        let reordered =
+            StaticRoutingAlgorithm::Priority(vec![connector("adyen"), connector("stripe")]);
+
+        assert_eq!(
+            diff(&priority, &reordered),
+            vec![AlgorithmChange::Output {
+                before: "[stripe, adyen]".to_string(),
+                after: "[adyen, stripe]".to_string(),
+            }]
+        );
+
+        let changes = diff(&priority, &advanced("default: stripe"));
+        assert!(matches!(
+            changes.as_slice(),
+            [AlgorithmChange::Replaced { .. }]
+        ));
+    }
+
+    #[test]
+    fn name_and_description_are_compared() {
+        let algorithm = StaticRoutingAlgorithm::Single(Box::new(connector("stripe")));
+        let changes = diff_algorithms(
+            &AlgorithmSnapshot {
+                name: "old",
+                description: "",
+                algorithm: &algorithm,
+            },
+            &AlgorithmSnapshot {
+                name: "new",
+                description: "card traffic",
+                algorithm: &algorithm,
+            },
+        );
+
+        assert_eq!(
+            changes,
+            vec![
+                AlgorithmChange::Name {
+                    before: "old".to_string(),
+                    after: "new".to_string(),
+                },
+                AlgorithmChange::Description {
+                    before: String::new(),
+                    after: "card traffic".to_string(),
+                },
+            ]
+        );
+    }
+}
@@ -0,0 +1,245 @@
+//! The textual form of an advanced routing program.
+//!
+//! A [`Program`] is stored and evaluated as its JSON tree, which is exact but unreadable past a
+//! handful of rules. This is the syntax the doc comments in [`super::ast`] have always sketched,
+//! made real: a parser producing the same AST, and a printer producing text the parser reads back
+//! into an identical tree.
+//!
+//! ```text
+//! global premium_bins = ("411111", "422222")
+//!
+//! card_rule: [stripe, adyen(mca_123)] {
+//!     payment.method = card & amount = (> 500, < 1000) {
+//!         payment.method.cardtype = (credit, debit)
+//!         card_bin = @premium_bins
+//!     }
+//! }
+//!
+//! split_rule: [stripe: 70, adyen: 30] {
+//!     currency != USD
+//! }
+//!
+//! default: [checkout]
+//! ```
+//!
+//! * A rule is `name: output { statements }`. Statements in a block are alternatives, the
+//!   comparisons joined by `&` within one statement must all hold, and a nested block narrows the
+//!   statement it follows — exactly as the interpreter reads the JSON tree.
+//! * Within a condition, `(a | b & c)` holds when either side of the `|` does, and `!(a & b)`
+//!   holds when its contents do not.
+//! * An output is a single connector, a priority list `[a, b]`, a volume split `[a: 70, b: 30]`,
+//!   or a split of priority lists `[[a, b]: 70, [c]: 30]`. A connector may carry its account id
+//!   in parentheses. The routing type follows from the output's shape; one that does not is
+//!   written after the name, `name (volume_split): ...`.
+//! * Values are numbers, bare enum variants, `"strings"`, `@global` references,
+//!   `metadata("key", "value")`, and parenthesised lists of numbers, variants or number
+//!   comparisons. A name a bare word cannot spell is written in backticks: `` `Visa Debit` ``.
+//! * `//` starts a comment that runs to the end of the line.
+//!
+//! Comparison and program metadata are frontend bookkeeping with no textual form; they are empty
+//! on a parsed program and dropped by the printer.
+
+pub mod lexer;
+pub mod parser;
+pub mod printer;
+
+pub use parser::parse_program;
+pub use printer::print_program;
+
+use super::{ast::Program, errors::EuclidErrors};
+
+/// A position in the source text. Both fields count from 1, columns in characters.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize)]
+pub struct Span {
+    pub line: usize,
+    pub column: usize,
+}
+
+/// Why a program did not parse, and where. Returned to the caller as-is, so the dashboard can put
+/// the cursor on the offending token.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, thiserror::Error)]
+#[error("line {line}, column {column}: {message}")]
+pub struct DslError {
+    pub line: usize,
+    pub column: usize,
+    pub message: String,
+}
+
+impl DslError {
+    pub fn at(span: Span, message: impl Into<String>) -> Self {
+        Self {
+            line: span.line,
+            column: span.column,
+            message: message.into(),
+        }
+    }
+}
+
+/// Replaces an `advanced` algorithm written as DSL text with the JSON tree the rest of the service
+/// stores and evaluates. Any other payload is returned untouched.
+///
+/// Done on the raw request ahead of serde, so a syntax error reaches the caller with its line and
+/// column rather than as a type mismatch on `algorithm.data`.
+pub fn expand_text_program(
+    mut payload: serde_json::Value,
+) -> Result<serde_json::Value, EuclidErrors> {
+    let Some(algorithm) = payload.get_mut("algorithm") else {
+        return Ok(payload);
+    };
+    if algorithm.get("type").and_then(serde_json::Value::as_str) != Some("advanced") {
+        return Ok(payload);
+    }
+    let Some(text) = algorithm.get("data").and_then(serde_json::Value::as_str) else {
+        return Ok(payload);
+    };
+
+    let program = parse_program(text).map_err(EuclidErrors::DslParseFailed)?;
+    algorithm["data"] =
+        serde_json::to_value(program).map_err(|_| EuclidErrors::FailedToSerializeJsonToString)?;
+    Ok(payload)
+}
+
+/// Parses `text` for a request that carries a program inline, reporting failure as a Euclid error.
+pub fn parse_inline_program(text: &str) -> Result<Program, EuclidErrors> {
+    parse_program(text).map_err(EuclidErrors::DslParseFailed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::euclid::ast::{ComparisonType, Condition, Output, RoutingType, ValueType};
+
+    const SAMPLE: &str = r#"
+        // Cards go to the card acquirers, split by amount.
+        global premium_bins = ("411111", "422222")
+
+        card_rule: [stripe, adyen(mca_123)] {
+            payment.method = card & amount = (> 500, < 1000) {
+                payment.method.cardtype = (credit, debit)
+                card_bin = @premium_bins
+            }
+            udf1 = metadata("tier", "gold")
+            (card_network = visa | billing_country = US & amount > 10) & !(card_type = prepaid)
+        }
+
+        "Split by weight" (volume_split_priority): [[stripe, adyen]: 70, [checkout]: 30] {
+            currency != USD & amount >= 100 {}
+        }
+
+        default: checkout
+    "#;
+
+    /// `Program` has no `PartialEq` — comparison metadata is arbitrary JSON — so rules are compared
+    /// through their JSON. Globals are compared directly: their sets serialise in hash order.
+    fn assert_same_program(left: &Program, right: &Program) {
+        assert_eq!(left.globals, right.globals);
+        assert_eq!(left.default_selection, right.default_selection);
+        assert_eq!(
+            serde_json::to_value(&left.rules).expect("rules serialise"),
+            serde_json::to_value(&right.rules).expect("rules serialise")
+        );
+    }
+
+    #[test]
+    fn parses_the_documented_shapes() {
+        let program = parse_program(SAMPLE).expect("sample parses");
+
+        assert_eq!(program.rules.len(), 2);
+        assert!(program.globals.contains_key("premium_bins"));
+
+        let card = &program.rules[0];
+        assert_eq!(card.name, "card_rule");
+        assert_eq!(card.routing_type, RoutingType::Priority);
+        assert!(matches!(&card.output, Output::Priority(list) if list.len() == 2));
+        assert_eq!(card.statements.len(), 3);
+
+        let first = &card.statements[0];
+        assert_eq!(first.condition.len(), 2);
+        let Condition::Comparison(amount) = &first.condition[1] else {
+            panic!("expected a comparison, found {:?}", first.condition[1]);
+        };
+        assert_eq!(
+            amount.value,
+            ValueType::NumberComparisonArray(vec![
+                crate::euclid::ast::NumberComparison {
+                    comparison_type: ComparisonType::GreaterThan,
+                    number: 500,
+                },
+                crate::euclid::ast::NumberComparison {
+                    comparison_type: ComparisonType::LessThan,
+                    number: 1000,
+                },
+            ])
+        );
+        assert_eq!(first.nested.as_ref().map(Vec::len), Some(2));
+        assert!(card.statements[1].nested.is_none());
+
+        let grouped = &card.statements[2].condition;
+        assert!(
+            matches!(&grouped[0], Condition::Any { any } if any.len() == 2 && any[1].len() == 2)
+        );
+        assert!(matches!(&grouped[1], Condition::Not { not } if not.len() == 1));
+
+        let split = &program.rules[1];
+        assert_eq!(split.name, "Split by weight");
+        assert_eq!(split.routing_type, RoutingType::VolumeSplitPriority);
+        assert_eq!(split.statements[0].nested.as_ref().map(Vec::len), Some(0));
+
+        assert!(matches!(program.default_selection, Output::Single(_)));
+    }
+
+    #[test]
+    fn printing_round_trips_through_the_parser() {
+        let program = parse_program(SAMPLE).expect("sample parses");
+        let printed = print_program(&program);
+        let reparsed = parse_program(&printed).expect("printed program parses");
+
+        assert_same_program(&program, &reparsed);
+        // Printing is canonical: a second pass changes nothing.
+        assert_eq!(printed, print_program(&reparsed));
+    }
+
+    #[test]
+    fn names_a_bare_word_cannot_spell_survive_the_round_trip() {
+        let text = "default: [`pay pal`(\"acct 1\")]\n\
+                    `global`: [stripe] { card_network = (`Visa Debit`, `3ds`) }";
+        let program = parse_program(text).expect("quoted names parse");
+        let reparsed = parse_program(&print_program(&program)).expect("printed program parses");
+
+        assert_same_program(&program, &reparsed);
+        assert_eq!(program.rules[0].name, "global");
+    }
+
+    #[test]
+    fn errors_carry_the_offending_position() {
+        let err = parse_program("default: stripe\n\nrule: [stripe] {\n    amount >> 10\n}")
+            .expect_err("`>>` is not an operator");
+        assert_eq!((err.line, err.column), (4, 13));
+
+        let err = parse_program("rule: [stripe] { amount = 10 }").expect_err("no default");
+        assert!(err.message.contains("default"), "{}", err.message);
+
+        let err = parse_program("default: stripe\nrule: [stripe] { network = (visa, 10) }")
+            .expect_err("mixed list");
+        assert_eq!(err.line, 2);
+    }
+
+    #[test]
+    fn text_algorithms_are_expanded_in_place() {
+        let payload = serde_json::json!({
+            "name": "r",
+            "created_by": "m",
+            "algorithm": { "type": "advanced", "data": "default: [stripe]" },
+        });
+        let expanded = expand_text_program(payload).expect("text program expands");
+        assert!(expanded["algorithm"]["data"]["default_selection"].is_object());
+
+        let json_payload = serde_json::json!({
+            "algorithm": { "type": "priority", "data": [] },
+        });
+        assert_eq!(
+            expand_text_program(json_payload.clone()).expect("untouched"),
+            json_payload
+        );
+    }
+}
@@ -0,0 +1,286 @@
+use std::{iter::Peekable, str::Chars};
+
+use super::{DslError, Span};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Token {
+    /// A bare word: a key such as `payment.method`, an enum variant, a connector or a rule name.
+    Word(String),
+    /// A backtick-quoted word, for a name a bare word cannot spell.
+    RawWord(String),
+    Number(u64),
+    Str(String),
+    At,
+    Colon,
+    Comma,
+    Amp,
+    Pipe,
+    Bang,
+    LParen,
+    RParen,
+    LBracket,
+    RBracket,
+    LBrace,
+    RBrace,
+    Eq,
+    NotEq,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+impl Token {
+    /// How the token is named in an error message.
+    pub fn describe(&self) -> String {
+        match self {
+            Self::Word(word) | Self::RawWord(word) => format!("`{word}`"),
+            Self::Number(number) => format!("`{number}`"),
+            Self::Str(text) => format!("\"{text}\""),
+            Self::At => "`@`".to_string(),
+            Self::Colon => "`:`".to_string(),
+            Self::Comma => "`,`".to_string(),
+            Self::Amp => "`&`".to_string(),
+            Self::Pipe => "`|`".to_string(),
+            Self::Bang => "`!`".to_string(),
+            Self::LParen => "`(`".to_string(),
+            Self::RParen => "`)`".to_string(),
+            Self::LBracket => "`[`".to_string(),
+            Self::RBracket => "`]`".to_string(),
+            Self::LBrace => "`{`".to_string(),
+            Self::RBrace => "`}`".to_string(),
+            Self::Eq => "`=`".to_string(),
+            Self::NotEq => "`!=`".to_string(),
+            Self::Lt => "`<`".to_string(),
+            Self::Le => "`<=`".to_string(),
+            Self::Gt => "`>`".to_string(),
+            Self::Ge => "`>=`".to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Spanned {
+    pub token: Token,
+    pub span: Span,
+}
+
+/// The tokens of a source text, and the position just past its end — where an error about
+/// missing input points.
+#[derive(Debug, Clone)]
+pub struct Tokens {
+    pub tokens: Vec<Spanned>,
+    pub end: Span,
+}
+
+/// Whether `c` may start a bare word.
+pub fn is_word_start(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+/// Whether `c` may continue a bare word. Dots and hyphens are allowed so that keys such as
+/// `payment.method.cardtype` read as one word.
+pub fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || matches!(c, '_' | '.' | '-')
+}
+
+/// Whether `text` lexes back as a single [`Token::Word`]. All-digit text is a number, not a word.
+pub fn is_plain_word(text: &str) -> bool {
+    let mut chars = text.chars();
+    chars.next().is_some_and(is_word_start)
+        && chars.all(is_word_char)
+        && !text.chars().all(|c| c.is_ascii_digit())
+}
+
+struct Cursor<'a> {
+    chars: Peekable<Chars<'a>>,
+    line: usize,
+    column: usize,
+}
+
+impl Cursor<'_> {
+    fn span(&self) -> Span {
+        Span {
+            line: self.line,
+            column: self.column,
+        }
+    }
+
+    fn peek(&mut self) -> Option<char> {
+        self.chars.peek().copied()
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let c = self.chars.next()?;
+        if c == '\n' {
+            self.line += 1;
+            self.column = 1;
+        } else {
+            self.column += 1;
+        }
+        Some(c)
+    }
+
+    fn bump_if(&mut self, expected: char) -> bool {
+        if self.peek() == Some(expected) {
+            self.bump();
+            true
+        } else {
+            false
+        }
+    }
+}
+
+pub fn tokenize(source: &str) -> Result<Tokens, DslError> {
+    let mut cursor = Cursor {
+        chars: source.chars().peekable(),
+        line: 1,
+        column: 1,
+    };
+    let mut tokens = Vec::new();
+
+    while let Some(c) = cursor.peek() {
+        let span = cursor.span();
+
+        if c.is_whitespace() {
+            cursor.bump();
+            continue;
+        }
+
+        let token = match c {
+            '/' => {
+                cursor.bump();
+                if !cursor.bump_if('/') {
+                    return Err(DslError::at(
+                        span,
+                        "unexpected `/`; comments start with `//`",
+                    ));
+                }
+                while cursor.peek().is_some_and(|c| c != '\n') {
+                    cursor.bump();
+                }
+                continue;
+            }
+            '"' => {
+                cursor.bump();
+                Token::Str(lex_string(&mut cursor, span)?)
+            }
+            '`' => {
+                cursor.bump();
+                let mut word = String::new();
+                loop {
+                    match cursor.bump() {
+                        Some('`') => break,
+                        Some('\n') | None => {
+                            return Err(DslError::at(span, "unterminated backtick name"))
+                        }
+                        Some(c) => word.push(c),
+                    }
+                }
+                if word.is_empty() {
+                    return Err(DslError::at(span, "empty backtick name"));
+                }
+                Token::RawWord(word)
+            }
+            '=' => {
+                cursor.bump();
+                // `==` is accepted as a courtesy; the printer writes `=`.
+                cursor.bump_if('=');
+                Token::Eq
+            }
+            '!' => {
+                cursor.bump();
+                if cursor.bump_if('=') {
+                    Token::NotEq
+                } else {
+                    Token::Bang
+                }
+            }
+            '<' => {
+                cursor.bump();
+                if cursor.bump_if('=') {
+                    Token::Le
+                } else {
+                    Token::Lt
+                }
+            }
+            '>' => {
+                cursor.bump();
+                if cursor.bump_if('=') {
+                    Token::Ge
+                } else {
+                    Token::Gt
+                }
+            }
+            '@' | ':' | ',' | '&' | '|' | '(' | ')' | '[' | ']' | '{' | '}' => {
+                cursor.bump();
+                match c {
+                    '@' => Token::At,
+                    ':' => Token::Colon,
+                    ',' => Token::Comma,
+                    '&' => Token::Amp,
+                    '|' => Token::Pipe,
+                    '(' => Token::LParen,
+                    ')' => Token::RParen,
+                    '[' => Token::LBracket,
+                    ']' => Token::RBracket,
+                    '{' => Token::LBrace,
+                    _ => Token::RBrace,
+                }
+            }
+            c if is_word_start(c) => {
+                let mut word = String::new();
+                while let Some(c) = cursor.peek().filter(|c| is_word_char(*c)) {
+                    word.push(c);
+                    cursor.bump();
+                }
+                if word.chars().all(|c| c.is_ascii_digit()) {
+                    let number = word
+                        .parse::<u64>()
+                        .map_err(|_| DslError::at(span, format!("number `{word}` is too large")))?;
+                    Token::Number(number)
+                } else {
+                    Token::Word(word)
+                }
+            }
+            other => {
+                return Err(DslError::at(
+                    span,
+                    format!("unexpected character `{other}`"),
+                ))
+            }
+        };
+
+        tokens.push(Spanned { token, span });
+    }
+
+    Ok(Tokens {
+        tokens,
+        end: cursor.span(),
+    })
+}
+
+fn lex_string(cursor: &mut Cursor<'_>, start: Span) -> Result<String, DslError> {
+    let mut text = String::new();
+    loop {
+        let escape_span = cursor.span();
+        match cursor.bump() {
+            Some('"') => return Ok(text),
+            Some('\\') => match cursor.bump() {
+                Some('"') => text.push('"'),
+                Some('\\') => text.push('\\'),
+                Some('n') => text.push('\n'),
+                Some('t') => text.push('\t'),
+                Some(other) => {
+                    return Err(DslError::at(
+                        escape_span,
+                        format!("unknown escape `\\{other}` in string"),
+                    ))
+                }
+                None => return Err(DslError::at(start, "unterminated string")),
+            },
+            Some('\n') | None => return Err(DslError::at(start, "unterminated string")),
+            Some(c) => text.push(c),
+        }
+    }
+}
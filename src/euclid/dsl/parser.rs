@@ -0,0 +1,505 @@
+use std::collections::{HashMap, HashSet};
+
+use super::{
+    lexer::{tokenize, Spanned, Token},
+    DslError, Span,
+};
+use crate::euclid::ast::{
+    Comparison, ComparisonType, Condition, ConnectorInfo, Globals, IfCondition, IfStatement,
+    MetadataValue, NumberComparison, Output, Program, RoutingType, Rule, ValueType, VolumeSplit,
+};
+
+/// Parses the textual form of a routing program into its AST. See the [module docs](super) for
+/// the syntax.
+pub fn parse_program(source: &str) -> Result<Program, DslError> {
+    let tokens = tokenize(source)?;
+    Parser {
+        tokens: tokens.tokens,
+        end: tokens.end,
+        pos: 0,
+    }
+    .program()
+}
+
+/// The routing type a rule's output implies. A rule whose type differs is written with it spelled
+/// out, so the printer and parser must agree on this.
+pub fn implied_routing_type(output: &Output) -> RoutingType {
+    match output {
+        Output::Single(_) | Output::Priority(_) => RoutingType::Priority,
+        Output::VolumeSplit(_) => RoutingType::VolumeSplit,
+        Output::VolumeSplitPriority(_) => RoutingType::VolumeSplitPriority,
+    }
+}
+
+struct Parser {
+    tokens: Vec<Spanned>,
+    end: Span,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.peek_at(0)
+    }
+
+    fn peek_at(&self, offset: usize) -> Option<&Token> {
+        self.tokens
+            .get(self.pos + offset)
+            .map(|spanned| &spanned.token)
+    }
+
+    fn span(&self) -> Span {
+        self.tokens
+            .get(self.pos)
+            .map(|spanned| spanned.span)
+            .unwrap_or(self.end)
+    }
+
+    fn eat(&mut self, expected: &Token) -> bool {
+        if self.peek() == Some(expected) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// An error about the current token, naming what was expected in its place.
+    fn unexpected(&self, expected: &str) -> DslError {
+        let found = self
+            .peek()
+            .map(Token::describe)
+            .unwrap_or_else(|| "end of input".to_string());
+        DslError::at(self.span(), format!("expected {expected}, found {found}"))
+    }
+
+    fn expect(&mut self, expected: &Token, description: &str) -> Result<(), DslError> {
+        if self.eat(expected) {
+            Ok(())
+        } else {
+            Err(self.unexpected(description))
+        }
+    }
+
+    fn program(mut self) -> Result<Program, DslError> {
+        let mut globals = Globals::new();
+        let mut default_selection = None;
+        let mut rules = Vec::new();
+
+        while let Some(token) = self.peek() {
+            match (token, self.peek_at(1)) {
+                (Token::Word(word), Some(Token::Word(_) | Token::RawWord(_)))
+                    if word == "global" =>
+                {
+                    let span = self.span();
+                    let (name, values) = self.global()?;
+                    if globals.insert(name.clone(), values).is_some() {
+                        return Err(DslError::at(
+                            span,
+                            format!("global `{name}` is defined twice"),
+                        ));
+                    }
+                }
+                (Token::Word(word), Some(Token::Colon)) if word == "default" => {
+                    let span = self.span();
+                    self.pos += 2;
+                    if default_selection.is_some() {
+                        return Err(DslError::at(span, "the default selection is given twice"));
+                    }
+                    default_selection = Some(self.output()?);
+                }
+                _ => rules.push(self.rule()?),
+            }
+        }
+
+        let default_selection = default_selection.ok_or_else(|| {
+            DslError::at(
+                self.end,
+                "missing the default selection, e.g. `default: [stripe]`",
+            )
+        })?;
+
+        Ok(Program {
+            globals,
+            default_selection,
+            rules,
+            metadata: None,
+        })
+    }
+
+    fn global(&mut self) -> Result<(String, HashSet<ValueType>), DslError> {
+        self.pos += 1;
+        let name = self.word("a global name")?;
+        self.expect(&Token::Eq, "`=`")?;
+        self.expect(&Token::LParen, "`(`")?;
+
+        let mut values = HashSet::new();
+        if !self.eat(&Token::RParen) {
+            loop {
+                values.insert(self.value()?);
+                if !self.eat(&Token::Comma) {
+                    break;
+                }
+            }
+            self.expect(&Token::RParen, "`,` or `)`")?;
+        }
+
+        Ok((name, values))
+    }
+
+    fn rule(&mut self) -> Result<Rule, DslError> {
+        let name = self.text("a rule name, `global` or `default`")?;
+
+        let declared_type = if self.eat(&Token::LParen) {
+            let span = self.span();
+            let routing_type = match self.word("a routing type")?.as_str() {
+                "priority" => RoutingType::Priority,
+                "volume_split" => RoutingType::VolumeSplit,
+                "volume_split_priority" => RoutingType::VolumeSplitPriority,
+                other => {
+                    return Err(DslError::at(
+                        span,
+                        format!(
+                            "unknown routing type `{other}`; expected `priority`, \
+                             `volume_split` or `volume_split_priority`"
+                        ),
+                    ))
+                }
+            };
+            self.expect(&Token::RParen, "`)`")?;
+            Some(routing_type)
+        } else {
+            None
+        };
+
+        self.expect(&Token::Colon, "`:` after the rule name")?;
+        let output = self.output()?;
+        let statements = self.block()?;
+
+        Ok(Rule {
+            name,
+            routing_type: declared_type.unwrap_or_else(|| implied_routing_type(&output)),
+            output,
+            statements,
+        })
+    }
+
+    fn output(&mut self) -> Result<Output, DslError> {
+        if !self.eat(&Token::LBracket) {
+            return Ok(Output::Single(self.connector()?));
+        }
+
+        if self.eat(&Token::RBracket) {
+            return Ok(Output::Priority(Vec::new()));
+        }
+
+        if self.peek() == Some(&Token::LBracket) {
+            let mut splits = Vec::new();
+            loop {
+                self.expect(&Token::LBracket, "`[`")?;
+                let mut connectors = Vec::new();
+                if !self.eat(&Token::RBracket) {
+                    loop {
+                        connectors.push(self.connector()?);
+                        if !self.eat(&Token::Comma) {
+                            break;
+                        }
+                    }
+                    self.expect(&Token::RBracket, "`,` or `]`")?;
+                }
+                self.expect(&Token::Colon, "`:` and a split percentage")?;
+                splits.push(VolumeSplit {
+                    split: self.split()?,
+                    output: connectors,
+                });
+                if !self.eat(&Token::Comma) {
+                    break;
+                }
+            }
+            self.expect(&Token::RBracket, "`,` or `]`")?;
+            return Ok(Output::VolumeSplitPriority(splits));
+        }
+
+        let first = self.connector()?;
+        let output = if self.eat(&Token::Colon) {
+            let mut splits = vec![VolumeSplit {
+                split: self.split()?,
+                output: first,
+            }];
+            while self.eat(&Token::Comma) {
+                let output = self.connector()?;
+                self.expect(&Token::Colon, "`:` and a split percentage")?;
+                splits.push(VolumeSplit {
+                    split: self.split()?,
+                    output,
+                });
+            }
+            Output::VolumeSplit(splits)
+        } else {
+            let mut connectors = vec![first];
+            while self.eat(&Token::Comma) {
+                connectors.push(self.connector()?);
+            }
+            Output::Priority(connectors)
+        };
+        self.expect(&Token::RBracket, "`,` or `]`")?;
+        Ok(output)
+    }
+
+    fn connector(&mut self) -> Result<ConnectorInfo, DslError> {
+        let gateway_name = self.word("a connector name")?;
+        let gateway_id = if self.eat(&Token::LParen) {
+            let id = match self.peek() {
+                Some(Token::Number(id)) => {
+                    let id = id.to_string();
+                    self.pos += 1;
+                    id
+                }
+                _ => self.text("a connector account id")?,
+            };
+            self.expect(&Token::RParen, "`)`")?;
+            Some(id)
+        } else {
+            None
+        };
+        Ok(ConnectorInfo {
+            gateway_name,
+            gateway_id,
+        })
+    }
+
+    fn split(&mut self) -> Result<u8, DslError> {
+        let span = self.span();
+        match self.peek() {
+            Some(Token::Number(number)) => {
+                let split = u8::try_from(*number).map_err(|_| {
+                    DslError::at(span, format!("split `{number}` is not a percentage"))
+                })?;
+                self.pos += 1;
+                Ok(split)
+            }
+            _ => Err(self.unexpected("a split percentage")),
+        }
+    }
+
+    fn block(&mut self) -> Result<Vec<IfStatement>, DslError> {
+        self.expect(&Token::LBrace, "`{`")?;
+        let mut statements = Vec::new();
+        while !self.eat(&Token::RBrace) {
+            if self.peek().is_none() {
+                return Err(self.unexpected("`}`"));
+            }
+            statements.push(self.statement()?);
+        }
+        Ok(statements)
+    }
+
+    fn statement(&mut self) -> Result<IfStatement, DslError> {
+        let condition = self.condition()?;
+        let nested = if self.peek() == Some(&Token::LBrace) {
+            Some(self.block()?)
+        } else {
+            None
+        };
+        Ok(IfStatement { condition, nested })
+    }
+
+    fn condition(&mut self) -> Result<IfCondition, DslError> {
+        let mut condition = vec![self.term()?];
+        while self.eat(&Token::Amp) {
+            condition.push(self.term()?);
+        }
+        Ok(condition)
+    }
+
+    /// A comparison, a group `(a | b & c)`, or a negated group `!(a & b)`.
+    fn term(&mut self) -> Result<Condition, DslError> {
+        if self.eat(&Token::Bang) {
+            self.expect(&Token::LParen, "`(` after `!`")?;
+            let mut alternatives = self.alternatives()?;
+            let not = match alternatives.len() {
+                1 => alternatives.remove(0),
+                _ => vec![Condition::Any { any: alternatives }],
+            };
+            return Ok(Condition::Not { not });
+        }
+        if self.eat(&Token::LParen) {
+            return Ok(Condition::Any {
+                any: self.alternatives()?,
+            });
+        }
+        Ok(Condition::Comparison(self.comparison()?))
+    }
+
+    /// The inside of a group: conditions separated by `|`, up to the closing `)`.
+    fn alternatives(&mut self) -> Result<Vec<IfCondition>, DslError> {
+        let mut alternatives = vec![self.condition()?];
+        while self.eat(&Token::Pipe) {
+            alternatives.push(self.condition()?);
+        }
+        self.expect(&Token::RParen, "`|` or `)`")?;
+        Ok(alternatives)
+    }
+
+    fn comparison(&mut self) -> Result<Comparison, DslError> {
+        let lhs = self.word("a condition key")?;
+        let comparison = self
+            .comparison_type()
+            .ok_or_else(|| self.unexpected("a comparison operator"))?;
+        let value = self.value()?;
+        Ok(Comparison {
+            lhs,
+            comparison,
+            value,
+            metadata: HashMap::new(),
+        })
+    }
+
+    fn comparison_type(&mut self) -> Option<ComparisonType> {
+        let comparison = match self.peek()? {
+            Token::Eq => ComparisonType::Equal,
+            Token::NotEq => ComparisonType::NotEqual,
+            Token::Lt => ComparisonType::LessThan,
+            Token::Le => ComparisonType::LessThanEqual,
+            Token::Gt => ComparisonType::GreaterThan,
+            Token::Ge => ComparisonType::GreaterThanEqual,
+            _ => return None,
+        };
+        self.pos += 1;
+        Some(comparison)
+    }
+
+    fn value(&mut self) -> Result<ValueType, DslError> {
+        let value = match self.peek() {
+            Some(Token::Number(number)) => ValueType::Number(*number),
+            Some(Token::Str(text)) => ValueType::StrValue(text.clone()),
+            Some(Token::At) => {
+                self.pos += 1;
+                return Ok(ValueType::GlobalRef(self.word("a global name after `@`")?));
+            }
+            Some(Token::Word(word))
+                if word == "metadata" && self.peek_at(1) == Some(&Token::LParen) =>
+            {
+                self.pos += 2;
+                let key = self.text("a metadata key")?;
+                self.expect(&Token::Comma, "`,`")?;
+                let value = self.text("a metadata value")?;
+                self.expect(&Token::RParen, "`)`")?;
+                return Ok(ValueType::MetadataVariant(MetadataValue { key, value }));
+            }
+            Some(Token::Word(word) | Token::RawWord(word)) => ValueType::EnumVariant(word.clone()),
+            Some(Token::LParen) => {
+                self.pos += 1;
+                return self.list();
+            }
+            _ => return Err(self.unexpected("a value")),
+        };
+        self.pos += 1;
+        Ok(value)
+    }
+
+    /// The inside of a parenthesised list, whose first element decides what kind of list it is.
+    fn list(&mut self) -> Result<ValueType, DslError> {
+        // `()` has no element to decide by. Read as an empty variant list, which no comparison
+        // matches, so it means the same whichever kind it was written for.
+        if self.eat(&Token::RParen) {
+            return Ok(ValueType::EnumVariantArray(Vec::new()));
+        }
+
+        let value = match self.peek() {
+            Some(Token::Number(_)) => {
+                let mut numbers = Vec::new();
+                loop {
+                    match self.peek() {
+                        Some(Token::Number(number)) => {
+                            numbers.push(*number);
+                            self.pos += 1;
+                        }
+                        _ => return Err(self.mixed_list("numbers")),
+                    }
+                    if !self.eat(&Token::Comma) {
+                        break;
+                    }
+                }
+                ValueType::NumberArray(numbers)
+            }
+            Some(Token::Word(_) | Token::RawWord(_)) => {
+                let mut variants = Vec::new();
+                loop {
+                    match self.peek() {
+                        Some(Token::Word(word) | Token::RawWord(word)) => {
+                            variants.push(word.clone());
+                            self.pos += 1;
+                        }
+                        _ => return Err(self.mixed_list("enum variants")),
+                    }
+                    if !self.eat(&Token::Comma) {
+                        break;
+                    }
+                }
+                ValueType::EnumVariantArray(variants)
+            }
+            _ => {
+                let mut comparisons = Vec::new();
+                loop {
+                    let comparison_type = self.comparison_type().ok_or_else(|| {
+                        self.unexpected("a number, an enum variant or a comparison")
+                    })?;
+                    let number = match self.peek() {
+                        Some(Token::Number(number)) => *number,
+                        _ => return Err(self.unexpected("a number")),
+                    };
+                    self.pos += 1;
+                    comparisons.push(NumberComparison {
+                        comparison_type,
+                        number,
+                    });
+                    if !self.eat(&Token::Comma) {
+                        break;
+                    }
+                }
+                ValueType::NumberComparisonArray(comparisons)
+            }
+        };
+
+        self.expect(&Token::RParen, "`,` or `)`")?;
+        Ok(value)
+    }
+
+    fn mixed_list(&self, kind: &str) -> DslError {
+        match self.peek() {
+            Some(token) => DslError::at(
+                self.span(),
+                format!(
+                    "a list of {kind} cannot also hold {}; lists hold one kind of value",
+                    token.describe()
+                ),
+            ),
+            None => self.unexpected(kind),
+        }
+    }
+
+    /// A bare or backtick-quoted word.
+    fn word(&mut self, expected: &str) -> Result<String, DslError> {
+        match self.peek() {
+            Some(Token::Word(word) | Token::RawWord(word)) => {
+                let word = word.clone();
+                self.pos += 1;
+                Ok(word)
+            }
+            _ => Err(self.unexpected(expected)),
+        }
+    }
+
+    /// A word or a string, where either reads as plain text.
+    fn text(&mut self, expected: &str) -> Result<String, DslError> {
+        match self.peek() {
+            Some(Token::Word(text) | Token::RawWord(text) | Token::Str(text)) => {
+                let text = text.clone();
+                self.pos += 1;
+                Ok(text)
+            }
+            _ => Err(self.unexpected(expected)),
+        }
+    }
+}
@@ -0,0 +1,220 @@
+use std::fmt::Write;
+
+use super::{lexer::is_plain_word, parser::implied_routing_type};
+use crate::euclid::ast::{
+    ComparisonType, Condition, ConnectorInfo, IfCondition, IfStatement, Output, Program,
+    RoutingType, Rule, ValueType,
+};
+
+const INDENT: &str = "    ";
+
+/// Words the parser reads as the start of a declaration when they open an item.
+const KEYWORDS: &[&str] = &["default", "global"];
+
+/// Prints a program in its textual form. The output is canonical — globals and their values in
+/// sorted order, rules in evaluation order, the default selection last — so printing a parsed
+/// program twice gives the same text, and parsing the text gives back the same tree.
+pub fn print_program(program: &Program) -> String {
+    let mut out = String::new();
+
+    let mut globals: Vec<_> = program.globals.iter().collect();
+    globals.sort_by_key(|(name, _)| *name);
+    for (name, values) in globals {
+        let mut values: Vec<String> = values.iter().map(value).collect();
+        values.sort();
+        let _ = writeln!(out, "global {} = ({})\n", word(name), values.join(", "));
+    }
+
+    for rule in &program.rules {
+        write_rule(&mut out, rule);
+        out.push_str("\n\n");
+    }
+
+    let _ = writeln!(out, "default: {}", output(&program.default_selection));
+    out
+}
+
+/// Prints one rule exactly as it appears in [`print_program`].
+pub fn print_rule(rule: &Rule) -> String {
+    let mut out = String::new();
+    write_rule(&mut out, rule);
+    out
+}
+
+fn write_rule(out: &mut String, rule: &Rule) {
+    let name = if is_plain_word(&rule.name) && !KEYWORDS.contains(&rule.name.as_str()) {
+        rule.name.clone()
+    } else {
+        string(&rule.name)
+    };
+    out.push_str(&name);
+
+    if rule.routing_type != implied_routing_type(&rule.output) {
+        let routing_type = match rule.routing_type {
+            RoutingType::Priority => "priority",
+            RoutingType::VolumeSplit => "volume_split",
+            RoutingType::VolumeSplitPriority => "volume_split_priority",
+        };
+        let _ = write!(out, " ({routing_type})");
+    }
+
+    let _ = write!(out, ": {} ", output(&rule.output));
+    print_block(out, &rule.statements, 0);
+}
+
+pub(crate) fn block(statements: &[IfStatement]) -> String {
+    let mut out = String::new();
+    print_block(&mut out, statements, 0);
+    out
+}
+
+fn print_block(out: &mut String, statements: &[IfStatement], depth: usize) {
+    if statements.is_empty() {
+        out.push_str("{}");
+        return;
+    }
+
+    out.push_str("{\n");
+    for statement in statements {
+        out.push_str(&INDENT.repeat(depth + 1));
+        out.push_str(&condition(&statement.condition));
+        if let Some(nested) = &statement.nested {
+            out.push(' ');
+            print_block(out, nested, depth + 1);
+        }
+        out.push('\n');
+    }
+    out.push_str(&INDENT.repeat(depth));
+    out.push('}');
+}
+
+fn condition(members: &IfCondition) -> String {
+    members
+        .iter()
+        .map(|member| match member {
+            Condition::Comparison(comparison) => format!(
+                "{} {} {}",
+                word(&comparison.lhs),
+                operator(&comparison.comparison),
+                value(&comparison.value)
+            ),
+            Condition::Any { any } => format!(
+                "({})",
+                any.iter().map(condition).collect::<Vec<_>>().join(" | ")
+            ),
+            Condition::Not { not } => format!("!({})", condition(not)),
+        })
+        .collect::<Vec<_>>()
+        .join(" & ")
+}
+
+pub(crate) fn output(output: &Output) -> String {
+    match output {
+        Output::Single(connector_info) => connector(connector_info),
+        Output::Priority(connectors) => format!("[{}]", connector_list(connectors)),
+        Output::VolumeSplit(splits) => {
+            let splits: Vec<String> = splits
+                .iter()
+                .map(|split| format!("{}: {}", connector(&split.output), split.split))
+                .collect();
+            format!("[{}]", splits.join(", "))
+        }
+        Output::VolumeSplitPriority(splits) => {
+            let splits: Vec<String> = splits
+                .iter()
+                .map(|split| format!("[{}]: {}", connector_list(&split.output), split.split))
+                .collect();
+            format!("[{}]", splits.join(", "))
+        }
+    }
+}
+
+fn connector_list(connectors: &[ConnectorInfo]) -> String {
+    connectors
+        .iter()
+        .map(connector)
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn connector(connector: &ConnectorInfo) -> String {
+    match &connector.gateway_id {
+        Some(id) if is_plain_word(id) => format!("{}({id})", word(&connector.gateway_name)),
+        Some(id) => format!("{}({})", word(&connector.gateway_name), string(id)),
+        None => word(&connector.gateway_name),
+    }
+}
+
+fn operator(comparison: &ComparisonType) -> &'static str {
+    match comparison {
+        ComparisonType::Equal => "=",
+        ComparisonType::NotEqual => "!=",
+        ComparisonType::LessThan => "<",
+        ComparisonType::LessThanEqual => "<=",
+        ComparisonType::GreaterThan => ">",
+        ComparisonType::GreaterThanEqual => ">=",
+    }
+}
+
+pub(crate) fn value(value: &ValueType) -> String {
+    match value {
+        ValueType::Number(number) => number.to_string(),
+        ValueType::EnumVariant(variant) => word(variant),
+        ValueType::MetadataVariant(metadata) => format!(
+            "metadata({}, {})",
+            string(&metadata.key),
+            string(&metadata.value)
+        ),
+        ValueType::StrValue(text) => string(text),
+        ValueType::GlobalRef(name) => format!("@{}", word(name)),
+        ValueType::NumberArray(numbers) => format!(
+            "({})",
+            numbers
+                .iter()
+                .map(u64::to_string)
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+        ValueType::EnumVariantArray(variants) => format!(
+            "({})",
+            variants
+                .iter()
+                .map(|v| word(v))
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+        ValueType::NumberComparisonArray(comparisons) => format!(
+            "({})",
+            comparisons
+                .iter()
+                .map(|c| format!("{} {}", operator(&c.comparison_type), c.number))
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+    }
+}
+
+/// A name as a bare word where it can be one, and in backticks where it cannot.
+fn word(name: &str) -> String {
+    if is_plain_word(name) {
+        name.to_string()
+    } else {
+        format!("`{name}`")
+    }
+}
+
+fn string(text: &str) -> String {
+    let mut quoted = String::with_capacity(text.len() + 2);
+    quoted.push('"');
+    for c in text.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\t' => quoted.push_str("\\t"),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
@@ -35,6 +35,9 @@ pub enum EuclidErrors {
     #[error("routing_algorithm not found for: {0}")]
     RoutingAlgorithmNotFound(String),
 
+    #[error("routing_algorithm version not found: {0}")]
+    RoutingAlgorithmVersionNotFound(String),
+
     #[error("Routing algorithm not active for merchant: {0}")]
     RoutingAlgorithmNotActive(String),
 
@@ -49,6 +52,9 @@ pub enum EuclidErrors {
 
     #[error("Field validation failed: {0}")]
     FieldValidationFailed(String),
+
+    #[error("Failed to parse routing program: {0}")]
+    DslParseFailed(super::dsl::DslError),
 }
 
 #[derive(Debug, Clone, serde::Serialize)]
@@ -187,6 +193,16 @@ impl axum::response::IntoResponse for EuclidErrors {
             )
                 .into_response(),
 
+            Self::RoutingAlgorithmVersionNotFound(msg) => (
+                hyper::StatusCode::NOT_FOUND,
+                axum::Json(ApiErrorResponse::new(
+                    error_codes::TE_04,
+                    format!("Routing algorithm version not found : {}", msg),
+                    None,
+                )),
+            )
+                .into_response(),
+
             Self::RoutingAlgorithmNotActive(msg) => (
                 hyper::StatusCode::NOT_FOUND,
                 axum::Json(ApiErrorResponse::new(
@@ -245,6 +261,16 @@ impl axum::response::IntoResponse for EuclidErrors {
             )
                 .into_response(),
 
+            Self::DslParseFailed(error) => (
+                hyper::StatusCode::BAD_REQUEST,
+                axum::Json(ApiErrorResponse::new(
+                    error_codes::TE_04,
+                    format!("Failed to parse routing program at {}", error),
+                    serde_json::to_value(&error).ok(),
+                )),
+            )
+                .into_response(),
+
             Self::FieldValidationFailed(msg) => (
                 hyper::StatusCode::BAD_REQUEST,
                 axum::Json(ApiErrorResponse::new(
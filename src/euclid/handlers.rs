@@ -1 +1,4 @@
+pub mod experiment_actions;
+pub mod routing_replay;
 pub mod routing_rules;
+pub mod routing_versions;
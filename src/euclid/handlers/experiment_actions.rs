@@ -0,0 +1,61 @@
+//! Audit trail of the actions the experiment evaluator (see [`crate::experiment_evaluator`])
+//! took on an A/B experiment.
+
+#[cfg(feature = "mysql")]
+use crate::storage::schema::experiment_action::dsl;
+#[cfg(feature = "postgres")]
+use crate::storage::schema_pg::experiment_action::dsl;
+
+use axum::Json;
+use diesel::{associations::HasTable, BoolExpressionMethods, ExpressionMethods};
+use error_stack::ResultExt;
+
+use crate::{
+    app::get_tenant_app_state,
+    error::ContainerError,
+    euclid::{
+        errors::EuclidErrors,
+        types::{ExperimentAction, ExperimentActionRecord, ListExperimentActionsRequest},
+    },
+    metrics::{API_LATENCY_HISTOGRAM, API_REQUEST_COUNTER, API_REQUEST_TOTAL_COUNTER},
+};
+
+/// Lists an experiment's automated actions, oldest first, each with the results it was taken on.
+pub async fn list_experiment_actions(
+    Json(payload): Json<ListExperimentActionsRequest>,
+) -> Result<Json<Vec<ExperimentActionRecord>>, ContainerError<EuclidErrors>> {
+    let timer = API_LATENCY_HISTOGRAM
+        .with_label_values(&["list_experiment_actions"])
+        .start_timer();
+    API_REQUEST_TOTAL_COUNTER
+        .with_label_values(&["list_experiment_actions"])
+        .inc();
+
+    let run = async {
+        let state = get_tenant_app_state().await;
+        let mut actions = crate::generics::generic_find_all::<
+            <ExperimentAction as HasTable>::Table,
+            _,
+            ExperimentAction,
+        >(
+            &state.db,
+            dsl::routing_algorithm_id
+                .eq(payload.routing_algorithm_id.clone())
+                .and(dsl::created_by.eq(payload.created_by.clone())),
+        )
+        .await
+        .change_context(EuclidErrors::StorageError)?;
+        actions.sort_by_key(|action| action.created_at);
+        Ok::<_, ContainerError<EuclidErrors>>(actions)
+    };
+
+    let result = run.await;
+    API_REQUEST_COUNTER
+        .with_label_values(&[
+            "list_experiment_actions",
+            if result.is_ok() { "success" } else { "failure" },
+        ])
+        .inc();
+    timer.observe_duration();
+    Ok(Json(result?.into_iter().map(Into::into).collect()))
+}
@@ -0,0 +1,43 @@
+//! Offline replay of a candidate routing config against a merchant's recorded decisions (see
+//! [`crate::replay`]).
+
+use axum::Json;
+use serde_json::Value;
+
+use crate::{
+    error::ContainerError,
+    euclid::errors::EuclidErrors,
+    metrics::{API_LATENCY_HISTOGRAM, API_REQUEST_COUNTER, API_REQUEST_TOTAL_COUNTER},
+    replay::{RoutingReplayRequest, RoutingReplayResponse},
+};
+
+/// Replays recorded decisions under the candidate and reports what would have changed. Changes
+/// nothing: no scores, no events, no stored algorithms.
+pub async fn replay_routing(
+    Json(payload): Json<Value>,
+) -> Result<Json<RoutingReplayResponse>, ContainerError<EuclidErrors>> {
+    let timer = API_LATENCY_HISTOGRAM
+        .with_label_values(&["replay_routing"])
+        .start_timer();
+    API_REQUEST_TOTAL_COUNTER
+        .with_label_values(&["replay_routing"])
+        .inc();
+
+    let run = async {
+        let payload = crate::euclid::dsl::expand_text_program(payload)?;
+        let request = serde_json::from_value::<RoutingReplayRequest>(payload).map_err(|error| {
+            EuclidErrors::InvalidRequest(format!("could not parse replay request: {error}"))
+        })?;
+        crate::replay::replay(request).await
+    };
+
+    let result = run.await;
+    API_REQUEST_COUNTER
+        .with_label_values(&[
+            "replay_routing",
+            if result.is_ok() { "success" } else { "failure" },
+        ])
+        .inc();
+    timer.observe_duration();
+    Ok(Json(result?))
+}
@@ -3,20 +3,30 @@ use crate::storage::schema::routing_algorithm::dsl;
 #[cfg(feature = "postgres")]
 use crate::storage::schema_pg::routing_algorithm::dsl;
 use crate::{
+    decider::storage::utils::merchant_gateway_account::get_all_enabled_mgas_by_merchant_id,
     error::ApiErrorResponse,
     euclid::{
+        analyzer::{analyze_algorithm, AnalysisWarning},
         ast::{ConnectorInfo, Output, ValueType},
         interpreter::{evaluate_output, InterpreterBackend},
         pm_filter_graph,
+        schedule::{ActivationSchedule, ScheduledActivation, WindowState},
         types::{
             ActivateRoutingConfigRequest, Context, DeactivateRoutingConfigRequest,
-            JsonifiedRoutingAlgorithm, KeyDataType, RoutingAlgorithmMapperNew,
-            RoutingDictionaryRecord, RoutingEvaluateResponse, RoutingRequest, RoutingRule,
-            SrDimensionConfig, StaticRoutingAlgorithm, ELIGIBLE_DIMENSIONS,
+            FormatRoutingProgramRequest, FormatRoutingProgramResponse, JsonifiedRoutingAlgorithm,
+            KeyDataType, RoutingAlgorithmMapperNew, RoutingDictionaryRecord,
+            RoutingEvaluateResponse, RoutingLintRequest, RoutingLintResponse, RoutingRequest,
+            RoutingRule, SrDimensionConfig, StaticRoutingAlgorithm, ELIGIBLE_DIMENSIONS,
         },
-        utils::{generate_random_id, is_valid_enum_value, validate_routing_rule},
+        utils::{
+            generate_random_id, is_valid_enum_value, validate_routing_algorithm,
+            validate_routing_rule, ValidationResult,
+        },
+    },
+    types::{
+        merchant::id::MerchantId,
+        service_configuration::{find_config_by_name, insert_config, update_config},
     },
-    types::service_configuration::{find_config_by_name, insert_config, update_config},
 };
 
 use crate::euclid::{
@@ -24,11 +34,16 @@ use crate::euclid::{
     errors::ValidationErrorDetails,
     types::{RoutingAlgorithmMapper, RoutingAlgorithmMapperUpdate},
 };
-use crate::{euclid::types::RoutingAlgorithm, logger, metrics};
-use axum::{extract::Path, response::IntoResponse, Json};
+use crate::{
+    auth::AuthContext,
+    euclid::types::{RoutingAlgorithm, RoutingAlgorithmVersion, VersionChangeKind},
+    logger, metrics,
+};
+use axum::{extract::Path, response::IntoResponse, Extension, Json};
 use diesel::{associations::HasTable, BoolExpressionMethods, ExpressionMethods};
 use error_stack::ResultExt;
 
+use super::routing_versions;
 use crate::app::get_tenant_app_state;
 
 use crate::error::ContainerError;
@@ -56,7 +71,7 @@ fn routing_algo_cache_key(merchant_id: &str) -> String {
     format!("{}{}", ROUTING_ALGO_CACHE_PREFIX, merchant_id)
 }
 
-async fn cache_routing_algorithm(
+pub(super) async fn cache_routing_algorithm(
     state: &crate::app::TenantAppState,
     merchant_id: &str,
     algorithm: &RoutingAlgorithm,
@@ -72,7 +87,10 @@ async fn cache_routing_algorithm(
     }
 }
 
-async fn invalidate_routing_algorithm_cache(state: &crate::app::TenantAppState, merchant_id: &str) {
+pub(crate) async fn invalidate_routing_algorithm_cache(
+    state: &crate::app::TenantAppState,
+    merchant_id: &str,
+) {
     let key = routing_algo_cache_key(merchant_id);
     if let Err(e) = state.redis_conn.delete_key(&key).await {
         logger::warn!(error = ?e, merchant_id = %merchant_id, "Failed to invalidate routing algorithm cache");
@@ -80,6 +98,7 @@ async fn invalidate_routing_algorithm_cache(state: &crate::app::TenantAppState,
 }
 use serde::Serialize;
 use serde_json::{json, Value};
+use std::collections::HashSet;
 
 #[allow(dead_code)]
 const DEFAULT_FALLBACK_IDENTIFIER: &str = "default_fallback_enabled";
@@ -166,6 +185,20 @@ fn validation_errors_payload(
     serde_json::to_value(ValidationErrorsPayload { validation_errors }).ok()
 }
 
+/// The 400 a rule that failed field validation is refused with, naming each failing field.
+fn field_validation_failed(validation_result: &ValidationResult) -> ContainerError<EuclidErrors> {
+    let detailed_error = validation_result.to_error_message();
+    ContainerError::new_with_status_code_and_payload(
+        EuclidErrors::FieldValidationFailed(detailed_error.clone()),
+        axum::http::StatusCode::BAD_REQUEST,
+        ApiErrorResponse::new(
+            "FIELD_VALIDATION_FAILED",
+            format!("Routing rule validation failed: {}", detailed_error),
+            validation_errors_payload(&validation_result.errors),
+        ),
+    )
+}
+
 pub async fn config_sr_dimensions(
     Json(payload): Json<SrDimensionConfig>,
 ) -> Result<Json<String>, ContainerError<EuclidErrors>> {
@@ -279,8 +312,39 @@ pub async fn get_sr_dimensions(
     Ok(Json(config))
 }
 
+/// Runs static analysis over an algorithm being stored or linted. The findings are returned to the
+/// caller and logged; none of them stops the write.
+async fn analyze_routing_algorithm(
+    merchant_id: &str,
+    algorithm: &StaticRoutingAlgorithm,
+) -> Vec<AnalysisWarning> {
+    let gateways = configured_gateways(merchant_id).await;
+    let warnings = analyze_algorithm(algorithm, gateways.as_ref());
+    if !warnings.is_empty() {
+        logger::info!(
+            merchant_id = %merchant_id,
+            warning_count = warnings.len(),
+            "Static analysis flagged the routing algorithm"
+        );
+    }
+    warnings
+}
+
+/// The gateways the merchant holds enabled accounts for, upper-cased as the accounts store them.
+/// `None` when there are none: a merchant that only uses rule-based routing keeps no gateway
+/// accounts here, and flagging every connector it names would bury the useful warnings.
+async fn configured_gateways(merchant_id: &str) -> Option<HashSet<String>> {
+    let mgas = get_all_enabled_mgas_by_merchant_id(MerchantId(merchant_id.to_string())).await;
+    (!mgas.is_empty()).then(|| {
+        mgas.into_iter()
+            .map(|mga| mga.gateway.to_uppercase())
+            .collect()
+    })
+}
+
 pub async fn routing_create(
     headers: axum::http::HeaderMap,
+    auth: Option<Extension<AuthContext>>,
     Json(payload): Json<Value>,
 ) -> Result<Json<RoutingDictionaryRecord>, ContainerError<EuclidErrors>> {
     let timer = metrics::API_LATENCY_HISTOGRAM
@@ -298,9 +362,13 @@ pub async fn routing_create(
     let global_request_id = crate::analytics::global_request_id_from_headers(&headers);
     let trace_id = crate::analytics::trace_id_from_headers(&headers);
 
+    // An advanced program may arrive as DSL text. It is expanded before serde sees it, so a syntax
+    // error names its line and column instead of failing as a type mismatch on `data`.
+    let rule_payload = crate::euclid::dsl::expand_text_program(payload.clone())?;
+
     // The serde message names the field that did not parse. Dropping it leaves the caller with a
     // bare 400 and nothing to act on.
-    let config: RoutingRule = serde_json::from_value(payload.clone()).map_err(|error| {
+    let mut config: RoutingRule = serde_json::from_value(rule_payload).map_err(|error| {
         error_stack::report!(EuclidErrors::InvalidRequest(format!(
             "could not parse routing rule: {error}"
         )))
@@ -323,22 +391,12 @@ pub async fn routing_create(
                     );
                 }
 
-                let detailed_error = validation_result.to_error_message();
-
                 metrics::API_REQUEST_COUNTER
                     .with_label_values(&["routing_create", "failure"])
                     .inc();
                 timer.observe_duration();
 
-                return Err(ContainerError::new_with_status_code_and_payload(
-                    EuclidErrors::FieldValidationFailed(detailed_error.clone()),
-                    axum::http::StatusCode::BAD_REQUEST,
-                    ApiErrorResponse::new(
-                        "FIELD_VALIDATION_FAILED",
-                        format!("Routing rule validation failed: {}", detailed_error),
-                        validation_errors_payload(&validation_result.errors),
-                    ),
-                ));
+                return Err(field_validation_failed(&validation_result));
             }
             logger::debug!("Routing rule validation passed successfully");
         }
@@ -352,12 +410,17 @@ pub async fn routing_create(
         }
     }
 
+    let warnings = analyze_routing_algorithm(&config.created_by, &config.algorithm).await;
+
     let utc_date_time = time::OffsetDateTime::now_utc();
     let timestamp = time::PrimitiveDateTime::new(utc_date_time.date(), utc_date_time.time());
 
     let algorithm_id = config
         .rule_id
         .unwrap_or_else(|| generate_random_id("routing"));
+    if let StaticRoutingAlgorithm::AbTest(data) = &mut config.algorithm {
+        data.salt_with(algorithm_id.clone());
+    }
 
     let new_algo = RoutingAlgorithm {
         id: algorithm_id.clone(),
@@ -378,12 +441,7 @@ pub async fn routing_create(
         modified_at: timestamp,
     };
 
-    crate::generics::generic_insert(&state.db, new_algo)
-        .await
-        .map_err(|e| {
-            logger::error!("{:?}", e);
-            ContainerError::from(EuclidErrors::StorageError)
-        })?;
+    routing_versions::insert_with_history(&state, new_algo, routing_versions::author(auth)).await?;
 
     let response = RoutingDictionaryRecord::new(
         algorithm_id,
@@ -391,7 +449,8 @@ pub async fn routing_create(
         config.algorithm_for.to_string(),
         timestamp,
         timestamp,
-    );
+    )
+    .with_warnings(warnings);
     logger::debug!("Response: {response:?}");
     crate::analytics::DomainAnalyticsEvent::record_operation(
         crate::analytics::AnalyticsFlowContext::new(
@@ -463,6 +522,66 @@ async fn fetch_algorithm_from_db_and_cache(
     Ok(algorithm)
 }
 
+// Resolves the merchant's active algorithm and parses it. On failure, also returns the preview
+// stage the error is recorded under.
+//
+// Cache hit  : skip both DB queries entirely.
+// Cache miss : run the original 2-query DB path and back-fill the cache.
+async fn fetch_active_algorithm_data(
+    state: &crate::app::TenantAppState,
+    merchant_id: &str,
+) -> Result<(String, StaticRoutingAlgorithm), (ContainerError<EuclidErrors>, &'static str)> {
+    let cache_key = routing_algo_cache_key(merchant_id);
+
+    let algorithm: RoutingAlgorithm = match state
+        .redis_conn
+        .get_key::<CachedRoutingAlgorithm>(&cache_key, "CachedRoutingAlgorithm")
+        .await
+    {
+        Ok(cached) => {
+            logger::debug!(
+                merchant_id = %merchant_id,
+                algorithm_id = %cached.id,
+                "routing_evaluate: cache hit"
+            );
+            RoutingAlgorithm {
+                id: cached.id,
+                created_by: merchant_id.to_string(),
+                name: String::new(),
+                description: String::new(),
+                metadata: None,
+                algorithm_data: cached.algorithm_data,
+                algorithm_for: String::new(),
+                created_at: time::PrimitiveDateTime::MIN,
+                modified_at: time::PrimitiveDateTime::MIN,
+            }
+        }
+        // Cache miss or stale entry — fetch from DB and back-fill
+        Err(_) => fetch_algorithm_from_db_and_cache(state, merchant_id)
+            .await
+            .map_err(|e| (e, "active_routing_lookup_failed"))?,
+    };
+
+    logger::debug!("Fetched routing algorithm: {:?}", algorithm);
+    let algorithm_data: StaticRoutingAlgorithm = serde_json::from_str(&algorithm.algorithm_data)
+        .map_err(|e| {
+            logger::error!(
+                error = ?e,
+                raw_data = %algorithm.algorithm_data,
+                "Failed to parse algorithm_data into StaticRoutingAlgorithm"
+            );
+            (
+                ContainerError::from(EuclidErrors::InvalidRequest(format!(
+                    "Invalid algorithm data format: {}",
+                    e
+                ))),
+                "routing_algorithm_parse_failed",
+            )
+        })?;
+
+    Ok((algorithm.id, algorithm_data))
+}
+
 pub async fn routing_evaluate(
     headers: axum::http::HeaderMap,
     Json(payload): Json<RoutingRequest>,
@@ -580,57 +699,30 @@ pub async fn routing_evaluate(
         }
     }
 
-    // ── Fetch active routing algorithm (Redis cache → DB fallback) ──────────
-    //
-    // Cache hit  : skip both DB queries entirely.
-    // Cache miss : run the original 2-query DB path and back-fill the cache.
-    let cache_key = routing_algo_cache_key(&payload.created_by);
-
-    let algorithm: RoutingAlgorithm = match state
-        .redis_conn
-        .get_key::<CachedRoutingAlgorithm>(&cache_key, "CachedRoutingAlgorithm")
-        .await
-    {
-        Ok(cached) => {
-            logger::debug!(
-                merchant_id = %payload.created_by,
-                algorithm_id = %cached.id,
-                "routing_evaluate: cache hit"
-            );
-            RoutingAlgorithm {
-                id: cached.id,
-                created_by: payload.created_by.clone(),
-                name: String::new(),
-                description: String::new(),
-                metadata: None,
-                algorithm_data: cached.algorithm_data,
-                algorithm_for: String::new(),
-                created_at: time::PrimitiveDateTime::MIN,
-                modified_at: time::PrimitiveDateTime::MIN,
+    // ── Inline program: evaluate the caller's text instead of the active algorithm ──
+    let (algorithm_id, algorithm_data) = if let Some(text) = payload.program.as_deref() {
+        let program = match crate::euclid::dsl::parse_inline_program(text) {
+            Ok(program) => program,
+            Err(e) => return fail_preview(e.into(), "inline_program_parse_failed"),
+        };
+        let algorithm = StaticRoutingAlgorithm::Advanced(program);
+        match validate_routing_algorithm(&algorithm, &state.config.routing_config) {
+            Ok(validation_result) if !validation_result.is_valid => {
+                return fail_preview(
+                    field_validation_failed(&validation_result),
+                    "inline_program_validation_failed",
+                )
             }
+            Ok(_) => {}
+            Err(e) => return fail_preview(e, "inline_program_validation_failed"),
         }
-        Err(_) => {
-            // Cache miss or stale entry — fetch from DB and back-fill
-            match fetch_algorithm_from_db_and_cache(&state, &payload.created_by).await {
-                Ok(algo) => algo,
-                Err(e) => return fail_preview(e, "active_routing_lookup_failed"),
-            }
+        ("inline_program".to_string(), algorithm)
+    } else {
+        match fetch_active_algorithm_data(&state, &payload.created_by).await {
+            Ok(active) => active,
+            Err((e, stage)) => return fail_preview(e, stage),
         }
     };
-
-    logger::debug!("Fetched routing algorithm: {:?}", algorithm);
-    let algorithm_data: StaticRoutingAlgorithm =
-        match serde_json::from_str(&algorithm.algorithm_data).map_err(|e| {
-            logger::error!(
-                error = ?e,
-                raw_data = %algorithm.algorithm_data,
-                "Failed to parse algorithm_data into StaticRoutingAlgorithm"
-            );
-            EuclidErrors::InvalidRequest(format!("Invalid algorithm data format: {}", e))
-        }) {
-            Ok(data) => data,
-            Err(e) => return fail_preview(e.into(), "routing_algorithm_parse_failed"),
-        };
     let mut preview_flow_type =
         crate::analytics::refine_routing_evaluate_flow_type(&algorithm_data);
 
@@ -708,27 +800,34 @@ pub async fn routing_evaluate(
 
             StaticRoutingAlgorithm::AbTest(ab_data) => {
                 let payment_id = payload.payment_id.as_deref().unwrap_or("");
-                let arm = crate::decider::gatewaydecider::ab_test::assign_arm(
+                let unit = crate::decider::gatewaydecider::ab_test::hash_unit_value(
+                    ab_data.hash_unit,
                     payment_id,
-                    ab_data.variant_split_pct,
+                    payload.customer_id.as_deref(),
+                    payload.card_fingerprint.as_deref(),
                 );
-                let arm_algorithm_id = if arm == "variant" {
-                    ab_data.variant_algorithm_id.as_str()
-                } else {
-                    ab_data.control_algorithm_id.as_str()
+                let Some(arm) = crate::decider::gatewaydecider::ab_test::assign_arm(&ab_data, unit)
+                else {
+                    return fail_preview(
+                        EuclidErrors::InvalidRequest(
+                            "A/B test has no arm with a positive weight".to_string(),
+                        )
+                        .into(),
+                        "ab_test_evaluation_failed",
+                    );
                 };
                 logger::debug!(
                     "A/B test routing evaluate: payment_id={:?} arm={} algorithm={}",
                     payload.payment_id,
-                    arm,
-                    arm_algorithm_id
+                    arm.name,
+                    arm.algorithm_id
                 );
-                ab_experiment_id = Some(algorithm.id.clone());
-                ab_variant_arm = Some(arm.to_string());
+                ab_experiment_id = Some(algorithm_id.clone());
+                ab_variant_arm = Some(arm.name.clone());
 
                 let result = crate::decider::gatewaydecider::ab_test::preview::evaluate_arm(
-                    arm,
-                    arm_algorithm_id,
+                    &arm.name,
+                    &arm.algorithm_id,
                     &payload,
                     default_output_present,
                     &state.db,
@@ -939,16 +1038,40 @@ pub async fn activate_routing_rule(
     .await
     .ok();
 
+    // === Step 3: With a schedule, work out which algorithm its window calls for right now ===
+    let (algorithm, schedule) = match payload.schedule {
+        Some(schedule) => {
+            match resolve_schedule(&state, algorithm, schedule, maybe_existing.as_ref()).await {
+                Ok(resolved) => resolved,
+                Err(e) => {
+                    update_failure_metrics();
+                    timer.observe_duration();
+                    return Err(e);
+                }
+            }
+        }
+        None => (algorithm, None),
+    };
+
+    let after = activation_state(&algorithm_for, &algorithm.id, schedule.as_deref());
+
     if let Some(existing) = maybe_existing {
-        if existing.routing_algorithm_id != payload.routing_algorithm_id {
-            // === Step 3a: Update routing_algorithm_id in place ===
+        if existing.routing_algorithm_id != algorithm.id || existing.schedule != schedule {
+            let before = activation_state(
+                &algorithm_for,
+                &existing.routing_algorithm_id,
+                existing.schedule.as_deref(),
+            );
+
+            // === Step 4a: Update routing_algorithm_id in place ===
             let predicate = mapper_dsl::created_by
                 .eq(payload.created_by.clone())
                 .and(mapper_dsl::algorithm_for.eq(algorithm_for.clone()));
 
             let values = RoutingAlgorithmMapperUpdate {
-                routing_algorithm_id: payload.routing_algorithm_id.clone(),
+                routing_algorithm_id: algorithm.id.clone(),
                 algorithm_for: algorithm_for.clone(),
+                schedule,
             };
 
             match crate::generics::generic_update_if_present::<
@@ -960,6 +1083,7 @@ pub async fn activate_routing_rule(
             .change_context(EuclidErrors::StorageError)
             {
                 Ok(_) => {
+                    crate::audit::record_change(&before, &after);
                     cache_routing_algorithm(&state, &payload.created_by, &algorithm).await;
                     API_REQUEST_COUNTER
                         .with_label_values(&["activate_routing_rule", "success"])
@@ -983,19 +1107,18 @@ pub async fn activate_routing_rule(
         return Ok(());
     }
 
-    // === Step 3b: Insert new if not present ===
+    // === Step 4b: Insert new if not present ===
     let merchant_id_for_cache = payload.created_by.clone();
-    let mapper_entry = RoutingAlgorithmMapperNew::new(
-        payload.created_by,
-        payload.routing_algorithm_id,
-        algorithm_for,
-    );
+    let mapper_entry =
+        RoutingAlgorithmMapperNew::new(payload.created_by, algorithm.id.clone(), algorithm_for)
+            .with_schedule(schedule);
 
     match crate::generics::generic_insert(&state.db, mapper_entry)
         .await
         .change_context(EuclidErrors::StorageError)
     {
         Ok(_) => {
+            crate::audit::record_change(&(), &after);
             cache_routing_algorithm(&state, &merchant_id_for_cache, &algorithm).await;
             API_REQUEST_COUNTER
                 .with_label_values(&["activate_routing_rule", "success"])
@@ -1011,6 +1134,95 @@ pub async fn activate_routing_rule(
     }
 }
 
+/// What is active for one `algorithm_for`, as the audit log records an activation.
+pub(crate) fn activation_state(
+    algorithm_for: &str,
+    routing_algorithm_id: &str,
+    schedule: Option<&str>,
+) -> serde_json::Value {
+    serde_json::json!({
+        "algorithm_for": algorithm_for,
+        "routing_algorithm_id": routing_algorithm_id,
+        "schedule": schedule.and_then(|raw| serde_json::from_str::<serde_json::Value>(raw).ok()),
+    })
+}
+
+/// Resolve the schedule on an activation request: validate it, settle its fallback, and pick which
+/// of the two algorithms is active right now. Returns that algorithm with the schedule to store.
+async fn resolve_schedule(
+    state: &crate::app::TenantAppState,
+    scheduled: RoutingAlgorithm,
+    request: ActivationSchedule,
+    existing: Option<&RoutingAlgorithmMapper>,
+) -> Result<(RoutingAlgorithm, Option<String>), ContainerError<EuclidErrors>> {
+    let window = request
+        .window
+        .parse()
+        .map_err(EuclidErrors::InvalidRequest)?
+        .state_at(chrono::Utc::now());
+    if window == WindowState::Ended {
+        return Err(EuclidErrors::InvalidRequest(
+            "The schedule's ends_at has already passed".to_string(),
+        )
+        .into());
+    }
+
+    // Re-scheduling keeps the fallback of the schedule it replaces, rather than whichever of its
+    // two algorithms happens to be active.
+    let fallback_id = request
+        .fallback_algorithm_id
+        .or_else(|| {
+            existing.map(|mapping| {
+                mapping
+                    .schedule
+                    .as_deref()
+                    .and_then(|raw| serde_json::from_str::<ScheduledActivation>(raw).ok())
+                    .map_or_else(
+                        || mapping.routing_algorithm_id.clone(),
+                        |current| current.fallback_algorithm_id,
+                    )
+            })
+        })
+        .ok_or_else(|| {
+            EuclidErrors::InvalidRequest(
+                "fallback_algorithm_id is required when no algorithm is active".to_string(),
+            )
+        })?;
+    if fallback_id == scheduled.id {
+        return Err(EuclidErrors::InvalidRequest(
+            "The fallback is the scheduled algorithm itself; set fallback_algorithm_id".to_string(),
+        )
+        .into());
+    }
+
+    let fallback = crate::generics::generic_find_one_optional::<
+        <RoutingAlgorithm as HasTable>::Table,
+        _,
+        RoutingAlgorithm,
+    >(
+        &state.db,
+        dsl::id
+            .eq(fallback_id.clone())
+            .and(dsl::created_by.eq(scheduled.created_by.clone()))
+            .and(dsl::algorithm_for.eq(scheduled.algorithm_for.clone())),
+    )
+    .await
+    .change_context(EuclidErrors::StorageError)?
+    .ok_or(EuclidErrors::RoutingAlgorithmNotFound(fallback_id))?;
+
+    let stored = ScheduledActivation {
+        scheduled_algorithm_id: scheduled.id.clone(),
+        fallback_algorithm_id: fallback.id.clone(),
+        window: request.window,
+    };
+    let schedule = serde_json::to_string(&stored).map_err(|_| EuclidErrors::StorageError)?;
+    let active = match window {
+        WindowState::Open => scheduled,
+        WindowState::Closed | WindowState::Ended => fallback,
+    };
+    Ok((active, Some(schedule)))
+}
+
 pub async fn deactivate_routing_rule(
     Json(payload): Json<DeactivateRoutingConfigRequest>,
 ) -> Result<(), ContainerError<EuclidErrors>> {
@@ -1149,10 +1361,49 @@ async fn ensure_routing_algorithm_inactive(
     Ok(())
 }
 
+/// Guard for the delete flow: reject deleting an algorithm that a schedule will activate, or fall
+/// back to, later on.
+async fn ensure_routing_algorithm_unscheduled(
+    state: &crate::app::TenantAppState,
+    created_by: &str,
+    routing_algorithm_id: &str,
+) -> Result<(), ContainerError<EuclidErrors>> {
+    let mappings = crate::generics::generic_find_all::<
+        <RoutingAlgorithmMapper as HasTable>::Table,
+        _,
+        RoutingAlgorithmMapper,
+    >(
+        &state.db,
+        mapper_dsl::created_by
+            .eq(created_by.to_string())
+            .and(mapper_dsl::schedule.is_not_null()),
+    )
+    .await
+    .change_context(EuclidErrors::StorageError)?;
+
+    let scheduled = mappings
+        .iter()
+        .filter_map(|mapping| mapping.schedule.as_deref())
+        .filter_map(|raw| serde_json::from_str::<ScheduledActivation>(raw).ok())
+        .any(|schedule| {
+            schedule.scheduled_algorithm_id == routing_algorithm_id
+                || schedule.fallback_algorithm_id == routing_algorithm_id
+        });
+    if scheduled {
+        return Err(EuclidErrors::InvalidRequest(
+            "This algorithm is part of an activation schedule. Replace the schedule before deleting."
+                .to_string(),
+        )
+        .into());
+    }
+    Ok(())
+}
+
 /// Edit an existing inactive routing algorithm in place (name/description/definition). Used by the
 /// A/B Testing dashboard's Edit action. Keeps the same id so history/links remain valid.
 pub async fn update_routing_rule(
-    Json(payload): Json<crate::euclid::types::UpdateRoutingConfigRequest>,
+    auth: Option<Extension<AuthContext>>,
+    Json(mut payload): Json<crate::euclid::types::UpdateRoutingConfigRequest>,
 ) -> Result<Json<RoutingDictionaryRecord>, ContainerError<EuclidErrors>> {
     let timer = API_LATENCY_HISTOGRAM
         .with_label_values(&["update_routing_rule"])
@@ -1168,11 +1419,6 @@ pub async fn update_routing_rule(
 
     let run = async {
         let state = get_tenant_app_state().await;
-        let conn = state
-            .db
-            .get_conn()
-            .await
-            .map_err(|_| EuclidErrors::StorageError)?;
 
         // Only inactive experiments can be edited.
         ensure_routing_algorithm_inactive(
@@ -1199,33 +1445,47 @@ pub async fn update_routing_rule(
             )));
         }
 
+        // An edit keeps the experiment's salt: a new one would move every unit to a fresh arm.
+        // Experiments stored before salting stay unsalted, keeping their original split.
+        if let StaticRoutingAlgorithm::AbTest(data) = &mut payload.algorithm {
+            match serde_json::from_str::<StaticRoutingAlgorithm>(&existing.algorithm_data) {
+                Ok(StaticRoutingAlgorithm::AbTest(stored)) => {
+                    if stored.salt.is_some() {
+                        data.salt = stored.salt;
+                    }
+                }
+                _ => data.salt_with(payload.routing_algorithm_id.clone()),
+            }
+        }
+
         let utc_date_time = time::OffsetDateTime::now_utc();
         let timestamp = time::PrimitiveDateTime::new(utc_date_time.date(), utc_date_time.time());
         let algorithm_data = serde_json::to_string(&payload.algorithm)
             .change_context(EuclidErrors::FailedToSerializeJsonToString)?;
 
-        crate::generics::generic_update::<<RoutingAlgorithm as HasTable>::Table, _, _>(
-            &conn,
-            dsl::id.eq(payload.routing_algorithm_id.clone()),
-            (
-                dsl::name.eq(payload.name.clone()),
-                dsl::description.eq(payload.description.clone()),
-                dsl::algorithm_data.eq(algorithm_data),
-                dsl::modified_at.eq(timestamp),
-            ),
-        )
-        .await
-        .change_context(EuclidErrors::StorageError)?;
+        let updated = RoutingAlgorithm {
+            name: payload.name.clone(),
+            description: payload.description.clone(),
+            algorithm_data,
+            modified_at: timestamp,
+            ..existing.clone()
+        };
+        let version = RoutingAlgorithmVersion::of(&updated, VersionChangeKind::Updated, timestamp)
+            .with_author(routing_versions::author(auth));
+        routing_versions::update_with_history(&state, updated, version).await?;
 
         invalidate_routing_algorithm_cache(&state, &payload.created_by).await;
 
+        let warnings = analyze_routing_algorithm(&payload.created_by, &payload.algorithm).await;
+
         Ok(RoutingDictionaryRecord::new(
             payload.routing_algorithm_id.clone(),
             payload.name.clone(),
             existing.algorithm_for,
             existing.created_at,
             timestamp,
-        ))
+        )
+        .with_warnings(warnings))
     };
 
     match run.await {
@@ -1276,6 +1536,12 @@ pub async fn delete_routing_rule(
             &payload.routing_algorithm_id,
         )
         .await?;
+        ensure_routing_algorithm_unscheduled(
+            &state,
+            &payload.created_by,
+            &payload.routing_algorithm_id,
+        )
+        .await?;
 
         crate::generics::generic_delete::<<RoutingAlgorithm as HasTable>::Table, _>(
             &conn,
@@ -1549,6 +1815,82 @@ pub(crate) fn extract_connectors_for_eligibility(output: &Output) -> Vec<Connect
     connectors
 }
 
+/// Formats a program into its canonical text, from either of its forms. The rule editor uses this
+/// to show a stored program as text, and to check and normalise text as it is written. Nothing is
+/// stored.
+pub async fn format_routing_program(
+    Json(payload): Json<FormatRoutingProgramRequest>,
+) -> Result<Json<FormatRoutingProgramResponse>, ContainerError<EuclidErrors>> {
+    let timer = metrics::API_LATENCY_HISTOGRAM
+        .with_label_values(&["format_routing_program"])
+        .start_timer();
+    metrics::API_REQUEST_TOTAL_COUNTER
+        .with_label_values(&["format_routing_program"])
+        .inc();
+
+    let program = match payload {
+        FormatRoutingProgramRequest::Text { program } => {
+            match crate::euclid::dsl::parse_inline_program(&program) {
+                Ok(program) => program,
+                Err(e) => {
+                    API_REQUEST_COUNTER
+                        .with_label_values(&["format_routing_program", "failure"])
+                        .inc();
+                    timer.observe_duration();
+                    return Err(e.into());
+                }
+            }
+        }
+        FormatRoutingProgramRequest::Tree { program } => program,
+    };
+
+    API_REQUEST_COUNTER
+        .with_label_values(&["format_routing_program", "success"])
+        .inc();
+    timer.observe_duration();
+    Ok(Json(FormatRoutingProgramResponse {
+        text: crate::euclid::dsl::print_program(&program),
+        program,
+    }))
+}
+
+/// Runs the static analysis create and update report over an algorithm, without storing it. The
+/// rule editor calls this as a rule is written, so problems show before anything is saved.
+pub async fn lint_routing_algorithm(
+    Json(payload): Json<Value>,
+) -> Result<Json<RoutingLintResponse>, ContainerError<EuclidErrors>> {
+    let timer = metrics::API_LATENCY_HISTOGRAM
+        .with_label_values(&["lint_routing_algorithm"])
+        .start_timer();
+    metrics::API_REQUEST_TOTAL_COUNTER
+        .with_label_values(&["lint_routing_algorithm"])
+        .inc();
+
+    let request = crate::euclid::dsl::expand_text_program(payload).and_then(|payload| {
+        serde_json::from_value::<RoutingLintRequest>(payload).map_err(|error| {
+            EuclidErrors::InvalidRequest(format!("could not parse routing algorithm: {error}"))
+        })
+    });
+    let request = match request {
+        Ok(request) => request,
+        Err(e) => {
+            API_REQUEST_COUNTER
+                .with_label_values(&["lint_routing_algorithm", "failure"])
+                .inc();
+            timer.observe_duration();
+            return Err(e.into());
+        }
+    };
+
+    let warnings = analyze_routing_algorithm(&request.created_by, &request.algorithm).await;
+
+    API_REQUEST_COUNTER
+        .with_label_values(&["lint_routing_algorithm", "success"])
+        .inc();
+    timer.observe_duration();
+    Ok(Json(RoutingLintResponse { warnings }))
+}
+
 /// GET endpoint to serve routing keys configuration
 /// Returns the routing config with all available keys and their enum values
 /// This allows the dashboard to dynamically fetch valid routing keys
@@ -0,0 +1,557 @@
+//! Version history of routing algorithms.
+//!
+//! Every create, update and rollback appends an immutable [`RoutingAlgorithmVersion`], written in
+//! the same transaction as the change itself, so the history can never disagree with what was
+//! stored. A version records who made the change and what changed since the one before it; any
+//! two can be compared, and any earlier one restored — which also makes the algorithm the
+//! merchant's active one, in that same transaction.
+
+#[cfg(feature = "mysql")]
+use crate::storage::schema::routing_algorithm::dsl;
+#[cfg(feature = "postgres")]
+use crate::storage::schema_pg::routing_algorithm::dsl;
+
+#[cfg(feature = "mysql")]
+use crate::storage::schema::routing_algorithm_mapper::dsl as mapper_dsl;
+#[cfg(feature = "postgres")]
+use crate::storage::schema_pg::routing_algorithm_mapper::dsl as mapper_dsl;
+
+#[cfg(feature = "mysql")]
+use crate::storage::schema::routing_algorithm_version::dsl as version_dsl;
+#[cfg(feature = "postgres")]
+use crate::storage::schema_pg::routing_algorithm_version::dsl as version_dsl;
+
+use async_bb8_diesel::{AsyncConnection, AsyncRunQueryDsl};
+use axum::{Extension, Json};
+use diesel::{
+    associations::HasTable, result::Error as DieselError, BoolExpressionMethods, ExpressionMethods,
+    QueryDsl,
+};
+use error_stack::ResultExt;
+
+use super::routing_rules::{activation_state, cache_routing_algorithm};
+use crate::{
+    app::{get_tenant_app_state, TenantAppState},
+    auth::AuthContext,
+    error::ContainerError,
+    euclid::{
+        diff::{diff_algorithms, AlgorithmChange, AlgorithmSnapshot},
+        errors::EuclidErrors,
+        types::{
+            ActivateRoutingVersionRequest, DiffRoutingVersionsRequest, DiffRoutingVersionsResponse,
+            ListRoutingVersionsRequest, RoutingAlgorithm, RoutingAlgorithmMapper,
+            RoutingAlgorithmMapperNew, RoutingAlgorithmMapperUpdate, RoutingAlgorithmVersion,
+            RoutingVersionRecord, StaticRoutingAlgorithm, VersionChangeKind,
+        },
+    },
+    logger,
+    metrics::{API_LATENCY_HISTOGRAM, API_REQUEST_COUNTER, API_REQUEST_TOTAL_COUNTER},
+};
+
+/// The author to record for a request: `None` when it carries no identity, as with the admin
+/// secret or compat mode.
+pub(crate) fn author(auth: Option<Extension<AuthContext>>) -> Option<String> {
+    auth.map(|Extension(context)| context.actor())
+}
+
+/// Aborts the surrounding transaction. The generic storage helpers report failures as
+/// `MeshError`, which a transaction cannot carry, so the cause is logged here instead.
+pub(crate) fn rollback(error: impl std::fmt::Debug) -> DieselError {
+    logger::error!(error = ?error, "Rolling back routing algorithm write");
+    DieselError::RollbackTransaction
+}
+
+/// Stores a new algorithm together with its first version.
+pub(crate) async fn insert_with_history(
+    state: &TenantAppState,
+    algorithm: RoutingAlgorithm,
+    author: Option<String>,
+) -> Result<(), ContainerError<EuclidErrors>> {
+    let version =
+        RoutingAlgorithmVersion::of(&algorithm, VersionChangeKind::Created, algorithm.created_at)
+            .with_author(author);
+
+    let conn = state
+        .db
+        .get_conn()
+        .await
+        .map_err(|_| EuclidErrors::StorageError)?;
+    conn.transaction_async(|conn| async move {
+        crate::generics::generic_insert_core::<<RoutingAlgorithm as HasTable>::Table, _>(
+            &conn, algorithm,
+        )
+        .await
+        .map_err(rollback)?;
+        crate::generics::generic_insert_core::<<RoutingAlgorithmVersion as HasTable>::Table, _>(
+            &conn, version,
+        )
+        .await
+        .map_err(rollback)?;
+        Ok::<_, DieselError>(())
+    })
+    .await
+    .change_context(EuclidErrors::StorageError)?;
+    Ok(())
+}
+
+/// Replaces the stored definition of `updated.id` and appends `next` to its history, in one
+/// transaction.
+pub(crate) async fn update_with_history(
+    state: &TenantAppState,
+    updated: RoutingAlgorithm,
+    next: RoutingAlgorithmVersion,
+) -> Result<(), ContainerError<EuclidErrors>> {
+    let conn = state
+        .db
+        .get_conn()
+        .await
+        .map_err(|_| EuclidErrors::StorageError)?;
+    conn.transaction_async(|conn| async move {
+        append_version(&conn, next).await?;
+        store_definition(&conn, &updated).await
+    })
+    .await
+    .change_context(EuclidErrors::StorageError)?;
+    Ok(())
+}
+
+#[cfg(feature = "mysql")]
+pub(crate) async fn store_definition(
+    conn: &crate::storage::MysqlPoolConn,
+    algorithm: &RoutingAlgorithm,
+) -> Result<(), DieselError> {
+    crate::generics::generic_update::<<RoutingAlgorithm as HasTable>::Table, _, _>(
+        conn,
+        dsl::id.eq(algorithm.id.clone()),
+        (
+            dsl::name.eq(algorithm.name.clone()),
+            dsl::description.eq(algorithm.description.clone()),
+            dsl::algorithm_data.eq(algorithm.algorithm_data.clone()),
+            dsl::metadata.eq(algorithm.metadata.clone()),
+            dsl::modified_at.eq(algorithm.modified_at),
+        ),
+    )
+    .await
+    .map(|_| ())
+    .map_err(rollback)
+}
+
+#[cfg(feature = "postgres")]
+pub(crate) async fn store_definition(
+    conn: &crate::storage::PgPoolConn,
+    algorithm: &RoutingAlgorithm,
+) -> Result<(), DieselError> {
+    crate::generics::generic_update::<<RoutingAlgorithm as HasTable>::Table, _, _>(
+        conn,
+        dsl::id.eq(algorithm.id.clone()),
+        (
+            dsl::name.eq(algorithm.name.clone()),
+            dsl::description.eq(algorithm.description.clone()),
+            dsl::algorithm_data.eq(algorithm.algorithm_data.clone()),
+            dsl::metadata.eq(algorithm.metadata.clone()),
+            dsl::modified_at.eq(algorithm.modified_at),
+        ),
+    )
+    .await
+    .map(|_| ())
+    .map_err(rollback)
+}
+
+#[cfg(feature = "mysql")]
+type Conn = crate::storage::MysqlPoolConn;
+#[cfg(feature = "postgres")]
+type Conn = crate::storage::PgPoolConn;
+
+/// Numbers `next` after the algorithm's latest version, records what changed since the
+/// definition it replaces, and appends it; returns the rows appended. Call inside the transaction
+/// that writes `next`'s definition: the algorithm's row is locked first, so concurrent writers
+/// number their changes one after another instead of racing for the same version. An algorithm
+/// stored before history was kept has none yet, so its current definition is appended first, as
+/// version 1, to keep it available as a rollback target.
+pub(crate) async fn append_version(
+    conn: &Conn,
+    mut next: RoutingAlgorithmVersion,
+) -> Result<Vec<RoutingAlgorithmVersion>, DieselError> {
+    let current: RoutingAlgorithm = dsl::routing_algorithm
+        .filter(dsl::id.eq(next.routing_algorithm_id.clone()))
+        .for_update()
+        .get_result_async(conn)
+        .await
+        .map_err(rollback)?;
+    let latest: Option<i32> = version_dsl::routing_algorithm_version
+        .filter(version_dsl::routing_algorithm_id.eq(current.id.clone()))
+        .select(diesel::dsl::max(version_dsl::version))
+        .get_result_async(conn)
+        .await
+        .map_err(rollback)?;
+
+    let replaced = baseline(&current);
+    let changes = changes_between(&replaced, &next);
+    next.version = latest.unwrap_or(replaced.version) + 1;
+    let next = next.with_changes(&changes);
+
+    let versions = match latest {
+        Some(_) => vec![next],
+        None => vec![replaced, next],
+    };
+    for version in versions.iter().cloned() {
+        crate::generics::generic_insert_core::<<RoutingAlgorithmVersion as HasTable>::Table, _>(
+            conn, version,
+        )
+        .await
+        .map_err(rollback)?;
+    }
+    Ok(versions)
+}
+
+/// What a rollback replaces, and what it leaves, for the audit log: the algorithm's definition and
+/// the merchant's active algorithm.
+fn rollback_state(
+    algorithm: &RoutingAlgorithm,
+    active: Option<serde_json::Value>,
+) -> serde_json::Value {
+    serde_json::json!({
+        "name": algorithm.name,
+        "description": algorithm.description,
+        "algorithm_data": serde_json::from_str::<serde_json::Value>(&algorithm.algorithm_data)
+            .unwrap_or_else(|_| serde_json::Value::String(algorithm.algorithm_data.clone())),
+        "active": active,
+    })
+}
+
+/// The algorithm's current definition as its first version, for one stored before history was
+/// kept.
+fn baseline(algorithm: &RoutingAlgorithm) -> RoutingAlgorithmVersion {
+    RoutingAlgorithmVersion::of(algorithm, VersionChangeKind::Created, algorithm.modified_at)
+}
+
+/// Every recorded version of an algorithm, oldest first.
+async fn find_versions(
+    state: &TenantAppState,
+    routing_algorithm_id: &str,
+) -> Result<Vec<RoutingAlgorithmVersion>, ContainerError<EuclidErrors>> {
+    let mut versions = crate::generics::generic_find_all::<
+        <RoutingAlgorithmVersion as HasTable>::Table,
+        _,
+        RoutingAlgorithmVersion,
+    >(
+        &state.db,
+        version_dsl::routing_algorithm_id.eq(routing_algorithm_id.to_string()),
+    )
+    .await
+    .change_context(EuclidErrors::StorageError)?;
+    versions.sort_by_key(|version| version.version);
+    Ok(versions)
+}
+
+/// The algorithm's history as the merchant may see it. One stored before history was kept lists
+/// its current definition as version 1, which its next change records for real.
+async fn load_history(
+    state: &TenantAppState,
+    created_by: &str,
+    routing_algorithm_id: &str,
+) -> Result<Vec<RoutingAlgorithmVersion>, ContainerError<EuclidErrors>> {
+    let ensure_owner = |owner: &str| {
+        if owner == created_by {
+            Ok(())
+        } else {
+            Err(ContainerError::from(EuclidErrors::InvalidRequest(
+                "Routing algorithm does not belong to this merchant".to_string(),
+            )))
+        }
+    };
+
+    let versions = find_versions(state, routing_algorithm_id).await?;
+    match versions.first() {
+        Some(first) => {
+            ensure_owner(&first.created_by)?;
+            Ok(versions)
+        }
+        None => {
+            let algorithm = find_algorithm(state, routing_algorithm_id).await?;
+            ensure_owner(&algorithm.created_by)?;
+            Ok(vec![baseline(&algorithm)])
+        }
+    }
+}
+
+async fn find_algorithm(
+    state: &TenantAppState,
+    routing_algorithm_id: &str,
+) -> Result<RoutingAlgorithm, ContainerError<EuclidErrors>> {
+    Ok(crate::generics::generic_find_one::<
+        <RoutingAlgorithm as HasTable>::Table,
+        _,
+        RoutingAlgorithm,
+    >(&state.db, dsl::id.eq(routing_algorithm_id.to_string()))
+    .await
+    .change_context(EuclidErrors::RoutingAlgorithmNotFound(
+        routing_algorithm_id.to_string(),
+    ))?)
+}
+
+fn find_version(
+    versions: &[RoutingAlgorithmVersion],
+    routing_algorithm_id: &str,
+    version: i32,
+) -> Result<RoutingAlgorithmVersion, ContainerError<EuclidErrors>> {
+    versions
+        .iter()
+        .find(|candidate| candidate.version == version)
+        .cloned()
+        .ok_or_else(|| {
+            ContainerError::from(EuclidErrors::RoutingAlgorithmVersionNotFound(format!(
+                "{routing_algorithm_id} v{version}"
+            )))
+        })
+}
+
+/// What changed between two stored definitions. One that no longer parses — written by an older
+/// build — can only be compared whole.
+fn changes_between(
+    before: &RoutingAlgorithmVersion,
+    after: &RoutingAlgorithmVersion,
+) -> Vec<AlgorithmChange> {
+    let parse = |data: &str| serde_json::from_str::<StaticRoutingAlgorithm>(data);
+    match (parse(&before.algorithm_data), parse(&after.algorithm_data)) {
+        (Ok(before_algorithm), Ok(after_algorithm)) => diff_algorithms(
+            &AlgorithmSnapshot {
+                name: &before.name,
+                description: &before.description,
+                algorithm: &before_algorithm,
+            },
+            &AlgorithmSnapshot {
+                name: &after.name,
+                description: &after.description,
+                algorithm: &after_algorithm,
+            },
+        ),
+        _ if before.algorithm_data == after.algorithm_data => Vec::new(),
+        _ => {
+            let value = |data: &str| serde_json::from_str(data).unwrap_or(serde_json::Value::Null);
+            vec![AlgorithmChange::Replaced {
+                before: value(&before.algorithm_data),
+                after: value(&after.algorithm_data),
+            }]
+        }
+    }
+}
+
+/// Lists an algorithm's versions, oldest first, each with what changed from the one before.
+pub async fn list_routing_versions(
+    Json(payload): Json<ListRoutingVersionsRequest>,
+) -> Result<Json<Vec<RoutingVersionRecord>>, ContainerError<EuclidErrors>> {
+    let timer = API_LATENCY_HISTOGRAM
+        .with_label_values(&["list_routing_versions"])
+        .start_timer();
+    API_REQUEST_TOTAL_COUNTER
+        .with_label_values(&["list_routing_versions"])
+        .inc();
+
+    let run = async {
+        let state = get_tenant_app_state().await;
+        load_history(&state, &payload.created_by, &payload.routing_algorithm_id).await
+    };
+
+    let result: Result<_, ContainerError<EuclidErrors>> = run.await;
+    API_REQUEST_COUNTER
+        .with_label_values(&[
+            "list_routing_versions",
+            if result.is_ok() { "success" } else { "failure" },
+        ])
+        .inc();
+    timer.observe_duration();
+    Ok(Json(result?.into_iter().map(Into::into).collect()))
+}
+
+/// Compares any two versions of an algorithm, in either order.
+pub async fn diff_routing_versions(
+    Json(payload): Json<DiffRoutingVersionsRequest>,
+) -> Result<Json<DiffRoutingVersionsResponse>, ContainerError<EuclidErrors>> {
+    let timer = API_LATENCY_HISTOGRAM
+        .with_label_values(&["diff_routing_versions"])
+        .start_timer();
+    API_REQUEST_TOTAL_COUNTER
+        .with_label_values(&["diff_routing_versions"])
+        .inc();
+
+    let run = async {
+        let state = get_tenant_app_state().await;
+        let versions =
+            load_history(&state, &payload.created_by, &payload.routing_algorithm_id).await?;
+        let from = find_version(
+            &versions,
+            &payload.routing_algorithm_id,
+            payload.from_version,
+        )?;
+        let to = find_version(&versions, &payload.routing_algorithm_id, payload.to_version)?;
+
+        Ok(DiffRoutingVersionsResponse {
+            routing_algorithm_id: payload.routing_algorithm_id.clone(),
+            from_version: payload.from_version,
+            to_version: payload.to_version,
+            changes: changes_between(&from, &to),
+        })
+    };
+
+    let result: Result<_, ContainerError<EuclidErrors>> = run.await;
+    API_REQUEST_COUNTER
+        .with_label_values(&[
+            "diff_routing_versions",
+            if result.is_ok() { "success" } else { "failure" },
+        ])
+        .inc();
+    timer.observe_duration();
+    result.map(Json)
+}
+
+/// Rolls an algorithm back to an earlier version and makes it the merchant's active algorithm.
+///
+/// The restored definition is written over the algorithm's row, recorded as a new version naming
+/// the one it came from, and mapped as active — all in one transaction, so a failure part-way
+/// leaves both the definition and the active algorithm as they were. The restored version keeps
+/// history linear: nothing after it is discarded, and the rollback can itself be rolled back.
+pub async fn activate_routing_version(
+    auth: Option<Extension<AuthContext>>,
+    Json(payload): Json<ActivateRoutingVersionRequest>,
+) -> Result<Json<RoutingVersionRecord>, ContainerError<EuclidErrors>> {
+    let timer = API_LATENCY_HISTOGRAM
+        .with_label_values(&["activate_routing_version"])
+        .start_timer();
+    API_REQUEST_TOTAL_COUNTER
+        .with_label_values(&["activate_routing_version"])
+        .inc();
+
+    let run = async {
+        let state = get_tenant_app_state().await;
+        let versions =
+            load_history(&state, &payload.created_by, &payload.routing_algorithm_id).await?;
+        let target = find_version(&versions, &payload.routing_algorithm_id, payload.version)?;
+        // A deleted algorithm keeps its history, but there is no row left to restore into.
+        let current = find_algorithm(&state, &payload.routing_algorithm_id).await?;
+
+        let utc_date_time = time::OffsetDateTime::now_utc();
+        let timestamp = time::PrimitiveDateTime::new(utc_date_time.date(), utc_date_time.time());
+        let restored = RoutingAlgorithm {
+            name: target.name,
+            description: target.description,
+            algorithm_data: target.algorithm_data,
+            metadata: target.metadata,
+            modified_at: timestamp,
+            ..current.clone()
+        };
+
+        let mut next =
+            RoutingAlgorithmVersion::of(&restored, VersionChangeKind::Restored, timestamp)
+                .with_author(author(auth));
+        next.restored_from = Some(payload.version);
+
+        let active = crate::generics::generic_find_one_optional::<
+            <RoutingAlgorithmMapper as HasTable>::Table,
+            _,
+            RoutingAlgorithmMapper,
+        >(
+            &state.db,
+            mapper_dsl::created_by
+                .eq(restored.created_by.clone())
+                .and(mapper_dsl::algorithm_for.eq(restored.algorithm_for.clone())),
+        )
+        .await
+        .change_context(EuclidErrors::StorageError)?;
+        let before = rollback_state(
+            &current,
+            active.as_ref().map(|mapping| {
+                activation_state(
+                    &mapping.algorithm_for,
+                    &mapping.routing_algorithm_id,
+                    mapping.schedule.as_deref(),
+                )
+            }),
+        );
+
+        let conn = state
+            .db
+            .get_conn()
+            .await
+            .map_err(|_| EuclidErrors::StorageError)?;
+        let written = restored.clone();
+        let appended = conn
+            .transaction_async(|conn| async move {
+                let appended = append_version(&conn, next).await?;
+                store_definition(&conn, &written).await?;
+                match active {
+                    Some(mapping) => {
+                        crate::generics::generic_update_if_present::<
+                            <RoutingAlgorithmMapper as HasTable>::Table,
+                            _,
+                            _,
+                        >(
+                            &conn,
+                            mapper_dsl::id.eq(mapping.id),
+                            RoutingAlgorithmMapperUpdate {
+                                routing_algorithm_id: written.id.clone(),
+                                algorithm_for: written.algorithm_for.clone(),
+                                schedule: None,
+                            },
+                        )
+                        .await
+                        .map_err(rollback)?;
+                    }
+                    None => {
+                        crate::generics::generic_insert_core::<
+                            <RoutingAlgorithmMapper as HasTable>::Table,
+                            _,
+                        >(
+                            &conn,
+                            RoutingAlgorithmMapperNew::new(
+                                written.created_by.clone(),
+                                written.id.clone(),
+                                written.algorithm_for.clone(),
+                            ),
+                        )
+                        .await
+                        .map_err(rollback)?;
+                    }
+                }
+                Ok::<_, DieselError>(appended)
+            })
+            .await
+            .change_context(EuclidErrors::StorageError)?;
+        let record = appended
+            .last()
+            .cloned()
+            .map(RoutingVersionRecord::from)
+            .ok_or(EuclidErrors::StorageError)?;
+
+        crate::audit::record_change(
+            &before,
+            &rollback_state(
+                &restored,
+                Some(activation_state(
+                    &restored.algorithm_for,
+                    &restored.id,
+                    None,
+                )),
+            ),
+        );
+        cache_routing_algorithm(&state, &restored.created_by, &restored).await;
+        logger::info!(
+            merchant_id = %restored.created_by,
+            routing_algorithm_id = %restored.id,
+            restored_from = payload.version,
+            "Restored routing algorithm version"
+        );
+
+        Ok(record)
+    };
+
+    let result: Result<_, ContainerError<EuclidErrors>> = run.await;
+    API_REQUEST_COUNTER
+        .with_label_values(&[
+            "activate_routing_version",
+            if result.is_ok() { "success" } else { "failure" },
+        ])
+        .inc();
+    timer.observe_duration();
+    result.map(Json)
+}
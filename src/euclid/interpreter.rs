@@ -99,13 +99,34 @@ impl InterpreterBackend {
         }
     }
 
+    fn eval_condition(
+        condition: &ast::Condition,
+        ctx: &types::Context,
+        globals: &ast::Globals,
+    ) -> Result<bool, types::InterpreterError> {
+        match condition {
+            ast::Condition::Comparison(comparison) => {
+                Self::eval_comparison(comparison, ctx, globals)
+            }
+            ast::Condition::Any { any } => {
+                for alternative in any {
+                    if Self::eval_if_condition(alternative, ctx, globals)? {
+                        return Ok(true);
+                    }
+                }
+                Ok(false)
+            }
+            ast::Condition::Not { not } => Ok(!Self::eval_if_condition(not, ctx, globals)?),
+        }
+    }
+
     fn eval_if_condition(
         condition: &ast::IfCondition,
         ctx: &types::Context,
         globals: &ast::Globals,
     ) -> Result<bool, types::InterpreterError> {
-        for comparison in condition {
-            let res = Self::eval_comparison(comparison, ctx, globals)?;
+        for member in condition {
+            let res = Self::eval_condition(member, ctx, globals)?;
 
             if !res {
                 return Ok(false);
@@ -0,0 +1,427 @@
+//! Scheduled activation windows for routing algorithms.
+//!
+//! A schedule keeps one algorithm active inside a window and a fallback outside it, so a merchant
+//! can switch to a holiday rule set at midnight and back a week later without anyone calling
+//! `/routing/activate` at that moment. Windows are written in the merchant's local time, named
+//! either by an IANA time zone, whose daylight-saving changes the window follows, or by a fixed
+//! UTC offset, which never changes.
+//!
+//! This module only decides whether a window is open. The background job that flips the active
+//! algorithm lives in [`crate::routing_scheduler`].
+
+use chrono::{DateTime, Datelike, FixedOffset, NaiveDateTime, NaiveTime, Utc, Weekday};
+use chrono_tz::Tz;
+use serde::{Deserialize, Serialize};
+
+/// When a scheduled algorithm is active. Stored on the mapper exactly as submitted, and re-parsed
+/// on every evaluation. All times are local to `time_zone`, or to `utc_offset` without one.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ActivationWindow {
+    /// An IANA name such as `Europe/London`. Local times are resolved for each date, so the window
+    /// moves with daylight saving. Takes the place of `utc_offset`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub time_zone: Option<String>,
+    /// `+HH:MM` or `-HH:MM`, when no `time_zone` is given. Defaults to UTC.
+    #[serde(default = "default_utc_offset")]
+    pub utc_offset: String,
+    /// `YYYY-MM-DDTHH:MM[:SS]`. The window never opens before this.
+    #[serde(default)]
+    pub starts_at: Option<String>,
+    /// `YYYY-MM-DDTHH:MM[:SS]`, exclusive. Once reached the schedule has ended, and the fallback
+    /// stays active for good.
+    #[serde(default)]
+    pub ends_at: Option<String>,
+    /// Restricts the window to certain hours of certain days, e.g. weekdays 09:00–18:00.
+    #[serde(default)]
+    pub recurring: Option<RecurringWindow>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RecurringWindow {
+    /// Days the window opens on, as `mon`..`sun` or full day names.
+    pub days: Vec<String>,
+    /// `HH:MM` the window opens at.
+    pub from: String,
+    /// `HH:MM` the window closes at, exclusive. Earlier than `from` means the window runs past
+    /// midnight and closes the next day.
+    pub until: String,
+}
+
+fn default_utc_offset() -> String {
+    "+00:00".to_string()
+}
+
+/// The optional `schedule` of `/routing/activate`: the requested algorithm is active only inside
+/// `window`, and `fallback_algorithm_id` outside it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ActivationSchedule {
+    /// Defaults to the algorithm active when the schedule is set.
+    #[serde(default)]
+    pub fallback_algorithm_id: Option<String>,
+    #[serde(flatten)]
+    pub window: ActivationWindow,
+}
+
+/// A schedule as stored on the merchant's mapper row.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ScheduledActivation {
+    /// Active while the window is open.
+    pub scheduled_algorithm_id: String,
+    /// Active while the window is closed, and once it has ended.
+    pub fallback_algorithm_id: String,
+    pub window: ActivationWindow,
+}
+
+impl ScheduledActivation {
+    /// The algorithm that should be active while the window is in `state`.
+    pub fn algorithm_id(&self, state: WindowState) -> &str {
+        match state {
+            WindowState::Open => &self.scheduled_algorithm_id,
+            WindowState::Closed | WindowState::Ended => &self.fallback_algorithm_id,
+        }
+    }
+}
+
+/// Where a window stands at a given instant.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, strum::Display, Serialize)]
+#[serde(rename_all = "snake_case")]
+#[strum(serialize_all = "snake_case")]
+pub enum WindowState {
+    Open,
+    Closed,
+    /// Past `ends_at`: the window will not open again.
+    Ended,
+}
+
+/// A validated [`ActivationWindow`].
+#[derive(Debug, Clone)]
+pub struct ParsedWindow {
+    zone: Zone,
+    starts_at: Option<NaiveDateTime>,
+    ends_at: Option<NaiveDateTime>,
+    recurring: Option<Recurrence>,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Zone {
+    Named(Tz),
+    Fixed(FixedOffset),
+}
+
+#[derive(Debug, Clone)]
+struct Recurrence {
+    days: Vec<Weekday>,
+    from: NaiveTime,
+    until: NaiveTime,
+}
+
+impl ActivationWindow {
+    /// Validate the window. Errors name the offending field, for the caller to report as-is.
+    pub fn parse(&self) -> Result<ParsedWindow, String> {
+        let zone = match self.time_zone.as_deref() {
+            Some(name) => Zone::Named(parse_time_zone(name)?),
+            None => Zone::Fixed(parse_offset(&self.utc_offset)?),
+        };
+        let starts_at = self
+            .starts_at
+            .as_deref()
+            .map(|value| parse_datetime("starts_at", value))
+            .transpose()?;
+        let ends_at = self
+            .ends_at
+            .as_deref()
+            .map(|value| parse_datetime("ends_at", value))
+            .transpose()?;
+        if let (Some(start), Some(end)) = (starts_at, ends_at) {
+            if end <= start {
+                return Err("ends_at must be after starts_at".to_string());
+            }
+        }
+        let recurring = self
+            .recurring
+            .as_ref()
+            .map(RecurringWindow::parse)
+            .transpose()?;
+        if starts_at.is_none() && ends_at.is_none() && recurring.is_none() {
+            return Err(
+                "a schedule needs at least one of starts_at, ends_at or recurring".to_string(),
+            );
+        }
+
+        Ok(ParsedWindow {
+            zone,
+            starts_at,
+            ends_at,
+            recurring,
+        })
+    }
+}
+
+impl RecurringWindow {
+    fn parse(&self) -> Result<Recurrence, String> {
+        if self.days.is_empty() {
+            return Err("recurring.days must name at least one day".to_string());
+        }
+        let days = self
+            .days
+            .iter()
+            .map(|day| {
+                day.parse::<Weekday>()
+                    .map_err(|_| format!("recurring.days: unknown day '{day}'"))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let from = parse_time("recurring.from", &self.from)?;
+        let until = parse_time("recurring.until", &self.until)?;
+        if from == until {
+            return Err("recurring.from and recurring.until must differ".to_string());
+        }
+
+        Ok(Recurrence { days, from, until })
+    }
+}
+
+impl ParsedWindow {
+    pub fn state_at(&self, now: DateTime<Utc>) -> WindowState {
+        // The local reading of this instant, which for a named zone carries whatever offset is in
+        // force on its date.
+        let local = match self.zone {
+            Zone::Named(tz) => now.with_timezone(&tz).naive_local(),
+            Zone::Fixed(offset) => now.with_timezone(&offset).naive_local(),
+        };
+        if self.ends_at.is_some_and(|end| local >= end) {
+            return WindowState::Ended;
+        }
+        let started = self.starts_at.is_none_or(|start| local >= start);
+        let in_recurrence = self
+            .recurring
+            .as_ref()
+            .is_none_or(|recurrence| recurrence.contains(local));
+        if started && in_recurrence {
+            WindowState::Open
+        } else {
+            WindowState::Closed
+        }
+    }
+}
+
+impl Recurrence {
+    fn contains(&self, local: NaiveDateTime) -> bool {
+        let time = local.time();
+        let weekday = local.weekday();
+        if self.from < self.until {
+            return self.days.contains(&weekday) && time >= self.from && time < self.until;
+        }
+        // Runs past midnight: late on an opening day, or early on the day after one.
+        (self.days.contains(&weekday) && time >= self.from)
+            || (self.days.contains(&weekday.pred()) && time < self.until)
+    }
+}
+
+fn parse_time_zone(value: &str) -> Result<Tz, String> {
+    value.parse::<Tz>().map_err(|_| {
+        format!("time_zone: expected an IANA name such as Europe/London, got '{value}'")
+    })
+}
+
+fn parse_offset(value: &str) -> Result<FixedOffset, String> {
+    let invalid = || format!("utc_offset: expected +HH:MM or -HH:MM, got '{value}'");
+    let sign = match value.chars().next() {
+        Some('+') => 1,
+        Some('-') => -1,
+        _ => return Err(invalid()),
+    };
+    let (hours, minutes) = value[1..].split_once(':').ok_or_else(invalid)?;
+    let hours: i32 = hours.parse().map_err(|_| invalid())?;
+    let minutes: i32 = minutes.parse().map_err(|_| invalid())?;
+    if !(0..=14).contains(&hours) || !(0..60).contains(&minutes) {
+        return Err(invalid());
+    }
+    FixedOffset::east_opt(sign * (hours * 3600 + minutes * 60)).ok_or_else(invalid)
+}
+
+fn parse_datetime(field: &str, value: &str) -> Result<NaiveDateTime, String> {
+    ["%Y-%m-%dT%H:%M:%S", "%Y-%m-%dT%H:%M"]
+        .iter()
+        .find_map(|format| NaiveDateTime::parse_from_str(value, format).ok())
+        .ok_or_else(|| format!("{field}: expected YYYY-MM-DDTHH:MM, got '{value}'"))
+}
+
+fn parse_time(field: &str, value: &str) -> Result<NaiveTime, String> {
+    NaiveTime::parse_from_str(value, "%H:%M")
+        .map_err(|_| format!("{field}: expected HH:MM, got '{value}'"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn utc(value: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(value)
+            .expect("valid timestamp")
+            .with_timezone(&Utc)
+    }
+
+    fn window(json: serde_json::Value) -> ParsedWindow {
+        serde_json::from_value::<ActivationWindow>(json)
+            .expect("valid window")
+            .parse()
+            .expect("parsable window")
+    }
+
+    #[test]
+    fn one_off_window_opens_and_ends_in_local_time() {
+        let holiday = window(serde_json::json!({
+            "utc_offset": "+05:30",
+            "starts_at": "2026-12-20T00:00",
+            "ends_at": "2026-12-27T00:00",
+        }));
+
+        // 23:59 local on the 19th.
+        assert_eq!(
+            holiday.state_at(utc("2026-12-19T18:29:00Z")),
+            WindowState::Closed
+        );
+        // Midnight local on the 20th.
+        assert_eq!(
+            holiday.state_at(utc("2026-12-19T18:30:00Z")),
+            WindowState::Open
+        );
+        assert_eq!(
+            holiday.state_at(utc("2026-12-26T18:30:00Z")),
+            WindowState::Ended
+        );
+    }
+
+    #[test]
+    fn named_zone_follows_daylight_saving() {
+        let mornings = window(serde_json::json!({
+            "time_zone": "Europe/London",
+            "recurring": { "days": ["mon"], "from": "09:00", "until": "10:00" },
+        }));
+
+        // Monday 2026-10-19, on BST (UTC+1).
+        assert_eq!(
+            mornings.state_at(utc("2026-10-19T08:00:00Z")),
+            WindowState::Open
+        );
+        assert_eq!(
+            mornings.state_at(utc("2026-10-19T09:00:00Z")),
+            WindowState::Closed
+        );
+        // Monday 2026-11-02, back on GMT.
+        assert_eq!(
+            mornings.state_at(utc("2026-11-02T08:00:00Z")),
+            WindowState::Closed
+        );
+        assert_eq!(
+            mornings.state_at(utc("2026-11-02T09:00:00Z")),
+            WindowState::Open
+        );
+    }
+
+    #[test]
+    fn recurring_window_follows_days_and_hours() {
+        let office_hours = window(serde_json::json!({
+            "recurring": { "days": ["mon", "tue", "wed", "thu", "fri"], "from": "09:00", "until": "18:00" },
+        }));
+
+        // Monday 2026-10-19.
+        assert_eq!(
+            office_hours.state_at(utc("2026-10-19T09:00:00Z")),
+            WindowState::Open
+        );
+        assert_eq!(
+            office_hours.state_at(utc("2026-10-19T18:00:00Z")),
+            WindowState::Closed
+        );
+        // Saturday.
+        assert_eq!(
+            office_hours.state_at(utc("2026-10-24T12:00:00Z")),
+            WindowState::Closed
+        );
+    }
+
+    #[test]
+    fn overnight_window_belongs_to_the_day_it_opens() {
+        let friday_night = window(serde_json::json!({
+            "recurring": { "days": ["friday"], "from": "22:00", "until": "06:00" },
+        }));
+
+        // Friday 2026-10-23 23:00, then Saturday 05:59 and 06:00.
+        assert_eq!(
+            friday_night.state_at(utc("2026-10-23T23:00:00Z")),
+            WindowState::Open
+        );
+        assert_eq!(
+            friday_night.state_at(utc("2026-10-24T05:59:00Z")),
+            WindowState::Open
+        );
+        assert_eq!(
+            friday_night.state_at(utc("2026-10-24T06:00:00Z")),
+            WindowState::Closed
+        );
+        // Friday early morning belongs to Thursday's window, which does not exist.
+        assert_eq!(
+            friday_night.state_at(utc("2026-10-23T05:00:00Z")),
+            WindowState::Closed
+        );
+    }
+
+    #[test]
+    fn bounds_limit_a_recurring_window() {
+        let season = window(serde_json::json!({
+            "starts_at": "2026-11-01T00:00",
+            "ends_at": "2026-11-08T00:00",
+            "recurring": { "days": ["sat", "sun"], "from": "10:00", "until": "20:00" },
+        }));
+
+        // Saturday 2026-10-31 is before the season starts.
+        assert_eq!(
+            season.state_at(utc("2026-10-31T12:00:00Z")),
+            WindowState::Closed
+        );
+        // Sunday 2026-11-01.
+        assert_eq!(
+            season.state_at(utc("2026-11-01T12:00:00Z")),
+            WindowState::Open
+        );
+        assert_eq!(
+            season.state_at(utc("2026-11-08T12:00:00Z")),
+            WindowState::Ended
+        );
+    }
+
+    #[test]
+    fn invalid_windows_name_the_field() {
+        let parse = |json: serde_json::Value| {
+            serde_json::from_value::<ActivationWindow>(json)
+                .expect("valid json")
+                .parse()
+                .expect_err("invalid window")
+        };
+
+        assert!(parse(serde_json::json!({})).contains("at least one of"));
+        assert!(
+            parse(serde_json::json!({ "utc_offset": "IST", "ends_at": "2026-12-27T00:00" }))
+                .starts_with("utc_offset")
+        );
+        assert!(parse(serde_json::json!({
+            "time_zone": "Mars/Olympus_Mons",
+            "ends_at": "2026-12-27T00:00",
+        }))
+        .starts_with("time_zone"));
+        assert!(parse(serde_json::json!({
+            "starts_at": "2026-12-27T00:00",
+            "ends_at": "2026-12-20T00:00",
+        }))
+        .contains("after starts_at"));
+        assert!(parse(serde_json::json!({
+            "recurring": { "days": ["someday"], "from": "09:00", "until": "18:00" },
+        }))
+        .starts_with("recurring.days"));
+        assert!(parse(serde_json::json!({
+            "recurring": { "days": ["mon"], "from": "9am", "until": "18:00" },
+        }))
+        .starts_with("recurring.from"));
+    }
+}
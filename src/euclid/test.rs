@@ -925,8 +925,8 @@ mod tests {
 
         use crate::euclid::{
             ast::{
-                Comparison, ComparisonType, ConnectorInfo, MetadataValue, Output, Program,
-                RoutingType, Rule, ValueType,
+                Comparison, ComparisonType, Condition, ConnectorInfo, MetadataValue, Output,
+                Program, RoutingType, Rule, ValueType,
             },
             interpreter::InterpreterBackend,
             types::Context,
@@ -955,12 +955,12 @@ mod tests {
                     routing_type: RoutingType::Priority,
                     output: Output::Priority(vec![gateway("matched")]),
                     statements: vec![crate::euclid::ast::IfStatement {
-                        condition: vec![Comparison {
+                        condition: vec![Condition::Comparison(Comparison {
                             lhs: "metadata".to_string(),
                             comparison: ComparisonType::Equal,
                             value: metadata_value(key, value),
                             metadata: HashMap::new(),
-                        }],
+                        })],
                         nested: None,
                     }],
                 }],
@@ -1102,6 +1102,183 @@ mod tests {
         }
     }
 
+    mod condition_groups {
+        use crate::euclid::{
+            ast::{Condition, Program, ValueType},
+            interpreter::InterpreterBackend,
+            types::{Context, RoutingRule},
+            utils::validate_routing_rule,
+        };
+
+        fn context(parameters: &[(&str, ValueType)]) -> Context {
+            Context::new(
+                parameters
+                    .iter()
+                    .map(|(key, value)| (key.to_string(), Some(value.clone())))
+                    .collect(),
+            )
+        }
+
+        fn variant(value: &str) -> ValueType {
+            ValueType::EnumVariant(value.to_string())
+        }
+
+        fn matched_rule(program: &Program, ctx: &Context) -> Option<String> {
+            InterpreterBackend::eval_program(program, ctx)
+                .expect("program evaluation failed")
+                .rule_name
+        }
+
+        fn comparison(lhs: &str, value: &str) -> serde_json::Value {
+            serde_json::json!({
+                "lhs": lhs,
+                "comparison": "equal",
+                "value": { "type": "enum_variant", "value": value },
+                "metadata": {},
+            })
+        }
+
+        fn program(condition: serde_json::Value) -> serde_json::Value {
+            serde_json::json!({
+                "globals": {},
+                "default_selection": { "priority": [{ "gateway_name": "fallback", "gateway_id": null }] },
+                "rules": [{
+                    "name": "grouped",
+                    "routing_type": "priority",
+                    "output": { "priority": [{ "gateway_name": "matched", "gateway_id": null }] },
+                    "statements": [{ "condition": condition, "nested": null }],
+                }],
+                "metadata": null,
+            })
+        }
+
+        #[test]
+        fn programs_stored_before_groups_existed_read_unchanged() {
+            let stored = program(serde_json::json!([
+                comparison("card_network", "visa"),
+                comparison("billing_country", "US"),
+            ]));
+            let program: Program = serde_json::from_value(stored.clone()).expect("deserializes");
+
+            let condition = &program.rules[0].statements[0].condition;
+            assert!(condition
+                .iter()
+                .all(|member| matches!(member, Condition::Comparison(_))));
+            assert_eq!(
+                serde_json::to_value(&program).expect("serializes"),
+                stored,
+                "a plain condition serializes exactly as it was stored"
+            );
+        }
+
+        #[test]
+        fn any_holds_when_one_alternative_does() {
+            let program: Program = serde_json::from_value(program(serde_json::json!([
+                { "any": [
+                    [comparison("card_network", "visa")],
+                    [comparison("billing_country", "US")],
+                ] },
+            ])))
+            .expect("deserializes");
+
+            let visa = context(&[("card_network", variant("visa"))]);
+            let us = context(&[
+                ("card_network", variant("mastercard")),
+                ("billing_country", variant("US")),
+            ]);
+            let neither = context(&[
+                ("card_network", variant("mastercard")),
+                ("billing_country", variant("IN")),
+            ]);
+
+            assert_eq!(matched_rule(&program, &visa), Some("grouped".to_string()));
+            assert_eq!(matched_rule(&program, &us), Some("grouped".to_string()));
+            assert_eq!(matched_rule(&program, &neither), None);
+        }
+
+        #[test]
+        fn a_malformed_condition_names_the_field_at_fault() {
+            let error = |condition: serde_json::Value| {
+                serde_json::from_value::<Program>(program(condition))
+                    .expect_err("malformed condition")
+                    .to_string()
+            };
+
+            let mut misspelt = comparison("card_type", "prepaid");
+            misspelt["comparison"] = serde_json::json!("equals");
+            let message = error(serde_json::json!([{ "not": [misspelt] }]));
+            assert!(
+                message.starts_with("not: unknown variant `equals`"),
+                "{message}"
+            );
+
+            let message = error(serde_json::json!([{ "any": [[{ "lhs": "card_type" }]] }]));
+            assert!(message.starts_with("any: missing field"), "{message}");
+
+            let message = error(serde_json::json!([{ "any": [], "not": [] }]));
+            assert!(message.contains("either `any` or `not`"), "{message}");
+        }
+
+        #[test]
+        fn not_inverts_its_group_including_a_missing_key() {
+            let program: Program = serde_json::from_value(program(serde_json::json!([
+                comparison("payment_method", "card"),
+                { "not": [comparison("card_type", "prepaid")] },
+            ])))
+            .expect("deserializes");
+
+            let prepaid = context(&[
+                ("payment_method", variant("card")),
+                ("card_type", variant("prepaid")),
+            ]);
+            let credit = context(&[
+                ("payment_method", variant("card")),
+                ("card_type", variant("credit")),
+            ]);
+            let untyped = context(&[("payment_method", variant("card"))]);
+
+            assert_eq!(matched_rule(&program, &prepaid), None);
+            assert_eq!(matched_rule(&program, &credit), Some("grouped".to_string()));
+            assert_eq!(
+                matched_rule(&program, &untyped),
+                Some("grouped".to_string())
+            );
+        }
+
+        #[test]
+        fn comparisons_inside_groups_are_validated() {
+            let rule = |condition: serde_json::Value| -> RoutingRule {
+                serde_json::from_value(serde_json::json!({
+                    "rule_id": null,
+                    "name": "grouped",
+                    "created_by": "merchant",
+                    "algorithm": { "type": "advanced", "data": program(condition) },
+                }))
+                .expect("deserializes")
+            };
+            let config = Some(super::routing_config_for_tests());
+
+            let valid = rule(serde_json::json!([
+                { "not": [comparison("billing_country", "US")] },
+            ]));
+            assert!(
+                validate_routing_rule(&valid, &config)
+                    .expect("config is present")
+                    .is_valid
+            );
+
+            let unknown_value = rule(serde_json::json!([
+                { "any": [[comparison("billing_country", "FR")]] },
+            ]));
+            let result = validate_routing_rule(&unknown_value, &config).expect("config is present");
+            assert_eq!(result.errors[0].error_type, "invalid_enum_value");
+
+            let empty = rule(serde_json::json!([{ "any": [] }]));
+            let result = validate_routing_rule(&empty, &config).expect("config is present");
+            assert_eq!(result.errors[0].error_type, "empty_group");
+        }
+    }
+
     /// `/config/routing-keys` serves this map verbatim, and the dashboard takes its first entry as
     /// the default field of a new rule condition. A `HashMap` here iterates in whatever order its
     /// per-process random seed produces, which moved that default — and the order of the field
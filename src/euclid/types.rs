@@ -1,4 +1,7 @@
+use super::analyzer::AnalysisWarning;
 use super::ast::ConnectorInfo;
+use super::diff::AlgorithmChange;
+use super::schedule::ActivationSchedule;
 use crate::euclid::ast::{Output, Program, ValueType};
 #[cfg(feature = "mysql")]
 use crate::storage::schema;
@@ -86,12 +89,36 @@ pub struct SrConfigOverride {
     pub use_autopilot: Option<bool>,
 }
 
+/// Arm names of a two-arm experiment written with the control/variant shorthand.
+pub const CONTROL_ARM: &str = "control";
+pub const VARIANT_ARM: &str = "variant";
+
+/// An A/B experiment: traffic split across weighted arms, each routed by its own algorithm.
+///
+/// Written either with `arms`, or with the original two-arm shorthand (`control_algorithm_id`,
+/// `variant_algorithm_id`, `variant_split_pct` and the per-arm SR configs), which [`Self::arms`]
+/// expands. The shorthand fields are ignored once `arms` is set.
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct ABTestData {
+    #[serde(default, skip_serializing_if = "String::is_empty")]
     pub control_algorithm_id: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
     pub variant_algorithm_id: String,
     /// Percentage of traffic routed to the variant arm (1–49).
+    #[serde(default, skip_serializing_if = "is_zero")]
     pub variant_split_pct: u8,
+    /// The arms traffic is split across, in proportion to their weights. The first is the
+    /// control every other arm is compared against.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub arms: Vec<ExperimentArm>,
+    /// What traffic is split by: every payment with the same unit lands on the same arm.
+    #[serde(default)]
+    pub hash_unit: HashUnit,
+    /// Mixed into the hash so that concurrent experiments split traffic independently of each
+    /// other. Set to the experiment's id when it is created; experiments created before salting
+    /// have none and keep their original split.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub salt: Option<String>,
     /// Minimum transactions to collect before reporting a significance verdict.
     pub min_sample_size: u32,
     /// Auto-pause threshold: if variant auth rate drops more than this many pp below control, flag for pause.
@@ -105,6 +132,135 @@ pub struct ABTestData {
     /// on" (control = multi-objective off) or "Autopilot value" (control = manual config).
     #[serde(skip_serializing_if = "Option::is_none")]
     pub control_sr_config: Option<SrConfigOverride>,
+    /// What the experiment evaluator may do on its own once the results are conclusive. Absent
+    /// means the experiment only ever changes by hand.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub policy: Option<ExperimentPolicy>,
+}
+
+fn is_zero(value: &u8) -> bool {
+    *value == 0
+}
+
+impl ABTestData {
+    /// Give an unsalted experiment `salt`. Experiments already salted keep theirs, so editing one
+    /// never reshuffles its traffic.
+    pub fn salt_with(&mut self, salt: impl Into<String>) {
+        if self.salt.is_none() {
+            self.salt = Some(salt.into());
+        }
+    }
+
+    /// The experiment's arms, with the two-arm shorthand expanded into a control and a variant.
+    pub fn arms(&self) -> Vec<ExperimentArm> {
+        if !self.arms.is_empty() {
+            return self.arms.clone();
+        }
+        vec![
+            ExperimentArm {
+                name: CONTROL_ARM.to_string(),
+                algorithm_id: self.control_algorithm_id.clone(),
+                weight: u32::from(100u8.saturating_sub(self.variant_split_pct)),
+                sr_config: self.control_sr_config.clone(),
+                paused: false,
+            },
+            ExperimentArm {
+                name: VARIANT_ARM.to_string(),
+                algorithm_id: self.variant_algorithm_id.clone(),
+                weight: u32::from(self.variant_split_pct),
+                sr_config: self.variant_sr_config.clone(),
+                paused: false,
+            },
+        ]
+    }
+
+    /// Stop sending traffic to the arm named `name`. Every arm keeps its weight, so units keep
+    /// the slots they hashed to: units on the other arms stay where they are, and only the paused
+    /// arm's units move, to the first arm still running.
+    pub fn pause_arm(&mut self, name: &str) {
+        self.expand_shorthand();
+        for arm in self.arms.iter_mut().filter(|arm| arm.name == name) {
+            arm.paused = true;
+        }
+    }
+
+    /// Send all traffic to the arm named `name`, by pausing every other arm.
+    pub fn concentrate_on(&mut self, name: &str) {
+        self.expand_shorthand();
+        for arm in self.arms.iter_mut() {
+            arm.paused = arm.name != name;
+        }
+    }
+
+    /// Rewrite the two-arm shorthand as `arms`, which can carry a paused arm. The split is
+    /// unchanged: the expanded arms hash exactly as the shorthand did.
+    fn expand_shorthand(&mut self) {
+        if !self.arms.is_empty() {
+            return;
+        }
+        self.arms = self.arms();
+        self.control_algorithm_id.clear();
+        self.variant_algorithm_id.clear();
+        self.variant_split_pct = 0;
+        self.control_sr_config = None;
+        self.variant_sr_config = None;
+    }
+}
+
+/// Automated actions an experiment allows. The evaluator judges the results with the sequential
+/// test, which stays valid however often it looks, and acts at most once per arm.
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
+pub struct ExperimentPolicy {
+    /// Stop sending traffic to a variant whose auth rate falls below the control's by more than
+    /// `guardrail_threshold_pp`.
+    #[serde(default)]
+    pub pause_on_guardrail: bool,
+    /// Give all traffic to the winner once one arm is significantly better than the control —
+    /// or to the control once every variant is significantly worse.
+    #[serde(default)]
+    pub promote_winner: bool,
+    /// Business margin (fraction of ticket) the results are valued at. Defaults to the results
+    /// endpoint's default.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub evaluation_margin: Option<f64>,
+    /// Addresses told about every action taken.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub notify_emails: Vec<String>,
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct ExperimentArm {
+    /// Identifies the arm in analytics and experiment results.
+    pub name: String,
+    /// `sr_routing`, or the id of a single/priority/volume_split/advanced algorithm.
+    pub algorithm_id: String,
+    /// Share of traffic, relative to the other arms' weights.
+    pub weight: u32,
+    /// Routing overrides for an `sr_routing` arm. Absent means the live SR config.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sr_config: Option<SrConfigOverride>,
+    /// Set when the arm is paused or another arm was promoted. A paused arm keeps its weight, so
+    /// the other arms' assignments don't move; its own traffic goes to the first running arm.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub paused: bool,
+}
+
+/// What an experiment hashes to pick an arm.
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize, strum::Display,
+)]
+#[serde(rename_all = "snake_case")]
+#[strum(serialize_all = "snake_case")]
+pub enum HashUnit {
+    /// Retries of a payment stay on one arm.
+    #[default]
+    PaymentId,
+    /// A customer's later orders stay on one arm. Payments without a customer id fall back to
+    /// their payment id.
+    CustomerId,
+    /// A card stays on one arm across customers and orders. Payments without a fingerprint fall
+    /// back to their payment id.
+    CardFingerprint,
 }
 
 #[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize, strum::Display)]
@@ -123,6 +279,15 @@ pub struct RoutingRequest {
     pub created_by: String,
     pub fallback_output: Option<Vec<ConnectorInfo>>,
     pub parameters: HashMap<String, Option<ValueType>>,
+    /// An advanced program in its textual form, evaluated in place of the merchant's active
+    /// algorithm. Lets a rule be tried against real parameters before it is created.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub program: Option<String>,
+    /// Hash units for A/B tests split by customer or card; without them the payment id is used.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub customer_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub card_fingerprint: Option<String>,
 }
 
 #[derive(Debug, Clone, serde::Serialize)]
@@ -139,6 +304,9 @@ pub struct RoutingDictionaryRecord {
     pub algorithm_for: String,
     pub created_at: time::PrimitiveDateTime,
     pub modified_at: time::PrimitiveDateTime,
+    /// What static analysis found in the stored algorithm. Never blocks the write.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub warnings: Vec<AnalysisWarning>,
 }
 
 impl RoutingDictionaryRecord {
@@ -155,8 +323,14 @@ impl RoutingDictionaryRecord {
             algorithm_for,
             created_at,
             modified_at,
+            warnings: Vec::new(),
         }
     }
+
+    pub fn with_warnings(mut self, warnings: Vec<AnalysisWarning>) -> Self {
+        self.warnings = warnings;
+        self
+    }
 }
 
 #[derive(Debug, Default, Clone, serde::Serialize, serde::Deserialize)]
@@ -176,6 +350,35 @@ pub const ELIGIBLE_DIMENSIONS: [&str; 5] = [
     "card_is_in",
     "card_network",
 ];
+
+/// A program to format, in either of its forms.
+#[derive(Debug, serde::Deserialize)]
+#[serde(untagged)]
+pub enum FormatRoutingProgramRequest {
+    Text { program: String },
+    Tree { program: Program },
+}
+
+/// Both forms of a program: the canonical text, and the tree it parses to.
+#[derive(Debug, serde::Serialize)]
+pub struct FormatRoutingProgramResponse {
+    pub text: String,
+    pub program: Program,
+}
+
+/// An algorithm to analyse without storing it. Advanced programs may be given as text, as on
+/// create.
+#[derive(Debug, serde::Deserialize)]
+pub struct RoutingLintRequest {
+    pub created_by: String,
+    pub algorithm: StaticRoutingAlgorithm,
+}
+
+#[derive(Debug, serde::Serialize)]
+pub struct RoutingLintResponse {
+    pub warnings: Vec<AnalysisWarning>,
+}
+
 #[derive(Debug, serde::Serialize)]
 pub struct RoutingEvaluateResponse {
     pub payment_id: Option<String>,
@@ -190,6 +393,7 @@ pub struct RoutingEvaluateResponse {
     AsChangeset,
     Insertable,
     Debug,
+    Clone,
     serde::Serialize,
     serde::Deserialize,
     Identifiable,
@@ -257,6 +461,9 @@ pub struct RoutingAlgorithmMapper {
     pub created_by: String,
     pub routing_algorithm_id: String,
     pub algorithm_for: String,
+    /// JSON [`super::schedule::ScheduledActivation`], while the active algorithm is driven by a
+    /// schedule.
+    pub schedule: Option<String>,
 }
 
 #[derive(Insertable, Debug, serde::Serialize, serde::Deserialize)]
@@ -266,6 +473,7 @@ pub struct RoutingAlgorithmMapperNew {
     pub created_by: String,
     pub routing_algorithm_id: String,
     pub algorithm_for: String,
+    pub schedule: Option<String>,
 }
 
 impl RoutingAlgorithmMapperNew {
@@ -274,14 +482,24 @@ impl RoutingAlgorithmMapperNew {
             created_by,
             routing_algorithm_id,
             algorithm_for,
+            schedule: None,
         }
     }
+
+    pub fn with_schedule(mut self, schedule: Option<String>) -> Self {
+        self.schedule = schedule;
+        self
+    }
 }
 
 #[derive(Debug, serde::Serialize, serde::Deserialize)]
 pub struct ActivateRoutingConfigRequest {
     pub created_by: String,
     pub routing_algorithm_id: String,
+    /// Activate only within a window, switching to a fallback outside it. Without one the
+    /// algorithm is active until replaced, and any earlier schedule is dropped.
+    #[serde(default)]
+    pub schedule: Option<ActivationSchedule>,
 }
 
 #[derive(Debug, serde::Serialize, serde::Deserialize)]
@@ -313,9 +531,239 @@ pub struct UpdateRoutingConfigRequest {
 #[derive(AsChangeset, Debug, serde::Serialize, serde::Deserialize, Queryable, Selectable)]
 #[cfg_attr(feature = "mysql", diesel(table_name = schema::routing_algorithm_mapper))]
 #[cfg_attr(feature = "postgres", diesel(table_name = schema_pg::routing_algorithm_mapper))]
+#[diesel(treat_none_as_null = true)]
 pub struct RoutingAlgorithmMapperUpdate {
     pub routing_algorithm_id: String,
     pub algorithm_for: String,
+    /// Written as-is, so `None` clears a schedule.
+    pub schedule: Option<String>,
+}
+
+/// Why a version was recorded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, strum::Display, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+#[strum(serialize_all = "snake_case")]
+pub enum VersionChangeKind {
+    Created,
+    Updated,
+    /// An earlier version copied back in by a rollback.
+    Restored,
+}
+
+/// An immutable snapshot of a routing algorithm. One is appended on every create, update and
+/// rollback; rows are never changed afterwards.
+#[derive(
+    Insertable,
+    Debug,
+    Clone,
+    serde::Serialize,
+    serde::Deserialize,
+    Identifiable,
+    Queryable,
+    Selectable,
+)]
+#[cfg_attr(feature = "mysql", diesel(table_name = schema::routing_algorithm_version))]
+#[cfg_attr(
+    feature = "postgres",
+    diesel(table_name = schema_pg::routing_algorithm_version)
+)]
+pub struct RoutingAlgorithmVersion {
+    pub id: String,
+    pub routing_algorithm_id: String,
+    pub version: i32,
+    pub created_by: String,
+    pub name: String,
+    pub description: String,
+    pub algorithm_data: String,
+    pub algorithm_for: String,
+    #[cfg(feature = "postgres")]
+    pub metadata: Option<serde_json::Value>,
+    #[cfg(feature = "mysql")]
+    pub metadata: Option<String>,
+    /// Who made the change, as [`crate::auth::AuthContext::actor`] names them. `None` for
+    /// requests made with the admin secret or in compat mode, which carry no identity.
+    pub author: Option<String>,
+    pub change_kind: String,
+    pub restored_from: Option<i32>,
+    /// The [`AlgorithmChange`]s from the previous version, as JSON. `None` on the first version.
+    pub diff: Option<String>,
+    pub created_at: PrimitiveDateTime,
+}
+
+impl RoutingAlgorithmVersion {
+    /// Snapshots the algorithm as it is now stored, as its first version.
+    pub fn of(
+        algorithm: &RoutingAlgorithm,
+        change_kind: VersionChangeKind,
+        created_at: PrimitiveDateTime,
+    ) -> Self {
+        Self {
+            id: super::utils::generate_random_id("routing_version"),
+            routing_algorithm_id: algorithm.id.clone(),
+            version: 1,
+            created_by: algorithm.created_by.clone(),
+            name: algorithm.name.clone(),
+            description: algorithm.description.clone(),
+            algorithm_data: algorithm.algorithm_data.clone(),
+            algorithm_for: algorithm.algorithm_for.clone(),
+            metadata: algorithm.metadata.clone(),
+            author: None,
+            change_kind: change_kind.to_string(),
+            restored_from: None,
+            diff: None,
+            created_at,
+        }
+    }
+
+    pub fn with_author(mut self, author: Option<String>) -> Self {
+        self.author = author;
+        self
+    }
+
+    pub fn with_changes(mut self, changes: &[AlgorithmChange]) -> Self {
+        self.diff = serde_json::to_string(changes).ok();
+        self
+    }
+}
+
+/// What the experiment evaluator did to an experiment.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, strum::Display, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+#[strum(serialize_all = "snake_case")]
+pub enum ExperimentActionKind {
+    /// A variant that breached the guardrail stopped receiving traffic.
+    Paused,
+    /// The winning arm was given all traffic.
+    Promoted,
+}
+
+/// One action the experiment evaluator took. Rows are only ever inserted, and at most one exists
+/// per experiment, action and arm.
+#[derive(
+    Insertable,
+    Debug,
+    Clone,
+    serde::Serialize,
+    serde::Deserialize,
+    Identifiable,
+    Queryable,
+    Selectable,
+)]
+#[cfg_attr(feature = "mysql", diesel(table_name = schema::experiment_action))]
+#[cfg_attr(feature = "postgres", diesel(table_name = schema_pg::experiment_action))]
+pub struct ExperimentAction {
+    pub id: String,
+    /// The experiment.
+    pub routing_algorithm_id: String,
+    pub created_by: String,
+    pub action: String,
+    pub arm: String,
+    /// The verdict the action was taken on.
+    pub verdict: String,
+    /// The algorithm a promotion made the merchant's active one. `None` for pauses, and for a
+    /// promoted `sr_routing` arm, which instead takes all of the experiment's traffic.
+    pub activated_algorithm_id: Option<String>,
+    /// The experiment results the action was taken on, as JSON.
+    pub results: String,
+    pub created_at: PrimitiveDateTime,
+}
+
+#[derive(Debug, serde::Deserialize)]
+pub struct ListExperimentActionsRequest {
+    pub created_by: String,
+    pub routing_algorithm_id: String,
+}
+
+#[derive(Debug, serde::Serialize)]
+pub struct ExperimentActionRecord {
+    pub action: String,
+    pub arm: String,
+    pub verdict: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub activated_algorithm_id: Option<String>,
+    pub results: serde_json::Value,
+    pub created_at: PrimitiveDateTime,
+}
+
+impl From<ExperimentAction> for ExperimentActionRecord {
+    fn from(action: ExperimentAction) -> Self {
+        Self {
+            action: action.action,
+            arm: action.arm,
+            verdict: action.verdict,
+            activated_algorithm_id: action.activated_algorithm_id,
+            results: serde_json::from_str(&action.results).unwrap_or(serde_json::Value::Null),
+            created_at: action.created_at,
+        }
+    }
+}
+
+#[derive(Debug, serde::Deserialize)]
+pub struct ListRoutingVersionsRequest {
+    pub created_by: String,
+    pub routing_algorithm_id: String,
+}
+
+#[derive(Debug, serde::Deserialize)]
+pub struct DiffRoutingVersionsRequest {
+    pub created_by: String,
+    pub routing_algorithm_id: String,
+    pub from_version: i32,
+    pub to_version: i32,
+}
+
+/// Make an earlier version the live definition of its algorithm, and the merchant's active one.
+#[derive(Debug, serde::Deserialize)]
+pub struct ActivateRoutingVersionRequest {
+    pub created_by: String,
+    pub routing_algorithm_id: String,
+    pub version: i32,
+}
+
+#[derive(Debug, serde::Serialize)]
+pub struct RoutingVersionRecord {
+    pub routing_algorithm_id: String,
+    pub version: i32,
+    pub name: String,
+    pub description: String,
+    pub algorithm: serde_json::Value,
+    pub author: Option<String>,
+    pub change_kind: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub restored_from: Option<i32>,
+    /// What changed from the previous version.
+    pub changes: Vec<AlgorithmChange>,
+    pub created_at: PrimitiveDateTime,
+}
+
+impl From<RoutingAlgorithmVersion> for RoutingVersionRecord {
+    fn from(version: RoutingAlgorithmVersion) -> Self {
+        Self {
+            algorithm: serde_json::from_str(&version.algorithm_data)
+                .unwrap_or(serde_json::Value::Null),
+            changes: version
+                .diff
+                .as_deref()
+                .and_then(|diff| serde_json::from_str(diff).ok())
+                .unwrap_or_default(),
+            routing_algorithm_id: version.routing_algorithm_id,
+            version: version.version,
+            name: version.name,
+            description: version.description,
+            author: version.author,
+            change_kind: version.change_kind,
+            restored_from: version.restored_from,
+            created_at: version.created_at,
+        }
+    }
+}
+
+#[derive(Debug, serde::Serialize)]
+pub struct DiffRoutingVersionsResponse {
+    pub routing_algorithm_id: String,
+    pub from_version: i32,
+    pub to_version: i32,
+    pub changes: Vec<AlgorithmChange>,
 }
 
 #[derive(Debug, Clone, Serialize, thiserror::Error)]
@@ -1,6 +1,8 @@
-use super::ast::{Comparison, ComparisonType, IfStatement, Rule, ValueType};
+use super::ast::{
+    Comparison, ComparisonType, Condition, IfCondition, IfStatement, Rule, ValueType,
+};
 use super::errors::{EuclidErrors, ValidationErrorDetails};
-use super::types::{KeyDataType, StaticRoutingAlgorithm};
+use super::types::{ABTestData, KeyDataType, StaticRoutingAlgorithm};
 use crate::error::ContainerError;
 use crate::euclid::types::{FieldValidationRules, KeyConfig, RoutingRule, TomlConfig};
 use std::collections::HashMap;
@@ -109,39 +111,119 @@ pub fn get_keys_by_type(config: &TomlConfig) -> HashMap<String, Vec<String>> {
 pub fn validate_routing_rule(
     rule: &RoutingRule,
     config: &Option<TomlConfig>,
+) -> Result<ValidationResult, ContainerError<EuclidErrors>> {
+    validate_routing_algorithm(&rule.algorithm, config)
+}
+
+/// Checks an algorithm's rules against the configured routing keys. Used for rules being stored
+/// and for programs previewed inline, so a preview accepts only what could be saved.
+pub fn validate_routing_algorithm(
+    algorithm: &StaticRoutingAlgorithm,
+    config: &Option<TomlConfig>,
 ) -> Result<ValidationResult, ContainerError<EuclidErrors>> {
     let config = config
         .clone()
         .ok_or_else(|| error_stack::report!(EuclidErrors::GlobalRoutingConfigsUnavailable))?;
 
-    match &rule.algorithm {
+    let mut validation_errors: Vec<ValidationErrorDetails> = Vec::new();
+    match algorithm {
         StaticRoutingAlgorithm::Single(_)
         | StaticRoutingAlgorithm::Priority(_)
-        | StaticRoutingAlgorithm::VolumeSplit(_)
-        | StaticRoutingAlgorithm::AbTest(_) => Ok(ValidationResult::success()),
+        | StaticRoutingAlgorithm::VolumeSplit(_) => return Ok(ValidationResult::success()),
+        StaticRoutingAlgorithm::AbTest(data) => validate_ab_test(data, &mut validation_errors),
         StaticRoutingAlgorithm::Advanced(program) => {
-            let mut validation_errors: Vec<ValidationErrorDetails> = Vec::new();
-
             for rule in &program.rules {
                 validate_rule(rule, &config, &mut validation_errors);
             }
+        }
+    }
 
-            if validation_errors.is_empty() {
-                Ok(ValidationResult::success())
-            } else {
-                for error in &validation_errors {
-                    crate::logger::warn!(
-                        field = %error.field,
-                        error_type = %error.error_type,
-                        message = %error.message,
-                        "Field validation error"
-                    );
-                }
+    if validation_errors.is_empty() {
+        Ok(ValidationResult::success())
+    } else {
+        for error in &validation_errors {
+            crate::logger::warn!(
+                field = %error.field,
+                error_type = %error.error_type,
+                message = %error.message,
+                "Field validation error"
+            );
+        }
+
+        let result = ValidationResult::failure(validation_errors);
+        Ok(result)
+    }
+}
 
-                let result = ValidationResult::failure(validation_errors);
-                Ok(result)
+/// An experiment is either the control/variant shorthand or a list of `arms`, never both. Arms
+/// need unique names and an algorithm, and every arm must be able to receive traffic.
+fn validate_ab_test(data: &ABTestData, errors: &mut Vec<ValidationErrorDetails>) {
+    if data.arms.is_empty() {
+        for (field, algorithm_id) in [
+            ("control_algorithm_id", &data.control_algorithm_id),
+            ("variant_algorithm_id", &data.variant_algorithm_id),
+        ] {
+            if algorithm_id.trim().is_empty() {
+                errors.push(ValidationErrorDetails::new(
+                    field,
+                    "missing_algorithm",
+                    format!("'{field}' is required unless the experiment lists its arms"),
+                ));
             }
         }
+        return;
+    }
+
+    if !data.control_algorithm_id.is_empty()
+        || !data.variant_algorithm_id.is_empty()
+        || data.variant_split_pct != 0
+        || data.control_sr_config.is_some()
+        || data.variant_sr_config.is_some()
+    {
+        errors.push(ValidationErrorDetails::new(
+            "arms",
+            "conflicting_arms",
+            "An experiment with 'arms' cannot also set the control/variant fields",
+        ));
+    }
+    if data.arms.len() < 2 {
+        errors.push(ValidationErrorDetails::new(
+            "arms",
+            "too_few_arms",
+            "An experiment needs at least two arms",
+        ));
+    }
+
+    let mut names = std::collections::HashSet::new();
+    for (index, arm) in data.arms.iter().enumerate() {
+        let field = format!("arms[{index}]");
+        if arm.name.trim().is_empty() {
+            errors.push(ValidationErrorDetails::new(
+                format!("{field}.name"),
+                "missing_name",
+                format!("Arm {index} has no name"),
+            ));
+        } else if !names.insert(arm.name.as_str()) {
+            errors.push(ValidationErrorDetails::new(
+                format!("{field}.name"),
+                "duplicate_arm",
+                format!("Arm name '{}' is used more than once", arm.name),
+            ));
+        }
+        if arm.algorithm_id.trim().is_empty() {
+            errors.push(ValidationErrorDetails::new(
+                format!("{field}.algorithm_id"),
+                "missing_algorithm",
+                format!("Arm '{}' has no algorithm_id", arm.name),
+            ));
+        }
+        if arm.weight == 0 {
+            errors.push(ValidationErrorDetails::new(
+                format!("{field}.weight"),
+                "zero_weight",
+                format!("Arm '{}' must have a positive weight", arm.name),
+            ));
+        }
     }
 }
 
@@ -156,9 +238,7 @@ fn validate_statement(
     config: &TomlConfig,
     errors: &mut Vec<ValidationErrorDetails>,
 ) {
-    for condition in &statement.condition {
-        validate_condition(condition, config, errors);
-    }
+    validate_if_condition(&statement.condition, config, errors);
 
     if let Some(nested) = &statement.nested {
         for nested_stmt in nested {
@@ -167,6 +247,41 @@ fn validate_statement(
     }
 }
 
+fn validate_if_condition(
+    condition: &IfCondition,
+    config: &TomlConfig,
+    errors: &mut Vec<ValidationErrorDetails>,
+) {
+    for member in condition {
+        match member {
+            Condition::Comparison(comparison) => validate_condition(comparison, config, errors),
+            Condition::Any { any } => {
+                // An empty group is legal JSON but never holds, which is never what was meant.
+                if any.is_empty() || any.iter().any(Vec::is_empty) {
+                    errors.push(ValidationErrorDetails::new(
+                        "any",
+                        "empty_group",
+                        "An `any` group and each of its alternatives must hold at least one condition",
+                    ));
+                }
+                for alternative in any {
+                    validate_if_condition(alternative, config, errors);
+                }
+            }
+            Condition::Not { not } => {
+                if not.is_empty() {
+                    errors.push(ValidationErrorDetails::new(
+                        "not",
+                        "empty_group",
+                        "A `not` group must hold at least one condition",
+                    ));
+                }
+                validate_if_condition(not, config, errors);
+            }
+        }
+    }
+}
+
 /// validates the comparison operators for different subtle value types present
 /// by throwing required errors for comparisons that can't be performed for a certain value type
 /// for example
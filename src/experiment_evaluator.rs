@@ -0,0 +1,606 @@
+//! Background job that acts on A/B experiment results.
+//!
+//! An experiment opts in through its `policy` (see [`ExperimentPolicy`]). On every tick this job
+//! reads the results of each active experiment that has one, comparing every variant against the
+//! control with the sequential test — the fixed-horizon test is not valid when read this often —
+//! and, as the policy allows:
+//!
+//! - pauses a variant whose auth rate breached the guardrail. The paused arm keeps its weight so
+//!   no other arm's units move; only its own traffic goes to the control;
+//! - promotes the winner once an arm is significantly better than the control, or the control
+//!   once every variant is significantly worse. An arm backed by a stored algorithm becomes the
+//!   merchant's active algorithm through the routing algorithm mapper, ending the experiment. An
+//!   `sr_routing` arm has no algorithm to activate, so it is given all of the experiment's traffic
+//!   instead, which keeps its SR overrides.
+//!
+//! The sequential test's p-value is always valid only as a running minimum over looks, so the
+//! lowest p-value each arm has reached is kept in Redis and the arm is judged on that: once the
+//! test has rejected, a later look drifting back above α does not undo it.
+//!
+//! Each action is recorded in `experiment_action`, in the same transaction as the change it
+//! makes, and at most once per arm: the table's unique key also stops replicas ticking together
+//! from acting twice. It is then emitted as an `experiment_auto_action` analytics event and
+//! emailed to the policy's `notify_emails`. At most one action is taken per experiment per tick,
+//! so every decision is made on a freshly read definition.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_bb8_diesel::AsyncConnection;
+use diesel::{
+    associations::HasTable, result::Error as DieselError, BoolExpressionMethods, ExpressionMethods,
+};
+use futures::FutureExt;
+
+use crate::analytics::clickhouse::endpoints::experiment_results::DEFAULT_EVALUATION_MARGIN;
+use crate::analytics::flow::{AnalyticsFlowContext, AnalyticsRoute, ApiFlow, FlowType};
+use crate::analytics::runtime::AnalyticsRuntime;
+use crate::analytics::{
+    DomainAnalyticsEvent, ExperimentResultsQuery, ExperimentResultsResponse, ExperimentVerdict,
+    SignificanceMethod,
+};
+use crate::app::{get_tenant_app_state, TenantAppState};
+use crate::config::ExperimentEvaluatorConfig;
+use crate::email::templates::ExperimentActionTemplate;
+use crate::euclid::handlers::routing_rules::invalidate_routing_algorithm_cache;
+use crate::euclid::handlers::routing_versions::{append_version, rollback, store_definition};
+use crate::euclid::types::{
+    ABTestData, ExperimentAction, ExperimentActionKind, ExperimentArm, ExperimentPolicy,
+    RoutingAlgorithm, RoutingAlgorithmMapper, RoutingAlgorithmMapperUpdate,
+    RoutingAlgorithmVersion, StaticRoutingAlgorithm, VersionChangeKind,
+};
+use crate::logger;
+#[cfg(feature = "mysql")]
+use crate::storage::schema::{
+    experiment_action::dsl as action_dsl, routing_algorithm::dsl as algo_dsl,
+    routing_algorithm_mapper::dsl as mapper_dsl,
+};
+#[cfg(feature = "postgres")]
+use crate::storage::schema_pg::{
+    experiment_action::dsl as action_dsl, routing_algorithm::dsl as algo_dsl,
+    routing_algorithm_mapper::dsl as mapper_dsl,
+};
+
+/// Default check cadence. Results move slowly, and every tick queries ClickHouse once per arm.
+const DEFAULT_INTERVAL_SECS: u64 = 300;
+/// Recorded as the author of the versions this job writes.
+const ACTOR: &str = "experiment_evaluator";
+/// How long an arm's running-minimum p-value outlives the last tick that read it.
+const MIN_P_TTL_SECS: i64 = 30 * 24 * 60 * 60;
+
+/// An active experiment with a policy, as read on this tick.
+struct Experiment {
+    mapping: RoutingAlgorithmMapper,
+    algorithm: RoutingAlgorithm,
+    data: ABTestData,
+    policy: ExperimentPolicy,
+}
+
+/// What the evaluator decided to do, and the results it decided on.
+struct Decision {
+    kind: ExperimentActionKind,
+    arm: ExperimentArm,
+    results: ExperimentResultsResponse,
+}
+
+#[derive(Debug, serde::Serialize)]
+struct ActionDetails<'a> {
+    experiment_id: &'a str,
+    action: ExperimentActionKind,
+    arm: &'a str,
+    verdict: &'a ExperimentVerdict,
+    activated_algorithm_id: Option<&'a str>,
+    p_value: Option<f64>,
+    confidence_interval: Option<(f64, f64)>,
+}
+
+/// Spawn the recurring evaluator loop. Call once at startup, after `APP_STATE` is set.
+pub fn spawn(runtime: Arc<AnalyticsRuntime>, config: ExperimentEvaluatorConfig) {
+    let interval_secs = config
+        .interval_secs
+        .filter(|&v| v > 0)
+        .unwrap_or(DEFAULT_INTERVAL_SECS);
+
+    tokio::spawn(async move {
+        logger::info!(
+            tag = "experiment_evaluator",
+            action = "start",
+            "experiment evaluator started; interval {}s",
+            interval_secs
+        );
+        let mut ticker = tokio::time::interval(Duration::from_secs(interval_secs));
+        loop {
+            ticker.tick().await;
+            // Isolate each cycle so a panic doesn't kill the loop.
+            if std::panic::AssertUnwindSafe(run_once(&runtime))
+                .catch_unwind()
+                .await
+                .is_err()
+            {
+                logger::error!(
+                    tag = "experiment_evaluator",
+                    action = "panic",
+                    "evaluator cycle panicked; continuing next cycle"
+                );
+            }
+        }
+    });
+}
+
+async fn run_once(runtime: &AnalyticsRuntime) {
+    if !runtime.read_enabled() {
+        return;
+    }
+    let state = get_tenant_app_state().await;
+    for experiment in active_experiments(&state).await {
+        let experiment_id = experiment.algorithm.id.clone();
+        let decision = match decide(runtime, &state, &experiment).await {
+            Ok(Some(decision)) => decision,
+            Ok(None) => continue,
+            Err(reason) => {
+                logger::warn!(
+                    tag = "experiment_evaluator",
+                    action = "skip",
+                    "experiment {} skipped: {}",
+                    experiment_id,
+                    reason
+                );
+                continue;
+            }
+        };
+        if let Err(reason) = apply(&state, &experiment, &decision).await {
+            logger::warn!(
+                tag = "experiment_evaluator",
+                action = "apply_error",
+                "could not {} arm {} of experiment {}: {}",
+                decision.kind,
+                decision.arm.name,
+                experiment_id,
+                reason
+            );
+        }
+    }
+}
+
+/// Every merchant's active algorithm that is an experiment with a policy.
+async fn active_experiments(state: &TenantAppState) -> Vec<Experiment> {
+    let mappings = match crate::generics::generic_find_all::<
+        <RoutingAlgorithmMapper as HasTable>::Table,
+        _,
+        RoutingAlgorithmMapper,
+    >(&state.db, mapper_dsl::routing_algorithm_id.is_not_null())
+    .await
+    {
+        Ok(mappings) => mappings,
+        Err(err) => {
+            logger::warn!(
+                tag = "experiment_evaluator",
+                action = "load_error",
+                "failed to load active routing mappings: {:?}",
+                err
+            );
+            return Vec::new();
+        }
+    };
+
+    let mut experiments = Vec::new();
+    for mapping in mappings {
+        let Ok(algorithm) = crate::generics::generic_find_one::<
+            <RoutingAlgorithm as HasTable>::Table,
+            _,
+            RoutingAlgorithm,
+        >(
+            &state.db,
+            algo_dsl::id.eq(mapping.routing_algorithm_id.clone()),
+        )
+        .await
+        else {
+            continue;
+        };
+        let Ok(StaticRoutingAlgorithm::AbTest(data)) =
+            serde_json::from_str::<StaticRoutingAlgorithm>(&algorithm.algorithm_data)
+        else {
+            continue;
+        };
+        let Some(policy) = data.policy.clone() else {
+            continue;
+        };
+        if policy.pause_on_guardrail || policy.promote_winner {
+            experiments.push(Experiment {
+                mapping,
+                algorithm,
+                data,
+                policy,
+            });
+        }
+    }
+    experiments
+}
+
+/// Reads every live variant against the control and picks the action to take, if any. Guardrail
+/// pauses come first: a breached variant is never promoted. Promotions act only on significant
+/// sequential-test verdicts.
+async fn decide(
+    runtime: &AnalyticsRuntime,
+    state: &TenantAppState,
+    experiment: &Experiment,
+) -> Result<Option<Decision>, String> {
+    let arms = experiment.data.arms();
+    let Some((control, variants)) = arms.split_first() else {
+        return Ok(None);
+    };
+    // Experiments paused before arms carried a flag had the arm's weight zeroed instead.
+    let running = |arm: &ExperimentArm| !arm.paused && arm.weight > 0;
+    let live: Vec<&ExperimentArm> = variants.iter().filter(|arm| running(arm)).collect();
+    // Nothing left to compare: the experiment has already been concluded.
+    if !running(control) || live.is_empty() {
+        return Ok(None);
+    }
+    let policy = &experiment.policy;
+    // The guardrail verdict is a point estimate that replaces the sequential test's. Without
+    // guardrail pauses it must not hide a significant result, so the check is switched off.
+    let guardrail_threshold_pp = if policy.pause_on_guardrail {
+        experiment.data.guardrail_threshold_pp
+    } else {
+        f64::INFINITY
+    };
+
+    let store = runtime.read_store();
+    let mut readings = Vec::with_capacity(live.len());
+    for arm in live {
+        let query = ExperimentResultsQuery {
+            experiment_id: experiment.algorithm.id.clone(),
+            merchant_id: experiment.mapping.created_by.clone(),
+            start_ms: None,
+            end_ms: None,
+            control_arm: control.name.clone(),
+            variant_arm: arm.name.clone(),
+            min_sample_size: experiment.data.min_sample_size,
+            guardrail_threshold_pp,
+            evaluation_margin: experiment
+                .policy
+                .evaluation_margin
+                .unwrap_or(DEFAULT_EVALUATION_MARGIN),
+            method: SignificanceMethod::Sequential,
+        };
+        let mut results = store
+            .experiment_results(&query)
+            .await
+            .map_err(|e| format!("results for arm {} unavailable: {e:?}", arm.name))?;
+        hold_running_min(state, &experiment.algorithm.id, &arm.name, &mut results).await;
+        readings.push((arm, results));
+    }
+
+    if policy.pause_on_guardrail {
+        if let Some((arm, results)) = readings
+            .iter()
+            .find(|(_, results)| results.verdict == ExperimentVerdict::GuardrailBreached)
+        {
+            return Ok(Some(Decision {
+                kind: ExperimentActionKind::Paused,
+                arm: (*arm).clone(),
+                results: results.clone(),
+            }));
+        }
+    }
+    if !policy.promote_winner {
+        return Ok(None);
+    }
+
+    // The variant beating the control by the widest margin, on the EV delta where the
+    // experiment has cost data and on auth rate otherwise.
+    let effect =
+        |results: &ExperimentResultsResponse| results.net_delta_bps.unwrap_or(results.delta_pp);
+    let winner = readings
+        .iter()
+        .filter(|(_, results)| results.verdict == ExperimentVerdict::VariantWins)
+        .max_by(|(_, a), (_, b)| effect(a).total_cmp(&effect(b)));
+    if let Some((arm, results)) = winner {
+        return Ok(Some(Decision {
+            kind: ExperimentActionKind::Promoted,
+            arm: (*arm).clone(),
+            results: results.clone(),
+        }));
+    }
+
+    let control_wins = readings
+        .iter()
+        .all(|(_, results)| results.verdict == ExperimentVerdict::VariantLoses);
+    Ok(control_wins
+        .then(|| readings.into_iter().next())
+        .flatten()
+        .map(|(_, results)| Decision {
+            kind: ExperimentActionKind::Promoted,
+            arm: control.clone(),
+            results,
+        }))
+}
+
+/// The lowest p-value an arm has reached, with the verdict read at that look.
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+struct RunningMin {
+    p_value: f64,
+    verdict: ExperimentVerdict,
+}
+
+/// Judges `results` on the lowest p-value its arm has reached so far, and records this look's
+/// p-value if it is the new lowest. A breached guardrail or too little data is read as is.
+async fn hold_running_min(
+    state: &TenantAppState,
+    experiment_id: &str,
+    arm: &str,
+    results: &mut ExperimentResultsResponse,
+) {
+    if matches!(
+        results.verdict,
+        ExperimentVerdict::GuardrailBreached | ExperimentVerdict::CollectingData
+    ) {
+        return;
+    }
+    let key = format!("experiment_min_p:{experiment_id}:{arm}");
+    let lowest = state
+        .redis_conn
+        .get_key::<RunningMin>(&key, "RunningMin")
+        .await
+        .ok();
+    let lowest = match (lowest, results.p_value) {
+        (Some(lowest), Some(p_value)) if lowest.p_value <= p_value => lowest,
+        (Some(lowest), None) => lowest,
+        (_, Some(p_value)) => RunningMin {
+            p_value,
+            verdict: results.verdict.clone(),
+        },
+        (None, None) => return,
+    };
+    results.p_value = Some(lowest.p_value);
+    results.verdict = lowest.verdict.clone();
+    if let Err(e) = state
+        .redis_conn
+        .set_key_with_ttl(&key, lowest, MIN_P_TTL_SECS)
+        .await
+    {
+        logger::warn!(
+            tag = "experiment_evaluator",
+            action = "min_p_error",
+            "could not record the lowest p-value of arm {} of experiment {}: {:?}",
+            arm,
+            experiment_id,
+            e
+        );
+    }
+}
+
+async fn apply(
+    state: &TenantAppState,
+    experiment: &Experiment,
+    decision: &Decision,
+) -> Result<(), String> {
+    let experiment_id = &experiment.algorithm.id;
+    let already_taken = crate::generics::generic_find_one_optional::<
+        <ExperimentAction as HasTable>::Table,
+        _,
+        ExperimentAction,
+    >(
+        &state.db,
+        action_dsl::routing_algorithm_id
+            .eq(experiment_id.clone())
+            .and(action_dsl::action.eq(decision.kind.to_string()))
+            .and(action_dsl::arm.eq(decision.arm.name.clone())),
+    )
+    .await
+    .map_err(|e| format!("action lookup failed: {e:?}"))?;
+    // Each action is taken once per arm. Undoing one, by editing the experiment or activating
+    // it again, is a human decision the evaluator does not second-guess.
+    if already_taken.is_some() {
+        return Ok(());
+    }
+
+    let now = time::OffsetDateTime::now_utc();
+    let timestamp = time::PrimitiveDateTime::new(now.date(), now.time());
+    let promoted_algorithm = match decision.kind {
+        ExperimentActionKind::Promoted if decision.arm.algorithm_id != "sr_routing" => {
+            Some(promotion_target(state, experiment, &decision.arm).await?)
+        }
+        _ => None,
+    };
+    let action = ExperimentAction {
+        id: crate::euclid::utils::generate_random_id("experiment_action"),
+        routing_algorithm_id: experiment_id.clone(),
+        created_by: experiment.mapping.created_by.clone(),
+        action: decision.kind.to_string(),
+        arm: decision.arm.name.clone(),
+        verdict: decision.results.verdict.to_string(),
+        activated_algorithm_id: promoted_algorithm.as_ref().map(|a| a.id.clone()),
+        results: serde_json::to_string(&decision.results)
+            .map_err(|e| format!("unserializable results: {e}"))?,
+        created_at: timestamp,
+    };
+
+    let conn = state
+        .db
+        .get_conn()
+        .await
+        .map_err(|_| "no database connection".to_string())?;
+    match &promoted_algorithm {
+        // End the experiment: the winner's algorithm replaces it as the merchant's active one.
+        Some(target) => {
+            let mapping_id = experiment.mapping.id;
+            let values = RoutingAlgorithmMapperUpdate {
+                routing_algorithm_id: target.id.clone(),
+                algorithm_for: experiment.mapping.algorithm_for.clone(),
+                schedule: None,
+            };
+            let still_active = experiment_id.clone();
+            let record = action.clone();
+            conn.transaction_async(|conn| async move {
+                crate::generics::generic_insert_core::<<ExperimentAction as HasTable>::Table, _>(
+                    &conn, record,
+                )
+                .await
+                .map_err(rollback)?;
+                let updated = crate::generics::generic_update_if_present::<
+                    <RoutingAlgorithmMapper as HasTable>::Table,
+                    _,
+                    _,
+                >(
+                    &conn,
+                    mapper_dsl::id
+                        .eq(mapping_id)
+                        .and(mapper_dsl::routing_algorithm_id.eq(still_active)),
+                    values,
+                )
+                .await
+                .map_err(rollback)?;
+                // The merchant activated something else since the mapping was read.
+                if updated == 0 {
+                    return Err(DieselError::RollbackTransaction);
+                }
+                Ok::<_, DieselError>(())
+            })
+            .await
+            .map_err(|e| format!("promotion not applied: {e:?}"))?;
+        }
+        // Rewrite the experiment's weights: pause the arm, or give the winner all traffic.
+        None => {
+            let mut data = experiment.data.clone();
+            match decision.kind {
+                ExperimentActionKind::Paused => data.pause_arm(&decision.arm.name),
+                ExperimentActionKind::Promoted => data.concentrate_on(&decision.arm.name),
+            }
+            let updated = RoutingAlgorithm {
+                algorithm_data: serde_json::to_string(&StaticRoutingAlgorithm::AbTest(data))
+                    .map_err(|e| format!("unserializable experiment: {e}"))?,
+                modified_at: timestamp,
+                ..experiment.algorithm.clone()
+            };
+            let version =
+                RoutingAlgorithmVersion::of(&updated, VersionChangeKind::Updated, timestamp)
+                    .with_author(Some(ACTOR.to_string()));
+            let record = action.clone();
+            conn.transaction_async(|conn| async move {
+                crate::generics::generic_insert_core::<<ExperimentAction as HasTable>::Table, _>(
+                    &conn, record,
+                )
+                .await
+                .map_err(rollback)?;
+                append_version(&conn, version).await?;
+                store_definition(&conn, &updated).await?;
+                Ok::<_, DieselError>(())
+            })
+            .await
+            .map_err(|e| format!("experiment not updated: {e:?}"))?;
+        }
+    }
+
+    invalidate_routing_algorithm_cache(state, &experiment.mapping.created_by).await;
+    logger::info!(
+        tag = "experiment_evaluator",
+        action = %decision.kind,
+        "experiment {} ({}): {} arm {} on verdict {}",
+        experiment_id,
+        experiment.mapping.created_by,
+        decision.kind,
+        decision.arm.name,
+        decision.results.verdict
+    );
+    report(experiment, decision, &action).await;
+    Ok(())
+}
+
+/// The stored algorithm a promoted arm runs, checked to be the merchant's own and for the same
+/// flow as the experiment.
+async fn promotion_target(
+    state: &TenantAppState,
+    experiment: &Experiment,
+    arm: &ExperimentArm,
+) -> Result<RoutingAlgorithm, String> {
+    let target = crate::generics::generic_find_one::<
+        <RoutingAlgorithm as HasTable>::Table,
+        _,
+        RoutingAlgorithm,
+    >(&state.db, algo_dsl::id.eq(arm.algorithm_id.clone()))
+    .await
+    .map_err(|_| format!("arm algorithm {} not found", arm.algorithm_id))?;
+    if target.created_by != experiment.mapping.created_by
+        || target.algorithm_for != experiment.mapping.algorithm_for
+    {
+        return Err(format!(
+            "arm algorithm {} cannot be activated for this merchant",
+            arm.algorithm_id
+        ));
+    }
+    Ok(target)
+}
+
+/// Emits the analytics event and notifies the policy's addresses.
+async fn report(experiment: &Experiment, decision: &Decision, action: &ExperimentAction) {
+    DomainAnalyticsEvent::record_operation(
+        AnalyticsFlowContext::new(ApiFlow::RuleBasedRouting, FlowType::ExperimentAutoAction),
+        AnalyticsRoute::RoutingActivate,
+        Some(experiment.mapping.created_by.clone()),
+        None,
+        None,
+        None,
+        None,
+        Some("success".to_string()),
+        crate::analytics::serialize_details(&ActionDetails {
+            experiment_id: &experiment.algorithm.id,
+            action: decision.kind,
+            arm: &decision.arm.name,
+            verdict: &decision.results.verdict,
+            activated_algorithm_id: action.activated_algorithm_id.as_deref(),
+            p_value: decision.results.p_value,
+            confidence_interval: decision.results.confidence_interval,
+        }),
+        Some("experiment_auto_action".to_string()),
+    );
+
+    if experiment.policy.notify_emails.is_empty() {
+        return;
+    }
+    let Some(global_state) = crate::app::APP_STATE.get() else {
+        return;
+    };
+    let (summary, reason) = match decision.kind {
+        ExperimentActionKind::Paused => (
+            format!("paused arm {}", decision.arm.name),
+            format!(
+                "Arm {} fell more than {} percentage points below the control's auth rate, so it no longer receives traffic.",
+                decision.arm.name, experiment.data.guardrail_threshold_pp
+            ),
+        ),
+        ExperimentActionKind::Promoted => (
+            format!("promoted arm {}", decision.arm.name),
+            match &action.activated_algorithm_id {
+                Some(algorithm_id) => format!(
+                    "Arm {} won with verdict {}. Its algorithm {} is now the active routing algorithm, ending the experiment.",
+                    decision.arm.name, decision.results.verdict, algorithm_id
+                ),
+                None => format!(
+                    "Arm {} won with verdict {} and now receives all of the experiment's traffic.",
+                    decision.arm.name, decision.results.verdict
+                ),
+            },
+        ),
+    };
+    for address in &experiment.policy.notify_emails {
+        let message = ExperimentActionTemplate {
+            user_email: address.clone(),
+            experiment_name: experiment.algorithm.name.clone(),
+            merchant_id: experiment.mapping.created_by.clone(),
+            action: summary.clone(),
+            reason: reason.clone(),
+        }
+        .into_message();
+        if let Err(err) = global_state.email_client.send_email(message).await {
+            logger::warn!(
+                tag = "experiment_evaluator",
+                action = "notify_error",
+                "could not notify {} about experiment {}: {:?}",
+                address,
+                experiment.algorithm.id,
+                err
+            );
+        }
+    }
+}
@@ -722,6 +722,36 @@ pub async fn update_gateway_score(
         routing_approach
     );
 
+    // Feed the gateway's latency window the multi-objective latency objective ranks on.
+    if let (Some(gateway), Some(latency_ms)) = (
+        txn_detail.gateway.as_ref(),
+        txn_latency.as_ref().and_then(|l| l.gateway_latency),
+    ) {
+        crate::decider::gatewaydecider::multi_objective::latency::record_latency(
+            &MID::merchant_id_to_text(txn_detail.merchantId.clone()),
+            gateway,
+            latency_ms,
+        )
+        .await;
+    }
+
+    // Count a successful payment toward its connector's month-to-date volume, which volume-tier
+    // contracts price against. Each transaction is counted once, however often it is reported.
+    if gateway_scoring_type == GST::Reward {
+        if let (Some(gateway), Some(amount)) =
+            (txn_detail.gateway.as_ref(), txn_detail.netAmount.as_ref())
+        {
+            crate::cost_ingestion::volume_tiers::record_routed_volume(
+                &MID::merchant_id_to_text(txn_detail.merchantId.clone()),
+                &txn_detail.txnUuid,
+                gateway,
+                &format!("{:?}", txn_detail.currency),
+                amount.to_double(),
+            )
+            .await;
+        }
+    }
+
     let m_source_object = if txn_card_info.paymentMethodType == UPI {
         txn_detail.sourceObject.clone()
     } else {
@@ -28,6 +28,7 @@
 pub mod analytics;
 pub mod api_client;
 pub mod app;
+pub mod audit;
 pub mod auth;
 pub mod config;
 pub mod cost_ingestion;
@@ -37,6 +38,7 @@ pub mod decider;
 pub mod email;
 pub mod error;
 pub mod euclid;
+pub mod experiment_evaluator;
 pub mod feedback;
 pub mod generics;
 pub mod gsm;
@@ -45,7 +47,9 @@ pub mod merchant_config_util;
 pub mod metrics;
 pub mod middleware;
 pub mod redis;
+pub mod replay;
 pub mod routes;
+pub mod routing_scheduler;
 pub mod sr_auto_calibration;
 pub mod storage;
 pub mod tenant;
@@ -9,6 +9,8 @@ use diesel::ExpressionMethods;
 use masking::PeekInterface;
 
 const API_KEY_CACHE_TTL: i64 = 300;
+/// How stale a key's `last_used_at` may be.
+const LAST_USED_RESOLUTION_SECS: i64 = 60;
 
 /// Middleware providing implementation to perform JWE + JWS encryption and decryption around the
 /// card APIs
@@ -56,23 +58,9 @@ pub async fn authenticate(
                         .require_explicit_permissions,
                 );
 
-                // Enforced here rather than per handler, so every route is covered at once. The
-                // routed pattern is what gets classified — `MatchedPath` is set during routing, and
-                // this layer runs after it — so a path parameter cannot be shaped to resemble a
-                // route that needs less.
-                let matched_path = req
-                    .extensions()
-                    .get::<axum::extract::MatchedPath>()
-                    .map(|matched| matched.as_str().to_owned());
-                let required =
-                    auth::access::required_permission(req.method(), matched_path.as_deref());
-
-                if !context.allows(&required) {
-                    return Ok((
-                        StatusCode::FORBIDDEN,
-                        format!("This session does not have {}", required.as_str()),
-                    )
-                        .into_response());
+                // Enforced here rather than per handler, so every route is covered at once.
+                if let Err(response) = check_permission(&req, &context) {
+                    return Ok(response);
                 }
 
                 req.extensions_mut().insert(context);
@@ -126,17 +114,77 @@ pub async fn authenticate(
                 return Ok((StatusCode::INTERNAL_SERVER_ERROR, "Tenant not found").into_response())
             }
         };
+    let now = time::OffsetDateTime::now_utc().unix_timestamp();
 
-    // Check Redis cache first
-    if let Ok(cached) = tenant_state.redis_conn.get_key_string(&cache_key).await {
-        if !cached.is_empty() {
-            req.extensions_mut()
-                .insert(auth::AuthContext::from_api_key(cached));
-            return Ok(next.run(req).await);
+    // Check Redis cache first. An entry written before keys carried scopes does not parse, and
+    // falls through to the DB like a miss.
+    let cached = tenant_state
+        .redis_conn
+        .get_key::<CachedApiKey>(&cache_key, "CachedApiKey")
+        .await
+        .ok();
+
+    let key = match cached {
+        Some(key) => key,
+        None => match load_api_key(&tenant_state, &key_hash).await {
+            Ok(key) => {
+                // Never cache past the key's expiry, so an expired key is caught on either path.
+                let ttl = key
+                    .expires_at
+                    .map_or(API_KEY_CACHE_TTL, |at| (at - now).min(API_KEY_CACHE_TTL));
+                if ttl > 0 {
+                    let _ = tenant_state
+                        .redis_conn
+                        .set_key_with_ttl(&cache_key, &key, ttl)
+                        .await;
+                }
+                key
+            }
+            Err(response) => return Ok(response),
+        },
+    };
+
+    if key.expires_at.is_some_and(|at| now >= at) {
+        return Ok((StatusCode::UNAUTHORIZED, "API key has expired").into_response());
+    }
+
+    let context = auth::AuthContext::from_api_key(key.merchant_id.clone(), key.permissions.clone());
+    if let Err(response) = check_permission(&req, &context) {
+        return Ok(response);
+    }
+
+    if let Some(limit) = key.rate_limit_per_minute {
+        if let Some(retry_after) = over_rate_limit(&tenant_state, &key.key_id, limit, now).await {
+            return Ok((
+                StatusCode::TOO_MANY_REQUESTS,
+                [(axum::http::header::RETRY_AFTER, retry_after.to_string())],
+                "API key rate limit exceeded",
+            )
+                .into_response());
         }
     }
 
-    // Cache miss — query DB
+    record_last_used(&tenant_state, &key.key_id).await;
+
+    req.extensions_mut().insert(context);
+    Ok(next.run(req).await)
+}
+
+/// What authentication needs from a key, cached so most requests skip the DB.
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+struct CachedApiKey {
+    key_id: String,
+    merchant_id: String,
+    permissions: Option<Vec<auth::Permission>>,
+    /// Unix seconds.
+    expires_at: Option<i64>,
+    rate_limit_per_minute: Option<u32>,
+}
+
+async fn load_api_key(
+    tenant_state: &crate::app::TenantAppState,
+    key_hash: &str,
+) -> Result<CachedApiKey, Response<Body>> {
     use crate::storage::types::MerchantApiKey;
     use diesel::associations::HasTable;
 
@@ -149,41 +197,125 @@ pub async fn authenticate(
         <MerchantApiKey as HasTable>::Table,
         _,
         MerchantApiKey,
-    >(&tenant_state.db, dsl::key_hash.eq(key_hash.clone()))
+    >(&tenant_state.db, dsl::key_hash.eq(key_hash.to_owned()))
     .await;
 
-    let key_record = match results {
+    let record = match results {
         Ok(mut rows) => rows.pop(),
         Err(_) => None,
+    }
+    .ok_or_else(|| (StatusCode::UNAUTHORIZED, "Invalid API key").into_response())?;
+
+    let is_active = {
+        #[cfg(feature = "mysql")]
+        {
+            record.is_active != 0
+        }
+        #[cfg(feature = "postgres")]
+        {
+            record.is_active
+        }
     };
 
-    match key_record {
-        Some(record) => {
-            let is_active = {
-                #[cfg(feature = "mysql")]
-                {
-                    record.is_active != 0
-                }
-                #[cfg(feature = "postgres")]
-                {
-                    record.is_active
-                }
-            };
+    if !is_active {
+        return Err((StatusCode::UNAUTHORIZED, "API key is revoked").into_response());
+    }
 
-            if !is_active {
-                return Ok((StatusCode::UNAUTHORIZED, "API key is revoked").into_response());
-            }
+    Ok(CachedApiKey {
+        permissions: crate::routes::api_key::stored_permissions(record.permissions.as_deref()),
+        expires_at: record.expires_at.map(|at| at.assume_utc().unix_timestamp()),
+        rate_limit_per_minute: record
+            .rate_limit_per_minute
+            .and_then(|n| u32::try_from(n).ok()),
+        key_id: record.key_id,
+        merchant_id: record.merchant_id,
+    })
+}
+
+/// Refuse a request the session or key does not have the permission for.
+///
+/// The routed pattern is what gets classified — `MatchedPath` is set during routing, and this layer
+/// runs after it — so a path parameter cannot be shaped to resemble a route that needs less.
+fn check_permission(
+    req: &Request<Body>,
+    context: &auth::AuthContext,
+) -> Result<(), Response<Body>> {
+    let matched_path = req
+        .extensions()
+        .get::<axum::extract::MatchedPath>()
+        .map(|matched| matched.as_str().to_owned());
+    let required = auth::access::required_permission(req.method(), matched_path.as_deref());
 
-            // Populate Redis cache
-            let _ = tenant_state
-                .redis_conn
-                .set_key_with_ttl(&cache_key, &record.merchant_id, API_KEY_CACHE_TTL)
-                .await;
+    if context.allows(&required) {
+        return Ok(());
+    }
+    let holder = match context.auth_kind {
+        auth::AuthKind::Jwt => "session",
+        auth::AuthKind::ApiKey => "API key",
+    };
+    Err((
+        StatusCode::FORBIDDEN,
+        format!("This {holder} does not have {}", required.as_str()),
+    )
+        .into_response())
+}
 
-            req.extensions_mut()
-                .insert(auth::AuthContext::from_api_key(record.merchant_id));
-            Ok(next.run(req).await)
+/// Count a request against the key's per-minute quota, in a fixed window per UTC minute shared by
+/// every instance. Returns the seconds until the window resets when the quota is spent.
+///
+/// Fails open: a Redis error lets the request through rather than taking a payment service down
+/// with the cache.
+async fn over_rate_limit(
+    tenant_state: &crate::app::TenantAppState,
+    key_id: &str,
+    limit: u32,
+    now: i64,
+) -> Option<i64> {
+    let window = now.div_euclid(60);
+    let counter = format!("api_key_rate:{key_id}:{window}");
+    let count = match tenant_state.redis_conn.increment_key(&counter).await {
+        Ok(count) => count,
+        Err(e) => {
+            crate::logger::warn!("api key rate limit check failed for {}: {:?}", key_id, e);
+            return None;
         }
-        None => Ok((StatusCode::UNAUTHORIZED, "Invalid API key").into_response()),
+    };
+    if count == 1 {
+        let _ = tenant_state.redis_conn.expire_key(&counter, 120).await;
     }
+    (count > i64::from(limit)).then(|| 60 - now.rem_euclid(60))
+}
+
+/// Record when a key was last used, at most once a minute per key so a busy key does not turn
+/// every request into a DB write. Best-effort.
+async fn record_last_used(tenant_state: &crate::app::TenantAppState, key_id: &str) {
+    use crate::storage::types::{MerchantApiKey, MerchantApiKeyLastUsed};
+    use diesel::associations::HasTable;
+
+    #[cfg(feature = "mysql")]
+    use crate::storage::schema::merchant_api_keys::dsl;
+    #[cfg(feature = "postgres")]
+    use crate::storage::schema_pg::merchant_api_keys::dsl;
+
+    let gate = format!("api_key_last_used:{key_id}");
+    if !matches!(
+        tenant_state
+            .redis_conn
+            .set_key_if_not_exists(&gate, "1", LAST_USED_RESOLUTION_SECS)
+            .await,
+        Ok(true)
+    ) {
+        return;
+    }
+    let Ok(conn) = tenant_state.db.get_conn().await else {
+        return;
+    };
+    let _ = crate::generics::generic_update::<<MerchantApiKey as HasTable>::Table, _, _>(
+        &conn,
+        dsl::key_id.eq(key_id.to_owned()),
+        MerchantApiKeyLastUsed {
+            last_used_at: crate::utils::date_time::now(),
+        },
+    )
+    .await;
 }
@@ -0,0 +1,576 @@
+//! Re-decides recorded payments under a candidate config and tallies the difference.
+//!
+//! Nothing here goes through the live decider flow, so a replay leaves no trace: no score or
+//! scoring-data writes to Redis, no analytics events, no A/B in-flight records. A candidate
+//! algorithm is evaluated by the euclid interpreter, a priority-logic script by the runner, and an
+//! SR config is applied to the scores the decider recorded with each decision.
+
+use std::collections::HashMap;
+
+use crate::decider::gatewaydecider::ab_test::evaluator::{
+    card_parameters, evaluate_static_algorithm,
+};
+use crate::decider::gatewaydecider::ab_test::{assign_arm, hash_unit_value};
+use crate::decider::gatewaydecider::multi_objective::algorithm::reorder_for_cost;
+use crate::decider::gatewaydecider::multi_objective::hypersense_client::PspCost;
+use crate::decider::gatewaydecider::multi_objective::latency::LatencyInputs;
+use crate::decider::gatewaydecider::runner;
+use crate::decider::gatewaydecider::types::{
+    DomainDeciderRequest, DomainDeciderRequestForApiCallV2,
+};
+use crate::euclid::ast::ValueType;
+use crate::euclid::types::{Context, SrConfigOverride, StaticRoutingAlgorithm};
+
+use super::source::HistoricalDecision;
+use super::{ChangedDecision, GatewayShareShift, ProjectedCost, RoutingReplayResponse};
+
+/// Reasons a decision can't be replayed, reported with their counts.
+pub const SKIP_NO_SCORES: &str = "no_recorded_scores";
+pub const SKIP_NO_GATEWAY: &str = "candidate_chose_no_gateway";
+
+/// The config a replay tries.
+#[derive(Debug, Clone)]
+pub enum ReplayCandidate {
+    /// A routing algorithm; `id` names it in logs.
+    Algorithm {
+        id: String,
+        algorithm: StaticRoutingAlgorithm,
+    },
+    /// SR overrides, applied to the scores recorded with each decision.
+    SrConfig(SrConfigOverride),
+    /// A priority-logic script.
+    PriorityLogic(String),
+}
+
+pub struct Replayer {
+    pub candidate: ReplayCandidate,
+    /// The algorithms behind a candidate experiment's static arms, loaded up front.
+    pub arm_algorithms: HashMap<String, StaticRoutingAlgorithm>,
+    /// The merchant's margin, for a multi-objective replay of a decision that recorded none.
+    pub default_margin: f64,
+}
+
+/// What the candidate would have done with a payment.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CandidateDecision {
+    pub gateway: String,
+    pub rule_name: Option<String>,
+}
+
+impl Replayer {
+    /// `costs` prices the gateways the decision scored, for a multi-objective candidate.
+    pub async fn decide(
+        &self,
+        decision: &HistoricalDecision,
+        domain: &DomainDeciderRequest,
+        costs: &HashMap<String, PspCost>,
+    ) -> Result<CandidateDecision, &'static str> {
+        match &self.candidate {
+            ReplayCandidate::Algorithm { id, algorithm } => {
+                self.decide_algorithm(id, algorithm, decision, costs)
+            }
+            ReplayCandidate::SrConfig(config) => {
+                decide_sr(Some(config), decision, costs, self.default_margin)
+            }
+            ReplayCandidate::PriorityLogic(script) => {
+                decide_priority_logic(script, decision, domain).await
+            }
+        }
+    }
+
+    fn decide_algorithm(
+        &self,
+        id: &str,
+        algorithm: &StaticRoutingAlgorithm,
+        decision: &HistoricalDecision,
+        costs: &HashMap<String, PspCost>,
+    ) -> Result<CandidateDecision, &'static str> {
+        let StaticRoutingAlgorithm::AbTest(experiment) = algorithm else {
+            return evaluate(id, algorithm, &decision.request);
+        };
+        let request = &decision.request;
+        let unit = hash_unit_value(
+            experiment.hash_unit,
+            request.payment_id(),
+            request.customer_id(),
+            request.card_fingerprint(),
+        );
+        let arm = assign_arm(experiment, unit).ok_or(SKIP_NO_GATEWAY)?;
+        let decided = if arm.algorithm_id == "sr_routing" {
+            decide_sr(arm.sr_config.as_ref(), decision, costs, self.default_margin)?
+        } else {
+            let arm_algorithm = self
+                .arm_algorithms
+                .get(&arm.algorithm_id)
+                .ok_or(SKIP_NO_GATEWAY)?;
+            evaluate(&arm.algorithm_id, arm_algorithm, request)?
+        };
+        Ok(CandidateDecision {
+            gateway: decided.gateway,
+            rule_name: Some(match decided.rule_name {
+                Some(rule) => format!("{}: {rule}", arm.name),
+                None => arm.name,
+            }),
+        })
+    }
+}
+
+fn evaluate(
+    id: &str,
+    algorithm: &StaticRoutingAlgorithm,
+    request: &DomainDeciderRequestForApiCallV2,
+) -> Result<CandidateDecision, &'static str> {
+    let result = evaluate_static_algorithm(
+        algorithm,
+        id,
+        request.payment_id(),
+        &replay_context(request),
+    )
+    .ok_or(SKIP_NO_GATEWAY)?;
+    Ok(CandidateDecision {
+        gateway: result.decided_gateway,
+        // Only a rule of an advanced program says anything the gateway doesn't.
+        rule_name: result
+            .rule_name
+            .filter(|_| matches!(algorithm, StaticRoutingAlgorithm::Advanced(_))),
+    })
+}
+
+/// The euclid parameters a recorded request carries: the card dimensions an A/B arm is evaluated
+/// on, plus amount, currency, card network, BIN and authentication type. A rule on any other key
+/// doesn't match, as when the key is missing from a live evaluate request.
+fn replay_context(request: &DomainDeciderRequestForApiCallV2) -> Context {
+    let mut params = card_parameters(request);
+    params.insert(
+        "amount".to_string(),
+        Some(ValueType::Number(request.payment_info.amount.round() as u64)),
+    );
+    params.insert(
+        "currency".to_string(),
+        Some(ValueType::EnumVariant(request.currency())),
+    );
+    if let Some(network) = request.card_network() {
+        params.insert(
+            "card_network".to_string(),
+            Some(ValueType::EnumVariant(network)),
+        );
+    }
+    if let Some(bin) = request.payment_info.card_isin.as_deref() {
+        if let Some(bin) = bin.get(..6) {
+            params.insert(
+                "card_bin".to_string(),
+                Some(ValueType::StrValue(bin.to_string())),
+            );
+        }
+    }
+    if let Some(auth_type) = request.auth_type() {
+        params.insert(
+            "authentication_type".to_string(),
+            Some(ValueType::EnumVariant(auth_type.to_lowercase())),
+        );
+    }
+    Context::new(params)
+}
+
+/// SR routing over the recorded scores, with `config`'s elimination threshold and multi-objective
+/// settings. Hedging is random exploration and isn't replayed.
+fn decide_sr(
+    config: Option<&SrConfigOverride>,
+    decision: &HistoricalDecision,
+    costs: &HashMap<String, PspCost>,
+    default_margin: f64,
+) -> Result<CandidateDecision, &'static str> {
+    if decision.scores.is_empty() {
+        return Err(SKIP_NO_SCORES);
+    }
+    let scores = eliminate(
+        &decision.scores,
+        config.and_then(|config| config.elimination_threshold),
+    );
+
+    // Unless the candidate says otherwise, the post-step runs where it ran when the decision was
+    // made, at the margin it was made with.
+    let multi_objective = config
+        .and_then(|config| config.enable_multi_objective)
+        .unwrap_or(decision.multi_objective_margin.is_some());
+    if multi_objective {
+        let margin = config
+            .and_then(|config| config.margin)
+            .or(decision.multi_objective_margin)
+            .unwrap_or(default_margin);
+        // The latency windows have moved on since; the candidate is ranked on auth and cost.
+        let outcome = reorder_for_cost(&scores, margin, costs, &LatencyInputs::default(), None);
+        if let Some(cost_decision) = outcome.cost_decision {
+            let rule = if outcome.head_moved {
+                "multi_objective_cost_won"
+            } else {
+                "multi_objective_auth_won"
+            };
+            return Ok(CandidateDecision {
+                gateway: cost_decision.chosen,
+                rule_name: Some(rule.to_string()),
+            });
+        }
+    }
+
+    let gateway = sr_head(scores.iter()).ok_or(SKIP_NO_GATEWAY)?;
+    Ok(CandidateDecision {
+        gateway,
+        rule_name: None,
+    })
+}
+
+/// Drops the gateways scoring below `threshold`, unless that would drop them all.
+fn eliminate(scores: &HashMap<String, f64>, threshold: Option<f64>) -> HashMap<String, f64> {
+    let Some(threshold) = threshold else {
+        return scores.clone();
+    };
+    let kept: HashMap<String, f64> = scores
+        .iter()
+        .filter(|(_, score)| **score >= threshold)
+        .map(|(gateway, score)| (gateway.clone(), *score))
+        .collect();
+    if kept.is_empty() {
+        scores.clone()
+    } else {
+        kept
+    }
+}
+
+/// The best-scoring gateway. The decider breaks a tie at random; a replay has to be repeatable, so
+/// here the lowest name wins it.
+fn sr_head<'a>(scores: impl Iterator<Item = (&'a String, &'a f64)>) -> Option<String> {
+    scores
+        .max_by(|(a_gateway, a), (b_gateway, b)| {
+            a.partial_cmp(b)
+                .unwrap_or(std::cmp::Ordering::Equal)
+                .then_with(|| b_gateway.cmp(a_gateway))
+        })
+        .map(|(gateway, _)| gateway.clone())
+}
+
+/// Runs the script through the priority-logic runner, as the decider would for a merchant that
+/// routes by code. The decider then ranks the script's gateways by SR; of those the recorded
+/// decision scored, the best wins, else the script's first.
+async fn decide_priority_logic(
+    script: &str,
+    decision: &HistoricalDecision,
+    domain: &DomainDeciderRequest,
+) -> Result<CandidateDecision, &'static str> {
+    let mut merchant = domain.merchantAccount.clone();
+    merchant.useCodeForGatewayPriority = true;
+    let output = runner::get_gateway_priority(
+        merchant,
+        domain.orderReference.clone(),
+        domain.txnDetail.clone(),
+        domain.txnCardInfo.clone(),
+        None,
+        domain.orderMetadata.metadata.clone(),
+        Some(script.to_string()),
+    )
+    .await;
+
+    let scored = output
+        .gws
+        .iter()
+        .filter_map(|gateway| decision.scores.get_key_value(gateway));
+    let gateway = sr_head(scored)
+        .or_else(|| output.gws.first().cloned())
+        .ok_or(SKIP_NO_GATEWAY)?;
+    Ok(CandidateDecision {
+        gateway,
+        rule_name: output.priority_logic_tag,
+    })
+}
+
+#[derive(Debug, Default)]
+struct CostTotals {
+    priced: usize,
+    volume: f64,
+    recorded_fees: f64,
+    candidate_fees: f64,
+}
+
+/// Accumulates the replayed decisions into the report.
+#[derive(Debug, Default)]
+pub struct ReplayTally {
+    sample_limit: usize,
+    replayed: usize,
+    changed: usize,
+    recorded: HashMap<String, usize>,
+    candidate: HashMap<String, usize>,
+    costs: HashMap<String, CostTotals>,
+    changed_decisions: Vec<ChangedDecision>,
+    skipped: HashMap<&'static str, usize>,
+}
+
+impl ReplayTally {
+    /// Keeps at most `sample_limit` changed decisions for the report.
+    pub fn new(sample_limit: usize) -> Self {
+        Self {
+            sample_limit,
+            ..Self::default()
+        }
+    }
+
+    pub fn skip(&mut self, reason: &'static str, count: usize) {
+        *self.skipped.entry(reason).or_default() += count;
+    }
+
+    /// `costs` prices the gateways of both decisions, where a price is known.
+    pub fn record(
+        &mut self,
+        decision: &HistoricalDecision,
+        candidate: CandidateDecision,
+        costs: &HashMap<String, PspCost>,
+    ) {
+        let cost_bps = |gateway: &str| {
+            costs
+                .get(gateway)
+                .filter(|cost| cost.available)
+                .map(|cost| cost.effective_cost_bps)
+        };
+        let recorded_bps = cost_bps(&decision.decided_gateway);
+        let candidate_bps = cost_bps(&candidate.gateway);
+
+        self.replayed += 1;
+        *self
+            .recorded
+            .entry(decision.decided_gateway.clone())
+            .or_default() += 1;
+        *self.candidate.entry(candidate.gateway.clone()).or_default() += 1;
+
+        // Fees are compared only where both gateways are priced, so the two totals cover the
+        // same payments.
+        if let (Some(recorded_bps), Some(candidate_bps)) = (recorded_bps, candidate_bps) {
+            let amount = decision.request.payment_info.amount;
+            let totals = self.costs.entry(decision.request.currency()).or_default();
+            totals.priced += 1;
+            totals.volume += amount;
+            totals.recorded_fees += amount * recorded_bps / 10_000.0;
+            totals.candidate_fees += amount * candidate_bps / 10_000.0;
+        }
+
+        if candidate.gateway == decision.decided_gateway {
+            return;
+        }
+        self.changed += 1;
+        if self.changed_decisions.len() < self.sample_limit {
+            self.changed_decisions.push(ChangedDecision {
+                payment_id: decision.request.payment_id().to_string(),
+                created_at_ms: decision.created_at_ms,
+                recorded_gateway: decision.decided_gateway.clone(),
+                candidate_gateway: candidate.gateway,
+                rule_name: candidate.rule_name,
+                recorded_cost_bps: recorded_bps,
+                candidate_cost_bps: candidate_bps,
+            });
+        }
+    }
+
+    pub fn finish(self) -> RoutingReplayResponse {
+        let share = |count: usize| percent(count as f64, self.replayed as f64);
+
+        let mut gateways: Vec<&String> =
+            self.recorded.keys().chain(self.candidate.keys()).collect();
+        gateways.sort();
+        gateways.dedup();
+        let mut gateway_share: Vec<GatewayShareShift> = gateways
+            .into_iter()
+            .map(|gateway| {
+                let recorded = self.recorded.get(gateway).copied().unwrap_or_default();
+                let candidate = self.candidate.get(gateway).copied().unwrap_or_default();
+                GatewayShareShift {
+                    gateway: gateway.clone(),
+                    recorded,
+                    candidate,
+                    recorded_share_pct: share(recorded),
+                    candidate_share_pct: share(candidate),
+                    shift_pp: share(candidate) - share(recorded),
+                }
+            })
+            .collect();
+        gateway_share.sort_by(|a, b| {
+            b.recorded
+                .cmp(&a.recorded)
+                .then(b.candidate.cmp(&a.candidate))
+        });
+
+        let mut projected_cost: Vec<ProjectedCost> = self
+            .costs
+            .into_iter()
+            .map(|(currency, totals)| ProjectedCost {
+                currency,
+                priced: totals.priced,
+                volume: totals.volume,
+                recorded_fees: totals.recorded_fees,
+                candidate_fees: totals.candidate_fees,
+                fee_delta: totals.candidate_fees - totals.recorded_fees,
+                recorded_bps: percent(totals.recorded_fees, totals.volume) * 100.0,
+                candidate_bps: percent(totals.candidate_fees, totals.volume) * 100.0,
+            })
+            .collect();
+        projected_cost.sort_by(|a, b| a.currency.cmp(&b.currency));
+
+        RoutingReplayResponse {
+            replayed: self.replayed,
+            changed: self.changed,
+            changed_pct: percent(self.changed as f64, self.replayed as f64),
+            skipped: self
+                .skipped
+                .into_iter()
+                .map(|(reason, count)| (reason.to_string(), count))
+                .collect(),
+            gateway_share,
+            projected_cost,
+            changed_decisions: self.changed_decisions,
+        }
+    }
+}
+
+fn percent(part: f64, whole: f64) -> f64 {
+    if whole > 0.0 {
+        part / whole * 100.0
+    } else {
+        0.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::decider::gatewaydecider::multi_objective::CostSource;
+
+    fn decision(decided: &str, scores: &[(&str, f64)]) -> HistoricalDecision {
+        let line = serde_json::json!({
+            "request": {
+                "merchantId": "merchant_1",
+                "paymentInfo": {
+                    "paymentId": "pay_1",
+                    "amount": 200.0,
+                    "currency": "USD",
+                    "paymentType": "ORDER_PAYMENT",
+                    "paymentMethodType": "card",
+                    "paymentMethod": "credit",
+                },
+            },
+            "response": { "decided_gateway": decided },
+        });
+        let mut decision = crate::replay::source::parse_line(&line.to_string()).expect("decision");
+        decision.scores = scores
+            .iter()
+            .map(|(gateway, score)| (gateway.to_string(), *score))
+            .collect();
+        decision
+    }
+
+    fn costs(pairs: &[(&str, f64)]) -> HashMap<String, PspCost> {
+        pairs
+            .iter()
+            .map(|(gateway, bps)| {
+                (
+                    gateway.to_string(),
+                    PspCost {
+                        available: true,
+                        effective_cost_bps: *bps,
+                        stderr_bps: None,
+                        source: CostSource::Seed,
+                        cost_model: None,
+                    },
+                )
+            })
+            .collect()
+    }
+
+    fn sr_config(threshold: Option<f64>, multi_objective: Option<bool>) -> SrConfigOverride {
+        SrConfigOverride {
+            hedging_percent: None,
+            elimination_threshold: threshold,
+            enable_multi_objective: multi_objective,
+            margin: None,
+            use_autopilot: None,
+        }
+    }
+
+    #[test]
+    fn elimination_moves_the_head_only_above_the_threshold() {
+        let recorded = decision(
+            "stripe",
+            &[("stripe", 0.9), ("adyen", 0.9), ("paypal", 0.5)],
+        );
+
+        // Ties go to the lowest name, so the replay is repeatable.
+        let head = decide_sr(None, &recorded, &HashMap::new(), 1.0).expect("head");
+        assert_eq!(head.gateway, "adyen");
+
+        let all_below = sr_config(Some(0.95), None);
+        let kept = decide_sr(Some(&all_below), &recorded, &HashMap::new(), 1.0).expect("kept");
+        assert_eq!(kept.gateway, "adyen");
+
+        let no_scores = decision("stripe", &[]);
+        assert_eq!(
+            decide_sr(None, &no_scores, &HashMap::new(), 1.0).err(),
+            Some(SKIP_NO_SCORES)
+        );
+    }
+
+    #[test]
+    fn multi_objective_candidate_trades_auth_for_cost() {
+        let recorded = decision("stripe", &[("stripe", 0.90), ("adyen", 0.899)]);
+        let prices = costs(&[("stripe", 300.0), ("adyen", 100.0)]);
+
+        let cost_on = sr_config(None, Some(true));
+        let decided = decide_sr(Some(&cost_on), &recorded, &prices, 1.0).expect("decided");
+        assert_eq!(decided.gateway, "adyen");
+        assert_eq!(
+            decided.rule_name.as_deref(),
+            Some("multi_objective_cost_won")
+        );
+
+        let auth_only = decide_sr(None, &recorded, &prices, 1.0).expect("decided");
+        assert_eq!(auth_only.gateway, "stripe");
+    }
+
+    #[test]
+    fn tally_reports_share_shift_changes_and_fees() {
+        let prices = costs(&[("stripe", 300.0), ("adyen", 100.0)]);
+        let mut tally = ReplayTally::new(1);
+        for _ in 0..3 {
+            let recorded = decision("stripe", &[]);
+            let candidate = CandidateDecision {
+                gateway: "adyen".to_string(),
+                rule_name: None,
+            };
+            tally.record(&recorded, candidate, &prices);
+        }
+        tally.record(
+            &decision("stripe", &[]),
+            CandidateDecision {
+                gateway: "stripe".to_string(),
+                rule_name: None,
+            },
+            &prices,
+        );
+        tally.skip(SKIP_NO_SCORES, 2);
+
+        let report = tally.finish();
+        assert_eq!(report.replayed, 4);
+        assert_eq!(report.changed, 3);
+        assert_eq!(report.changed_decisions.len(), 1);
+        assert_eq!(report.skipped[SKIP_NO_SCORES], 2);
+
+        let adyen = report
+            .gateway_share
+            .iter()
+            .find(|share| share.gateway == "adyen")
+            .expect("adyen");
+        assert_eq!((adyen.recorded, adyen.candidate), (0, 3));
+        assert!((adyen.shift_pp - 75.0).abs() < 1e-9);
+
+        let usd = &report.projected_cost[0];
+        assert_eq!(usd.priced, 4);
+        assert!((usd.recorded_fees - 24.0).abs() < 1e-9);
+        assert!((usd.candidate_fees - 12.0).abs() < 1e-9);
+        assert!((usd.candidate_bps - 150.0).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,247 @@
+//! Offline routing replay: what a candidate config would have done with a merchant's recorded
+//! traffic.
+//!
+//! The history is the decisions `/decide-gateway` recorded, read from the analytics store or a JSONL
+//! export (see [`source`]). Each is re-decided under the candidate (see [`engine`]) without touching
+//! Redis or emitting events, and the report gives the shift in gateway share, the decisions that
+//! changed, and the fees each side would have paid where the cost model prices both gateways.
+
+pub mod engine;
+pub mod source;
+
+#[cfg(feature = "mysql")]
+use crate::storage::schema::routing_algorithm::dsl;
+#[cfg(feature = "postgres")]
+use crate::storage::schema_pg::routing_algorithm::dsl;
+
+use std::collections::{BTreeMap, HashMap};
+
+use diesel::{associations::HasTable, BoolExpressionMethods, ExpressionMethods};
+use serde::{Deserialize, Serialize};
+
+use crate::app::get_tenant_app_state;
+use crate::decider::gatewaydecider::flow_new::load_margin;
+use crate::decider::gatewaydecider::multi_objective::cluster_key::derive_cluster_key;
+use crate::decider::gatewaydecider::multi_objective::hypersense_client::lookup_costs;
+use crate::euclid::errors::EuclidErrors;
+use crate::euclid::types::{RoutingAlgorithm, SrConfigOverride, StaticRoutingAlgorithm};
+use crate::types::merchant::merchant_account::load_merchant_by_merchant_id;
+
+use engine::{ReplayCandidate, ReplayTally, Replayer};
+use source::ReplaySource;
+
+/// Decisions replayed when the request sets no `limit`, and the most it may set.
+const DEFAULT_LIMIT: usize = 10_000;
+const MAX_LIMIT: usize = 100_000;
+/// Changed decisions listed in the report when the request sets no `sample_limit`.
+const DEFAULT_SAMPLE_LIMIT: usize = 100;
+
+/// A replay of `created_by`'s recorded decisions under one candidate: an inline `algorithm`, a
+/// stored `algorithm_id`, an `sr_config`, or a `priority_logic_script`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct RoutingReplayRequest {
+    pub created_by: String,
+    pub source: ReplaySource,
+    #[serde(default)]
+    pub algorithm: Option<StaticRoutingAlgorithm>,
+    #[serde(default)]
+    pub algorithm_id: Option<String>,
+    #[serde(default)]
+    pub sr_config: Option<SrConfigOverride>,
+    #[serde(default)]
+    pub priority_logic_script: Option<String>,
+    /// Most decisions to replay, oldest first. Defaults to 10,000; at most 100,000.
+    #[serde(default)]
+    pub limit: Option<usize>,
+    /// Most changed decisions to list. Defaults to 100.
+    #[serde(default)]
+    pub sample_limit: Option<usize>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RoutingReplayResponse {
+    pub replayed: usize,
+    pub changed: usize,
+    pub changed_pct: f64,
+    /// Decisions left out, by reason.
+    pub skipped: BTreeMap<String, usize>,
+    /// Every gateway either side routed to, busiest recorded first.
+    pub gateway_share: Vec<GatewayShareShift>,
+    /// Per currency, over the decisions where both gateways are priced.
+    pub projected_cost: Vec<ProjectedCost>,
+    /// The first `sample_limit` decisions the candidate changed.
+    pub changed_decisions: Vec<ChangedDecision>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct GatewayShareShift {
+    pub gateway: String,
+    pub recorded: usize,
+    pub candidate: usize,
+    pub recorded_share_pct: f64,
+    pub candidate_share_pct: f64,
+    /// Candidate share less recorded share, in percentage points.
+    pub shift_pp: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ProjectedCost {
+    pub currency: String,
+    /// Decisions priced on both sides.
+    pub priced: usize,
+    pub volume: f64,
+    pub recorded_fees: f64,
+    pub candidate_fees: f64,
+    /// Candidate fees less recorded fees; negative is a saving.
+    pub fee_delta: f64,
+    pub recorded_bps: f64,
+    pub candidate_bps: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ChangedDecision {
+    pub payment_id: String,
+    pub created_at_ms: i64,
+    pub recorded_gateway: String,
+    pub candidate_gateway: String,
+    pub rule_name: Option<String>,
+    pub recorded_cost_bps: Option<f64>,
+    pub candidate_cost_bps: Option<f64>,
+}
+
+/// Replays the request's history under its candidate.
+pub async fn replay(request: RoutingReplayRequest) -> Result<RoutingReplayResponse, EuclidErrors> {
+    // Building a decider request looks the merchant up and can't do without it.
+    if load_merchant_by_merchant_id(request.created_by.clone())
+        .await
+        .is_none()
+    {
+        return Err(EuclidErrors::InvalidRequest(format!(
+            "merchant {} not found",
+            request.created_by
+        )));
+    }
+
+    let candidate = candidate(&request).await?;
+    let arm_algorithms = arm_algorithms(&request.created_by, &candidate).await?;
+    let replayer = Replayer {
+        candidate,
+        arm_algorithms,
+        default_margin: load_margin(&request.created_by).await,
+    };
+
+    let limit = request.limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT);
+    let history = source::load(&request.source, &request.created_by, limit).await?;
+
+    let mut tally = ReplayTally::new(request.sample_limit.unwrap_or(DEFAULT_SAMPLE_LIMIT));
+    for (reason, count) in history.skipped {
+        tally.skip(reason, count);
+    }
+    for decision in history.decisions {
+        let domain = decision.request.to_domain_decider_request().await;
+        let cluster = derive_cluster_key(&domain.txnDetail, &domain.txnCardInfo);
+        let mut gateways: Vec<String> = decision.scores.keys().cloned().collect();
+        if !decision.scores.contains_key(&decision.decided_gateway) {
+            gateways.push(decision.decided_gateway.clone());
+        }
+        let mut costs = lookup_costs(&request.created_by, &cluster, &gateways).await;
+
+        let candidate = match replayer.decide(&decision, &domain, &costs).await {
+            Ok(candidate) => candidate,
+            Err(reason) => {
+                tally.skip(reason, 1);
+                continue;
+            }
+        };
+        if !costs.contains_key(&candidate.gateway) {
+            costs.extend(
+                lookup_costs(
+                    &request.created_by,
+                    &cluster,
+                    std::slice::from_ref(&candidate.gateway),
+                )
+                .await,
+            );
+        }
+        tally.record(&decision, candidate, &costs);
+    }
+    Ok(tally.finish())
+}
+
+async fn candidate(request: &RoutingReplayRequest) -> Result<ReplayCandidate, EuclidErrors> {
+    let given = [
+        request.algorithm.is_some(),
+        request.algorithm_id.is_some(),
+        request.sr_config.is_some(),
+        request.priority_logic_script.is_some(),
+    ];
+    if given.into_iter().filter(|given| *given).count() != 1 {
+        return Err(EuclidErrors::InvalidRequest(
+            "give exactly one of algorithm, algorithm_id, sr_config and priority_logic_script"
+                .to_string(),
+        ));
+    }
+
+    if let Some(algorithm) = &request.algorithm {
+        return Ok(ReplayCandidate::Algorithm {
+            id: "candidate".to_string(),
+            algorithm: algorithm.clone(),
+        });
+    }
+    if let Some(id) = &request.algorithm_id {
+        return Ok(ReplayCandidate::Algorithm {
+            id: id.clone(),
+            algorithm: load_algorithm(&request.created_by, id).await?,
+        });
+    }
+    if let Some(config) = &request.sr_config {
+        return Ok(ReplayCandidate::SrConfig(config.clone()));
+    }
+    Ok(ReplayCandidate::PriorityLogic(
+        request.priority_logic_script.clone().unwrap_or_default(),
+    ))
+}
+
+/// The algorithms behind a candidate experiment's static arms.
+async fn arm_algorithms(
+    created_by: &str,
+    candidate: &ReplayCandidate,
+) -> Result<HashMap<String, StaticRoutingAlgorithm>, EuclidErrors> {
+    let mut algorithms = HashMap::new();
+    let ReplayCandidate::Algorithm {
+        algorithm: StaticRoutingAlgorithm::AbTest(experiment),
+        ..
+    } = candidate
+    else {
+        return Ok(algorithms);
+    };
+    for arm in experiment.arms() {
+        if arm.algorithm_id == "sr_routing" || algorithms.contains_key(&arm.algorithm_id) {
+            continue;
+        }
+        let algorithm = load_algorithm(created_by, &arm.algorithm_id).await?;
+        algorithms.insert(arm.algorithm_id, algorithm);
+    }
+    Ok(algorithms)
+}
+
+async fn load_algorithm(
+    created_by: &str,
+    algorithm_id: &str,
+) -> Result<StaticRoutingAlgorithm, EuclidErrors> {
+    let state = get_tenant_app_state().await;
+    let algorithm = crate::generics::generic_find_one::<
+        <RoutingAlgorithm as HasTable>::Table,
+        _,
+        RoutingAlgorithm,
+    >(
+        &state.db,
+        dsl::id
+            .eq(algorithm_id.to_string())
+            .and(dsl::created_by.eq(created_by.to_string())),
+    )
+    .await
+    .map_err(|_| EuclidErrors::RoutingAlgorithmNotFound(algorithm_id.to_string()))?;
+    serde_json::from_str(&algorithm.algorithm_data)
+        .map_err(|_| EuclidErrors::FailedToParseJsonInput)
+}
@@ -0,0 +1,261 @@
+//! Where the replay reads its history from: the decisions recorded in the analytics read store, or
+//! an export of them as JSONL.
+//!
+//! Either way a decision is the `details` of a `decide_gateway_decision` event, the request the
+//! decider was called with and the response it gave. A JSONL line may be the whole event row (as
+//! exported from the store) or just its details.
+
+use std::collections::HashMap;
+
+use serde::Deserialize;
+use serde_json::Value;
+
+use crate::analytics::flow::FlowType;
+use crate::decider::gatewaydecider::types::DomainDeciderRequestForApiCallV2;
+use crate::euclid::errors::EuclidErrors;
+
+/// How far back an analytics replay reads when no `start_ms` is given: a week.
+const DEFAULT_WINDOW_MS: i64 = 7 * 24 * 60 * 60 * 1000;
+
+/// Reasons a record is left out of the replay, reported with their counts.
+pub const SKIP_UNREADABLE: &str = "unreadable";
+pub const SKIP_NOT_A_DECISION: &str = "not_a_decision";
+pub const SKIP_OTHER_MERCHANT: &str = "other_merchant";
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "type", content = "data", rename_all = "snake_case")]
+pub enum ReplaySource {
+    /// Decisions recorded in the analytics read store, by default over the last seven days.
+    Analytics {
+        #[serde(default)]
+        start_ms: Option<i64>,
+        #[serde(default)]
+        end_ms: Option<i64>,
+    },
+    /// An export of decision events, one JSON object per line.
+    Jsonl(String),
+}
+
+/// One recorded decision, as the replay needs it.
+#[derive(Debug, Clone)]
+pub struct HistoricalDecision {
+    pub created_at_ms: i64,
+    pub request: DomainDeciderRequestForApiCallV2,
+    pub decided_gateway: String,
+    /// The SR score of each gateway the decider ranked. Empty when the decision was not scored,
+    /// as with priority logic or a static experiment arm.
+    pub scores: HashMap<String, f64>,
+    /// The margin the multi-objective post-step applied, when it ran.
+    pub multi_objective_margin: Option<f64>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RecordedDetails {
+    request: Value,
+    response: RecordedResponse,
+}
+
+#[derive(Debug, Deserialize)]
+struct RecordedResponse {
+    decided_gateway: String,
+    #[serde(default)]
+    gateway_priority_map: Option<HashMap<String, f64>>,
+    #[serde(default)]
+    multi_objective_info: Option<RecordedMultiObjective>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RecordedMultiObjective {
+    margin: f64,
+}
+
+/// The records read, and a count per reason of those left out.
+#[derive(Debug, Default)]
+pub struct History {
+    pub decisions: Vec<HistoricalDecision>,
+    pub skipped: HashMap<&'static str, usize>,
+}
+
+impl History {
+    fn push(&mut self, merchant_id: &str, record: Result<HistoricalDecision, &'static str>) {
+        match record {
+            Ok(decision) if decision.request.merchant_id == merchant_id => {
+                self.decisions.push(decision)
+            }
+            Ok(_) => *self.skipped.entry(SKIP_OTHER_MERCHANT).or_default() += 1,
+            Err(reason) => *self.skipped.entry(reason).or_default() += 1,
+        }
+    }
+}
+
+/// Reads up to `limit` of `merchant_id`'s decisions from `source`.
+pub async fn load(
+    source: &ReplaySource,
+    merchant_id: &str,
+    limit: usize,
+) -> Result<History, EuclidErrors> {
+    let mut history = History::default();
+    match source {
+        ReplaySource::Analytics { start_ms, end_ms } => {
+            let runtime = &crate::app::APP_STATE
+                .get()
+                .ok_or(EuclidErrors::StorageError)?
+                .analytics_runtime;
+            if !runtime.read_enabled() {
+                return Err(EuclidErrors::InvalidRequest(
+                    "the analytics read store is not enabled; replay a jsonl export instead"
+                        .to_string(),
+                ));
+            }
+            let end_ms = end_ms.unwrap_or_else(crate::analytics::now_ms);
+            let start_ms = start_ms.unwrap_or(end_ms - DEFAULT_WINDOW_MS);
+            let recorded = runtime
+                .read_store()
+                .recorded_decisions(merchant_id, start_ms, end_ms, limit as u64)
+                .await
+                .map_err(|_| EuclidErrors::StorageError)?;
+            for record in recorded {
+                let decision = serde_json::from_str(&record.details)
+                    .map_err(|_| SKIP_UNREADABLE)
+                    .and_then(|details| parse_details(details, record.created_at_ms));
+                history.push(merchant_id, decision);
+            }
+        }
+        ReplaySource::Jsonl(text) => {
+            let lines = text.lines().filter(|line| !line.trim().is_empty());
+            for line in lines.take(limit) {
+                history.push(merchant_id, parse_line(line));
+            }
+        }
+    }
+    Ok(history)
+}
+
+/// Parses one JSONL line: an exported event row, whose `details` is a JSON string, or the details
+/// object on its own.
+pub fn parse_line(line: &str) -> Result<HistoricalDecision, &'static str> {
+    let value: Value = serde_json::from_str(line).map_err(|_| SKIP_UNREADABLE)?;
+    let created_at_ms = value
+        .get("created_at_ms")
+        .and_then(Value::as_i64)
+        .unwrap_or_default();
+    match value.get("details") {
+        Some(details) => {
+            let flow_type = value.get("flow_type").and_then(Value::as_str);
+            if flow_type.is_some_and(|flow| flow != FlowType::DecideGatewayDecision.as_str()) {
+                return Err(SKIP_NOT_A_DECISION);
+            }
+            let details = details.as_str().ok_or(SKIP_NOT_A_DECISION)?;
+            let details = serde_json::from_str(details).map_err(|_| SKIP_UNREADABLE)?;
+            parse_details(details, created_at_ms)
+        }
+        None => parse_details(value, created_at_ms),
+    }
+}
+
+fn parse_details(details: Value, created_at_ms: i64) -> Result<HistoricalDecision, &'static str> {
+    if details.get("response").is_none() {
+        return Err(SKIP_NOT_A_DECISION);
+    }
+    let RecordedDetails {
+        mut request,
+        response,
+    } = serde_json::from_value(details).map_err(|_| SKIP_UNREADABLE)?;
+    restore_udfs(&mut request);
+    let request = serde_json::from_value(request).map_err(|_| SKIP_UNREADABLE)?;
+    Ok(HistoricalDecision {
+        created_at_ms,
+        request,
+        decided_gateway: response.decided_gateway,
+        scores: response.gateway_priority_map.unwrap_or_default(),
+        multi_objective_margin: response.multi_objective_info.map(|info| info.margin),
+    })
+}
+
+/// UDFs arrive as a list but are recorded as the index-keyed map they are held in; turn the map
+/// back into the list so the request parses as it did when it was made.
+fn restore_udfs(request: &mut Value) {
+    let Some(udfs) = request.pointer_mut("/paymentInfo/udfs") else {
+        return;
+    };
+    let Some(map) = udfs.as_object() else {
+        return;
+    };
+    let entries: Vec<(usize, Value)> = map
+        .iter()
+        .filter_map(|(index, value)| Some((index.parse().ok()?, value.clone())))
+        .collect();
+    let len = entries
+        .iter()
+        .map(|(index, _)| index + 1)
+        .max()
+        .unwrap_or(0);
+    let mut list = vec![Value::Null; len];
+    for (index, value) in entries {
+        list[index] = value;
+    }
+    *udfs = Value::Array(list);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn details(udfs: Value) -> Value {
+        serde_json::json!({
+            "request": {
+                "merchantId": "merchant_1",
+                "paymentInfo": {
+                    "paymentId": "pay_1",
+                    "amount": 100.0,
+                    "currency": "USD",
+                    "paymentType": "ORDER_PAYMENT",
+                    "paymentMethodType": "card",
+                    "paymentMethod": "credit",
+                    "udfs": udfs,
+                },
+            },
+            "response": {
+                "decided_gateway": "stripe",
+                "gateway_priority_map": { "stripe": 0.92, "adyen": 0.9 },
+                "multi_objective_info": null,
+            },
+        })
+    }
+
+    #[test]
+    fn reads_details_and_exported_rows() {
+        let bare = parse_line(&details(Value::Null).to_string()).expect("details");
+        assert_eq!(bare.decided_gateway, "stripe");
+        assert_eq!(bare.scores["adyen"], 0.9);
+        assert_eq!(bare.multi_objective_margin, None);
+
+        let row = serde_json::json!({
+            "flow_type": "decide_gateway_decision",
+            "created_at_ms": 1_700_000_000_000i64,
+            "details": details(Value::Null).to_string(),
+        });
+        let decision = parse_line(&row.to_string()).expect("row");
+        assert_eq!(decision.created_at_ms, 1_700_000_000_000);
+        assert_eq!(decision.request.payment_id(), "pay_1");
+    }
+
+    #[test]
+    fn skips_other_events() {
+        let row = serde_json::json!({
+            "flow_type": "decide_gateway_error",
+            "details": details(Value::Null).to_string(),
+        });
+        assert_eq!(
+            parse_line(&row.to_string()).err(),
+            Some(SKIP_NOT_A_DECISION)
+        );
+        assert_eq!(parse_line("{ not json").err(), Some(SKIP_UNREADABLE));
+    }
+
+    #[test]
+    fn recorded_udf_map_parses() {
+        let recorded = details(serde_json::json!({ "0": "gold", "2": "web" }));
+        assert!(parse_line(&recorded.to_string()).is_ok());
+    }
+}
@@ -3,11 +3,13 @@ pub mod hybrid_routing;
 pub mod user_auth;
 // pub mod data;
 pub mod analytics;
+pub mod audit;
 pub mod body;
 pub mod connector_credentials;
 pub mod connector_fees;
 pub mod cost_clusters;
 pub mod cost_coverage;
+pub mod cost_price_alerts;
 pub mod decide_gateway;
 pub mod decision_gateway;
 pub mod gateway_score;
@@ -20,5 +22,7 @@ pub mod report_upload;
 pub mod rule_configuration;
 pub mod seed_costs;
 pub mod settlement_webhook;
+pub mod sso;
+pub mod two_factor;
 pub mod update_gateway_score;
 pub mod update_score;
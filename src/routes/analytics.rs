@@ -6,7 +6,7 @@ use crate::analytics::{
     overview as fetch_overview, payment_audit as fetch_payment_audit,
     preview_trace as fetch_preview_trace, routing_events as fetch_routing_events,
     routing_stats as fetch_routing_stats, AnalyticsQuery, AuthBandSpec, ExperimentResultsQuery,
-    ExperimentTransactionsQuery, PaymentAuditQuery, RoutingEventsQuery,
+    ExperimentTransactionsQuery, PaymentAuditQuery, RoutingEventsQuery, SignificanceMethod,
 };
 use crate::custom_extractors::{AuthenticatedAnalyticsContext, TenantStateResolver};
 use crate::error;
@@ -270,11 +270,18 @@ async fn resolve_auth_band(_merchant_id: &str, _explicit: Option<f64>) -> AuthBa
 pub struct ExperimentResultsParams {
     pub start_ms: Option<i64>,
     pub end_ms: Option<i64>,
+    /// Arm compared against: defaults to `control`.
+    pub control_arm: Option<String>,
+    /// Arm under test: defaults to `variant`. Multi-arm experiments pass each arm in turn.
+    pub variant_arm: Option<String>,
     pub min_sample_size: Option<u32>,
     pub guardrail_threshold_pp: Option<f64>,
     /// Common business margin (fraction of ticket) to value net EV for cost/autopilot
     /// experiments. Omitted → `DEFAULT_EVALUATION_MARGIN`.
     pub evaluation_margin: Option<f64>,
+    /// `sequential` for results that stay valid under continuous monitoring. Omitted →
+    /// `fixed_horizon`.
+    pub method: Option<SignificanceMethod>,
 }
 
 pub async fn experiment_results(
@@ -289,11 +296,18 @@ pub async fn experiment_results(
         merchant_id: auth_context.merchant_id.clone(),
         start_ms: params.start_ms,
         end_ms: params.end_ms,
+        control_arm: params
+            .control_arm
+            .unwrap_or_else(|| crate::euclid::types::CONTROL_ARM.to_string()),
+        variant_arm: params
+            .variant_arm
+            .unwrap_or_else(|| crate::euclid::types::VARIANT_ARM.to_string()),
         min_sample_size: params.min_sample_size.unwrap_or(1000),
         guardrail_threshold_pp: params.guardrail_threshold_pp.unwrap_or(3.0),
         evaluation_margin: params.evaluation_margin.unwrap_or(
             crate::analytics::clickhouse::endpoints::experiment_results::DEFAULT_EVALUATION_MARGIN,
         ),
+        method: params.method.unwrap_or_default(),
     };
     Ok(Json(fetch_experiment_results(&state, &query).await?))
 }
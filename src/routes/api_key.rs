@@ -1,9 +1,11 @@
 use crate::app::get_tenant_app_state;
-use crate::auth;
+use crate::auth::{self, AuthContext, Permission};
 use crate::error::{self, ApiKeyError};
-use crate::storage::types::{MerchantApiKey, MerchantApiKeyNew, MerchantApiKeyRevoke};
+use crate::storage::types::{
+    MerchantApiKey, MerchantApiKeyExpiry, MerchantApiKeyNew, MerchantApiKeyRevoke,
+};
 use crate::utils::date_time;
-use axum::{extract::Path, Json};
+use axum::{extract::Path, Extension, Json};
 use diesel::associations::HasTable;
 use diesel::ExpressionMethods;
 use serde::{Deserialize, Serialize};
@@ -14,10 +16,23 @@ use crate::storage::schema::merchant_api_keys::dsl;
 #[cfg(feature = "postgres")]
 use crate::storage::schema_pg::merchant_api_keys::dsl;
 
+/// The longest a rotated-out key may keep working alongside its replacement.
+const MAX_ROTATION_OVERLAP_SECS: u64 = 30 * 24 * 60 * 60;
+const DEFAULT_ROTATION_OVERLAP_SECS: u64 = 24 * 60 * 60;
+
 #[derive(Debug, Deserialize)]
 pub struct CreateApiKeyRequest {
     pub merchant_id: String,
     pub description: Option<String>,
+    /// What the key may do. Absent creates an unrestricted key, as before scopes existed.
+    #[serde(default)]
+    pub permissions: Option<Vec<Permission>>,
+    /// Seconds from now until the key stops working. Absent never expires.
+    #[serde(default)]
+    pub expires_in_secs: Option<u64>,
+    /// Requests a minute the key may make before it is answered with 429. Absent is unlimited.
+    #[serde(default)]
+    pub rate_limit_per_minute: Option<u32>,
 }
 
 #[derive(Debug, Serialize)]
@@ -28,6 +43,9 @@ pub struct CreateApiKeyResponse {
     pub merchant_id: String,
     pub description: Option<String>,
     pub created_at: PrimitiveDateTime,
+    pub permissions: Option<Vec<Permission>>,
+    pub expires_at: Option<PrimitiveDateTime>,
+    pub rate_limit_per_minute: Option<u32>,
 }
 
 #[derive(Debug, Serialize)]
@@ -38,6 +56,27 @@ pub struct ApiKeyListItem {
     pub description: Option<String>,
     pub is_active: bool,
     pub created_at: PrimitiveDateTime,
+    pub permissions: Option<Vec<Permission>>,
+    pub expires_at: Option<PrimitiveDateTime>,
+    pub rate_limit_per_minute: Option<u32>,
+    pub last_used_at: Option<PrimitiveDateTime>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct RotateApiKeyRequest {
+    /// How long the old key keeps working alongside the new one. Defaults to a day.
+    #[serde(default)]
+    pub overlap_secs: Option<u64>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RotateApiKeyResponse {
+    /// The replacement, with the old key's description, permissions and quota.
+    #[serde(flatten)]
+    pub key: CreateApiKeyResponse,
+    pub replaced_key_id: String,
+    /// When the old key stops working.
+    pub replaced_key_expires_at: PrimitiveDateTime,
 }
 
 #[derive(Debug, Serialize)]
@@ -58,14 +97,101 @@ impl From<MerchantApiKey> for ApiKeyListItem {
             #[cfg(feature = "postgres")]
             is_active: k.is_active,
             created_at: k.created_at,
+            permissions: stored_permissions(k.permissions.as_deref()),
+            expires_at: k.expires_at,
+            rate_limit_per_minute: k.rate_limit_per_minute.map(|n| n.max(0) as u32),
+            last_used_at: k.last_used_at,
         }
     }
 }
 
-#[axum::debug_handler]
-pub async fn create_api_key(
-    Json(payload): Json<CreateApiKeyRequest>,
-) -> Result<Json<CreateApiKeyResponse>, error::ContainerError<ApiKeyError>> {
+impl From<&CreateApiKeyResponse> for ApiKeyListItem {
+    fn from(k: &CreateApiKeyResponse) -> Self {
+        Self {
+            key_id: k.key_id.clone(),
+            key_prefix: k.key_prefix.clone(),
+            merchant_id: k.merchant_id.clone(),
+            description: k.description.clone(),
+            is_active: true,
+            created_at: k.created_at,
+            permissions: k.permissions.clone(),
+            expires_at: k.expires_at,
+            rate_limit_per_minute: k.rate_limit_per_minute,
+            last_used_at: None,
+        }
+    }
+}
+
+/// A key's permissions as stored. A value that no longer parses reads as holding nothing, so a
+/// damaged row is never itself the reason a key gains something.
+pub(crate) fn stored_permissions(raw: Option<&str>) -> Option<Vec<Permission>> {
+    raw.map(|raw| serde_json::from_str(raw).unwrap_or_default())
+}
+
+/// Whether `key` still works at `now`: not revoked, and not past its expiry.
+pub(crate) fn is_usable(key: &MerchantApiKey, now: PrimitiveDateTime) -> bool {
+    #[cfg(feature = "mysql")]
+    let is_active = key.is_active != 0;
+    #[cfg(feature = "postgres")]
+    let is_active = key.is_active;
+    is_active && key.expires_at.is_none_or(|at| now < at)
+}
+
+/// The scope a new key may be given. A caller can hand on only what it holds itself, so a scoped
+/// key cannot mint a broader one; an unrestricted key counts as asking for everything this build
+/// knows.
+fn check_grantable(
+    caller: Option<&AuthContext>,
+    requested: Option<&[Permission]>,
+) -> Result<(), ApiKeyError> {
+    if let Some(requested) = requested {
+        if requested.is_empty() {
+            return Err(ApiKeyError::InvalidRequest(
+                "permissions must name at least one permission; omit it for an unrestricted key"
+                    .to_string(),
+            ));
+        }
+        if let Some(unknown) = requested
+            .iter()
+            .find(|p| matches!(p, Permission::Unknown(_)))
+        {
+            return Err(ApiKeyError::InvalidRequest(format!(
+                "unknown permission {}",
+                unknown.as_str()
+            )));
+        }
+    }
+    // Member administration is left out of an unrestricted key: no key can use it, so no caller
+    // should need it to mint one.
+    let unrestricted: Vec<Permission> = auth::KNOWN_PERMISSIONS
+        .iter()
+        .filter(|p| **p != Permission::MembersManage)
+        .cloned()
+        .collect();
+    let wanted = requested.unwrap_or(&unrestricted);
+    match caller {
+        Some(caller) if !wanted.iter().all(|p| caller.allows(p)) => Err(ApiKeyError::Forbidden),
+        _ => Ok(()),
+    }
+}
+
+/// Whether the caller may manage `merchant_id`'s keys. A key or session acts for its own merchant
+/// only; the admin secret, which carries no context, may act for any.
+fn check_merchant(caller: Option<&AuthContext>, merchant_id: &str) -> Result<(), ApiKeyError> {
+    match caller {
+        Some(caller) if caller.merchant_id != merchant_id => Err(ApiKeyError::Forbidden),
+        _ => Ok(()),
+    }
+}
+
+/// Insert a new key and return it with its raw value, which is never stored.
+async fn insert_key(
+    merchant_id: String,
+    description: Option<String>,
+    permissions: Option<Vec<Permission>>,
+    expires_at: Option<PrimitiveDateTime>,
+    rate_limit_per_minute: Option<u32>,
+) -> Result<CreateApiKeyResponse, ApiKeyError> {
     let raw_key = auth::generate_api_key();
     let key_hash = auth::hash_api_key(&raw_key);
     let key_prefix = auth::extract_key_prefix(&raw_key);
@@ -74,15 +200,22 @@ pub async fn create_api_key(
 
     let new_key = MerchantApiKeyNew {
         key_id: key_id.clone(),
-        merchant_id: payload.merchant_id.clone(),
+        merchant_id: merchant_id.clone(),
         key_hash,
         key_prefix: key_prefix.clone(),
-        description: payload.description.clone(),
+        description: description.clone(),
         #[cfg(feature = "mysql")]
         is_active: 1,
         #[cfg(feature = "postgres")]
         is_active: true,
         created_at: now,
+        permissions: permissions
+            .as_ref()
+            .map(serde_json::to_string)
+            .transpose()
+            .map_err(|_| ApiKeyError::CreationFailed)?,
+        expires_at,
+        rate_limit_per_minute: rate_limit_per_minute.map(|n| i32::try_from(n).unwrap_or(i32::MAX)),
     };
 
     let app_state = get_tenant_app_state().await;
@@ -90,20 +223,159 @@ pub async fn create_api_key(
         .await
         .map_err(|_| ApiKeyError::CreationFailed)?;
 
-    Ok(Json(CreateApiKeyResponse {
+    Ok(CreateApiKeyResponse {
         key_id,
         api_key: raw_key,
         key_prefix,
-        merchant_id: payload.merchant_id,
-        description: payload.description,
+        merchant_id,
+        description,
         created_at: now,
+        permissions,
+        expires_at,
+        rate_limit_per_minute,
+    })
+}
+
+#[axum::debug_handler]
+pub async fn create_api_key(
+    auth: Option<Extension<AuthContext>>,
+    Json(payload): Json<CreateApiKeyRequest>,
+) -> Result<Json<CreateApiKeyResponse>, error::ContainerError<ApiKeyError>> {
+    check_merchant(auth.as_deref(), &payload.merchant_id)?;
+    check_grantable(auth.as_deref(), payload.permissions.as_deref())?;
+    if payload.expires_in_secs == Some(0) {
+        return Err(ApiKeyError::InvalidRequest(
+            "expires_in_secs must be positive; omit it for a key that does not expire".to_string(),
+        )
+        .into());
+    }
+    if payload.rate_limit_per_minute == Some(0) {
+        return Err(ApiKeyError::InvalidRequest(
+            "rate_limit_per_minute must be positive; omit it for an unlimited key".to_string(),
+        )
+        .into());
+    }
+
+    let expires_at = payload.expires_in_secs.map(|secs| {
+        date_time::now() + time::Duration::seconds(i64::try_from(secs).unwrap_or(i64::MAX))
+    });
+    let created = insert_key(
+        payload.merchant_id,
+        payload.description,
+        payload.permissions,
+        expires_at,
+        payload.rate_limit_per_minute,
+    )
+    .await?;
+    Ok(Json(created))
+}
+
+/// `POST /api-key/:key_id/rotate`
+///
+/// Issue a replacement for a key and cut the old one's life short, so both work while callers move
+/// across. The replacement keeps the old key's description, permissions, quota and expiry, so
+/// rotating — even a key already in its overlap — never extends how long a credential lives.
+#[axum::debug_handler]
+pub async fn rotate_api_key(
+    auth: Option<Extension<AuthContext>>,
+    Path(key_id): Path<String>,
+    payload: Option<Json<RotateApiKeyRequest>>,
+) -> Result<Json<RotateApiKeyResponse>, error::ContainerError<ApiKeyError>> {
+    let overlap_secs = payload
+        .map(|Json(p)| p)
+        .unwrap_or_default()
+        .overlap_secs
+        .unwrap_or(DEFAULT_ROTATION_OVERLAP_SECS);
+    if overlap_secs > MAX_ROTATION_OVERLAP_SECS {
+        return Err(ApiKeyError::InvalidRequest(format!(
+            "overlap_secs must be at most {MAX_ROTATION_OVERLAP_SECS}"
+        ))
+        .into());
+    }
+
+    let app_state = get_tenant_app_state().await;
+    let old = crate::generics::generic_find_all::<
+        <MerchantApiKey as HasTable>::Table,
+        _,
+        MerchantApiKey,
+    >(&app_state.db, dsl::key_id.eq(key_id.clone()))
+    .await
+    .map_err(|_| ApiKeyError::StorageError)?
+    .pop()
+    .ok_or(ApiKeyError::NotFound)?;
+
+    // A key from one merchant must not be able to mint keys for another.
+    check_merchant(auth.as_deref(), &old.merchant_id)?;
+    let now = date_time::now();
+    if !is_usable(&old, now) {
+        return Err(ApiKeyError::InvalidRequest(
+            "only an active, unexpired key can be rotated".to_string(),
+        )
+        .into());
+    }
+    let permissions = stored_permissions(old.permissions.as_deref());
+    check_grantable(auth.as_deref(), permissions.as_deref())?;
+
+    let replacement = insert_key(
+        old.merchant_id.clone(),
+        old.description.clone(),
+        permissions,
+        old.expires_at,
+        old.rate_limit_per_minute.map(|n| n.max(1) as u32),
+    )
+    .await?;
+
+    let overlap_ends = now + time::Duration::seconds(overlap_secs as i64);
+    let replaced_key_expires_at = old
+        .expires_at
+        .map_or(overlap_ends, |at| at.min(overlap_ends));
+    let conn = &app_state
+        .db
+        .get_conn()
+        .await
+        .map_err(|_| ApiKeyError::StorageError)?;
+    crate::generics::generic_update::<<MerchantApiKey as HasTable>::Table, _, _>(
+        conn,
+        dsl::key_id.eq(key_id.clone()),
+        MerchantApiKeyExpiry {
+            expires_at: replaced_key_expires_at,
+        },
+    )
+    .await
+    .map_err(|_| ApiKeyError::StorageError)?;
+    // The listed view of each key, which never carries its hash or raw value.
+    let before = ApiKeyListItem::from(old.clone());
+    crate::audit::record_change(
+        &before,
+        &serde_json::json!({
+            "replaced": ApiKeyListItem {
+                expires_at: Some(replaced_key_expires_at),
+                ..ApiKeyListItem::from(old.clone())
+            },
+            "replacement": ApiKeyListItem::from(&replacement),
+        }),
+    );
+
+    // The cached entry carries the old expiry; drop it so the new one applies now.
+    let _ = app_state
+        .redis_conn
+        .conn
+        .delete_key(&format!("api_key:{}", old.key_hash))
+        .await;
+
+    Ok(Json(RotateApiKeyResponse {
+        key: replacement,
+        replaced_key_id: key_id,
+        replaced_key_expires_at,
     }))
 }
 
 #[axum::debug_handler]
 pub async fn list_api_keys(
+    auth: Option<Extension<AuthContext>>,
     Path(merchant_id): Path<String>,
 ) -> Result<Json<Vec<ApiKeyListItem>>, error::ContainerError<ApiKeyError>> {
+    check_merchant(auth.as_deref(), &merchant_id)?;
     let app_state = get_tenant_app_state().await;
     let keys = crate::generics::generic_find_all::<
         <MerchantApiKey as HasTable>::Table,
@@ -118,9 +390,21 @@ pub async fn list_api_keys(
 
 #[axum::debug_handler]
 pub async fn revoke_api_key(
+    auth: Option<Extension<AuthContext>>,
     Path(key_id): Path<String>,
 ) -> Result<Json<RevokeApiKeyResponse>, error::ContainerError<ApiKeyError>> {
     let app_state = get_tenant_app_state().await;
+    let key = crate::generics::generic_find_all::<
+        <MerchantApiKey as HasTable>::Table,
+        _,
+        MerchantApiKey,
+    >(&app_state.db, dsl::key_id.eq(key_id.clone()))
+    .await
+    .map_err(|_| ApiKeyError::StorageError)?
+    .pop()
+    .ok_or(ApiKeyError::NotFound)?;
+    check_merchant(auth.as_deref(), &key.merchant_id)?;
+
     let conn = &app_state
         .db
         .get_conn()
@@ -141,20 +425,18 @@ pub async fn revoke_api_key(
     )
     .await
     .map_err(|_| ApiKeyError::RevocationFailed)?;
+    let before = ApiKeyListItem::from(key.clone());
+    crate::audit::record_change(
+        &before,
+        &ApiKeyListItem {
+            is_active: false,
+            ..ApiKeyListItem::from(key.clone())
+        },
+    );
 
     // Remove from Redis cache
-    if let Ok(keys) = crate::generics::generic_find_all::<
-        <MerchantApiKey as HasTable>::Table,
-        _,
-        MerchantApiKey,
-    >(&app_state.db, dsl::key_id.eq(key_id.clone()))
-    .await
-    {
-        for key in keys {
-            let cache_key = format!("api_key:{}", key.key_hash);
-            let _ = app_state.redis_conn.conn.delete_key(&cache_key).await;
-        }
-    }
+    let cache_key = format!("api_key:{}", key.key_hash);
+    let _ = app_state.redis_conn.conn.delete_key(&cache_key).await;
 
     Ok(Json(RevokeApiKeyResponse {
         key_id,
@@ -166,28 +448,74 @@ pub async fn insert_api_key_for_merchant(
     merchant_id: &str,
     description: Option<String>,
 ) -> Option<String> {
-    let raw_key = auth::generate_api_key();
-    let key_hash = auth::hash_api_key(&raw_key);
-    let key_prefix = auth::extract_key_prefix(&raw_key);
-    let key_id = uuid::Uuid::new_v4().to_string();
-    let now = date_time::now();
+    insert_key(merchant_id.to_string(), description, None, None, None)
+        .await
+        .ok()
+        .map(|created| created.api_key)
+}
 
-    let new_key = MerchantApiKeyNew {
-        key_id,
-        merchant_id: merchant_id.to_string(),
-        key_hash,
-        key_prefix,
-        description,
-        #[cfg(feature = "mysql")]
-        is_active: 1,
-        #[cfg(feature = "postgres")]
-        is_active: true,
-        created_at: now,
-    };
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-    let app_state = get_tenant_app_state().await;
-    match crate::generics::generic_insert(&app_state.db, new_key).await {
-        Ok(_) => Some(raw_key),
-        Err(_) => None,
+    fn caller(permissions: Option<Vec<Permission>>) -> AuthContext {
+        AuthContext::from_api_key("merc_1", permissions)
+    }
+
+    #[test]
+    fn a_scoped_key_cannot_mint_a_broader_one() {
+        let decide_only = caller(Some(vec![Permission::RoutingDecide]));
+        assert!(check_grantable(Some(&decide_only), Some(&[Permission::RoutingDecide])).is_ok());
+        assert!(matches!(
+            check_grantable(Some(&decide_only), Some(&[Permission::RoutingRead])),
+            Err(ApiKeyError::Forbidden)
+        ));
+        // Unrestricted is everything, so it is out of reach too.
+        assert!(matches!(
+            check_grantable(Some(&decide_only), None),
+            Err(ApiKeyError::Forbidden)
+        ));
+    }
+
+    #[test]
+    fn a_full_session_can_mint_an_unrestricted_key() {
+        let admin = caller(Some(auth::KNOWN_PERMISSIONS.to_vec()));
+        assert!(check_grantable(Some(&admin), None).is_ok());
+        assert!(check_grantable(Some(&caller(None)), None).is_ok());
+        assert!(check_grantable(None, None).is_ok());
+    }
+
+    #[test]
+    fn a_scope_must_name_known_permissions() {
+        assert!(matches!(
+            check_grantable(None, Some(&[])),
+            Err(ApiKeyError::InvalidRequest(_))
+        ));
+        assert!(matches!(
+            check_grantable(None, Some(&[Permission::from("routing:everything")])),
+            Err(ApiKeyError::InvalidRequest(_))
+        ));
+    }
+
+    #[test]
+    fn one_merchant_cannot_manage_another_merchants_keys() {
+        let other = AuthContext::from_api_key("merc_2", Some(auth::KNOWN_PERMISSIONS.to_vec()));
+        // Creating, listing and revoking all name merchant `merc_1`: by body, path and key owner.
+        assert!(matches!(
+            check_merchant(Some(&other), "merc_1"),
+            Err(ApiKeyError::Forbidden)
+        ));
+        assert!(check_merchant(Some(&caller(None)), "merc_1").is_ok());
+        assert!(check_merchant(None, "merc_1").is_ok());
+    }
+
+    #[test]
+    fn a_damaged_scope_reads_as_holding_nothing() {
+        assert_eq!(stored_permissions(None), None);
+        assert_eq!(stored_permissions(Some("not json")), Some(Vec::new()));
+        assert_eq!(
+            stored_permissions(Some(r#"["routing:decide"]"#)),
+            Some(vec![Permission::RoutingDecide])
+        );
     }
 }
@@ -0,0 +1,166 @@
+//! Reading the configuration audit log ([`crate::audit`]).
+//!
+//! Newest first, a page at a time: each page names the id to pass as `before_id` for the next. A
+//! session or API key reads only its own merchant's changes; the admin secret may read any.
+
+use async_bb8_diesel::AsyncRunQueryDsl;
+use axum::extract::Query;
+use axum::http::StatusCode;
+use axum::{Extension, Json};
+use diesel::{ExpressionMethods, QueryDsl};
+use serde::{Deserialize, Serialize};
+use time::PrimitiveDateTime;
+
+use crate::app::get_tenant_app_state;
+use crate::auth::AuthContext;
+use crate::storage::types::ConfigAuditEntry;
+
+#[cfg(feature = "mysql")]
+use crate::storage::schema::config_audit_log::dsl;
+#[cfg(feature = "postgres")]
+use crate::storage::schema_pg::config_audit_log::dsl;
+
+const DEFAULT_LIMIT: i64 = 50;
+const MAX_LIMIT: i64 = 500;
+
+#[derive(Debug, Default, Deserialize)]
+pub struct AuditQuery {
+    /// Defaults to the caller's own merchant. With the admin secret, absent reads every merchant.
+    pub merchant_id: Option<String>,
+    pub actor: Option<String>,
+    /// The routed pattern, e.g. `/merchant-account/:merchant-id/debit-routing`.
+    pub route: Option<String>,
+    pub request_id: Option<String>,
+    /// Unix seconds, inclusive.
+    pub from: Option<i64>,
+    /// Unix seconds, exclusive.
+    pub to: Option<i64>,
+    /// Only entries older than this id: the `next_before_id` of the previous page.
+    pub before_id: Option<i64>,
+    pub limit: Option<i64>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct AuditEntryResponse {
+    pub id: i64,
+    pub merchant_id: Option<String>,
+    pub actor: String,
+    pub actor_kind: String,
+    pub user_id: Option<String>,
+    pub http_method: String,
+    pub route: String,
+    pub path: String,
+    pub request_id: Option<String>,
+    pub status_code: i32,
+    pub request_body: Option<serde_json::Value>,
+    pub before: Option<serde_json::Value>,
+    pub after: Option<serde_json::Value>,
+    pub created_at: PrimitiveDateTime,
+}
+
+impl From<ConfigAuditEntry> for AuditEntryResponse {
+    fn from(entry: ConfigAuditEntry) -> Self {
+        // Stored as JSON text by `audit::capture`; anything unparsable is shown as the text itself.
+        let json = |raw: Option<String>| {
+            raw.map(|raw| serde_json::from_str(&raw).unwrap_or(serde_json::Value::String(raw)))
+        };
+        Self {
+            id: entry.id,
+            merchant_id: entry.merchant_id,
+            actor: entry.actor,
+            actor_kind: entry.actor_kind,
+            user_id: entry.user_id,
+            http_method: entry.http_method,
+            route: entry.route,
+            path: entry.path,
+            request_id: entry.request_id,
+            status_code: entry.status_code,
+            request_body: json(entry.request_body),
+            before: json(entry.before_state),
+            after: json(entry.after_state),
+            created_at: entry.created_at,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct AuditLogResponse {
+    pub entries: Vec<AuditEntryResponse>,
+    /// Pass as `before_id` for the next page; `None` on the last one.
+    pub next_before_id: Option<i64>,
+}
+
+/// `GET /audit`
+pub async fn list_audit_log(
+    auth: Option<Extension<AuthContext>>,
+    Query(query): Query<AuditQuery>,
+) -> Result<Json<AuditLogResponse>, (StatusCode, String)> {
+    let merchant_id = match (auth, query.merchant_id) {
+        (Some(Extension(context)), Some(requested)) if requested != context.merchant_id => {
+            return Err((
+                StatusCode::FORBIDDEN,
+                "the audit log can only be read for your own merchant".to_string(),
+            ))
+        }
+        (Some(Extension(context)), _) => Some(context.merchant_id),
+        (None, requested) => requested,
+    };
+    let limit = query.limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT);
+    let from = query.from.map(at_unix_secs).transpose()?;
+    let to = query.to.map(at_unix_secs).transpose()?;
+
+    let mut rows = dsl::config_audit_log.into_boxed();
+    if let Some(merchant_id) = merchant_id {
+        rows = rows.filter(dsl::merchant_id.eq(merchant_id));
+    }
+    if let Some(actor) = query.actor {
+        rows = rows.filter(dsl::actor.eq(actor));
+    }
+    if let Some(route) = query.route {
+        rows = rows.filter(dsl::route.eq(route));
+    }
+    if let Some(request_id) = query.request_id {
+        rows = rows.filter(dsl::request_id.eq(request_id));
+    }
+    if let Some(from) = from {
+        rows = rows.filter(dsl::created_at.ge(from));
+    }
+    if let Some(to) = to {
+        rows = rows.filter(dsl::created_at.lt(to));
+    }
+    if let Some(before_id) = query.before_id {
+        rows = rows.filter(dsl::id.lt(before_id));
+    }
+
+    let app_state = get_tenant_app_state().await;
+    let conn = app_state.db.get_conn().await.map_err(|_| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "db connection".to_string(),
+        )
+    })?;
+    let entries: Vec<ConfigAuditEntry> = rows
+        .order(dsl::id.desc())
+        .limit(limit)
+        .get_results_async(&*conn)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("{e:?}")))?;
+
+    let next_before_id = (entries.len() as i64 == limit)
+        .then(|| entries.last().map(|entry| entry.id))
+        .flatten();
+    Ok(Json(AuditLogResponse {
+        entries: entries.into_iter().map(AuditEntryResponse::from).collect(),
+        next_before_id,
+    }))
+}
+
+fn at_unix_secs(secs: i64) -> Result<PrimitiveDateTime, (StatusCode, String)> {
+    let at = time::OffsetDateTime::from_unix_timestamp(secs).map_err(|_| {
+        (
+            StatusCode::BAD_REQUEST,
+            format!("{secs} is not a valid unix timestamp"),
+        )
+    })?;
+    Ok(PrimitiveDateTime::new(at.date(), at.time()))
+}
@@ -48,6 +48,7 @@ pub async fn set_connector_credentials(
         "credential encryption keyring not configured".to_string(),
     ))?;
 
+    let before = masked_source(&store, &merchant_id, &connector, &body.account).await;
     let new = ConnectorCreds {
         webhook_secret: Secret::new(body.webhook_secret),
         download_auth: Secret::new(body.download_auth),
@@ -56,6 +57,8 @@ pub async fn set_connector_credentials(
         .put(&connector, &body.account, &merchant_id, &new)
         .await
         .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("{e:?}")))?;
+    let after = masked_source(&store, &merchant_id, &connector, &body.account).await;
+    crate::audit::record_change(&before, &after);
 
     Ok(Json(SetCredentialsResponse {
         merchant_id,
@@ -72,12 +75,38 @@ pub async fn set_connector_credentials(
 pub async fn delete_connector_credentials(
     Path((merchant_id, connector, account)): Path<(String, String, String)>,
 ) -> Result<StatusCode, (StatusCode, String)> {
+    let app_state = get_tenant_app_state().await;
+    let cfg = &app_state.config.cost_ingestion;
+    let before = match ConnectorCredsStore::from_keyring(
+        &cfg.creds_encryption_current,
+        &cfg.creds_encryption_keys,
+    ) {
+        Some(store) => masked_source(&store, &merchant_id, &connector, &account).await,
+        None => None,
+    };
     creds::delete_source(&connector, &account, &merchant_id)
         .await
         .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("{e:?}")))?;
+    crate::audit::record_change(&before, &());
     Ok(StatusCode::NO_CONTENT)
 }
 
+/// One source as the listing shows it, for the audit log: the masked hints tell a rotation apart
+/// from a re-save without recording either secret. Best effort, so a failed read records nothing.
+async fn masked_source(
+    store: &ConnectorCredsStore,
+    merchant_id: &str,
+    connector: &str,
+    account: &str,
+) -> Option<creds::MaskedSource> {
+    store
+        .list_masked(merchant_id)
+        .await
+        .ok()?
+        .into_iter()
+        .find(|source| source.connector == connector && source.account == account)
+}
+
 /// `GET /merchant-account/:merchant_id/connectors` — configured sources with *masked* credential
 /// previews (last-4 hints only, never full secrets). Falls back to source ids without hints when
 /// the credential keyring isn't configured (nothing decryptable).
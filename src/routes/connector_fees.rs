@@ -3,7 +3,8 @@
 //! Surfaces, per connector, the model-derived blended cost (rolled up from the fitted snapshot) and
 //! any manual override the merchant has set, and lets them upsert/clear that override. An override
 //! replaces the learned model for every EV calculation on that connector — see
-//! [`crate::cost_ingestion::serving::lookup`] and `overrides`.
+//! [`crate::cost_ingestion::serving::lookup`] and `overrides`. A connector priced by cumulative
+//! monthly volume gets a volume-tier contract instead (`volume_tiers`), priced at its marginal tier.
 
 use std::collections::{BTreeSet, HashMap};
 
@@ -15,7 +16,9 @@ use time::format_description::well_known::Iso8601;
 
 use crate::config::ClickHouseAnalyticsConfig;
 use crate::cost_ingestion::blended::{self, ConnectorBlend};
+use crate::cost_ingestion::interchange::{self, ClusterComparison, DeclaredMarkup};
 use crate::cost_ingestion::overrides::{self, FeeOverride};
+use crate::cost_ingestion::volume_tiers::{self, TierPosition, VolumeTier, VolumeTierSchedule};
 use crate::cost_ingestion::{creds, serving};
 
 /// One connector's fee picture for the dashboard.
@@ -168,6 +171,11 @@ pub async fn set_fee_override(
     let updated_at = time::OffsetDateTime::now_utc()
         .format(&Iso8601::DEFAULT)
         .unwrap_or_default();
+    // Read for the audit log only, so a failed read records no prior state rather than failing.
+    let before = overrides::get(&merchant_id, &connector)
+        .await
+        .ok()
+        .flatten();
     let ov = FeeOverride {
         pct_bps: body.pct_bps,
         fixed: body.fixed,
@@ -176,6 +184,7 @@ pub async fn set_fee_override(
     overrides::put(&merchant_id, &connector, &ov)
         .await
         .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("{e:?}")))?;
+    crate::audit::record_change(&before, &ov);
 
     // Push the change onto the hot path now, so the next decide uses it without waiting for the
     // periodic refresh. Best-effort: the override is already persisted and will be picked up on the
@@ -196,9 +205,14 @@ pub async fn delete_fee_override(
     Path((merchant_id, connector)): Path<(String, String)>,
 ) -> Result<StatusCode, (StatusCode, String)> {
     let connector = connector.to_lowercase();
+    let before = overrides::get(&merchant_id, &connector)
+        .await
+        .ok()
+        .flatten();
     overrides::delete(&merchant_id, &connector)
         .await
         .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("{e:?}")))?;
+    crate::audit::record_change(&before, &());
     refresh_serving(&merchant_id).await;
     Ok(StatusCode::NO_CONTENT)
 }
@@ -216,3 +230,197 @@ pub(crate) async fn refresh_serving(merchant_id: &str) {
         );
     }
 }
+
+// ── volume-tier contracts ─────────────────────────────────────────────────────────────────────────
+
+/// One connector's volume-tier contract with where it stands this month.
+#[derive(Debug, Serialize)]
+pub struct VolumeTierResponse {
+    pub connector: String,
+    #[serde(flatten)]
+    pub schedule: VolumeTierSchedule,
+    pub position: TierPosition,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SetVolumeTiersRequest {
+    pub currency: String,
+    pub tiers: Vec<VolumeTier>,
+}
+
+/// `GET /merchant-account/:merchant_id/volume-tiers`
+pub async fn list_volume_tiers(
+    Path(merchant_id): Path<String>,
+) -> Result<Json<Vec<VolumeTierResponse>>, (StatusCode, String)> {
+    let month = volume_tiers::current_month();
+    let schedules = volume_tiers::list(&merchant_id)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("{e:?}")))?;
+    Ok(Json(
+        schedules
+            .into_iter()
+            .map(|(connector, schedule)| {
+                let mtd = volume_tiers::mtd_volume(&merchant_id, &connector, &schedule.currency);
+                VolumeTierResponse {
+                    position: schedule.position(&connector, &month, mtd),
+                    connector,
+                    schedule,
+                }
+            })
+            .collect(),
+    ))
+}
+
+/// `PUT /merchant-account/:merchant_id/connectors/:connector/volume-tiers`
+pub async fn set_volume_tiers(
+    Path((merchant_id, connector)): Path<(String, String)>,
+    Json(body): Json<SetVolumeTiersRequest>,
+) -> Result<Json<VolumeTierResponse>, (StatusCode, String)> {
+    let connector = connector.to_lowercase();
+    let schedule = VolumeTierSchedule {
+        currency: body.currency.trim().to_uppercase(),
+        tiers: body.tiers,
+        updated_at: time::OffsetDateTime::now_utc()
+            .format(&Iso8601::DEFAULT)
+            .unwrap_or_default(),
+    };
+    schedule
+        .validate()
+        .map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+    let before = volume_tiers::get(&merchant_id, &connector)
+        .await
+        .ok()
+        .flatten();
+    volume_tiers::put(&merchant_id, &connector, &schedule)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("{e:?}")))?;
+    crate::audit::record_change(&before, &schedule);
+
+    // Pick up the month's volume so far and put the contract on the hot path now. Both are
+    // best-effort; the periodic sync and refresh are the backstop.
+    if let Err(e) = volume_tiers::sync_mtd().await {
+        crate::logger::warn!(
+            tag = "cost_volume_tiers",
+            "inline month-to-date sync failed for {}: {:?}",
+            merchant_id,
+            e
+        );
+    }
+    refresh_serving(&merchant_id).await;
+
+    let mtd = volume_tiers::mtd_volume(&merchant_id, &connector, &schedule.currency);
+    Ok(Json(VolumeTierResponse {
+        position: schedule.position(&connector, &volume_tiers::current_month(), mtd),
+        connector,
+        schedule,
+    }))
+}
+
+/// `DELETE /merchant-account/:merchant_id/connectors/:connector/volume-tiers`
+pub async fn delete_volume_tiers(
+    Path((merchant_id, connector)): Path<(String, String)>,
+) -> Result<StatusCode, (StatusCode, String)> {
+    let connector = connector.to_lowercase();
+    let before = volume_tiers::get(&merchant_id, &connector)
+        .await
+        .ok()
+        .flatten();
+    volume_tiers::delete(&merchant_id, &connector)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("{e:?}")))?;
+    crate::audit::record_change(&before, &());
+    refresh_serving(&merchant_id).await;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+// ── IC++ markups and the interchange comparison ──────────────────────────────────────────────────
+
+/// A connector's declared IC++ markup, charged on top of the shared interchange table.
+#[derive(Debug, Serialize)]
+pub struct MarkupResponse {
+    pub connector: String,
+    #[serde(flatten)]
+    pub markup: DeclaredMarkup,
+}
+
+/// `GET /merchant-account/:merchant_id/ic-markups`
+pub async fn list_markups(
+    Path(merchant_id): Path<String>,
+) -> Result<Json<Vec<MarkupResponse>>, (StatusCode, String)> {
+    let markups = interchange::list_markups(&merchant_id)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("{e:?}")))?;
+    Ok(Json(
+        markups
+            .into_iter()
+            .map(|(connector, markup)| MarkupResponse { connector, markup })
+            .collect(),
+    ))
+}
+
+/// `PUT /merchant-account/:merchant_id/connectors/:connector/ic-markup`
+pub async fn set_markup(
+    Path((merchant_id, connector)): Path<(String, String)>,
+    Json(body): Json<SetFeeOverrideRequest>,
+) -> Result<Json<MarkupResponse>, (StatusCode, String)> {
+    if !body.pct_bps.is_finite()
+        || !body.fixed.is_finite()
+        || body.pct_bps < 0.0
+        || body.fixed < 0.0
+    {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            "pct_bps and fixed must be finite and non-negative".to_string(),
+        ));
+    }
+    let connector = connector.to_lowercase();
+    let markup = DeclaredMarkup {
+        pct_bps: body.pct_bps,
+        fixed: body.fixed,
+        updated_at: time::OffsetDateTime::now_utc()
+            .format(&Iso8601::DEFAULT)
+            .unwrap_or_default(),
+    };
+    let before = interchange::get_markup(&merchant_id, &connector)
+        .await
+        .ok()
+        .flatten();
+    interchange::put_markup(&merchant_id, &connector, &markup)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("{e:?}")))?;
+    crate::audit::record_change(&before, &markup);
+    refresh_serving(&merchant_id).await;
+    Ok(Json(MarkupResponse { connector, markup }))
+}
+
+/// `DELETE /merchant-account/:merchant_id/connectors/:connector/ic-markup`
+pub async fn delete_markup(
+    Path((merchant_id, connector)): Path<(String, String)>,
+) -> Result<StatusCode, (StatusCode, String)> {
+    let connector = connector.to_lowercase();
+    let before = interchange::get_markup(&merchant_id, &connector)
+        .await
+        .ok()
+        .flatten();
+    interchange::delete_markup(&merchant_id, &connector)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("{e:?}")))?;
+    crate::audit::record_change(&before, &());
+    refresh_serving(&merchant_id).await;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// `GET /merchant-account/:merchant_id/interchange-comparison`
+///
+/// Every connector priced as interchange + markup on the same card clusters, cheapest first, so a
+/// blended connector's implied markup sits next to the IC++ connectors' and a connector that hasn't
+/// processed a cluster yet is priced from its markup alone.
+pub async fn interchange_comparison(
+    Path(merchant_id): Path<String>,
+) -> Result<Json<Vec<ClusterComparison>>, (StatusCode, String)> {
+    let cfg = clickhouse_config()?;
+    interchange::compare(&cfg, &merchant_id)
+        .await
+        .map(Json)
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("{e:?}")))
+}
@@ -514,6 +514,7 @@ pub async fn set_cluster_override(
         StatusCode::BAD_REQUEST,
         "cluster key must have 7 '|'-separated fields".to_string(),
     ))?;
+    let before = current_cluster_override(&merchant_id, &dims).await;
     let ov = ClusterOverride {
         dims,
         pct_bps: body.pct_bps,
@@ -525,6 +526,7 @@ pub async fn set_cluster_override(
     overrides::put_cluster(&merchant_id, &ov)
         .await
         .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("{e:?}")))?;
+    crate::audit::record_change(&before, &ov);
     refresh_serving(&merchant_id).await;
     Ok(Json(ov))
 }
@@ -537,9 +539,24 @@ pub async fn delete_cluster_override(
         StatusCode::BAD_REQUEST,
         "cluster key must have 7 '|'-separated fields".to_string(),
     ))?;
+    let before = current_cluster_override(&merchant_id, &dims).await;
     overrides::delete_cluster(&merchant_id, &dims)
         .await
         .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("{e:?}")))?;
+    crate::audit::record_change(&before, &());
     refresh_serving(&merchant_id).await;
     Ok(StatusCode::NO_CONTENT)
 }
+
+/// The override currently set on a cluster, for the audit log. Best effort: a failed read records
+/// no prior state rather than failing the edit.
+async fn current_cluster_override(
+    merchant_id: &str,
+    dims: &ClusterDims,
+) -> Option<ClusterOverride> {
+    overrides::list_clusters(merchant_id)
+        .await
+        .ok()?
+        .into_iter()
+        .find(|ov| ov.dims == *dims)
+}
@@ -0,0 +1,179 @@
+//! Merchant-facing API for fee price-change alerts.
+//!
+//! Lists the price changes raised after each fit ([`crate::cost_ingestion::alerts`]), lets the
+//! merchant acknowledge them, and edits the thresholds and destinations alerts are raised at. The
+//! webhook signing secret is generated here and shown once, on the response that creates it.
+
+use axum::extract::{Path, Query};
+use axum::http::StatusCode;
+use axum::{Extension, Json};
+use serde::{Deserialize, Serialize};
+use time::format_description::well_known::Iso8601;
+
+use crate::auth::AuthContext;
+use crate::cost_ingestion::alerts::{self, AlertSettings, PriceChangeAlert};
+use crate::euclid::handlers::routing_versions::author;
+use crate::routes::connector_fees::clickhouse_config;
+
+#[derive(Debug, Default, Deserialize)]
+pub struct ListAlertsQuery {
+    /// Only the alerts nobody has acknowledged yet.
+    #[serde(default)]
+    pub unacknowledged: bool,
+}
+
+/// Settings as returned: the secret is masked unless this response generated it.
+#[derive(Debug, Serialize)]
+pub struct AlertSettingsResponse {
+    pub min_pct_bps: f64,
+    pub min_fixed: f64,
+    pub major_pct_bps: f64,
+    pub major_fixed: f64,
+    pub notify_emails: Vec<String>,
+    pub webhook_url: Option<String>,
+    /// `••••` plus the last four characters of the stored secret.
+    pub webhook_secret_hint: Option<String>,
+    /// The full secret — present only when this request generated or rotated it.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub webhook_secret: Option<String>,
+    pub updated_at: String,
+}
+
+impl AlertSettingsResponse {
+    fn new(settings: AlertSettings, revealed: Option<String>) -> Self {
+        Self {
+            min_pct_bps: settings.min_pct_bps,
+            min_fixed: settings.min_fixed,
+            major_pct_bps: settings.major_pct_bps,
+            major_fixed: settings.major_fixed,
+            notify_emails: settings.notify_emails,
+            webhook_url: settings.webhook_url,
+            webhook_secret_hint: settings.webhook_secret.as_deref().map(mask_secret),
+            webhook_secret: revealed,
+            updated_at: settings.updated_at,
+        }
+    }
+}
+
+/// Omitted thresholds keep their current value.
+#[derive(Debug, Deserialize)]
+pub struct SetAlertSettingsRequest {
+    pub min_pct_bps: Option<f64>,
+    pub min_fixed: Option<f64>,
+    pub major_pct_bps: Option<f64>,
+    pub major_fixed: Option<f64>,
+    #[serde(default)]
+    pub notify_emails: Vec<String>,
+    /// Clearing it stops webhook delivery and discards the secret.
+    pub webhook_url: Option<String>,
+    /// Replace the webhook secret with a new one.
+    #[serde(default)]
+    pub rotate_webhook_secret: bool,
+}
+
+/// `GET /merchant-account/:merchant_id/cost-price-alerts`
+pub async fn list_price_alerts(
+    Path(merchant_id): Path<String>,
+    Query(query): Query<ListAlertsQuery>,
+) -> Result<Json<Vec<PriceChangeAlert>>, (StatusCode, String)> {
+    let clickhouse = clickhouse_config()?;
+    let list = alerts::list(&clickhouse, &merchant_id, query.unacknowledged)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("{e:?}")))?;
+    Ok(Json(list))
+}
+
+/// `POST /merchant-account/:merchant_id/cost-price-alerts/:alert_id/acknowledge`
+pub async fn acknowledge_price_alert(
+    Path((merchant_id, alert_id)): Path<(String, String)>,
+    auth: Option<Extension<AuthContext>>,
+) -> Result<Json<PriceChangeAlert>, (StatusCode, String)> {
+    let clickhouse = clickhouse_config()?;
+    let by = author(auth).unwrap_or_default();
+    alerts::acknowledge(&clickhouse, &merchant_id, &alert_id, &by)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("{e:?}")))?
+        .map(Json)
+        .ok_or((
+            StatusCode::NOT_FOUND,
+            format!("no price alert '{alert_id}' for merchant '{merchant_id}'"),
+        ))
+}
+
+/// `GET /merchant-account/:merchant_id/cost-price-alert-settings`
+pub async fn get_price_alert_settings(
+    Path(merchant_id): Path<String>,
+) -> Result<Json<AlertSettingsResponse>, (StatusCode, String)> {
+    let settings = alerts::get_settings(&merchant_id)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("{e:?}")))?;
+    Ok(Json(AlertSettingsResponse::new(settings, None)))
+}
+
+/// `PUT /merchant-account/:merchant_id/cost-price-alert-settings`
+pub async fn set_price_alert_settings(
+    Path(merchant_id): Path<String>,
+    Json(body): Json<SetAlertSettingsRequest>,
+) -> Result<Json<AlertSettingsResponse>, (StatusCode, String)> {
+    let current = alerts::get_settings(&merchant_id)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("{e:?}")))?;
+    let before = AlertSettingsResponse::new(current.clone(), None);
+    let webhook_url = body
+        .webhook_url
+        .map(|url| url.trim().to_string())
+        .filter(|url| !url.is_empty());
+
+    // A URL needs a secret to sign with; one is minted the first time a URL is set, or on request.
+    let mut revealed = None;
+    let webhook_secret = match (&webhook_url, current.webhook_secret) {
+        (None, _) => None,
+        (Some(_), Some(secret)) if !body.rotate_webhook_secret => Some(secret),
+        (Some(_), _) => {
+            let secret = alerts::generate_webhook_secret();
+            revealed = Some(secret.clone());
+            Some(secret)
+        }
+    };
+
+    let settings = AlertSettings {
+        min_pct_bps: body.min_pct_bps.unwrap_or(current.min_pct_bps),
+        min_fixed: body.min_fixed.unwrap_or(current.min_fixed),
+        major_pct_bps: body.major_pct_bps.unwrap_or(current.major_pct_bps),
+        major_fixed: body.major_fixed.unwrap_or(current.major_fixed),
+        notify_emails: body
+            .notify_emails
+            .into_iter()
+            .map(|address| address.trim().to_string())
+            .filter(|address| !address.is_empty())
+            .collect(),
+        webhook_url,
+        webhook_secret,
+        updated_at: time::OffsetDateTime::now_utc()
+            .format(&Iso8601::DEFAULT)
+            .unwrap_or_default(),
+    };
+    settings
+        .validate()
+        .map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+    if let Some(url) = &settings.webhook_url {
+        alerts::resolve_webhook_target(url)
+            .await
+            .map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+    }
+    alerts::put_settings(&merchant_id, &settings)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("{e:?}")))?;
+    // Both sides as the API shows them, so the audit log sees the secret's hint, never the secret.
+    crate::audit::record_change(&before, &AlertSettingsResponse::new(settings.clone(), None));
+    Ok(Json(AlertSettingsResponse::new(settings, revealed)))
+}
+
+fn mask_secret(secret: &str) -> String {
+    let n = secret.chars().count();
+    if n <= 4 {
+        return "••••".to_string();
+    }
+    let last4: String = secret.chars().skip(n - 4).collect();
+    format!("••••{last4}")
+}
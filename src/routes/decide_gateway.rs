@@ -42,6 +42,7 @@ struct DecideGatewaySelectionReason<'a> {
     gateway_before_evaluation: Option<&'a str>,
     priority_logic_tag: Option<&'a str>,
     reset_approach: &'a ResetApproach,
+    scoring_strategy: Option<&'a str>,
 }
 
 #[derive(Debug, Serialize)]
@@ -209,6 +210,7 @@ pub async fn decide_gateway(
                                     .as_deref(),
                                 priority_logic_tag: decided_gateway.priority_logic_tag.as_deref(),
                                 reset_approach: &decided_gateway.reset_approach,
+                                scoring_strategy: decided_gateway.scoring_strategy.as_deref(),
                             },
                         }),
                         Some(payload.payment_id().to_string()),
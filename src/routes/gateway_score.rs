@@ -74,6 +74,14 @@ pub async fn reset_gateway_scores(
         removed_overrides,
         merchant_id
     );
+    // The scores themselves are too many to keep; the log records how much the reset cleared.
+    crate::audit::record_change(
+        &serde_json::json!({
+            "score_keys": deleted_keys,
+            "autopilot_overrides": removed_overrides,
+        }),
+        &(),
+    );
 
     Ok(Json(ResetGatewayScoresResponse {
         merchant_id,
@@ -415,6 +415,19 @@ pub async fn update_debit_routing(
 
     let response = match result {
         Ok(_) => {
+            let before = existing_config
+                .and_then(|config| config.value)
+                .and_then(|value| value.parse::<bool>().ok());
+            crate::audit::record_change(
+                &before.map(|enabled| DebitRoutingResponse {
+                    merchant_id: merchant_id.clone(),
+                    debit_routing_enabled: enabled,
+                }),
+                &DebitRoutingResponse {
+                    merchant_id: merchant_id.clone(),
+                    debit_routing_enabled: payload.enabled,
+                },
+            );
             API_REQUEST_COUNTER
                 .with_label_values(&["merchant_debit_routing_update", "success"])
                 .inc();
@@ -505,6 +518,10 @@ pub async fn update_merchant_feature(
         .await
         .ok_or(error::MerchantAccountConfigurationError::MerchantNotFound)?;
 
+    let before = MerchantFeatureEntry {
+        feature: feature.clone(),
+        enabled: feature.read_effective(&merchant_id).await,
+    };
     let result = feature
         .update_conf(&merchant_id, payload.enabled)
         .await
@@ -512,6 +529,13 @@ pub async fn update_merchant_feature(
 
     let response = match result {
         Ok(_) => {
+            crate::audit::record_change(
+                &before,
+                &MerchantFeatureEntry {
+                    feature: feature.clone(),
+                    enabled: payload.enabled,
+                },
+            );
             let mut features = Vec::new();
             for f in KnownFeature::all() {
                 features.push(MerchantFeatureEntry {
@@ -67,6 +67,8 @@ pub struct IngestionDto {
     pub good_clusters: i64,
     pub last_error: Option<String>,
     pub created_at: String,
+    /// Whether the raw report was retained, so the ingestion can be reprocessed.
+    pub report_retained: bool,
 }
 
 impl From<CostIngestion> for IngestionDto {
@@ -90,6 +92,7 @@ impl From<CostIngestion> for IngestionDto {
             good_clusters: r.good_clusters,
             last_error: r.last_error,
             created_at: datetime_str(r.created_at),
+            report_retained: r.report_blob.is_some(),
         }
     }
 }
@@ -186,6 +189,158 @@ pub async fn delete_ingestion(
     Ok(StatusCode::NO_CONTENT)
 }
 
+/// Returned immediately (202) by the reprocess endpoints: the ingestions now being reprocessed
+/// (poll them in the history), and any the request matched but could not start.
+#[derive(Debug, Serialize)]
+pub struct ReprocessAccepted {
+    pub reprocessing: Vec<String>,
+    pub skipped: Vec<ReprocessSkipped>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ReprocessSkipped {
+    pub id: String,
+    pub reason: String,
+}
+
+/// Body of a date-range reprocess: every completed ingestion whose report covers a day in
+/// `[from, to]` (`YYYY-MM-DD`), optionally only one connector's or one account's.
+#[derive(Debug, Deserialize)]
+pub struct ReprocessRangeRequest {
+    pub from: String,
+    pub to: String,
+    #[serde(default)]
+    pub connector: Option<String>,
+    #[serde(default)]
+    pub account: Option<String>,
+}
+
+/// `POST /merchant-account/:id/cost-ingestions/:ingestion_id/reprocess` — re-run one ingestion from
+/// its retained report with the current parser and column mapping, replacing what it staged, then
+/// refit. Runs in the background like an upload; the row shows `processing` until it finishes.
+/// `409` when the report wasn't retained or the ingestion is still running.
+pub async fn reprocess_ingestion(
+    Path((merchant_id, ingestion_id)): Path<(String, String)>,
+) -> Result<(StatusCode, Json<ReprocessAccepted>), (StatusCode, String)> {
+    let row = store::get_for_merchant(&merchant_id, &ingestion_id)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("{e:?}")))?
+        .ok_or((StatusCode::NOT_FOUND, "ingestion not found".to_string()))?;
+    if row.report_blob.is_none() {
+        return Err((
+            StatusCode::CONFLICT,
+            "this ingestion's report was not retained, so it can't be reprocessed".to_string(),
+        ));
+    }
+    let configs = reprocess_configs()?;
+    let claimed = store::claim_for_reprocess(&merchant_id, &ingestion_id)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("{e:?}")))?;
+    if !claimed {
+        return Err((
+            StatusCode::CONFLICT,
+            "cannot reprocess an in-progress ingestion".to_string(),
+        ));
+    }
+    Ok(start_reprocess(configs, merchant_id, vec![row], Vec::new()))
+}
+
+/// `POST /merchant-account/:id/cost-ingestions/reprocess` — reprocess every completed ingestion
+/// whose report covers a day in the requested range, oldest first, refitting each affected source
+/// once at the end. Ingestions without a retained report, or already running, are listed under
+/// `skipped`.
+pub async fn reprocess_ingestions(
+    Path(merchant_id): Path<String>,
+    Json(req): Json<ReprocessRangeRequest>,
+) -> Result<(StatusCode, Json<ReprocessAccepted>), (StatusCode, String)> {
+    let parse = |s: &str| {
+        chrono::NaiveDate::parse_from_str(s, "%Y-%m-%d")
+            .map_err(|e| (StatusCode::BAD_REQUEST, format!("bad date {s}: {e}")))
+    };
+    let (from, to) = (parse(&req.from)?, parse(&req.to)?);
+    if from > to {
+        return Err((StatusCode::BAD_REQUEST, "`from` is after `to`".to_string()));
+    }
+
+    let rows = store::list_in_period(
+        &merchant_id,
+        from,
+        to,
+        req.connector.as_deref(),
+        req.account.as_deref(),
+    )
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("{e:?}")))?;
+    let configs = reprocess_configs()?;
+
+    let mut jobs = Vec::new();
+    let mut skipped = Vec::new();
+    for row in rows {
+        if row.report_blob.is_none() {
+            skipped.push(ReprocessSkipped {
+                id: row.id,
+                reason: "report not retained".to_string(),
+            });
+            continue;
+        }
+        let claimed = store::claim_for_reprocess(&merchant_id, &row.id)
+            .await
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("{e:?}")))?;
+        if claimed {
+            jobs.push(row);
+        } else {
+            skipped.push(ReprocessSkipped {
+                id: row.id,
+                reason: "in progress".to_string(),
+            });
+        }
+    }
+    Ok(start_reprocess(configs, merchant_id, jobs, skipped))
+}
+
+/// What a background reprocess runs with, both off the global config.
+struct ReprocessConfig {
+    clickhouse: crate::config::ClickHouseAnalyticsConfig,
+    archive: crate::config::ReportArchiveConfig,
+}
+
+/// Read before any ingestion is claimed, so a failure here can't strand one in `processing`.
+fn reprocess_configs() -> Result<ReprocessConfig, (StatusCode, String)> {
+    let state = crate::app::APP_STATE.get().ok_or((
+        StatusCode::INTERNAL_SERVER_ERROR,
+        "app state not initialized".to_string(),
+    ))?;
+    Ok(ReprocessConfig {
+        clickhouse: state.global_config.analytics.clickhouse.clone(),
+        archive: state.global_config.cost_ingestion.report_archive.clone(),
+    })
+}
+
+/// Hand claimed ingestions to a background reprocess and answer 202.
+fn start_reprocess(
+    config: ReprocessConfig,
+    merchant_id: String,
+    jobs: Vec<CostIngestion>,
+    skipped: Vec<ReprocessSkipped>,
+) -> (StatusCode, Json<ReprocessAccepted>) {
+    let reprocessing = jobs.iter().map(|j| j.id.clone()).collect();
+    if !jobs.is_empty() {
+        tokio::spawn(crate::cost_ingestion::reprocess::run(
+            config.clickhouse,
+            config.archive,
+            merchant_id,
+            jobs,
+        ));
+    }
+    (
+        StatusCode::ACCEPTED,
+        Json(ReprocessAccepted {
+            reprocessing,
+            skipped,
+        }),
+    )
+}
+
 /// `GET /merchant-account/:id/cost-price-changes` — fee-regime changes detected by diffing each
 /// cluster's two most recent fits. Surfaces when a connector's pricing moved and which part (% or
 /// flat fee) changed.
@@ -788,9 +943,10 @@ async fn process_upload(
     }
 }
 
-/// Record the outcome of an ingest run: on success mark the job completed and refresh this
-/// merchant's served models immediately (so a just-ingested cluster doesn't fall back for ~5 min);
-/// on failure mark it failed with the error. Shared by the manual upload and sample flows.
+/// Record the outcome of an ingest run: on success mark the job completed, refresh this
+/// merchant's served models immediately (so a just-ingested cluster doesn't fall back for ~5 min),
+/// and raise alerts for any price the new fit moved; on failure mark it failed with the error.
+/// Shared by the manual upload and sample flows.
 async fn finish_ingest(
     job_id: &str,
     result: Result<pipeline::IngestOutcome, crate::cost_ingestion::IngestError>,
@@ -820,6 +976,7 @@ async fn finish_ingest(
                     e
                 );
             }
+            crate::cost_ingestion::alerts::check_after_fit(clickhouse, merchant_id).await;
         }
         Err(e) => {
             let msg = format!("{e:?}");
@@ -846,6 +1003,18 @@ async fn run_ingest(
     merchant_id: &str,
 ) -> Result<pipeline::IngestOutcome, crate::cost_ingestion::IngestError> {
     use crate::cost_ingestion::IngestError;
+
+    // Keep the report before parsing it, so an ingestion a parser bug fails can be reprocessed once
+    // fixed.
+    if let Some(state) = crate::app::APP_STATE.get() {
+        crate::cost_ingestion::archive::retain_file(
+            &state.global_config.cost_ingestion.report_archive,
+            job_id,
+            path,
+        )
+        .await;
+    }
+
     let std_file = std::fs::File::open(path)
         .map_err(|e| IngestError::Storage(format!("could not reopen temp file: {e}")))?;
     let reader = Box::new(std::io::BufReader::new(std_file));
@@ -24,6 +24,33 @@ struct RuleConfigAnalyticsDetails<'a, Request, Response> {
     response: &'a Response,
 }
 
+/// A stored configuration as the audit log records it: parsed, or the raw text when it is not JSON.
+fn stored_state(raw: &str) -> serde_json::Value {
+    serde_json::from_str(raw).unwrap_or_else(|_| serde_json::Value::String(raw.to_string()))
+}
+
+/// The configuration `name` holds before an update, for the audit log only, so a failed read
+/// records no prior state rather than failing the update.
+async fn stored_config(name: &str) -> Option<serde_json::Value> {
+    types::service_configuration::find_config_by_name(name.to_string())
+        .await
+        .ok()
+        .flatten()
+        .and_then(|config| config.value)
+        .map(|raw| stored_state(&raw))
+}
+
+/// Refuses an SR config naming a scoring strategy that isn't registered, so a typo is caught
+/// when it is written rather than scored with the default.
+fn validate_scoring_strategy(
+    config: &types::routing_configuration::SuccessRateData,
+) -> Result<(), String> {
+    config.scoring_strategy.as_deref().map_or(
+        Ok(()),
+        crate::decider::gatewaydecider::gw_scoring::strategy::validate_name,
+    )
+}
+
 fn serialize_rule_config_analytics_details<Request: Serialize, Response: Serialize>(
     request: &Request,
     response: &Response,
@@ -72,6 +99,13 @@ pub async fn create_rule_config(
 
     let result = match payload.config {
         types::routing_configuration::ConfigVariant::SuccessRate(success_config) => {
+            if let Err(message) = validate_scoring_strategy(&success_config) {
+                API_REQUEST_COUNTER
+                    .with_label_values(&["sr_create_rule_config", "failure"])
+                    .inc();
+                timer.observe_duration();
+                return Err(error::RuleConfigurationError::UnknownScoringStrategy(message).into());
+            }
             let name = format!("SR_V3_INPUT_CONFIG_{}", mid);
             let serialized_config = serde_json::to_string(&success_config)
                 .map_err(|_| error::RuleConfigurationError::StorageError)?;
@@ -400,23 +434,33 @@ pub async fn update_rule_config(
     let trace_id = crate::analytics::trace_id_from_headers(&headers);
 
     let mid = payload.merchant_id.clone();
-    ETM::merchant_account::load_merchant_by_merchant_id(mid.clone())
+    let merchant_account = ETM::merchant_account::load_merchant_by_merchant_id(mid.clone())
         .await
         .ok_or(error::RuleConfigurationError::MerchantNotFound)?;
 
     // Update DB call for updating the rule configuration
     let result = match payload.config {
         types::routing_configuration::ConfigVariant::SuccessRate(success_config) => {
+            if let Err(message) = validate_scoring_strategy(&success_config) {
+                API_REQUEST_COUNTER
+                    .with_label_values(&["sr_update_rule_config", "failure"])
+                    .inc();
+                timer.observe_duration();
+                return Err(error::RuleConfigurationError::UnknownScoringStrategy(message).into());
+            }
             let name = format!("SR_V3_INPUT_CONFIG_{}", mid);
             let serialized_config = serde_json::to_string(&success_config)
                 .map_err(|_| error::RuleConfigurationError::StorageError)?;
+            let before = stored_config(&name).await;
 
-            let result = types::service_configuration::update_config(name, Some(serialized_config))
-                .await
-                .change_context(error::RuleConfigurationError::ConfigurationNotFound);
+            let result =
+                types::service_configuration::update_config(name, Some(serialized_config.clone()))
+                    .await
+                    .change_context(error::RuleConfigurationError::ConfigurationNotFound);
 
             match result {
                 Ok(_) => {
+                    crate::audit::record_change(&before, &stored_state(&serialized_config));
                     API_REQUEST_COUNTER
                         .with_label_values(&["sr_update_rule_config", "success"])
                         .inc();
@@ -439,15 +483,18 @@ pub async fn update_rule_config(
             let serialized_config = serde_json::to_string(&db_config)
                 .map_err(|_| error::RuleConfigurationError::StorageError)?;
 
+            let before = stored_state(&merchant_account.gatewaySuccessRateBasedDeciderInput);
+
             let result = types::merchant::merchant_account::update_merchant_account(
                 mid,
-                Some(serialized_config),
+                Some(serialized_config.clone()),
             )
             .await
             .change_context(error::RuleConfigurationError::ConfigurationNotFound);
 
             match result {
                 Ok(_) => {
+                    crate::audit::record_change(&before, &stored_state(&serialized_config));
                     API_REQUEST_COUNTER
                         .with_label_values(&["elimination_update_rule_config", "success"])
                         .inc();
@@ -469,13 +516,16 @@ pub async fn update_rule_config(
             let name = format!("DEBIT_ROUTING_CONFIG_{}", mid);
             let serialized_config = serde_json::to_string(&debit_config)
                 .map_err(|_| error::RuleConfigurationError::StorageError)?;
+            let before = stored_config(&name).await;
 
-            let result = types::service_configuration::update_config(name, Some(serialized_config))
-                .await
-                .change_context(error::RuleConfigurationError::ConfigurationNotFound);
+            let result =
+                types::service_configuration::update_config(name, Some(serialized_config.clone()))
+                    .await
+                    .change_context(error::RuleConfigurationError::ConfigurationNotFound);
 
             match result {
                 Ok(_) => {
+                    crate::audit::record_change(&before, &stored_state(&serialized_config));
                     API_REQUEST_COUNTER
                         .with_label_values(&["debit_routing_update_rule_config", "success"])
                         .inc();
@@ -205,10 +205,16 @@ pub async fn set_seed_costs(
         }
     }
     let entries = rows_to_entries(body.rows);
+    // The merchant's own edits only: `None` before means they were on the config default.
+    let before = seed_store::get_seed_table(&merchant_id)
+        .await
+        .map(|table| entries_to_rows(&table));
     seed_store::put_seed_table(&merchant_id, &entries)
         .await
         .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?;
-    Ok(Json(entries_to_rows(&entries)))
+    let rows = entries_to_rows(&entries);
+    crate::audit::record_change(&before, &rows);
+    Ok(Json(rows))
 }
 
 /// `DELETE /merchant-account/:merchant-id/seed-costs` — clear edits, reverting to the config
@@ -216,9 +222,13 @@ pub async fn set_seed_costs(
 pub async fn delete_seed_costs(
     Path(merchant_id): Path<String>,
 ) -> Result<Json<Vec<SeedCostRow>>, (StatusCode, String)> {
+    let before = seed_store::get_seed_table(&merchant_id)
+        .await
+        .map(|table| entries_to_rows(&table));
     seed_store::delete_seed_table(&merchant_id)
         .await
         .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?;
+    crate::audit::record_change(&before, &());
     let app_state = get_tenant_app_state().await;
     let entries =
         seed_store::effective_seed_entries(&merchant_id, &app_state.config.hypersense).await;
@@ -0,0 +1,720 @@
+//! Dashboard sign-in through an identity provider ([`auth::oidc`]).
+//!
+//! `start` sends the browser to the provider; the provider sends it back to `callback`, which
+//! verifies who signed in, provisions them and brings their memberships in line with their claims,
+//! then hands the browser back to the dashboard with a one-time code. The dashboard redeems the code
+//! at `exchange` for the same session a password login returns, marked as signed in through SSO —
+//! as with the Hyperswitch handoff, no session token ever travels in a URL.
+//!
+//! A merchant can require SSO. Password login then lands on another of the user's merchants, and a
+//! password session cannot switch onto it. Sessions already issued run out as usual.
+
+use axum::extract::{Path, Query};
+use axum::http::HeaderMap;
+use axum::response::Redirect;
+use axum::Json;
+use diesel::associations::HasTable;
+use diesel::{BoolExpressionMethods, ExpressionMethods};
+use error_stack::{Report, ResultExt};
+use masking::PeekInterface;
+use serde::{Deserialize, Serialize};
+
+use crate::app::{get_tenant_app_state, APP_STATE};
+use crate::auth::{self, oidc, roles};
+use crate::config::OidcProviderConfig;
+use crate::error::{ContainerError, ResultContainerExt, UserAuthError};
+use crate::routes::two_factor;
+use crate::routes::user_auth::{self, LoginResponse};
+use crate::storage::types::{
+    NewUser, NewUserIdentity, NewUserMerchant, User, UserIdentity, UserMerchant,
+    UserMerchantRoleUpdate,
+};
+use crate::types::merchant::merchant_account::load_merchant_by_merchant_id;
+use crate::types::service_configuration;
+use crate::utils::date_time;
+
+#[cfg(feature = "mysql")]
+use crate::storage::schema::users::dsl;
+#[cfg(feature = "postgres")]
+use crate::storage::schema_pg::users::dsl;
+
+#[cfg(feature = "mysql")]
+use crate::storage::schema::user_merchants::dsl as um_dsl;
+#[cfg(feature = "postgres")]
+use crate::storage::schema_pg::user_merchants::dsl as um_dsl;
+
+#[cfg(feature = "mysql")]
+use crate::storage::schema::user_identities::dsl as id_dsl;
+#[cfg(feature = "postgres")]
+use crate::storage::schema_pg::user_identities::dsl as id_dsl;
+
+/// A sign-in in progress, from `start` until the provider sends the browser back.
+const SSO_STATE_PREFIX: &str = "sso_state:";
+const SSO_STATE_TTL_SECONDS: i64 = 600;
+
+/// A finished sign-in waiting for the dashboard to redeem it. Single-use and short-lived, like the
+/// Hyperswitch handoff code.
+const SSO_LOGIN_CODE_PREFIX: &str = "sso_login_code:";
+const SSO_LOGIN_CODE_TTL_SECONDS: i64 = 60;
+
+#[derive(Debug, Serialize, Deserialize)]
+struct PendingSignIn {
+    provider: String,
+    nonce: String,
+    code_verifier: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct CompletedSignIn {
+    user_id: String,
+    merchant_id: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SsoProviderInfo {
+    pub id: String,
+    pub display_name: String,
+    /// Where the login page sends the browser to sign in with this provider.
+    pub start_url: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CallbackQuery {
+    pub code: Option<String>,
+    pub state: Option<String>,
+    /// Set instead of `code` when the provider refused the sign-in.
+    pub error: Option<String>,
+    pub error_description: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ExchangeSsoCodeRequest {
+    pub code: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SsoRequirementRequest {
+    pub sso_required: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SsoRequirementResponse {
+    pub merchant_id: String,
+    pub sso_required: bool,
+}
+
+/// `GET /auth/sso/providers` — what the login page offers besides a password.
+pub async fn list_providers() -> Result<Json<Vec<SsoProviderInfo>>, ContainerError<UserAuthError>> {
+    let global_config = APP_STATE
+        .get()
+        .map(|s| s.global_config.clone())
+        .ok_or(UserAuthError::StorageError)?;
+
+    Ok(Json(
+        global_config
+            .user_auth
+            .sso
+            .providers
+            .iter()
+            .map(|provider| SsoProviderInfo {
+                id: provider.id.clone(),
+                display_name: provider
+                    .display_name
+                    .clone()
+                    .unwrap_or_else(|| provider.id.clone()),
+                start_url: format!("/auth/sso/{}/start", provider.id),
+            })
+            .collect(),
+    ))
+}
+
+/// `GET /auth/sso/:provider/start`
+pub async fn start(
+    Path(provider_id): Path<String>,
+) -> Result<Redirect, ContainerError<UserAuthError>> {
+    let global_config = APP_STATE
+        .get()
+        .map(|s| s.global_config.clone())
+        .ok_or(UserAuthError::StorageError)?;
+    let provider = global_config
+        .user_auth
+        .sso
+        .provider(&provider_id)
+        .ok_or(UserAuthError::SsoProviderNotFound)?;
+
+    let metadata = oidc::discover(provider)
+        .await
+        .map_err(failed("the identity provider could not be reached"))?;
+    let request = oidc::authorization_request(provider, &metadata);
+
+    let app_state = get_tenant_app_state().await;
+    app_state
+        .redis_conn
+        .set_key_with_ttl(
+            &format!("{SSO_STATE_PREFIX}{}", request.state),
+            PendingSignIn {
+                provider: provider.id.clone(),
+                nonce: request.nonce,
+                code_verifier: request.code_verifier,
+            },
+            SSO_STATE_TTL_SECONDS,
+        )
+        .await
+        .change_context(UserAuthError::StorageError)?;
+
+    Ok(Redirect::to(&request.url))
+}
+
+/// `GET /auth/sso/:provider/callback` — where the provider sends the browser back. Always answers
+/// with a redirect to the dashboard: a one-time code on success, the reason otherwise.
+pub async fn callback(
+    Path(provider_id): Path<String>,
+    Query(query): Query<CallbackQuery>,
+) -> Result<Redirect, ContainerError<UserAuthError>> {
+    let global_config = APP_STATE
+        .get()
+        .map(|s| s.global_config.clone())
+        .ok_or(UserAuthError::StorageError)?;
+    let dashboard = global_config.email.base_url.trim_end_matches('/');
+
+    let target = match complete_sign_in(&global_config, &provider_id, query).await {
+        Ok(code) => format!("{dashboard}/sso/callback?code={code}"),
+        Err(error) => {
+            crate::logger::warn!(
+                provider = %provider_id,
+                error = ?error.error,
+                "SSO sign-in failed"
+            );
+            let reason = error.error.current_context().to_string();
+            let reason: String = form_urlencoded::byte_serialize(reason.as_bytes()).collect();
+            format!("{dashboard}/login?sso_error={reason}")
+        }
+    };
+    Ok(Redirect::to(&target))
+}
+
+/// The code the dashboard redeems for a session, once the provider vouches for the user.
+async fn complete_sign_in(
+    global_config: &crate::config::GlobalConfig,
+    provider_id: &str,
+    query: CallbackQuery,
+) -> Result<String, ContainerError<UserAuthError>> {
+    if let Some(refused) = query.error {
+        return Err(ContainerError::from(UserAuthError::SsoFailed(
+            query.error_description.unwrap_or(refused),
+        )));
+    }
+    let (Some(code), Some(state)) = (query.code, query.state) else {
+        return Err(ContainerError::from(UserAuthError::SsoFailed(
+            "the identity provider sent no authorization code".to_string(),
+        )));
+    };
+    let provider = global_config
+        .user_auth
+        .sso
+        .provider(provider_id)
+        .ok_or(UserAuthError::SsoProviderNotFound)?;
+
+    let app_state = get_tenant_app_state().await;
+    let pending = claim::<PendingSignIn>(
+        &app_state,
+        &format!("{SSO_STATE_PREFIX}{state}"),
+        "PendingSignIn",
+    )
+    .await
+    .filter(|pending| pending.provider == provider.id)
+    .ok_or_else(|| {
+        ContainerError::from(UserAuthError::SsoFailed(
+            "the sign-in expired or was already used; start again".to_string(),
+        ))
+    })?;
+
+    let metadata = oidc::discover(provider)
+        .await
+        .map_err(failed("the identity provider could not be reached"))?;
+    let id_token = oidc::exchange_code(provider, &metadata, &code, &pending.code_verifier)
+        .await
+        .map_err(failed("the identity provider did not accept the sign-in"))?;
+    let claims = oidc::verify_id_token(provider, &metadata, &id_token, &pending.nonce)
+        .await
+        .map_err(failed(
+            "the identity provider's answer could not be verified",
+        ))?;
+    let subject = oidc::subject_of(&claims).ok_or_else(|| {
+        ContainerError::from(UserAuthError::SsoFailed(
+            "the identity provider did not say who signed in".to_string(),
+        ))
+    })?;
+
+    // After the first sign-in the provider's account is linked to the user, and the email no
+    // longer matters: an address the provider later gives someone else cannot reach this user.
+    let linked = find_linked_user(&app_state, &metadata.issuer, subject).await?;
+    let user = match linked.clone() {
+        Some(user) => user,
+        None => {
+            let email = oidc::email_of(provider, &claims).ok_or_else(|| {
+                ContainerError::from(UserAuthError::SsoFailed(
+                    "the identity provider did not supply a verified email".to_string(),
+                ))
+            })?;
+            match find_user(&app_state, &email).await? {
+                Some(user) => user,
+                None if provider.jit_provisioning => provision_user(&app_state, &email).await?,
+                None => return Err(ContainerError::from(UserAuthError::SsoNotProvisioned)),
+            }
+        }
+    };
+    if !is_active(&user) {
+        return Err(ContainerError::from(UserAuthError::AccountInactive));
+    }
+    if linked.is_none() {
+        link_identity(&app_state, &metadata.issuer, subject, &user.user_id).await?;
+    }
+
+    sync_memberships(&app_state, provider, &user.user_id, &claims).await?;
+
+    let merchants = user_auth::fetch_user_merchants(&app_state, &user.user_id).await?;
+    let merchant_id = user
+        .merchant_id
+        .filter(|last| merchants.iter().any(|m| m.merchant_id == *last))
+        .or_else(|| merchants.first().map(|m| m.merchant_id.clone()))
+        .unwrap_or_default();
+
+    let code = auth::generate_api_key();
+    app_state
+        .redis_conn
+        .set_key_with_ttl(
+            &format!("{SSO_LOGIN_CODE_PREFIX}{code}"),
+            CompletedSignIn {
+                user_id: user.user_id,
+                merchant_id,
+            },
+            SSO_LOGIN_CODE_TTL_SECONDS,
+        )
+        .await
+        .change_context(UserAuthError::StorageError)?;
+    Ok(code)
+}
+
+/// `POST /auth/sso/exchange` — the dashboard redeems the code from the callback redirect.
+#[axum::debug_handler]
+pub async fn exchange(
+    Json(payload): Json<ExchangeSsoCodeRequest>,
+) -> Result<Json<LoginResponse>, ContainerError<UserAuthError>> {
+    let global_config = APP_STATE
+        .get()
+        .map(|s| s.global_config.clone())
+        .ok_or(UserAuthError::StorageError)?;
+    let app_state = get_tenant_app_state().await;
+
+    let signed_in = claim::<CompletedSignIn>(
+        &app_state,
+        &format!("{SSO_LOGIN_CODE_PREFIX}{}", payload.code),
+        "CompletedSignIn",
+    )
+    .await
+    .ok_or(UserAuthError::InvalidToken)?;
+
+    let user = crate::generics::generic_find_all::<<User as HasTable>::Table, _, User>(
+        &app_state.db,
+        dsl::user_id.eq(signed_in.user_id.clone()),
+    )
+    .await
+    .change_error(UserAuthError::StorageError)?
+    .pop()
+    .ok_or(UserAuthError::UserNotFound)?;
+    if !is_active(&user) {
+        return Err(ContainerError::from(UserAuthError::AccountInactive));
+    }
+
+    // The provider stands in for the merchant's two-factor requirement, but not for an
+    // authenticator the user enrolled themselves.
+    if two_factor::second_step_required(
+        two_factor::is_enabled(&user),
+        two_factor::two_factor_required(&signed_in.merchant_id).await?,
+        true,
+    ) {
+        return Ok(Json(LoginResponse::TwoFactorRequired(
+            two_factor::challenge(&app_state, &user, signed_in.merchant_id, true).await?,
+        )));
+    }
+
+    let merchants = user_auth::fetch_user_merchants(&app_state, &user.user_id).await?;
+    Ok(Json(LoginResponse::Authenticated(
+        user_auth::issue_session(
+            &app_state,
+            &global_config,
+            user,
+            signed_in.merchant_id,
+            merchants,
+            true,
+            false,
+        )
+        .await?,
+    )))
+}
+
+/// `GET /merchant/sso` — whether the session's merchant requires SSO.
+pub async fn get_sso_requirement(
+    headers: HeaderMap,
+) -> Result<Json<SsoRequirementResponse>, ContainerError<UserAuthError>> {
+    let token = user_auth::extract_bearer_token(&headers)?;
+    let global_config = APP_STATE
+        .get()
+        .map(|s| s.global_config.clone())
+        .ok_or(UserAuthError::StorageError)?;
+    let claims =
+        user_auth::verify_jwt_not_revoked(token, global_config.user_auth.jwt_secret.peek()).await?;
+
+    Ok(Json(SsoRequirementResponse {
+        sso_required: sso_required(&claims.merchant_id).await?,
+        merchant_id: claims.merchant_id,
+    }))
+}
+
+/// `PUT /merchant/sso` — require SSO for the session's merchant, or stop requiring it.
+///
+/// Only a session that itself signed in through SSO can turn the requirement on, so nobody locks
+/// the merchant behind a provider they have not shown works for them.
+pub async fn set_sso_requirement(
+    headers: HeaderMap,
+    Json(payload): Json<SsoRequirementRequest>,
+) -> Result<Json<SsoRequirementResponse>, ContainerError<UserAuthError>> {
+    let (claims, _) = user_auth::member_admin_session(&headers).await?;
+    if payload.sso_required && !claims.sso {
+        return Err(ContainerError::from(UserAuthError::SsoRequired));
+    }
+
+    let config_name = sso_required_config_name(&claims.merchant_id);
+    let existing = service_configuration::find_config_by_name(config_name.clone())
+        .await
+        .change_context(UserAuthError::StorageError)?;
+    let before = existing
+        .as_ref()
+        .and_then(|config| config.value.as_deref())
+        .and_then(|value| value.parse::<bool>().ok())
+        .unwrap_or(false);
+
+    let value = Some(payload.sso_required.to_string());
+    if existing.is_some() {
+        service_configuration::update_config(config_name, value)
+            .await
+            .change_context(UserAuthError::StorageError)?;
+    } else {
+        service_configuration::insert_config(config_name, value)
+            .await
+            .change_context(UserAuthError::StorageError)?;
+    }
+
+    let response = SsoRequirementResponse {
+        merchant_id: claims.merchant_id,
+        sso_required: payload.sso_required,
+    };
+    crate::audit::record_change(
+        &SsoRequirementResponse {
+            merchant_id: response.merchant_id.clone(),
+            sso_required: before,
+        },
+        &response,
+    );
+    Ok(Json(response))
+}
+
+fn sso_required_config_name(merchant_id: &str) -> String {
+    format!("SSO_REQUIRED_{}", merchant_id)
+}
+
+/// Whether `merchant_id` admits only sessions signed in through SSO.
+pub(crate) async fn sso_required(merchant_id: &str) -> Result<bool, ContainerError<UserAuthError>> {
+    if merchant_id.is_empty() {
+        return Ok(false);
+    }
+    Ok(
+        service_configuration::find_config_by_name(sso_required_config_name(merchant_id))
+            .await
+            .change_context(UserAuthError::StorageError)?
+            .and_then(|config| config.value)
+            .and_then(|value| value.parse::<bool>().ok())
+            .unwrap_or(false),
+    )
+}
+
+/// Reads and deletes a one-time Redis entry. Only the caller whose delete removed it gets it back,
+/// so two concurrent redemptions cannot both succeed.
+async fn claim<T: serde::de::DeserializeOwned>(
+    app_state: &crate::app::TenantAppState,
+    key: &str,
+    type_name: &'static str,
+) -> Option<T> {
+    let value = app_state
+        .redis_conn
+        .get_key::<T>(key, type_name)
+        .await
+        .ok()?;
+    let claimed = app_state.redis_conn.delete_key(key).await.ok()?;
+    matches!(claimed, redis_interface::types::DelReply::KeyDeleted).then_some(value)
+}
+
+fn failed(
+    reason: &'static str,
+) -> impl FnOnce(Report<oidc::OidcError>) -> ContainerError<UserAuthError> {
+    move |report| {
+        crate::logger::warn!(error = ?report, "{reason}");
+        ContainerError::from(UserAuthError::SsoFailed(reason.to_string()))
+    }
+}
+
+fn is_active(user: &User) -> bool {
+    #[cfg(feature = "mysql")]
+    {
+        user.is_active != 0
+    }
+    #[cfg(feature = "postgres")]
+    {
+        user.is_active
+    }
+}
+
+async fn find_user(
+    app_state: &crate::app::TenantAppState,
+    email: &str,
+) -> Result<Option<User>, ContainerError<UserAuthError>> {
+    Ok(
+        crate::generics::generic_find_all::<<User as HasTable>::Table, _, User>(
+            &app_state.db,
+            dsl::email.eq(email.to_string()),
+        )
+        .await
+        .change_error(UserAuthError::StorageError)?
+        .pop(),
+    )
+}
+
+/// The user the provider's account `subject` at `issuer` was linked to on its first sign-in.
+async fn find_linked_user(
+    app_state: &crate::app::TenantAppState,
+    issuer: &str,
+    subject: &str,
+) -> Result<Option<User>, ContainerError<UserAuthError>> {
+    let Some(identity) =
+        crate::generics::generic_find_all::<<UserIdentity as HasTable>::Table, _, UserIdentity>(
+            &app_state.db,
+            id_dsl::issuer
+                .eq(issuer.to_string())
+                .and(id_dsl::subject.eq(subject.to_string())),
+        )
+        .await
+        .change_error(UserAuthError::StorageError)?
+        .pop()
+    else {
+        return Ok(None);
+    };
+    crate::generics::generic_find_all::<<User as HasTable>::Table, _, User>(
+        &app_state.db,
+        dsl::user_id.eq(identity.user_id),
+    )
+    .await
+    .change_error(UserAuthError::StorageError)?
+    .pop()
+    .map(Some)
+    .ok_or_else(|| ContainerError::from(UserAuthError::UserNotFound))
+}
+
+/// Links the provider's account to the user its verified email found. A user already linked to
+/// another account at the same issuer is refused: the email now belongs to someone else there.
+async fn link_identity(
+    app_state: &crate::app::TenantAppState,
+    issuer: &str,
+    subject: &str,
+    user_id: &str,
+) -> Result<(), ContainerError<UserAuthError>> {
+    let existing =
+        crate::generics::generic_find_all::<<UserIdentity as HasTable>::Table, _, UserIdentity>(
+            &app_state.db,
+            id_dsl::issuer
+                .eq(issuer.to_string())
+                .and(id_dsl::user_id.eq(user_id.to_string())),
+        )
+        .await
+        .change_error(UserAuthError::StorageError)?;
+    if !existing.is_empty() {
+        return Err(ContainerError::from(UserAuthError::SsoFailed(
+            "this user already signs in with another account at the identity provider".to_string(),
+        )));
+    }
+    crate::generics::generic_insert(
+        &app_state.db,
+        NewUserIdentity {
+            issuer: issuer.to_string(),
+            subject: subject.to_string(),
+            user_id: user_id.to_string(),
+            created_at: date_time::now(),
+        },
+    )
+    .await
+    .change_context(UserAuthError::StorageError)?;
+    Ok(())
+}
+
+/// A user for someone the provider vouches for but Decision Engine has not met. Their password is
+/// random and never shown: they sign in through the provider, or reset it to use one.
+async fn provision_user(
+    app_state: &crate::app::TenantAppState,
+    email: &str,
+) -> Result<User, ContainerError<UserAuthError>> {
+    let password_hash = auth::hash_password(&user_auth::generate_random_password())
+        .change_context(UserAuthError::PasswordHashingFailed)?;
+    let new_user = NewUser {
+        user_id: uuid::Uuid::new_v4().to_string(),
+        email: email.to_string(),
+        password_hash,
+        merchant_id: None,
+        role: roles::MEMBER.to_string(),
+        #[cfg(feature = "mysql")]
+        is_active: 1,
+        #[cfg(feature = "postgres")]
+        is_active: true,
+        // The provider verified it, which is what verification would have established.
+        #[cfg(feature = "mysql")]
+        email_verified: 1,
+        #[cfg(feature = "postgres")]
+        email_verified: true,
+        created_at: date_time::now(),
+    };
+    crate::generics::generic_insert(&app_state.db, new_user)
+        .await
+        .change_context(UserAuthError::StorageError)?;
+
+    find_user(app_state, email)
+        .await?
+        .ok_or_else(|| ContainerError::from(UserAuthError::StorageError))
+}
+
+/// Brings the user's memberships of the merchants `provider` manages in line with what `claims`
+/// confer. Memberships of other merchants are left alone.
+///
+/// A rule naming a merchant or role that does not exist is skipped rather than failing the sign-in,
+/// and so is a change that would leave a merchant without an admin — the same rule member
+/// management enforces.
+async fn sync_memberships(
+    app_state: &crate::app::TenantAppState,
+    provider: &OidcProviderConfig,
+    user_id: &str,
+    claims: &oidc::Claims,
+) -> Result<(), ContainerError<UserAuthError>> {
+    let granted = oidc::memberships(&provider.memberships, claims);
+    let current = crate::generics::generic_find_all::<
+        <UserMerchant as HasTable>::Table,
+        _,
+        UserMerchant,
+    >(&app_state.db, um_dsl::user_id.eq(user_id.to_string()))
+    .await
+    .change_error(UserAuthError::StorageError)?;
+    let conn = &app_state
+        .db
+        .get_conn()
+        .await
+        .change_error(UserAuthError::StorageError)?;
+
+    for merchant_id in oidc::managed_merchants(provider) {
+        let wanted = granted
+            .iter()
+            .find(|rule| rule.merchant_id == merchant_id)
+            .map(|rule| rule.role.as_str());
+        let held = current.iter().find(|m| m.merchant_id == merchant_id);
+
+        match (held, wanted) {
+            (None, None) => {}
+            (Some(held), Some(role)) if held.role == role => {}
+            (held, Some(role)) => {
+                if load_merchant_by_merchant_id(merchant_id.to_string())
+                    .await
+                    .is_none()
+                    || user_auth::existing_role_permissions(app_state, merchant_id, role)
+                        .await
+                        .is_err()
+                {
+                    crate::logger::warn!(
+                        provider = %provider.id,
+                        merchant_id = %merchant_id,
+                        role = %role,
+                        "SSO membership rule names a merchant or role that does not exist; skipped"
+                    );
+                    continue;
+                }
+                match held {
+                    Some(held) => {
+                        if role != roles::ADMIN && is_last_admin(app_state, held).await? {
+                            continue;
+                        }
+                        crate::generics::generic_update::<<UserMerchant as HasTable>::Table, _, _>(
+                            conn,
+                            um_dsl::merchant_id
+                                .eq(merchant_id.to_string())
+                                .and(um_dsl::user_id.eq(user_id.to_string())),
+                            UserMerchantRoleUpdate {
+                                role: role.to_string(),
+                            },
+                        )
+                        .await
+                        .change_context(UserAuthError::StorageError)?;
+                    }
+                    None => {
+                        crate::generics::generic_insert(
+                            &app_state.db,
+                            NewUserMerchant {
+                                user_id: user_id.to_string(),
+                                merchant_id: merchant_id.to_string(),
+                                role: role.to_string(),
+                                created_at: date_time::now(),
+                            },
+                        )
+                        .await
+                        .change_context(UserAuthError::StorageError)?;
+                    }
+                }
+            }
+            (Some(held), None) => {
+                if is_last_admin(app_state, held).await? {
+                    continue;
+                }
+                crate::generics::generic_delete::<<UserMerchant as HasTable>::Table, _>(
+                    conn,
+                    um_dsl::merchant_id
+                        .eq(merchant_id.to_string())
+                        .and(um_dsl::user_id.eq(user_id.to_string())),
+                )
+                .await
+                .change_context(UserAuthError::StorageError)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+async fn is_last_admin(
+    app_state: &crate::app::TenantAppState,
+    membership: &UserMerchant,
+) -> Result<bool, ContainerError<UserAuthError>> {
+    if membership.role != roles::ADMIN {
+        return Ok(false);
+    }
+    let admins =
+        crate::generics::generic_find_all::<<UserMerchant as HasTable>::Table, _, UserMerchant>(
+            &app_state.db,
+            um_dsl::merchant_id
+                .eq(membership.merchant_id.clone())
+                .and(um_dsl::role.eq(roles::ADMIN.to_string())),
+        )
+        .await
+        .change_error(UserAuthError::StorageError)?;
+    let last = admins.len() <= 1;
+    if last {
+        crate::logger::warn!(
+            merchant_id = %membership.merchant_id,
+            user_id = %membership.user_id,
+            "SSO claims would leave the merchant without an admin; membership kept"
+        );
+    }
+    Ok(last)
+}
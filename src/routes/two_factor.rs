@@ -0,0 +1,828 @@
+//! Two-factor sign-in for dashboard users ([`auth::totp`]).
+//!
+//! A user who has enrolled an authenticator app does not get a session from their password alone:
+//! login answers with a challenge, which `verify` redeems for the session once it is given a code
+//! from the app, or one of the recovery codes handed out at enrollment. Each recovery code works
+//! once, and a code from the app is not accepted twice either.
+//!
+//! A merchant can require two-factor. A member who has not enrolled is then challenged at login
+//! too, and enrolls with that challenge before getting a session; a password session that did not
+//! pass the second step cannot switch onto the merchant. Sessions signed in through SSO are exempt
+//! from that requirement — the identity provider decides how its users prove who they are — but a
+//! user who enrolled an authenticator is still asked for it after signing in through SSO.
+//!
+//! A platform super admin can reset a user's two-factor, for when both the app and the recovery
+//! codes are lost.
+
+use axum::http::HeaderMap;
+use axum::Json;
+use diesel::associations::HasTable;
+use diesel::{BoolExpressionMethods, ExpressionMethods};
+use error_stack::ResultExt;
+use masking::PeekInterface;
+use serde::{Deserialize, Serialize};
+
+use crate::app::{get_tenant_app_state, APP_STATE};
+use crate::auth::{self, totp, TOKEN_TYPE_STANDARD};
+use crate::error::{ContainerError, ResultContainerExt, UserAuthError};
+use crate::routes::user_auth::{self, AuthResponse, MessageResponse};
+use crate::storage::types::{
+    User, UserTotpEnabledUpdate, UserTotpRecoveryCodesUpdate, UserTotpStepUpdate, UserTotpUpdate,
+};
+use crate::types::service_configuration;
+
+#[cfg(feature = "mysql")]
+use crate::storage::schema::users::dsl;
+#[cfg(feature = "postgres")]
+use crate::storage::schema_pg::users::dsl;
+
+/// A login whose password checked out, waiting for its second factor. Long enough to set up an
+/// authenticator app when the login is also the enrollment.
+const CHALLENGE_PREFIX: &str = "two_factor_challenge:";
+const CHALLENGE_TTL_SECONDS: i64 = 600;
+
+/// Invalid codes a user may send before further codes are refused for the rest of the window.
+/// Counted per user rather than per challenge, so starting a new login does not reset it.
+const FAILURES_PREFIX: &str = "two_factor_failures:";
+const MAX_FAILURES: i64 = 5;
+const FAILURES_WINDOW_SECONDS: i64 = 900;
+
+#[derive(Debug, Serialize, Deserialize)]
+struct PendingLogin {
+    user_id: String,
+    merchant_id: String,
+    /// The first step was an SSO sign-in rather than a password.
+    #[serde(default)]
+    sso: bool,
+}
+
+/// What login answers instead of a session when a second factor is needed.
+#[derive(Debug, Serialize)]
+pub struct TwoFactorChallenge {
+    pub two_factor_required: bool,
+    /// The user has no authenticator yet: enroll with `challenge`, then confirm with a code, which
+    /// returns the session.
+    pub enrollment_required: bool,
+    pub challenge: String,
+    pub expires_in: i64,
+}
+
+/// A code from the authenticator app, or a recovery code instead.
+#[derive(Debug, Default, Deserialize)]
+pub struct SecondFactor {
+    pub code: Option<String>,
+    pub recovery_code: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct VerifyTwoFactorRequest {
+    pub challenge: String,
+    #[serde(flatten)]
+    pub factor: SecondFactor,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct EnrollTwoFactorRequest {
+    /// From login, when enrolling is what stands between the user and a session. Without it the
+    /// caller's own session enrolls.
+    pub challenge: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct EnrollTwoFactorResponse {
+    /// Base32, for typing into an app that cannot scan.
+    pub secret: String,
+    /// The `otpauth://` URI to show as a QR code.
+    pub provisioning_uri: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ConfirmTwoFactorRequest {
+    pub challenge: Option<String>,
+    pub code: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RecoveryCodesResponse {
+    /// Shown once: only their hashes are kept.
+    pub recovery_codes: Vec<String>,
+    /// The session, when enrollment was completing a login.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub session: Option<AuthResponse>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RegenerateRecoveryCodesRequest {
+    pub code: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ResetTwoFactorRequest {
+    pub email: String,
+}
+
+#[derive(Debug, Serialize)]
+struct TwoFactorState {
+    email: String,
+    two_factor_enabled: bool,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TwoFactorRequirementRequest {
+    pub two_factor_required: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TwoFactorRequirementResponse {
+    pub merchant_id: String,
+    pub two_factor_required: bool,
+}
+
+/// The challenge login answers with for `user`, who is landing on `merchant_id`. `sso` when the
+/// first step was an SSO sign-in, which the session redeemed from it keeps.
+pub(crate) async fn challenge(
+    app_state: &crate::app::TenantAppState,
+    user: &User,
+    merchant_id: String,
+    sso: bool,
+) -> Result<TwoFactorChallenge, ContainerError<UserAuthError>> {
+    let challenge = auth::generate_api_key();
+    app_state
+        .redis_conn
+        .set_key_with_ttl(
+            &format!("{CHALLENGE_PREFIX}{challenge}"),
+            PendingLogin {
+                user_id: user.user_id.clone(),
+                merchant_id,
+                sso,
+            },
+            CHALLENGE_TTL_SECONDS,
+        )
+        .await
+        .change_context(UserAuthError::StorageError)?;
+
+    Ok(TwoFactorChallenge {
+        two_factor_required: true,
+        enrollment_required: !is_enabled(user),
+        challenge,
+        expires_in: CHALLENGE_TTL_SECONDS,
+    })
+}
+
+/// `POST /auth/2fa/verify` — the second step of login.
+#[axum::debug_handler]
+pub async fn verify(
+    Json(payload): Json<VerifyTwoFactorRequest>,
+) -> Result<Json<AuthResponse>, ContainerError<UserAuthError>> {
+    let global_config = APP_STATE
+        .get()
+        .map(|s| s.global_config.clone())
+        .ok_or(UserAuthError::StorageError)?;
+    let app_state = get_tenant_app_state().await;
+
+    let user = pending_login(&app_state, &payload.challenge).await?;
+    if !is_enabled(&user) {
+        return Err(ContainerError::from(UserAuthError::TwoFactorNotEnabled));
+    }
+    check_second_factor(&app_state, &user, &payload.factor).await?;
+
+    let pending = redeem_challenge(&app_state, &payload.challenge).await?;
+    let merchants = user_auth::fetch_user_merchants(&app_state, &user.user_id).await?;
+    let session = user_auth::issue_session(
+        &app_state,
+        &global_config,
+        user,
+        pending.merchant_id,
+        merchants,
+        pending.sso,
+        true,
+    )
+    .await?;
+    Ok(Json(session))
+}
+
+/// `POST /auth/2fa/enroll` — a new secret for the user's authenticator app. Nothing changes at
+/// login until a code from it is confirmed; enrolling again before then replaces the secret.
+#[axum::debug_handler]
+pub async fn enroll(
+    headers: HeaderMap,
+    payload: Option<Json<EnrollTwoFactorRequest>>,
+) -> Result<Json<EnrollTwoFactorResponse>, ContainerError<UserAuthError>> {
+    let app_state = get_tenant_app_state().await;
+    let challenge = payload.and_then(|Json(payload)| payload.challenge);
+    let user = match challenge {
+        Some(challenge) => pending_login(&app_state, &challenge).await?,
+        None => account_session(&app_state, &headers).await?,
+    };
+    if is_enabled(&user) {
+        return Err(ContainerError::from(UserAuthError::TwoFactorAlreadyEnabled));
+    }
+
+    let secret = totp::generate_secret();
+    let sealed = secret_keyring()?
+        .seal(&secret)
+        .ok_or(UserAuthError::StorageError)?;
+    let conn = app_state
+        .db
+        .get_conn()
+        .await
+        .change_error(UserAuthError::StorageError)?;
+    crate::generics::generic_update::<<User as HasTable>::Table, _, _>(
+        &conn,
+        dsl::user_id.eq(user.user_id.clone()),
+        UserTotpUpdate {
+            totp_secret: Some(sealed),
+            #[cfg(feature = "mysql")]
+            totp_enabled: 0,
+            #[cfg(feature = "postgres")]
+            totp_enabled: false,
+            totp_recovery_codes: None,
+            totp_last_step: None,
+        },
+    )
+    .await
+    .change_context(UserAuthError::StorageError)?;
+
+    Ok(Json(EnrollTwoFactorResponse {
+        provisioning_uri: totp::provisioning_uri(&secret, &user.email),
+        secret,
+    }))
+}
+
+/// `POST /auth/2fa/confirm` — turns two-factor on once the app shows it has the secret, and hands
+/// out the recovery codes. Completing a login this way also returns its session.
+#[axum::debug_handler]
+pub async fn confirm(
+    headers: HeaderMap,
+    Json(payload): Json<ConfirmTwoFactorRequest>,
+) -> Result<Json<RecoveryCodesResponse>, ContainerError<UserAuthError>> {
+    let global_config = APP_STATE
+        .get()
+        .map(|s| s.global_config.clone())
+        .ok_or(UserAuthError::StorageError)?;
+    let app_state = get_tenant_app_state().await;
+
+    let user = match payload.challenge.as_deref() {
+        Some(challenge) => pending_login(&app_state, challenge).await?,
+        None => account_session(&app_state, &headers).await?,
+    };
+    if is_enabled(&user) {
+        return Err(ContainerError::from(UserAuthError::TwoFactorAlreadyEnabled));
+    }
+    let Some(stored_secret) = user.totp_secret.clone() else {
+        return Err(ContainerError::from(UserAuthError::TwoFactorNotEnabled));
+    };
+    check_second_factor(
+        &app_state,
+        &user,
+        &SecondFactor {
+            code: Some(payload.code),
+            recovery_code: None,
+        },
+    )
+    .await?;
+
+    let (recovery_codes, hashes) = new_recovery_codes()?;
+    let conn = app_state
+        .db
+        .get_conn()
+        .await
+        .change_error(UserAuthError::StorageError)?;
+    // Only the secret the code was checked against: an enrollment started meanwhile stays pending.
+    let enabled = crate::generics::generic_update_if_present::<<User as HasTable>::Table, _, _>(
+        &conn,
+        dsl::user_id
+            .eq(user.user_id.clone())
+            .and(dsl::totp_secret.eq(stored_secret)),
+        UserTotpEnabledUpdate {
+            #[cfg(feature = "mysql")]
+            totp_enabled: 1,
+            #[cfg(feature = "postgres")]
+            totp_enabled: true,
+            totp_recovery_codes: Some(hashes),
+        },
+    )
+    .await
+    .change_context(UserAuthError::StorageError)?;
+    if enabled == 0 {
+        return Err(ContainerError::from(UserAuthError::InvalidTwoFactorCode));
+    }
+
+    let session = match payload.challenge.as_deref() {
+        Some(challenge) => {
+            let pending = redeem_challenge(&app_state, challenge).await?;
+            let merchants = user_auth::fetch_user_merchants(&app_state, &user.user_id).await?;
+            Some(
+                user_auth::issue_session(
+                    &app_state,
+                    &global_config,
+                    user,
+                    pending.merchant_id,
+                    merchants,
+                    pending.sso,
+                    true,
+                )
+                .await?,
+            )
+        }
+        None => None,
+    };
+    Ok(Json(RecoveryCodesResponse {
+        recovery_codes,
+        session,
+    }))
+}
+
+/// `POST /auth/2fa/recovery-codes` — a fresh set of recovery codes, replacing any left.
+#[axum::debug_handler]
+pub async fn regenerate_recovery_codes(
+    headers: HeaderMap,
+    Json(payload): Json<RegenerateRecoveryCodesRequest>,
+) -> Result<Json<RecoveryCodesResponse>, ContainerError<UserAuthError>> {
+    let app_state = get_tenant_app_state().await;
+    let user = account_session(&app_state, &headers).await?;
+    if !is_enabled(&user) {
+        return Err(ContainerError::from(UserAuthError::TwoFactorNotEnabled));
+    }
+    // The app only: a recovery code should not be able to mint ten more.
+    check_second_factor(
+        &app_state,
+        &user,
+        &SecondFactor {
+            code: Some(payload.code),
+            recovery_code: None,
+        },
+    )
+    .await?;
+
+    let (recovery_codes, hashes) = new_recovery_codes()?;
+    let conn = app_state
+        .db
+        .get_conn()
+        .await
+        .change_error(UserAuthError::StorageError)?;
+    crate::generics::generic_update::<<User as HasTable>::Table, _, _>(
+        &conn,
+        dsl::user_id.eq(user.user_id.clone()),
+        UserTotpRecoveryCodesUpdate {
+            totp_recovery_codes: Some(hashes),
+        },
+    )
+    .await
+    .change_context(UserAuthError::StorageError)?;
+    Ok(Json(RecoveryCodesResponse {
+        recovery_codes,
+        session: None,
+    }))
+}
+
+/// `POST /auth/2fa/disable` — turns two-factor off, given a current code. Refused while any of the
+/// user's merchants requires it.
+#[axum::debug_handler]
+pub async fn disable(
+    headers: HeaderMap,
+    Json(payload): Json<SecondFactor>,
+) -> Result<Json<MessageResponse>, ContainerError<UserAuthError>> {
+    let app_state = get_tenant_app_state().await;
+    let user = account_session(&app_state, &headers).await?;
+    if !is_enabled(&user) {
+        return Err(ContainerError::from(UserAuthError::TwoFactorNotEnabled));
+    }
+    for merchant in user_auth::fetch_user_merchants(&app_state, &user.user_id).await? {
+        if two_factor_required(&merchant.merchant_id).await? {
+            return Err(ContainerError::from(UserAuthError::TwoFactorRequired));
+        }
+    }
+    check_second_factor(&app_state, &user, &payload).await?;
+
+    clear(&app_state, &user.user_id).await?;
+    Ok(Json(MessageResponse {
+        message: "Two-factor authentication disabled.".to_string(),
+    }))
+}
+
+/// `POST /auth/super-admin/reset-2fa` — clears a user's two-factor so they can enroll again. For
+/// a user who has lost both the app and the recovery codes; the roster is checked as for entering
+/// a merchant.
+#[axum::debug_handler]
+pub async fn reset(
+    headers: HeaderMap,
+    Json(payload): Json<ResetTwoFactorRequest>,
+) -> Result<Json<MessageResponse>, ContainerError<UserAuthError>> {
+    let token = user_auth::extract_bearer_token(&headers)?;
+    let global_config = APP_STATE
+        .get()
+        .map(|s| s.global_config.clone())
+        .ok_or(UserAuthError::StorageError)?;
+    let claims =
+        user_auth::verify_jwt_not_revoked(token, global_config.user_auth.jwt_secret.peek()).await?;
+    if claims.token_type != TOKEN_TYPE_STANDARD {
+        return Err(ContainerError::from(UserAuthError::UnsupportedOperation));
+    }
+    if !user_auth::is_super_admin(&global_config, &claims) {
+        return Err(ContainerError::from(UserAuthError::Forbidden));
+    }
+
+    let app_state = get_tenant_app_state().await;
+    let user = crate::generics::generic_find_all::<<User as HasTable>::Table, _, User>(
+        &app_state.db,
+        dsl::email.eq(payload.email.clone()),
+    )
+    .await
+    .change_error(UserAuthError::StorageError)?
+    .pop()
+    .ok_or(UserAuthError::UserNotFound)?;
+
+    clear(&app_state, &user.user_id).await?;
+    // A fresh start, including the count of codes already got wrong.
+    let _ = app_state
+        .redis_conn
+        .delete_key(&format!("{FAILURES_PREFIX}{}", user.user_id))
+        .await;
+
+    crate::audit::record_change(
+        &TwoFactorState {
+            email: user.email.clone(),
+            two_factor_enabled: is_enabled(&user),
+        },
+        &TwoFactorState {
+            email: user.email,
+            two_factor_enabled: false,
+        },
+    );
+    Ok(Json(MessageResponse {
+        message: "Two-factor authentication reset.".to_string(),
+    }))
+}
+
+/// `GET /merchant/two-factor` — whether the session's merchant requires two-factor.
+pub async fn get_two_factor_requirement(
+    headers: HeaderMap,
+) -> Result<Json<TwoFactorRequirementResponse>, ContainerError<UserAuthError>> {
+    let token = user_auth::extract_bearer_token(&headers)?;
+    let global_config = APP_STATE
+        .get()
+        .map(|s| s.global_config.clone())
+        .ok_or(UserAuthError::StorageError)?;
+    let claims =
+        user_auth::verify_jwt_not_revoked(token, global_config.user_auth.jwt_secret.peek()).await?;
+
+    Ok(Json(TwoFactorRequirementResponse {
+        two_factor_required: two_factor_required(&claims.merchant_id).await?,
+        merchant_id: claims.merchant_id,
+    }))
+}
+
+/// `PUT /merchant/two-factor` — require two-factor for the session's merchant's members, or stop
+/// requiring it.
+///
+/// Turning it on needs the admin to have two-factor themselves, so the requirement is never set
+/// by someone who has not been through enrollment.
+pub async fn set_two_factor_requirement(
+    headers: HeaderMap,
+    Json(payload): Json<TwoFactorRequirementRequest>,
+) -> Result<Json<TwoFactorRequirementResponse>, ContainerError<UserAuthError>> {
+    let (claims, _) = user_auth::member_admin_session(&headers).await?;
+    if payload.two_factor_required {
+        let app_state = get_tenant_app_state().await;
+        let admin = find_user(&app_state, &claims.user_id).await?;
+        if !is_enabled(&admin) {
+            return Err(ContainerError::from(UserAuthError::TwoFactorNotEnabled));
+        }
+    }
+
+    let config_name = two_factor_required_config_name(&claims.merchant_id);
+    let existing = service_configuration::find_config_by_name(config_name.clone())
+        .await
+        .change_context(UserAuthError::StorageError)?;
+    let before = existing
+        .as_ref()
+        .and_then(|config| config.value.as_deref())
+        .and_then(|value| value.parse::<bool>().ok())
+        .unwrap_or(false);
+
+    let value = Some(payload.two_factor_required.to_string());
+    if existing.is_some() {
+        service_configuration::update_config(config_name, value)
+            .await
+            .change_context(UserAuthError::StorageError)?;
+    } else {
+        service_configuration::insert_config(config_name, value)
+            .await
+            .change_context(UserAuthError::StorageError)?;
+    }
+
+    let response = TwoFactorRequirementResponse {
+        merchant_id: claims.merchant_id,
+        two_factor_required: payload.two_factor_required,
+    };
+    crate::audit::record_change(
+        &TwoFactorRequirementResponse {
+            merchant_id: response.merchant_id.clone(),
+            two_factor_required: before,
+        },
+        &response,
+    );
+    Ok(Json(response))
+}
+
+fn two_factor_required_config_name(merchant_id: &str) -> String {
+    format!("TWO_FACTOR_REQUIRED_{}", merchant_id)
+}
+
+/// Whether `merchant_id` requires its members to sign in with two-factor.
+pub(crate) async fn two_factor_required(
+    merchant_id: &str,
+) -> Result<bool, ContainerError<UserAuthError>> {
+    if merchant_id.is_empty() {
+        return Ok(false);
+    }
+    Ok(
+        service_configuration::find_config_by_name(two_factor_required_config_name(merchant_id))
+            .await
+            .change_context(UserAuthError::StorageError)?
+            .and_then(|config| config.value)
+            .and_then(|value| value.parse::<bool>().ok())
+            .unwrap_or(false),
+    )
+}
+
+/// Whether a sign-in must pass the second step before it gets a session: always for a user with an
+/// authenticator, and for a merchant that requires it unless the sign-in came through SSO.
+pub(crate) fn second_step_required(enrolled: bool, merchant_requires: bool, sso: bool) -> bool {
+    enrolled || (merchant_requires && !sso)
+}
+
+/// Whether the session in `claims` may move onto a merchant, given whether that merchant requires
+/// two-factor.
+pub(crate) fn session_admitted(claims: &auth::JwtClaims, merchant_requires: bool) -> bool {
+    !merchant_requires || claims.sso || claims.mfa
+}
+
+pub(crate) fn is_enabled(user: &User) -> bool {
+    #[cfg(feature = "mysql")]
+    {
+        user.totp_enabled != 0
+    }
+    #[cfg(feature = "postgres")]
+    {
+        user.totp_enabled
+    }
+}
+
+/// Accepts `factor` for `user` or refuses it. A code from the app is checked against the window
+/// around now and must be for a later step than the last one accepted; a recovery code is used up.
+/// Both are settled by a conditional write, so two requests racing with the same code cannot both
+/// get through.
+async fn check_second_factor(
+    app_state: &crate::app::TenantAppState,
+    user: &User,
+    factor: &SecondFactor,
+) -> Result<(), ContainerError<UserAuthError>> {
+    let failures_key = format!("{FAILURES_PREFIX}{}", user.user_id);
+    let attempts = app_state
+        .redis_conn
+        .increment_key(&failures_key)
+        .await
+        .change_context(UserAuthError::StorageError)?;
+    if attempts == 1 {
+        let _ = app_state
+            .redis_conn
+            .expire_key(&failures_key, FAILURES_WINDOW_SECONDS)
+            .await;
+    }
+    if attempts > MAX_FAILURES {
+        return Err(ContainerError::from(
+            UserAuthError::TooManyTwoFactorAttempts,
+        ));
+    }
+
+    let accepted = match (factor.code.as_deref(), factor.recovery_code.as_deref()) {
+        (Some(code), _) => accept_code(app_state, user, code).await?,
+        (None, Some(recovery_code)) => use_recovery_code(app_state, user, recovery_code).await?,
+        (None, None) => false,
+    };
+    if !accepted {
+        return Err(ContainerError::from(UserAuthError::InvalidTwoFactorCode));
+    }
+    let _ = app_state.redis_conn.delete_key(&failures_key).await;
+    Ok(())
+}
+
+async fn accept_code(
+    app_state: &crate::app::TenantAppState,
+    user: &User,
+    code: &str,
+) -> Result<bool, ContainerError<UserAuthError>> {
+    let Some(stored) = user.totp_secret.as_deref() else {
+        return Ok(false);
+    };
+    let keyring = secret_keyring()?;
+    let secret = keyring.open(stored).ok_or(UserAuthError::StorageError)?;
+    let Some(step) = totp::verify(&secret, code, user_auth::unix_now(), user.totp_last_step) else {
+        return Ok(false);
+    };
+    // A secret stored before secrets were sealed is sealed now. Not while enrollment is pending:
+    // confirming matches on the stored secret.
+    let resealed = if is_enabled(user) && !totp::SecretKeyring::is_sealed(stored) {
+        keyring.seal(&secret)
+    } else {
+        None
+    };
+
+    let conn = app_state
+        .db
+        .get_conn()
+        .await
+        .change_error(UserAuthError::StorageError)?;
+    let updated = crate::generics::generic_update_if_present::<<User as HasTable>::Table, _, _>(
+        &conn,
+        dsl::user_id.eq(user.user_id.clone()).and(
+            dsl::totp_last_step
+                .is_null()
+                .or(dsl::totp_last_step.lt(step)),
+        ),
+        UserTotpStepUpdate {
+            totp_last_step: Some(step),
+            totp_secret: resealed,
+        },
+    )
+    .await
+    .change_context(UserAuthError::StorageError)?;
+    Ok(updated == 1)
+}
+
+async fn use_recovery_code(
+    app_state: &crate::app::TenantAppState,
+    user: &User,
+    recovery_code: &str,
+) -> Result<bool, ContainerError<UserAuthError>> {
+    let Some(stored) = user.totp_recovery_codes.clone() else {
+        return Ok(false);
+    };
+    let mut hashes: Vec<String> = serde_json::from_str(&stored).unwrap_or_default();
+    let typed = totp::normalize_recovery_code(recovery_code);
+    let Some(used) = hashes
+        .iter()
+        .position(|hash| auth::verify_password(&typed, hash).unwrap_or(false))
+    else {
+        return Ok(false);
+    };
+    hashes.remove(used);
+    let remaining = serde_json::to_string(&hashes).change_context(UserAuthError::StorageError)?;
+
+    let conn = app_state
+        .db
+        .get_conn()
+        .await
+        .change_error(UserAuthError::StorageError)?;
+    let updated = crate::generics::generic_update_if_present::<<User as HasTable>::Table, _, _>(
+        &conn,
+        dsl::user_id
+            .eq(user.user_id.clone())
+            .and(dsl::totp_recovery_codes.eq(stored)),
+        UserTotpRecoveryCodesUpdate {
+            totp_recovery_codes: Some(remaining),
+        },
+    )
+    .await
+    .change_context(UserAuthError::StorageError)?;
+    Ok(updated == 1)
+}
+
+/// The keyring TOTP secrets are sealed with at rest.
+fn secret_keyring() -> Result<totp::SecretKeyring, ContainerError<UserAuthError>> {
+    let global_config = APP_STATE
+        .get()
+        .map(|s| s.global_config.clone())
+        .ok_or(UserAuthError::StorageError)?;
+    let user_auth = &global_config.user_auth;
+    totp::SecretKeyring::from_keyring(
+        &user_auth.totp_encryption_current,
+        &user_auth.totp_encryption_keys,
+    )
+    .ok_or_else(|| ContainerError::from(UserAuthError::TwoFactorUnavailable))
+}
+
+/// A new set of recovery codes, and the JSON array of their hashes to store.
+fn new_recovery_codes() -> Result<(Vec<String>, String), ContainerError<UserAuthError>> {
+    let codes = totp::generate_recovery_codes();
+    let hashes = codes
+        .iter()
+        .map(|code| auth::hash_password(code))
+        .collect::<Result<Vec<_>, _>>()
+        .change_context(UserAuthError::PasswordHashingFailed)?;
+    let hashes = serde_json::to_string(&hashes).change_context(UserAuthError::StorageError)?;
+    Ok((codes, hashes))
+}
+
+/// Back to no two-factor at all: no secret, no recovery codes.
+async fn clear(
+    app_state: &crate::app::TenantAppState,
+    user_id: &str,
+) -> Result<(), ContainerError<UserAuthError>> {
+    let conn = app_state
+        .db
+        .get_conn()
+        .await
+        .change_error(UserAuthError::StorageError)?;
+    crate::generics::generic_update::<<User as HasTable>::Table, _, _>(
+        &conn,
+        dsl::user_id.eq(user_id.to_string()),
+        UserTotpUpdate {
+            totp_secret: None,
+            #[cfg(feature = "mysql")]
+            totp_enabled: 0,
+            #[cfg(feature = "postgres")]
+            totp_enabled: false,
+            totp_recovery_codes: None,
+            totp_last_step: None,
+        },
+    )
+    .await
+    .change_context(UserAuthError::StorageError)?;
+    Ok(())
+}
+
+/// The user a login challenge is for, without using the challenge up.
+async fn pending_login(
+    app_state: &crate::app::TenantAppState,
+    challenge: &str,
+) -> Result<User, ContainerError<UserAuthError>> {
+    let pending = app_state
+        .redis_conn
+        .get_key::<PendingLogin>(&format!("{CHALLENGE_PREFIX}{challenge}"), "PendingLogin")
+        .await
+        .change_context(UserAuthError::InvalidToken)?;
+    find_user(app_state, &pending.user_id).await
+}
+
+/// Uses the challenge up. Only the caller whose delete removed it gets the session, so a challenge
+/// answered twice at once yields one.
+async fn redeem_challenge(
+    app_state: &crate::app::TenantAppState,
+    challenge: &str,
+) -> Result<PendingLogin, ContainerError<UserAuthError>> {
+    let key = format!("{CHALLENGE_PREFIX}{challenge}");
+    let pending = app_state
+        .redis_conn
+        .get_key::<PendingLogin>(&key, "PendingLogin")
+        .await
+        .change_context(UserAuthError::InvalidToken)?;
+    let claimed = app_state
+        .redis_conn
+        .delete_key(&key)
+        .await
+        .change_context(UserAuthError::StorageError)?;
+    if !matches!(claimed, redis_interface::types::DelReply::KeyDeleted) {
+        return Err(ContainerError::from(UserAuthError::InvalidToken));
+    }
+    Ok(pending)
+}
+
+/// The account behind a full standard session. Two-factor belongs to the user's own account, so a
+/// handed-over or super-admin-view session cannot change it.
+async fn account_session(
+    app_state: &crate::app::TenantAppState,
+    headers: &HeaderMap,
+) -> Result<User, ContainerError<UserAuthError>> {
+    let token = user_auth::extract_bearer_token(headers)?;
+    let global_config = APP_STATE
+        .get()
+        .map(|s| s.global_config.clone())
+        .ok_or(UserAuthError::StorageError)?;
+    let claims =
+        user_auth::verify_jwt_not_revoked(token, global_config.user_auth.jwt_secret.peek()).await?;
+    if claims.token_type != TOKEN_TYPE_STANDARD {
+        return Err(ContainerError::from(UserAuthError::UnsupportedOperation));
+    }
+    find_user(app_state, &claims.user_id).await
+}
+
+async fn find_user(
+    app_state: &crate::app::TenantAppState,
+    user_id: &str,
+) -> Result<User, ContainerError<UserAuthError>> {
+    crate::generics::generic_find_all::<<User as HasTable>::Table, _, User>(
+        &app_state.db,
+        dsl::user_id.eq(user_id.to_string()),
+    )
+    .await
+    .change_error(UserAuthError::StorageError)?
+    .pop()
+    .ok_or_else(|| ContainerError::from(UserAuthError::UserNotFound))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sso_skips_the_merchant_requirement_but_not_an_enrolled_authenticator() {
+        assert!(!second_step_required(false, false, false));
+        assert!(second_step_required(false, true, false));
+        assert!(!second_step_required(false, true, true));
+        assert!(second_step_required(true, false, true));
+        assert!(second_step_required(true, true, true));
+    }
+}
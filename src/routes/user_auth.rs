@@ -1,14 +1,19 @@
 use crate::app::{get_tenant_app_state, APP_STATE};
-use crate::auth::{self, TOKEN_TYPE_HS_REDIRECT, TOKEN_TYPE_STANDARD, TOKEN_TYPE_SUPER_ADMIN_VIEW};
+use crate::auth::{
+    self, roles, Permission, TOKEN_TYPE_HS_REDIRECT, TOKEN_TYPE_STANDARD,
+    TOKEN_TYPE_SUPER_ADMIN_VIEW,
+};
 use crate::error::{self, ContainerError, ResultContainerExt, UserAuthError};
+use crate::routes::two_factor::{self, TwoFactorChallenge};
 use crate::storage::types::{
-    MerchantAccountNew, NewUser, NewUserMerchant, User, UserEmailVerifiedUpdate, UserMerchant,
-    UserMerchantIdUpdate,
+    MerchantAccountNew, MerchantRole, MerchantRoleNew, MerchantRoleUpdate, NewUser,
+    NewUserMerchant, User, UserEmailVerifiedUpdate, UserMerchant, UserMerchantIdUpdate,
+    UserMerchantRoleUpdate,
 };
 use crate::types::merchant::hierarchy::{self, GrantLevel, ScopeGrant};
 use crate::types::merchant::merchant_account::load_merchant_by_merchant_id;
 use crate::utils::date_time;
-use axum::extract::Query;
+use axum::extract::{Path, Query};
 use axum::http::HeaderMap;
 use axum::Json;
 use diesel::associations::HasTable;
@@ -87,6 +92,8 @@ pub struct MeResponse {
     pub is_super_admin: bool,
     /// The current session is a super-admin viewing another merchant (drives the banner + Exit).
     pub is_super_admin_view: bool,
+    /// The user has an authenticator app set up for sign-in.
+    pub two_factor_enabled: bool,
     /// What this session may do. Drives which controls the dashboard offers — the middleware is
     /// what actually refuses a request, so this is presentation, never the guard.
     pub permissions: Vec<auth::Permission>,
@@ -97,6 +104,14 @@ pub struct MeResponse {
     pub hierarchy: Option<crate::types::merchant::hierarchy::Hierarchy>,
 }
 
+/// Login response — a session, or a challenge to answer with a second factor first
+#[derive(Debug, Serialize)]
+#[serde(untagged)]
+pub enum LoginResponse {
+    Authenticated(AuthResponse),
+    TwoFactorRequired(TwoFactorChallenge),
+}
+
 #[derive(Debug, Serialize)]
 pub struct CreateMerchantResponse {
     pub token: String,
@@ -336,7 +351,8 @@ pub async fn signup(
         &user_id,
         &payload.email,
         requested_merchant_id.as_deref().unwrap_or(""),
-        "admin",
+        roles::ADMIN,
+        auth::KNOWN_PERMISSIONS,
         TOKEN_TYPE_STANDARD,
         global_config.user_auth.jwt_secret.peek(),
         global_config.user_auth.jwt_expiry_seconds,
@@ -358,7 +374,7 @@ pub async fn signup(
 #[axum::debug_handler]
 pub async fn login(
     Json(payload): Json<LoginRequest>,
-) -> Result<Json<AuthResponse>, error::ContainerError<UserAuthError>> {
+) -> Result<Json<LoginResponse>, error::ContainerError<UserAuthError>> {
     let app_state = get_tenant_app_state().await;
     let global_config = APP_STATE
         .get()
@@ -409,32 +425,84 @@ pub async fn login(
     }
 
     let merchants = fetch_user_merchants(&app_state, &user.user_id).await?;
-    let active_merchant_id = user.merchant_id.clone().unwrap_or_else(|| {
+    let mut active_merchant_id = user.merchant_id.clone().unwrap_or_else(|| {
         merchants
             .first()
             .map(|m| m.merchant_id.clone())
             .unwrap_or_default()
     });
+    // A password session cannot land on a merchant that requires SSO. It lands on another of the
+    // user's merchants instead, and is refused only when every one of them requires it.
+    if crate::routes::sso::sso_required(&active_merchant_id).await? {
+        let mut open = None;
+        for merchant in &merchants {
+            if !crate::routes::sso::sso_required(&merchant.merchant_id).await? {
+                open = Some(merchant.merchant_id.clone());
+                break;
+            }
+        }
+        active_merchant_id = open.ok_or(UserAuthError::SsoRequired)?;
+    }
+    // The password alone is not enough for a user with an authenticator, nor for one landing on a
+    // merchant that requires one: the session waits for the second step.
+    if two_factor::second_step_required(
+        two_factor::is_enabled(&user),
+        two_factor::two_factor_required(&active_merchant_id).await?,
+        false,
+    ) {
+        return Ok(Json(LoginResponse::TwoFactorRequired(
+            two_factor::challenge(&app_state, &user, active_merchant_id, false).await?,
+        )));
+    }
 
-    let token = auth::generate_jwt(
+    Ok(Json(LoginResponse::Authenticated(
+        issue_session(
+            &app_state,
+            &global_config,
+            user,
+            active_merchant_id,
+            merchants,
+            false,
+            false,
+        )
+        .await?,
+    )))
+}
+
+/// The standard session a sign-in ends in, on `merchant_id`: with a password, or through an
+/// identity provider when `sso`. `two_factor` marks it as having passed the second step too.
+pub(crate) async fn issue_session(
+    app_state: &crate::app::TenantAppState,
+    global_config: &crate::config::GlobalConfig,
+    user: User,
+    merchant_id: String,
+    merchants: Vec<MerchantInfo>,
+    sso: bool,
+    two_factor: bool,
+) -> Result<AuthResponse, error::ContainerError<UserAuthError>> {
+    let role = role_on(&merchants, &merchant_id, &user.role);
+    let perms = role_permissions(app_state, &merchant_id, &role).await?;
+
+    let token = auth::session_minter(sso, two_factor)(
         &user.user_id,
         &user.email,
-        &active_merchant_id,
-        &user.role,
+        &merchant_id,
+        &role,
+        &perms,
         TOKEN_TYPE_STANDARD,
         global_config.user_auth.jwt_secret.peek(),
         global_config.user_auth.jwt_expiry_seconds,
     )
     .change_context(UserAuthError::TokenGenerationFailed)?;
 
-    Ok(Json(AuthResponse {
+    Ok(AuthResponse {
         token,
         user_id: user.user_id,
         email: user.email,
-        merchant_id: active_merchant_id,
-        role: user.role,
+        merchant_id,
+        role,
         merchants,
-    }))
+    })
 }
 
 #[axum::debug_handler]
@@ -521,11 +589,13 @@ pub async fn create_merchant(
 
     let merchants = fetch_user_merchants(&app_state, &claims.user_id).await?;
 
-    let new_token = auth::generate_jwt(
+    // The creator was made its admin above, whatever they were on the merchant they came from.
+    let new_token = (claims.minter())(
         &claims.user_id,
         &claims.email,
         &merchant_id,
-        &claims.role,
+        roles::ADMIN,
+        auth::KNOWN_PERMISSIONS,
         TOKEN_TYPE_STANDARD,
         global_config.user_auth.jwt_secret.peek(),
         global_config.user_auth.jwt_expiry_seconds,
@@ -596,12 +666,28 @@ async fn switch_within_memberships(
         .iter()
         .find(|m| m.merchant_id == payload.merchant_id)
         .ok_or_else(|| error::ContainerError::from(UserAuthError::MerchantNotFound))?;
+    if !claims.sso && crate::routes::sso::sso_required(&target.merchant_id).await? {
+        return Err(error::ContainerError::from(UserAuthError::SsoRequired));
+    }
+    // What counts is that this session passed the second step, not that the user has an
+    // authenticator: a password-only session of an enrolled user is still password-only.
+    if !two_factor::session_admitted(
+        &claims,
+        two_factor::two_factor_required(&target.merchant_id).await?,
+    ) {
+        return Err(error::ContainerError::from(
+            UserAuthError::TwoFactorRequired,
+        ));
+    }
+    let perms = role_permissions(&app_state, &target.merchant_id, &target.role).await?;
 
-    let new_token = auth::generate_jwt(
+    // A session signed in through SSO, or through two-factor, stays one as it moves.
+    let new_token = (claims.minter())(
         &claims.user_id,
         &claims.email,
         &target.merchant_id,
         &target.role,
+        &perms,
         TOKEN_TYPE_STANDARD,
         global_config.user_auth.jwt_secret.peek(),
         global_config.user_auth.jwt_expiry_seconds,
@@ -667,7 +753,7 @@ async fn switch_within_grant(
 
 /// Seconds since the Unix epoch. A clock before the epoch reads as 0, which only ever shortens a
 /// derived expiry.
-fn unix_now() -> u64 {
+pub(crate) fn unix_now() -> u64 {
     std::time::SystemTime::now()
         .duration_since(std::time::UNIX_EPOCH)
         .map(|elapsed| elapsed.as_secs())
@@ -769,7 +855,7 @@ pub struct InviteMemberResponse {
     pub role: String,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Clone, Serialize)]
 pub struct MemberInfo {
     pub user_id: String,
     pub email: String,
@@ -817,17 +903,20 @@ pub async fn invite_member(
         ));
     }
 
-    if claims.role != "admin" {
+    let held = claims.permissions(global_config.user_auth.require_explicit_permissions);
+    if !held.iter().any(|p| p.satisfies(&Permission::MembersManage)) {
         return Err(error::ContainerError::from(UserAuthError::Forbidden));
     }
 
-    let role = match payload.role.as_deref().unwrap_or("member") {
-        "admin" => "admin".to_string(),
-        _ => "member".to_string(),
-    };
-
     let app_state = get_tenant_app_state().await;
 
+    let role = payload
+        .role
+        .clone()
+        .unwrap_or_else(|| roles::MEMBER.to_string());
+    let granted = existing_role_permissions(&app_state, &claims.merchant_id, &role).await?;
+    check_grantable(&held, &granted)?;
+
     #[cfg(feature = "mysql")]
     use crate::storage::schema::merchant_account::dsl as ma_dsl;
     #[cfg(feature = "postgres")]
@@ -886,6 +975,14 @@ pub async fn invite_member(
         crate::generics::generic_insert(&app_state.db, new_user_merchant)
             .await
             .change_context(UserAuthError::StorageError)?;
+        crate::audit::record_change(
+            &(),
+            &MemberInfo {
+                user_id: existing_user.user_id.clone(),
+                email: existing_user.email.clone(),
+                role: role.clone(),
+            },
+        );
 
         let email_config = &global_config.email;
         if email_config.is_active() {
@@ -968,6 +1065,14 @@ pub async fn invite_member(
             }
             return Err(error::ContainerError::from(UserAuthError::StorageError));
         }
+        crate::audit::record_change(
+            &(),
+            &MemberInfo {
+                user_id: user_id.clone(),
+                email: payload.email.clone(),
+                role: role.clone(),
+            },
+        );
 
         let email_config = &global_config.email;
         if email_config.is_active() {
@@ -1053,7 +1158,412 @@ pub async fn list_members(
     Ok(Json(members))
 }
 
-fn generate_random_password() -> String {
+#[derive(Debug, Serialize)]
+pub struct RoleInfo {
+    pub name: String,
+    pub permissions: Vec<Permission>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// One of `admin`, `member` and `viewer`, which every merchant has and none can change.
+    pub built_in: bool,
+}
+
+impl From<MerchantRole> for RoleInfo {
+    fn from(role: MerchantRole) -> Self {
+        Self {
+            permissions: stored_role_permissions(&role.permissions),
+            name: role.role_name,
+            description: role.description,
+            built_in: false,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UpsertRoleRequest {
+    pub permissions: Vec<Permission>,
+    pub description: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UpdateMemberRoleRequest {
+    pub role: String,
+}
+
+/// The roles this merchant's members can be given: the built-in ones, then its own.
+#[axum::debug_handler]
+pub async fn list_roles(
+    headers: HeaderMap,
+) -> Result<Json<Vec<RoleInfo>>, error::ContainerError<UserAuthError>> {
+    let token = extract_bearer_token(&headers)?;
+    let global_config = APP_STATE
+        .get()
+        .map(|s| s.global_config.clone())
+        .ok_or(UserAuthError::StorageError)?;
+
+    let claims = verify_jwt_not_revoked(token, global_config.user_auth.jwt_secret.peek()).await?;
+    let app_state = get_tenant_app_state().await;
+
+    let mut result: Vec<RoleInfo> = roles::builtin_names()
+        .into_iter()
+        .filter_map(|name| {
+            roles::builtin(name).map(|permissions| RoleInfo {
+                name: name.to_string(),
+                permissions: permissions.to_vec(),
+                description: None,
+                built_in: true,
+            })
+        })
+        .collect();
+    result.extend(
+        custom_roles(&app_state, &claims.merchant_id)
+            .await?
+            .into_iter()
+            .map(RoleInfo::from),
+    );
+
+    Ok(Json(result))
+}
+
+/// Creates the named custom role, or replaces what it holds. Members already holding it see the
+/// change at their next sign-in or merchant switch.
+#[axum::debug_handler]
+pub async fn upsert_role(
+    headers: HeaderMap,
+    Path(role_name): Path<String>,
+    Json(payload): Json<UpsertRoleRequest>,
+) -> Result<Json<RoleInfo>, error::ContainerError<UserAuthError>> {
+    let (claims, held) = member_admin_session(&headers).await?;
+
+    if let Some(reason) = roles::validate_custom(&role_name, &payload.permissions) {
+        return Err(error::ContainerError::from(UserAuthError::InvalidRole(
+            reason,
+        )));
+    }
+    check_grantable(&held, &payload.permissions)?;
+
+    let app_state = get_tenant_app_state().await;
+    let permissions =
+        serde_json::to_string(&payload.permissions).change_context(UserAuthError::StorageError)?;
+    let now = date_time::now();
+
+    let before = find_custom_role(&app_state, &claims.merchant_id, &role_name)
+        .await?
+        .map(RoleInfo::from);
+    if before.is_some() {
+        #[cfg(feature = "mysql")]
+        use crate::storage::schema::merchant_roles::dsl as mr_dsl;
+        #[cfg(feature = "postgres")]
+        use crate::storage::schema_pg::merchant_roles::dsl as mr_dsl;
+
+        let conn = &app_state
+            .db
+            .get_conn()
+            .await
+            .change_error(UserAuthError::StorageError)?;
+        crate::generics::generic_update::<<MerchantRole as HasTable>::Table, _, _>(
+            conn,
+            mr_dsl::merchant_id
+                .eq(claims.merchant_id.clone())
+                .and(mr_dsl::role_name.eq(role_name.clone())),
+            MerchantRoleUpdate {
+                permissions,
+                description: payload.description.clone(),
+                updated_at: now,
+            },
+        )
+        .await
+        .change_context(UserAuthError::StorageError)?;
+    } else {
+        crate::generics::generic_insert(
+            &app_state.db,
+            MerchantRoleNew {
+                merchant_id: claims.merchant_id.clone(),
+                role_name: role_name.clone(),
+                permissions,
+                description: payload.description.clone(),
+                created_at: now,
+                updated_at: now,
+            },
+        )
+        .await
+        .change_context(UserAuthError::StorageError)?;
+    }
+
+    let role = RoleInfo {
+        name: role_name,
+        permissions: payload.permissions,
+        description: payload.description,
+        built_in: false,
+    };
+    crate::audit::record_change(&before, &role);
+    Ok(Json(role))
+}
+
+/// Deletes a custom role nobody holds. One still held is refused rather than left dangling, since
+/// its members would sign in next time with nothing.
+#[axum::debug_handler]
+pub async fn delete_role(
+    headers: HeaderMap,
+    Path(role_name): Path<String>,
+) -> Result<Json<MessageResponse>, error::ContainerError<UserAuthError>> {
+    let (claims, _) = member_admin_session(&headers).await?;
+
+    if roles::builtin(&role_name).is_some() {
+        return Err(error::ContainerError::from(UserAuthError::InvalidRole(
+            format!("{role_name} is a built-in role and cannot be deleted"),
+        )));
+    }
+
+    let app_state = get_tenant_app_state().await;
+    let before = find_custom_role(&app_state, &claims.merchant_id, &role_name)
+        .await?
+        .map(RoleInfo::from)
+        .ok_or(UserAuthError::RoleNotFound)?;
+
+    let holders =
+        crate::generics::generic_find_all::<<UserMerchant as HasTable>::Table, _, UserMerchant>(
+            &app_state.db,
+            um_dsl::merchant_id
+                .eq(claims.merchant_id.clone())
+                .and(um_dsl::role.eq(role_name.clone())),
+        )
+        .await
+        .change_error(UserAuthError::StorageError)?;
+    if !holders.is_empty() {
+        return Err(error::ContainerError::from(UserAuthError::RoleInUse));
+    }
+
+    #[cfg(feature = "mysql")]
+    use crate::storage::schema::merchant_roles::dsl as mr_dsl;
+    #[cfg(feature = "postgres")]
+    use crate::storage::schema_pg::merchant_roles::dsl as mr_dsl;
+
+    let conn = &app_state
+        .db
+        .get_conn()
+        .await
+        .change_error(UserAuthError::StorageError)?;
+    crate::generics::generic_delete::<<MerchantRole as HasTable>::Table, _>(
+        conn,
+        mr_dsl::merchant_id
+            .eq(claims.merchant_id.clone())
+            .and(mr_dsl::role_name.eq(role_name.clone())),
+    )
+    .await
+    .change_context(UserAuthError::StorageError)?;
+    crate::audit::record_change(&before, &());
+
+    Ok(Json(MessageResponse {
+        message: format!("Role {role_name} deleted"),
+    }))
+}
+
+/// Gives a member of this merchant a different role. The caller must hold everything both the old
+/// and the new role grant, so nobody can demote a member who outranks them, nor promote one past
+/// themselves.
+#[axum::debug_handler]
+pub async fn update_member_role(
+    headers: HeaderMap,
+    Path(user_id): Path<String>,
+    Json(payload): Json<UpdateMemberRoleRequest>,
+) -> Result<Json<MemberInfo>, error::ContainerError<UserAuthError>> {
+    let (claims, held) = member_admin_session(&headers).await?;
+    let app_state = get_tenant_app_state().await;
+
+    let memberships =
+        crate::generics::generic_find_all::<<UserMerchant as HasTable>::Table, _, UserMerchant>(
+            &app_state.db,
+            um_dsl::merchant_id.eq(claims.merchant_id.clone()),
+        )
+        .await
+        .change_error(UserAuthError::StorageError)?;
+    let membership = memberships
+        .iter()
+        .find(|m| m.user_id == user_id)
+        .ok_or(UserAuthError::MemberNotFound)?;
+
+    let granted = existing_role_permissions(&app_state, &claims.merchant_id, &payload.role).await?;
+    check_grantable(&held, &granted)?;
+    check_grantable(
+        &held,
+        &role_permissions(&app_state, &claims.merchant_id, &membership.role).await?,
+    )?;
+
+    let admins = memberships
+        .iter()
+        .filter(|m| m.role == roles::ADMIN)
+        .count();
+    if membership.role == roles::ADMIN && payload.role != roles::ADMIN && admins == 1 {
+        return Err(error::ContainerError::from(UserAuthError::InvalidRole(
+            "a merchant must keep at least one admin".to_string(),
+        )));
+    }
+    let previous_role = membership.role.clone();
+
+    let conn = &app_state
+        .db
+        .get_conn()
+        .await
+        .change_error(UserAuthError::StorageError)?;
+    crate::generics::generic_update::<<UserMerchant as HasTable>::Table, _, _>(
+        conn,
+        um_dsl::merchant_id
+            .eq(claims.merchant_id.clone())
+            .and(um_dsl::user_id.eq(user_id.clone())),
+        UserMerchantRoleUpdate {
+            role: payload.role.clone(),
+        },
+    )
+    .await
+    .change_context(UserAuthError::StorageError)?;
+
+    let email = crate::generics::generic_find_all::<<User as HasTable>::Table, _, User>(
+        &app_state.db,
+        dsl::user_id.eq(user_id.clone()),
+    )
+    .await
+    .change_error(UserAuthError::StorageError)?
+    .into_iter()
+    .next()
+    .map(|u| u.email)
+    .unwrap_or_default();
+
+    let member = MemberInfo {
+        user_id,
+        email,
+        role: payload.role,
+    };
+    crate::audit::record_change(
+        &MemberInfo {
+            role: previous_role,
+            ..member.clone()
+        },
+        &member,
+    );
+    Ok(Json(member))
+}
+
+/// The session behind a member-administration request, with what it may do. Only a full standard
+/// session holding `members:manage` qualifies: a handed-over or super-admin-view session has no
+/// business changing who belongs to a merchant.
+pub(crate) async fn member_admin_session(
+    headers: &HeaderMap,
+) -> Result<(auth::JwtClaims, Vec<Permission>), error::ContainerError<UserAuthError>> {
+    let token = extract_bearer_token(headers)?;
+    let global_config = APP_STATE
+        .get()
+        .map(|s| s.global_config.clone())
+        .ok_or(UserAuthError::StorageError)?;
+
+    let claims = verify_jwt_not_revoked(token, global_config.user_auth.jwt_secret.peek()).await?;
+    if claims.token_type != TOKEN_TYPE_STANDARD {
+        return Err(error::ContainerError::from(
+            UserAuthError::UnsupportedOperation,
+        ));
+    }
+
+    let held = claims.permissions(global_config.user_auth.require_explicit_permissions);
+    if !held.iter().any(|p| p.satisfies(&Permission::MembersManage)) {
+        return Err(error::ContainerError::from(UserAuthError::Forbidden));
+    }
+    Ok((claims, held))
+}
+
+/// Refuses to hand out anything the caller does not hold themselves.
+fn check_grantable(
+    held: &[Permission],
+    granted: &[Permission],
+) -> Result<(), error::ContainerError<UserAuthError>> {
+    if granted
+        .iter()
+        .all(|wanted| held.iter().any(|p| p.satisfies(wanted)))
+    {
+        Ok(())
+    } else {
+        Err(error::ContainerError::from(UserAuthError::Forbidden))
+    }
+}
+
+/// The role `merchants` records for the user on `merchant_id`, or `fallback` when they record
+/// none — a user with no memberships yet.
+pub(crate) fn role_on(merchants: &[MerchantInfo], merchant_id: &str, fallback: &str) -> String {
+    merchants
+        .iter()
+        .find(|m| m.merchant_id == merchant_id)
+        .map_or_else(|| fallback.to_string(), |m| m.role.clone())
+}
+
+/// What a member holding `role` on `merchant_id` may do: the built-in role's permissions, or those
+/// of the merchant's custom role by that name. A role that has since been deleted grants nothing.
+pub(crate) async fn role_permissions(
+    app_state: &crate::app::TenantAppState,
+    merchant_id: &str,
+    role: &str,
+) -> Result<Vec<Permission>, ContainerError<UserAuthError>> {
+    if let Some(permissions) = roles::builtin(role) {
+        return Ok(permissions.to_vec());
+    }
+    Ok(find_custom_role(app_state, merchant_id, role)
+        .await?
+        .map(|r| stored_role_permissions(&r.permissions))
+        .unwrap_or_default())
+}
+
+/// As [`role_permissions`], but for a role about to be given out, which must exist.
+pub(crate) async fn existing_role_permissions(
+    app_state: &crate::app::TenantAppState,
+    merchant_id: &str,
+    role: &str,
+) -> Result<Vec<Permission>, ContainerError<UserAuthError>> {
+    if let Some(permissions) = roles::builtin(role) {
+        return Ok(permissions.to_vec());
+    }
+    find_custom_role(app_state, merchant_id, role)
+        .await?
+        .map(|r| stored_role_permissions(&r.permissions))
+        .ok_or_else(|| {
+            error::ContainerError::from(UserAuthError::InvalidRole(format!(
+                "{role} is neither a built-in role nor one defined for this merchant"
+            )))
+        })
+}
+
+async fn custom_roles(
+    app_state: &crate::app::TenantAppState,
+    merchant_id: &str,
+) -> Result<Vec<MerchantRole>, ContainerError<UserAuthError>> {
+    #[cfg(feature = "mysql")]
+    use crate::storage::schema::merchant_roles::dsl as mr_dsl;
+    #[cfg(feature = "postgres")]
+    use crate::storage::schema_pg::merchant_roles::dsl as mr_dsl;
+
+    crate::generics::generic_find_all::<<MerchantRole as HasTable>::Table, _, MerchantRole>(
+        &app_state.db,
+        mr_dsl::merchant_id.eq(merchant_id.to_string()),
+    )
+    .await
+    .change_error(UserAuthError::StorageError)
+}
+
+async fn find_custom_role(
+    app_state: &crate::app::TenantAppState,
+    merchant_id: &str,
+    role_name: &str,
+) -> Result<Option<MerchantRole>, ContainerError<UserAuthError>> {
+    Ok(custom_roles(app_state, merchant_id)
+        .await?
+        .into_iter()
+        .find(|r| r.role_name == role_name))
+}
+
+/// A stored role's permissions. Damaged JSON reads as holding nothing, the direction that cannot
+/// widen anyone's access.
+fn stored_role_permissions(raw: &str) -> Vec<Permission> {
+    serde_json::from_str(raw).unwrap_or_default()
+}
+
+pub(crate) fn generate_random_password() -> String {
     use rand::Rng;
     let mut rng = rand::thread_rng();
     let uppercase = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ";
@@ -1148,6 +1658,7 @@ pub async fn me(
             merchants,
             is_super_admin: false,
             is_super_admin_view: false,
+            two_factor_enabled: false,
             permissions,
             hierarchy,
         }));
@@ -1178,6 +1689,7 @@ pub async fn me(
         // identity the rest of this session authorizes against.
         is_super_admin: is_super_admin(&global_config, &claims),
         is_super_admin_view: claims.token_type == TOKEN_TYPE_SUPER_ADMIN_VIEW,
+        two_factor_enabled: two_factor::is_enabled(&user),
         permissions,
         email: user.email,
         merchant_id: claims.merchant_id,
@@ -1240,7 +1752,10 @@ fn view_grant(
 /// roster: platform super admins tend to hold Hyperswitch logins too. Such a session must never be
 /// able to enter another merchant, and the only thing standing between it and the roster is this
 /// check — so it belongs at the one point every caller goes through, not repeated at each of them.
-fn is_super_admin(global_config: &crate::config::GlobalConfig, claims: &auth::JwtClaims) -> bool {
+pub(crate) fn is_super_admin(
+    global_config: &crate::config::GlobalConfig,
+    claims: &auth::JwtClaims,
+) -> bool {
     roster_admits(&global_config.user_auth.super_admin_emails, claims)
 }
 
@@ -1257,7 +1772,7 @@ fn roster_admits(roster: &[String], claims: &auth::JwtClaims) -> bool {
             .any(|allowed| allowed.eq_ignore_ascii_case(&claims.email))
 }
 
-async fn fetch_user_merchants(
+pub(crate) async fn fetch_user_merchants(
     app_state: &crate::app::TenantAppState,
     user_id: &String,
 ) -> Result<Vec<MerchantInfo>, ContainerError<UserAuthError>> {
@@ -1532,7 +2047,9 @@ pub async fn verify_email(
     }))
 }
 
-fn extract_bearer_token(headers: &HeaderMap) -> Result<&str, error::ContainerError<UserAuthError>> {
+pub(crate) fn extract_bearer_token(
+    headers: &HeaderMap,
+) -> Result<&str, error::ContainerError<UserAuthError>> {
     headers
         .get(axum::http::header::AUTHORIZATION)
         .and_then(|v| v.to_str().ok())
@@ -1856,16 +2373,10 @@ pub async fn enter_merchant(
         hierarchy_of_scope(&payload.merchant_id).await.as_ref(),
     );
 
-    let new_token = auth::generate_scoped_jwt(
-        &claims.user_id,
-        &claims.email,
+    let new_token = auth::generate_super_admin_view_jwt(
+        &claims,
         &payload.merchant_id,
-        "admin",
-        TOKEN_TYPE_SUPER_ADMIN_VIEW,
-        Some(&grant),
-        // Named rather than left absent, exactly as `generate_jwt` does, so the reading never
-        // depends on the explicit-permissions flag for a session Decision Engine issued.
-        Some(auth::KNOWN_PERMISSIONS),
+        &grant,
         global_config.user_auth.jwt_secret.peek(),
         global_config.user_auth.jwt_expiry_seconds,
     )
@@ -1923,12 +2434,28 @@ pub async fn exit_merchant(
             .map(|m| m.merchant_id.clone())
             .unwrap_or_default()
     });
+    // The home merchant admits the returning session on the same terms as a switch onto it.
+    if !claims.sso && crate::routes::sso::sso_required(&home_merchant_id).await? {
+        return Err(error::ContainerError::from(UserAuthError::SsoRequired));
+    }
+    if !two_factor::session_admitted(
+        &claims,
+        two_factor::two_factor_required(&home_merchant_id).await?,
+    ) {
+        return Err(error::ContainerError::from(
+            UserAuthError::TwoFactorRequired,
+        ));
+    }
+    let role = role_on(&merchants, &home_merchant_id, &user.role);
+    let perms = role_permissions(&app_state, &home_merchant_id, &role).await?;
 
-    let new_token = auth::generate_jwt(
+    // The view session kept how the admin signed in; the returning session keeps it too.
+    let new_token = (claims.minter())(
         &user.user_id,
         &user.email,
         &home_merchant_id,
-        &user.role,
+        &role,
+        &perms,
         TOKEN_TYPE_STANDARD,
         global_config.user_auth.jwt_secret.peek(),
         global_config.user_auth.jwt_expiry_seconds,
@@ -1940,7 +2467,7 @@ pub async fn exit_merchant(
         user_id: user.user_id,
         email: user.email,
         merchant_id: home_merchant_id,
-        role: user.role,
+        role,
         merchants,
     }))
 }
@@ -2216,6 +2743,9 @@ mod tests {
             iat: 0,
             grant: None,
             perms: None,
+            perms_split: false,
+            sso: false,
+            mfa: false,
         }
     }
 
@@ -2358,4 +2888,42 @@ mod tests {
             "default"
         );
     }
+
+    #[test]
+    fn nobody_hands_out_more_than_they_hold() {
+        let member = roles::builtin(roles::MEMBER).expect("built in");
+        let viewer = roles::builtin(roles::VIEWER).expect("built in");
+        assert!(check_grantable(member, viewer).is_ok());
+        assert!(check_grantable(viewer, member).is_err());
+        assert!(check_grantable(member, &[Permission::MembersManage]).is_err());
+        // Write covers decide, so a role holding only decide is within a writer's reach.
+        assert!(check_grantable(&[Permission::RoutingWrite], &[Permission::RoutingDecide]).is_ok());
+    }
+
+    #[test]
+    fn a_session_takes_the_role_held_on_its_merchant() {
+        let merchants = vec![
+            MerchantInfo {
+                merchant_id: "m1".to_string(),
+                merchant_name: "One".to_string(),
+                role: roles::ADMIN.to_string(),
+            },
+            MerchantInfo {
+                merchant_id: "m2".to_string(),
+                merchant_name: "Two".to_string(),
+                role: "finance".to_string(),
+            },
+        ];
+        assert_eq!(role_on(&merchants, "m2", roles::ADMIN), "finance");
+        assert_eq!(role_on(&[], "m1", roles::MEMBER), roles::MEMBER);
+    }
+
+    #[test]
+    fn a_damaged_role_grants_nothing() {
+        assert!(stored_role_permissions("not json").is_empty());
+        assert_eq!(
+            stored_role_permissions(r#"["cost:read"]"#),
+            vec![Permission::CostRead]
+        );
+    }
 }
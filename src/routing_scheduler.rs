@@ -0,0 +1,247 @@
+//! Background job that applies scheduled routing activations.
+//!
+//! `/routing/activate` may attach a schedule (see [`crate::euclid::schedule`]) to a merchant's
+//! mapping. On every tick this job evaluates each scheduled mapping and, when its window has
+//! opened or closed, swaps the active algorithm, drops the merchant's cached algorithm and emits a
+//! `routing_schedule_transition` analytics event. Once a schedule has ended its fallback stays
+//! active and the schedule is cleared; so is a schedule that can no longer be read, leaving the
+//! active algorithm as it is. Each change is appended to the configuration audit log with
+//! `scheduler` as its actor. Runs only where `routing_scheduler.enabled` is set.
+
+use std::time::Duration;
+
+use diesel::{associations::HasTable, BoolExpressionMethods, ExpressionMethods};
+use futures::FutureExt;
+
+use crate::analytics::flow::{AnalyticsFlowContext, AnalyticsRoute, ApiFlow, FlowType};
+use crate::analytics::DomainAnalyticsEvent;
+use crate::app::{get_tenant_app_state, TenantAppState};
+use crate::config::RoutingSchedulerConfig;
+use crate::euclid::handlers::routing_rules::{
+    activation_state, invalidate_routing_algorithm_cache,
+};
+use crate::euclid::schedule::{ScheduledActivation, WindowState};
+use crate::euclid::types::{RoutingAlgorithmMapper, RoutingAlgorithmMapperUpdate};
+use crate::logger;
+#[cfg(feature = "mysql")]
+use crate::storage::schema::routing_algorithm_mapper::dsl as mapper_dsl;
+#[cfg(feature = "postgres")]
+use crate::storage::schema_pg::routing_algorithm_mapper::dsl as mapper_dsl;
+
+/// Default check cadence. A window opens or closes at most this late.
+const DEFAULT_INTERVAL_SECS: u64 = 30;
+
+/// Who the audit log names for the changes this job makes.
+const AUDIT_ACTOR: &str = "scheduler";
+
+#[derive(Debug, serde::Serialize)]
+struct ScheduleTransitionDetails<'a> {
+    algorithm_for: &'a str,
+    from_routing_algorithm_id: &'a str,
+    to_routing_algorithm_id: &'a str,
+    window: WindowState,
+    schedule: &'a ScheduledActivation,
+}
+
+/// Spawn the recurring scheduler loop. Call once at startup, after `APP_STATE` is set. A no-op
+/// unless `enabled` is true.
+pub fn spawn(config: RoutingSchedulerConfig) {
+    if !config.enabled {
+        logger::info!(tag = "routing_scheduler", "routing scheduler disabled");
+        return;
+    }
+    let interval_secs = config
+        .interval_secs
+        .filter(|&v| v > 0)
+        .unwrap_or(DEFAULT_INTERVAL_SECS);
+
+    tokio::spawn(async move {
+        logger::info!(
+            tag = "routing_scheduler",
+            action = "start",
+            "routing scheduler started; interval {}s",
+            interval_secs
+        );
+        let mut ticker = tokio::time::interval(Duration::from_secs(interval_secs));
+        loop {
+            ticker.tick().await;
+            // Isolate each cycle so a panic doesn't kill the loop.
+            if std::panic::AssertUnwindSafe(run_once())
+                .catch_unwind()
+                .await
+                .is_err()
+            {
+                logger::error!(
+                    tag = "routing_scheduler",
+                    action = "panic",
+                    "scheduler cycle panicked; continuing next cycle"
+                );
+            }
+        }
+    });
+}
+
+async fn run_once() {
+    let state = get_tenant_app_state().await;
+    let mappings = match crate::generics::generic_find_all::<
+        <RoutingAlgorithmMapper as HasTable>::Table,
+        _,
+        RoutingAlgorithmMapper,
+    >(&state.db, mapper_dsl::schedule.is_not_null())
+    .await
+    {
+        Ok(mappings) => mappings,
+        Err(err) => {
+            logger::warn!(
+                tag = "routing_scheduler",
+                action = "load_error",
+                "failed to load scheduled routing mappings: {:?}",
+                err
+            );
+            return;
+        }
+    };
+
+    let now = chrono::Utc::now();
+    for mapping in mappings {
+        if let Err(reason) = apply_schedule(&state, &mapping, now).await {
+            logger::warn!(
+                tag = "routing_scheduler",
+                action = "skip",
+                "schedule for {} ({}) skipped: {}",
+                mapping.created_by,
+                mapping.algorithm_for,
+                reason
+            );
+        }
+    }
+}
+
+async fn apply_schedule(
+    state: &TenantAppState,
+    mapping: &RoutingAlgorithmMapper,
+    now: chrono::DateTime<chrono::Utc>,
+) -> Result<(), String> {
+    let Some(raw) = mapping.schedule.as_deref() else {
+        return Ok(());
+    };
+    let parsed = serde_json::from_str::<ScheduledActivation>(raw)
+        .map_err(|e| format!("unreadable schedule: {e}"))
+        .and_then(|schedule| Ok((schedule.window.parse()?, schedule)));
+    let (window, schedule) = match parsed {
+        Ok(parsed) => parsed,
+        Err(reason) => {
+            // It won't read any better next tick: drop it, so it's reported once, and leave the
+            // active algorithm as it is.
+            if store(state, mapping, raw, &mapping.routing_algorithm_id, None).await? {
+                logger::warn!(
+                    tag = "routing_scheduler",
+                    action = "clear_invalid",
+                    "schedule for {} ({}) cleared: {}",
+                    mapping.created_by,
+                    mapping.algorithm_for,
+                    reason
+                );
+            }
+            return Ok(());
+        }
+    };
+    let window = window.state_at(now);
+    let target = schedule.algorithm_id(window);
+    let ended = window == WindowState::Ended;
+    if target == mapping.routing_algorithm_id && !ended {
+        return Ok(());
+    }
+
+    let stored_schedule = (!ended).then(|| raw.to_string());
+    if !store(state, mapping, raw, target, stored_schedule).await? {
+        return Ok(());
+    }
+    if target == mapping.routing_algorithm_id {
+        return Ok(());
+    }
+
+    invalidate_routing_algorithm_cache(state, &mapping.created_by).await;
+    logger::info!(
+        tag = "routing_scheduler",
+        action = "transition",
+        "schedule for {} ({}) is {}: {} -> {}",
+        mapping.created_by,
+        mapping.algorithm_for,
+        window,
+        mapping.routing_algorithm_id,
+        target
+    );
+    DomainAnalyticsEvent::record_operation(
+        AnalyticsFlowContext::new(
+            ApiFlow::RuleBasedRouting,
+            FlowType::RoutingScheduleTransition,
+        ),
+        AnalyticsRoute::RoutingActivate,
+        Some(mapping.created_by.clone()),
+        None,
+        None,
+        None,
+        None,
+        Some("success".to_string()),
+        crate::analytics::serialize_details(&ScheduleTransitionDetails {
+            algorithm_for: &mapping.algorithm_for,
+            from_routing_algorithm_id: &mapping.routing_algorithm_id,
+            to_routing_algorithm_id: target,
+            window,
+            schedule: &schedule,
+        }),
+        Some("routing_schedule_transition".to_string()),
+    );
+    Ok(())
+}
+
+/// Points `mapping` at `target` with `schedule`, if the row still holds what was read, and audits
+/// the change. Returns whether this call made it.
+async fn store(
+    state: &TenantAppState,
+    mapping: &RoutingAlgorithmMapper,
+    raw: &str,
+    target: &str,
+    schedule: Option<String>,
+) -> Result<bool, String> {
+    // Only touch the row if it still holds what was read: of several replicas ticking at once
+    // exactly one applies (and reports) the change, and an activation made in the meantime is
+    // never overwritten.
+    let predicate = mapper_dsl::id
+        .eq(mapping.id)
+        .and(mapper_dsl::routing_algorithm_id.eq(mapping.routing_algorithm_id.clone()))
+        .and(mapper_dsl::schedule.eq(raw.to_string()));
+    let values = RoutingAlgorithmMapperUpdate {
+        routing_algorithm_id: target.to_string(),
+        algorithm_for: mapping.algorithm_for.clone(),
+        schedule: schedule.clone(),
+    };
+    let conn = state
+        .db
+        .get_conn()
+        .await
+        .map_err(|_| "no database connection".to_string())?;
+    let updated = crate::generics::generic_update_if_present::<
+        <RoutingAlgorithmMapper as HasTable>::Table,
+        _,
+        _,
+    >(&conn, predicate, values)
+    .await
+    .map_err(|e| format!("mapper update failed: {e:?}"))?;
+    if updated == 0 {
+        return Ok(false);
+    }
+    crate::audit::record_job_change(
+        AUDIT_ACTOR,
+        &mapping.created_by,
+        &activation_state(
+            &mapping.algorithm_for,
+            &mapping.routing_algorithm_id,
+            Some(raw),
+        ),
+        &activation_state(&mapping.algorithm_for, target, schedule.as_deref()),
+    )
+    .await;
+    Ok(true)
+}
@@ -44,6 +44,8 @@ diesel::table! {
         good_clusters -> Bigint,
         created_at -> Datetime,
         updated_at -> Datetime,
+        #[max_length = 80]
+        report_blob -> Nullable<Varchar>,
     }
 }
 
@@ -86,6 +88,33 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    config_audit_log (id) {
+        id -> Bigint,
+        #[max_length = 255]
+        merchant_id -> Nullable<Varchar>,
+        #[max_length = 255]
+        actor -> Varchar,
+        #[max_length = 32]
+        actor_kind -> Varchar,
+        #[max_length = 64]
+        user_id -> Nullable<Varchar>,
+        #[max_length = 16]
+        http_method -> Varchar,
+        #[max_length = 255]
+        route -> Varchar,
+        #[max_length = 1024]
+        path -> Varchar,
+        #[max_length = 128]
+        request_id -> Nullable<Varchar>,
+        status_code -> Integer,
+        request_body -> Nullable<Text>,
+        before_state -> Nullable<Text>,
+        after_state -> Nullable<Text>,
+        created_at -> Datetime,
+    }
+}
+
 diesel::table! {
     use diesel::sql_types::*;
     emi_bank_code (id) {
@@ -96,6 +125,27 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    experiment_action (id) {
+        #[max_length = 64]
+        id -> Varchar,
+        #[max_length = 255]
+        routing_algorithm_id -> Varchar,
+        #[max_length = 255]
+        created_by -> Varchar,
+        #[max_length = 16]
+        action -> Varchar,
+        #[max_length = 255]
+        arm -> Varchar,
+        #[max_length = 32]
+        verdict -> Varchar,
+        #[max_length = 255]
+        activated_algorithm_id -> Nullable<Varchar>,
+        results -> Text,
+        created_at -> Timestamp,
+    }
+}
+
 diesel::table! {
     use diesel::sql_types::*;
     feature (id) {
@@ -553,6 +603,34 @@ diesel::table! {
         routing_algorithm_id -> Varchar,
         #[max_length = 64]
         algorithm_for -> Varchar,
+        schedule -> Nullable<Text>,
+    }
+}
+
+diesel::table! {
+    use diesel::sql_types::*;
+    routing_algorithm_version (id) {
+        #[max_length = 64]
+        id -> Varchar,
+        #[max_length = 255]
+        routing_algorithm_id -> Varchar,
+        version -> Integer,
+        #[max_length = 255]
+        created_by -> Varchar,
+        #[max_length = 255]
+        name -> Varchar,
+        description -> Text,
+        algorithm_data -> Text,
+        #[max_length = 64]
+        algorithm_for -> Varchar,
+        metadata -> Nullable<Text>,
+        #[max_length = 255]
+        author -> Nullable<Varchar>,
+        #[max_length = 16]
+        change_kind -> Varchar,
+        restored_from -> Nullable<Integer>,
+        diff -> Nullable<Text>,
+        created_at -> Timestamp,
     }
 }
 
@@ -572,6 +650,24 @@ diesel::table! {
         is_active -> TinyInt,
         email_verified -> TinyInt,
         created_at -> Datetime,
+        totp_secret -> Nullable<Text>,
+        totp_enabled -> TinyInt,
+        totp_recovery_codes -> Nullable<Text>,
+        totp_last_step -> Nullable<Bigint>,
+    }
+}
+
+diesel::table! {
+    use diesel::sql_types::*;
+    user_identities (id) {
+        id -> Bigint,
+        #[max_length = 255]
+        issuer -> Varchar,
+        #[max_length = 255]
+        subject -> Varchar,
+        #[max_length = 64]
+        user_id -> Varchar,
+        created_at -> Datetime,
     }
 }
 
@@ -604,14 +700,35 @@ diesel::table! {
         description -> Nullable<Varchar>,
         is_active -> TinyInt,
         created_at -> Datetime,
+        permissions -> Nullable<Text>,
+        expires_at -> Nullable<Datetime>,
+        rate_limit_per_minute -> Nullable<Integer>,
+        last_used_at -> Nullable<Datetime>,
+    }
+}
+
+diesel::table! {
+    merchant_roles (id) {
+        id -> Bigint,
+        #[max_length = 255]
+        merchant_id -> Varchar,
+        #[max_length = 50]
+        role_name -> Varchar,
+        permissions -> Text,
+        #[max_length = 255]
+        description -> Nullable<Varchar>,
+        created_at -> Datetime,
+        updated_at -> Datetime,
     }
 }
 
 diesel::allow_tables_to_appear_in_same_query!(
     card_brand_routes,
     card_info,
+    config_audit_log,
     cost_ingestion,
     emi_bank_code,
+    experiment_action,
     feature,
     gateway_bank_emi_support,
     gateway_bank_emi_support_v2,
@@ -639,6 +756,8 @@ diesel::allow_tables_to_appear_in_same_query!(
     txn_offer_detail,
     user_eligibility_info,
     merchant_api_keys,
+    merchant_roles,
     users,
+    user_identities,
     user_merchants,
 );
@@ -52,6 +52,33 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    config_audit_log (id) {
+        id -> Int8,
+        #[max_length = 255]
+        merchant_id -> Nullable<Varchar>,
+        #[max_length = 255]
+        actor -> Varchar,
+        #[max_length = 32]
+        actor_kind -> Varchar,
+        #[max_length = 64]
+        user_id -> Nullable<Varchar>,
+        #[max_length = 16]
+        http_method -> Varchar,
+        #[max_length = 255]
+        route -> Varchar,
+        #[max_length = 1024]
+        path -> Varchar,
+        #[max_length = 128]
+        request_id -> Nullable<Varchar>,
+        status_code -> Int4,
+        request_body -> Nullable<Text>,
+        before_state -> Nullable<Text>,
+        after_state -> Nullable<Text>,
+        created_at -> Timestamp,
+    }
+}
+
 diesel::table! {
     cost_ingestion (id) {
         #[max_length = 36]
@@ -84,6 +111,8 @@ diesel::table! {
         good_clusters -> Int8,
         created_at -> Timestamp,
         updated_at -> Timestamp,
+        #[max_length = 80]
+        report_blob -> Nullable<Varchar>,
     }
 }
 
@@ -96,6 +125,27 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    experiment_action (id) {
+        #[max_length = 64]
+        id -> Varchar,
+        #[max_length = 255]
+        routing_algorithm_id -> Varchar,
+        #[max_length = 255]
+        created_by -> Varchar,
+        #[max_length = 16]
+        action -> Varchar,
+        #[max_length = 255]
+        arm -> Varchar,
+        #[max_length = 32]
+        verdict -> Varchar,
+        #[max_length = 255]
+        activated_algorithm_id -> Nullable<Varchar>,
+        results -> Text,
+        created_at -> Timestamp,
+    }
+}
+
 diesel::table! {
     feature (id) {
         id -> Int4,
@@ -272,6 +322,10 @@ diesel::table! {
         description -> Nullable<Varchar>,
         is_active -> Bool,
         created_at -> Timestamp,
+        permissions -> Nullable<Text>,
+        expires_at -> Nullable<Timestamp>,
+        rate_limit_per_minute -> Nullable<Integer>,
+        last_used_at -> Nullable<Timestamp>,
     }
 }
 
@@ -372,6 +426,21 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    merchant_roles (id) {
+        id -> Int8,
+        #[max_length = 255]
+        merchant_id -> Varchar,
+        #[max_length = 50]
+        role_name -> Varchar,
+        permissions -> Text,
+        #[max_length = 255]
+        description -> Nullable<Varchar>,
+        created_at -> Timestamp,
+        updated_at -> Timestamp,
+    }
+}
+
 diesel::table! {
     payment_method (id) {
         id -> Int8,
@@ -416,6 +485,33 @@ diesel::table! {
         routing_algorithm_id -> Varchar,
         #[max_length = 64]
         algorithm_for -> Varchar,
+        schedule -> Nullable<Text>,
+    }
+}
+
+diesel::table! {
+    routing_algorithm_version (id) {
+        #[max_length = 64]
+        id -> Varchar,
+        #[max_length = 255]
+        routing_algorithm_id -> Varchar,
+        version -> Integer,
+        #[max_length = 255]
+        created_by -> Varchar,
+        #[max_length = 255]
+        name -> Varchar,
+        description -> Text,
+        algorithm_data -> Text,
+        #[max_length = 64]
+        algorithm_for -> Varchar,
+        metadata -> Nullable<Jsonb>,
+        #[max_length = 255]
+        author -> Nullable<Varchar>,
+        #[max_length = 16]
+        change_kind -> Varchar,
+        restored_from -> Nullable<Integer>,
+        diff -> Nullable<Text>,
+        created_at -> Timestamp,
     }
 }
 
@@ -568,6 +664,19 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    user_identities (id) {
+        id -> Int8,
+        #[max_length = 255]
+        issuer -> Varchar,
+        #[max_length = 255]
+        subject -> Varchar,
+        #[max_length = 64]
+        user_id -> Varchar,
+        created_at -> Timestamp,
+    }
+}
+
 diesel::table! {
     user_merchants (id) {
         id -> Int8,
@@ -597,15 +706,21 @@ diesel::table! {
         is_active -> Bool,
         email_verified -> Bool,
         created_at -> Timestamp,
+        totp_secret -> Nullable<Text>,
+        totp_enabled -> Bool,
+        totp_recovery_codes -> Nullable<Text>,
+        totp_last_step -> Nullable<Int8>,
     }
 }
 
 diesel::allow_tables_to_appear_in_same_query!(
     card_brand_routes,
     card_info,
+    config_audit_log,
     co_badged_cards_info_test,
     cost_ingestion,
     emi_bank_code,
+    experiment_action,
     feature,
     gateway_bank_emi_support,
     gateway_bank_emi_support_v2,
@@ -624,9 +739,11 @@ diesel::allow_tables_to_appear_in_same_query!(
     merchant_gateway_payment_method_flow,
     merchant_iframe_preferences,
     merchant_priority_logic,
+    merchant_roles,
     payment_method,
     routing_algorithm,
     routing_algorithm_mapper,
+    routing_algorithm_version,
     service_configuration,
     tenant_config,
     tenant_config_filter,
@@ -636,6 +753,7 @@ diesel::allow_tables_to_appear_in_same_query!(
     txn_offer,
     txn_offer_detail,
     user_eligibility_info,
+    user_identities,
     user_merchants,
     users,
 );
@@ -320,6 +320,47 @@ pub struct MerchantAccountHierarchyUpdate {
     pub merchant_name: Option<String>,
 }
 
+/// One recorded configuration change. Append-only: there is deliberately no changeset for it.
+/// Field order matches the schema column order (diesel `Queryable` maps by position).
+#[derive(Debug, Clone, Identifiable, Queryable, Serialize, Deserialize)]
+#[cfg_attr(feature = "mysql", diesel(table_name = schema::config_audit_log))]
+#[cfg_attr(feature = "postgres", diesel(table_name = schema_pg::config_audit_log))]
+pub struct ConfigAuditEntry {
+    pub id: i64,
+    pub merchant_id: Option<String>,
+    pub actor: String,
+    pub actor_kind: String,
+    pub user_id: Option<String>,
+    pub http_method: String,
+    pub route: String,
+    pub path: String,
+    pub request_id: Option<String>,
+    pub status_code: i32,
+    pub request_body: Option<String>,
+    pub before_state: Option<String>,
+    pub after_state: Option<String>,
+    pub created_at: PrimitiveDateTime,
+}
+
+#[derive(Debug, Clone, Insertable, Serialize)]
+#[cfg_attr(feature = "mysql", diesel(table_name = schema::config_audit_log))]
+#[cfg_attr(feature = "postgres", diesel(table_name = schema_pg::config_audit_log))]
+pub struct ConfigAuditEntryNew {
+    pub merchant_id: Option<String>,
+    pub actor: String,
+    pub actor_kind: String,
+    pub user_id: Option<String>,
+    pub http_method: String,
+    pub route: String,
+    pub path: String,
+    pub request_id: Option<String>,
+    pub status_code: i32,
+    pub request_body: Option<String>,
+    pub before_state: Option<String>,
+    pub after_state: Option<String>,
+    pub created_at: PrimitiveDateTime,
+}
+
 /// One settlement-report ingestion — the unified queue + progress + history row (`cost_ingestion`).
 /// Field order matches the schema column order (diesel `Queryable` maps by position).
 #[derive(Debug, Clone, Identifiable, Queryable)]
@@ -349,6 +390,8 @@ pub struct CostIngestion {
     pub good_clusters: i64,
     pub created_at: PrimitiveDateTime,
     pub updated_at: PrimitiveDateTime,
+    /// Content address of the retained raw report (`sha256:<hex>`); `None` when it wasn't kept.
+    pub report_blob: Option<String>,
 }
 
 /// Insert shape for a new ingestion. `status` is explicit (`pending` for webhook jobs the worker
@@ -381,6 +424,15 @@ pub struct CostIngestionStatusUpdate {
     pub updated_at: PrimitiveDateTime,
 }
 
+/// Records where an ingestion's raw report was retained.
+#[derive(Debug, Clone, AsChangeset)]
+#[cfg_attr(feature = "mysql", diesel(table_name = schema::cost_ingestion))]
+#[cfg_attr(feature = "postgres", diesel(table_name = schema_pg::cost_ingestion))]
+pub struct CostIngestionBlobUpdate {
+    pub report_blob: Option<String>,
+    pub updated_at: PrimitiveDateTime,
+}
+
 /// Live-progress changeset: bump the staged-row counter the dashboard polls.
 #[derive(Debug, Clone, AsChangeset)]
 #[cfg_attr(feature = "mysql", diesel(table_name = schema::cost_ingestion))]
@@ -812,6 +864,11 @@ pub struct MerchantApiKey {
     #[cfg(feature = "postgres")]
     pub is_active: bool,
     pub created_at: PrimitiveDateTime,
+    /// JSON array of the permissions the key holds; `None` is unrestricted.
+    pub permissions: Option<String>,
+    pub expires_at: Option<PrimitiveDateTime>,
+    pub rate_limit_per_minute: Option<i32>,
+    pub last_used_at: Option<PrimitiveDateTime>,
 }
 
 #[derive(Debug, Clone, Insertable)]
@@ -828,6 +885,9 @@ pub struct MerchantApiKeyNew {
     #[cfg(feature = "postgres")]
     pub is_active: bool,
     pub created_at: PrimitiveDateTime,
+    pub permissions: Option<String>,
+    pub expires_at: Option<PrimitiveDateTime>,
+    pub rate_limit_per_minute: Option<i32>,
 }
 
 #[derive(Debug, Clone, AsChangeset)]
@@ -840,6 +900,21 @@ pub struct MerchantApiKeyRevoke {
     pub is_active: bool,
 }
 
+/// Brings a key's expiry forward — the old key's end of the overlap window when it is rotated.
+#[derive(Debug, Clone, AsChangeset)]
+#[cfg_attr(feature = "mysql", diesel(table_name = schema::merchant_api_keys))]
+#[cfg_attr(feature = "postgres", diesel(table_name = schema_pg::merchant_api_keys))]
+pub struct MerchantApiKeyExpiry {
+    pub expires_at: PrimitiveDateTime,
+}
+
+#[derive(Debug, Clone, AsChangeset)]
+#[cfg_attr(feature = "mysql", diesel(table_name = schema::merchant_api_keys))]
+#[cfg_attr(feature = "postgres", diesel(table_name = schema_pg::merchant_api_keys))]
+pub struct MerchantApiKeyLastUsed {
+    pub last_used_at: PrimitiveDateTime,
+}
+
 #[derive(Debug, Clone, Identifiable, Queryable, Serialize, Deserialize)]
 #[cfg_attr(feature = "mysql", diesel(table_name = schema::users))]
 #[cfg_attr(feature = "postgres", diesel(table_name = schema_pg::users))]
@@ -859,6 +934,17 @@ pub struct User {
     #[cfg(feature = "postgres")]
     pub email_verified: bool,
     pub created_at: PrimitiveDateTime,
+    /// Base32, as authenticator apps take it. Set at enrollment, and only asked for once
+    /// `totp_enabled` says a code from it has been confirmed.
+    pub totp_secret: Option<String>,
+    #[cfg(feature = "mysql")]
+    pub totp_enabled: i8,
+    #[cfg(feature = "postgres")]
+    pub totp_enabled: bool,
+    /// A JSON array of bcrypt hashes of the unused recovery codes.
+    pub totp_recovery_codes: Option<String>,
+    /// The last time step a code was accepted for, so the same code cannot be used twice.
+    pub totp_last_step: Option<i64>,
 }
 
 #[derive(Debug, Clone, Insertable)]
@@ -902,6 +988,71 @@ pub struct NewUserMerchant {
     pub created_at: PrimitiveDateTime,
 }
 
+#[derive(AsChangeset, Debug)]
+#[cfg_attr(feature = "mysql", diesel(table_name = schema::user_merchants))]
+#[cfg_attr(feature = "postgres", diesel(table_name = schema_pg::user_merchants))]
+pub struct UserMerchantRoleUpdate {
+    pub role: String,
+}
+
+/// The identity-provider account an SSO user signs in with.
+#[derive(Debug, Clone, Identifiable, Queryable, Serialize, Deserialize)]
+#[cfg_attr(feature = "mysql", diesel(table_name = schema::user_identities))]
+#[cfg_attr(feature = "postgres", diesel(table_name = schema_pg::user_identities))]
+pub struct UserIdentity {
+    pub id: i64,
+    pub issuer: String,
+    pub subject: String,
+    pub user_id: String,
+    pub created_at: PrimitiveDateTime,
+}
+
+#[derive(Debug, Clone, Insertable)]
+#[cfg_attr(feature = "mysql", diesel(table_name = schema::user_identities))]
+#[cfg_attr(feature = "postgres", diesel(table_name = schema_pg::user_identities))]
+pub struct NewUserIdentity {
+    pub issuer: String,
+    pub subject: String,
+    pub user_id: String,
+    pub created_at: PrimitiveDateTime,
+}
+
+#[derive(Debug, Clone, Identifiable, Queryable, Serialize, Deserialize)]
+#[cfg_attr(feature = "mysql", diesel(table_name = schema::merchant_roles))]
+#[cfg_attr(feature = "postgres", diesel(table_name = schema_pg::merchant_roles))]
+pub struct MerchantRole {
+    pub id: i64,
+    pub merchant_id: String,
+    pub role_name: String,
+    /// A JSON array of permission strings.
+    pub permissions: String,
+    pub description: Option<String>,
+    pub created_at: PrimitiveDateTime,
+    pub updated_at: PrimitiveDateTime,
+}
+
+#[derive(Debug, Clone, Insertable)]
+#[cfg_attr(feature = "mysql", diesel(table_name = schema::merchant_roles))]
+#[cfg_attr(feature = "postgres", diesel(table_name = schema_pg::merchant_roles))]
+pub struct MerchantRoleNew {
+    pub merchant_id: String,
+    pub role_name: String,
+    pub permissions: String,
+    pub description: Option<String>,
+    pub created_at: PrimitiveDateTime,
+    pub updated_at: PrimitiveDateTime,
+}
+
+#[derive(AsChangeset, Debug)]
+#[cfg_attr(feature = "mysql", diesel(table_name = schema::merchant_roles))]
+#[cfg_attr(feature = "postgres", diesel(table_name = schema_pg::merchant_roles))]
+#[diesel(treat_none_as_null = true)]
+pub struct MerchantRoleUpdate {
+    pub permissions: String,
+    pub description: Option<String>,
+    pub updated_at: PrimitiveDateTime,
+}
+
 #[derive(AsChangeset, Debug)]
 #[cfg_attr(feature = "mysql", diesel(table_name = schema::users))]
 #[cfg_attr(feature = "postgres", diesel(table_name = schema_pg::users))]
@@ -925,3 +1076,48 @@ pub struct UserEmailVerifiedUpdate {
 pub struct UserPasswordUpdate {
     pub password_hash: String,
 }
+
+/// The whole of a user's two-factor state, written at once: `None` clears a column.
+#[derive(AsChangeset, Debug)]
+#[diesel(treat_none_as_null = true)]
+#[cfg_attr(feature = "mysql", diesel(table_name = schema::users))]
+#[cfg_attr(feature = "postgres", diesel(table_name = schema_pg::users))]
+pub struct UserTotpUpdate {
+    pub totp_secret: Option<String>,
+    #[cfg(feature = "mysql")]
+    pub totp_enabled: i8,
+    #[cfg(feature = "postgres")]
+    pub totp_enabled: bool,
+    pub totp_recovery_codes: Option<String>,
+    pub totp_last_step: Option<i64>,
+}
+
+/// Accepting a code: the step it was accepted for, and the secret sealed when it was stored before
+/// secrets were. `None` leaves the secret as it is.
+#[derive(AsChangeset, Debug)]
+#[cfg_attr(feature = "mysql", diesel(table_name = schema::users))]
+#[cfg_attr(feature = "postgres", diesel(table_name = schema_pg::users))]
+pub struct UserTotpStepUpdate {
+    pub totp_last_step: Option<i64>,
+    pub totp_secret: Option<String>,
+}
+
+/// Confirming enrollment: two-factor on, with the first set of recovery codes.
+#[derive(AsChangeset, Debug)]
+#[cfg_attr(feature = "mysql", diesel(table_name = schema::users))]
+#[cfg_attr(feature = "postgres", diesel(table_name = schema_pg::users))]
+pub struct UserTotpEnabledUpdate {
+    #[cfg(feature = "mysql")]
+    pub totp_enabled: i8,
+    #[cfg(feature = "postgres")]
+    pub totp_enabled: bool,
+    pub totp_recovery_codes: Option<String>,
+}
+
+/// Using up a recovery code, or replacing the whole set.
+#[derive(AsChangeset, Debug)]
+#[cfg_attr(feature = "mysql", diesel(table_name = schema::users))]
+#[cfg_attr(feature = "postgres", diesel(table_name = schema_pg::users))]
+pub struct UserTotpRecoveryCodesUpdate {
+    pub totp_recovery_codes: Option<String>,
+}
@@ -44,6 +44,22 @@ pub struct SuccessRateData {
     /// band or admission gate. Defaults to [`crate::decider::gatewaydecider::
     /// multi_objective::DEFAULT_MARGIN`] when unset.
     pub margin: Option<f64>,
+    /// Latency objective for the multi-objective ranking: bps of ticket charged in the EV per
+    /// second of a PSP's p95 gateway latency, and a p95 ceiling in ms above which a PSP can't be
+    /// chosen over one within it. See [`crate::decider::gatewaydecider::multi_objective::latency`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub latency_penalty_bps_per_second: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub latency_sla_ms: Option<f64>,
+    /// How many standard errors of a PSP's fitted cost the multi-objective ranking adds to it, so a
+    /// thinly-fitted cost is ranked nearer its upper bound (e.g. `1.645` for a one-sided 95% bound).
+    /// Unset ranks on the point estimate.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cost_risk_aversion: Option<f64>,
+    /// Name of the SR scoring strategy (see [`crate::decider::gatewaydecider::gw_scoring::
+    /// strategy::STRATEGIES`]). Unset scores with SR v3.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scoring_strategy: Option<String>,
 }
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
@@ -1,5 +1,5 @@
 import { Spinner } from '../ui/Spinner'
-import { useCostCoverage, type CoverageSummary } from '../../hooks/useCostRouting'
+import { useCostCoverage, type CoverageSummary, type TierPosition } from '../../hooks/useCostRouting'
 
 /**
  * Coverage of the merchant's fitted cost models. {@link CoverageBreakdown} is the per-verdict table
@@ -34,6 +34,53 @@ export function CoverageBreakdown({ merchantId }: { merchantId?: string }) {
   return (
     <div className="space-y-4">
       <VerdictTable coverage={coverage} />
+      {coverage.volume_tiers?.length ? <VolumeTierTable positions={coverage.volume_tiers} /> : null}
+    </div>
+  )
+}
+
+/**
+ * Month-to-date standing on each volume-tier contract: the tier the next payment is priced in, and
+ * how much more volume reaches the cheaper one — the lever for pushing volume to a PSP near a tier.
+ */
+function VolumeTierTable({ positions }: { positions: TierPosition[] }) {
+  return (
+    <div className="overflow-x-auto">
+      <table className="w-full min-w-[520px] text-left text-sm">
+        <thead>
+          <tr className="border-b border-slate-200 text-[12px] font-medium text-slate-500 dark:text-[#8d96aa] dark:border-[#232833]">
+            <th className="py-2 pr-3 font-semibold">Volume tiers</th>
+            <th className="py-2 pr-3 text-right font-semibold">This month</th>
+            <th className="py-2 pr-3 text-right font-semibold">Tier</th>
+            <th className="py-2 pr-3 text-right font-semibold">Rate</th>
+            <th className="py-2 pr-3 text-right font-semibold">To next tier</th>
+          </tr>
+        </thead>
+        <tbody>
+          {positions.map((p) => (
+            <tr
+              key={p.connector}
+              className="border-b border-slate-100 last:border-0 dark:border-[#1c1c23]"
+            >
+              <td className="py-2 pr-3 font-medium text-slate-700 dark:text-[#c7cfdd]">{p.connector}</td>
+              <td className="py-2 pr-3 text-right tabular-nums text-slate-600 dark:text-[#c7cfdd]">
+                {formatCompact(p.mtd_volume)} {p.currency}
+              </td>
+              <td className="py-2 pr-3 text-right tabular-nums text-slate-500 dark:text-[#9ca7ba]">
+                {p.tier + 1} of {p.tier_count}
+              </td>
+              <td className="py-2 pr-3 text-right tabular-nums text-slate-600 dark:text-[#c7cfdd]">
+                {p.pct_bps.toFixed(1)} bps
+              </td>
+              <td className="py-2 pr-3 text-right tabular-nums text-slate-600 dark:text-[#c7cfdd]">
+                {p.volume_to_next_tier != null && p.next_pct_bps != null
+                  ? `${formatCompact(p.volume_to_next_tier)} → ${p.next_pct_bps.toFixed(1)} bps`
+                  : 'top tier'}
+              </td>
+            </tr>
+          ))}
+        </tbody>
+      </table>
     </div>
   )
 }
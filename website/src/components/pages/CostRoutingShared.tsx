@@ -2,14 +2,16 @@ import type { ReactNode } from 'react'
 import * as type from '../ui/typography'
 
 /** Connectors whose settlement report can be manually uploaded (their parser is implemented). The
- * automatic path is gated separately — Chase pulls via its poller (OAuth + reporting API), while
- * Braintree's webhook/download is still upload-only. */
+ * automatic path is gated separately — Chase pulls via its poller (OAuth + reporting API), PayPal and
+ * Worldpay via their SFTP drop, while Braintree's webhook/download is still upload-only. */
 export const UPLOAD_CONNECTORS = [
   { value: 'adyen', label: 'Adyen' },
   { value: 'braintree', label: 'Braintree' },
   { value: 'chase', label: 'Chase' },
   { value: 'checkout', label: 'Checkout' },
+  { value: 'paypal', label: 'PayPal' },
   { value: 'stripe', label: 'Stripe' },
+  { value: 'worldpay', label: 'Worldpay' },
 ] as const
 
 /** Shared input styling for the cost-routing forms (credentials + manual upload). */
@@ -14,8 +14,14 @@ import {
 } from '../../hooks/useCostRouting'
 import { Field, inputClass } from './CostRoutingShared'
 
-/** Connectors whose invoice parser is implemented. Only Adyen for now (Braintree invoice pending). */
-const INVOICE_CONNECTORS = [{ value: 'adyen', label: 'Adyen' }] as const
+/** Connectors whose invoice parser is implemented. Stripe takes the itemized balance export,
+ * Braintree its monthly statement. */
+const INVOICE_CONNECTORS = [
+  { value: 'adyen', label: 'Adyen' },
+  { value: 'braintree', label: 'Braintree' },
+  { value: 'checkout', label: 'Checkout' },
+  { value: 'stripe', label: 'Stripe' },
+] as const
 
 /** Money / rate formatting. Invoice currency may be blank on odd exports → fall back to a plain code. */
 function money(v: number, ccy: string, digits = 2): string {
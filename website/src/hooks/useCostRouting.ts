@@ -35,6 +35,28 @@ export interface CoverageSummary {
   bps_rmse_p90: number
   /** Snapshot date these numbers are from (YYYY-MM-DD). */
   report_date: string
+  /** Each volume-tier contract's standing this month. Empty when none is configured. */
+  volume_tiers: TierPosition[]
+}
+
+/** Where a connector priced by cumulative monthly volume stands against its contract this month. */
+export interface TierPosition {
+  connector: string
+  currency: string
+  /** UTC month the volume is counted for (YYYY-MM). */
+  month: string
+  mtd_volume: number
+  /** 0-based tier the next payment starts in. */
+  tier: number
+  tier_count: number
+  pct_bps: number
+  fixed: number
+  /** Volume at which the next tier starts; null on the top tier. */
+  next_tier_at: number | null
+  /** Volume still to route this month to reach the next tier. */
+  volume_to_next_tier: number | null
+  next_pct_bps: number | null
+  next_fixed: number | null
 }
 
 export interface ConnectorSource {
@@ -752,6 +774,8 @@ export interface InvoiceAddon {
   fixed_addon: number
   invoice_ref: string
   subtotal_ex_tax: number | null
+  /** False when the connector nets its per-transaction fees at settlement rather than invoicing them. */
+  includes_settled_fees: boolean
   card_volume: number | null
   txn_count: number | null
   currency: string
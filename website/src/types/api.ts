@@ -32,6 +32,10 @@ export interface PspSummary {
 // are the rows flagged isSrHead / isChosen (the same row on AUTH_WON).
 export interface RankedPsp extends PspSummary {
   ev: number
+  // Standard error of costBps, when its source fits one, and the cost plus the merchant's risk
+  // aversion times it — what the EV charged — when the merchant sets one.
+  costStderrBps?: number
+  riskAdjustedCostBps?: number
   isSrHead: boolean
   isChosen: boolean
 }
@@ -53,6 +57,11 @@ export interface MultiObjectiveInfo {
   /// EV ranking wasn't performed (fewer than two PSPs, or the SR head had no finite score / no cost
   /// data). srHead/chosen live here as the flagged isSrHead/isChosen rows.
   ranked?: RankedPsp[]
+  /// Standard errors of fitted cost the EV added to each PSP's cost, when the merchant sets it.
+  costRiskAversion?: number
+  /// The payment's currency; every ranked cost is in bps of the ticket in it, converted from the
+  /// cost model's settlement currency where needed.
+  currency?: string
 }
 
 export type RoutingAlgorithmName =